@@ -6,29 +6,65 @@ use crate::utils::{receiver_into_stream, spawn_future, CustomMutex};
 use autoagents_llm::chat::{
     ChatMessage, ChatProvider, ChatResponse, StreamResponse, StructuredOutputFormat, Tool,
 };
+use autoagents_llm::chat_template::ChatTemplate;
 use autoagents_llm::completion::{CompletionProvider, CompletionRequest, CompletionResponse};
 use autoagents_llm::embedding::EmbeddingProvider;
 use autoagents_llm::error::LLMError;
+use autoagents_llm::grammar::Grammar;
 use autoagents_llm::models::ModelsProvider;
 use autoagents_llm::{async_trait, LLMProvider};
 use burn::prelude::Backend;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
 
+/// Chat template of TinyLlama-1.1B-Chat, as in its `tokenizer_config.json`
+const TINY_LLAMA_CHAT_TEMPLATE: &str = "{% for message in messages %}\n{% if message['role'] == 'user' %}\n{{ '<|user|>\n' + message['content'] + eos_token }}\n{% elif message['role'] == 'system' %}\n{{ '<|system|>\n' + message['content'] + eos_token }}\n{% elif message['role'] == 'assistant' %}\n{{ '<|assistant|>\n'  + message['content'] + eos_token }}\n{% endif %}\n{% if loop.last and add_generation_prompt %}\n{{ '<|assistant|>' }}\n{% endif %}\n{% endfor %}";
+
+/// Chat template of the Llama 3 instruct models, as in their `tokenizer_config.json`
+const LLAMA3_CHAT_TEMPLATE: &str = "{% set loop_messages = messages %}{% for message in loop_messages %}{% set content = '<|start_header_id|>' + message['role'] + '<|end_header_id|>\n\n'+ message['content'] | trim + '<|eot_id|>' %}{% if loop.index0 == 0 %}{% set content = bos_token + content %}{% endif %}{{ content }}{% endfor %}{% if add_generation_prompt %}{{ '<|start_header_id|>assistant<|end_header_id|>\n\n' }}{% endif %}";
+
 pub(crate) enum LLamaModel {
     TinyLLama,
     Llama3,
 }
 
+impl LLamaModel {
+    /// The template prompts are formatted with: the one set on the builder,
+    /// else the one of the model's `tokenizer_config.json`, else the
+    /// template the model family was trained with.
+    pub(crate) fn chat_template(
+        &self,
+        template: Option<ChatTemplate>,
+        tokenizer_config: Option<&Path>,
+    ) -> Result<ChatTemplate, LLMError> {
+        if let Some(template) = template {
+            return Ok(template);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = tokenizer_config {
+            return ChatTemplate::from_tokenizer_config_file(path);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = tokenizer_config;
+        Ok(match self {
+            LLamaModel::TinyLLama => ChatTemplate::new(TINY_LLAMA_CHAT_TEMPLATE, "<s>", "</s>"),
+            LLamaModel::Llama3 => {
+                ChatTemplate::new(LLAMA3_CHAT_TEMPLATE, "<|begin_of_text|>", "<|eot_id|>")
+            }
+        })
+    }
+}
+
 /// Llama model wrapper for LLM provider
 pub struct LlamaChat<B: Backend, T: Tokenizer> {
     pub(crate) llama: Arc<CustomMutex<Llama<InferenceBackend, T>>>,
     pub(crate) config: GenerationConfig,
     pub(crate) marker: PhantomData<B>,
-    pub(crate) model: LLamaModel,
+    pub(crate) chat_template: ChatTemplate,
 }
 
 #[derive(Clone, Debug)]
@@ -37,6 +73,8 @@ pub struct GenerationConfig {
     pub temperature: f64,
     pub top_p: f64,
     pub seed: u64,
+    /// Grammar every answer has to match, unless a request brings a schema
+    pub grammar: Option<Grammar>,
 }
 
 impl Default for GenerationConfig {
@@ -46,35 +84,25 @@ impl Default for GenerationConfig {
             temperature: 0.7,
             top_p: 0.9,
             seed: 42,
+            grammar: None,
         }
     }
 }
 
 impl<B: Backend, T: Tokenizer> LlamaChat<B, T> {
     fn prompt(&self, messages: &[ChatMessage]) -> Result<String, LLMError> {
-        match self.model {
-            LLamaModel::Llama3 => {
-                let mut prompt: Vec<String> = vec![];
-                for message in messages {
-                    prompt.push(format!(
-                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
-                        message.role.to_string().to_lowercase(),
-                        message.content
-                    ));
-                }
-                let mut prompt = prompt.join("");
-                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
-                Ok(prompt)
-            }
-            LLamaModel::TinyLLama => {
-                let mut prompt: Vec<String> = vec![];
-                for message in messages {
-                    prompt.push(format!("<|{}|>\n{}</s>\n", message.role, message.content));
-                }
-                let mut prompt = prompt.join("\n");
-                prompt.push_str("<|assistant|>\n");
-                Ok(prompt)
-            }
+        self.chat_template.render(messages, true)
+    }
+
+    /// The grammar output is constrained to: the one of the requested
+    /// schema, else the configured one.
+    fn grammar(
+        &self,
+        json_schema: Option<&StructuredOutputFormat>,
+    ) -> Result<Option<Grammar>, LLMError> {
+        match json_schema.and_then(|format| format.schema.as_ref()) {
+            Some(schema) => Grammar::from_json_schema(schema).map(Some),
+            None => Ok(self.config.grammar.clone()),
         }
     }
 }
@@ -84,8 +112,9 @@ impl<B: Backend, T: Tokenizer> CompletionProvider for LlamaChat<B, T> {
     async fn complete(
         &self,
         req: &CompletionRequest,
-        _json_schema: Option<StructuredOutputFormat>,
+        json_schema: Option<StructuredOutputFormat>,
     ) -> Result<CompletionResponse, LLMError> {
+        let grammar = self.grammar(json_schema.as_ref())?;
         let mut llama = self.llama.lock().await;
         llama.reset();
 
@@ -105,12 +134,20 @@ impl<B: Backend, T: Tokenizer> CompletionProvider for LlamaChat<B, T> {
         };
 
         let result = llama
-            .generate(&req.prompt, max_tokens, temperature, &mut sampler, None)
+            .generate(
+                &req.prompt,
+                max_tokens,
+                temperature,
+                &mut sampler,
+                grammar.as_ref(),
+                None,
+            )
             .await
             .map_err(|e| LLMError::Generic(format!("Generation error: {:?}", e)))?;
 
         Ok(CompletionResponse {
             text: result.result,
+            usage: None,
         })
     }
 }
@@ -121,10 +158,11 @@ impl<B: Backend, T: Tokenizer> ChatProvider for LlamaChat<B, T> {
         &self,
         messages: &[ChatMessage],
         _tools: Option<&[Tool]>,
-        _json_schema: Option<StructuredOutputFormat>,
+        json_schema: Option<StructuredOutputFormat>,
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
         // Format messages into chat format
         let prompt = self.prompt(messages)?;
+        let grammar = self.grammar(json_schema.as_ref())?;
 
         let mut llama = self.llama.lock().await;
         llama.reset();
@@ -141,6 +179,7 @@ impl<B: Backend, T: Tokenizer> ChatProvider for LlamaChat<B, T> {
                 self.config.max_tokens,
                 self.config.temperature,
                 &mut sampler,
+                grammar.as_ref(),
                 None,
             )
             .await
@@ -188,11 +227,12 @@ impl<B: Backend, T: Tokenizer> ChatProvider for LlamaChat<B, T> {
         &self,
         messages: &[ChatMessage],
         _tools: Option<&[Tool]>,
-        _json_schema: Option<StructuredOutputFormat>,
+        json_schema: Option<StructuredOutputFormat>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
     {
         // Format messages into Llama chat format
         let prompt = self.prompt(messages)?;
+        let grammar = self.grammar(json_schema.as_ref())?;
 
         let llama = self.llama.clone();
         let config = self.config.clone();
@@ -218,6 +258,7 @@ impl<B: Backend, T: Tokenizer> ChatProvider for LlamaChat<B, T> {
                     config.max_tokens,
                     config.temperature,
                     &mut sampler,
+                    grammar.as_ref(),
                     Some(tx.clone()),
                 )
                 .await;
@@ -226,13 +267,7 @@ impl<B: Backend, T: Tokenizer> ChatProvider for LlamaChat<B, T> {
                 Ok(_) => {
                     let final_response = StreamResponse {
                         choices: vec![],
-                        usage: Some(autoagents_llm::chat::Usage {
-                            prompt_tokens: 0,
-                            completion_tokens: total_tokens as u32,
-                            total_tokens: total_tokens as u32,
-                            completion_tokens_details: None,
-                            prompt_tokens_details: None,
-                        }),
+                        usage: Some(autoagents_llm::chat::Usage::new(0, total_tokens as u32)),
                     };
                     tx.send(Ok(final_response)).await;
                 }
@@ -245,6 +280,11 @@ impl<B: Backend, T: Tokenizer> ChatProvider for LlamaChat<B, T> {
 
         Ok(receiver_into_stream(rx))
     }
+
+    /// Schemas are enforced while sampling
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -263,13 +303,7 @@ impl ChatResponse for SimpleChatResponse {
     }
 
     fn usage(&self) -> Option<autoagents_llm::chat::Usage> {
-        Some(autoagents_llm::chat::Usage {
-            prompt_tokens: 0,
-            completion_tokens: self.tokens_used as u32,
-            total_tokens: self.tokens_used as u32,
-            completion_tokens_details: None,
-            prompt_tokens_details: None,
-        })
+        Some(autoagents_llm::chat::Usage::new(0, self.tokens_used as u32))
     }
 }
 
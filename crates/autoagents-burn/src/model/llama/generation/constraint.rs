@@ -0,0 +1,61 @@
+use super::super::tokenizer::Tokenizer;
+use autoagents_llm::grammar::{Grammar, GrammarMatcher};
+
+/// Keeps generated text inside a grammar by telling the sampler which tokens
+/// may come next.
+pub struct TokenConstraint {
+    matcher: GrammarMatcher,
+    /// Bytes of every token, `None` for special tokens
+    vocabulary: Vec<Option<Vec<u8>>>,
+    stop_ids: Vec<u32>,
+}
+
+impl TokenConstraint {
+    pub fn new<T: Tokenizer>(grammar: &Grammar, tokenizer: &T, vocab_size: usize) -> Self {
+        let stop_ids = tokenizer.stop_ids();
+        let vocabulary = (0..vocab_size as u32)
+            .map(|token| {
+                if stop_ids.contains(&token) {
+                    None
+                } else {
+                    tokenizer
+                        .token_bytes(token)
+                        .filter(|bytes| !bytes.is_empty())
+                }
+            })
+            .collect();
+        Self {
+            matcher: grammar.matcher(),
+            vocabulary,
+            stop_ids,
+        }
+    }
+
+    /// Whether `token` may come next. Stop tokens are allowed once the text
+    /// is a complete match.
+    pub fn allows(&self, token: u32) -> bool {
+        if self.is_stop(token) {
+            return self.matcher.is_complete();
+        }
+        match self.vocabulary.get(token as usize) {
+            Some(Some(bytes)) => self.matcher.allows_bytes(bytes),
+            _ => false,
+        }
+    }
+
+    /// Advance past the sampled `token`
+    pub fn accept(&mut self, token: u32) {
+        if let Some(Some(bytes)) = self.vocabulary.get(token as usize) {
+            self.matcher.accept_bytes(bytes);
+        }
+    }
+
+    pub fn is_stop(&self, token: u32) -> bool {
+        self.stop_ids.contains(&token)
+    }
+
+    /// Token to end with when no token fits the grammar any more
+    pub fn stop_token(&self) -> u32 {
+        self.stop_ids.first().copied().unwrap_or_default()
+    }
+}
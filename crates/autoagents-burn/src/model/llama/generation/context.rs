@@ -203,6 +203,8 @@ impl<T: Tokenizer> TokenGeneration<T> {
                         delta: StreamDelta {
                             content: Some(text),
                             tool_calls: None,
+                            thinking: None,
+                            reasoning: None,
                         },
                     }],
                     usage: None,
@@ -1,6 +1,7 @@
 use super::super::{tokenizer::Tokenizer, Llama};
-use super::{GenerationContext, Sampler};
+use super::{GenerationContext, Sampler, TokenConstraint};
 use crate::model::llama::generation::stream_sender::StreamSender;
+use autoagents_llm::grammar::Grammar;
 use burn::{prelude::*, tensor::activation::softmax};
 use log::debug;
 
@@ -34,6 +35,7 @@ impl<B: Backend, T: Tokenizer + 'static> Llama<B, T> {
     /// - `temperature`: Temperature value for controlling randomness in sampling (scales logits by `1 / temperature`).
     ///   High values result in more random sampling.
     /// - `sampler`: The sampling strategy to use when selecting the next token based on the predicted probabilities.
+    /// - `grammar`: Grammar the generated text has to match; tokens that would leave it are never sampled.
     ///
     /// # Returns
     /// The generated text along with some other metadata (see [GenerationOutput]).
@@ -43,6 +45,7 @@ impl<B: Backend, T: Tokenizer + 'static> Llama<B, T> {
         sample_len: usize,
         temperature: f64,
         sampler: &mut Sampler,
+        grammar: Option<&Grammar>,
         emitter: Option<StreamSender>,
     ) -> Result<GenerationOutput, GenerationError> {
         let input_tokens = self.tokenize(prompt);
@@ -58,6 +61,7 @@ impl<B: Backend, T: Tokenizer + 'static> Llama<B, T> {
         state.append(input_tokens);
 
         let mut input_pos = Tensor::<B, 1, Int>::arange(0..prompt_len as i64, &self.device);
+        let mut constraint: Option<TokenConstraint> = None;
 
         debug!("Starting Generation Loop");
         for i in 0..sample_len {
@@ -96,11 +100,33 @@ impl<B: Backend, T: Tokenizer + 'static> Llama<B, T> {
             };
 
             debug!("Sampling Tokens");
-            let next_token = sampler.sample(next_token_logits).await.squeeze_dim(0);
+            let (next_token, stop) = match grammar {
+                Some(grammar) => {
+                    let constraint = constraint.get_or_insert_with(|| {
+                        TokenConstraint::new(grammar, &self.tokenizer, next_token_logits.dims()[1])
+                    });
+                    let token = sampler
+                        .sample_constrained(next_token_logits, constraint)
+                        .await;
+                    constraint.accept(token);
+                    let next_token = Tensor::<B, 1, Int>::from_data(
+                        TensorData::new(vec![token as i64], [1]),
+                        &self.device,
+                    );
+                    (next_token, constraint.is_stop(token))
+                }
+                None => (
+                    sampler.sample(next_token_logits).await.squeeze_dim(0),
+                    false,
+                ),
+            };
 
             // Update with the new generated token
             state.update(next_token.clone()).await;
             debug!("Update Tokens Complete");
+            if stop {
+                break;
+            }
 
             // Advance
             let t = input_pos.dims()[0];
@@ -1,8 +1,10 @@
+mod constraint;
 mod context;
 mod generate;
 mod sampling;
 mod streaming;
 
+pub use constraint::*;
 pub use context::*;
 pub use generate::*;
 pub use sampling::*;
@@ -1,3 +1,4 @@
+use super::TokenConstraint;
 use burn::tensor::{backend::Backend, Int, Tensor};
 use burn::tensor::{DType, TensorData};
 use rand::{
@@ -6,23 +7,24 @@ use rand::{
     SeedableRng,
 };
 
-pub async fn manual_argmax<B: Backend>(logits: Tensor<B, 2>) -> Tensor<B, 2, Int> {
-    // Pull logits data to CPU
-    let data = logits.clone().into_data_async().await;
-
-    // decode the bytes into f32 values
-    let values: Vec<f32> = match data.dtype {
+/// Values of a float tensor, pulled to the CPU
+async fn values<B: Backend>(tensor: Tensor<B, 2>) -> Vec<f32> {
+    let data = tensor.into_data_async().await;
+    match data.dtype {
         DType::F32 => bytemuck::cast_slice::<u8, f32>(&data.bytes).to_vec(),
         DType::F16 => {
             let halves: &[half::f16] = bytemuck::cast_slice(&data.bytes);
             halves.iter().map(|h| f32::from(*h)).collect()
         }
         _ => panic!("Unexpected dtype {:?}", data.dtype),
-    };
+    }
+}
 
-    let shape = data.shape.clone(); // [batch, vocab]
-    let batch = shape[0];
-    let vocab = shape[1];
+pub async fn manual_argmax<B: Backend>(logits: Tensor<B, 2>) -> Tensor<B, 2, Int> {
+    let [batch, vocab] = logits.dims();
+    let device = logits.device();
+    // Pull logits data to CPU
+    let values = values(logits).await;
 
     // compute argmax per row
     let mut indices: Vec<i64> = Vec::with_capacity(batch);
@@ -46,7 +48,7 @@ pub async fn manual_argmax<B: Backend>(logits: Tensor<B, 2>) -> Tensor<B, 2, Int
     let indices_data = TensorData::new(indices, vec![batch, 1]);
 
     // build Tensor<B, 2, Int>
-    Tensor::<B, 2, Int>::from_data(indices_data, &logits.device())
+    Tensor::<B, 2, Int>::from_data(indices_data, &device)
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -62,6 +64,27 @@ impl Sampler {
             Self::Argmax => logits.argmax(1),
         }
     }
+
+    /// Sample a token `constraint` allows from single-batch logits, or
+    /// probabilities for top-p. Ends generation with a stop token when no
+    /// token fits.
+    pub async fn sample_constrained<B: Backend>(
+        &mut self,
+        logits: Tensor<B, 2>,
+        constraint: &TokenConstraint,
+    ) -> u32 {
+        let weights = values(logits).await;
+        let mut order: Vec<usize> = (0..weights.len()).collect();
+        order.sort_unstable_by(|&a, &b| weights[b].total_cmp(&weights[a]));
+        let token = match self {
+            Self::TopP(s) => s.sample_allowed(&weights, &order, constraint),
+            Self::Argmax => order
+                .into_iter()
+                .map(|token| token as u32)
+                .find(|&token| constraint.allows(token)),
+        };
+        token.unwrap_or_else(|| constraint.stop_token())
+    }
 }
 
 #[async_trait::async_trait]
@@ -83,6 +106,37 @@ impl TopP {
         let rng = StdRng::seed_from_u64(seed);
         Self { p, rng }
     }
+
+    /// Top-p sampling among the tokens `constraint` allows, with `order`
+    /// the tokens by descending probability. Checking stops as soon as the
+    /// unchecked tokens could no longer change the nucleus.
+    fn sample_allowed(
+        &mut self,
+        probs: &[f32],
+        order: &[usize],
+        constraint: &TokenConstraint,
+    ) -> Option<u32> {
+        let mut unchecked: f64 = probs.iter().map(|&p| p as f64).sum();
+        let mut allowed = Vec::new();
+        let mut allowed_mass = 0.0;
+        for &token in order {
+            let p = probs[token] as f64;
+            unchecked -= p;
+            if constraint.allows(token as u32) {
+                allowed.push((token as u32, p));
+                allowed_mass += p;
+            }
+            if !allowed.is_empty() && allowed_mass >= self.p * (allowed_mass + unchecked) {
+                break;
+            }
+        }
+        let (first, _) = *allowed.first()?;
+        match WeightedIndex::new(allowed.iter().map(|&(_, p)| p)) {
+            Ok(index) => Some(allowed[index.sample(&mut self.rng)].0),
+            // All allowed tokens are improbable
+            Err(_) => Some(first),
+        }
+    }
 }
 
 #[async_trait::async_trait]
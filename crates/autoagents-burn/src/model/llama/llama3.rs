@@ -3,7 +3,9 @@ use crate::model::llama::chat::{GenerationConfig, LLamaModel, LlamaChat};
 use crate::model::llama::tokenizer::Tiktoken;
 use crate::model::llama::{Llama, LlamaConfig};
 use crate::utils::{spawn_blocking, CustomMutex};
+use autoagents_llm::chat_template::ChatTemplate;
 use autoagents_llm::error::LLMError;
+use autoagents_llm::grammar::Grammar;
 use log::info;
 use std::marker::PhantomData;
 use std::path::PathBuf;
@@ -35,6 +37,10 @@ pub struct LLama3ModelConfig {
     pub tokenizer_path: PathBuf,
     pub max_seq_len: usize,
     pub generation_config: GenerationConfig,
+    /// Template prompts are formatted with instead of the model's own
+    pub chat_template: Option<ChatTemplate>,
+    /// `tokenizer_config.json` to take the chat template from
+    pub tokenizer_config_path: Option<PathBuf>,
     pub model_variant: Llama3Model,
     pub import: bool,
     pub model_bytes: Option<Vec<u8>>,
@@ -48,6 +54,8 @@ impl Default for LLama3ModelConfig {
             tokenizer_path: PathBuf::from("models/tokenizer.model"),
             max_seq_len: 8192,
             generation_config: GenerationConfig::default(),
+            chat_template: None,
+            tokenizer_config_path: None,
             model_variant: Llama3Model::default(),
             import: false,
             model_bytes: None,
@@ -79,6 +87,20 @@ impl Llama3Builder {
         self
     }
 
+    /// Take the chat template from the model's `tokenizer_config.json`, or
+    /// the `chat_template.jinja` next to it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tokenizer_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.tokenizer_config_path = Some(path.into());
+        self
+    }
+
+    /// Format prompts with `template` instead of the model's own
+    pub fn chat_template(mut self, template: ChatTemplate) -> Self {
+        self.config.chat_template = Some(template);
+        self
+    }
+
     pub fn max_seq_len(mut self, len: usize) -> Self {
         self.config.max_seq_len = len;
         self
@@ -94,6 +116,14 @@ impl Llama3Builder {
         self
     }
 
+    /// Constrain every answer to `grammar`, e.g. a GBNF grammar or one
+    /// derived from a JSON schema. A schema sent with a request takes
+    /// precedence.
+    pub fn grammar(mut self, grammar: Grammar) -> Self {
+        self.config.generation_config.grammar = Some(grammar);
+        self
+    }
+
     /// Set the Llama3 model variant
     pub fn model_variant(mut self, variant: Llama3Model) -> Self {
         self.config.model_variant = variant;
@@ -187,7 +217,10 @@ impl Llama3Builder {
             llama: Arc::new(CustomMutex::new(llama)),
             config: self.config.generation_config,
             marker: PhantomData,
-            model: LLamaModel::Llama3,
+            chat_template: LLamaModel::Llama3.chat_template(
+                self.config.chat_template,
+                self.config.tokenizer_config_path.as_deref(),
+            )?,
         }))
     }
 
@@ -237,7 +270,10 @@ impl Llama3Builder {
             llama: Arc::new(CustomMutex::new(llama)),
             config: self.config.generation_config,
             marker: PhantomData,
-            model: LLamaModel::TinyLLama,
+            chat_template: LLamaModel::Llama3.chat_template(
+                self.config.chat_template,
+                self.config.tokenizer_config_path.as_deref(),
+            )?,
         }))
     }
 
@@ -312,7 +348,10 @@ impl Llama3Builder {
             llama: Arc::new(CustomMutex::new(llama)),
             config: self.config.generation_config,
             marker: PhantomData,
-            model: LLamaModel::Llama3,
+            chat_template: LLamaModel::Llama3.chat_template(
+                self.config.chat_template,
+                self.config.tokenizer_config_path.as_deref(),
+            )?,
         }))
     }
 
@@ -405,7 +444,10 @@ impl Llama3Builder {
                 llama: Arc::new(CustomMutex::new(llama)),
                 config: self.config.generation_config,
                 marker: PhantomData,
-                model: LLamaModel::Llama3,
+                chat_template: LLamaModel::Llama3.chat_template(
+                    self.config.chat_template,
+                    self.config.tokenizer_config_path.as_deref(),
+                )?,
             }));
         }
         #[cfg(not(target_arch = "wasm32"))]
@@ -3,7 +3,9 @@ use crate::model::llama::chat::{GenerationConfig, LLamaModel, LlamaChat};
 use crate::model::llama::tokenizer::SentencePieceTokenizer;
 use crate::model::llama::{Llama, LlamaConfig};
 use crate::utils::CustomMutex;
+use autoagents_llm::chat_template::ChatTemplate;
 use autoagents_llm::error::LLMError;
+use autoagents_llm::grammar::Grammar;
 use log::info;
 use std::marker::PhantomData;
 use std::path::PathBuf;
@@ -14,6 +16,10 @@ pub struct TinyLLamaModelConfig {
     pub tokenizer_path: PathBuf,
     pub max_seq_len: usize,
     pub generation_config: GenerationConfig,
+    /// Template prompts are formatted with instead of the model's own
+    pub chat_template: Option<ChatTemplate>,
+    /// `tokenizer_config.json` to take the chat template from
+    pub tokenizer_config_path: Option<PathBuf>,
     #[allow(dead_code)]
     pub import: bool,
     #[allow(dead_code)]
@@ -29,6 +35,8 @@ impl Default for TinyLLamaModelConfig {
             tokenizer_path: PathBuf::from("models/tokenizer.model"),
             max_seq_len: 512,
             generation_config: GenerationConfig::default(),
+            chat_template: None,
+            tokenizer_config_path: None,
             import: false,
             model_bytes: None,
             tokenizer_bytes: None,
@@ -59,6 +67,20 @@ impl TinyLlamaBuilder {
         self
     }
 
+    /// Take the chat template from the model's `tokenizer_config.json`, or
+    /// the `chat_template.jinja` next to it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tokenizer_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.tokenizer_config_path = Some(path.into());
+        self
+    }
+
+    /// Format prompts with `template` instead of the model's own
+    pub fn chat_template(mut self, template: ChatTemplate) -> Self {
+        self.config.chat_template = Some(template);
+        self
+    }
+
     pub fn max_seq_len(mut self, len: usize) -> Self {
         self.config.max_seq_len = len;
         self
@@ -74,6 +96,14 @@ impl TinyLlamaBuilder {
         self
     }
 
+    /// Constrain every answer to `grammar`, e.g. a GBNF grammar or one
+    /// derived from a JSON schema. A schema sent with a request takes
+    /// precedence.
+    pub fn grammar(mut self, grammar: Grammar) -> Self {
+        self.config.generation_config.grammar = Some(grammar);
+        self
+    }
+
     pub fn with_model_bytes(self, _bytes: Vec<u8>) -> Self {
         #[cfg(all(feature = "import", target_arch = "wasm32"))]
         {
@@ -125,7 +155,10 @@ impl TinyLlamaBuilder {
             llama: Arc::new(CustomMutex::new(llama)),
             config: self.config.generation_config,
             marker: PhantomData,
-            model: LLamaModel::TinyLLama,
+            chat_template: LLamaModel::TinyLLama.chat_template(
+                self.config.chat_template,
+                self.config.tokenizer_config_path.as_deref(),
+            )?,
         }))
     }
 
@@ -250,7 +283,10 @@ impl TinyLlamaBuilder {
             llama: Arc::new(CustomMutex::new(llama)),
             config: self.config.generation_config,
             marker: PhantomData,
-            model: LLamaModel::TinyLLama,
+            chat_template: LLamaModel::TinyLLama.chat_template(
+                self.config.chat_template,
+                self.config.tokenizer_config_path.as_deref(),
+            )?,
         }))
     }
 }
@@ -29,6 +29,12 @@ pub trait Tokenizer: Send + Sync + Clone + 'static {
     /// Stop token identifiers.
     fn stop_ids(&self) -> Vec<u32>;
 
+    /// Bytes of the text a token stands for, `None` for special tokens.
+    /// Grammar constrained sampling checks candidate tokens by these.
+    fn token_bytes(&self, token: u32) -> Option<Vec<u8>> {
+        Some(self.decode(&[token]).into_bytes())
+    }
+
     /// Number of tokens needed as context for incremental streaming decoding.
     /// Default is 0 (no context/buffering needed).
     fn streaming_context_size(&self) -> usize {
@@ -46,4 +46,8 @@ impl Tokenizer for ByteTokenizer {
     fn stop_ids(&self) -> Vec<u32> {
         vec![2]
     }
+
+    fn token_bytes(&self, token: u32) -> Option<Vec<u8>> {
+        u8::try_from(token).ok().map(|byte| vec![byte])
+    }
 }
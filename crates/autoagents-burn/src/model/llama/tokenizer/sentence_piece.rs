@@ -75,6 +75,19 @@ impl Tokenizer for SentencePieceTokenizer {
         vec![self.eos_id()]
     }
 
+    fn token_bytes(&self, token: u32) -> Option<Vec<u8>> {
+        // <unk>, <s> and </s> come first
+        if token <= self.eos_token_id {
+            return None;
+        }
+        let piece = self.bpe.id_to_token(token)?;
+        // Byte fallback pieces such as <0xE2> stand for a single byte
+        if let Some(hex) = piece.strip_prefix("<0x").and_then(|p| p.strip_suffix('>')) {
+            return u8::from_str_radix(hex, 16).ok().map(|byte| vec![byte]);
+        }
+        Some(piece.replace('\u{2581}', " ").into_bytes())
+    }
+
     fn streaming_context_size(&self) -> usize {
         // SentencePiece tokens represent subwords with special markers (e.g., _ suffix for spaces),
         // requiring a short token buffer for correct incremental decoding.
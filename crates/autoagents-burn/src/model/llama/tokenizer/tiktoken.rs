@@ -1,6 +1,7 @@
 use std::{
     fs::File,
     io::{BufRead, BufReader},
+    sync::Arc,
 };
 
 use base64::{engine::general_purpose::STANDARD, Engine};
@@ -37,6 +38,8 @@ pub struct Tiktoken {
     eos_token_id: usize,
     eot_token_id: usize,
     eom_token_id: usize,
+    /// Bytes of each token of the base vocabulary, by rank
+    token_bytes: Arc<Vec<Vec<u8>>>,
 }
 
 impl Tiktoken {
@@ -61,6 +64,7 @@ impl Tiktoken {
             mergeable_ranks.insert(token, rank);
         }
         let num_base_tokens = mergeable_ranks.len();
+        let token_bytes = token_bytes(&mergeable_ranks);
 
         let special_tokens = [
             SPECIAL_TOKENS
@@ -91,10 +95,22 @@ impl Tiktoken {
             eos_token_id,
             eot_token_id,
             eom_token_id,
+            token_bytes,
         })
     }
 }
 
+/// The bytes of each rank of `mergeable_ranks`
+fn token_bytes(mergeable_ranks: &HashMap<Vec<u8>, usize>) -> Arc<Vec<Vec<u8>>> {
+    let mut token_bytes = vec![Vec::new(); mergeable_ranks.len()];
+    for (bytes, &rank) in mergeable_ranks {
+        if let Some(slot) = token_bytes.get_mut(rank) {
+            *slot = bytes.clone();
+        }
+    }
+    Arc::new(token_bytes)
+}
+
 impl Tokenizer for Tiktoken {
     /// Load the [Tiktoken](https://github.com/openai/tiktoken) tokenizer.
     fn new(tiktoken_bpe_file: &str) -> Result<Self, String> {
@@ -119,6 +135,7 @@ impl Tokenizer for Tiktoken {
             mergeable_ranks.insert(token, rank);
         }
         let num_base_tokens = mergeable_ranks.len();
+        let token_bytes = token_bytes(&mergeable_ranks);
 
         let special_tokens = [
             SPECIAL_TOKENS
@@ -149,6 +166,7 @@ impl Tokenizer for Tiktoken {
             eos_token_id,
             eot_token_id,
             eom_token_id,
+            token_bytes,
         })
     }
 
@@ -186,4 +204,9 @@ impl Tokenizer for Tiktoken {
             self.eot_token_id as u32,
         ]
     }
+
+    fn token_bytes(&self, token: u32) -> Option<Vec<u8>> {
+        // Special tokens follow the base vocabulary
+        self.token_bytes.get(token as usize).cloned()
+    }
 }
@@ -4,6 +4,8 @@ use crate::agent::base::AgentType;
 use crate::agent::error::{AgentBuildError, RunnableAgentError};
 use crate::agent::hooks::AgentHooks;
 use crate::agent::state::AgentState;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::agent::supervisor::{AgentSupervisor, RestartStrategy};
 use crate::agent::task::Task;
 use crate::agent::{AgentBuilder, AgentDeriveT, AgentExecutor, BaseAgent, HookOutcome};
 use crate::channel::Sender;
@@ -87,16 +89,37 @@ where
         ))?;
         let tx = runtime.tx();
 
-        let agent: Arc<BaseAgent<T, ActorAgent>> = Arc::new(
-            BaseAgent::<T, ActorAgent>::new(self.inner, llm, self.memory, tx, self.stream).await?,
-        );
+        let mut base_agent =
+            BaseAgent::<T, ActorAgent>::new(self.inner, llm, self.memory, tx, self.stream).await?;
+        base_agent.tool_selector = self.tool_selector;
+        base_agent.guardrails = self.guardrails;
+        base_agent.memory_rollback = self.memory_rollback;
+        base_agent.tool_middleware = self.tool_middleware.into();
+        base_agent.sub_agent_limits = self.sub_agent_limits;
+        base_agent.prompt_variables = self.prompt_variables;
+        base_agent.few_shot = self.few_shot;
+        base_agent.tool_cache_scope = self.tool_cache_scope;
+        base_agent.credentials = self.credentials;
+        base_agent.audit_sink = self.audit_sink;
+        base_agent.event_bus = self.event_bus;
+        let agent: Arc<BaseAgent<T, ActorAgent>> = Arc::new(base_agent);
 
-        // Create agent actor
-        let agent_actor = AgentActor(agent.clone());
-        let actor_ref = Actor::spawn(Some(agent_actor.0.name().into()), agent_actor, ())
-            .await
-            .map_err(AgentBuildError::SpawnError)?
-            .0;
+        // Create agent actor, behind a supervisor when restarts are requested
+        let name = Some(agent.name().to_string());
+        let actor_ref = match self.supervision {
+            Some(strategy) => {
+                Actor::spawn(name, AgentSupervisor::new(agent.clone(), strategy), ())
+                    .await
+                    .map_err(AgentBuildError::SpawnError)?
+                    .0
+            }
+            None => {
+                Actor::spawn(name, AgentActor(agent.clone()), ())
+                    .await
+                    .map_err(AgentBuildError::SpawnError)?
+                    .0
+            }
+        };
 
         // Subscribe to topics
         for topic in self.subscribed_topics {
@@ -110,6 +133,12 @@ where
         self.subscribed_topics.push(topic);
         self
     }
+
+    /// Run the agent under a supervisor that restarts it when a task fails
+    pub fn supervise(mut self, strategy: RestartStrategy) -> Self {
+        self.supervision = Some(strategy);
+        self
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -129,7 +158,8 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, ActorAgent> {
         let submission_id = task.submission_id;
         let tx = self.tx().map_err(|_| RunnableAgentError::EmptyTx)?;
 
-        let context = self.create_context();
+        let context = self.create_context(&task).await;
+        let task = self.guard_input(task, &context).await?;
 
         //Run Hook
         let hook_outcome = self.inner.on_run_start(&task, &context).await;
@@ -138,14 +168,25 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, ActorAgent> {
             HookOutcome::Continue => {}
         }
 
+        let checkpoint = self.checkpoint_memory(&context).await;
+
         // Execute the agent's logic using the executor
-        match self.inner().execute(&task, context.clone()).await {
+        let result = match context
+            .scoped(self.inner().execute(&task, context.clone()))
+            .await
+        {
+            Ok(output) => self.guard_output(output, &task, &context).await,
+            Err(e) => Err(RunnableAgentError::ExecutorError(e.to_string())),
+        };
+        self.settle_memory(checkpoint, &result).await;
+        match result {
             Ok(output) => {
                 let value: Value = output.clone().into();
 
                 #[cfg(not(target_arch = "wasm32"))]
                 tx.send(Event::TaskComplete {
                     sub_id: submission_id,
+                    run_id: context.run_id(),
                     actor_id: self.id,
                     actor_name: self.name().to_string(),
                     result: serde_json::to_string_pretty(&value)
@@ -168,12 +209,13 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, ActorAgent> {
                 #[cfg(not(target_arch = "wasm32"))]
                 tx.send(Event::TaskError {
                     sub_id: submission_id,
+                    run_id: context.run_id(),
                     actor_id: self.id,
                     error: e.to_string(),
                 })
                 .await
                 .map_err(|e| RunnableAgentError::ExecutorError(e.to_string()))?;
-                Err(RunnableAgentError::ExecutorError(e.to_string()))
+                Err(e)
             }
         }
     }
@@ -191,29 +233,31 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, ActorAgent> {
         <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
     {
         // let submission_id = task.submission_id;
-        let context = self.create_context();
+        let context = self.create_context(&task).await;
+        let task = self.guard_input(task, &context).await?;
+
+        let checkpoint = self.checkpoint_memory(&context).await;
 
         // Execute the agent's streaming logic using the executor
-        match self.inner().execute_stream(&task, context).await {
+        match context
+            .scoped(self.inner().execute_stream(&task, context.clone()))
+            .await
+        {
             Ok(stream) => {
                 use futures::StreamExt;
                 // Transform the stream to convert agent output to TaskResult
-                let transformed_stream = stream.map(move |result| {
-                    match result {
-                        Ok(output) => Ok(output.into()),
-                        Err(e) => {
-                            // Handle error
-                            let error_msg = e.to_string();
-                            Err(RunnableAgentError::ExecutorError(error_msg))
-                        }
-                    }
-                });
+                let guarded = self.guard_output_stream(stream, task, context);
+                let transformed_stream = self
+                    .settle_memory_stream(checkpoint, guarded)
+                    .map(|result| result.map(Into::into));
 
                 Ok(Box::pin(transformed_stream))
             }
             Err(e) => {
                 // Send error event for stream creation failure
-                Err(RunnableAgentError::ExecutorError(e.to_string()))
+                let result = Err(RunnableAgentError::ExecutorError(e.to_string()));
+                self.settle_memory(checkpoint, &result).await;
+                result
             }
         }
     }
@@ -1,13 +1,33 @@
 use crate::agent::config::AgentConfig;
-use crate::agent::memory::MemoryProvider;
+use crate::agent::executor::event_helper::EventHelper;
+use crate::agent::few_shot::FewShotExamples;
+use crate::agent::guardrail::{GuardrailChain, GuardrailStage, GuardrailViolation};
+use crate::agent::memory::{MemoryCheckpoint, MemoryProvider, MemoryRollback};
+use crate::agent::prompt::PromptVariables;
+use crate::agent::subagent::SubAgentLimits;
+use crate::agent::task::Task;
 use crate::agent::{output::AgentOutputT, AgentExecutor, Context};
-use crate::protocol::Event;
-use crate::{protocol::ActorID, tool::ToolT};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::event_bus::EventBus;
+use crate::protocol::{Event, RunId};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::tool::{
+    AuditSink, AuditedTool, CredentialProvider, CredentialScopedTool, ToolCacheScope,
+    ToolResultCache,
+};
+use crate::{
+    protocol::ActorID,
+    tool::{MiddlewareTool, RuntimeToolRegistry, ToolMiddleware, ToolSelector, ToolT},
+};
 use async_trait::async_trait;
+use autoagents_llm::audio::{Audio, SpeechRequest, TranscriptionRequest};
 use autoagents_llm::LLMProvider;
 
+use futures::{Stream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 use std::marker::PhantomData;
+use std::pin::Pin;
 use std::{fmt::Debug, sync::Arc};
 
 #[cfg(target_arch = "wasm32")]
@@ -64,6 +84,37 @@ pub struct BaseAgent<T: AgentDeriveT + AgentExecutor + AgentHooks + Send + Sync,
     pub(crate) tx: Option<Sender<Event>>,
     //Stream
     pub(crate) stream: bool,
+    /// Tools offered to each run, changeable after the agent is built
+    pub(crate) tool_registry: RuntimeToolRegistry,
+    /// Optional per-turn tool filter
+    pub(crate) tool_selector: Option<Arc<dyn ToolSelector>>,
+    /// Input and output checks applied around every run
+    pub(crate) guardrails: GuardrailChain,
+    /// When memory writes from a failed run are undone
+    pub(crate) memory_rollback: MemoryRollback,
+    /// Run around every tool call, outermost first
+    pub(crate) tool_middleware: Arc<[Arc<dyn ToolMiddleware>]>,
+    /// Scope of tool result caching, disabled when None
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) tool_cache_scope: Option<ToolCacheScope>,
+    /// Secrets offered to the agent's tools while they run
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) credentials: Option<Arc<dyn CredentialProvider>>,
+    /// Where every tool call is recorded, disabled when None
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) audit_sink: Option<Arc<dyn AuditSink>>,
+    /// Depth and fan-out bounds for sub-agents spawned during a run
+    pub(crate) sub_agent_limits: SubAgentLimits,
+    /// Bus exposed to executors through the run context
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) event_bus: Option<EventBus>,
+    /// Session variables for templated descriptions, shared by every run
+    pub(crate) prompt_variables: PromptVariables,
+    /// Few-shot examples and how they are selected for a run
+    pub(crate) few_shot: FewShotExamples,
+    /// Cache shared across runs for session scoped caching
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) tool_cache: Arc<ToolResultCache>,
     pub(crate) marker: PhantomData<A>,
 }
 
@@ -83,12 +134,30 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks, A: AgentType> BaseAgent<T, A>
         stream: bool,
     ) -> Result<Self, RunnableAgentError> {
         let agent = Self {
+            tool_registry: RuntimeToolRegistry::new(inner.tools()),
             inner: Arc::new(inner),
             id: Uuid::new_v4(),
             llm,
             tx: Some(tx),
             memory: memory.map(|m| Arc::new(Mutex::new(m))),
             stream,
+            tool_selector: None,
+            guardrails: GuardrailChain::new(),
+            memory_rollback: MemoryRollback::Never,
+            tool_middleware: Arc::new([]),
+            #[cfg(not(target_arch = "wasm32"))]
+            tool_cache_scope: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            credentials: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            audit_sink: None,
+            sub_agent_limits: SubAgentLimits::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            event_bus: None,
+            prompt_variables: PromptVariables::new(),
+            few_shot: FewShotExamples::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            tool_cache: Arc::new(ToolResultCache::new()),
             marker: PhantomData,
         };
 
@@ -114,21 +183,263 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks, A: AgentType> BaseAgent<T, A>
 
     /// Get the tools as Arc-wrapped references
     pub fn tools(&self) -> Vec<Box<dyn ToolT>> {
-        self.inner.tools()
+        self.tool_registry.snapshot()
+    }
+
+    /// The agent's tools, starting out as those of the agent definition.
+    /// Tools added, replaced or removed through it are used from the next
+    /// run on, by this agent and all its clones.
+    pub fn tool_registry(&self) -> RuntimeToolRegistry {
+        self.tool_registry.clone()
     }
 
     pub fn stream(&self) -> bool {
         self.stream
     }
 
-    pub(crate) fn create_context(&self) -> Arc<Context> {
-        Arc::new(
-            Context::new(self.llm(), self.tx.clone())
-                .with_memory(self.memory())
-                .with_tools(self.tools())
-                .with_config(self.agent_config())
-                .with_stream(self.stream()),
+    /// Variables a templated description can use, shared by this agent and
+    /// all its clones. Changes apply from the next run on.
+    pub fn prompt_variables(&self) -> PromptVariables {
+        self.prompt_variables.clone()
+    }
+
+    /// Few-shot examples attached to the agent
+    pub fn few_shot(&self) -> &FewShotExamples {
+        &self.few_shot
+    }
+
+    /// Session-scoped tool result cache, shared by every run of this agent
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tool_cache(&self) -> Arc<ToolResultCache> {
+        self.tool_cache.clone()
+    }
+
+    /// Tools for a new run, limited to the task's tool groups, given the
+    /// agent's credentials, wrapped with the result cache when caching is
+    /// enabled, run through the agent's middleware and audited when the
+    /// agent keeps an audit log
+    fn run_tools(&self, task: &Task, run_id: RunId) -> Vec<Box<dyn ToolT>> {
+        let mut tools = self.tools();
+        tools.retain(|tool| task.tool_groups.allows(tool.as_ref()));
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(provider) = &self.credentials {
+                tools = tools
+                    .into_iter()
+                    .map(|tool| CredentialScopedTool::wrap(provider, tool))
+                    .collect();
+            }
+            if let Some(scope) = self.tool_cache_scope {
+                let cache = match scope {
+                    ToolCacheScope::Session => self.tool_cache.clone(),
+                    ToolCacheScope::Run => Arc::new(ToolResultCache::new()),
+                };
+                tools = tools.into_iter().map(|tool| cache.wrap(tool)).collect();
+            }
+        }
+        // Outside the cache so policies also apply to calls answered from it
+        if !self.tool_middleware.is_empty() {
+            tools = tools
+                .into_iter()
+                .map(|tool| MiddlewareTool::wrap(&self.tool_middleware, tool))
+                .collect();
+        }
+        // Audit last so cache hits are recorded too
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(sink) = &self.audit_sink {
+                tools = tools
+                    .into_iter()
+                    .map(|tool| AuditedTool::wrap(sink, self.name(), run_id, tool))
+                    .collect();
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = run_id;
+        tools
+    }
+
+    /// Memory for a run, narrowed to the caller's namespace when the agent's
+    /// memory is partitioned by user or tenant
+    async fn run_memory(&self, task: &Task) -> Option<Arc<Mutex<Box<dyn MemoryProvider>>>> {
+        let memory = self.memory()?;
+        let Some(namespace) = task.metadata.memory_namespace() else {
+            return Some(memory);
+        };
+        let scoped = memory.lock().await.namespace(&namespace);
+        Some(scoped.unwrap_or(memory))
+    }
+
+    pub(crate) async fn create_context(&self, task: &Task) -> Arc<Context> {
+        let run_id = Uuid::new_v4();
+        let context = Context::new(self.llm(), self.tx.clone())
+            .with_run_id(run_id)
+            .with_memory(self.run_memory(task).await)
+            .with_tools(self.run_tools(task, run_id))
+            .with_tool_selector(self.tool_selector.clone())
+            .with_config(self.agent_config())
+            .with_stream(self.stream())
+            .with_metadata(task.metadata.clone())
+            .with_payload(task.payload.clone())
+            .with_prompt_variables(self.prompt_variables.snapshot())
+            .with_examples(self.few_shot.select(&task.prompt).await)
+            .with_attachments(task.attachments.clone())
+            .with_sub_agent_limits(self.sub_agent_limits);
+        #[cfg(not(target_arch = "wasm32"))]
+        let context = context.with_event_bus(self.event_bus.clone());
+        Arc::new(context)
+    }
+
+    /// Transcribe any spoken input, then run the input guardrails over the
+    /// task prompt, applying any rewrite.
+    pub(crate) async fn guard_input(
+        &self,
+        task: Task,
+        context: &Context,
+    ) -> Result<Task, RunnableAgentError> {
+        let mut task = self.transcribe_input(task).await?;
+        if !self.guardrails.covers(GuardrailStage::Input) {
+            return Ok(task);
+        }
+        let content = std::mem::take(&mut task.prompt);
+        task.prompt = apply_guardrails(
+            &self.guardrails,
+            GuardrailStage::Input,
+            content,
+            task.submission_id,
+            context,
         )
+        .await?;
+        Ok(task)
+    }
+
+    /// Replace the task's audio with its transcript, appended to the prompt.
+    async fn transcribe_input(&self, mut task: Task) -> Result<Task, RunnableAgentError> {
+        let Some(audio) = task.audio.take() else {
+            return Ok(task);
+        };
+        let stt = self.llm.speech_to_text().ok_or_else(|| {
+            RunnableAgentError::task_error("The LLM provider cannot transcribe audio input")
+        })?;
+        let transcription = stt
+            .transcribe(&TranscriptionRequest::new(audio))
+            .await
+            .map_err(|e| RunnableAgentError::Other(Box::new(e)))?;
+        if !task.prompt.is_empty() {
+            task.prompt.push_str("\n\n");
+        }
+        task.prompt.push_str(&transcription.text);
+        Ok(task)
+    }
+
+    /// Run the output guardrails over the executor's final response.
+    pub(crate) async fn guard_output(
+        &self,
+        output: <T as AgentExecutor>::Output,
+        task: &Task,
+        context: &Context,
+    ) -> Result<<T as AgentExecutor>::Output, RunnableAgentError> {
+        check_output(&self.guardrails, output, task, context).await
+    }
+
+    /// Convert the executor errors of a streamed run and run the output
+    /// guardrails over its last item.
+    ///
+    /// With output guardrails configured, every item is held back until the
+    /// stream ends: partial outputs such as streamed deltas are released
+    /// only once the final response passes, so a blocked response is never
+    /// emitted in part. A blocked or failed run emits just its errors.
+    pub(crate) fn guard_output_stream<E: std::fmt::Display + 'static>(
+        &self,
+        stream: Pin<Box<dyn Stream<Item = Result<<T as AgentExecutor>::Output, E>> + Send>>,
+        task: Task,
+        context: Arc<Context>,
+    ) -> Pin<Box<dyn Stream<Item = Result<<T as AgentExecutor>::Output, RunnableAgentError>> + Send>>
+    {
+        let stream = stream
+            .map(|result| result.map_err(|e| RunnableAgentError::ExecutorError(e.to_string())));
+        if !self.guardrails.covers(GuardrailStage::Output) {
+            return Box::pin(stream);
+        }
+        let guardrails = self.guardrails.clone();
+        Box::pin(
+            futures::stream::once(async move {
+                let mut items: Vec<_> = stream.collect().await;
+                let released = match items.pop() {
+                    Some(Ok(output)) => {
+                        match check_output(&guardrails, output, &task, &context).await {
+                            Ok(output) => {
+                                items.push(Ok(output));
+                                items
+                            }
+                            Err(e) => vec![Err(e)],
+                        }
+                    }
+                    Some(Err(e)) => {
+                        items.retain(Result::is_err);
+                        items.push(Err(e));
+                        items
+                    }
+                    None => items,
+                };
+                futures::stream::iter(released)
+            })
+            .flatten(),
+        )
+    }
+
+    /// Checkpoint the run's memory when the rollback policy may need it.
+    pub(crate) async fn checkpoint_memory(&self, context: &Context) -> Option<MemoryCheckpoint> {
+        if self.memory_rollback == MemoryRollback::Never {
+            return None;
+        }
+        match MemoryCheckpoint::take(context.memory()?).await {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                log::warn!("Failed to checkpoint memory, rollback disabled for this run: {e}");
+                None
+            }
+        }
+    }
+
+    /// Roll the memory back to `checkpoint` if the run's outcome calls for it.
+    pub(crate) async fn settle_memory<O>(
+        &self,
+        checkpoint: Option<MemoryCheckpoint>,
+        result: &Result<O, RunnableAgentError>,
+    ) {
+        settle_checkpoint(self.memory_rollback, checkpoint, result.as_ref().err()).await;
+    }
+
+    /// Settle `checkpoint` when a streamed run ends: with its first error,
+    /// before that error is emitted, or as a success once the stream is
+    /// exhausted. A stream dropped early keeps the memory as it is.
+    pub(crate) fn settle_memory_stream<O: Send + 'static>(
+        &self,
+        checkpoint: Option<MemoryCheckpoint>,
+        stream: Pin<Box<dyn Stream<Item = Result<O, RunnableAgentError>> + Send>>,
+    ) -> Pin<Box<dyn Stream<Item = Result<O, RunnableAgentError>> + Send>> {
+        if checkpoint.is_none() {
+            return stream;
+        }
+        let policy = self.memory_rollback;
+        Box::pin(futures::stream::unfold(
+            (stream, checkpoint),
+            move |(mut stream, mut checkpoint)| async move {
+                match stream.next().await {
+                    Some(item) => {
+                        if let Err(e) = &item {
+                            settle_checkpoint(policy, checkpoint.take(), Some(e)).await;
+                        }
+                        Some((item, (stream, checkpoint)))
+                    }
+                    None => {
+                        settle_checkpoint(policy, checkpoint.take(), None).await;
+                        None
+                    }
+                }
+            },
+        ))
     }
 
     pub fn agent_config(&self) -> AgentConfig {
@@ -148,17 +459,125 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks, A: AgentType> BaseAgent<T, A>
         self.llm.clone()
     }
 
+    /// Read text aloud with the LLM provider, e.g. to answer a voice task
+    pub async fn speak(&self, request: &SpeechRequest) -> Result<Audio, RunnableAgentError> {
+        let tts = self.llm.text_to_speech().ok_or_else(|| {
+            RunnableAgentError::task_error("The LLM provider cannot synthesize speech")
+        })?;
+        tts.speech(request)
+            .await
+            .map_err(|e| RunnableAgentError::Other(Box::new(e)))
+    }
+
     /// Get the memory provider if available
     pub fn memory(&self) -> Option<Arc<Mutex<Box<dyn MemoryProvider>>>> {
         self.memory.clone()
     }
 }
 
+/// Run the output guardrails over an executor's final response.
+///
+/// The checked text is the `response` field when the output has one, the
+/// serialized output otherwise. Rewrites are written back the same way.
+async fn check_output<O: Serialize + DeserializeOwned>(
+    guardrails: &GuardrailChain,
+    output: O,
+    task: &Task,
+    context: &Context,
+) -> Result<O, RunnableAgentError> {
+    if !guardrails.covers(GuardrailStage::Output) {
+        return Ok(output);
+    }
+    let mut value = serde_json::to_value(&output)
+        .map_err(|e| RunnableAgentError::SerializationError(e.to_string()))?;
+    let content = match &value {
+        Value::String(text) => text.clone(),
+        Value::Object(map) => match map.get("response") {
+            Some(Value::String(text)) => text.clone(),
+            _ => value.to_string(),
+        },
+        other => other.to_string(),
+    };
+    let checked = apply_guardrails(
+        guardrails,
+        GuardrailStage::Output,
+        content.clone(),
+        task.submission_id,
+        context,
+    )
+    .await?;
+    if checked == content {
+        return Ok(output);
+    }
+    match &mut value {
+        Value::Object(map) if map.get("response").is_some_and(Value::is_string) => {
+            map.insert("response".into(), Value::String(checked));
+        }
+        Value::String(_) => value = Value::String(checked),
+        _ => {
+            value = serde_json::from_str(&checked)
+                .map_err(|e| RunnableAgentError::SerializationError(e.to_string()))?
+        }
+    }
+    serde_json::from_value(value).map_err(|e| RunnableAgentError::SerializationError(e.to_string()))
+}
+
+async fn apply_guardrails(
+    guardrails: &GuardrailChain,
+    stage: GuardrailStage,
+    content: String,
+    sub_id: crate::protocol::SubmissionId,
+    context: &Context,
+) -> Result<String, RunnableAgentError> {
+    let tx = context.tx().ok();
+    let notify = |violation: GuardrailViolation| {
+        EventHelper::send_guardrail_triggered(&tx, sub_id, context.run_id(), violation)
+    };
+    match guardrails.run(stage, content).await {
+        Ok(report) => {
+            for violation in report.violations {
+                context.record_guardrail_violation(violation.clone());
+                notify(violation).await;
+            }
+            Ok(report.content)
+        }
+        Err(violation) => {
+            context.record_guardrail_violation(violation.clone());
+            notify(violation.clone()).await;
+            Err(RunnableAgentError::GuardrailBlocked(Box::new(violation)))
+        }
+    }
+}
+
+/// Roll the memory back to `checkpoint` if `policy` calls for it given the
+/// run's `failure`, and keep it otherwise.
+async fn settle_checkpoint(
+    policy: MemoryRollback,
+    checkpoint: Option<MemoryCheckpoint>,
+    failure: Option<&RunnableAgentError>,
+) {
+    let Some(checkpoint) = checkpoint else {
+        return;
+    };
+    let rollback = match (policy, failure) {
+        (_, None) | (MemoryRollback::Never, _) => false,
+        (MemoryRollback::OnFailure, Some(_)) => true,
+        (MemoryRollback::OnGuardrailBlock, Some(e)) => {
+            matches!(e, RunnableAgentError::GuardrailBlocked(_))
+        }
+    };
+    if !rollback {
+        checkpoint.commit();
+    } else if let Err(e) = checkpoint.rollback().await {
+        log::warn!("Failed to roll back memory: {e}");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agent::{AgentConfig, DirectAgent};
-    use crate::tests::agent::MockAgentImpl;
+    use crate::tests::agent::{MockAgentImpl, MockTool};
     use autoagents_llm::chat::StructuredOutputFormat;
     use autoagents_test_utils::llm::MockLLMProvider;
     use std::sync::Arc;
@@ -230,6 +649,45 @@ mod tests {
         assert!(base_agent.memory().is_some());
     }
 
+    #[tokio::test]
+    async fn test_runs_use_the_callers_memory_namespace() {
+        use crate::agent::memory::{NamespacedMemory, SlidingWindowMemory};
+        use autoagents_llm::chat::ChatMessage;
+
+        let memory = NamespacedMemory::from_template(Box::new(SlidingWindowMemory::new(5)));
+        let (tx, _): (Sender<Event>, Receiver<Event>) = channel(32);
+        let base_agent = BaseAgent::<_, DirectAgent>::new(
+            MockAgentImpl::new("test", "test description"),
+            Arc::new(MockLLMProvider),
+            Some(Box::new(memory.clone())),
+            tx,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let task_for = |user: &str| Task::new("hi").with_user_id(user);
+        let alice = base_agent.create_context(&task_for("alice")).await;
+        alice
+            .memory()
+            .unwrap()
+            .lock()
+            .await
+            .remember(&ChatMessage::user().content("I'm Alice").build())
+            .await
+            .unwrap();
+        let bob = base_agent.create_context(&task_for("bob")).await;
+        assert!(bob.memory().unwrap().lock().await.is_empty());
+
+        let alice_again = base_agent.create_context(&task_for("alice")).await;
+        assert_eq!(alice_again.memory().unwrap().lock().await.size(), 1);
+        assert_eq!(memory.namespaces(), vec!["/alice", "/bob"]);
+
+        // Runs without a user or tenant share the default memory
+        let anonymous = base_agent.create_context(&Task::new("hi")).await;
+        assert!(anonymous.memory().unwrap().lock().await.is_empty());
+    }
+
     #[tokio::test]
     async fn test_base_agent_inner() {
         let mock_agent = MockAgentImpl::new("test", "test description");
@@ -257,6 +715,27 @@ mod tests {
         assert!(tools.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_tools_added_at_runtime_reach_new_runs() {
+        let mock_agent = MockAgentImpl::new("test", "test description");
+        let llm = Arc::new(MockLLMProvider);
+        let (tx, _): (Sender<Event>, Receiver<Event>) = channel(32);
+        let base_agent = BaseAgent::<_, DirectAgent>::new(mock_agent, llm, None, tx, false)
+            .await
+            .unwrap();
+        let registry = base_agent.tool_registry();
+
+        registry
+            .add(Arc::new(MockTool::new("mock_tool", "mock")))
+            .unwrap();
+        let context = base_agent.create_context(&Task::new("hi")).await;
+        assert_eq!(context.tools().len(), 1);
+        assert_eq!(context.tools()[0].name(), "mock_tool");
+
+        registry.remove("mock_tool");
+        assert!(base_agent.tools().is_empty());
+    }
+
     #[tokio::test]
     async fn test_base_agent_llm() {
         let mock_agent = MockAgentImpl::new("test", "test description");
@@ -285,4 +764,263 @@ mod tests {
         assert!(base_agent.memory().is_none());
         assert!(base_agent.stream);
     }
+
+    #[tokio::test]
+    async fn test_guardrails_rewrite_input_and_output() {
+        use crate::agent::guardrail::{GuardrailAction, RegexGuardrail};
+        use crate::agent::AgentBuilder;
+        use futures::StreamExt;
+
+        let handle = AgentBuilder::<_, DirectAgent>::new(MockAgentImpl::new("guarded", "test"))
+            .llm(Arc::new(MockLLMProvider))
+            .guardrail(
+                RegexGuardrail::new("email", r"\S+@\S+")
+                    .unwrap()
+                    .action(GuardrailAction::Redact("<email>".into()))
+                    .stage(GuardrailStage::Input),
+            )
+            .guardrail(
+                RegexGuardrail::denylist("profanity", ["Processed"])
+                    .unwrap()
+                    .action(GuardrailAction::Redact("Handled".into()))
+                    .stage(GuardrailStage::Output),
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let output = handle
+            .agent
+            .run(Task::new("mail bob@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(output.result, "Handled: mail <email>");
+
+        drop(handle.agent);
+        let events: Vec<Event> = handle.rx.collect().await;
+        let triggered: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::GuardrailTriggered { violation, .. } => Some(violation.guardrail.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(triggered, vec!["email", "profanity"]);
+    }
+
+    #[tokio::test]
+    async fn test_guardrail_block_stops_run() {
+        use crate::agent::guardrail::RegexGuardrail;
+        use crate::agent::AgentBuilder;
+
+        let handle = AgentBuilder::<_, DirectAgent>::new(MockAgentImpl::new("guarded", "test"))
+            .llm(Arc::new(MockLLMProvider))
+            .guardrail(RegexGuardrail::denylist("deny", ["drop table"]).unwrap())
+            .build()
+            .await
+            .unwrap();
+
+        let err = handle
+            .agent
+            .run(Task::new("please DROP TABLE users"))
+            .await
+            .unwrap_err();
+        match err {
+            RunnableAgentError::GuardrailBlocked(violation) => {
+                assert_eq!(violation.guardrail, "deny");
+                assert_eq!(violation.stage, GuardrailStage::Input);
+            }
+            other => panic!("expected guardrail block, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_guardrails_check_final_streamed_output() {
+        use crate::agent::guardrail::{GuardrailAction, RegexGuardrail};
+        use crate::agent::AgentBuilder;
+        use crate::error::Error;
+        use futures::StreamExt;
+
+        let handle = AgentBuilder::<_, DirectAgent>::new(MockAgentImpl::new("guarded", "test"))
+            .llm(Arc::new(MockLLMProvider))
+            .guardrail(
+                RegexGuardrail::denylist("profanity", ["Processed"])
+                    .unwrap()
+                    .action(GuardrailAction::Redact("Handled".into()))
+                    .stage(GuardrailStage::Output),
+            )
+            .build()
+            .await
+            .unwrap();
+        let outputs: Vec<String> = handle
+            .agent
+            .run_stream(Task::new("hi"))
+            .await
+            .unwrap()
+            .map(|output| output.unwrap().result)
+            .collect()
+            .await;
+        assert_eq!(outputs, vec!["Working", "Handled: hi"]);
+
+        let handle = AgentBuilder::<_, DirectAgent>::new(MockAgentImpl::new("guarded", "test"))
+            .llm(Arc::new(MockLLMProvider))
+            .guardrail(
+                RegexGuardrail::denylist("deny", ["processed"])
+                    .unwrap()
+                    .stage(GuardrailStage::Output),
+            )
+            .build()
+            .await
+            .unwrap();
+        let outputs: Vec<_> = handle
+            .agent
+            .run_stream(Task::new("hi"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        // The partial output is withheld along with the blocked response
+        assert_eq!(outputs.len(), 1);
+        match &outputs[0] {
+            Err(Error::RunnableAgentError(RunnableAgentError::GuardrailBlocked(violation))) => {
+                assert_eq!(violation.guardrail, "deny");
+                assert_eq!(violation.stage, GuardrailStage::Output);
+            }
+            other => panic!("expected guardrail block, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_voice_task_is_transcribed_into_prompt() {
+        use crate::agent::AgentBuilder;
+        use autoagents_llm::audio::AudioFormat;
+
+        let handle = AgentBuilder::<_, DirectAgent>::new(MockAgentImpl::new("voice", "test"))
+            .llm(Arc::new(MockLLMProvider))
+            .build()
+            .await
+            .unwrap();
+
+        let task = Task::new("Answer briefly.")
+            .with_audio(Audio::new(b"what time is it".to_vec(), AudioFormat::Wav));
+        let output = handle.agent.run(task).await.unwrap();
+        assert_eq!(
+            output.result,
+            "Processed: Answer briefly.\n\nwhat time is it"
+        );
+
+        let speech = handle
+            .agent
+            .speak(&SpeechRequest::new("noon"))
+            .await
+            .unwrap();
+        assert_eq!(speech.data, b"noon");
+    }
+
+    #[tokio::test]
+    async fn test_voice_task_needs_speech_to_text() {
+        use crate::agent::AgentBuilder;
+        use autoagents_llm::audio::AudioFormat;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+        let handle = AgentBuilder::<_, DirectAgent>::new(MockAgentImpl::new("voice", "test"))
+            .llm(Arc::new(ScriptedLLMProvider::new(["ok"])))
+            .build()
+            .await
+            .unwrap();
+
+        let task = Task::new_with_audio(Audio::new(vec![0; 4], AudioFormat::Wav));
+        let err = handle.agent.run(task).await.unwrap_err();
+        assert!(err.to_string().contains("cannot transcribe"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_memory_rollback_follows_policy() {
+        use crate::agent::guardrail::ViolationKind;
+        use crate::agent::memory::SlidingWindowMemory;
+        use autoagents_llm::chat::ChatMessage;
+
+        let (tx, _): (Sender<Event>, Receiver<Event>) = channel(32);
+        let mut base_agent = BaseAgent::<_, DirectAgent>::new(
+            MockAgentImpl::new("test", "test description"),
+            Arc::new(MockLLMProvider),
+            Some(Box::new(SlidingWindowMemory::new(5))),
+            tx,
+            false,
+        )
+        .await
+        .unwrap();
+        base_agent.memory_rollback = MemoryRollback::OnGuardrailBlock;
+        let context = base_agent.create_context(&Task::new("hi")).await;
+        let memory = context.memory().unwrap();
+        let draft = ChatMessage::assistant().content("draft").build();
+        let blocked: Result<(), _> = Err(RunnableAgentError::GuardrailBlocked(Box::new(
+            GuardrailViolation {
+                guardrail: "deny".into(),
+                stage: GuardrailStage::Output,
+                kind: ViolationKind::Blocked,
+                reason: "denied".into(),
+            },
+        )));
+        let failed: Result<(), _> = Err(RunnableAgentError::ExecutorError("boom".into()));
+
+        let checkpoint = base_agent.checkpoint_memory(&context).await;
+        memory.lock().await.remember(&draft).await.unwrap();
+        base_agent.settle_memory(checkpoint, &blocked).await;
+        assert_eq!(memory.lock().await.size(), 0);
+
+        // Executor failures only roll back under OnFailure
+        let checkpoint = base_agent.checkpoint_memory(&context).await;
+        memory.lock().await.remember(&draft).await.unwrap();
+        base_agent.settle_memory(checkpoint, &failed).await;
+        assert_eq!(memory.lock().await.size(), 1);
+
+        base_agent.memory_rollback = MemoryRollback::OnFailure;
+        let checkpoint = base_agent.checkpoint_memory(&context).await;
+        memory.lock().await.remember(&draft).await.unwrap();
+        base_agent.settle_memory(checkpoint, &failed).await;
+        assert_eq!(memory.lock().await.size(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_rollback_settles_streams() {
+        use crate::agent::memory::SlidingWindowMemory;
+        use autoagents_llm::chat::ChatMessage;
+
+        let (tx, _): (Sender<Event>, Receiver<Event>) = channel(32);
+        let mut base_agent = BaseAgent::<_, DirectAgent>::new(
+            MockAgentImpl::new("test", "test description"),
+            Arc::new(MockLLMProvider),
+            Some(Box::new(SlidingWindowMemory::new(5))),
+            tx,
+            false,
+        )
+        .await
+        .unwrap();
+        base_agent.memory_rollback = MemoryRollback::OnFailure;
+        let context = base_agent.create_context(&Task::new("hi")).await;
+        let memory = context.memory().unwrap();
+        let draft = ChatMessage::assistant().content("draft").build();
+
+        // A failed stream is rolled back before its error is emitted
+        let checkpoint = base_agent.checkpoint_memory(&context).await;
+        memory.lock().await.remember(&draft).await.unwrap();
+        let items: Vec<Result<u8, RunnableAgentError>> =
+            vec![Ok(1), Err(RunnableAgentError::ExecutorError("boom".into()))];
+        let mut stream =
+            base_agent.settle_memory_stream(checkpoint, Box::pin(futures::stream::iter(items)));
+        assert!(stream.next().await.unwrap().is_ok());
+        assert_eq!(memory.lock().await.size(), 1);
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(memory.lock().await.size(), 0);
+
+        // A stream that runs to completion keeps its writes
+        let checkpoint = base_agent.checkpoint_memory(&context).await;
+        memory.lock().await.remember(&draft).await.unwrap();
+        let items: Vec<Result<u8, RunnableAgentError>> = vec![Ok(1), Ok(2)];
+        let stream =
+            base_agent.settle_memory_stream(checkpoint, Box::pin(futures::stream::iter(items)));
+        assert_eq!(stream.count().await, 2);
+        assert_eq!(memory.lock().await.size(), 1);
+    }
 }
@@ -1,12 +1,24 @@
 #[cfg(not(target_arch = "wasm32"))]
 use crate::actor::Topic;
 use crate::agent::base::AgentType;
+use crate::agent::few_shot::FewShotExamples;
+use crate::agent::guardrail::{Guardrail, GuardrailChain, ModerationGuardrail};
 use crate::agent::hooks::AgentHooks;
-use crate::agent::memory::MemoryProvider;
+use crate::agent::memory::{MemoryProvider, MemoryRollback};
+use crate::agent::prompt::PromptVariables;
+use crate::agent::subagent::SubAgentLimits;
 use crate::agent::task::Task;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::agent::RestartStrategy;
 use crate::agent::{AgentDeriveT, AgentExecutor};
 #[cfg(not(target_arch = "wasm32"))]
+use crate::event_bus::EventBus;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::runtime::Runtime;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::tool::{AuditSink, CredentialProvider, ToolCacheScope};
+use crate::tool::{ToolMiddleware, ToolSelector};
+use autoagents_llm::moderation::ModerationProvider;
 use autoagents_llm::LLMProvider;
 use std::marker::PhantomData;
 use std::sync::Arc;
@@ -17,6 +29,23 @@ pub struct AgentBuilder<T: AgentDeriveT + AgentExecutor + AgentHooks, A: AgentTy
     pub(crate) stream: bool,
     pub(crate) llm: Option<Arc<dyn LLMProvider>>,
     pub(crate) memory: Option<Box<dyn MemoryProvider>>,
+    pub(crate) tool_selector: Option<Arc<dyn ToolSelector>>,
+    pub(crate) guardrails: GuardrailChain,
+    pub(crate) memory_rollback: MemoryRollback,
+    pub(crate) tool_middleware: Vec<Arc<dyn ToolMiddleware>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) tool_cache_scope: Option<ToolCacheScope>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) credentials: Option<Arc<dyn CredentialProvider>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) audit_sink: Option<Arc<dyn AuditSink>>,
+    pub(crate) sub_agent_limits: SubAgentLimits,
+    pub(crate) prompt_variables: PromptVariables,
+    pub(crate) few_shot: FewShotExamples,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) event_bus: Option<EventBus>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) supervision: Option<RestartStrategy>,
     #[cfg(not(target_arch = "wasm32"))]
     pub(crate) runtime: Option<Arc<dyn Runtime>>,
     #[cfg(not(target_arch = "wasm32"))]
@@ -31,6 +60,23 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks, A: AgentType> AgentBuilder<T,
             inner,
             llm: None,
             memory: None,
+            tool_selector: None,
+            guardrails: GuardrailChain::new(),
+            memory_rollback: MemoryRollback::Never,
+            tool_middleware: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            tool_cache_scope: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            credentials: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            audit_sink: None,
+            sub_agent_limits: SubAgentLimits::default(),
+            prompt_variables: PromptVariables::new(),
+            few_shot: FewShotExamples::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            event_bus: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            supervision: None,
             #[cfg(not(target_arch = "wasm32"))]
             runtime: None,
             stream: false,
@@ -57,6 +103,92 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks, A: AgentType> AgentBuilder<T,
         self
     }
 
+    /// Undo the memory writes of runs that end as `policy` describes.
+    ///
+    /// Applies to non-streaming runs; memory is checkpointed once input
+    /// guardrails have passed.
+    pub fn memory_rollback(mut self, policy: MemoryRollback) -> Self {
+        self.memory_rollback = policy;
+        self
+    }
+
+    /// Set a selector that narrows the tools sent to the LLM on each turn
+    pub fn tool_selector(mut self, selector: Arc<dyn ToolSelector>) -> Self {
+        self.tool_selector = Some(selector);
+        self
+    }
+
+    /// Append a guardrail; guardrails run in the order they are added
+    pub fn guardrail(mut self, guardrail: impl Guardrail + 'static) -> Self {
+        self.guardrails.push(Arc::new(guardrail));
+        self
+    }
+
+    /// Moderate both the task input and the final output with `provider`.
+    ///
+    /// Shorthand for adding a default [`ModerationGuardrail`]; add one through
+    /// [`Self::guardrail`] to pick categories, thresholds or stages.
+    pub fn moderation(self, provider: Arc<dyn ModerationProvider + Send + Sync>) -> Self {
+        self.guardrail(ModerationGuardrail::new(provider))
+    }
+
+    /// Append middleware run around every tool call; the first one added
+    /// sees each call first and its result last
+    pub fn tool_middleware(mut self, middleware: impl ToolMiddleware + 'static) -> Self {
+        self.tool_middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Reuse results of cacheable tools for identical arguments within the given scope
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tool_cache(mut self, scope: ToolCacheScope) -> Self {
+        self.tool_cache_scope = Some(scope);
+        self
+    }
+
+    /// Secrets this agent's tools can look up with [`crate::tool::credential`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn credentials(mut self, provider: Arc<dyn CredentialProvider>) -> Self {
+        self.credentials = Some(provider);
+        self
+    }
+
+    /// Append a record of every tool call this agent makes to `sink`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn audit_log(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit_sink = Some(sink);
+        self
+    }
+
+    /// Bound how deep and how wide executors may spawn sub-agents
+    pub fn sub_agent_limits(mut self, limits: SubAgentLimits) -> Self {
+        self.sub_agent_limits = limits;
+        self
+    }
+
+    /// Set a variable for the agent's templated description
+    pub fn prompt_variable(
+        self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.prompt_variables.set(key, value);
+        self
+    }
+
+    /// Show the LLM worked examples in the system prompt of every run
+    pub fn few_shot(mut self, examples: FewShotExamples) -> Self {
+        self.few_shot = examples;
+        self
+    }
+
+    /// Give executors and hooks access to a bus shared with other agents
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
         self.runtime = Some(runtime);
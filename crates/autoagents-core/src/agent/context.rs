@@ -1,16 +1,27 @@
 #[cfg(not(target_arch = "wasm32"))]
 use crate::actor::{ActorMessage, Topic};
+use crate::agent::few_shot::Example;
+use crate::agent::guardrail::GuardrailViolation;
 use crate::agent::memory::MemoryProvider;
+use crate::agent::prompt::{self, PromptTemplate, SystemPromptBuilder};
 use crate::agent::state::AgentState;
-use crate::agent::AgentConfig;
-use crate::protocol::Event;
-use crate::tool::ToolT;
+use crate::agent::subagent::{SubAgentLimits, SubAgentRun};
+use crate::agent::task::{Attachment, RunMetadata};
+use crate::agent::{validation, AgentConfig};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::event_bus::EventBus;
+use crate::protocol::{Event, RunId};
+use crate::tool::{ToolSelector, ToolT};
 use autoagents_llm::chat::ChatMessage;
 use autoagents_llm::LLMProvider;
 use std::any::Any;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
 
 #[cfg(target_arch = "wasm32")]
 use futures::channel::mpsc;
@@ -22,10 +33,24 @@ pub struct Context {
     messages: Vec<ChatMessage>,
     memory: Option<Arc<Mutex<Box<dyn MemoryProvider>>>>,
     tools: Vec<Box<dyn ToolT>>,
+    tool_selector: Option<Arc<dyn ToolSelector>>,
     config: AgentConfig,
     state: Arc<Mutex<AgentState>>,
     tx: Option<mpsc::Sender<Event>>,
     stream: bool,
+    run_id: RunId,
+    metadata: RunMetadata,
+    payload: Option<serde_json::Value>,
+    prompt_variables: serde_json::Map<String, serde_json::Value>,
+    examples: Vec<Example>,
+    attachments: Vec<Attachment>,
+    guardrail_violations: std::sync::Mutex<Vec<GuardrailViolation>>,
+    depth: usize,
+    sub_agent_limits: SubAgentLimits,
+    sub_agents_spawned: AtomicUsize,
+    sub_agent_runs: std::sync::Mutex<Vec<SubAgentRun>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    event_bus: Option<EventBus>,
 }
 
 #[derive(Clone, Debug, thiserror::Error)]
@@ -44,10 +69,24 @@ impl Context {
             messages: vec![],
             memory: None,
             tools: vec![],
+            tool_selector: None,
             config: AgentConfig::default(),
             state: Arc::new(Mutex::new(AgentState::new())),
             stream: false,
             tx,
+            run_id: Uuid::new_v4(),
+            metadata: RunMetadata::default(),
+            payload: None,
+            prompt_variables: serde_json::Map::new(),
+            examples: vec![],
+            attachments: vec![],
+            guardrail_violations: std::sync::Mutex::new(vec![]),
+            depth: 0,
+            sub_agent_limits: SubAgentLimits::default(),
+            sub_agents_spawned: AtomicUsize::new(0),
+            sub_agent_runs: std::sync::Mutex::new(vec![]),
+            #[cfg(not(target_arch = "wasm32"))]
+            event_bus: None,
         }
     }
 
@@ -79,6 +118,11 @@ impl Context {
         self
     }
 
+    pub fn with_tool_selector(mut self, tool_selector: Option<Arc<dyn ToolSelector>>) -> Self {
+        self.tool_selector = tool_selector;
+        self
+    }
+
     pub fn with_config(mut self, config: AgentConfig) -> Self {
         self.config = config;
         self
@@ -94,6 +138,58 @@ impl Context {
         self
     }
 
+    pub fn with_run_id(mut self, run_id: RunId) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: RunMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_payload(mut self, payload: Option<serde_json::Value>) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Session variables for the agent's prompt templates
+    pub fn with_prompt_variables(
+        mut self,
+        variables: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.prompt_variables = variables;
+        self
+    }
+
+    /// Few-shot examples shown to the LLM in this run
+    pub fn with_examples(mut self, examples: Vec<Example>) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    pub fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    /// Nesting level of this run, `0` unless it belongs to a sub-agent
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_sub_agent_limits(mut self, limits: SubAgentLimits) -> Self {
+        self.sub_agent_limits = limits;
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_event_bus(mut self, event_bus: Option<EventBus>) -> Self {
+        self.event_bus = event_bus;
+        self
+    }
+
     // Getters
     pub fn llm(&self) -> &Arc<dyn LLMProvider> {
         &self.llm
@@ -111,10 +207,47 @@ impl Context {
         &self.tools
     }
 
+    pub fn tool_selector(&self) -> Option<&Arc<dyn ToolSelector>> {
+        self.tool_selector.as_ref()
+    }
+
     pub fn config(&self) -> &AgentConfig {
         &self.config
     }
 
+    /// The sections of the system prompt known from the run: the agent
+    /// description as persona, rendered with the run's
+    /// [template variables](Self::template_variables), and an output schema
+    /// the LLM cannot enforce itself, spelled out, followed by the run's
+    /// few-shot examples.
+    pub fn system_prompt_builder(&self) -> SystemPromptBuilder {
+        let template = PromptTemplate::new(self.config.description.as_str());
+        let persona = template
+            .render(&self.template_variables())
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    "Using the description of {} as written: {e}",
+                    self.config.name
+                );
+                self.config.description.clone()
+            });
+        let mut prompt = SystemPromptBuilder::new().persona(persona);
+        let schema = self
+            .config
+            .output_schema
+            .as_ref()
+            .and_then(|format| format.schema.as_ref());
+        if let Some(schema) = schema.filter(|_| !self.llm.supports_structured_output()) {
+            prompt = prompt.output_schema(validation::schema_instructions(schema));
+        }
+        prompt.examples(&self.examples)
+    }
+
+    /// The system prompt from [`system_prompt_builder`](Self::system_prompt_builder)
+    pub fn system_prompt(&self) -> String {
+        self.system_prompt_builder().build()
+    }
+
     pub fn state(&self) -> Arc<Mutex<AgentState>> {
         self.state.clone()
     }
@@ -126,6 +259,115 @@ impl Context {
     pub fn stream(&self) -> bool {
         self.stream
     }
+
+    /// Unique ID of the run this context was created for
+    pub fn run_id(&self) -> RunId {
+        self.run_id
+    }
+
+    /// Metadata supplied with the task that started this run
+    pub fn metadata(&self) -> &RunMetadata {
+        &self.metadata
+    }
+
+    /// Structured payload of the task being run
+    pub fn payload(&self) -> Option<&serde_json::Value> {
+        self.payload.as_ref()
+    }
+
+    /// Session variables the agent's prompt templates can use
+    pub fn prompt_variables(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.prompt_variables
+    }
+
+    /// Few-shot examples selected for this run
+    pub fn examples(&self) -> &[Example] {
+        &self.examples
+    }
+
+    /// Variables the agent's prompt templates are rendered with, see
+    /// [`prompt`](crate::agent::prompt)
+    pub fn template_variables(&self) -> serde_json::Value {
+        prompt::template_variables(
+            &self.prompt_variables,
+            &self.metadata,
+            self.payload.as_ref(),
+        )
+    }
+
+    /// Binary inputs of the task being run
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /// Guardrail rewrites and annotations recorded so far in this run
+    pub fn guardrail_violations(&self) -> Vec<GuardrailViolation> {
+        self.guardrail_violations
+            .lock()
+            .map(|v| v.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn record_guardrail_violation(&self, violation: GuardrailViolation) {
+        if let Ok(mut violations) = self.guardrail_violations.lock() {
+            violations.push(violation);
+        }
+    }
+
+    /// Bus shared with other agents, if the agent was built with one
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn event_bus(&self) -> Option<&EventBus> {
+        self.event_bus.as_ref()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn sub_agent_limits(&self) -> SubAgentLimits {
+        self.sub_agent_limits
+    }
+
+    /// Sub-agents that completed during this run, in completion order
+    pub fn sub_agent_runs(&self) -> Vec<SubAgentRun> {
+        self.sub_agent_runs
+            .lock()
+            .map(|runs| runs.clone())
+            .unwrap_or_default()
+    }
+
+    /// Claim one of this run's sub-agent slots, `false` once they are used up.
+    pub(crate) fn reserve_sub_agent_slot(&self) -> bool {
+        let max = self.sub_agent_limits.max_children;
+        self.sub_agents_spawned
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    pub(crate) fn record_sub_agent_run(&self, run: SubAgentRun) {
+        if let Ok(mut runs) = self.sub_agent_runs.lock() {
+            runs.push(run);
+        }
+    }
+
+    /// Headers forwarded to the LLM provider for requests made during this run
+    pub fn request_headers(&self) -> BTreeMap<String, String> {
+        self.metadata.to_headers(self.run_id)
+    }
+
+    /// Drive `fut` with this run's request headers attached to every LLM call it makes.
+    pub async fn scoped<F: Future>(&self, fut: F) -> F::Output {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            autoagents_llm::request_context::scope(self.request_headers(), fut).await
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            fut.await
+        }
+    }
 }
 
 #[cfg(test)]
@@ -201,4 +443,78 @@ mod tests {
         assert_eq!(context.messages().len(), 1);
         assert!(context.stream());
     }
+
+    #[test]
+    fn test_system_prompt_spells_out_schema_the_llm_cannot_enforce() {
+        let llm = Arc::new(MockLLMProvider);
+        let mut config = AgentConfig::new("agent".into(), "Answer questions.".into());
+        let context = Context::new(llm.clone(), None).with_config(config.clone());
+        assert_eq!(context.system_prompt(), "Answer questions.");
+
+        config.output_schema = Some(autoagents_llm::chat::StructuredOutputFormat {
+            name: "answer".into(),
+            description: None,
+            schema: Some(serde_json::json!({"type": "object", "required": ["city"]})),
+            strict: None,
+        });
+        let prompt = Context::new(llm, None).with_config(config).system_prompt();
+        assert!(prompt.starts_with("Answer questions.\n\n"), "{prompt}");
+        assert!(prompt.contains("JSON schema"), "{prompt}");
+        assert!(prompt.contains("\"required\""), "{prompt}");
+    }
+
+    #[test]
+    fn test_system_prompt_renders_templated_description() {
+        let config = AgentConfig::new(
+            "agent".into(),
+            "Help {{ user_id }} in {{ language }} about {{ payload.topic }}.".into(),
+        );
+        let metadata = RunMetadata {
+            user_id: Some("ada".into()),
+            ..Default::default()
+        };
+        let mut variables = serde_json::Map::new();
+        variables.insert("language".into(), "French".into());
+        let context = Context::new(Arc::new(MockLLMProvider), None)
+            .with_config(config)
+            .with_metadata(metadata)
+            .with_payload(Some(serde_json::json!({"topic": "billing"})))
+            .with_prompt_variables(variables);
+
+        assert_eq!(context.system_prompt(), "Help ada in French about billing.");
+    }
+
+    #[test]
+    fn test_context_run_id_and_metadata() {
+        let llm = Arc::new(MockLLMProvider);
+        let metadata = RunMetadata {
+            tenant_id: Some("acme".into()),
+            forward_ids: true,
+            ..Default::default()
+        };
+        let run_id = Uuid::new_v4();
+        let context = Context::new(llm, None)
+            .with_run_id(run_id)
+            .with_metadata(metadata.clone());
+
+        assert_eq!(context.run_id(), run_id);
+        assert_eq!(context.metadata(), &metadata);
+        assert_eq!(
+            context.request_headers().get("x-autoagents-tenant-id"),
+            Some(&"acme".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_scoped_exposes_headers() {
+        let llm = Arc::new(MockLLMProvider);
+        let context = Context::new(llm, None);
+        let headers = context
+            .scoped(async { autoagents_llm::request_context::current_headers() })
+            .await;
+        assert_eq!(
+            headers.get("x-autoagents-run-id"),
+            Some(&context.run_id().to_string())
+        );
+    }
 }
@@ -0,0 +1,633 @@
+//! Agents described in YAML or JSON instead of Rust.
+//!
+//! An [`AgentDefinition`] names the agent, its system prompt, model, tools and
+//! memory. [`AgentLoader`] turns it into a [`DeclarativeAgent`], resolving
+//! tools through a [`ToolRegistry`] and the model through a caller supplied
+//! factory, so definitions can change without recompiling.
+//!
+//! ```yaml
+//! name: support
+//! description: Answers product questions
+//! system_prompt: You are a friendly support agent.
+//! executor: react
+//! model:
+//!   provider: openai
+//!   name: gpt-4o-mini
+//!   temperature: 0.2
+//! tools:
+//!   - search_docs
+//!   - name: http_get
+//!     options: { timeout_secs: 5 }
+//! memory:
+//!   kind: sliding_window
+//!   window_size: 20
+//! examples:
+//!   - input: How do I reset my password?
+//!     output: Open Settings, then Security, and choose "Reset password".
+//! ```
+
+use crate::agent::base::AgentType;
+use crate::agent::few_shot::{Example, FewShotExamples};
+use crate::agent::memory::{MemoryProvider, SlidingWindowMemory};
+use crate::agent::prebuilt::executor::{
+    BasicAgent, BasicExecutorError, ReActAgent, ReActAgentOutput, ReActExecutorError,
+};
+use crate::agent::task::Task;
+use crate::agent::{
+    AgentBuilder, AgentDeriveT, AgentExecutor, AgentHooks, Context, ExecutorConfig,
+};
+use crate::tool::{shared_tools_to_boxes, ToolRegistry, ToolRegistryError, ToolT};
+use crate::utils::intern;
+use async_trait::async_trait;
+use autoagents_llm::LLMProvider;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DefinitionError {
+    #[error("Failed to read agent definition: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid YAML agent definition: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("Invalid JSON agent definition: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Tool(#[from] ToolRegistryError),
+
+    #[error("No LLM available for provider '{provider}': {reason}")]
+    Model { provider: String, reason: String },
+}
+
+/// Executor strategy of a declarative agent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutorKind {
+    /// Single LLM call without tools
+    Basic,
+    /// Reason/act loop with tool calls
+    #[default]
+    #[serde(alias = "re_act")]
+    ReAct,
+}
+
+/// Model settings passed to the loader's LLM factory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelDefinition {
+    /// Backend name, e.g. `openai`, `anthropic`, `ollama`
+    pub provider: String,
+    /// Model identifier understood by the provider
+    #[serde(default, alias = "model")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Provider specific settings
+    #[serde(default)]
+    pub options: Value,
+}
+
+/// A tool by registered name, optionally with options for its factory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolReference {
+    Name(String),
+    Configured {
+        name: String,
+        #[serde(default)]
+        options: Value,
+    },
+}
+
+impl ToolReference {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Configured { name, .. } => name,
+        }
+    }
+
+    pub fn options(&self) -> &Value {
+        match self {
+            Self::Name(_) => &Value::Null,
+            Self::Configured { options, .. } => options,
+        }
+    }
+}
+
+fn default_window_size() -> usize {
+    10
+}
+
+/// Memory attached to a declarative agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MemoryDefinition {
+    SlidingWindow {
+        #[serde(default = "default_window_size")]
+        window_size: usize,
+    },
+    None,
+}
+
+impl MemoryDefinition {
+    pub fn build(&self) -> Option<Box<dyn MemoryProvider>> {
+        match self {
+            Self::SlidingWindow { window_size } => {
+                Some(Box::new(SlidingWindowMemory::new(*window_size)))
+            }
+            Self::None => None,
+        }
+    }
+}
+
+/// Serializable description of an agent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Instructions sent as the system message, `description` if unset
+    #[serde(default, alias = "instructions")]
+    pub system_prompt: Option<String>,
+    #[serde(default)]
+    pub executor: ExecutorKind,
+    pub model: ModelDefinition,
+    #[serde(default)]
+    pub tools: Vec<ToolReference>,
+    #[serde(default)]
+    pub memory: Option<MemoryDefinition>,
+    /// JSON schema the final answer must follow
+    #[serde(default)]
+    pub output_schema: Option<Value>,
+    #[serde(default)]
+    pub stream: bool,
+    /// Few-shot examples shown in the system prompt
+    #[serde(default)]
+    pub examples: Vec<Example>,
+}
+
+impl AgentDefinition {
+    pub fn from_yaml_str(yaml: &str) -> Result<Self, DefinitionError> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self, DefinitionError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Read a definition from disk; `.json` files are parsed as JSON and
+    /// everything else as YAML.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DefinitionError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::from_json_str(&content),
+            _ => Self::from_yaml_str(&content),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeclarativeAgentError {
+    #[error(transparent)]
+    ReAct(#[from] ReActExecutorError),
+
+    #[error(transparent)]
+    Basic(#[from] BasicExecutorError),
+}
+
+/// Agent built from an [`AgentDefinition`].
+///
+/// Dispatches to the [`ReActAgent`] or [`BasicAgent`] executor at runtime and
+/// reports both through [`ReActAgentOutput`].
+#[derive(Debug, Clone)]
+pub struct DeclarativeAgent {
+    name: &'static str,
+    instructions: &'static str,
+    tools: Vec<Arc<dyn ToolT>>,
+    output_schema: Option<Value>,
+    executor: ExecutorKind,
+}
+
+impl DeclarativeAgent {
+    pub fn new(
+        name: impl Into<String>,
+        instructions: impl Into<String>,
+        tools: Vec<Arc<dyn ToolT>>,
+        output_schema: Option<Value>,
+        executor: ExecutorKind,
+    ) -> Self {
+        let name: String = name.into();
+        let instructions: String = instructions.into();
+        // Agents expose `&'static str` metadata, so the strings are interned
+        let output_schema = output_schema.map(|schema| {
+            json!({
+                "name": name,
+                "description": null,
+                "schema": schema,
+                "strict": true,
+            })
+        });
+        Self {
+            name: intern(&name),
+            instructions: intern(&instructions),
+            tools,
+            output_schema,
+            executor,
+        }
+    }
+
+    pub fn executor(&self) -> ExecutorKind {
+        self.executor
+    }
+}
+
+impl AgentDeriveT for DeclarativeAgent {
+    type Output = String;
+
+    fn description(&self) -> &'static str {
+        self.instructions
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        self.output_schema.clone()
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn tools(&self) -> Vec<Box<dyn ToolT>> {
+        shared_tools_to_boxes(&self.tools)
+    }
+}
+
+impl AgentHooks for DeclarativeAgent {}
+
+fn basic_to_react(
+    result: Result<crate::agent::prebuilt::executor::BasicAgentOutput, BasicExecutorError>,
+) -> Result<ReActAgentOutput, DeclarativeAgentError> {
+    result
+        .map(|output| ReActAgentOutput {
+            response: output.response,
+            tool_calls: vec![],
+            done: output.done,
+            citations: output.citations,
+            usage: output.usage,
+        })
+        .map_err(Into::into)
+}
+
+#[async_trait]
+impl AgentExecutor for DeclarativeAgent {
+    type Output = ReActAgentOutput;
+    type Error = DeclarativeAgentError;
+
+    fn config(&self) -> ExecutorConfig {
+        match self.executor {
+            ExecutorKind::Basic => BasicAgent::new(self.clone()).config(),
+            ExecutorKind::ReAct => ReActAgent::new(self.clone()).config(),
+        }
+    }
+
+    async fn execute(
+        &self,
+        task: &Task,
+        context: Arc<Context>,
+    ) -> Result<Self::Output, Self::Error> {
+        match self.executor {
+            ExecutorKind::Basic => {
+                basic_to_react(BasicAgent::new(self.clone()).execute(task, context).await)
+            }
+            ExecutorKind::ReAct => Ok(ReActAgent::new(self.clone()).execute(task, context).await?),
+        }
+    }
+
+    async fn execute_stream(
+        &self,
+        task: &Task,
+        context: Arc<Context>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Self::Output, Self::Error>> + Send>>, Self::Error>
+    {
+        match self.executor {
+            ExecutorKind::Basic => {
+                let stream = BasicAgent::new(self.clone())
+                    .execute_stream(task, context)
+                    .await?;
+                Ok(Box::pin(stream.map(basic_to_react)))
+            }
+            ExecutorKind::ReAct => {
+                let stream = ReActAgent::new(self.clone())
+                    .execute_stream(task, context)
+                    .await?;
+                Ok(Box::pin(stream.map(|result| result.map_err(Into::into))))
+            }
+        }
+    }
+}
+
+type LLMFactory = dyn Fn(&ModelDefinition) -> Result<Arc<dyn LLMProvider>, String> + Send + Sync;
+
+/// Builds agents from definitions.
+///
+/// LLM backends live behind feature flags in `autoagents-llm`, so the loader
+/// does not construct them itself: supply a factory for the `model` section,
+/// a fallback provider, or both.
+#[derive(Clone, Default)]
+pub struct AgentLoader {
+    tools: ToolRegistry,
+    llm_factory: Option<Arc<LLMFactory>>,
+    default_llm: Option<Arc<dyn LLMProvider>>,
+}
+
+impl AgentLoader {
+    pub fn new(tools: ToolRegistry) -> Self {
+        Self {
+            tools,
+            ..Self::default()
+        }
+    }
+
+    /// Create the LLM for each definition from its `model` section
+    pub fn llm_factory<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&ModelDefinition) -> Result<Arc<dyn LLMProvider>, String> + Send + Sync + 'static,
+    {
+        self.llm_factory = Some(Arc::new(factory));
+        self
+    }
+
+    /// Provider used when there is no factory
+    pub fn llm(mut self, llm: Arc<dyn LLMProvider>) -> Self {
+        self.default_llm = Some(llm);
+        self
+    }
+
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+
+    pub fn load(&self, definition: AgentDefinition) -> Result<LoadedAgent, DefinitionError> {
+        let llm = self.resolve_llm(&definition.model)?;
+        let tools = definition
+            .tools
+            .iter()
+            .map(|tool| self.tools.resolve(tool.name(), tool.options()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let agent = DeclarativeAgent::new(
+            definition.name.clone(),
+            definition
+                .system_prompt
+                .clone()
+                .unwrap_or_else(|| definition.description.clone()),
+            tools,
+            definition.output_schema.clone(),
+            definition.executor,
+        );
+        Ok(LoadedAgent {
+            agent,
+            llm,
+            definition,
+        })
+    }
+
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<LoadedAgent, DefinitionError> {
+        self.load(AgentDefinition::from_file(path)?)
+    }
+
+    fn resolve_llm(
+        &self,
+        model: &ModelDefinition,
+    ) -> Result<Arc<dyn LLMProvider>, DefinitionError> {
+        let model_error = |reason: String| DefinitionError::Model {
+            provider: model.provider.clone(),
+            reason,
+        };
+        match (&self.llm_factory, &self.default_llm) {
+            (Some(factory), _) => factory(model).map_err(model_error),
+            (None, Some(llm)) => Ok(llm.clone()),
+            (None, None) => Err(model_error("no LLM factory configured".to_string())),
+        }
+    }
+}
+
+impl std::fmt::Debug for AgentLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentLoader")
+            .field("tools", &self.tools)
+            .field("llm_factory", &self.llm_factory.is_some())
+            .field("default_llm", &self.default_llm.is_some())
+            .finish()
+    }
+}
+
+/// Result of [`AgentLoader::load`], ready to be built into a running agent.
+pub struct LoadedAgent {
+    pub agent: DeclarativeAgent,
+    pub llm: Arc<dyn LLMProvider>,
+    pub definition: AgentDefinition,
+}
+
+impl LoadedAgent {
+    /// Builder preconfigured with the definition's LLM, memory, streaming mode
+    /// and few-shot examples.
+    pub fn into_builder<A: AgentType>(self) -> AgentBuilder<DeclarativeAgent, A> {
+        let mut builder = AgentBuilder::new(self.agent)
+            .llm(self.llm)
+            .stream(self.definition.stream);
+        if let Some(memory) = self.definition.memory.as_ref().and_then(|m| m.build()) {
+            builder = builder.memory(memory);
+        }
+        if !self.definition.examples.is_empty() {
+            builder = builder.few_shot(FewShotExamples::new(self.definition.examples));
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::DirectAgent;
+    use crate::tool::{ToolCallError, ToolRuntime};
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+    #[derive(Debug)]
+    struct Clock;
+
+    #[async_trait]
+    impl ToolRuntime for Clock {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!("12:00"))
+        }
+    }
+
+    impl ToolT for Clock {
+        fn name(&self) -> &'static str {
+            "clock"
+        }
+
+        fn description(&self) -> &'static str {
+            "current time"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    const YAML: &str = r#"
+name: timekeeper
+description: Tells the time
+system_prompt: Always answer politely.
+executor: basic
+model:
+  provider: openai
+  name: gpt-4o-mini
+  temperature: 0.1
+tools:
+  - clock
+memory:
+  kind: sliding_window
+  window_size: 4
+"#;
+
+    fn registry() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.register(Clock);
+        registry
+    }
+
+    #[test]
+    fn test_parse_yaml_definition() {
+        let def = AgentDefinition::from_yaml_str(YAML).unwrap();
+        assert_eq!(def.name, "timekeeper");
+        assert_eq!(def.executor, ExecutorKind::Basic);
+        assert_eq!(def.model.name.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(def.tools, vec![ToolReference::Name("clock".into())]);
+        assert_eq!(
+            def.memory,
+            Some(MemoryDefinition::SlidingWindow { window_size: 4 })
+        );
+        assert!(!def.stream);
+    }
+
+    #[test]
+    fn test_parse_json_definition_with_tool_options() {
+        let def = AgentDefinition::from_json_str(
+            r#"{
+                "name": "a",
+                "instructions": "be brief",
+                "model": {"provider": "ollama", "model": "llama3"},
+                "tools": [{"name": "clock", "options": {"tz": "UTC"}}],
+                "memory": {"kind": "none"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(def.executor, ExecutorKind::ReAct);
+        assert_eq!(def.system_prompt.as_deref(), Some("be brief"));
+        assert_eq!(def.model.name.as_deref(), Some("llama3"));
+        assert_eq!(def.tools[0].options()["tz"], "UTC");
+        assert!(def.memory.unwrap().build().is_none());
+    }
+
+    #[test]
+    fn test_from_file_picks_format_by_extension() {
+        let dir = std::env::temp_dir().join(format!("agent-def-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let yaml = dir.join("agent.yaml");
+        std::fs::write(&yaml, YAML).unwrap();
+        let json = dir.join("agent.json");
+        std::fs::write(
+            &json,
+            serde_json::to_string(&AgentDefinition::from_yaml_str(YAML).unwrap()).unwrap(),
+        )
+        .unwrap();
+
+        let from_yaml = AgentDefinition::from_file(&yaml).unwrap();
+        let from_json = AgentDefinition::from_file(&json).unwrap();
+        assert_eq!(from_yaml, from_json);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_loader_reports_unknown_tools_and_missing_llm() {
+        let def = AgentDefinition::from_yaml_str(YAML).unwrap();
+
+        let err = AgentLoader::new(registry())
+            .load(def.clone())
+            .err()
+            .unwrap();
+        assert!(matches!(err, DefinitionError::Model { provider, .. } if provider == "openai"));
+
+        let err = AgentLoader::new(ToolRegistry::new())
+            .llm(Arc::new(ScriptedLLMProvider::new(["ok"])))
+            .load(def)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            DefinitionError::Tool(ToolRegistryError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_loaded_agent_runs() {
+        let llm = Arc::new(ScriptedLLMProvider::new(["It is noon."]));
+        let factory_llm = llm.clone();
+        let loaded = AgentLoader::new(registry())
+            .llm_factory(move |model| {
+                assert_eq!(model.provider, "openai");
+                Ok(factory_llm.clone() as Arc<dyn LLMProvider>)
+            })
+            .load(AgentDefinition::from_yaml_str(YAML).unwrap())
+            .unwrap();
+
+        assert_eq!(loaded.agent.name(), "timekeeper");
+        assert_eq!(loaded.agent.description(), "Always answer politely.");
+        assert_eq!(loaded.agent.tools().len(), 1);
+
+        let handle = loaded.into_builder::<DirectAgent>().build().await.unwrap();
+        let output = handle
+            .agent
+            .run(Task::new("What time is it?"))
+            .await
+            .unwrap();
+        assert_eq!(output, "It is noon.");
+        assert_eq!(llm.last_messages()[0].content, "Always answer politely.");
+    }
+
+    #[tokio::test]
+    async fn test_definition_examples_reach_the_system_prompt() {
+        let yaml = format!("{YAML}examples:\n  - input: Time?\n    output: It is 9:00.\n");
+        let def = AgentDefinition::from_yaml_str(&yaml).unwrap();
+        assert_eq!(def.examples, vec![Example::new("Time?", "It is 9:00.")]);
+
+        let llm = Arc::new(ScriptedLLMProvider::new(["It is noon."]));
+        let loaded = AgentLoader::new(registry())
+            .llm(llm.clone())
+            .load(def)
+            .unwrap();
+        let handle = loaded.into_builder::<DirectAgent>().build().await.unwrap();
+        handle
+            .agent
+            .run(Task::new("What time is it?"))
+            .await
+            .unwrap();
+        assert_eq!(
+            llm.last_messages()[0].content,
+            "Always answer politely.\n\nExamples of inputs and the expected outputs:\n\nInput: Time?\nOutput: It is 9:00."
+        );
+    }
+}
@@ -40,8 +40,22 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> AgentBuilder<T, DirectAgent>
             "LLM provider is required".to_string(),
         ))?;
         let (tx, rx): (Sender<Event>, Receiver<Event>) = channel(DEFAULT_CHANNEL_BUFFER);
-        let agent: BaseAgent<T, DirectAgent> =
+        let mut agent: BaseAgent<T, DirectAgent> =
             BaseAgent::<T, DirectAgent>::new(self.inner, llm, self.memory, tx, self.stream).await?;
+        agent.tool_selector = self.tool_selector;
+        agent.guardrails = self.guardrails;
+        agent.memory_rollback = self.memory_rollback;
+        agent.tool_middleware = self.tool_middleware.into();
+        agent.sub_agent_limits = self.sub_agent_limits;
+        agent.prompt_variables = self.prompt_variables;
+        agent.few_shot = self.few_shot;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            agent.tool_cache_scope = self.tool_cache_scope;
+            agent.credentials = self.credentials;
+            agent.audit_sink = self.audit_sink;
+            agent.event_bus = self.event_bus;
+        }
         let stream = receiver_into_stream(rx);
         Ok(DirectAgentHandle::new(agent, stream))
     }
@@ -52,7 +66,8 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, DirectAgent> {
     where
         <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
     {
-        let context = self.create_context();
+        let context = self.create_context(&task).await;
+        let task = self.guard_input(task, &context).await?;
 
         //Run Hook
         let hook_outcome = self.inner.on_run_start(&task, &context).await;
@@ -61,25 +76,27 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, DirectAgent> {
             HookOutcome::Continue => {}
         }
 
+        let checkpoint = self.checkpoint_memory(&context).await;
+
         // Execute the agent's logic using the executor
-        match self.inner().execute(&task, context.clone()).await {
-            Ok(output) => {
-                let output: <T as AgentExecutor>::Output = output;
-
-                //Extract Agent output into the desired type
-                let agent_out: <T as AgentDeriveT>::Output = output.into();
-
-                //Run On complete Hook
-                self.inner
-                    .on_run_complete(&task, &agent_out, &context)
-                    .await;
-                Ok(agent_out)
-            }
-            Err(e) => {
-                // Send error event
-                Err(RunnableAgentError::ExecutorError(e.to_string()))
-            }
-        }
+        let result = match context
+            .scoped(self.inner().execute(&task, context.clone()))
+            .await
+        {
+            Ok(output) => self.guard_output(output, &task, &context).await,
+            Err(e) => Err(RunnableAgentError::ExecutorError(e.to_string())),
+        };
+        self.settle_memory(checkpoint, &result).await;
+        let output: <T as AgentExecutor>::Output = result?;
+
+        //Extract Agent output into the desired type
+        let agent_out: <T as AgentDeriveT>::Output = output.into();
+
+        //Run On complete Hook
+        self.inner
+            .on_run_complete(&task, &agent_out, &context)
+            .await;
+        Ok(agent_out)
     }
 
     pub async fn run_stream(
@@ -92,7 +109,8 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, DirectAgent> {
     where
         <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
     {
-        let context = self.create_context();
+        let context = self.create_context(&task).await;
+        let task = self.guard_input(task, &context).await?;
 
         //Run Hook
         let hook_outcome = self.inner.on_run_start(&task, &context).await;
@@ -101,24 +119,28 @@ impl<T: AgentDeriveT + AgentExecutor + AgentHooks> BaseAgent<T, DirectAgent> {
             HookOutcome::Continue => {}
         }
 
+        let checkpoint = self.checkpoint_memory(&context).await;
+
         // Execute the agent's streaming logic using the executor
-        match self.inner().execute_stream(&task, context.clone()).await {
+        match context
+            .scoped(self.inner().execute_stream(&task, context.clone()))
+            .await
+        {
             Ok(stream) => {
                 use futures::StreamExt;
                 // Convert the stream output
-                let transformed_stream = stream.map(move |result| match result {
-                    Ok(output) => Ok(output.into()),
-                    Err(e) => {
-                        let error_msg = e.to_string();
-                        Err(RunnableAgentError::ExecutorError(error_msg).into())
-                    }
-                });
+                let guarded = self.guard_output_stream(stream, task, context);
+                let transformed_stream = self
+                    .settle_memory_stream(checkpoint, guarded)
+                    .map(|result| result.map(Into::into).map_err(Into::into));
 
                 Ok(Box::pin(transformed_stream))
             }
             Err(e) => {
                 // Send error event for stream creation failure
-                Err(RunnableAgentError::ExecutorError(e.to_string()))
+                let result = Err(RunnableAgentError::ExecutorError(e.to_string()));
+                self.settle_memory(checkpoint, &result).await;
+                result
             }
         }
     }
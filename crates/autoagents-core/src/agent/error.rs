@@ -1,3 +1,4 @@
+use crate::agent::guardrail::GuardrailViolation;
 #[cfg(not(target_arch = "wasm32"))]
 use ractor::SpawnErr;
 use std::fmt::Debug;
@@ -46,6 +47,10 @@ pub enum RunnableAgentError {
     #[error("Abort the execution")]
     Abort,
 
+    /// A guardrail blocked the task input or the final response
+    #[error("Blocked by guardrail `{}`: {}", .0.guardrail, .0.reason)]
+    GuardrailBlocked(Box<GuardrailViolation>),
+
     /// Generic error wrapper for any std::error::Error
     #[error(transparent)]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
@@ -1,4 +1,6 @@
-use crate::protocol::{ActorID, Event, SubmissionId};
+use crate::agent::guardrail::GuardrailViolation;
+use crate::agent::task::RunMetadata;
+use crate::protocol::{ActorID, Event, RunId, SubmissionId};
 use autoagents_llm::chat::StreamChoice;
 use serde_json::Value;
 
@@ -28,17 +30,21 @@ impl EventHelper {
     pub async fn send_task_started(
         tx: &Option<mpsc::Sender<Event>>,
         sub_id: SubmissionId,
+        run_id: RunId,
         actor_id: ActorID,
         actor_name: String,
         task_description: String,
+        metadata: RunMetadata,
     ) {
         Self::send(
             tx,
             Event::TaskStarted {
                 sub_id,
+                run_id,
                 actor_id,
                 actor_name,
                 task_description,
+                metadata: Box::new(metadata),
             },
         )
         .await;
@@ -48,6 +54,7 @@ impl EventHelper {
     pub async fn send_task_completed(
         tx: &Option<mpsc::Sender<Event>>,
         sub_id: SubmissionId,
+        run_id: RunId,
         actor_id: ActorID,
         actor_name: String,
         result: String,
@@ -56,6 +63,7 @@ impl EventHelper {
             tx,
             Event::TaskComplete {
                 sub_id,
+                run_id,
                 result,
                 actor_id,
                 actor_name,
@@ -118,4 +126,22 @@ impl EventHelper {
     pub async fn send_stream_complete(tx: &Option<mpsc::Sender<Event>>, sub_id: SubmissionId) {
         Self::send(tx, Event::StreamComplete { sub_id }).await;
     }
+
+    /// Send guardrail triggered event
+    pub async fn send_guardrail_triggered(
+        tx: &Option<mpsc::Sender<Event>>,
+        sub_id: SubmissionId,
+        run_id: RunId,
+        violation: GuardrailViolation,
+    ) {
+        Self::send(
+            tx,
+            Event::GuardrailTriggered {
+                sub_id,
+                run_id,
+                violation: Box::new(violation),
+            },
+        )
+        .await;
+    }
 }
@@ -1,6 +1,7 @@
 use crate::agent::memory::MemoryProvider;
+use crate::agent::task::Task;
 use crate::tool::ToolCallResult;
-use autoagents_llm::chat::{ChatMessage, ChatRole, ImageMime, MessageType};
+use autoagents_llm::chat::{ChatMessage, ChatRole, ImageMime, MessageType, ReasoningBlock};
 use autoagents_llm::ToolCall;
 use std::sync::Arc;
 
@@ -27,12 +28,14 @@ impl MemoryHelper {
         }
     }
 
-    /// Store tool calls and results in memory
+    /// Store tool calls and results in memory, with the reasoning that led
+    /// to the calls
     pub async fn store_tool_interaction(
         memory: &Option<Arc<Mutex<Box<dyn MemoryProvider>>>>,
         tool_calls: &[ToolCall],
         tool_results: &[ToolCallResult],
         response_text: &str,
+        reasoning: Vec<ReasoningBlock>,
     ) {
         if let Some(mem) = memory {
             let mut mem = mem.lock().await;
@@ -43,6 +46,7 @@ impl MemoryHelper {
                     role: ChatRole::Assistant,
                     message_type: MessageType::ToolUse(tool_calls.to_vec()),
                     content: response_text.to_string(),
+                    reasoning,
                 })
                 .await;
 
@@ -55,6 +59,7 @@ impl MemoryHelper {
                     role: ChatRole::Tool,
                     message_type: MessageType::ToolResult(result_tool_calls),
                     content: String::new(),
+                    reasoning: Vec::new(),
                 })
                 .await;
         }
@@ -73,18 +78,33 @@ impl MemoryHelper {
                     role: ChatRole::User,
                     message_type: MessageType::Image((mime, data)),
                     content,
+                    reasoning: Vec::new(),
                 }
             } else {
                 ChatMessage {
                     role: ChatRole::User,
                     message_type: MessageType::Text,
                     content,
+                    reasoning: Vec::new(),
                 }
             };
             let _ = mem.remember(&message).await;
         }
     }
 
+    /// Store the user messages that carry a task, including any images
+    pub async fn store_task_input(
+        memory: &Option<Arc<Mutex<Box<dyn MemoryProvider>>>>,
+        task: &Task,
+    ) {
+        if let Some(mem) = memory {
+            let mut mem = mem.lock().await;
+            for message in task.user_messages() {
+                let _ = mem.remember(&message).await;
+            }
+        }
+    }
+
     /// Store assistant response in memory
     pub async fn store_assistant_response(
         memory: &Option<Arc<Mutex<Box<dyn MemoryProvider>>>>,
@@ -96,11 +116,46 @@ impl MemoryHelper {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: response,
+                reasoning: Vec::new(),
             },
         )
         .await;
     }
 
+    /// Extra system prompt context contributed by the memory, if any
+    pub async fn system_context(
+        memory: &Option<Arc<Mutex<Box<dyn MemoryProvider>>>>,
+    ) -> Option<String> {
+        let mem = memory.as_ref()?;
+        match mem.lock().await.system_context("").await {
+            Ok(context) => context.filter(|c| !c.trim().is_empty()),
+            Err(e) => {
+                log::warn!("Failed to build memory context for the system prompt: {e}");
+                None
+            }
+        }
+    }
+
+    /// Search memory for earlier messages matching `query`.
+    ///
+    /// Returns nothing if there is no memory or the search fails.
+    pub async fn search(
+        memory: &Option<Arc<Mutex<Box<dyn MemoryProvider>>>>,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Vec<ChatMessage> {
+        let Some(mem) = memory else {
+            return Vec::new();
+        };
+        match mem.lock().await.search(query, limit).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                log::warn!("Memory search failed: {e}");
+                Vec::new()
+            }
+        }
+    }
+
     /// Recall messages from memory
     pub async fn recall_messages(
         memory: &Option<Arc<Mutex<Box<dyn MemoryProvider>>>>,
@@ -1,16 +1,20 @@
 pub mod event_helper;
 pub mod memory_helper;
 pub mod tool_processor;
+pub mod validation;
 
 use crate::agent::context::Context;
 use crate::agent::task::Task;
 use async_trait::async_trait;
+use autoagents_llm::chat::Usage;
 use futures::Stream;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::error::Error;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Duration;
+pub use validation::ValidationPolicy;
 
 /// Result of processing a single turn in the agent's execution
 #[derive(Debug)]
@@ -21,15 +25,34 @@ pub enum TurnResult<T> {
     Complete(T),
 }
 
+/// Adds the usage of one LLM call to the running total of an agent run
+pub(crate) fn accumulate_usage(total: &mut Option<Usage>, usage: Option<Usage>) {
+    if let Some(usage) = usage {
+        *total.get_or_insert_with(Usage::default) += usage;
+    }
+}
+
 /// Configuration for executors
 #[derive(Debug, Clone)]
 pub struct ExecutorConfig {
     pub max_turns: usize,
+    /// Checks the final response against the agent's output schema
+    pub validation: ValidationPolicy,
+    /// Time limit for tool calls whose tool does not set its own
+    /// [`ToolT::timeout`](crate::tool::ToolT::timeout)
+    pub tool_timeout: Option<Duration>,
+    /// Tool calls from one turn that may run at the same time
+    pub tool_parallelism: usize,
 }
 
 impl Default for ExecutorConfig {
     fn default() -> Self {
-        Self { max_turns: 10 }
+        Self {
+            max_turns: 10,
+            validation: ValidationPolicy::default(),
+            tool_timeout: None,
+            tool_parallelism: 1,
+        }
     }
 }
 
@@ -132,6 +155,7 @@ mod tests {
         fn config(&self) -> ExecutorConfig {
             ExecutorConfig {
                 max_turns: self.max_turns,
+                ..Default::default()
             }
         }
 
@@ -190,6 +214,7 @@ mod tests {
         ) -> Result<CompletionResponse, LLMError> {
             Ok(CompletionResponse {
                 text: "Mock completion".to_string(),
+                usage: None,
             })
         }
     }
@@ -236,24 +261,34 @@ mod tests {
     fn test_executor_config_default() {
         let config = ExecutorConfig::default();
         assert_eq!(config.max_turns, 10);
+        assert_eq!(config.validation, ValidationPolicy::Disabled);
     }
 
     #[test]
     fn test_executor_config_custom() {
-        let config = ExecutorConfig { max_turns: 5 };
+        let config = ExecutorConfig {
+            max_turns: 5,
+            ..Default::default()
+        };
         assert_eq!(config.max_turns, 5);
     }
 
     #[test]
     fn test_executor_config_clone() {
-        let config = ExecutorConfig { max_turns: 15 };
+        let config = ExecutorConfig {
+            max_turns: 15,
+            ..Default::default()
+        };
         let cloned = config.clone();
         assert_eq!(config.max_turns, cloned.max_turns);
     }
 
     #[test]
     fn test_executor_config_debug() {
-        let config = ExecutorConfig { max_turns: 20 };
+        let config = ExecutorConfig {
+            max_turns: 20,
+            ..Default::default()
+        };
         let debug_str = format!("{config:?}");
         assert!(debug_str.contains("ExecutorConfig"));
         assert!(debug_str.contains("20"));
@@ -286,6 +321,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_accumulate_usage() {
+        let mut total = None;
+        accumulate_usage(&mut total, None);
+        assert!(total.is_none());
+
+        accumulate_usage(&mut total, Some(Usage::new(10, 2)));
+        accumulate_usage(&mut total, None);
+        accumulate_usage(&mut total, Some(Usage::new(20, 3)));
+        let total = total.unwrap();
+        assert_eq!(total.prompt_tokens, 30);
+        assert_eq!(total.completion_tokens, 5);
+        assert_eq!(total.total_tokens, 35);
+    }
+
     #[test]
     fn test_turn_result_debug() {
         let result = TurnResult::Complete("test".to_string());
@@ -1,7 +1,13 @@
 use crate::protocol::Event;
-use crate::tool::{ToolCallResult, ToolT};
+use crate::tool::{
+    buffer_chunks, observe_signals, ToolCallError, ToolCallResult, ToolProgress, ToolRetryPolicy,
+    ToolSignal, ToolStream, ToolT,
+};
 use autoagents_llm::{FunctionCall, ToolCall};
+use futures::stream::{self, StreamExt};
 use serde_json::Value;
+use std::future::Future;
+use std::time::Duration;
 
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::sync::mpsc;
@@ -9,6 +15,7 @@ use tokio::sync::mpsc;
 #[cfg(target_arch = "wasm32")]
 use futures::channel::mpsc;
 
+use super::validation::validate_value;
 use crate::agent::{AgentHooks, Context, HookOutcome};
 #[cfg(target_arch = "wasm32")]
 use futures::SinkExt;
@@ -18,19 +25,51 @@ pub struct ToolProcessor;
 
 impl ToolProcessor {
     /// Process multiple tool calls and return results
+    ///
+    /// Up to `parallelism` calls run at once; results keep the order of
+    /// `tool_calls`. `timeout` applies to tools that do not set their own
+    /// [`ToolT::timeout`].
     pub async fn process_tool_calls(
         tools: &[Box<dyn ToolT>],
         tool_calls: Vec<ToolCall>,
         tx_event: Option<mpsc::Sender<Event>>,
+        timeout: Option<Duration>,
+        parallelism: usize,
     ) -> Vec<ToolCallResult> {
-        let mut results = Vec::new();
-
-        for call in &tool_calls {
-            let result = Self::process_single_tool_call(tools, call, &tx_event).await;
-            results.push(result);
-        }
+        stream::iter(&tool_calls)
+            .map(|call| Self::process_single_tool_call(tools, call, &tx_event, timeout))
+            .buffered(parallelism.max(1))
+            .collect()
+            .await
+    }
 
-        results
+    /// Process multiple tool calls with hooks, running up to `parallelism`
+    /// at once. Calls aborted by a hook are left out of the results, which
+    /// otherwise keep the order of `tool_calls`.
+    pub(crate) async fn process_tool_calls_with_hooks<H: AgentHooks>(
+        hooks: &H,
+        context: &Context,
+        tools: &[Box<dyn ToolT>],
+        tool_calls: &[ToolCall],
+        tx_event: &Option<mpsc::Sender<Event>>,
+        timeout: Option<Duration>,
+        parallelism: usize,
+    ) -> Vec<ToolCallResult> {
+        // Collected first so the stream holds futures rather than a closure
+        // over borrowed calls, which would make the caller's future not Send
+        let calls: Vec<_> = tool_calls
+            .iter()
+            .map(|call| {
+                Self::process_single_tool_call_with_hooks(
+                    hooks, context, tools, call, tx_event, timeout,
+                )
+            })
+            .collect();
+        stream::iter(calls)
+            .buffered(parallelism.max(1))
+            .filter_map(|result| async move { result })
+            .collect()
+            .await
     }
 
     /// Process a single tool call (with hooks)
@@ -40,6 +79,7 @@ impl ToolProcessor {
         tools: &[Box<dyn ToolT>],
         call: &ToolCall,
         tx_event: &Option<mpsc::Sender<Event>>,
+        timeout: Option<Duration>,
     ) -> Option<ToolCallResult> {
         // Run hook before execution
         match hooks.on_tool_call(call, context).await {
@@ -52,7 +92,11 @@ impl ToolProcessor {
         //Run the tool start hook
         hooks.on_tool_start(call, context).await;
 
-        let result = Self::process_single_tool_call(tools, call, tx_event).await;
+        let result =
+            Self::observe_single_tool_call(tools, call, tx_event, timeout, |progress| async move {
+                hooks.on_tool_progress(call, &progress, context).await
+            })
+            .await;
 
         //Run on tool result hook
         if result.success {
@@ -71,7 +115,24 @@ impl ToolProcessor {
         tools: &[Box<dyn ToolT>],
         call: &ToolCall,
         tx_event: &Option<mpsc::Sender<Event>>,
+        timeout: Option<Duration>,
     ) -> ToolCallResult {
+        Self::observe_single_tool_call(tools, call, tx_event, timeout, |_| async {}).await
+    }
+
+    /// Process a single tool call, passing each progress update the tool
+    /// reports to `on_progress` after sending it as an event
+    async fn observe_single_tool_call<F, Fut>(
+        tools: &[Box<dyn ToolT>],
+        call: &ToolCall,
+        tx_event: &Option<mpsc::Sender<Event>>,
+        timeout: Option<Duration>,
+        mut on_progress: F,
+    ) -> ToolCallResult
+    where
+        F: FnMut(ToolProgress) -> Fut,
+        Fut: Future<Output = ()>,
+    {
         let tool_name = call.function.name.clone();
         let tool_args = call.function.arguments.clone();
 
@@ -88,7 +149,39 @@ impl ToolProcessor {
 
         // Find and execute the tool
         let result = match tools.iter().find(|t| t.name() == tool_name) {
-            Some(tool) => Self::execute_tool(tool.as_ref(), &tool_name, &tool_args).await,
+            Some(tool) => {
+                let timeout = tool.timeout().or(timeout);
+                let execute = Self::execute_tool(tool.as_ref(), call, tx_event, timeout);
+                observe_signals(execute, |signal| {
+                    let (event, progress) = match signal {
+                        ToolSignal::Progress(progress) => (
+                            Event::ToolCallProgress {
+                                id: call.id.clone(),
+                                tool_name: tool_name.clone(),
+                                progress: progress.clone(),
+                            },
+                            Some(progress),
+                        ),
+                        #[cfg(not(target_arch = "wasm32"))]
+                        ToolSignal::Question(question) => (
+                            Event::HumanInputRequested {
+                                id: call.id.clone(),
+                                tool_name: tool_name.clone(),
+                                question,
+                            },
+                            None,
+                        ),
+                    };
+                    let notify = progress.map(&mut on_progress);
+                    async move {
+                        Self::send_event(tx_event, event).await;
+                        if let Some(notify) = notify {
+                            notify.await;
+                        }
+                    }
+                })
+                .await
+            }
             None => Self::create_error_result(
                 &tool_name,
                 &tool_args,
@@ -103,29 +196,152 @@ impl ToolProcessor {
     }
 
     /// Execute a tool and return the result
-    async fn execute_tool(tool: &dyn ToolT, tool_name: &str, tool_args: &str) -> ToolCallResult {
+    async fn execute_tool(
+        tool: &dyn ToolT,
+        call: &ToolCall,
+        tx_event: &Option<mpsc::Sender<Event>>,
+        timeout: Option<Duration>,
+    ) -> ToolCallResult {
+        let tool_name = call.function.name.as_str();
+        let tool_args = call.function.arguments.as_str();
         match serde_json::from_str::<Value>(tool_args) {
-            Ok(parsed_args) => match tool.execute(parsed_args).await {
-                Ok(output) => ToolCallResult {
-                    tool_name: tool_name.to_string(),
-                    success: true,
-                    arguments: serde_json::from_str(tool_args).unwrap_or(Value::Null),
-                    result: output,
-                },
-                Err(e) => Self::create_error_result(
-                    tool_name,
-                    tool_args,
-                    &format!("Tool execution failed: {e}"),
-                ),
-            },
-            Err(e) => Self::create_error_result(
-                tool_name,
+            Ok(parsed_args) => {
+                let errors = validate_value(&tool.args_schema(), &parsed_args);
+                if !errors.is_empty() {
+                    return Self::create_invalid_arguments_result(tool, tool_args, errors);
+                }
+                let output = match tool.execute_stream(parsed_args.clone()) {
+                    // Chunks already forwarded cannot be taken back, so
+                    // streaming calls are never retried
+                    Some(stream) => Self::run_stream(stream, call, tx_event, timeout).await,
+                    None => Self::run_tool_with_retries(tool, parsed_args, timeout).await,
+                };
+                match output {
+                    Ok(output) => ToolCallResult {
+                        tool_name: tool_name.to_string(),
+                        success: true,
+                        arguments: serde_json::from_str(tool_args).unwrap_or(Value::Null),
+                        result: output,
+                    },
+                    Err(ToolCallError::Timeout(limit)) => ToolCallResult {
+                        tool_name: tool_name.to_string(),
+                        success: false,
+                        arguments: serde_json::from_str(tool_args).unwrap_or(Value::Null),
+                        result: serde_json::json!({
+                            "error": format!("Tool '{tool_name}' timed out after {limit:?}"),
+                            "timed_out": true,
+                            "timeout_ms": limit.as_millis() as u64,
+                        }),
+                    },
+                    // Classified errors tell the model whether to retry
+                    Err(ToolCallError::Tool(error)) => ToolCallResult {
+                        tool_name: tool_name.to_string(),
+                        success: false,
+                        arguments: serde_json::from_str(tool_args).unwrap_or(Value::Null),
+                        result: error.feedback(tool_name, tool.args_schema()),
+                    },
+                    Err(e) => Self::create_error_result(
+                        tool_name,
+                        tool_args,
+                        &format!("Tool execution failed: {e}"),
+                    ),
+                }
+            }
+            Err(e) => Self::create_invalid_arguments_result(
+                tool,
                 tool_args,
-                &format!("Failed to parse arguments: {e}"),
+                vec![format!("arguments are not valid JSON: {e}")],
             ),
         }
     }
 
+    /// Run the tool, retrying failed attempts when it is idempotent and
+    /// declares a retry policy
+    async fn run_tool_with_retries(
+        tool: &dyn ToolT,
+        args: Value,
+        timeout: Option<Duration>,
+    ) -> Result<Value, ToolCallError> {
+        let Some(policy) = tool.retry_policy().filter(|_| tool.idempotent()) else {
+            return Self::run_tool(tool, args, timeout).await;
+        };
+
+        let mut retry = 0;
+        loop {
+            match Self::run_tool(tool, args.clone(), timeout).await {
+                Err(e) if retry < policy.max_retries && ToolRetryPolicy::is_retryable(&e) => {
+                    let delay = policy.backoff(retry);
+                    log::debug!(
+                        "Retrying tool '{}' in {delay:?} after error: {e}",
+                        tool.name()
+                    );
+                    #[cfg(not(target_arch = "wasm32"))]
+                    tokio::time::sleep(delay).await;
+                    #[cfg(target_arch = "wasm32")]
+                    gloo_timers::future::sleep(delay).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run the tool, cancelling it by dropping its future once `timeout`
+    /// has elapsed
+    async fn run_tool(
+        tool: &dyn ToolT,
+        args: Value,
+        timeout: Option<Duration>,
+    ) -> Result<Value, ToolCallError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(limit) = timeout {
+            return tokio::time::timeout(limit, tool.execute(args))
+                .await
+                .map_err(|_| ToolCallError::Timeout(limit))?;
+        }
+        // No timer is available on wasm, so calls run to completion
+        #[cfg(target_arch = "wasm32")]
+        let _ = timeout;
+        tool.execute(args).await
+    }
+
+    /// Forward each chunk of a streaming tool as a [`Event::ToolCallChunk`]
+    /// and buffer them into the result the LLM sees, giving up once
+    /// `timeout` has elapsed
+    async fn run_stream(
+        mut stream: ToolStream<'_>,
+        call: &ToolCall,
+        tx_event: &Option<mpsc::Sender<Event>>,
+        timeout: Option<Duration>,
+    ) -> Result<Value, ToolCallError> {
+        let collect = async {
+            let mut chunks = Vec::new();
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                Self::send_event(
+                    tx_event,
+                    Event::ToolCallChunk {
+                        id: call.id.clone(),
+                        tool_name: call.function.name.clone(),
+                        chunk: chunk.clone(),
+                    },
+                )
+                .await;
+                chunks.push(chunk);
+            }
+            Ok::<_, ToolCallError>(buffer_chunks(chunks))
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(limit) = timeout {
+            return tokio::time::timeout(limit, collect)
+                .await
+                .map_err(|_| ToolCallError::Timeout(limit))?;
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = timeout;
+        collect.await
+    }
+
     /// Create an error result for tool execution
     fn create_error_result(tool_name: &str, tool_args: &str, error: &str) -> ToolCallResult {
         ToolCallResult {
@@ -136,6 +352,26 @@ impl ToolProcessor {
         }
     }
 
+    /// Create an error result for arguments that do not match the tool's
+    /// schema, listing every problem alongside the schema so the model can
+    /// correct the call
+    fn create_invalid_arguments_result(
+        tool: &dyn ToolT,
+        tool_args: &str,
+        errors: Vec<String>,
+    ) -> ToolCallResult {
+        ToolCallResult {
+            tool_name: tool.name().to_string(),
+            success: false,
+            arguments: serde_json::from_str(tool_args).unwrap_or(Value::Null),
+            result: serde_json::json!({
+                "error": format!("Invalid arguments for tool '{}'", tool.name()),
+                "validation_errors": errors,
+                "schema": tool.args_schema(),
+            }),
+        }
+    }
+
     /// Send an event if tx is available
     async fn send_event(tx: &Option<mpsc::Sender<Event>>, event: Event) {
         if let Some(tx) = tx {
@@ -196,8 +432,438 @@ impl ToolProcessor {
                 Value::String(s) => s.clone(),
                 other => serde_json::to_string(other).unwrap_or_default(),
             }
+        } else if result.result.get("error").is_some() {
+            // Already structured, pass it on as is so the model can act on it
+            result.result.to_string()
         } else {
-            serde_json::json!({"error": format!("{:?}", result.result)}).to_string()
+            serde_json::json!({"error": result.result}).to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::{ToolError, ToolRuntime};
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct SlowTool {
+        timeout: Option<Duration>,
+    }
+
+    impl ToolT for SlowTool {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        fn description(&self) -> &'static str {
+            "Sleeps for the given number of milliseconds"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+    }
+
+    #[async_trait]
+    impl ToolRuntime for SlowTool {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            let ms = args["ms"].as_u64().unwrap_or_default();
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(json!("done"))
+        }
+    }
+
+    fn call(ms: u64) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "slow".to_string(),
+                arguments: json!({ "ms": ms }).to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_tool_reports_structured_error() {
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(SlowTool { timeout: None })];
+        let result = ToolProcessor::process_single_tool_call(
+            &tools,
+            &call(10_000),
+            &None,
+            Some(Duration::from_millis(20)),
+        )
+        .await;
+
+        assert!(!result.success);
+        assert_eq!(result.result["timed_out"], true);
+        assert_eq!(result.result["timeout_ms"], 20);
+
+        let result = ToolProcessor::process_single_tool_call(
+            &tools,
+            &call(1),
+            &None,
+            Some(Duration::from_secs(5)),
+        )
+        .await;
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_arguments_are_rejected_before_execution() {
+        #[derive(Debug)]
+        struct Strict;
+
+        impl ToolT for Strict {
+            fn name(&self) -> &'static str {
+                "strict"
+            }
+
+            fn description(&self) -> &'static str {
+                "Takes a required count"
+            }
+
+            fn args_schema(&self) -> Value {
+                json!({
+                    "type": "object",
+                    "properties": {"count": {"type": "integer"}},
+                    "required": ["count"]
+                })
+            }
+        }
+
+        #[async_trait]
+        impl ToolRuntime for Strict {
+            async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+                panic!("must not run with invalid arguments");
+            }
+        }
+
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(Strict)];
+        let mut bad = call(1);
+        bad.function.name = "strict".to_string();
+        bad.function.arguments = json!({"count": "three"}).to_string();
+        let result = ToolProcessor::process_single_tool_call(&tools, &bad, &None, None).await;
+
+        assert!(!result.success);
+        assert_eq!(
+            result.result["validation_errors"],
+            json!(["$.count: expected integer, found string"])
+        );
+        assert_eq!(result.result["schema"], Strict.args_schema());
+
+        bad.function.arguments = "{\"count\":".to_string();
+        let result = ToolProcessor::process_single_tool_call(&tools, &bad, &None, None).await;
+        assert!(!result.success);
+        assert_eq!(
+            result.result["validation_errors"].as_array().unwrap().len(),
+            1
+        );
+
+        let content = ToolProcessor::extract_result_content(&result);
+        let content: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(content["error"], "Invalid arguments for tool 'strict'");
+    }
+
+    #[tokio::test]
+    async fn test_classified_errors_guide_the_model() {
+        #[derive(Debug)]
+        struct Transfer;
+
+        impl ToolT for Transfer {
+            fn name(&self) -> &'static str {
+                "slow"
+            }
+
+            fn description(&self) -> &'static str {
+                "Moves money between accounts"
+            }
+
+            fn args_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+        }
+
+        #[async_trait]
+        impl ToolRuntime for Transfer {
+            async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+                match args["ms"].as_u64() {
+                    Some(0) => Err(ToolError::InvalidArgs("amount must be positive".into()).into()),
+                    Some(1) => Err(ToolError::Transient("bank is offline".into()).into()),
+                    _ => Err(ToolError::PermissionDenied("account is frozen".into()).into()),
+                }
+            }
+        }
+
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(Transfer)];
+        let run = |ms| {
+            let tools = &tools;
+            async move {
+                ToolProcessor::process_single_tool_call(tools, &call(ms), &None, None)
+                    .await
+                    .result
+            }
+        };
+
+        let invalid = run(0).await;
+        assert_eq!(invalid["kind"], "invalid_args");
+        assert_eq!(invalid["schema"], Transfer.args_schema());
+
+        let transient = run(1).await;
+        assert_eq!(transient["kind"], "transient");
+        assert_eq!(transient["retryable"], true);
+
+        let denied = run(2).await;
+        assert_eq!(denied["kind"], "permission_denied");
+        assert_eq!(denied["retryable"], false);
+        assert_eq!(
+            denied["error"],
+            "Tool 'slow' failed: Permission denied: account is frozen"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streamed_chunks_are_forwarded_and_buffered() {
+        #[derive(Debug)]
+        struct Tail;
+
+        impl ToolT for Tail {
+            fn name(&self) -> &'static str {
+                "slow"
+            }
+
+            fn description(&self) -> &'static str {
+                "Streams log lines"
+            }
+
+            fn args_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+        }
+
+        #[async_trait]
+        impl ToolRuntime for Tail {
+            async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+                unreachable!("streaming tools are run through execute_stream");
+            }
+
+            fn execute_stream(&self, _args: Value) -> Option<ToolStream<'_>> {
+                let lines = ["first\n", "second\n"].map(|line| Ok(json!(line)));
+                Some(Box::pin(stream::iter(lines)))
+            }
+        }
+
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(Tail)];
+        let (tx, mut rx) = mpsc::channel(16);
+        let result =
+            ToolProcessor::process_single_tool_call(&tools, &call(1), &Some(tx), None).await;
+        assert!(result.success);
+        assert_eq!(result.result, "first\nsecond\n");
+
+        let mut chunks = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let Event::ToolCallChunk { chunk, .. } = event {
+                chunks.push(chunk);
+            }
+        }
+        assert_eq!(chunks, vec![json!("first\n"), json!("second\n")]);
+
+        assert_eq!(
+            buffer_chunks(vec![json!({"line": 1}), json!("two")]),
+            json!([{"line": 1}, "two"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_progress_is_sent_while_the_tool_runs() {
+        #[derive(Debug)]
+        struct Download;
+
+        impl ToolT for Download {
+            fn name(&self) -> &'static str {
+                "slow"
+            }
+
+            fn description(&self) -> &'static str {
+                "Reports progress"
+            }
+
+            fn args_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+        }
+
+        #[async_trait]
+        impl ToolRuntime for Download {
+            async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+                for percent in [25.0, 100.0] {
+                    crate::tool::report_progress(ToolProgress::percent(percent));
+                    tokio::task::yield_now().await;
+                }
+                Ok(json!("downloaded"))
+            }
+        }
+
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(Download)];
+        let (tx, mut rx) = mpsc::channel(16);
+        let result =
+            ToolProcessor::process_single_tool_call(&tools, &call(1), &Some(tx), None).await;
+        assert!(result.success);
+
+        let mut reported = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let Event::ToolCallProgress { progress, .. } = event {
+                reported.push(progress.percent);
+            }
+        }
+        assert_eq!(reported, vec![Some(25.0), Some(100.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_human_questions_are_sent_as_events() {
+        let tool = crate::tool::AskHumanTool::new();
+        let input = tool.handle();
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(tool)];
+        let mut ask = call(1);
+        ask.function.name = "ask_human".to_string();
+        ask.function.arguments = json!({"question": "Which branch?"}).to_string();
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let tx = Some(tx);
+        let run = ToolProcessor::process_single_tool_call(&tools, &ask, &tx, None);
+        let reply = async {
+            while let Some(event) = rx.recv().await {
+                if let Event::HumanInputRequested { question, .. } = event {
+                    input.answer(&question.id, "main").unwrap();
+                    break;
+                }
+            }
+        };
+        let (result, ()) = tokio::join!(run, reply);
+
+        assert!(result.success);
+        assert_eq!(result.result["answer"], "main");
+    }
+
+    #[tokio::test]
+    async fn test_tool_timeout_overrides_executor_default() {
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(SlowTool {
+            timeout: Some(Duration::from_millis(20)),
+        })];
+        let results =
+            ToolProcessor::process_tool_calls(&tools, vec![call(10_000)], None, None, 1).await;
+        assert_eq!(results[0].result["timed_out"], true);
+    }
+
+    #[derive(Debug)]
+    struct FlakyTool {
+        failures: std::sync::atomic::AtomicU32,
+        idempotent: bool,
+    }
+
+    impl FlakyTool {
+        fn new(failures: u32, idempotent: bool) -> Self {
+            Self {
+                failures: failures.into(),
+                idempotent,
+            }
+        }
+    }
+
+    impl ToolT for FlakyTool {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn description(&self) -> &'static str {
+            "Fails a fixed number of times"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        fn idempotent(&self) -> bool {
+            self.idempotent
         }
+
+        fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+            Some(ToolRetryPolicy::new(2).with_backoff(Duration::from_millis(1)))
+        }
+    }
+
+    #[async_trait]
+    impl ToolRuntime for FlakyTool {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            use std::sync::atomic::Ordering;
+            let remaining = self.failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                return Err(ToolCallError::RuntimeError("unavailable".into()));
+            }
+            Ok(json!("ok"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_idempotent_tools_are_retried() {
+        let retried = FlakyTool::new(2, true);
+        let result = ToolProcessor::run_tool_with_retries(&retried, json!({}), None).await;
+        assert_eq!(result.unwrap(), "ok");
+
+        let exhausted = FlakyTool::new(3, true);
+        let result = ToolProcessor::run_tool_with_retries(&exhausted, json!({}), None).await;
+        assert!(result.is_err());
+
+        let unsafe_to_repeat = FlakyTool::new(1, false);
+        let result = ToolProcessor::run_tool_with_retries(&unsafe_to_repeat, json!({}), None).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parallel_calls_run_together_and_keep_order() {
+        // Both calls wait for each other, so they only finish when run concurrently
+        #[derive(Debug)]
+        struct Rendezvous(tokio::sync::Barrier);
+
+        impl ToolT for Rendezvous {
+            fn name(&self) -> &'static str {
+                "slow"
+            }
+
+            fn description(&self) -> &'static str {
+                "Waits for another call"
+            }
+
+            fn args_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+        }
+
+        #[async_trait]
+        impl ToolRuntime for Rendezvous {
+            async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+                self.0.wait().await;
+                Ok(args["ms"].clone())
+            }
+        }
+
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(Rendezvous(tokio::sync::Barrier::new(2)))];
+        let results = tokio::time::timeout(
+            Duration::from_secs(5),
+            ToolProcessor::process_tool_calls(&tools, vec![call(1), call(2)], None, None, 2),
+        )
+        .await
+        .expect("calls should run concurrently");
+
+        let order: Vec<Value> = results.into_iter().map(|r| r.result).collect();
+        assert_eq!(order, vec![json!(1), json!(2)]);
     }
 }
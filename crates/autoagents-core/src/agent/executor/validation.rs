@@ -0,0 +1,315 @@
+use serde_json::Value;
+
+/// What an executor does when the final LLM response does not satisfy the
+/// agent's output schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationPolicy {
+    /// Return the response unchecked
+    #[default]
+    Disabled,
+    /// Re-prompt the LLM with the validation errors up to `max_retries` times,
+    /// failing the run if the response is still invalid afterwards
+    Repair { max_retries: usize },
+}
+
+impl ValidationPolicy {
+    pub fn repair(max_retries: usize) -> Self {
+        Self::Repair { max_retries }
+    }
+
+    /// Number of repair prompts allowed, `0` when validation is disabled.
+    pub fn max_retries(&self) -> usize {
+        match self {
+            Self::Disabled => 0,
+            Self::Repair { max_retries } => *max_retries,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, Self::Repair { .. })
+    }
+}
+
+/// Parse `response` as JSON and check it against `schema`.
+///
+/// Returns the parsed value, or every problem found so they can all be fed
+/// back to the model at once.
+pub fn validate_output(schema: &Value, response: &str) -> Result<Value, Vec<String>> {
+    let value: Value = serde_json::from_str(response.trim())
+        .map_err(|e| vec![format!("response is not valid JSON: {e}")])?;
+    let errors = validate_value(schema, &value);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check `value` against a JSON schema.
+///
+/// Covers the subset of JSON Schema produced for agent outputs: `type`,
+/// `enum`, `const`, `properties`, `required`, `additionalProperties`,
+/// `items`, `anyOf`/`oneOf`/`allOf` and the numeric, length and size bounds.
+/// Unknown keywords are ignored.
+pub fn validate_value(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, value, "$", &mut errors);
+    errors
+}
+
+/// System prompt section asking for output matching `schema`, for LLMs that
+/// do not enforce structured output themselves.
+pub fn schema_instructions(schema: &Value) -> String {
+    let schema = serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string());
+    format!(
+        "Respond with only a JSON value that satisfies this JSON schema, without any other text:\n{schema}"
+    )
+}
+
+/// Follow-up prompt asking the model to fix its previous answer.
+pub fn repair_prompt(errors: &[String]) -> String {
+    let mut prompt =
+        String::from("Your previous response did not match the required output schema:\n");
+    for error in errors {
+        prompt.push_str("- ");
+        prompt.push_str(error);
+        prompt.push('\n');
+    }
+    prompt.push_str("Respond again with only the corrected JSON that satisfies the schema.");
+    prompt
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true`, `{}` and other non-object schemas accept anything
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{path}: no value is allowed here"));
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| has_type(value, t)) {
+            errors.push(format!(
+                "{path}: expected {}, found {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{path}: {value} is not one of {}",
+                Value::from(options.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{path}: expected {expected}, found {value}"));
+        }
+    }
+
+    if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+        for branch in branches {
+            check(branch, value, path, errors);
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(branches) = schema.get(keyword).and_then(Value::as_array) {
+            let matching = branches
+                .iter()
+                .filter(|b| validate_value(b, value).is_empty())
+                .count();
+            if matching == 0 || (keyword == "oneOf" && matching > 1) {
+                errors.push(format!(
+                    "{path}: must match {} of the `{keyword}` schemas, matched {matching}",
+                    if keyword == "oneOf" {
+                        "exactly one"
+                    } else {
+                        "at least one"
+                    }
+                ));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(name) {
+                        errors.push(format!("{path}: missing required property `{name}`"));
+                    }
+                }
+            }
+            for (key, item) in map {
+                let child = format!("{path}.{key}");
+                match properties.and_then(|p| p.get(key)) {
+                    Some(prop_schema) => check(prop_schema, item, &child, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{path}: unexpected property `{key}`"))
+                        }
+                        Some(extra @ Value::Object(_)) => check(extra, item, &child, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            bound(schema, "minItems", items.len(), path, errors, |n, min| {
+                n >= min
+            });
+            bound(schema, "maxItems", items.len(), path, errors, |n, max| {
+                n <= max
+            });
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}[{i}]"), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count();
+            bound(schema, "minLength", len, path, errors, |n, min| n >= min);
+            bound(schema, "maxLength", len, path, errors, |n, max| n <= max);
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    errors.push(format!("{path}: {n} is less than the minimum {min}"));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    errors.push(format!("{path}: {n} is greater than the maximum {max}"));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn bound(
+    schema: &serde_json::Map<String, Value>,
+    keyword: &str,
+    actual: usize,
+    path: &str,
+    errors: &mut Vec<String>,
+    ok: impl Fn(usize, usize) -> bool,
+) {
+    if let Some(limit) = schema.get(keyword).and_then(Value::as_u64) {
+        if !ok(actual, limit as usize) {
+            errors.push(format!("{path}: `{keyword}` is {limit}, found {actual}"));
+        }
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some(),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn weather_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "city": {"type": "string", "minLength": 1},
+                "temperature": {"type": "integer", "minimum": -100},
+                "unit": {"enum": ["C", "F"]},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["city", "temperature"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_policy_defaults_to_disabled() {
+        let policy = ValidationPolicy::default();
+        assert!(!policy.is_enabled());
+        assert_eq!(policy.max_retries(), 0);
+        assert_eq!(ValidationPolicy::repair(3).max_retries(), 3);
+    }
+
+    #[test]
+    fn test_valid_output_is_parsed() {
+        let value = validate_output(
+            &weather_schema(),
+            r#"{"city": "Oslo", "temperature": 4, "unit": "C", "tags": ["cold"]}"#,
+        )
+        .unwrap();
+        assert_eq!(value["city"], "Oslo");
+    }
+
+    #[test]
+    fn test_invalid_json_is_reported() {
+        let errors = validate_output(&weather_schema(), "It is 4 degrees in Oslo").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("not valid JSON"));
+    }
+
+    #[test]
+    fn test_collects_all_schema_errors() {
+        let errors = validate_value(
+            &weather_schema(),
+            &json!({"temperature": 4.5, "unit": "K", "tags": ["a", 1], "extra": true}),
+        );
+        let joined = errors.join("\n");
+        assert!(joined.contains("missing required property `city`"));
+        assert!(joined.contains("$.temperature: expected integer, found number"));
+        assert!(joined.contains("$.unit"));
+        assert!(joined.contains("$.tags[1]: expected string"));
+        assert!(joined.contains("unexpected property `extra`"));
+        assert_eq!(errors.len(), 5);
+    }
+
+    #[test]
+    fn test_any_of_and_nullable_types() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "note": {"type": ["string", "null"]},
+                "id": {"anyOf": [{"type": "string"}, {"type": "integer"}]}
+            }
+        });
+        assert!(validate_value(&schema, &json!({"note": null, "id": 7})).is_empty());
+        assert_eq!(validate_value(&schema, &json!({"id": false})).len(), 1);
+    }
+
+    #[test]
+    fn test_repair_prompt_lists_errors() {
+        let prompt = repair_prompt(&["$.a: missing".to_string(), "$.b: wrong".to_string()]);
+        assert!(prompt.contains("- $.a: missing\n- $.b: wrong\n"));
+    }
+}
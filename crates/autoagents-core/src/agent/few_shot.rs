@@ -0,0 +1,258 @@
+//! Few-shot examples attached to an agent.
+//!
+//! Examples show the model how inputs map to the expected outputs. They are
+//! added to the system prompt as an examples section, after the output
+//! schema, so they read the same to every provider.
+//!
+//! Agents with many examples can pick the ones that fit the task with an
+//! [`ExampleSelector`], such as [`EmbeddingExampleSelector`], which keeps the
+//! examples most similar to the task prompt:
+//!
+//! ```rust,ignore
+//! let examples = FewShotExamples::new([
+//!     Example::new("2 + 2", "4"),
+//!     Example::new("What is the capital of France?", "Paris"),
+//! ])
+//! .with_selector(Arc::new(EmbeddingExampleSelector::new(embedder, 3)));
+//! let agent = AgentBuilder::<_, DirectAgent>::new(agent).few_shot(examples);
+//! ```
+
+use crate::utils::cosine_similarity;
+use async_trait::async_trait;
+use autoagents_llm::embedding::EmbeddingProvider;
+use autoagents_llm::error::LLMError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::Mutex;
+
+#[cfg(target_arch = "wasm32")]
+use futures::lock::Mutex;
+
+/// An input and the output the agent should give for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Example {
+    pub input: String,
+    pub output: String,
+}
+
+impl Example {
+    pub fn new(input: impl Into<String>, output: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            output: output.into(),
+        }
+    }
+}
+
+/// Picks the examples shown for a task.
+#[async_trait]
+pub trait ExampleSelector: Send + Sync + Debug {
+    /// Return the indices into `examples` to show for `query`, in the order
+    /// they should appear.
+    async fn select(&self, query: &str, examples: &[Example]) -> Result<Vec<usize>, LLMError>;
+}
+
+/// Keeps the `top_k` examples whose input embedding is most similar to the
+/// task prompt, most similar first.
+///
+/// Example embeddings are computed once and cached by input.
+pub struct EmbeddingExampleSelector {
+    embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    top_k: usize,
+    cache: Mutex<HashMap<String, Vec<f32>>>,
+}
+
+impl EmbeddingExampleSelector {
+    pub fn new(embedder: Arc<dyn EmbeddingProvider + Send + Sync>, top_k: usize) -> Self {
+        Self {
+            embedder,
+            top_k,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Debug for EmbeddingExampleSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingExampleSelector")
+            .field("top_k", &self.top_k)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl ExampleSelector for EmbeddingExampleSelector {
+    async fn select(&self, query: &str, examples: &[Example]) -> Result<Vec<usize>, LLMError> {
+        if examples.len() <= self.top_k || query.is_empty() {
+            return Ok((0..examples.len()).collect());
+        }
+
+        let mut cache = self.cache.lock().await;
+        let missing: Vec<String> = examples
+            .iter()
+            .filter(|example| !cache.contains_key(&example.input))
+            .map(|example| example.input.clone())
+            .collect();
+        if !missing.is_empty() {
+            let embeddings = self.embedder.embed(missing.clone()).await?;
+            if embeddings.len() != missing.len() {
+                return Err(LLMError::ProviderError(format!(
+                    "expected {} embeddings, got {}",
+                    missing.len(),
+                    embeddings.len()
+                )));
+            }
+            cache.extend(missing.into_iter().zip(embeddings));
+        }
+
+        let query_embedding = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("empty query embedding".into()))?;
+
+        let mut scored: Vec<(usize, f32)> = examples
+            .iter()
+            .enumerate()
+            .map(|(i, example)| {
+                let score = cache
+                    .get(&example.input)
+                    .map(|e| cosine_similarity(&query_embedding, e))
+                    .unwrap_or_default();
+                (i, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored
+            .into_iter()
+            .take(self.top_k)
+            .map(|(i, _)| i)
+            .collect())
+    }
+}
+
+/// The examples of an agent and how the ones for a task are chosen.
+#[derive(Debug, Clone, Default)]
+pub struct FewShotExamples {
+    examples: Vec<Example>,
+    selector: Option<Arc<dyn ExampleSelector>>,
+}
+
+impl FewShotExamples {
+    /// Show all of `examples`, in order, for every task
+    pub fn new(examples: impl IntoIterator<Item = Example>) -> Self {
+        Self {
+            examples: examples.into_iter().collect(),
+            selector: None,
+        }
+    }
+
+    /// Show only the examples `selector` picks for the task
+    pub fn with_selector(mut self, selector: Arc<dyn ExampleSelector>) -> Self {
+        self.selector = Some(selector);
+        self
+    }
+
+    pub fn examples(&self) -> &[Example] {
+        &self.examples
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    /// The examples to show for a task with prompt `query`. Selection errors
+    /// are logged and fall back to all examples so a run is never blocked.
+    pub async fn select(&self, query: &str) -> Vec<Example> {
+        let Some(selector) = &self.selector else {
+            return self.examples.clone();
+        };
+        match selector.select(query, &self.examples).await {
+            Ok(indices) => indices
+                .into_iter()
+                .filter_map(|i| self.examples.get(i).cloned())
+                .collect(),
+            Err(e) => {
+                log::warn!("Example selection failed, showing all examples: {e}");
+                self.examples.clone()
+            }
+        }
+    }
+}
+
+/// `examples` as a system prompt section
+pub(crate) fn render_examples(examples: &[Example]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("Examples of inputs and the expected outputs:");
+    for example in examples {
+        section.push_str(&format!(
+            "\n\nInput: {}\nOutput: {}",
+            example.input, example.output
+        ));
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds text as counts of a few topic words.
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    ["math", "geography", "cooking"]
+                        .iter()
+                        .map(|topic| text.matches(topic).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    fn examples() -> Vec<Example> {
+        vec![
+            Example::new("a math question", "42"),
+            Example::new("a geography question", "Paris"),
+            Example::new("a cooking question", "Boil it"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_embedding_selector_keeps_the_most_similar() {
+        let few_shot = FewShotExamples::new(examples()).with_selector(Arc::new(
+            EmbeddingExampleSelector::new(Arc::new(TopicEmbedder), 1),
+        ));
+
+        let selected = few_shot.select("more geography please").await;
+        assert_eq!(
+            selected,
+            vec![Example::new("a geography question", "Paris")]
+        );
+        assert_eq!(few_shot.select("").await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_examples_without_selector_are_all_shown() {
+        let few_shot = FewShotExamples::new(examples());
+        assert_eq!(few_shot.select("anything").await, examples());
+        assert_eq!(
+            render_examples(&few_shot.examples()[..2]),
+            "Examples of inputs and the expected outputs:\n\nInput: a math question\nOutput: 42\n\nInput: a geography question\nOutput: Paris"
+        );
+        assert_eq!(render_examples(&[]), "");
+    }
+}
@@ -0,0 +1,749 @@
+//! Input and output guardrails.
+//!
+//! A [`Guardrail`] inspects the task prompt before a run and the final
+//! response after it. Guardrails are chained on the [`AgentBuilder`] and run
+//! in order; each one either passes, blocks the run, rewrites the content for
+//! the next guardrail, or annotates the run with a note.
+//!
+//! Content moderation plugs in the same way through [`ModerationGuardrail`],
+//! backed by a hosted endpoint or a local classifier.
+//!
+//! [`AgentBuilder`]: crate::agent::AgentBuilder
+
+use crate::agent::executor::validation;
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::moderation::{ModerationProvider, ModerationResult};
+use autoagents_llm::LLMProvider;
+use futures::future::BoxFuture;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Point of the run a guardrail is evaluated at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GuardrailStage {
+    /// The task prompt, before the executor runs
+    Input,
+    /// The final response, before it is returned to the caller
+    Output,
+}
+
+/// Verdict of a single guardrail check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardrailOutcome {
+    Pass,
+    /// Stop the run
+    Block {
+        reason: String,
+    },
+    /// Replace the content and continue
+    Rewrite {
+        content: String,
+        reason: String,
+    },
+    /// Keep the content but record a note on the run
+    Annotate {
+        note: String,
+    },
+}
+
+/// What the built-in guardrails do when their check fails.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum GuardrailAction {
+    #[default]
+    Block,
+    Annotate,
+    /// Replace the offending text with the given string. Guardrails that
+    /// cannot point at a span of text block instead.
+    Redact(String),
+}
+
+/// How a violation was handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ViolationKind {
+    Blocked,
+    Rewritten,
+    Annotated,
+}
+
+/// Record of a guardrail that did not pass.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardrailViolation {
+    pub guardrail: String,
+    pub stage: GuardrailStage,
+    pub kind: ViolationKind,
+    pub reason: String,
+}
+
+#[async_trait]
+pub trait Guardrail: Send + Sync + Debug {
+    fn name(&self) -> &str;
+
+    /// Whether this guardrail runs at `stage`. Defaults to both stages.
+    fn applies_to(&self, _stage: GuardrailStage) -> bool {
+        true
+    }
+
+    async fn check(&self, stage: GuardrailStage, content: &str) -> GuardrailOutcome;
+}
+
+/// Content that made it through a [`GuardrailChain`], with every rewrite and
+/// annotation applied along the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardrailReport {
+    pub content: String,
+    pub violations: Vec<GuardrailViolation>,
+}
+
+/// Ordered list of guardrails evaluated one after another.
+#[derive(Debug, Clone, Default)]
+pub struct GuardrailChain {
+    guardrails: Vec<Arc<dyn Guardrail>>,
+}
+
+impl GuardrailChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, guardrail: Arc<dyn Guardrail>) {
+        self.guardrails.push(guardrail);
+    }
+
+    pub fn with(mut self, guardrail: impl Guardrail + 'static) -> Self {
+        self.push(Arc::new(guardrail));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.guardrails.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.guardrails.is_empty()
+    }
+
+    /// Whether any guardrail in the chain runs at `stage`.
+    pub fn covers(&self, stage: GuardrailStage) -> bool {
+        self.guardrails.iter().any(|g| g.applies_to(stage))
+    }
+
+    /// Run every guardrail for `stage` over `content`.
+    ///
+    /// Rewrites feed into the next guardrail. The first block stops the chain
+    /// and is returned as the error.
+    pub async fn run(
+        &self,
+        stage: GuardrailStage,
+        content: String,
+    ) -> Result<GuardrailReport, GuardrailViolation> {
+        let mut report = GuardrailReport {
+            content,
+            violations: Vec::new(),
+        };
+        for guardrail in self.guardrails.iter().filter(|g| g.applies_to(stage)) {
+            let violation = |kind, reason| GuardrailViolation {
+                guardrail: guardrail.name().to_string(),
+                stage,
+                kind,
+                reason,
+            };
+            match guardrail.check(stage, &report.content).await {
+                GuardrailOutcome::Pass => {}
+                GuardrailOutcome::Block { reason } => {
+                    return Err(violation(ViolationKind::Blocked, reason));
+                }
+                GuardrailOutcome::Rewrite { content, reason } => {
+                    report.content = content;
+                    report
+                        .violations
+                        .push(violation(ViolationKind::Rewritten, reason));
+                }
+                GuardrailOutcome::Annotate { note } => {
+                    report
+                        .violations
+                        .push(violation(ViolationKind::Annotated, note));
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+fn outcome_for(action: &GuardrailAction, reason: String) -> GuardrailOutcome {
+    match action {
+        GuardrailAction::Annotate => GuardrailOutcome::Annotate { note: reason },
+        GuardrailAction::Block | GuardrailAction::Redact(_) => GuardrailOutcome::Block { reason },
+    }
+}
+
+/// Flags content matching a regular expression, or any word of a denylist.
+#[derive(Debug, Clone)]
+pub struct RegexGuardrail {
+    name: String,
+    pattern: Regex,
+    action: GuardrailAction,
+    stage: Option<GuardrailStage>,
+}
+
+impl RegexGuardrail {
+    pub fn new(name: impl Into<String>, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            name: name.into(),
+            pattern: Regex::new(pattern)?,
+            action: GuardrailAction::default(),
+            stage: None,
+        })
+    }
+
+    /// Match any of `terms` as whole words, ignoring case.
+    pub fn denylist<S: AsRef<str>>(
+        name: impl Into<String>,
+        terms: impl IntoIterator<Item = S>,
+    ) -> Result<Self, regex::Error> {
+        let alternatives: Vec<String> = terms
+            .into_iter()
+            .map(|t| regex::escape(t.as_ref()))
+            .collect();
+        Self::new(name, &format!(r"(?i)\b(?:{})\b", alternatives.join("|")))
+    }
+
+    pub fn action(mut self, action: GuardrailAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Restrict the guardrail to one stage
+    pub fn stage(mut self, stage: GuardrailStage) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+}
+
+#[async_trait]
+impl Guardrail for RegexGuardrail {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, stage: GuardrailStage) -> bool {
+        self.stage.is_none_or(|s| s == stage)
+    }
+
+    async fn check(&self, _stage: GuardrailStage, content: &str) -> GuardrailOutcome {
+        let matches = self.pattern.find_iter(content).count();
+        if matches == 0 {
+            return GuardrailOutcome::Pass;
+        }
+        let reason = format!("{matches} match(es) of `{}`", self.pattern.as_str());
+        match &self.action {
+            GuardrailAction::Redact(replacement) => GuardrailOutcome::Rewrite {
+                content: self
+                    .pattern
+                    .replace_all(content, replacement.as_str())
+                    .into_owned(),
+                reason,
+            },
+            action => outcome_for(action, reason),
+        }
+    }
+}
+
+/// Requires the final response to be JSON matching a schema.
+#[derive(Debug, Clone)]
+pub struct JsonSchemaGuardrail {
+    name: String,
+    schema: Value,
+    action: GuardrailAction,
+}
+
+impl JsonSchemaGuardrail {
+    pub fn new(name: impl Into<String>, schema: Value) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+            action: GuardrailAction::default(),
+        }
+    }
+
+    pub fn action(mut self, action: GuardrailAction) -> Self {
+        self.action = action;
+        self
+    }
+}
+
+#[async_trait]
+impl Guardrail for JsonSchemaGuardrail {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, stage: GuardrailStage) -> bool {
+        stage == GuardrailStage::Output
+    }
+
+    async fn check(&self, _stage: GuardrailStage, content: &str) -> GuardrailOutcome {
+        match validation::validate_output(&self.schema, content) {
+            Ok(_) => GuardrailOutcome::Pass,
+            Err(errors) => outcome_for(&self.action, errors.join("; ")),
+        }
+    }
+}
+
+type CheckFn = dyn Fn(GuardrailStage, String) -> BoxFuture<'static, GuardrailOutcome> + Send + Sync;
+
+/// Guardrail backed by an async closure.
+pub struct FnGuardrail {
+    name: String,
+    check: Box<CheckFn>,
+}
+
+impl FnGuardrail {
+    pub fn new<F, Fut>(name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(GuardrailStage, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = GuardrailOutcome> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            check: Box::new(move |stage, content| Box::pin(check(stage, content))),
+        }
+    }
+}
+
+impl Debug for FnGuardrail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnGuardrail")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Guardrail for FnGuardrail {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self, stage: GuardrailStage, content: &str) -> GuardrailOutcome {
+        (self.check)(stage, content.to_string()).await
+    }
+}
+
+#[derive(Deserialize)]
+struct JudgeVerdict {
+    violation: bool,
+    #[serde(default)]
+    reason: String,
+}
+
+/// Asks an LLM whether content breaks a natural-language policy.
+///
+/// Failures to reach the judge or parse its verdict count as violations, so
+/// a misbehaving judge never lets content through silently.
+pub struct LLMJudgeGuardrail {
+    name: String,
+    llm: Arc<dyn LLMProvider>,
+    policy: String,
+    action: GuardrailAction,
+    stage: Option<GuardrailStage>,
+}
+
+impl LLMJudgeGuardrail {
+    pub fn new(
+        name: impl Into<String>,
+        llm: Arc<dyn LLMProvider>,
+        policy: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            llm,
+            policy: policy.into(),
+            action: GuardrailAction::default(),
+            stage: None,
+        }
+    }
+
+    pub fn action(mut self, action: GuardrailAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Restrict the guardrail to one stage
+    pub fn stage(mut self, stage: GuardrailStage) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+
+    async fn verdict(&self, stage: GuardrailStage, content: &str) -> Result<JudgeVerdict, String> {
+        let subject = match stage {
+            GuardrailStage::Input => "user request",
+            GuardrailStage::Output => "assistant response",
+        };
+        let messages = [
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: format!(
+                    "You review a {subject} against this policy:\n{}\n\nReply with only a JSON \
+                     object {{\"violation\": true|false, \"reason\": \"...\"}}.",
+                    self.policy
+                ),
+                reasoning: Vec::new(),
+            },
+            ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: content.to_string(),
+                reasoning: Vec::new(),
+            },
+        ];
+        let response = self
+            .llm
+            .chat(&messages, None, None)
+            .await
+            .map_err(|e| format!("judge request failed: {e}"))?;
+        let text = response.text().unwrap_or_default();
+        let json = text
+            .find('{')
+            .zip(text.rfind('}'))
+            .map(|(start, end)| &text[start..=end])
+            .unwrap_or(text.as_str());
+        serde_json::from_str(json).map_err(|_| format!("unreadable judge verdict: {text}"))
+    }
+}
+
+impl Debug for LLMJudgeGuardrail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LLMJudgeGuardrail")
+            .field("name", &self.name)
+            .field("policy", &self.policy)
+            .field("action", &self.action)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Guardrail for LLMJudgeGuardrail {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, stage: GuardrailStage) -> bool {
+        self.stage.is_none_or(|s| s == stage)
+    }
+
+    async fn check(&self, stage: GuardrailStage, content: &str) -> GuardrailOutcome {
+        match self.verdict(stage, content).await {
+            Ok(verdict) if !verdict.violation => GuardrailOutcome::Pass,
+            Ok(verdict) => outcome_for(&self.action, verdict.reason),
+            Err(reason) => outcome_for(&self.action, reason),
+        }
+    }
+}
+
+/// Screens content with a [`ModerationProvider`], such as the OpenAI
+/// moderations endpoint or a local classifier.
+///
+/// By default any flagged category triggers the guardrail. Errors from the
+/// provider are treated as violations.
+pub struct ModerationGuardrail {
+    name: String,
+    provider: Arc<dyn ModerationProvider + Send + Sync>,
+    categories: Vec<String>,
+    threshold: Option<f32>,
+    action: GuardrailAction,
+    stage: Option<GuardrailStage>,
+}
+
+impl ModerationGuardrail {
+    pub fn new(provider: Arc<dyn ModerationProvider + Send + Sync>) -> Self {
+        Self {
+            name: "moderation".to_string(),
+            provider,
+            categories: Vec::new(),
+            threshold: None,
+            action: GuardrailAction::default(),
+            stage: None,
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Only react to these categories; all categories count when empty.
+    pub fn categories<S: Into<String>>(mut self, categories: impl IntoIterator<Item = S>) -> Self {
+        self.categories = categories.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Flag a category once its score reaches `threshold`, instead of relying
+    /// on the provider's own verdict.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    pub fn action(mut self, action: GuardrailAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Restrict the guardrail to one stage
+    pub fn stage(mut self, stage: GuardrailStage) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+
+    fn flagged_categories(&self, result: &ModerationResult) -> Vec<String> {
+        let relevant = |name: &String| self.categories.is_empty() || self.categories.contains(name);
+        match self.threshold {
+            Some(threshold) => result
+                .category_scores
+                .iter()
+                .filter(|(name, score)| relevant(name) && **score >= threshold)
+                .map(|(name, _)| name.clone())
+                .collect(),
+            None if result.flagged => {
+                let hits: Vec<String> = result
+                    .categories
+                    .iter()
+                    .filter(|c| relevant(c))
+                    .cloned()
+                    .collect();
+                if hits.is_empty() && self.categories.is_empty() {
+                    vec!["unspecified".to_string()]
+                } else {
+                    hits
+                }
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Debug for ModerationGuardrail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModerationGuardrail")
+            .field("name", &self.name)
+            .field("categories", &self.categories)
+            .field("threshold", &self.threshold)
+            .field("action", &self.action)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Guardrail for ModerationGuardrail {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, stage: GuardrailStage) -> bool {
+        self.stage.is_none_or(|s| s == stage)
+    }
+
+    async fn check(&self, _stage: GuardrailStage, content: &str) -> GuardrailOutcome {
+        let results = match self.provider.moderate(vec![content.to_string()]).await {
+            Ok(results) => results,
+            Err(e) => return outcome_for(&self.action, format!("moderation failed: {e}")),
+        };
+        let flagged: Vec<String> = results
+            .iter()
+            .flat_map(|result| self.flagged_categories(result))
+            .collect();
+        if flagged.is_empty() {
+            GuardrailOutcome::Pass
+        } else {
+            outcome_for(&self.action, format!("flagged for {}", flagged.join(", ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_regex_guardrail_actions() {
+        let block = RegexGuardrail::new("ssn", r"\d{3}-\d{2}-\d{4}").unwrap();
+        assert_eq!(
+            block.check(GuardrailStage::Input, "hello").await,
+            GuardrailOutcome::Pass
+        );
+        assert!(matches!(
+            block.check(GuardrailStage::Input, "id 123-45-6789").await,
+            GuardrailOutcome::Block { .. }
+        ));
+
+        let redact = block.action(GuardrailAction::Redact("[redacted]".into()));
+        match redact.check(GuardrailStage::Output, "id 123-45-6789").await {
+            GuardrailOutcome::Rewrite { content, .. } => assert_eq!(content, "id [redacted]"),
+            other => panic!("expected rewrite, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_denylist_matches_whole_words_case_insensitively() {
+        let guard = RegexGuardrail::denylist("words", ["secret", "a.b"]).unwrap();
+        assert!(matches!(
+            guard.check(GuardrailStage::Input, "the SECRET plan").await,
+            GuardrailOutcome::Block { .. }
+        ));
+        assert_eq!(
+            guard
+                .check(GuardrailStage::Input, "secretary and axb")
+                .await,
+            GuardrailOutcome::Pass
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_schema_guardrail_only_checks_output() {
+        let guard = JsonSchemaGuardrail::new("shape", json!({"type": "object", "required": ["a"]}))
+            .action(GuardrailAction::Annotate);
+        assert!(!guard.applies_to(GuardrailStage::Input));
+        assert_eq!(
+            guard.check(GuardrailStage::Output, r#"{"a": 1}"#).await,
+            GuardrailOutcome::Pass
+        );
+        assert!(matches!(
+            guard.check(GuardrailStage::Output, "{}").await,
+            GuardrailOutcome::Annotate { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chain_applies_rewrites_in_order_and_stops_on_block() {
+        let chain = GuardrailChain::new()
+            .with(
+                RegexGuardrail::new("digits", r"\d+")
+                    .unwrap()
+                    .action(GuardrailAction::Redact("#".into())),
+            )
+            .with(FnGuardrail::new("note", |_, content: String| async move {
+                GuardrailOutcome::Annotate {
+                    note: format!("saw {content}"),
+                }
+            }))
+            .with(
+                RegexGuardrail::denylist("deny", ["forbidden"])
+                    .unwrap()
+                    .stage(GuardrailStage::Input),
+            );
+
+        let report = chain
+            .run(GuardrailStage::Output, "call 555 forbidden".into())
+            .await
+            .unwrap();
+        assert_eq!(report.content, "call # forbidden");
+        assert_eq!(report.violations.len(), 2);
+        assert_eq!(report.violations[0].kind, ViolationKind::Rewritten);
+        assert_eq!(report.violations[1].reason, "saw call # forbidden");
+
+        let blocked = chain
+            .run(GuardrailStage::Input, "forbidden".into())
+            .await
+            .unwrap_err();
+        assert_eq!(blocked.guardrail, "deny");
+        assert_eq!(blocked.kind, ViolationKind::Blocked);
+    }
+
+    #[tokio::test]
+    async fn test_llm_judge_verdicts() {
+        let llm = Arc::new(ScriptedLLMProvider::new([
+            r#"{"violation": false}"#,
+            r#"Verdict: {"violation": true, "reason": "medical advice"}"#,
+            "no idea",
+        ]));
+        let guard = LLMJudgeGuardrail::new("judge", llm, "No medical advice");
+
+        assert_eq!(
+            guard.check(GuardrailStage::Output, "hi").await,
+            GuardrailOutcome::Pass
+        );
+        assert_eq!(
+            guard.check(GuardrailStage::Output, "take two").await,
+            GuardrailOutcome::Block {
+                reason: "medical advice".into()
+            }
+        );
+        assert!(matches!(
+            guard.check(GuardrailStage::Output, "?").await,
+            GuardrailOutcome::Block { reason } if reason.contains("unreadable")
+        ));
+    }
+
+    struct ScoreModerator;
+
+    #[async_trait]
+    impl ModerationProvider for ScoreModerator {
+        async fn moderate(
+            &self,
+            input: Vec<String>,
+        ) -> Result<Vec<ModerationResult>, autoagents_llm::error::LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    let score = if text.contains("fight") { 0.7 } else { 0.01 };
+                    ModerationResult {
+                        flagged: score > 0.5,
+                        categories: if score > 0.5 {
+                            vec!["violence".to_string()]
+                        } else {
+                            vec![]
+                        },
+                        category_scores: [
+                            ("violence".to_string(), score),
+                            ("hate".to_string(), 0.2),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    }
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_moderation_guardrail_uses_provider_verdict() {
+        let guard = ModerationGuardrail::new(Arc::new(ScoreModerator));
+        assert_eq!(
+            guard.check(GuardrailStage::Input, "hello").await,
+            GuardrailOutcome::Pass
+        );
+        assert_eq!(
+            guard.check(GuardrailStage::Input, "let's fight").await,
+            GuardrailOutcome::Block {
+                reason: "flagged for violence".into()
+            }
+        );
+
+        let hate_only = ModerationGuardrail::new(Arc::new(ScoreModerator)).categories(["hate"]);
+        assert_eq!(
+            hate_only.check(GuardrailStage::Input, "let's fight").await,
+            GuardrailOutcome::Pass
+        );
+    }
+
+    #[tokio::test]
+    async fn test_moderation_guardrail_threshold() {
+        let strict = ModerationGuardrail::new(Arc::new(ScoreModerator))
+            .threshold(0.1)
+            .action(GuardrailAction::Annotate);
+        assert_eq!(
+            strict.check(GuardrailStage::Output, "hello").await,
+            GuardrailOutcome::Annotate {
+                note: "flagged for hate".into()
+            }
+        );
+    }
+}
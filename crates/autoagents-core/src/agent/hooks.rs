@@ -1,6 +1,7 @@
+use crate::agent::prompt::SystemPromptBuilder;
 use crate::agent::task::Task;
 use crate::agent::{AgentDeriveT, Context};
-use crate::tool::ToolCallResult;
+use crate::tool::{ToolCallResult, ToolProgress};
 use async_trait::async_trait;
 use autoagents_llm::ToolCall;
 use serde_json::Value;
@@ -19,6 +20,9 @@ pub trait AgentHooks: AgentDeriveT + Send + Sync {
     async fn on_run_start(&self, _task: &Task, _ctx: &Context) -> HookOutcome {
         HookOutcome::Continue
     }
+    /// Called each time an executor assembles the system prompt, to add or
+    /// change sections for the run
+    async fn on_system_prompt(&self, _prompt: &mut SystemPromptBuilder, _ctx: &Context) {}
     /// Called when the Agent Execution is Completed
     async fn on_run_complete(&self, _task: &Task, _result: &Self::Output, _ctx: &Context) {}
     /// Called when an executor turn is started, useful for multi-turn Executors like ReAct
@@ -31,6 +35,14 @@ pub trait AgentHooks: AgentDeriveT + Send + Sync {
     }
     /// Called before executing the tool
     async fn on_tool_start(&self, _tool_call: &ToolCall, _ctx: &Context) {}
+    /// Called each time a running tool reports progress
+    async fn on_tool_progress(
+        &self,
+        _tool_call: &ToolCall,
+        _progress: &ToolProgress,
+        _ctx: &Context,
+    ) {
+    }
     /// Called post execution of tool with results
     async fn on_tool_result(
         &self,
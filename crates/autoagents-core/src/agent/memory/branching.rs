@@ -0,0 +1,421 @@
+//! Forkable conversation history.
+//!
+//! A [`BranchingMemory`] keeps several versions of a conversation side by
+//! side. Forking copies the current branch up to a given turn into a new
+//! branch; the agent then writes to whichever branch is checked out, so
+//! alternative continuations can be explored and later merged back into
+//! their parent or discarded.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole};
+use autoagents_llm::error::LLMError;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use super::{MemoryProvider, MemoryType};
+
+/// Name of the branch every [`BranchingMemory`] starts on
+pub const MAIN_BRANCH: &str = "main";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum BranchError {
+    #[error("Unknown branch `{0}`")]
+    UnknownBranch(String),
+
+    #[error("Branch `{0}` already exists")]
+    BranchExists(String),
+
+    #[error("Branch has {turns} turn(s), cannot fork at turn {turn}")]
+    TurnOutOfRange { turn: usize, turns: usize },
+
+    #[error("The `main` branch cannot be merged or discarded")]
+    MainBranch,
+
+    #[error("Branch `{0}` has branches forked from it")]
+    HasChildren(String),
+}
+
+impl From<BranchError> for LLMError {
+    fn from(error: BranchError) -> Self {
+        LLMError::InvalidRequest(error.to_string())
+    }
+}
+
+/// Summary of a branch, as listed by [`BranchingMemory::branches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchInfo {
+    pub name: String,
+    /// Branch this one was forked from; `None` for the main branch
+    pub parent: Option<String>,
+    /// Number of messages copied from the parent when forking
+    pub fork_point: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Branch {
+    parent: Option<String>,
+    fork_point: usize,
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug)]
+struct BranchState {
+    current: String,
+    branches: BTreeMap<String, Branch>,
+}
+
+impl BranchState {
+    fn branch(&self, name: &str) -> Result<&Branch, BranchError> {
+        self.branches
+            .get(name)
+            .ok_or_else(|| BranchError::UnknownBranch(name.to_string()))
+    }
+
+    fn current(&self) -> &Branch {
+        &self.branches[&self.current]
+    }
+
+    fn current_mut(&mut self) -> &mut Branch {
+        self.branches
+            .get_mut(&self.current)
+            .expect("current branch exists")
+    }
+
+    /// Copy the first `len` messages of the current branch into a new
+    /// branch `name` and make it current.
+    fn fork(&mut self, name: String, len: usize) -> Result<(), BranchError> {
+        if self.branches.contains_key(&name) {
+            return Err(BranchError::BranchExists(name));
+        }
+        let branch = Branch {
+            parent: Some(self.current.clone()),
+            fork_point: len,
+            messages: self.current().messages[..len].to_vec(),
+        };
+        self.branches.insert(name.clone(), branch);
+        self.current = name;
+        Ok(())
+    }
+
+    /// Remove a branch other than main, checking out its parent if it was
+    /// current.
+    fn remove(&mut self, name: &str) -> Result<Branch, BranchError> {
+        if name == MAIN_BRANCH {
+            return Err(BranchError::MainBranch);
+        }
+        self.branch(name)?;
+        if self
+            .branches
+            .values()
+            .any(|b| b.parent.as_deref() == Some(name))
+        {
+            return Err(BranchError::HasChildren(name.to_string()));
+        }
+        let branch = self.branches.remove(name).expect("branch exists");
+        if self.current == name {
+            self.current = branch.parent.clone().expect("only main has no parent");
+        }
+        Ok(branch)
+    }
+}
+
+/// Conversation memory with named branches.
+///
+/// Clones share the branches, so an application can keep a handle to fork,
+/// switch and merge while the agent owns another. Memory operations act on
+/// the checked-out branch.
+#[derive(Debug, Clone)]
+pub struct BranchingMemory {
+    state: Arc<RwLock<BranchState>>,
+}
+
+impl Default for BranchingMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BranchingMemory {
+    /// Empty memory with only the main branch
+    pub fn new() -> Self {
+        let main = Branch {
+            parent: None,
+            fork_point: 0,
+            messages: Vec::new(),
+        };
+        Self {
+            state: Arc::new(RwLock::new(BranchState {
+                current: MAIN_BRANCH.to_string(),
+                branches: BTreeMap::from([(MAIN_BRANCH.to_string(), main)]),
+            })),
+        }
+    }
+
+    /// Name of the checked-out branch
+    pub fn current_branch(&self) -> String {
+        self.read().current.clone()
+    }
+
+    /// All branches, ordered by name
+    pub fn branches(&self) -> Vec<BranchInfo> {
+        self.read()
+            .branches
+            .iter()
+            .map(|(name, branch)| BranchInfo {
+                name: name.clone(),
+                parent: branch.parent.clone(),
+                fork_point: branch.fork_point,
+                len: branch.messages.len(),
+            })
+            .collect()
+    }
+
+    /// Messages of `branch`, oldest first
+    pub fn messages(&self, branch: &str) -> Result<Vec<ChatMessage>, BranchError> {
+        Ok(self.read().branch(branch)?.messages.clone())
+    }
+
+    /// Number of user turns in the checked-out branch
+    pub fn turns(&self) -> usize {
+        count_turns(&self.read().current().messages)
+    }
+
+    /// Copy the first `turn` turns of the checked-out branch into a new
+    /// branch `name` and check it out.
+    ///
+    /// A turn starts with a user message, so forking at turn 1 keeps the
+    /// first user message and everything answering it. Forking at the
+    /// current number of turns copies the whole branch.
+    pub fn fork(&self, name: impl Into<String>, turn: usize) -> Result<(), BranchError> {
+        let mut state = self.write();
+        let messages = &state.current().messages;
+        let turns = count_turns(messages);
+        if turn > turns {
+            return Err(BranchError::TurnOutOfRange { turn, turns });
+        }
+        let end = turn_end(messages, turn);
+        state.fork(name.into(), end)
+    }
+
+    /// Like [`fork`](Self::fork), but copying exactly the first `len`
+    /// messages.
+    pub fn fork_at_message(&self, name: impl Into<String>, len: usize) -> Result<(), BranchError> {
+        let mut state = self.write();
+        let len = len.min(state.current().messages.len());
+        state.fork(name.into(), len)
+    }
+
+    /// Make `name` the branch the memory reads and writes
+    pub fn checkout(&self, name: &str) -> Result<(), BranchError> {
+        let mut state = self.write();
+        state.branch(name)?;
+        state.current = name.to_string();
+        Ok(())
+    }
+
+    /// Append the messages `name` added since it was forked to its parent
+    /// and delete it. Returns the number of messages merged.
+    ///
+    /// Messages added to the parent after the fork are kept, with the
+    /// branch's messages following them.
+    pub fn merge(&self, name: &str) -> Result<usize, BranchError> {
+        let mut state = self.write();
+        let branch = state.remove(name)?;
+        let parent = branch.parent.expect("only main has no parent");
+        let merged = branch.messages[branch.fork_point..].to_vec();
+        let count = merged.len();
+        state
+            .branches
+            .get_mut(&parent)
+            .expect("parent of an existing branch exists")
+            .messages
+            .extend(merged);
+        Ok(count)
+    }
+
+    /// Delete `name` without merging it
+    pub fn discard(&self, name: &str) -> Result<(), BranchError> {
+        self.write().remove(name).map(|_| ())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BranchState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, BranchState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn count_turns(messages: &[ChatMessage]) -> usize {
+    messages.iter().filter(|m| m.role == ChatRole::User).count()
+}
+
+/// Number of messages making up the first `turn` turns
+fn turn_end(messages: &[ChatMessage], turn: usize) -> usize {
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.role == ChatRole::User)
+        .nth(turn)
+        .map_or(messages.len(), |(index, _)| index)
+}
+
+#[async_trait]
+impl MemoryProvider for BranchingMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.write().current_mut().messages.push(message.clone());
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        _query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let state = self.read();
+        let messages = &state.current().messages;
+        let start = messages
+            .len()
+            .saturating_sub(limit.unwrap_or(messages.len()));
+        Ok(messages[start..].to_vec())
+    }
+
+    /// Clear the checked-out branch only
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        let mut state = self.write();
+        let branch = state.current_mut();
+        branch.messages.clear();
+        branch.fork_point = 0;
+        Ok(())
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Branching
+    }
+
+    fn size(&self) -> usize {
+        self.read().current().messages.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn preload(&mut self, data: Vec<ChatMessage>) -> bool {
+        let mut state = self.write();
+        let branch = state.current_mut();
+        branch.messages = data;
+        branch.fork_point = branch.fork_point.min(branch.messages.len());
+        true
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.read().current().messages.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn conversation(memory: &mut BranchingMemory, turns: &[(&str, &str)]) {
+        for (question, answer) in turns {
+            memory
+                .remember(&ChatMessage::user().content(*question).build())
+                .await
+                .unwrap();
+            memory
+                .remember(&ChatMessage::assistant().content(*answer).build())
+                .await
+                .unwrap();
+        }
+    }
+
+    fn contents(messages: &[ChatMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_fork_runs_alternative_continuation() {
+        let mut memory = BranchingMemory::new();
+        conversation(
+            &mut memory,
+            &[("plan a trip", "where to?"), ("Paris", "ok")],
+        )
+        .await;
+        assert_eq!(memory.turns(), 2);
+
+        let handle = memory.clone();
+        handle.fork("rome", 1).unwrap();
+        assert_eq!(memory.current_branch(), "rome");
+        conversation(&mut memory, &[("Rome", "great choice")]).await;
+
+        let recalled = memory.recall("", None).await.unwrap();
+        assert_eq!(
+            contents(&recalled),
+            vec!["plan a trip", "where to?", "Rome", "great choice"]
+        );
+        assert_eq!(
+            contents(&handle.messages(MAIN_BRANCH).unwrap()),
+            vec!["plan a trip", "where to?", "Paris", "ok"]
+        );
+
+        handle.checkout(MAIN_BRANCH).unwrap();
+        assert_eq!(memory.size(), 4);
+        assert_eq!(memory.recall("", Some(1)).await.unwrap()[0].content, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_merge_and_discard() {
+        let mut memory = BranchingMemory::new();
+        conversation(&mut memory, &[("hi", "hello")]).await;
+
+        memory.fork("draft", 1).unwrap();
+        conversation(&mut memory, &[("more", "sure")]).await;
+        assert_eq!(memory.merge("draft").unwrap(), 2);
+        assert_eq!(memory.current_branch(), MAIN_BRANCH);
+        assert_eq!(
+            contents(&memory.export()),
+            vec!["hi", "hello", "more", "sure"]
+        );
+
+        memory.fork("dead-end", 0).unwrap();
+        conversation(&mut memory, &[("nope", "ok")]).await;
+        memory.discard("dead-end").unwrap();
+        assert_eq!(memory.current_branch(), MAIN_BRANCH);
+        assert_eq!(memory.size(), 4);
+        assert_eq!(memory.branches().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_branch_errors() {
+        let mut memory = BranchingMemory::new();
+        conversation(&mut memory, &[("hi", "hello")]).await;
+
+        assert_eq!(
+            memory.fork("x", 2),
+            Err(BranchError::TurnOutOfRange { turn: 2, turns: 1 })
+        );
+        memory.fork("a", 1).unwrap();
+        assert_eq!(
+            memory.fork("a", 0),
+            Err(BranchError::BranchExists("a".into()))
+        );
+        memory.fork("b", 0).unwrap();
+        assert_eq!(
+            memory.discard("a"),
+            Err(BranchError::HasChildren("a".into()))
+        );
+        assert_eq!(memory.merge(MAIN_BRANCH), Err(BranchError::MainBranch));
+        assert_eq!(
+            memory.checkout("missing"),
+            Err(BranchError::UnknownBranch("missing".into()))
+        );
+
+        let info = &memory.branches()[1];
+        assert_eq!(info.name, "b");
+        assert_eq!(info.parent.as_deref(), Some("a"));
+        assert_eq!(info.fork_point, 0);
+    }
+}
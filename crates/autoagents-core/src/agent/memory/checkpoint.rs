@@ -0,0 +1,171 @@
+//! Checkpoints for undoing memory writes.
+//!
+//! A [`MemoryCheckpoint`] captures a memory's state before a risky step so it can be restored if the step is abandoned. Agents take one at
+//! the start of each run when configured with a [`MemoryRollback`] policy.
+use autoagents_llm::error::LLMError;
+use std::sync::Arc;
+
+use super::{MemoryProvider, MemorySnapshot};
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::Mutex;
+
+#[cfg(target_arch = "wasm32")]
+use futures::lock::Mutex;
+
+/// When an agent restores its memory to the state it had before a run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MemoryRollback {
+    /// Keep whatever the run wrote
+    #[default]
+    Never,
+    /// Roll back when an output guardrail blocks the response
+    OnGuardrailBlock,
+    /// Roll back when the run fails for any reason, including guardrails
+    OnFailure,
+}
+
+/// The saved state of a memory, restorable with
+/// [`rollback`](Self::rollback).
+///
+/// Dropping the checkpoint, or calling [`commit`](Self::commit), keeps the
+/// memory as it is.
+pub struct MemoryCheckpoint {
+    memory: Arc<Mutex<Box<dyn MemoryProvider>>>,
+    saved: Saved,
+}
+
+enum Saved {
+    Fork(Box<dyn MemoryProvider>),
+    Snapshot(MemorySnapshot),
+}
+
+impl MemoryCheckpoint {
+    /// Save the current state of `memory`, as a
+    /// [`fork`](MemoryProvider::fork) when the memory supports it and as a
+    /// [`MemorySnapshot`] otherwise.
+    pub async fn take(memory: Arc<Mutex<Box<dyn MemoryProvider>>>) -> Result<Self, LLMError> {
+        let saved = {
+            let guard = memory.lock().await;
+            match guard.fork() {
+                Some(fork) => Saved::Fork(fork),
+                None => Saved::Snapshot(guard.save().await?),
+            }
+        };
+        Ok(Self { memory, saved })
+    }
+
+    /// The saved snapshot, or `None` when the checkpoint holds a fork
+    pub fn snapshot(&self) -> Option<&MemorySnapshot> {
+        match &self.saved {
+            Saved::Snapshot(snapshot) => Some(snapshot),
+            Saved::Fork(_) => None,
+        }
+    }
+
+    /// Restore the memory to the saved state, discarding everything written
+    /// since.
+    pub async fn rollback(self) -> Result<(), LLMError> {
+        let mut memory = self.memory.lock().await;
+        match self.saved {
+            Saved::Fork(fork) => {
+                *memory = fork;
+                Ok(())
+            }
+            Saved::Snapshot(snapshot) => memory.load(snapshot).await,
+        }
+    }
+
+    /// Keep the memory as it is
+    pub fn commit(self) {}
+}
+
+impl std::fmt::Debug for MemoryCheckpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (kind, messages) = match &self.saved {
+            Saved::Fork(fork) => ("fork", fork.size()),
+            Saved::Snapshot(snapshot) => ("snapshot", snapshot.messages.len()),
+        };
+        f.debug_struct("MemoryCheckpoint")
+            .field("saved", &kind)
+            .field("messages", &messages)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::{SlidingWindowMemory, TokenWindowMemory};
+    use autoagents_llm::chat::ChatMessage;
+    use autoagents_llm::tokenizer::HeuristicTokenCounter;
+
+    fn shared(memory: impl MemoryProvider + 'static) -> Arc<Mutex<Box<dyn MemoryProvider>>> {
+        Arc::new(Mutex::new(Box::new(memory)))
+    }
+
+    #[tokio::test]
+    async fn test_rollback_discards_later_writes() {
+        let memory = shared(SlidingWindowMemory::new(10));
+        memory
+            .lock()
+            .await
+            .remember(&ChatMessage::user().content("kept").build())
+            .await
+            .unwrap();
+
+        let checkpoint = MemoryCheckpoint::take(memory.clone()).await.unwrap();
+        memory
+            .lock()
+            .await
+            .remember(&ChatMessage::assistant().content("rejected").build())
+            .await
+            .unwrap();
+        assert!(checkpoint.snapshot().is_none());
+
+        checkpoint.rollback().await.unwrap();
+        let messages = memory.lock().await.recall("", None).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "kept");
+    }
+
+    #[tokio::test]
+    async fn test_rollback_loads_snapshot_without_fork() {
+        let counter = Arc::new(HeuristicTokenCounter::default());
+        let memory = shared(TokenWindowMemory::new(counter, 1000));
+        memory
+            .lock()
+            .await
+            .remember(&ChatMessage::user().content("kept").build())
+            .await
+            .unwrap();
+
+        let checkpoint = MemoryCheckpoint::take(memory.clone()).await.unwrap();
+        assert_eq!(checkpoint.snapshot().unwrap().messages.len(), 1);
+        memory
+            .lock()
+            .await
+            .remember(&ChatMessage::assistant().content("rejected").build())
+            .await
+            .unwrap();
+
+        checkpoint.rollback().await.unwrap();
+        let messages = memory.lock().await.recall("", None).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "kept");
+    }
+
+    #[tokio::test]
+    async fn test_commit_keeps_writes() {
+        let memory = shared(SlidingWindowMemory::new(10));
+        let checkpoint = MemoryCheckpoint::take(memory.clone()).await.unwrap();
+        memory
+            .lock()
+            .await
+            .remember(&ChatMessage::user().content("new").build())
+            .await
+            .unwrap();
+        checkpoint.commit();
+        assert_eq!(memory.lock().await.size(), 1);
+    }
+}
@@ -0,0 +1,417 @@
+//! Memory of the people, projects and preferences a conversation mentions.
+//!
+//! An LLM extracts entities from each user and assistant message and they are
+//! merged into structured records. Records relevant to the latest request are
+//! added to the agent's system prompt through
+//! [`MemoryProvider::system_context`], while the conversation itself is kept
+//! by an ordinary short-term memory.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::error::LLMError;
+use autoagents_llm::LLMProvider;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::{MemoryProvider, MemoryType};
+
+const DEFAULT_MAX_ENTITIES: usize = 5;
+
+const EXTRACTION_PROMPT: &str = "Extract the entities mentioned in the message below: people, \
+projects, organizations, places and the user's preferences. Reply only with a JSON array of \
+objects with the fields `name`, `kind` (person, project, organization, place, preference or \
+other) and `attributes` (an object of short string facts about the entity). Refer to the person \
+writing as \"user\". Reply with [] if there are none.";
+
+/// A structured record of something the conversation talked about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    pub name: String,
+    pub kind: String,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Entity {
+    fn describe(&self) -> String {
+        let mut line = format!("{} ({})", self.name, self.kind);
+        if !self.attributes.is_empty() {
+            let attributes: Vec<String> = self
+                .attributes
+                .iter()
+                .map(|(key, value)| format!("{key}: {value}"))
+                .collect();
+            line.push_str(" - ");
+            line.push_str(&attributes.join("; "));
+        }
+        line
+    }
+}
+
+#[derive(Deserialize)]
+struct ExtractedEntity {
+    name: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    attributes: BTreeMap<String, Value>,
+}
+
+/// Conversation memory that also maintains an entity store.
+///
+/// Entities are matched by case-insensitive name; later mentions add or
+/// overwrite attributes. The system prompt receives up to `max_entities`
+/// records, those named in the latest user message first, then the most
+/// recently updated ones.
+pub struct EntityMemory {
+    llm: Arc<dyn LLMProvider>,
+    short_term: Box<dyn MemoryProvider>,
+    entities: BTreeMap<String, (Entity, u64)>,
+    updates: u64,
+    last_user_message: Option<String>,
+    max_entities: usize,
+}
+
+impl EntityMemory {
+    /// Extract entities with `llm` and keep messages in `short_term`.
+    pub fn new(llm: Arc<dyn LLMProvider>, short_term: Box<dyn MemoryProvider>) -> Self {
+        Self {
+            llm,
+            short_term,
+            entities: BTreeMap::new(),
+            updates: 0,
+            last_user_message: None,
+            max_entities: DEFAULT_MAX_ENTITIES,
+        }
+    }
+
+    pub fn max_entities(mut self, max_entities: usize) -> Self {
+        self.max_entities = max_entities;
+        self
+    }
+
+    pub fn entity(&self, name: &str) -> Option<&Entity> {
+        self.entities
+            .get(&name.to_lowercase())
+            .map(|(entity, _)| entity)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.values().map(|(entity, _)| entity)
+    }
+
+    /// Add or update an entity directly
+    pub fn upsert(&mut self, entity: Entity) {
+        self.updates += 1;
+        let key = entity.name.to_lowercase();
+        match self.entities.get_mut(&key) {
+            Some((existing, updated)) => {
+                if !entity.kind.is_empty() {
+                    existing.kind = entity.kind;
+                }
+                existing.attributes.extend(entity.attributes);
+                *updated = self.updates;
+            }
+            None => {
+                self.entities.insert(key, (entity, self.updates));
+            }
+        }
+    }
+
+    async fn extract(&self, message: &ChatMessage) -> Result<Vec<Entity>, LLMError> {
+        let request = [
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: EXTRACTION_PROMPT.to_string(),
+                reasoning: Vec::new(),
+            },
+            ChatMessage::user()
+                .content(format!("{}: {}", message.role, message.content))
+                .build(),
+        ];
+        let response = self.llm.chat(&request, None, None).await?;
+        let text = response.text().unwrap_or_default();
+        parse_entities(&text)
+    }
+
+    /// Entities relevant to `query`, best first
+    fn relevant(&self, query: &str) -> Vec<&Entity> {
+        let query = query.to_lowercase();
+        let mut ranked: Vec<(bool, u64, &Entity)> = self
+            .entities
+            .iter()
+            .map(|(name, (entity, updated))| (query.contains(name.as_str()), *updated, entity))
+            .collect();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        ranked
+            .into_iter()
+            .take(self.max_entities)
+            .map(|(_, _, entity)| entity)
+            .collect()
+    }
+}
+
+fn parse_entities(text: &str) -> Result<Vec<Entity>, LLMError> {
+    let invalid = |message: String| LLMError::ResponseFormatError {
+        message,
+        raw_response: text.to_string(),
+    };
+    let (Some(start), Some(end)) = (text.find('['), text.rfind(']')) else {
+        return Err(invalid("expected a JSON array of entities".into()));
+    };
+    if start > end {
+        return Err(invalid("expected a JSON array of entities".into()));
+    }
+    let extracted: Vec<ExtractedEntity> =
+        serde_json::from_str(&text[start..=end]).map_err(|e| invalid(e.to_string()))?;
+    Ok(extracted
+        .into_iter()
+        .filter(|e| !e.name.trim().is_empty())
+        .map(|e| Entity {
+            name: e.name.trim().to_string(),
+            kind: e.kind,
+            attributes: e
+                .attributes
+                .into_iter()
+                .map(|(key, value)| match value {
+                    Value::String(value) => (key, value),
+                    other => (key, other.to_string()),
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+impl Clone for EntityMemory {
+    fn clone(&self) -> Self {
+        Self {
+            llm: self.llm.clone(),
+            short_term: self.short_term.clone_box(),
+            entities: self.entities.clone(),
+            updates: self.updates,
+            last_user_message: self.last_user_message.clone(),
+            max_entities: self.max_entities,
+        }
+    }
+}
+
+impl std::fmt::Debug for EntityMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EntityMemory")
+            .field("short_term", &self.short_term.memory_type())
+            .field("entities", &self.entities.len())
+            .field("max_entities", &self.max_entities)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for EntityMemory {
+    /// Store the message and merge the entities it mentions.
+    ///
+    /// The message is kept even if extraction fails; the error is returned.
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.short_term.remember(message).await?;
+
+        let conversational = matches!(message.role, ChatRole::User | ChatRole::Assistant)
+            && matches!(message.message_type, MessageType::Text)
+            && !message.content.trim().is_empty();
+        if !conversational {
+            return Ok(());
+        }
+        if message.role == ChatRole::User {
+            self.last_user_message = Some(message.content.clone());
+        }
+        for entity in self.extract(message).await? {
+            self.upsert(entity);
+        }
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.short_term.recall(query, limit).await
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.entities.clear();
+        self.last_user_message = None;
+        self.short_term.clear().await
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Entity
+    }
+
+    fn size(&self) -> usize {
+        self.short_term.size()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn fork(&self) -> Option<Box<dyn MemoryProvider>> {
+        Some(Box::new(Self {
+            llm: self.llm.clone(),
+            short_term: self.short_term.fork()?,
+            entities: self.entities.clone(),
+            updates: self.updates,
+            last_user_message: self.last_user_message.clone(),
+            max_entities: self.max_entities,
+        }))
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.short_term.export()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.short_term.search(query, limit).await
+    }
+
+    async fn system_context(&self, query: &str) -> Result<Option<String>, LLMError> {
+        let query = match query {
+            "" => self.last_user_message.as_deref().unwrap_or_default(),
+            query => query,
+        };
+        let entities = self.relevant(query);
+        if entities.is_empty() {
+            return Ok(None);
+        }
+        let mut context = String::from("Known entities:");
+        for entity in entities {
+            context.push_str("\n- ");
+            context.push_str(&entity.describe());
+        }
+        Ok(Some(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::MemoryCheckpoint;
+    use crate::agent::memory::SlidingWindowMemory;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use tokio::sync::Mutex;
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    fn memory(responses: &[&str]) -> EntityMemory {
+        let llm = Arc::new(ScriptedLLMProvider::new(responses.iter().copied()));
+        EntityMemory::new(llm, Box::new(SlidingWindowMemory::new(10)))
+    }
+
+    #[tokio::test]
+    async fn test_entities_are_merged_by_name() {
+        let mut memory = memory(&[
+            r#"[{"name": "Ada", "kind": "person", "attributes": {"role": "engineer"}}]"#,
+            r#"```json
+            [{"name": "ada", "attributes": {"team": "infra", "years": 4}}]
+            ```"#,
+        ]);
+        memory.remember(&user("Ada is our engineer")).await.unwrap();
+        memory
+            .remember(&user("ada joined infra 4 years ago"))
+            .await
+            .unwrap();
+
+        let ada = memory.entity("ADA").unwrap();
+        assert_eq!(ada.kind, "person");
+        assert_eq!(ada.attributes["role"], "engineer");
+        assert_eq!(ada.attributes["team"], "infra");
+        assert_eq!(ada.attributes["years"], "4");
+        assert_eq!(memory.size(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mentioned_entities_come_first() {
+        let mut memory = memory(&["[]"]).max_entities(2);
+        for (name, kind) in [("Apollo", "project"), ("Ada", "person"), ("user", "person")] {
+            memory.upsert(Entity {
+                name: name.into(),
+                kind: kind.into(),
+                attributes: BTreeMap::new(),
+            });
+        }
+        memory
+            .remember(&user("How is apollo going?"))
+            .await
+            .unwrap();
+
+        let context = memory.system_context("").await.unwrap().unwrap();
+        assert_eq!(
+            context,
+            "Known entities:\n- Apollo (project)\n- user (person)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_extraction_keeps_message() {
+        let mut memory = memory(&["no entities here"]);
+        let err = memory.remember(&user("hello")).await.unwrap_err();
+        assert!(matches!(err, LLMError::ResponseFormatError { .. }));
+        assert_eq!(memory.size(), 1);
+        assert!(memory.system_context("").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_clear_forgets_entities() {
+        let mut memory = memory(&[r#"[{"name": "user", "kind": "preference",
+            "attributes": {"language": "Rust"}}]"#]);
+        memory.remember(&user("I prefer Rust")).await.unwrap();
+        assert!(memory
+            .system_context("")
+            .await
+            .unwrap()
+            .unwrap()
+            .contains("language: Rust"));
+
+        memory.clear().await.unwrap();
+        assert_eq!(memory.entities().count(), 0);
+        assert_eq!(memory.memory_type(), MemoryType::Entity);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_restores_entities_without_extraction() {
+        let llm = Arc::new(ScriptedLLMProvider::new([
+            r#"[{"name": "Ada", "kind": "person", "attributes": {"role": "engineer"}}]"#,
+            r#"[{"name": "Ada", "attributes": {"role": "manager"}}]"#,
+        ]));
+        let memory: Arc<Mutex<Box<dyn MemoryProvider>>> = Arc::new(Mutex::new(Box::new(
+            EntityMemory::new(llm.clone(), Box::new(SlidingWindowMemory::new(10))),
+        )));
+        memory
+            .lock()
+            .await
+            .remember(&user("Ada is our engineer"))
+            .await
+            .unwrap();
+
+        let checkpoint = MemoryCheckpoint::take(memory.clone()).await.unwrap();
+        memory
+            .lock()
+            .await
+            .remember(&user("Ada is a manager now"))
+            .await
+            .unwrap();
+        checkpoint.rollback().await.unwrap();
+
+        let memory = memory.lock().await;
+        assert_eq!(memory.size(), 1);
+        let context = memory.system_context("").await.unwrap().unwrap();
+        assert_eq!(context, "Known entities:\n- Ada (person) - role: engineer");
+        assert_eq!(llm.calls(), 2);
+    }
+}
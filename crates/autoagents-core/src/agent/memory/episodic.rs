@@ -0,0 +1,611 @@
+//! Time-aware conversation memory.
+//!
+//! An [`EpisodicMemory`] stores every message with the wall-clock time it was
+//! remembered. Retrieval weighs keyword relevance by a [`RecencyDecay`], and
+//! relative time expressions in the query ("yesterday", "last week",
+//! "3 days ago") restrict it to that period, so questions like "what did we
+//! discuss yesterday?" find the right messages.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole};
+use autoagents_llm::error::LLMError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime};
+
+use super::{MemoryProvider, MemoryType};
+
+const DEFAULT_TOP_K: usize = 5;
+const DEFAULT_RECENT: usize = 4;
+const DEFAULT_HALF_LIFE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+/// Words ignored when matching queries against messages
+const STOPWORDS: &[&str] = &[
+    "about",
+    "and",
+    "are",
+    "can",
+    "did",
+    "discuss",
+    "discussed",
+    "for",
+    "from",
+    "how",
+    "talk",
+    "talked",
+    "tell",
+    "that",
+    "the",
+    "this",
+    "was",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "who",
+    "why",
+    "with",
+    "you",
+    "your",
+];
+
+static TIME_EXPRESSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?ix)\b(?:
+            (?P<today>today)
+          | (?P<yesterday>yesterday)
+          | (?P<this_week>this\s+week)
+          | (?P<last_week>last\s+week)
+          | (?P<ago_n>\d+)\s+(?P<ago_unit>hour|day|week)s?\s+ago
+          | (?:last|past)\s+(?:(?P<past_n>\d+)\s+)?(?P<past_unit>hour|day|week)s?
+        )\b",
+    )
+    .expect("time expression pattern is valid")
+});
+
+type Clock = dyn Fn() -> SystemTime + Send + Sync;
+
+/// A message and when it was remembered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    pub message: ChatMessage,
+    pub timestamp: SystemTime,
+}
+
+/// How the weight of a message falls with its age.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecencyDecay {
+    /// Every message weighs the same
+    None,
+    /// The weight halves every `half_life`
+    Exponential { half_life: Duration },
+    /// The weight falls linearly, reaching zero at `horizon`
+    Linear { horizon: Duration },
+}
+
+impl Default for RecencyDecay {
+    fn default() -> Self {
+        RecencyDecay::Exponential {
+            half_life: DEFAULT_HALF_LIFE,
+        }
+    }
+}
+
+impl RecencyDecay {
+    /// Weight between 0 and 1 of a message `age` old
+    pub fn weight(&self, age: Duration) -> f32 {
+        match self {
+            RecencyDecay::None => 1.0,
+            RecencyDecay::Exponential { half_life } if half_life.is_zero() => 0.0,
+            RecencyDecay::Exponential { half_life } => {
+                0.5f64.powf(age.as_secs_f64() / half_life.as_secs_f64()) as f32
+            }
+            RecencyDecay::Linear { horizon } if horizon.is_zero() => 0.0,
+            RecencyDecay::Linear { horizon } => {
+                (1.0 - age.as_secs_f64() / horizon.as_secs_f64()).max(0.0) as f32
+            }
+        }
+    }
+}
+
+/// A period of time named in a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: SystemTime,
+    /// Exclusive end
+    pub end: SystemTime,
+}
+
+impl TimeWindow {
+    /// Find a relative time expression in `query`, evaluated at `now` in a
+    /// time zone `utc_offset` seconds east of UTC.
+    ///
+    /// Recognizes "today", "yesterday", "this week", "last week" (calendar
+    /// weeks starting on Monday), "N hours/days/weeks ago" and
+    /// "last/past [N] hours/days/weeks".
+    pub fn parse(query: &str, now: SystemTime, utc_offset: i64) -> Option<Self> {
+        Self::find(query, now, utc_offset).map(|(window, _)| window)
+    }
+
+    pub fn contains(&self, time: SystemTime) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    /// The window and the byte range of the expression it was parsed from
+    fn find(
+        query: &str,
+        now: SystemTime,
+        utc_offset: i64,
+    ) -> Option<(Self, std::ops::Range<usize>)> {
+        let captures = TIME_EXPRESSION.captures(query)?;
+        let now_secs = unix_seconds(now);
+        let local = now_secs + utc_offset;
+        let today = local - local.rem_euclid(DAY) - utc_offset;
+        // 1970-01-01 was a Thursday
+        let week = today - (local.div_euclid(DAY) + 3).rem_euclid(7) * DAY;
+        let unit = |name: &str| match name.to_lowercase().as_str() {
+            "hour" => HOUR,
+            "day" => DAY,
+            _ => WEEK,
+        };
+        let count = |name: &str| {
+            captures
+                .name(name)
+                .and_then(|n| n.as_str().parse::<i64>().ok())
+                .unwrap_or(1)
+        };
+
+        let (start, end) = if captures.name("today").is_some() {
+            (today, now_secs + 1)
+        } else if captures.name("yesterday").is_some() {
+            (today - DAY, today)
+        } else if captures.name("this_week").is_some() {
+            (week, now_secs + 1)
+        } else if captures.name("last_week").is_some() {
+            (week - WEEK, week)
+        } else if let Some(ago_unit) = captures.name("ago_unit") {
+            let unit = unit(ago_unit.as_str());
+            let n = count("ago_n");
+            if unit == HOUR {
+                (now_secs - (n + 1) * HOUR, now_secs - (n - 1) * HOUR)
+            } else {
+                let period = if unit == DAY { today } else { week };
+                (period - n * unit, period - (n - 1) * unit)
+            }
+        } else {
+            let unit = unit(captures.name("past_unit")?.as_str());
+            (now_secs - count("past_n") * unit, now_secs + 1)
+        };
+        let expression = captures.get(0)?.range();
+        Some((
+            Self {
+                start: from_unix_seconds(start),
+                end: from_unix_seconds(end),
+            },
+            expression,
+        ))
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+}
+
+fn from_unix_seconds(seconds: i64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64)
+}
+
+/// Lowercase content words of `text`
+fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Fraction of `query` terms that occur in `message`
+fn relevance(query: &HashSet<String>, message: &ChatMessage) -> f32 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    let words = terms(&message.content);
+    query.iter().filter(|term| words.contains(*term)).count() as f32 / query.len() as f32
+}
+
+/// Conversation memory that remembers when each message was said.
+///
+/// Recall returns the latest messages plus the best matches for the query
+/// (the latest user message when empty), in chronological order. A match
+/// scores its keyword relevance times its recency weight; when the query
+/// names a period, only messages from that period are considered and they
+/// need not share any keyword.
+#[derive(Clone)]
+pub struct EpisodicMemory {
+    episodes: Vec<Episode>,
+    decay: RecencyDecay,
+    top_k: usize,
+    recent: usize,
+    utc_offset: i64,
+    clock: Arc<Clock>,
+}
+
+impl Default for EpisodicMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EpisodicMemory {
+    pub fn new() -> Self {
+        Self {
+            episodes: Vec::new(),
+            decay: RecencyDecay::default(),
+            top_k: DEFAULT_TOP_K,
+            recent: DEFAULT_RECENT,
+            utc_offset: 0,
+            clock: Arc::new(SystemTime::now),
+        }
+    }
+
+    /// Defaults to halving every 7 days
+    pub fn with_decay(mut self, decay: RecencyDecay) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Number of matches to retrieve when recall gives no limit
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Number of latest messages always included regardless of relevance
+    pub fn recent(mut self, recent: usize) -> Self {
+        self.recent = recent;
+        self
+    }
+
+    /// Interpret "today", "yesterday" and weeks in a time zone `seconds`
+    /// east of UTC. Defaults to UTC.
+    pub fn with_utc_offset(mut self, seconds: i64) -> Self {
+        self.utc_offset = seconds;
+        self
+    }
+
+    /// Source of the current time, e.g. a fixed clock in tests
+    pub fn with_clock<F>(mut self, clock: F) -> Self
+    where
+        F: Fn() -> SystemTime + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Start from episodes stored earlier
+    pub fn with_episodes(mut self, mut episodes: Vec<Episode>) -> Self {
+        episodes.sort_by_key(|e| e.timestamp);
+        self.episodes = episodes;
+        self
+    }
+
+    pub fn episodes(&self) -> &[Episode] {
+        &self.episodes
+    }
+
+    /// Store `message` as remembered at `timestamp`, e.g. when importing
+    /// history
+    pub fn remember_at(&mut self, message: ChatMessage, timestamp: SystemTime) {
+        let position = self.episodes.partition_point(|e| e.timestamp <= timestamp);
+        self.episodes
+            .insert(position, Episode { message, timestamp });
+    }
+
+    /// Episodes from `window`, oldest first
+    pub fn between(&self, window: TimeWindow) -> Vec<&Episode> {
+        self.episodes
+            .iter()
+            .filter(|e| window.contains(e.timestamp))
+            .collect()
+    }
+
+    /// Up to `limit` episodes matching `query`, best first, with their
+    /// scores.
+    pub fn retrieve(&self, query: &str, limit: usize) -> Vec<(&Episode, f32)> {
+        self.ranked(query)
+            .into_iter()
+            .take(limit)
+            .map(|(index, score)| (&self.episodes[index], score))
+            .collect()
+    }
+
+    /// Indices and scores of matching episodes, best first
+    fn ranked(&self, query: &str) -> Vec<(usize, f32)> {
+        let now = (self.clock)();
+        let (window, query_terms) = match TimeWindow::find(query, now, self.utc_offset) {
+            Some((window, expression)) => {
+                let mut rest = query.to_string();
+                rest.replace_range(expression, " ");
+                (Some(window), terms(&rest))
+            }
+            None => (None, terms(query)),
+        };
+
+        let mut ranked: Vec<(usize, f32)> = self
+            .episodes
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| window.is_none_or(|w| w.contains(e.timestamp)))
+            .filter_map(|(index, episode)| {
+                let relevance = relevance(&query_terms, &episode.message);
+                // Inside a named period every message is a candidate
+                let relevance = if window.is_some() {
+                    1.0 + relevance
+                } else {
+                    relevance
+                };
+                let age = now.duration_since(episode.timestamp).unwrap_or_default();
+                let score = relevance * self.decay.weight(age);
+                (relevance > 0.0).then_some((index, score))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.cmp(&a.0)));
+        ranked
+    }
+
+    fn latest_user_message(&self) -> Option<&str> {
+        self.episodes
+            .iter()
+            .rev()
+            .find(|e| e.message.role == ChatRole::User && !e.message.content.is_empty())
+            .map(|e| e.message.content.as_str())
+    }
+}
+
+impl std::fmt::Debug for EpisodicMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EpisodicMemory")
+            .field("episodes", &self.episodes.len())
+            .field("decay", &self.decay)
+            .field("top_k", &self.top_k)
+            .field("recent", &self.recent)
+            .field("utc_offset", &self.utc_offset)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for EpisodicMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        let now = (self.clock)();
+        self.remember_at(message.clone(), now);
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let recent_start = self.episodes.len().saturating_sub(self.recent);
+        let mut selected: BTreeSet<usize> = (recent_start..self.episodes.len()).collect();
+
+        let query = match query {
+            "" => self.latest_user_message(),
+            query => Some(query),
+        };
+        if let Some(query) = query {
+            let top_k = limit.unwrap_or(self.top_k);
+            selected.extend(self.ranked(query).into_iter().take(top_k).map(|(i, _)| i));
+        }
+
+        Ok(selected
+            .into_iter()
+            .map(|index| self.episodes[index].message.clone())
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.episodes.clear();
+        Ok(())
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Episodic
+    }
+
+    fn size(&self) -> usize {
+        self.episodes.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn fork(&self) -> Option<Box<dyn MemoryProvider>> {
+        Some(Box::new(self.clone()))
+    }
+
+    /// Preloaded messages are timestamped now.
+    fn preload(&mut self, data: Vec<ChatMessage>) -> bool {
+        let now = (self.clock)();
+        self.episodes = data
+            .into_iter()
+            .map(|message| Episode {
+                message,
+                timestamp: now,
+            })
+            .collect();
+        true
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.episodes.iter().map(|e| e.message.clone()).collect()
+    }
+
+    /// Messages ranked by relevance and recency, best first.
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        Ok(self
+            .retrieve(query, limit.unwrap_or(usize::MAX))
+            .into_iter()
+            .map(|(episode, _)| episode.message.clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::MemoryCheckpoint;
+    use tokio::sync::Mutex;
+
+    /// Wednesday 2024-05-15 12:00 UTC
+    const NOW: u64 = 1_715_774_400;
+
+    fn at(seconds_before_now: i64) -> SystemTime {
+        from_unix_seconds(NOW as i64 - seconds_before_now)
+    }
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    fn memory() -> EpisodicMemory {
+        let mut memory = EpisodicMemory::new()
+            .recent(0)
+            .with_clock(|| from_unix_seconds(NOW as i64));
+        memory.remember_at(user("the deploy pipeline is flaky"), at(10 * DAY));
+        memory.remember_at(user("we picked Postgres for the store"), at(DAY));
+        memory.remember_at(user("deploy went fine after the fix"), at(DAY + HOUR));
+        memory.remember_at(user("lunch plans"), at(HOUR));
+        memory
+    }
+
+    fn contents(messages: &[ChatMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_time_windows() {
+        let now = from_unix_seconds(NOW as i64);
+        let today = NOW as i64 - 12 * HOUR;
+        let window = |query| TimeWindow::parse(query, now, 0).unwrap();
+
+        assert_eq!(
+            window("what did we discuss yesterday?").start,
+            from_unix_seconds(today - DAY)
+        );
+        assert_eq!(window("Yesterday").end, from_unix_seconds(today));
+        assert_eq!(window("today").start, from_unix_seconds(today));
+        // Monday 2024-05-13
+        assert_eq!(
+            window("this week").start,
+            from_unix_seconds(today - 2 * DAY)
+        );
+        assert_eq!(
+            window("last week").start,
+            from_unix_seconds(today - 9 * DAY)
+        );
+        assert_eq!(
+            window("3 days ago").start,
+            from_unix_seconds(today - 3 * DAY)
+        );
+        assert_eq!(
+            window("past 2 hours").start,
+            from_unix_seconds(NOW as i64 - 2 * HOUR)
+        );
+        assert_eq!(
+            window("in the last day").start,
+            from_unix_seconds(NOW as i64 - DAY)
+        );
+        assert!(TimeWindow::parse("the deploy", now, 0).is_none());
+
+        // 22:00 UTC is already 01:00 the next day in UTC+3
+        let late = from_unix_seconds(NOW as i64 + 10 * HOUR);
+        let window = TimeWindow::parse("today", late, 3 * HOUR).unwrap();
+        assert_eq!(window.start, from_unix_seconds(today + DAY - 3 * HOUR));
+    }
+
+    #[test]
+    fn test_decay_weights() {
+        let day = Duration::from_secs(DAY as u64);
+        let half_life = RecencyDecay::Exponential { half_life: day };
+        assert_eq!(half_life.weight(Duration::ZERO), 1.0);
+        assert!((half_life.weight(2 * day) - 0.25).abs() < 1e-6);
+        let linear = RecencyDecay::Linear { horizon: 2 * day };
+        assert_eq!(linear.weight(day), 0.5);
+        assert_eq!(linear.weight(3 * day), 0.0);
+        assert_eq!(RecencyDecay::None.weight(100 * day), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_recall_restricts_to_named_period() {
+        let memory = memory();
+        let recalled = memory
+            .recall("what did we discuss yesterday?", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            contents(&recalled),
+            vec![
+                "deploy went fine after the fix",
+                "we picked Postgres for the store"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_prefers_recent_matches() {
+        let memory = memory();
+        let found = memory.search("deploy", None).await.unwrap();
+        assert_eq!(
+            contents(&found),
+            vec![
+                "deploy went fine after the fix",
+                "the deploy pipeline is flaky"
+            ]
+        );
+
+        let flat = memory.clone().with_decay(RecencyDecay::None);
+        let scores: Vec<f32> = flat.retrieve("deploy", 2).iter().map(|(_, s)| *s).collect();
+        assert_eq!(scores, vec![1.0, 1.0]);
+        assert_eq!(
+            memory
+                .between(TimeWindow::parse("today", at(0), 0).unwrap())
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rollback_keeps_timestamps() {
+        let query = "what did we discuss yesterday?";
+        let before = memory().recall(query, None).await.unwrap();
+        let memory: Arc<Mutex<Box<dyn MemoryProvider>>> = Arc::new(Mutex::new(Box::new(memory())));
+
+        let checkpoint = MemoryCheckpoint::take(memory.clone()).await.unwrap();
+        memory
+            .lock()
+            .await
+            .remember(&user("rolled back"))
+            .await
+            .unwrap();
+        checkpoint.rollback().await.unwrap();
+
+        let memory = memory.lock().await;
+        assert_eq!(memory.size(), 4);
+        let after = memory.recall(query, None).await.unwrap();
+        assert_eq!(contents(&after), contents(&before));
+    }
+}
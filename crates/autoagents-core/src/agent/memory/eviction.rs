@@ -0,0 +1,183 @@
+//! Eviction policies for [`SlidingWindowMemory`](super::SlidingWindowMemory).
+//!
+//! When the window is full, the memory asks its [`EvictionPolicy`] which
+//! unpinned message to drop. The default, [`FifoEviction`], drops the oldest
+//! one, which is the classic sliding window.
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A message that may be evicted.
+#[derive(Debug, Clone, Copy)]
+pub struct EvictionCandidate<'a> {
+    pub message: &'a ChatMessage,
+    /// Logical time the message was stored
+    pub inserted_at: u64,
+    /// Logical time the message was stored or last recalled
+    pub last_used: u64,
+}
+
+/// Chooses which message leaves a full memory.
+pub trait EvictionPolicy: Send + Sync + Debug {
+    fn name(&self) -> &str;
+
+    /// Index into `candidates` of the message to evict. Candidates are the
+    /// unpinned messages, oldest first, and never empty.
+    fn select(&self, candidates: &[EvictionCandidate<'_>]) -> usize;
+}
+
+/// Evicts the oldest message.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FifoEviction;
+
+impl EvictionPolicy for FifoEviction {
+    fn name(&self) -> &str {
+        "fifo"
+    }
+
+    fn select(&self, _candidates: &[EvictionCandidate<'_>]) -> usize {
+        0
+    }
+}
+
+/// Evicts the message that was least recently stored or recalled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LruEviction;
+
+impl EvictionPolicy for LruEviction {
+    fn name(&self) -> &str {
+        "lru"
+    }
+
+    fn select(&self, candidates: &[EvictionCandidate<'_>]) -> usize {
+        candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, c)| c.last_used)
+            .map_or(0, |(index, _)| index)
+    }
+}
+
+type ImportanceFn = dyn Fn(&ChatMessage) -> f32 + Send + Sync;
+
+/// Evicts the least important message, the oldest one among equals.
+///
+/// Importance comes from a scoring function; the default ranks system
+/// messages highest and tool traffic lowest.
+#[derive(Clone)]
+pub struct ImportanceEviction {
+    score: Arc<ImportanceFn>,
+}
+
+impl ImportanceEviction {
+    pub fn new<F>(score: F) -> Self
+    where
+        F: Fn(&ChatMessage) -> f32 + Send + Sync + 'static,
+    {
+        Self {
+            score: Arc::new(score),
+        }
+    }
+}
+
+impl Default for ImportanceEviction {
+    fn default() -> Self {
+        Self::new(default_importance)
+    }
+}
+
+fn default_importance(message: &ChatMessage) -> f32 {
+    match (&message.role, &message.message_type) {
+        (ChatRole::System, _) => 1.0,
+        (_, MessageType::ToolUse(_) | MessageType::ToolResult(_)) => 0.2,
+        (ChatRole::User, _) => 0.6,
+        _ => 0.4,
+    }
+}
+
+impl Debug for ImportanceEviction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImportanceEviction").finish_non_exhaustive()
+    }
+}
+
+impl EvictionPolicy for ImportanceEviction {
+    fn name(&self) -> &str {
+        "importance"
+    }
+
+    fn select(&self, candidates: &[EvictionCandidate<'_>]) -> usize {
+        candidates
+            .iter()
+            .map(|c| (self.score)(c.message))
+            .enumerate()
+            .fold((0, f32::INFINITY), |lowest, (index, score)| {
+                if score < lowest.1 {
+                    (index, score)
+                } else {
+                    lowest
+                }
+            })
+            .0
+    }
+}
+
+/// Monotonic logical clock whose value is copied, not shared, on clone.
+#[derive(Debug, Default)]
+pub(super) struct Tick(AtomicU64);
+
+impl Tick {
+    pub(super) fn new(value: u64) -> Self {
+        Self(AtomicU64::new(value))
+    }
+
+    pub(super) fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set(&self, value: u64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    /// Advance the clock and return the new time
+    pub(super) fn advance(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+impl Clone for Tick {
+    fn clone(&self) -> Self {
+        Self::new(self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies_pick_expected_victim() {
+        let messages = [
+            ChatMessage::user().content("question").build(),
+            ChatMessage::assistant().content("answer").build(),
+            ChatMessage::user().content("follow-up").build(),
+        ];
+        let candidates: Vec<EvictionCandidate<'_>> = messages
+            .iter()
+            .zip([7, 3, 5])
+            .enumerate()
+            .map(|(i, (message, last_used))| EvictionCandidate {
+                message,
+                inserted_at: i as u64,
+                last_used,
+            })
+            .collect();
+
+        assert_eq!(FifoEviction.select(&candidates), 0);
+        assert_eq!(LruEviction.select(&candidates), 1);
+        assert_eq!(ImportanceEviction::default().select(&candidates), 1);
+        let by_length = ImportanceEviction::new(|m| m.content.len() as f32);
+        assert_eq!(by_length.select(&candidates), 1);
+    }
+}
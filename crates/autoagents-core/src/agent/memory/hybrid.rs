@@ -0,0 +1,651 @@
+//! Short-term conversation memory combined with a long-term fact store.
+//!
+//! Every message goes to the short-term memory as usual. A [`FactExtractor`]
+//! decides what is worth keeping beyond the window and promotes it to a
+//! [`LongTermStore`]. On recall, the facts relevant to the current query are
+//! retrieved and placed in front of the recent messages.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::embedding::EmbeddingProvider;
+use autoagents_llm::error::LLMError;
+use autoagents_llm::LLMProvider;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use super::{
+    InMemoryVectorIndex, MemoryProvider, MemoryType, MessageCondition, MessageEvent, VectorIndex,
+};
+
+const DEFAULT_MAX_FACTS: usize = 5;
+
+const DEFAULT_EXTRACTION_PROMPT: &str = "Extract facts from the message that will still matter \
+in later conversations: names, preferences, decisions, commitments and stable attributes of the \
+user or their work. Write one fact per line as `key: value` with a short snake_case key. Reply \
+with NONE if there is nothing worth remembering.";
+
+/// Long-term storage of facts, searchable by relevance.
+#[async_trait]
+pub trait LongTermStore: Send + Sync {
+    async fn store(&mut self, fact: &str) -> Result<(), LLMError>;
+
+    /// Up to `limit` facts relevant to `query` with a score in `0.0..=1.0`,
+    /// best first.
+    async fn retrieve(&self, query: &str, limit: usize) -> Result<Vec<(String, f32)>, LLMError>;
+
+    async fn clear(&mut self) -> Result<(), LLMError>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clone_box(&self) -> Box<dyn LongTermStore>;
+
+    /// An independent copy of the store, or `None` (the default) when clones
+    /// share their facts.
+    fn fork(&self) -> Option<Box<dyn LongTermStore>> {
+        None
+    }
+}
+
+/// Facts keyed by name, where a newer fact replaces an older one with the
+/// same key.
+///
+/// Facts of the form `key: value` are keyed by `key`; others by their full
+/// text. Relevance is the share of query words found in the fact.
+#[derive(Debug, Clone, Default)]
+pub struct KeyValueFactStore {
+    facts: BTreeMap<String, String>,
+}
+
+impl KeyValueFactStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.facts.get(key).map(String::as_str)
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[async_trait]
+impl LongTermStore for KeyValueFactStore {
+    async fn store(&mut self, fact: &str) -> Result<(), LLMError> {
+        let (key, value) = match fact.split_once(':') {
+            Some((key, value)) if !key.trim().is_empty() => (key.trim(), value.trim()),
+            _ => (fact.trim(), ""),
+        };
+        self.facts.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    async fn retrieve(&self, query: &str, limit: usize) -> Result<Vec<(String, f32)>, LLMError> {
+        let query = words(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut scored: Vec<(String, f32)> = self
+            .facts
+            .iter()
+            .map(|(key, value)| {
+                let fact = if value.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{key}: {value}")
+                };
+                let fact_words = words(&fact.replace('_', " "));
+                let hits = query.iter().filter(|w| fact_words.contains(*w)).count();
+                (fact, hits as f32 / query.len() as f32)
+            })
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.facts.clear();
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.facts.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn LongTermStore> {
+        Box::new(self.clone())
+    }
+
+    fn fork(&self) -> Option<Box<dyn LongTermStore>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Facts retrieved by embedding similarity.
+pub struct VectorFactStore {
+    embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    index: Box<dyn VectorIndex>,
+    facts: Vec<String>,
+}
+
+impl VectorFactStore {
+    pub fn new(embedder: Arc<dyn EmbeddingProvider + Send + Sync>) -> Self {
+        Self {
+            embedder,
+            index: Box::new(InMemoryVectorIndex::new()),
+            facts: Vec::new(),
+        }
+    }
+
+    pub fn with_index(mut self, index: Box<dyn VectorIndex>) -> Self {
+        self.index = index;
+        self
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, LLMError> {
+        self.embedder
+            .embed(vec![text.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("Embedder returned no embedding".into()))
+    }
+}
+
+impl Clone for VectorFactStore {
+    fn clone(&self) -> Self {
+        Self {
+            embedder: self.embedder.clone(),
+            index: self.index.clone_box(),
+            facts: self.facts.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for VectorFactStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VectorFactStore")
+            .field("facts", &self.facts.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl LongTermStore for VectorFactStore {
+    async fn store(&mut self, fact: &str) -> Result<(), LLMError> {
+        let embedding = self.embed(fact).await?;
+        self.index.insert(self.facts.len(), embedding).await?;
+        self.facts.push(fact.to_string());
+        Ok(())
+    }
+
+    async fn retrieve(&self, query: &str, limit: usize) -> Result<Vec<(String, f32)>, LLMError> {
+        if self.facts.is_empty() || query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let embedding = self.embed(query).await?;
+        Ok(self
+            .index
+            .search(&embedding, limit)
+            .await?
+            .into_iter()
+            .filter_map(|(id, score)| Some((self.facts.get(id)?.clone(), score.max(0.0))))
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.facts.clear();
+        self.index.clear().await
+    }
+
+    fn len(&self) -> usize {
+        self.facts.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn LongTermStore> {
+        Box::new(self.clone())
+    }
+
+    fn fork(&self) -> Option<Box<dyn LongTermStore>> {
+        Some(Box::new(Self {
+            embedder: self.embedder.clone(),
+            index: self.index.fork()?,
+            facts: self.facts.clone(),
+        }))
+    }
+}
+
+/// Decides which facts in a message deserve long-term storage.
+#[async_trait]
+pub trait FactExtractor: Send + Sync {
+    async fn extract(&self, message: &ChatMessage) -> Result<Vec<String>, LLMError>;
+
+    fn clone_box(&self) -> Box<dyn FactExtractor>;
+}
+
+/// Promotes whole messages that match a [`MessageCondition`].
+#[derive(Clone)]
+pub struct ConditionFactExtractor {
+    condition: MessageCondition,
+}
+
+impl ConditionFactExtractor {
+    pub fn new(condition: MessageCondition) -> Self {
+        Self { condition }
+    }
+}
+
+#[async_trait]
+impl FactExtractor for ConditionFactExtractor {
+    async fn extract(&self, message: &ChatMessage) -> Result<Vec<String>, LLMError> {
+        let event = MessageEvent {
+            role: message.role.to_string(),
+            msg: message.clone(),
+        };
+        Ok(if self.condition.matches(&event) {
+            vec![message.content.clone()]
+        } else {
+            Vec::new()
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn FactExtractor> {
+        Box::new(self.clone())
+    }
+}
+
+/// Asks an LLM to pull durable `key: value` facts out of each message.
+#[derive(Clone)]
+pub struct LLMFactExtractor {
+    llm: Arc<dyn LLMProvider>,
+    prompt: String,
+}
+
+impl LLMFactExtractor {
+    pub fn new(llm: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            llm,
+            prompt: DEFAULT_EXTRACTION_PROMPT.to_string(),
+        }
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+}
+
+#[async_trait]
+impl FactExtractor for LLMFactExtractor {
+    async fn extract(&self, message: &ChatMessage) -> Result<Vec<String>, LLMError> {
+        let request = [
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: self.prompt.clone(),
+                reasoning: Vec::new(),
+            },
+            ChatMessage::user()
+                .content(format!("{}: {}", message.role, message.content))
+                .build(),
+        ];
+        let response = self.llm.chat(&request, None, None).await?;
+        Ok(response
+            .text()
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.trim().trim_start_matches(['-', '*']).trim())
+            .filter(|line| !line.is_empty() && !line.eq_ignore_ascii_case("none"))
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn clone_box(&self) -> Box<dyn FactExtractor> {
+        Box::new(self.clone())
+    }
+}
+
+/// Recent turns verbatim plus relevant long-term facts.
+///
+/// Only user and assistant text messages are offered to the extractor. On
+/// recall, the query (or, when empty, the latest user message) selects up to
+/// `max_facts` facts scoring at least `min_relevance`; facts already present
+/// in the recent messages are left out. The selected facts are returned as
+/// one assistant message ahead of the short-term messages.
+pub struct HybridMemory {
+    short_term: Box<dyn MemoryProvider>,
+    long_term: Box<dyn LongTermStore>,
+    extractor: Box<dyn FactExtractor>,
+    max_facts: usize,
+    min_relevance: f32,
+}
+
+impl HybridMemory {
+    pub fn new(
+        short_term: Box<dyn MemoryProvider>,
+        long_term: Box<dyn LongTermStore>,
+        extractor: Box<dyn FactExtractor>,
+    ) -> Self {
+        Self {
+            short_term,
+            long_term,
+            extractor,
+            max_facts: DEFAULT_MAX_FACTS,
+            min_relevance: 0.0,
+        }
+    }
+
+    pub fn max_facts(mut self, max_facts: usize) -> Self {
+        self.max_facts = max_facts;
+        self
+    }
+
+    /// Drop retrieved facts scoring below `min_relevance` (`0.0..=1.0`)
+    pub fn min_relevance(mut self, min_relevance: f32) -> Self {
+        self.min_relevance = min_relevance;
+        self
+    }
+
+    pub fn long_term(&self) -> &dyn LongTermStore {
+        self.long_term.as_ref()
+    }
+
+    /// Store a fact directly, bypassing the extractor
+    pub async fn promote(&mut self, fact: &str) -> Result<(), LLMError> {
+        self.long_term.store(fact).await
+    }
+}
+
+impl Clone for HybridMemory {
+    fn clone(&self) -> Self {
+        Self {
+            short_term: self.short_term.clone_box(),
+            long_term: self.long_term.clone_box(),
+            extractor: self.extractor.clone_box(),
+            max_facts: self.max_facts,
+            min_relevance: self.min_relevance,
+        }
+    }
+}
+
+impl std::fmt::Debug for HybridMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HybridMemory")
+            .field("short_term", &self.short_term.memory_type())
+            .field("facts", &self.long_term.len())
+            .field("max_facts", &self.max_facts)
+            .field("min_relevance", &self.min_relevance)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for HybridMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.short_term.remember(message).await?;
+
+        let conversational = matches!(message.role, ChatRole::User | ChatRole::Assistant)
+            && matches!(message.message_type, MessageType::Text)
+            && !message.content.trim().is_empty();
+        if conversational {
+            for fact in self.extractor.extract(message).await? {
+                self.long_term.store(&fact).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let recent = self.short_term.recall(query, limit).await?;
+
+        let query = match query {
+            "" => recent
+                .iter()
+                .rev()
+                .find(|m| m.role == ChatRole::User && !m.content.is_empty())
+                .map(|m| m.content.as_str())
+                .unwrap_or_default(),
+            query => query,
+        };
+        let facts: Vec<String> = self
+            .long_term
+            .retrieve(query, self.max_facts)
+            .await?
+            .into_iter()
+            .filter(|(_, score)| *score >= self.min_relevance)
+            .map(|(fact, _)| fact)
+            .filter(|fact| !recent.iter().any(|m| m.content.contains(fact.as_str())))
+            .collect();
+
+        if facts.is_empty() {
+            return Ok(recent);
+        }
+        let mut content = String::from("Relevant facts from earlier conversations:");
+        for fact in &facts {
+            content.push_str("\n- ");
+            content.push_str(fact);
+        }
+        let mut messages = vec![ChatMessage::assistant().content(content).build()];
+        messages.extend(recent);
+        Ok(messages)
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.short_term.clear().await?;
+        self.long_term.clear().await
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Hybrid
+    }
+
+    fn size(&self) -> usize {
+        self.short_term.size()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn fork(&self) -> Option<Box<dyn MemoryProvider>> {
+        Some(Box::new(Self {
+            short_term: self.short_term.fork()?,
+            long_term: self.long_term.fork()?,
+            extractor: self.extractor.clone_box(),
+            max_facts: self.max_facts,
+            min_relevance: self.min_relevance,
+        }))
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.short_term.export()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.short_term.search(query, limit).await
+    }
+
+    async fn system_context(&self, query: &str) -> Result<Option<String>, LLMError> {
+        self.short_term.system_context(query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::MemoryCheckpoint;
+    use crate::agent::memory::SlidingWindowMemory;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use tokio::sync::Mutex;
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    fn remember_facts() -> Box<dyn FactExtractor> {
+        Box::new(ConditionFactExtractor::new(MessageCondition::Contains(
+            "remember".into(),
+        )))
+    }
+
+    #[tokio::test]
+    async fn test_promoted_facts_outlive_the_window() {
+        let mut memory = HybridMemory::new(
+            Box::new(SlidingWindowMemory::new(2)),
+            Box::new(KeyValueFactStore::new()),
+            remember_facts(),
+        );
+        memory
+            .remember(&user("remember: my favourite colour is green"))
+            .await
+            .unwrap();
+        memory.remember(&user("hello")).await.unwrap();
+        memory
+            .remember(&user("what colour do I like?"))
+            .await
+            .unwrap();
+
+        let recalled = memory.recall("", None).await.unwrap();
+        assert_eq!(recalled.len(), 3);
+        assert!(recalled[0]
+            .content
+            .ends_with("- remember: my favourite colour is green"));
+        assert_eq!(recalled[2].content, "what colour do I like?");
+    }
+
+    #[tokio::test]
+    async fn test_irrelevant_and_duplicate_facts_are_filtered() {
+        let mut memory = HybridMemory::new(
+            Box::new(SlidingWindowMemory::new(10)),
+            Box::new(KeyValueFactStore::new()),
+            remember_facts(),
+        )
+        .min_relevance(0.3);
+        memory.promote("user_city: Lisbon").await.unwrap();
+        memory
+            .remember(&user("remember the deadline is friday"))
+            .await
+            .unwrap();
+
+        // The deadline fact is already in the window; the city is unrelated
+        let recalled = memory.recall("when is the deadline", None).await.unwrap();
+        assert_eq!(recalled.len(), 1);
+
+        let recalled = memory
+            .recall("which city do I live in", None)
+            .await
+            .unwrap();
+        assert!(recalled[0].content.contains("user_city: Lisbon"));
+    }
+
+    #[tokio::test]
+    async fn test_key_value_store_replaces_by_key() {
+        let mut store = KeyValueFactStore::new();
+        store.store("user_name: Ada").await.unwrap();
+        store.store("user_name: Grace").await.unwrap();
+        store.store("likes tea").await.unwrap();
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get("user_name"), Some("Grace"));
+        let hits = store.retrieve("what is my name", 5).await.unwrap();
+        assert_eq!(hits[0].0, "user_name: Grace");
+    }
+
+    #[tokio::test]
+    async fn test_llm_extractor_parses_fact_lines() {
+        let llm = Arc::new(ScriptedLLMProvider::new([
+            "- user_name: Ada\n- prefers: short answers",
+            "NONE",
+        ]));
+        let extractor = LLMFactExtractor::new(llm.clone());
+
+        let facts = extractor
+            .extract(&user("I'm Ada, keep it short"))
+            .await
+            .unwrap();
+        assert_eq!(facts, vec!["user_name: Ada", "prefers: short answers"]);
+        assert!(extractor.extract(&user("ok")).await.unwrap().is_empty());
+        assert!(llm.last_messages()[1].content.starts_with("User: ok"));
+    }
+
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    ["deploy", "budget"]
+                        .iter()
+                        .map(|topic| text.matches(topic).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vector_store_ranks_by_similarity() {
+        let mut store = VectorFactStore::new(Arc::new(TopicEmbedder));
+        store.store("budget is 10k").await.unwrap();
+        store.store("deploy on fridays is banned").await.unwrap();
+
+        let hits = store.retrieve("can we deploy today", 1).await.unwrap();
+        assert_eq!(hits, vec![("deploy on fridays is banned".to_string(), 1.0)]);
+
+        let mut copy = store.clone_box();
+        copy.clear().await.unwrap();
+        assert!(copy.is_empty());
+        assert_eq!(store.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_restores_facts() {
+        let mut memory = HybridMemory::new(
+            Box::new(SlidingWindowMemory::new(1)),
+            Box::new(KeyValueFactStore::new()),
+            remember_facts(),
+        );
+        memory
+            .remember(&user("remember: the deadline is friday"))
+            .await
+            .unwrap();
+        memory.remember(&user("hello")).await.unwrap();
+        let memory: Arc<Mutex<Box<dyn MemoryProvider>>> = Arc::new(Mutex::new(Box::new(memory)));
+
+        let checkpoint = MemoryCheckpoint::take(memory.clone()).await.unwrap();
+        memory
+            .lock()
+            .await
+            .remember(&user("remember: the deadline is monday"))
+            .await
+            .unwrap();
+        checkpoint.rollback().await.unwrap();
+
+        // The fact has left the window, so only the long-term store has it
+        let recalled = memory.lock().await.recall("deadline", None).await.unwrap();
+        assert_eq!(recalled.len(), 2);
+        assert!(recalled[0]
+            .content
+            .ends_with("- remember: the deadline is friday"));
+        assert_eq!(recalled[1].content, "hello");
+    }
+}
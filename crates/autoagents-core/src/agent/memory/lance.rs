@@ -0,0 +1,146 @@
+//! LanceDB-backed [`VectorIndex`] for [`VectorMemory`](super::VectorMemory).
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int64Type;
+use arrow_array::{ArrayRef, Int64Array, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use autoagents_llm::error::LLMError;
+use futures::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::{Connection, DistanceType, Table};
+use std::sync::Arc;
+
+use super::VectorIndex;
+use crate::rag::{
+    batch_reader, distances, lance_error, missing_column, open_or_create_table, vector_array,
+    vector_field,
+};
+
+/// Message embeddings stored in a LanceDB table, one row per message with
+/// the message position as id.
+///
+/// Give each memory a table of its own, empty when the memory starts;
+/// clearing the memory empties the table.
+#[derive(Clone)]
+pub struct LanceVectorIndex {
+    table: Table,
+    dimensions: usize,
+    len: usize,
+}
+
+impl LanceVectorIndex {
+    /// Open table `table` of the database in the directory `path`, storing
+    /// embeddings of `dimensions`.
+    pub async fn open(path: &str, table: &str, dimensions: usize) -> Result<Self, LLMError> {
+        let db = lancedb::connect(path)
+            .execute()
+            .await
+            .map_err(lance_error)?;
+        Self::from_connection(&db, table, dimensions).await
+    }
+
+    /// Open table `table` of an existing connection.
+    pub async fn from_connection(
+        db: &Connection,
+        table: &str,
+        dimensions: usize,
+    ) -> Result<Self, LLMError> {
+        let table = open_or_create_table(db, table, schema(dimensions)).await?;
+        Ok(Self {
+            table,
+            dimensions,
+            len: 0,
+        })
+    }
+}
+
+fn schema(dimensions: usize) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        vector_field(dimensions),
+    ]))
+}
+
+impl std::fmt::Debug for LanceVectorIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LanceVectorIndex")
+            .field("table", &self.table.name())
+            .field("dimensions", &self.dimensions)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl VectorIndex for LanceVectorIndex {
+    async fn insert(&mut self, id: usize, embedding: Vec<f32>) -> Result<(), LLMError> {
+        if embedding.len() != self.dimensions {
+            return Err(LLMError::InvalidRequest(format!(
+                "expected an embedding of {} dimensions, got {}",
+                self.dimensions,
+                embedding.len()
+            )));
+        }
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![id as i64])),
+            Arc::new(vector_array(&[embedding], self.dimensions)),
+        ];
+        let batch = RecordBatch::try_new(schema(self.dimensions), columns)
+            .map_err(|e| LLMError::ProviderError(format!("Arrow error: {e}")))?;
+        self.table
+            .add(batch_reader(batch))
+            .execute()
+            .await
+            .map_err(lance_error)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    async fn search(&self, query: &[f32], top_k: usize) -> Result<Vec<(usize, f32)>, LLMError> {
+        if self.len == 0 {
+            return Ok(Vec::new());
+        }
+        let batches: Vec<RecordBatch> = self
+            .table
+            .query()
+            .nearest_to(query)
+            .map_err(lance_error)?
+            .distance_type(DistanceType::Cosine)
+            .limit(top_k)
+            .execute()
+            .await
+            .map_err(lance_error)?
+            .try_collect()
+            .await
+            .map_err(lance_error)?;
+
+        let mut hits = Vec::new();
+        for batch in &batches {
+            let ids = batch
+                .column_by_name("id")
+                .and_then(|column| column.as_primitive_opt::<Int64Type>())
+                .ok_or_else(|| missing_column("id"))?;
+            hits.extend(
+                ids.values()
+                    .iter()
+                    .map(|id| *id as usize)
+                    .zip(distances(batch)?),
+            );
+        }
+        Ok(hits)
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.table.delete("true").await.map_err(lance_error)?;
+        self.len = 0;
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clone_box(&self) -> Box<dyn VectorIndex> {
+        Box::new(self.clone())
+    }
+}
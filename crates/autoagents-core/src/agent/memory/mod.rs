@@ -1,13 +1,82 @@
 use async_trait::async_trait;
-use autoagents_llm::{chat::ChatMessage, error::LLMError};
+use autoagents_llm::{
+    chat::{ChatMessage, MessageType},
+    error::LLMError,
+};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 #[cfg(not(target_arch = "wasm32"))]
-use tokio::sync::broadcast;
-
+use tokio::sync::{broadcast, Mutex};
+
+#[cfg(target_arch = "wasm32")]
+use futures::lock::Mutex;
+
+mod branching;
+mod checkpoint;
+mod entity;
+mod episodic;
+mod eviction;
+mod hybrid;
+#[cfg(all(feature = "lancedb", not(target_arch = "wasm32")))]
+mod lance;
+mod middleware;
+mod namespaced;
+mod persistent;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+mod pgvector;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+mod postgres;
+#[cfg(all(feature = "qdrant", not(target_arch = "wasm32")))]
+mod qdrant;
+#[cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+mod redis;
+mod retention;
+mod shared;
 mod sliding_window;
-pub use sliding_window::SlidingWindowMemory;
+mod snapshot;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod sqlite;
+mod summary;
+mod token_window;
+mod tool_compression;
+mod vector;
+#[cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+pub use self::redis::RedisMemory;
+pub use branching::{BranchError, BranchInfo, BranchingMemory, MAIN_BRANCH};
+pub use checkpoint::{MemoryCheckpoint, MemoryRollback};
+pub use entity::{Entity, EntityMemory};
+pub use episodic::{Episode, EpisodicMemory, RecencyDecay, TimeWindow};
+pub use eviction::{
+    EvictionCandidate, EvictionPolicy, FifoEviction, ImportanceEviction, LruEviction,
+};
+pub use hybrid::{
+    ConditionFactExtractor, FactExtractor, HybridMemory, KeyValueFactStore, LLMFactExtractor,
+    LongTermStore, VectorFactStore,
+};
+#[cfg(all(feature = "lancedb", not(target_arch = "wasm32")))]
+pub use lance::LanceVectorIndex;
+pub use middleware::{FnMiddleware, MemoryMiddleware, MiddlewareMemory, Redactor};
+pub use namespaced::NamespacedMemory;
+pub use persistent::{PersistedMemory, PersistentMemory, PersistentMemoryError};
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+pub use pgvector::PgVectorIndex;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+pub use postgres::PostgresMemory;
+#[cfg(all(feature = "qdrant", not(target_arch = "wasm32")))]
+pub use qdrant::QdrantVectorIndex;
+#[cfg(not(target_arch = "wasm32"))]
+pub use retention::RetentionSweeper;
+pub use retention::{RetentionPolicy, RetentionReport};
+pub use shared::{SharedMemory, SharedMemoryScope, SharedNote};
+pub use sliding_window::{SlidingWindowMemory, TrimStrategy};
+pub use snapshot::{MemorySnapshot, MEMORY_SNAPSHOT_VERSION};
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use sqlite::SqliteMemory;
+pub use summary::SummaryMemory;
+pub use token_window::TokenWindowMemory;
+pub use tool_compression::{CompressedToolMemory, ToolResultArchive};
+pub use vector::{InMemoryVectorIndex, VectorIndex, VectorMemory};
 
 #[cfg(test)]
 mod tests {
@@ -129,6 +198,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -144,6 +214,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -155,6 +226,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "different message".to_string(),
+            reasoning: Vec::new(),
         };
         let different_event = MessageEvent {
             role: "user".to_string(),
@@ -170,6 +242,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "this is a test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -181,6 +254,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "this is different".to_string(),
+            reasoning: Vec::new(),
         };
         let non_matching_event = MessageEvent {
             role: "user".to_string(),
@@ -196,6 +270,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "this is a test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -207,6 +282,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "this is an error message".to_string(),
+            reasoning: Vec::new(),
         };
         let error_event = MessageEvent {
             role: "user".to_string(),
@@ -222,6 +298,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -235,6 +312,7 @@ mod tests {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: "test message".to_string(),
+                reasoning: Vec::new(),
             },
         };
         assert!(!condition.matches(&assistant_event));
@@ -247,6 +325,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -260,6 +339,7 @@ mod tests {
                 role: ChatRole::System,
                 message_type: MessageType::Text,
                 content: "test message".to_string(),
+                reasoning: Vec::new(),
             },
         };
         assert!(!condition.matches(&system_event));
@@ -272,6 +352,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "this is a long message".to_string(),
+            reasoning: Vec::new(),
         };
         let long_event = MessageEvent {
             role: "user".to_string(),
@@ -283,6 +364,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "hi".to_string(),
+            reasoning: Vec::new(),
         };
         let short_event = MessageEvent {
             role: "user".to_string(),
@@ -298,6 +380,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "hello world".to_string(),
+            reasoning: Vec::new(),
         };
         let hello_event = MessageEvent {
             role: "user".to_string(),
@@ -309,6 +392,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "goodbye world".to_string(),
+            reasoning: Vec::new(),
         };
         let goodbye_event = MessageEvent {
             role: "user".to_string(),
@@ -324,6 +408,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "".to_string(),
+            reasoning: Vec::new(),
         };
         let empty_event = MessageEvent {
             role: "user".to_string(),
@@ -335,6 +420,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "not empty".to_string(),
+            reasoning: Vec::new(),
         };
         let non_empty_event = MessageEvent {
             role: "user".to_string(),
@@ -355,6 +441,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "this is a test message".to_string(),
+            reasoning: Vec::new(),
         };
         let matching_event = MessageEvent {
             role: "user".to_string(),
@@ -366,6 +453,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "hi".to_string(),
+            reasoning: Vec::new(),
         };
         let non_matching_event = MessageEvent {
             role: "user".to_string(),
@@ -386,6 +474,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "hello world".to_string(),
+            reasoning: Vec::new(),
         };
         let hello_event = MessageEvent {
             role: "user".to_string(),
@@ -397,6 +486,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "goodbye world".to_string(),
+            reasoning: Vec::new(),
         };
         let goodbye_event = MessageEvent {
             role: "user".to_string(),
@@ -408,6 +498,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "".to_string(),
+            reasoning: Vec::new(),
         };
         let empty_event = MessageEvent {
             role: "user".to_string(),
@@ -419,6 +510,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let non_matching_event = MessageEvent {
             role: "user".to_string(),
@@ -434,6 +526,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "there are 123 items".to_string(),
+            reasoning: Vec::new(),
         };
         let number_event = MessageEvent {
             role: "user".to_string(),
@@ -445,6 +538,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "no numbers here".to_string(),
+            reasoning: Vec::new(),
         };
         let no_number_event = MessageEvent {
             role: "user".to_string(),
@@ -460,6 +554,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -474,6 +569,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -489,6 +585,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -505,6 +602,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
         let event = MessageEvent {
             role: "user".to_string(),
@@ -522,6 +620,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
 
         let result = provider.remember(&message).await;
@@ -536,6 +635,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
 
         let result = provider.remember(&message).await;
@@ -553,11 +653,13 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: "first message".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: "second message".to_string(),
+                reasoning: Vec::new(),
             },
         ];
         let provider = MockMemoryProvider::with_messages(messages);
@@ -577,11 +679,13 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: "first message".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: "second message".to_string(),
+                reasoning: Vec::new(),
             },
         ];
         let provider = MockMemoryProvider::with_messages(messages);
@@ -611,6 +715,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "first message".to_string(),
+            reasoning: Vec::new(),
         }];
         let mut provider = MockMemoryProvider::with_messages(messages);
         assert_eq!(provider.size(), 1);
@@ -644,6 +749,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "message".to_string(),
+            reasoning: Vec::new(),
         }];
         let provider_with_messages = MockMemoryProvider::with_messages(messages);
         assert_eq!(provider_with_messages.size(), 1);
@@ -658,6 +764,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "message".to_string(),
+            reasoning: Vec::new(),
         }];
         let provider_with_messages = MockMemoryProvider::with_messages(messages);
         assert!(!provider_with_messages.is_empty());
@@ -677,6 +784,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         };
 
         let result = provider
@@ -761,11 +869,63 @@ impl MessageCondition {
     }
 }
 
+/// Messages whose text or tool call payload contains `query`, ignoring case,
+/// newest first.
+pub(crate) fn search_messages(
+    messages: &[ChatMessage],
+    query: &str,
+    limit: Option<usize>,
+) -> Vec<ChatMessage> {
+    let query = query.to_lowercase();
+    messages
+        .iter()
+        .rev()
+        .filter(|m| message_matches(m, &query))
+        .take(limit.unwrap_or(usize::MAX))
+        .cloned()
+        .collect()
+}
+
+/// Whether the text or a tool call payload of `message` contains the
+/// lowercase `query`
+pub(crate) fn message_matches(message: &ChatMessage, query: &str) -> bool {
+    if message.content.to_lowercase().contains(query) {
+        return true;
+    }
+    match &message.message_type {
+        MessageType::ToolUse(calls) | MessageType::ToolResult(calls) => calls.iter().any(|c| {
+            c.function.name.to_lowercase().contains(query)
+                || c.function.arguments.to_lowercase().contains(query)
+        }),
+        _ => false,
+    }
+}
+
 /// Types of memory implementations available
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MemoryType {
     /// Simple sliding window that keeps the N most recent messages
     SlidingWindow,
+    /// History kept in a [`PersistentMemory`] store
+    Persistent,
+    /// Messages retrieved by embedding similarity
+    Vector,
+    /// Recent window plus an LLM-written summary of older messages
+    Summary,
+    /// Most recent messages that fit in a token budget
+    TokenWindow,
+    /// Short-term messages combined with long-term facts
+    Hybrid,
+    /// Conversation plus structured records of mentioned entities
+    Entity,
+    /// Private history plus notes shared between agents
+    Shared,
+    /// Separate memories per user or tenant
+    Namespaced,
+    /// Conversation with forkable branches
+    Branching,
+    /// Timestamped messages retrieved by relevance and recency
+    Episodic,
 }
 
 /// Trait for memory providers that can store and retrieve conversation history.
@@ -885,4 +1045,70 @@ pub trait MemoryProvider: Send + Sync {
     fn export(&self) -> Vec<ChatMessage> {
         Vec::new()
     }
+
+    /// Look up earlier messages related to `query`, best match first.
+    ///
+    /// Unlike [`recall`](Self::recall), which builds the context for the next
+    /// turn, this is meant for explicit lookups by tools and executors. The
+    /// default is a case-insensitive substring search over
+    /// [`export`](Self::export), newest first; `limit` of `None` returns
+    /// every match.
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        Ok(search_messages(&self.export(), query, limit))
+    }
+
+    /// Capture the stored conversation as a versioned [`MemorySnapshot`].
+    ///
+    /// Defaults to the messages returned by [`export`](Self::export).
+    async fn save(&self) -> Result<MemorySnapshot, LLMError> {
+        Ok(MemorySnapshot::new(self.memory_type(), self.export()))
+    }
+
+    /// Replace the stored conversation with the one in `snapshot`.
+    ///
+    /// Uses [`preload`](Self::preload) when supported, otherwise clears the
+    /// memory and remembers each message in order.
+    async fn load(&mut self, snapshot: MemorySnapshot) -> Result<(), LLMError> {
+        snapshot.check_version()?;
+        if self.preload(snapshot.messages.clone()) {
+            return Ok(());
+        }
+        self.clear().await?;
+        for message in &snapshot.messages {
+            self.remember(message).await?;
+        }
+        Ok(())
+    }
+
+    /// An independent copy of the memory's full state, which
+    /// [`MemoryCheckpoint`] keeps instead of a [`save`](Self::save)d
+    /// snapshot so a rollback restores derived state such as extracted
+    /// facts or embeddings exactly.
+    ///
+    /// Returns `None` by default, for memories whose clones share state or
+    /// keep it outside the process.
+    fn fork(&self) -> Option<Box<dyn MemoryProvider>> {
+        None
+    }
+
+    /// Isolated memory for `namespace`, typically a user or tenant id.
+    ///
+    /// Agents call this at the start of a run whose task metadata carries a
+    /// namespace and use the returned memory for that run. Returns `None`,
+    /// meaning the memory is shared by all runs, unless the provider
+    /// partitions by namespace like [`NamespacedMemory`].
+    fn namespace(&self, _namespace: &str) -> Option<Arc<Mutex<Box<dyn MemoryProvider>>>> {
+        None
+    }
+
+    /// Text to append to the agent's system prompt, such as known facts
+    /// about the user, selected for `query` (the latest user message when
+    /// empty). Returns `None` by default.
+    async fn system_context(&self, _query: &str) -> Result<Option<String>, LLMError> {
+        Ok(None)
+    }
 }
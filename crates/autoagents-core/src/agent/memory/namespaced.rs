@@ -0,0 +1,242 @@
+//! Per-user (or per-tenant) isolation of conversation memory.
+//!
+//! A [`NamespacedMemory`] lets one agent serve many users. Each namespace
+//! gets its own memory, created on first use, and agents pick the namespace
+//! of a run from [`RunMetadata::memory_namespace`](crate::agent::task::RunMetadata::memory_namespace).
+use async_trait::async_trait;
+use autoagents_llm::chat::ChatMessage;
+use autoagents_llm::error::LLMError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{MemoryProvider, MemorySnapshot, MemoryType};
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::Mutex;
+
+#[cfg(target_arch = "wasm32")]
+use futures::lock::Mutex;
+
+type MemoryFactory = dyn Fn(&str) -> Box<dyn MemoryProvider> + Send + Sync;
+type NamespaceMap = HashMap<String, Arc<Mutex<Box<dyn MemoryProvider>>>>;
+
+/// Memory partitioned by namespace, such as a user or tenant id.
+///
+/// Runs whose task metadata names a user or tenant read and write only that
+/// namespace's memory; runs without one use a separate default memory.
+/// Clones share the namespaces.
+pub struct NamespacedMemory {
+    factory: Arc<MemoryFactory>,
+    default: Box<dyn MemoryProvider>,
+    namespaces: Arc<std::sync::Mutex<NamespaceMap>>,
+}
+
+impl NamespacedMemory {
+    /// Create each namespace's memory with `factory`, which receives the
+    /// namespace. Use this for backends keyed by session, e.g. to give every
+    /// user their own Redis or SQLite session.
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn(&str) -> Box<dyn MemoryProvider> + Send + Sync + 'static,
+    {
+        let default = factory("");
+        Self {
+            factory: Arc::new(factory),
+            default,
+            namespaces: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Give every namespace a copy of the empty `template`.
+    ///
+    /// Only suitable for in-process memories: copies of store-backed memories
+    /// such as [`PersistedMemory`](super::PersistedMemory) share their
+    /// session and would not be isolated.
+    pub fn from_template(template: Box<dyn MemoryProvider>) -> Self {
+        let template: Arc<dyn MemoryProvider> = Arc::from(template);
+        Self::new(move |_| template.clone_box())
+    }
+
+    /// The memory of `namespace`, created if it does not exist yet.
+    pub fn get(&self, namespace: &str) -> Arc<Mutex<Box<dyn MemoryProvider>>> {
+        self.lock_namespaces()
+            .entry(namespace.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new((self.factory)(namespace))))
+            .clone()
+    }
+
+    /// Namespaces that have been used so far
+    pub fn namespaces(&self) -> Vec<String> {
+        let mut namespaces: Vec<String> = self.lock_namespaces().keys().cloned().collect();
+        namespaces.sort();
+        namespaces
+    }
+
+    /// Clear and drop the memory of `namespace`, e.g. when a user asks to be
+    /// forgotten. Returns whether the namespace existed.
+    pub async fn remove(&self, namespace: &str) -> Result<bool, LLMError> {
+        let Some(memory) = self.lock_namespaces().remove(namespace) else {
+            return Ok(false);
+        };
+        memory.lock().await.clear().await?;
+        Ok(true)
+    }
+
+    fn lock_namespaces(&self) -> std::sync::MutexGuard<'_, NamespaceMap> {
+        self.namespaces.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clone for NamespacedMemory {
+    fn clone(&self) -> Self {
+        Self {
+            factory: self.factory.clone(),
+            default: self.default.clone_box(),
+            namespaces: self.namespaces.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for NamespacedMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NamespacedMemory")
+            .field("default", &self.default.memory_type())
+            .field("namespaces", &self.lock_namespaces().len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Calls made directly on the provider act on the default memory.
+#[async_trait]
+impl MemoryProvider for NamespacedMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.default.remember(message).await
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.default.recall(query, limit).await
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.default.clear().await
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Namespaced
+    }
+
+    fn size(&self) -> usize {
+        self.default.size()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn preload(&mut self, data: Vec<ChatMessage>) -> bool {
+        self.default.preload(data)
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.default.export()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.default.search(query, limit).await
+    }
+
+    async fn save(&self) -> Result<MemorySnapshot, LLMError> {
+        self.default.save().await
+    }
+
+    async fn system_context(&self, query: &str) -> Result<Option<String>, LLMError> {
+        self.default.system_context(query).await
+    }
+
+    fn namespace(&self, namespace: &str) -> Option<Arc<Mutex<Box<dyn MemoryProvider>>>> {
+        Some(self.get(namespace))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::SlidingWindowMemory;
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_are_isolated() {
+        let memory = NamespacedMemory::from_template(Box::new(SlidingWindowMemory::new(10)));
+        memory
+            .get("alice")
+            .lock()
+            .await
+            .remember(&user("I'm Alice"))
+            .await
+            .unwrap();
+        memory
+            .get("bob")
+            .lock()
+            .await
+            .remember(&user("I'm Bob"))
+            .await
+            .unwrap();
+
+        let alice = memory
+            .get("alice")
+            .lock()
+            .await
+            .recall("", None)
+            .await
+            .unwrap();
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].content, "I'm Alice");
+        assert!(memory.is_empty());
+        assert_eq!(memory.namespaces(), vec!["alice", "bob"]);
+    }
+
+    #[tokio::test]
+    async fn test_factory_receives_namespace_and_clones_share() {
+        let memory = NamespacedMemory::new(|namespace| {
+            let mut window = SlidingWindowMemory::new(5);
+            window.preload(vec![user(&format!("namespace={namespace}"))]);
+            Box::new(window)
+        });
+        let clone = memory.clone_box();
+
+        let handle = clone.namespace("t1/u1").unwrap();
+        assert_eq!(
+            handle.lock().await.recall("", None).await.unwrap()[0].content,
+            "namespace=t1/u1"
+        );
+        assert!(Arc::ptr_eq(&handle, &memory.get("t1/u1")));
+        assert_eq!(
+            memory.recall("", None).await.unwrap()[0].content,
+            "namespace="
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_forgets_namespace() {
+        let memory = NamespacedMemory::from_template(Box::new(SlidingWindowMemory::new(10)));
+        let alice = memory.get("alice");
+        alice.lock().await.remember(&user("secret")).await.unwrap();
+
+        assert!(memory.remove("alice").await.unwrap());
+        assert!(!memory.remove("alice").await.unwrap());
+        assert!(alice.lock().await.is_empty());
+        assert!(memory.namespaces().is_empty());
+        assert_eq!(memory.memory_type(), MemoryType::Namespaced);
+    }
+}
@@ -0,0 +1,341 @@
+//! Memory that outlives the process.
+//!
+//! A [`PersistentMemory`] backend stores conversation history per session;
+//! [`PersistedMemory`] adapts one session of a backend to [`MemoryProvider`]
+//! so it can be handed to an agent like any in-RAM memory.
+use async_trait::async_trait;
+use autoagents_llm::{chat::ChatMessage, error::LLMError};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::{search_messages, MemoryProvider, MemorySnapshot, MemoryType};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PersistentMemoryError {
+    #[error("Memory backend error: {0}")]
+    Backend(String),
+
+    #[error("Failed to (de)serialize stored message: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Operation not supported by this memory backend: {0}")]
+    Unsupported(String),
+}
+
+impl From<PersistentMemoryError> for LLMError {
+    fn from(error: PersistentMemoryError) -> Self {
+        match error {
+            PersistentMemoryError::Serialization(e) => LLMError::JsonError(e.to_string()),
+            other => LLMError::ProviderError(other.to_string()),
+        }
+    }
+}
+
+/// Storage backend for conversation history, keyed by session id.
+///
+/// Implementations take `&self` and are expected to be shareable, so several
+/// agents (or processes, for database-backed stores) can read and append to
+/// the same session.
+#[async_trait]
+pub trait PersistentMemory: Send + Sync {
+    /// Append a message to the end of a session.
+    async fn append(
+        &self,
+        session_id: &str,
+        message: &ChatMessage,
+    ) -> Result<(), PersistentMemoryError>;
+
+    /// Load the most recent `limit` messages of a session, oldest first.
+    ///
+    /// `None` loads the whole session; unknown sessions are empty.
+    async fn load(
+        &self,
+        session_id: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, PersistentMemoryError>;
+
+    /// Delete every message of a session.
+    async fn clear(&self, session_id: &str) -> Result<(), PersistentMemoryError>;
+
+    /// Number of messages stored for a session.
+    async fn count(&self, session_id: &str) -> Result<usize, PersistentMemoryError>;
+
+    /// Ids of all sessions that have at least one message.
+    async fn sessions(&self) -> Result<Vec<String>, PersistentMemoryError>;
+
+    /// Delete every message appended before `cutoff`, returning how many
+    /// were removed. Used by [`RetentionPolicy`](super::RetentionPolicy).
+    async fn expire_messages_before(
+        &self,
+        _cutoff: SystemTime,
+    ) -> Result<usize, PersistentMemoryError> {
+        Err(PersistentMemoryError::Unsupported(
+            "expiring messages by age".into(),
+        ))
+    }
+
+    /// Delete every session whose latest message was appended before
+    /// `cutoff`, returning how many sessions were removed.
+    async fn expire_sessions_idle_since(
+        &self,
+        _cutoff: SystemTime,
+    ) -> Result<usize, PersistentMemoryError> {
+        Err(PersistentMemoryError::Unsupported(
+            "expiring idle sessions".into(),
+        ))
+    }
+}
+
+/// [`MemoryProvider`] backed by one session of a [`PersistentMemory`] store.
+///
+/// Every remembered message is written through to the store, and recall
+/// always reads from it, so messages appended by other agents or processes
+/// sharing the session are visible. [`MemoryProvider::size`] is synchronous
+/// and therefore only tracks the count seen at [`open`](Self::open) plus this
+/// instance's own writes.
+///
+/// Clones, including those made by `clone_box`, share the session rather than
+/// snapshotting it.
+#[derive(Clone)]
+pub struct PersistedMemory {
+    store: Arc<dyn PersistentMemory>,
+    session_id: String,
+    window_size: Option<usize>,
+    size: usize,
+}
+
+impl PersistedMemory {
+    /// Attach to `session_id`, resuming whatever history the store holds.
+    pub async fn open(
+        store: Arc<dyn PersistentMemory>,
+        session_id: impl Into<String>,
+    ) -> Result<Self, PersistentMemoryError> {
+        let session_id = session_id.into();
+        let size = store.count(&session_id).await?;
+        Ok(Self {
+            store,
+            session_id,
+            window_size: None,
+            size,
+        })
+    }
+
+    /// Recall at most the `window_size` most recent messages, like
+    /// [`SlidingWindowMemory`](super::SlidingWindowMemory), while keeping
+    /// the full history in the store.
+    pub fn with_window(mut self, window_size: usize) -> Self {
+        self.window_size = Some(window_size);
+        self
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn store(&self) -> Arc<dyn PersistentMemory> {
+        self.store.clone()
+    }
+}
+
+impl std::fmt::Debug for PersistedMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PersistedMemory")
+            .field("session_id", &self.session_id)
+            .field("window_size", &self.window_size)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for PersistedMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.store.append(&self.session_id, message).await?;
+        self.size += 1;
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        _query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let limit = match (limit, self.window_size) {
+            (Some(limit), Some(window)) => Some(limit.min(window)),
+            (limit, window) => limit.or(window),
+        };
+        Ok(self.store.load(&self.session_id, limit).await?)
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.store.clear(&self.session_id).await?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Persistent
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn id(&self) -> Option<String> {
+        Some(self.session_id.clone())
+    }
+
+    /// Searches the whole session, not only the recall window
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let messages = self.store.load(&self.session_id, None).await?;
+        Ok(search_messages(&messages, query, limit))
+    }
+
+    /// Snapshot of the whole session, regardless of the recall window
+    async fn save(&self) -> Result<MemorySnapshot, LLMError> {
+        let messages = self.store.load(&self.session_id, None).await?;
+        Ok(MemorySnapshot::new(self.memory_type(), messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_llm::chat::ChatRole;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct InMemoryStore {
+        sessions: Mutex<HashMap<String, Vec<ChatMessage>>>,
+    }
+
+    #[async_trait]
+    impl PersistentMemory for InMemoryStore {
+        async fn append(
+            &self,
+            session_id: &str,
+            message: &ChatMessage,
+        ) -> Result<(), PersistentMemoryError> {
+            self.sessions
+                .lock()
+                .unwrap()
+                .entry(session_id.to_string())
+                .or_default()
+                .push(message.clone());
+            Ok(())
+        }
+
+        async fn load(
+            &self,
+            session_id: &str,
+            limit: Option<usize>,
+        ) -> Result<Vec<ChatMessage>, PersistentMemoryError> {
+            let sessions = self.sessions.lock().unwrap();
+            let messages = sessions.get(session_id).cloned().unwrap_or_default();
+            let skip = limit.map_or(0, |limit| messages.len().saturating_sub(limit));
+            Ok(messages.into_iter().skip(skip).collect())
+        }
+
+        async fn clear(&self, session_id: &str) -> Result<(), PersistentMemoryError> {
+            self.sessions.lock().unwrap().remove(session_id);
+            Ok(())
+        }
+
+        async fn count(&self, session_id: &str) -> Result<usize, PersistentMemoryError> {
+            Ok(self
+                .sessions
+                .lock()
+                .unwrap()
+                .get(session_id)
+                .map_or(0, Vec::len))
+        }
+
+        async fn sessions(&self) -> Result<Vec<String>, PersistentMemoryError> {
+            Ok(self.sessions.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    fn message(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    #[tokio::test]
+    async fn test_history_survives_reopen() {
+        let store: Arc<dyn PersistentMemory> = Arc::new(InMemoryStore::default());
+        let mut memory = PersistedMemory::open(store.clone(), "chat-1")
+            .await
+            .unwrap();
+        memory.remember(&message("hello")).await.unwrap();
+        memory.remember(&message("again")).await.unwrap();
+        drop(memory);
+
+        let reopened = PersistedMemory::open(store, "chat-1").await.unwrap();
+        assert_eq!(reopened.size(), 2);
+        let recalled = reopened.recall("", None).await.unwrap();
+        assert_eq!(recalled[0].content, "hello");
+        assert_eq!(recalled[1].role, ChatRole::User);
+        assert_eq!(reopened.id().as_deref(), Some("chat-1"));
+    }
+
+    #[tokio::test]
+    async fn test_window_caps_recall() {
+        let store: Arc<dyn PersistentMemory> = Arc::new(InMemoryStore::default());
+        let mut memory = PersistedMemory::open(store, "chat")
+            .await
+            .unwrap()
+            .with_window(2);
+        for content in ["a", "b", "c"] {
+            memory.remember(&message(content)).await.unwrap();
+        }
+
+        let recalled = memory.recall("", None).await.unwrap();
+        let contents: Vec<_> = recalled.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["b", "c"]);
+        assert_eq!(memory.recall("", Some(1)).await.unwrap()[0].content, "c");
+        assert_eq!(memory.size(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_the_session() {
+        let store: Arc<dyn PersistentMemory> = Arc::new(InMemoryStore::default());
+        let mut memory = PersistedMemory::open(store.clone(), "shared")
+            .await
+            .unwrap();
+        let mut other = memory.clone_box();
+        other.remember(&message("from clone")).await.unwrap();
+
+        assert_eq!(memory.recall("", None).await.unwrap().len(), 1);
+        memory.clear().await.unwrap();
+        assert!(memory.is_empty());
+        assert_eq!(store.count("shared").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_migrates_between_sessions() {
+        let store: Arc<dyn PersistentMemory> = Arc::new(InMemoryStore::default());
+        let mut source = PersistedMemory::open(store.clone(), "old")
+            .await
+            .unwrap()
+            .with_window(1);
+        for content in ["a", "b", "c"] {
+            source.remember(&message(content)).await.unwrap();
+        }
+        let snapshot = source.save().await.unwrap();
+        assert_eq!(snapshot.messages.len(), 3);
+
+        let mut target = PersistedMemory::open(store.clone(), "new").await.unwrap();
+        target.remember(&message("stale")).await.unwrap();
+        target.load(snapshot).await.unwrap();
+        assert_eq!(target.size(), 3);
+        assert_eq!(store.load("new", None).await.unwrap()[0].content, "a");
+    }
+}
@@ -0,0 +1,119 @@
+//! Postgres/pgvector-backed [`VectorIndex`] for
+//! [`VectorMemory`](super::VectorMemory).
+use async_trait::async_trait;
+use autoagents_llm::error::LLMError;
+use sqlx::postgres::PgPool;
+use sqlx::{AssertSqlSafe, Row};
+
+use super::VectorIndex;
+use crate::rag::{pg_error, validate_table, vector_literal};
+
+/// Message embeddings stored in a Postgres table with a pgvector column,
+/// one row per message with the message position as id.
+///
+/// Give each memory a table of its own, empty when the memory starts;
+/// clearing the memory empties the table. Call
+/// [`create_schema`](Self::create_schema) once before use.
+#[derive(Debug, Clone)]
+pub struct PgVectorIndex {
+    pool: PgPool,
+    table: String,
+    dimensions: usize,
+    len: usize,
+}
+
+impl PgVectorIndex {
+    pub fn new(
+        pool: PgPool,
+        table: impl Into<String>,
+        dimensions: usize,
+    ) -> Result<Self, LLMError> {
+        let table = table.into();
+        validate_table(&table)?;
+        Ok(Self {
+            pool,
+            table,
+            dimensions,
+            len: 0,
+        })
+    }
+
+    /// Statements creating the extension, the table and its HNSW index
+    pub fn schema_sql(&self) -> String {
+        let table = &self.table;
+        format!(
+            "CREATE EXTENSION IF NOT EXISTS vector;
+            CREATE TABLE IF NOT EXISTS {table} (
+                id BIGINT PRIMARY KEY,
+                embedding vector({}) NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS {table}_embedding
+                ON {table} USING hnsw (embedding vector_cosine_ops);",
+            self.dimensions
+        )
+    }
+
+    /// Create the table unless it exists
+    pub async fn create_schema(&self) -> Result<(), LLMError> {
+        sqlx::raw_sql(AssertSqlSafe(self.schema_sql()))
+            .execute(&self.pool)
+            .await
+            .map_err(pg_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VectorIndex for PgVectorIndex {
+    async fn insert(&mut self, id: usize, embedding: Vec<f32>) -> Result<(), LLMError> {
+        sqlx::query(AssertSqlSafe(format!(
+            "INSERT INTO {} (id, embedding) VALUES ($1, $2::vector)
+             ON CONFLICT (id) DO UPDATE SET embedding = EXCLUDED.embedding",
+            self.table
+        )))
+        .bind(id as i64)
+        .bind(vector_literal(&embedding))
+        .execute(&self.pool)
+        .await
+        .map_err(pg_error)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    async fn search(&self, query: &[f32], top_k: usize) -> Result<Vec<(usize, f32)>, LLMError> {
+        let rows = sqlx::query(AssertSqlSafe(format!(
+            "SELECT id, 1 - (embedding <=> $1::vector) FROM {}
+             ORDER BY embedding <=> $1::vector LIMIT $2",
+            self.table
+        )))
+        .bind(vector_literal(query))
+        .bind(i64::try_from(top_k).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(pg_error)?;
+        rows.iter()
+            .map(|row| {
+                let id: i64 = row.try_get(0).map_err(pg_error)?;
+                let score: f64 = row.try_get(1).map_err(pg_error)?;
+                Ok((id as usize, score as f32))
+            })
+            .collect()
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        sqlx::query(AssertSqlSafe(format!("DELETE FROM {}", self.table)))
+            .execute(&self.pool)
+            .await
+            .map_err(pg_error)?;
+        self.len = 0;
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clone_box(&self) -> Box<dyn VectorIndex> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,78 @@
+//! Qdrant-backed [`VectorIndex`] for [`VectorMemory`](super::VectorMemory).
+use async_trait::async_trait;
+use autoagents_llm::error::LLMError;
+
+use super::VectorIndex;
+use crate::rag::{PointId, QdrantClient, QdrantFilter, QdrantPoint};
+
+/// Message embeddings stored in a Qdrant collection, one point per message
+/// with the message position as id.
+///
+/// Give each memory a collection of its own, empty when the memory starts;
+/// clearing the memory deletes every point in the collection. The collection
+/// is created on the first insert with the size of that embedding.
+#[derive(Debug, Clone)]
+pub struct QdrantVectorIndex {
+    client: QdrantClient,
+    len: usize,
+    collection_ready: bool,
+}
+
+impl QdrantVectorIndex {
+    pub fn new(client: QdrantClient) -> Self {
+        Self {
+            client,
+            len: 0,
+            collection_ready: false,
+        }
+    }
+}
+
+#[async_trait]
+impl VectorIndex for QdrantVectorIndex {
+    async fn insert(&mut self, id: usize, embedding: Vec<f32>) -> Result<(), LLMError> {
+        if !self.collection_ready {
+            self.client.ensure_collection(embedding.len()).await?;
+            self.collection_ready = true;
+        }
+        self.client
+            .upsert(vec![QdrantPoint {
+                id: PointId::Num(id as u64),
+                vector: embedding,
+                payload: Default::default(),
+            }])
+            .await?;
+        self.len += 1;
+        Ok(())
+    }
+
+    async fn search(&self, query: &[f32], top_k: usize) -> Result<Vec<(usize, f32)>, LLMError> {
+        if self.len == 0 {
+            return Ok(Vec::new());
+        }
+        let points = self.client.search(query, top_k, None).await?;
+        Ok(points
+            .into_iter()
+            .filter_map(|point| match point.id {
+                PointId::Num(id) => Some((id as usize, point.score)),
+                PointId::Uuid(_) => None,
+            })
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        if self.collection_ready {
+            self.client.delete_matching(&QdrantFilter::new()).await?;
+        }
+        self.len = 0;
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clone_box(&self) -> Box<dyn VectorIndex> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,184 @@
+//! Redis-backed [`PersistentMemory`] store.
+//!
+//! Each session is a Redis list of JSON-serialized messages under
+//! `<prefix>:<session_id>`, so any node connected to the same server sees the
+//! same history. Sessions can expire after a period without new messages.
+use async_trait::async_trait;
+use autoagents_llm::chat::ChatMessage;
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::time::Duration;
+
+use super::{PersistentMemory, PersistentMemoryError};
+
+const DEFAULT_PREFIX: &str = "autoagents:memory";
+
+impl From<redis::RedisError> for PersistentMemoryError {
+    fn from(error: redis::RedisError) -> Self {
+        PersistentMemoryError::Backend(error.to_string())
+    }
+}
+
+/// Conversation history stored in Redis.
+///
+/// Clones share one multiplexed connection that reconnects on failure.
+#[derive(Clone)]
+pub struct RedisMemory {
+    conn: ConnectionManager,
+    prefix: String,
+    ttl: Option<Duration>,
+}
+
+impl RedisMemory {
+    /// Connect to the server at `url`, e.g. `redis://127.0.0.1:6379/0`.
+    pub async fn connect(url: &str) -> Result<Self, PersistentMemoryError> {
+        let client = redis::Client::open(url)?;
+        Ok(Self::from_manager(ConnectionManager::new(client).await?))
+    }
+
+    pub fn from_manager(conn: ConnectionManager) -> Self {
+        Self {
+            conn,
+            prefix: DEFAULT_PREFIX.to_string(),
+            ttl: None,
+        }
+    }
+
+    /// Namespace for session keys, defaults to `autoagents:memory`.
+    ///
+    /// The prefix is also used as a `SCAN` pattern when listing sessions, so
+    /// it should not contain glob characters.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Expire a session `ttl` after its last appended message.
+    ///
+    /// Redis enforces this itself, so it takes the place of a
+    /// [`RetentionPolicy`](super::RetentionPolicy) idle limit for this store.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    fn key(&self, session_id: &str) -> String {
+        session_key(&self.prefix, session_id)
+    }
+}
+
+fn session_key(prefix: &str, session_id: &str) -> String {
+    format!("{prefix}:{session_id}")
+}
+
+/// `LRANGE` start index selecting the last `limit` entries
+fn range_start(limit: Option<usize>) -> isize {
+    limit.map_or(0, |limit| -(isize::try_from(limit).unwrap_or(isize::MAX)))
+}
+
+impl std::fmt::Debug for RedisMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisMemory")
+            .field("prefix", &self.prefix)
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl PersistentMemory for RedisMemory {
+    async fn append(
+        &self,
+        session_id: &str,
+        message: &ChatMessage,
+    ) -> Result<(), PersistentMemoryError> {
+        let key = self.key(session_id);
+        let payload = serde_json::to_string(message)?;
+        let mut pipe = redis::pipe();
+        pipe.atomic().rpush(&key, payload).ignore();
+        if let Some(ttl) = self.ttl {
+            let millis = i64::try_from(ttl.as_millis()).unwrap_or(i64::MAX);
+            pipe.pexpire(&key, millis).ignore();
+        }
+        let mut conn = self.conn.clone();
+        let _: () = pipe.query_async(&mut conn).await?;
+        Ok(())
+    }
+
+    async fn load(
+        &self,
+        session_id: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, PersistentMemoryError> {
+        if limit == Some(0) {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.conn.clone();
+        let payloads: Vec<String> = conn
+            .lrange(self.key(session_id), range_start(limit), -1)
+            .await?;
+        payloads
+            .iter()
+            .map(|payload| Ok(serde_json::from_str(payload)?))
+            .collect()
+    }
+
+    async fn clear(&self, session_id: &str) -> Result<(), PersistentMemoryError> {
+        let mut conn = self.conn.clone();
+        let _: () = conn.del(self.key(session_id)).await?;
+        Ok(())
+    }
+
+    async fn count(&self, session_id: &str) -> Result<usize, PersistentMemoryError> {
+        let mut conn = self.conn.clone();
+        Ok(conn.llen(self.key(session_id)).await?)
+    }
+
+    async fn sessions(&self) -> Result<Vec<String>, PersistentMemoryError> {
+        let mut conn = self.conn.clone();
+        let pattern = session_key(&self.prefix, "*");
+        let keys: Vec<String> = conn.scan_match(pattern).await?.collect().await;
+        let prefix = session_key(&self.prefix, "");
+        let mut sessions: Vec<String> = keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&prefix).map(str::to_string))
+            .collect();
+        // SCAN may return a key more than once
+        sessions.sort();
+        sessions.dedup();
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_keys_are_namespaced() {
+        assert_eq!(
+            session_key(DEFAULT_PREFIX, "user-42"),
+            "autoagents:memory:user-42"
+        );
+        assert_eq!(session_key("app", "*"), "app:*");
+    }
+
+    #[test]
+    fn test_range_start_selects_most_recent() {
+        assert_eq!(range_start(None), 0);
+        assert_eq!(range_start(Some(3)), -3);
+        assert_eq!(range_start(Some(usize::MAX)), -isize::MAX);
+    }
+
+    #[test]
+    fn test_backend_errors_convert() {
+        let error: PersistentMemoryError =
+            redis::RedisError::from((redis::ErrorKind::IoError, "connection refused")).into();
+        assert!(matches!(error, PersistentMemoryError::Backend(msg) if msg.contains("refused")));
+    }
+}
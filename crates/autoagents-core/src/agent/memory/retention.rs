@@ -0,0 +1,235 @@
+//! Retention policies for [`PersistentMemory`] stores.
+//!
+//! A [`RetentionPolicy`] deletes messages, or whole sessions, once they are
+//! older than a configured age. Apply it on demand with
+//! [`RetentionPolicy::apply`] or let a [`RetentionSweeper`] enforce it
+//! periodically in the background.
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use super::{PersistentMemory, PersistentMemoryError};
+
+/// How long stored conversations are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    max_message_age: Option<Duration>,
+    max_session_idle: Option<Duration>,
+}
+
+/// What a single application of a [`RetentionPolicy`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// Individual messages deleted for exceeding the maximum age
+    pub expired_messages: usize,
+    /// Sessions deleted as a whole for being idle too long
+    pub expired_sessions: usize,
+}
+
+impl RetentionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delete any message written more than `age` ago.
+    pub fn max_message_age(mut self, age: Duration) -> Self {
+        self.max_message_age = Some(age);
+        self
+    }
+
+    /// Delete a whole session once nothing has been appended to it for `idle`.
+    pub fn max_session_idle(mut self, idle: Duration) -> Self {
+        self.max_session_idle = Some(idle);
+        self
+    }
+
+    /// Convenience for day-based policies, e.g. `RetentionPolicy::days(30)`.
+    pub fn days(days: u64) -> Self {
+        Self::new().max_message_age(Duration::from_secs(days * 24 * 60 * 60))
+    }
+
+    /// Enforce the policy on `store` now.
+    pub async fn apply(
+        &self,
+        store: &dyn PersistentMemory,
+    ) -> Result<RetentionReport, PersistentMemoryError> {
+        self.apply_at(store, SystemTime::now()).await
+    }
+
+    /// Enforce the policy as if the current time were `now`.
+    pub async fn apply_at(
+        &self,
+        store: &dyn PersistentMemory,
+        now: SystemTime,
+    ) -> Result<RetentionReport, PersistentMemoryError> {
+        let mut report = RetentionReport::default();
+        if let Some(idle) = self.max_session_idle {
+            report.expired_sessions = store.expire_sessions_idle_since(cutoff(now, idle)).await?;
+        }
+        if let Some(age) = self.max_message_age {
+            report.expired_messages = store.expire_messages_before(cutoff(now, age)).await?;
+        }
+        Ok(report)
+    }
+}
+
+fn cutoff(now: SystemTime, age: Duration) -> SystemTime {
+    now.checked_sub(age).unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Background task applying a [`RetentionPolicy`] at a fixed interval.
+///
+/// The first sweep runs immediately. Failures are logged and retried on the
+/// next tick. The task stops when the sweeper is dropped or
+/// [`stop`](Self::stop) is called.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct RetentionSweeper {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RetentionSweeper {
+    /// Start sweeping `store` every `interval`. Requires a tokio runtime.
+    pub fn spawn(
+        store: Arc<dyn PersistentMemory>,
+        policy: RetentionPolicy,
+        interval: Duration,
+    ) -> Self {
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match policy.apply(store.as_ref()).await {
+                    Ok(report) if report != RetentionReport::default() => {
+                        log::debug!("Memory retention sweep removed {report:?}");
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Memory retention sweep failed: {e}"),
+                }
+            }
+        });
+        Self { handle }
+    }
+
+    /// Abort the background task.
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for RetentionSweeper {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use autoagents_llm::chat::ChatMessage;
+    use std::sync::Mutex;
+
+    /// Records each expiry request and expires one item per call.
+    #[derive(Default)]
+    struct RecordingStore {
+        calls: Mutex<Vec<(&'static str, SystemTime)>>,
+    }
+
+    #[async_trait]
+    impl PersistentMemory for RecordingStore {
+        async fn append(&self, _: &str, _: &ChatMessage) -> Result<(), PersistentMemoryError> {
+            Ok(())
+        }
+
+        async fn load(
+            &self,
+            _: &str,
+            _: Option<usize>,
+        ) -> Result<Vec<ChatMessage>, PersistentMemoryError> {
+            Ok(Vec::new())
+        }
+
+        async fn clear(&self, _: &str) -> Result<(), PersistentMemoryError> {
+            Ok(())
+        }
+
+        async fn count(&self, _: &str) -> Result<usize, PersistentMemoryError> {
+            Ok(0)
+        }
+
+        async fn sessions(&self) -> Result<Vec<String>, PersistentMemoryError> {
+            Ok(Vec::new())
+        }
+
+        async fn expire_messages_before(
+            &self,
+            cutoff: SystemTime,
+        ) -> Result<usize, PersistentMemoryError> {
+            self.calls.lock().unwrap().push(("messages", cutoff));
+            Ok(1)
+        }
+
+        async fn expire_sessions_idle_since(
+            &self,
+            cutoff: SystemTime,
+        ) -> Result<usize, PersistentMemoryError> {
+            self.calls.lock().unwrap().push(("sessions", cutoff));
+            Ok(1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_policy_computes_cutoffs() {
+        let store = RecordingStore::default();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 24 * 60 * 60);
+        let policy = RetentionPolicy::days(30).max_session_idle(Duration::from_secs(60));
+
+        let report = policy.apply_at(&store, now).await.unwrap();
+        assert_eq!(
+            report,
+            RetentionReport {
+                expired_messages: 1,
+                expired_sessions: 1,
+            }
+        );
+        let calls = store.calls.lock().unwrap();
+        assert_eq!(calls[0], ("sessions", now - Duration::from_secs(60)));
+        assert_eq!(
+            calls[1],
+            (
+                "messages",
+                SystemTime::UNIX_EPOCH + Duration::from_secs(70 * 24 * 60 * 60)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_policy_does_nothing() {
+        let store = RecordingStore::default();
+        let report = RetentionPolicy::new().apply(&store).await.unwrap();
+        assert_eq!(report, RetentionReport::default());
+        assert!(store.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_runs_until_stopped() {
+        let store = Arc::new(RecordingStore::default());
+        let sweeper = RetentionSweeper::spawn(
+            store.clone(),
+            RetentionPolicy::days(1),
+            Duration::from_millis(10),
+        );
+        tokio::time::sleep(Duration::from_millis(35)).await;
+        sweeper.stop();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let sweeps = store.calls.lock().unwrap().len();
+        assert!(sweeps >= 2);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(store.calls.lock().unwrap().len(), sweeps);
+    }
+}
@@ -0,0 +1,321 @@
+//! Memory shared between the agents of a workflow.
+//!
+//! A [`SharedMemoryScope`] is a notice board that several agents can write to.
+//! Each agent gets a [`SharedMemory`] wrapping its own private memory; the
+//! agent's answers are published to the scope and notes written by the other
+//! agents are added to its system prompt. A researcher's findings therefore
+//! reach the writer without being pasted into the writer's task.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::error::LLMError;
+use std::sync::{Arc, RwLock};
+
+use super::{MemoryProvider, MemoryType, MessageCondition, MessageEvent};
+
+const DEFAULT_MAX_NOTES: usize = 10;
+
+/// An entry in a shared scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedNote {
+    /// Name of the agent that wrote the note
+    pub author: String,
+    pub content: String,
+}
+
+/// A memory scope visible to every agent holding a handle to it.
+///
+/// Cloning the scope gives another handle to the same notes.
+#[derive(Debug, Clone)]
+pub struct SharedMemoryScope {
+    name: String,
+    notes: Arc<RwLock<Vec<SharedNote>>>,
+}
+
+impl SharedMemoryScope {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            notes: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Write a note on behalf of `author`
+    pub fn publish(&self, author: impl Into<String>, content: impl Into<String>) {
+        self.notes
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(SharedNote {
+                author: author.into(),
+                content: content.into(),
+            });
+    }
+
+    /// All notes, oldest first
+    pub fn notes(&self) -> Vec<SharedNote> {
+        self.notes.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.notes
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Memory for the agent `agent`, keeping its own history in `private`.
+    pub fn memory_for(
+        &self,
+        agent: impl Into<String>,
+        private: Box<dyn MemoryProvider>,
+    ) -> SharedMemory {
+        SharedMemory {
+            agent: agent.into(),
+            private,
+            scope: self.clone(),
+            publish_when: MessageCondition::Any,
+            max_notes: DEFAULT_MAX_NOTES,
+        }
+    }
+}
+
+/// An agent's private memory joined to a [`SharedMemoryScope`].
+///
+/// Non-empty assistant text messages matching the publish condition are
+/// written to the scope under the agent's name. Recall only returns the
+/// private history; the latest `max_notes` notes from other agents are
+/// provided through [`MemoryProvider::system_context`].
+pub struct SharedMemory {
+    agent: String,
+    private: Box<dyn MemoryProvider>,
+    scope: SharedMemoryScope,
+    publish_when: MessageCondition,
+    max_notes: usize,
+}
+
+impl SharedMemory {
+    /// Only publish assistant messages matching `condition`
+    pub fn publish_when(mut self, condition: MessageCondition) -> Self {
+        self.publish_when = condition;
+        self
+    }
+
+    pub fn max_notes(mut self, max_notes: usize) -> Self {
+        self.max_notes = max_notes;
+        self
+    }
+
+    pub fn agent(&self) -> &str {
+        &self.agent
+    }
+
+    pub fn scope(&self) -> &SharedMemoryScope {
+        &self.scope
+    }
+
+    fn should_publish(&self, message: &ChatMessage) -> bool {
+        if message.role != ChatRole::Assistant
+            || !matches!(message.message_type, MessageType::Text)
+            || message.content.trim().is_empty()
+        {
+            return false;
+        }
+        self.publish_when.matches(&MessageEvent {
+            role: message.role.to_string(),
+            msg: message.clone(),
+        })
+    }
+
+    fn notes_from_others(&self) -> Vec<SharedNote> {
+        let mut notes: Vec<SharedNote> = self
+            .scope
+            .notes()
+            .into_iter()
+            .rev()
+            .filter(|note| note.author != self.agent)
+            .take(self.max_notes)
+            .collect();
+        notes.reverse();
+        notes
+    }
+}
+
+impl Clone for SharedMemory {
+    fn clone(&self) -> Self {
+        Self {
+            agent: self.agent.clone(),
+            private: self.private.clone_box(),
+            scope: self.scope.clone(),
+            publish_when: self.publish_when.clone(),
+            max_notes: self.max_notes,
+        }
+    }
+}
+
+impl std::fmt::Debug for SharedMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedMemory")
+            .field("agent", &self.agent)
+            .field("scope", &self.scope.name)
+            .field("private", &self.private.memory_type())
+            .field("max_notes", &self.max_notes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for SharedMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.private.remember(message).await?;
+        if self.should_publish(message) {
+            self.scope.publish(&self.agent, &message.content);
+        }
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.private.recall(query, limit).await
+    }
+
+    /// Clear the private history. The shared scope belongs to every agent
+    /// using it and is cleared with [`SharedMemoryScope::clear`].
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.private.clear().await
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Shared
+    }
+
+    fn size(&self) -> usize {
+        self.private.size()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn preload(&mut self, data: Vec<ChatMessage>) -> bool {
+        self.private.preload(data)
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.private.export()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.private.search(query, limit).await
+    }
+
+    async fn system_context(&self, query: &str) -> Result<Option<String>, LLMError> {
+        let private = self.private.system_context(query).await?;
+        let notes = self.notes_from_others();
+        if notes.is_empty() {
+            return Ok(private);
+        }
+
+        let mut context = format!("Notes shared by other agents ({}):", self.scope.name);
+        for note in notes {
+            context.push_str(&format!("\n- {}: {}", note.author, note.content));
+        }
+        Ok(Some(match private {
+            Some(private) => format!("{private}\n\n{context}"),
+            None => context,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::SlidingWindowMemory;
+
+    fn window() -> Box<dyn MemoryProvider> {
+        Box::new(SlidingWindowMemory::new(10))
+    }
+
+    fn assistant(content: &str) -> ChatMessage {
+        ChatMessage::assistant().content(content).build()
+    }
+
+    #[tokio::test]
+    async fn test_findings_are_visible_to_other_agents() {
+        let scope = SharedMemoryScope::new("report");
+        let mut researcher = scope.memory_for("researcher", window());
+        let mut writer = scope.memory_for("writer", window());
+
+        researcher
+            .remember(&ChatMessage::user().content("Research Rust").build())
+            .await
+            .unwrap();
+        researcher
+            .remember(&assistant("Rust 1.0 shipped in 2015"))
+            .await
+            .unwrap();
+        writer.remember(&assistant("Draft ready")).await.unwrap();
+
+        assert_eq!(scope.len(), 2);
+        assert_eq!(writer.size(), 1);
+        assert_eq!(
+            writer.system_context("").await.unwrap().unwrap(),
+            "Notes shared by other agents (report):\n- researcher: Rust 1.0 shipped in 2015"
+        );
+        // An agent does not see its own notes
+        assert_eq!(
+            researcher.system_context("").await.unwrap().unwrap(),
+            "Notes shared by other agents (report):\n- writer: Draft ready"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_condition_and_note_limit() {
+        let scope = SharedMemoryScope::new("team");
+        let mut researcher = scope
+            .memory_for("researcher", window())
+            .publish_when(MessageCondition::Contains("FINDING".into()));
+        let reader = scope.memory_for("reader", window()).max_notes(2);
+
+        for content in ["thinking...", "FINDING 1", "FINDING 2", "FINDING 3"] {
+            researcher.remember(&assistant(content)).await.unwrap();
+        }
+
+        let context = reader.system_context("").await.unwrap().unwrap();
+        assert!(!context.contains("thinking"));
+        assert!(!context.contains("FINDING 1"));
+        assert!(context.ends_with("- researcher: FINDING 2\n- researcher: FINDING 3"));
+    }
+
+    #[tokio::test]
+    async fn test_clear_keeps_shared_scope() {
+        let scope = SharedMemoryScope::new("team");
+        let mut agent = scope.memory_for("a", window());
+        agent.remember(&assistant("note")).await.unwrap();
+
+        agent.clear().await.unwrap();
+        assert!(agent.is_empty());
+        assert_eq!(scope.len(), 1);
+
+        scope.clear();
+        assert!(scope.is_empty());
+        assert_eq!(agent.memory_type(), MemoryType::Shared);
+    }
+}
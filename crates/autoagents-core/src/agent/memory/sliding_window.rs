@@ -1,17 +1,24 @@
 //! Simple sliding window memory implementation.
 //!
 //! This module provides a basic FIFO (First In, First Out) memory that maintains
-//! a fixed-size window of the most recent conversation messages.
+//! a fixed-size window of the most recent conversation messages. The message
+//! dropped on overflow can be chosen by a different [`EvictionPolicy`], and
+//! pinned messages are never dropped.
 use async_trait::async_trait;
-use autoagents_llm::{chat::ChatMessage, error::LLMError};
+use autoagents_llm::{
+    chat::{ChatMessage, ChatRole, MessageType},
+    error::LLMError,
+};
 use std::collections::VecDeque;
+use std::sync::Arc;
 
-use super::{MemoryProvider, MemoryType};
+use super::eviction::{EvictionCandidate, EvictionPolicy, FifoEviction, Tick};
+use super::{message_matches, MemoryProvider, MemoryType};
 
 /// Strategy for handling memory when window size limit is reached
 #[derive(Debug, Clone)]
 pub enum TrimStrategy {
-    /// Drop oldest messages (FIFO behavior)
+    /// Drop a message chosen by the eviction policy, the oldest by default
     Drop,
     /// Summarize all messages into one before adding new ones
     Summarize,
@@ -25,14 +32,27 @@ pub enum TrimStrategy {
 /// - Memory-constrained environments
 /// - Cases where only recent context matters
 ///
+/// Pinned messages, such as system facts, do not count as candidates for
+/// eviction; if every message is pinned the window grows past its size.
 #[derive(Debug, Clone)]
 pub struct SlidingWindowMemory {
     messages: VecDeque<ChatMessage>,
+    entries: VecDeque<Entry>,
     window_size: usize,
     trim_strategy: TrimStrategy,
+    eviction: Arc<dyn EvictionPolicy>,
+    clock: Tick,
     needs_summary: bool,
 }
 
+/// Bookkeeping for the message at the same position
+#[derive(Debug, Clone)]
+struct Entry {
+    inserted_at: u64,
+    last_used: Tick,
+    pinned: bool,
+}
+
 impl SlidingWindowMemory {
     /// Create a new sliding window memory with the specified window size.
     ///
@@ -61,12 +81,122 @@ impl SlidingWindowMemory {
 
         Self {
             messages: VecDeque::with_capacity(window_size),
+            entries: VecDeque::with_capacity(window_size),
             window_size,
             trim_strategy: strategy,
+            eviction: Arc::new(FifoEviction),
+            clock: Tick::default(),
             needs_summary: false,
         }
     }
 
+    /// Choose the message to drop on overflow with `policy` instead of
+    /// dropping the oldest one
+    pub fn with_eviction(mut self, policy: impl EvictionPolicy + 'static) -> Self {
+        self.eviction = Arc::new(policy);
+        self
+    }
+
+    pub fn eviction_policy(&self) -> &dyn EvictionPolicy {
+        self.eviction.as_ref()
+    }
+
+    /// Store `message` and protect it from eviction
+    pub fn remember_pinned(&mut self, message: ChatMessage) {
+        self.make_room();
+        self.push(message, true);
+    }
+
+    /// Store `fact` as a pinned system message
+    pub fn pin_fact(&mut self, fact: impl Into<String>) {
+        self.remember_pinned(ChatMessage {
+            role: ChatRole::System,
+            message_type: MessageType::Text,
+            content: fact.into(),
+            reasoning: Vec::new(),
+        });
+    }
+
+    /// Pin every stored message matching `predicate`. Returns how many
+    /// messages were newly pinned.
+    pub fn pin_where(&mut self, predicate: impl Fn(&ChatMessage) -> bool) -> usize {
+        self.set_pinned(predicate, true)
+    }
+
+    /// Unpin every stored message matching `predicate`. Returns how many
+    /// messages were unpinned.
+    pub fn unpin_where(&mut self, predicate: impl Fn(&ChatMessage) -> bool) -> usize {
+        self.set_pinned(predicate, false)
+    }
+
+    /// Pinned messages in chronological order
+    pub fn pinned_messages(&self) -> Vec<ChatMessage> {
+        self.messages
+            .iter()
+            .zip(&self.entries)
+            .filter(|(_, entry)| entry.pinned)
+            .map(|(message, _)| message.clone())
+            .collect()
+    }
+
+    fn set_pinned(&mut self, predicate: impl Fn(&ChatMessage) -> bool, pinned: bool) -> usize {
+        let mut changed = 0;
+        for (message, entry) in self.messages.iter().zip(self.entries.iter_mut()) {
+            if entry.pinned != pinned && predicate(message) {
+                entry.pinned = pinned;
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    fn push(&mut self, message: ChatMessage, pinned: bool) {
+        let now = self.clock.advance();
+        self.messages.push_back(message);
+        self.entries.push_back(Entry {
+            inserted_at: now,
+            last_used: Tick::new(now),
+            pinned,
+        });
+    }
+
+    /// Evict or mark for summary if the window is full
+    fn make_room(&mut self) {
+        if self.messages.len() < self.window_size {
+            return;
+        }
+        match self.trim_strategy {
+            TrimStrategy::Drop => self.evict(),
+            TrimStrategy::Summarize => self.mark_for_summary(),
+        }
+    }
+
+    /// Drop the unpinned message chosen by the eviction policy, if any
+    fn evict(&mut self) {
+        let (positions, candidates): (Vec<usize>, Vec<EvictionCandidate<'_>>) = self
+            .messages
+            .iter()
+            .zip(&self.entries)
+            .enumerate()
+            .filter(|(_, (_, entry))| !entry.pinned)
+            .map(|(position, (message, entry))| {
+                let candidate = EvictionCandidate {
+                    message,
+                    inserted_at: entry.inserted_at,
+                    last_used: entry.last_used.get(),
+                };
+                (position, candidate)
+            })
+            .unzip();
+        if candidates.is_empty() {
+            return;
+        }
+        let choice = self.eviction.select(&candidates).min(candidates.len() - 1);
+        let position = positions[choice];
+        self.messages.remove(position);
+        self.entries.remove(position);
+    }
+
     /// Get the configured window size.
     ///
     /// # Returns
@@ -110,15 +240,20 @@ impl SlidingWindowMemory {
         self.needs_summary = true;
     }
 
-    /// Replace all messages with a summary
+    /// Replace all unpinned messages with a summary
     ///
     /// # Arguments
     ///
-    /// * `summary` - The summary text to replace all messages with
+    /// * `summary` - The summary text to replace the messages with
     pub fn replace_with_summary(&mut self, summary: String) {
-        self.messages.clear();
-        self.messages
-            .push_back(ChatMessage::assistant().content(summary).build());
+        let (messages, entries) = std::mem::take(&mut self.messages)
+            .into_iter()
+            .zip(std::mem::take(&mut self.entries))
+            .filter(|(_, entry)| entry.pinned)
+            .unzip();
+        self.messages = messages;
+        self.entries = entries;
+        self.push(ChatMessage::assistant().content(summary).build(), false);
         self.needs_summary = false;
     }
 }
@@ -126,31 +261,29 @@ impl SlidingWindowMemory {
 #[async_trait]
 impl MemoryProvider for SlidingWindowMemory {
     async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
-        if self.messages.len() >= self.window_size {
-            match self.trim_strategy {
-                TrimStrategy::Drop => {
-                    self.messages.pop_front();
-                }
-                TrimStrategy::Summarize => {
-                    self.mark_for_summary();
-                }
-            }
-        }
-        self.messages.push_back(message.clone());
+        self.make_room();
+        self.push(message.clone(), false);
         Ok(())
     }
 
+    /// Recalled messages count as used for [`LruEviction`](super::LruEviction).
     async fn recall(
         &self,
         _query: &str,
         limit: Option<usize>,
     ) -> Result<Vec<ChatMessage>, LLMError> {
         let limit = limit.unwrap_or(self.messages.len());
+        let now = self.clock.advance();
+        let start = self.entries.len().saturating_sub(limit);
+        for entry in self.entries.range(start..) {
+            entry.last_used.set(now);
+        }
         Ok(self.recent_messages(limit))
     }
 
     async fn clear(&mut self) -> Result<(), LLMError> {
         self.messages.clear();
+        self.entries.clear();
         Ok(())
     }
 
@@ -171,20 +304,22 @@ impl MemoryProvider for SlidingWindowMemory {
     }
 
     fn replace_with_summary(&mut self, summary: String) {
-        self.messages.clear();
-        self.messages
-            .push_back(ChatMessage::assistant().content(summary).build());
-        self.needs_summary = false;
+        SlidingWindowMemory::replace_with_summary(self, summary);
     }
 
     fn clone_box(&self) -> Box<dyn MemoryProvider> {
         Box::new(self.clone())
     }
 
+    fn fork(&self) -> Option<Box<dyn MemoryProvider>> {
+        Some(Box::new(self.clone()))
+    }
+
     fn preload(&mut self, data: Vec<ChatMessage>) -> bool {
         self.messages.clear();
+        self.entries.clear();
         for msg in data {
-            self.messages.push_back(msg);
+            self.push(msg, false);
         }
         true
     }
@@ -192,6 +327,28 @@ impl MemoryProvider for SlidingWindowMemory {
     fn export(&self) -> Vec<ChatMessage> {
         Vec::from(self.messages.clone())
     }
+
+    /// Messages found count as used for [`LruEviction`](super::LruEviction).
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let query = query.to_lowercase();
+        let now = self.clock.advance();
+        Ok(self
+            .messages
+            .iter()
+            .zip(&self.entries)
+            .rev()
+            .filter(|(message, _)| message_matches(message, &query))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(message, entry)| {
+                entry.last_used.set(now);
+                message.clone()
+            })
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -229,6 +386,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "Hello".to_string(),
+            reasoning: Vec::new(),
         };
 
         memory.remember(&message).await.unwrap();
@@ -249,6 +407,7 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: format!("Message {i}"),
+                reasoning: Vec::new(),
             };
             memory.remember(&message).await.unwrap();
         }
@@ -270,6 +429,7 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: format!("Message {i}"),
+                reasoning: Vec::new(),
             };
             memory.remember(&message).await.unwrap();
         }
@@ -290,6 +450,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "First message".to_string(),
+            reasoning: Vec::new(),
         };
         memory.remember(&message1).await.unwrap();
 
@@ -298,6 +459,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "Second message".to_string(),
+            reasoning: Vec::new(),
         };
         memory.remember(&message2).await.unwrap();
 
@@ -306,6 +468,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "Third message".to_string(),
+            reasoning: Vec::new(),
         };
         memory.remember(&message3).await.unwrap();
 
@@ -322,6 +485,7 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: format!("Message {i}"),
+                reasoning: Vec::new(),
             };
             memory.remember(&message).await.unwrap();
         }
@@ -341,6 +505,7 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: format!("Message {i}"),
+                reasoning: Vec::new(),
             };
             memory.remember(&message).await.unwrap();
         }
@@ -359,6 +524,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "Test message".to_string(),
+            reasoning: Vec::new(),
         };
         memory.remember(&message).await.unwrap();
 
@@ -378,8 +544,9 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: format!("Message {i}"),
+                reasoning: Vec::new(),
             };
-            memory.messages.push_back(message);
+            memory.push(message, false);
         }
 
         let recent = memory.recent_messages(3);
@@ -398,8 +565,9 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: format!("Message {i}"),
+                reasoning: Vec::new(),
             };
-            memory.messages.push_back(message);
+            memory.push(message, false);
         }
 
         let recent = memory.recent_messages(10);
@@ -427,8 +595,9 @@ mod tests {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: format!("Message {i}"),
+                reasoning: Vec::new(),
             };
-            memory.messages.push_back(message);
+            memory.push(message, false);
         }
 
         memory.mark_for_summary();
@@ -455,4 +624,79 @@ mod tests {
         assert!(!memory.needs_summary());
         assert!(memory.get_event_receiver().is_none());
     }
+
+    #[tokio::test]
+    async fn test_search_matches_substring_newest_first() {
+        let mut memory = SlidingWindowMemory::new(10);
+        for content in ["Deploy to staging", "lunch?", "deploy to PROD done"] {
+            memory
+                .remember(&ChatMessage::user().content(content).build())
+                .await
+                .unwrap();
+        }
+
+        let found = memory.search("DEPLOY", None).await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].content, "deploy to PROD done");
+        assert_eq!(memory.search("deploy", Some(1)).await.unwrap().len(), 1);
+        assert!(memory.search("dinner", None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_messages_are_never_evicted() {
+        let mut memory = SlidingWindowMemory::new(3);
+        memory.pin_fact("User's name is Ada");
+        for content in ["one", "two", "three", "four"] {
+            memory
+                .remember(&ChatMessage::user().content(content).build())
+                .await
+                .unwrap();
+        }
+
+        let contents: Vec<String> = memory.messages().into_iter().map(|m| m.content).collect();
+        assert_eq!(contents, vec!["User's name is Ada", "three", "four"]);
+        assert_eq!(memory.pinned_messages()[0].role, ChatRole::System);
+
+        assert_eq!(memory.pin_where(|m| m.content == "three"), 1);
+        memory.replace_with_summary("summary".to_string());
+        assert_eq!(memory.size(), 3);
+        assert_eq!(memory.messages()[2].content, "summary");
+
+        assert_eq!(memory.unpin_where(|_| true), 2);
+        memory.pin_fact("new fact");
+        assert_eq!(memory.messages()[0].content, "three");
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction_keeps_searched_messages() {
+        use crate::agent::memory::LruEviction;
+
+        let mut memory = SlidingWindowMemory::new(3).with_eviction(LruEviction);
+        for content in ["deploy notes", "lunch?", "ok"] {
+            memory
+                .remember(&ChatMessage::user().content(content).build())
+                .await
+                .unwrap();
+        }
+        memory.search("deploy", None).await.unwrap();
+        memory
+            .remember(&ChatMessage::user().content("next").build())
+            .await
+            .unwrap();
+
+        let contents: Vec<String> = memory.messages().into_iter().map(|m| m.content).collect();
+        assert_eq!(contents, vec!["deploy notes", "ok", "next"]);
+        assert_eq!(memory.eviction_policy().name(), "lru");
+    }
+
+    #[tokio::test]
+    async fn test_window_grows_when_everything_is_pinned() {
+        let mut memory = SlidingWindowMemory::new(1);
+        memory.pin_fact("a");
+        memory
+            .remember(&ChatMessage::user().content("b").build())
+            .await
+            .unwrap();
+        assert_eq!(memory.size(), 2);
+    }
 }
@@ -0,0 +1,111 @@
+//! Versioned, backend-independent serialization of a conversation.
+use autoagents_llm::chat::ChatMessage;
+use autoagents_llm::error::LLMError;
+use serde::{Deserialize, Serialize};
+
+use super::MemoryType;
+
+/// Format version written by [`MemorySnapshot::new`]
+pub const MEMORY_SNAPSHOT_VERSION: u32 = 1;
+
+/// The messages held by a memory, as produced by
+/// [`MemoryProvider::save`](super::MemoryProvider::save).
+///
+/// Snapshots can be loaded into any memory type, which makes them suitable
+/// for archiving conversations, moving them between backends and replaying
+/// them in tests. `memory_type` records where the snapshot came from and is
+/// not checked on load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    pub version: u32,
+    pub memory_type: MemoryType,
+    pub messages: Vec<ChatMessage>,
+}
+
+impl MemorySnapshot {
+    pub fn new(memory_type: MemoryType, messages: Vec<ChatMessage>) -> Self {
+        Self {
+            version: MEMORY_SNAPSHOT_VERSION,
+            memory_type,
+            messages,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, LLMError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse a snapshot, rejecting versions newer than this library supports.
+    pub fn from_json(json: &str) -> Result<Self, LLMError> {
+        let snapshot: Self = serde_json::from_str(json)?;
+        snapshot.check_version()?;
+        Ok(snapshot)
+    }
+
+    pub(crate) fn check_version(&self) -> Result<(), LLMError> {
+        if self.version == 0 || self.version > MEMORY_SNAPSHOT_VERSION {
+            return Err(LLMError::InvalidRequest(format!(
+                "Unsupported memory snapshot version {} (supported: 1..={})",
+                self.version, MEMORY_SNAPSHOT_VERSION
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::{MemoryProvider, SlidingWindowMemory, TokenWindowMemory};
+    use autoagents_llm::tokenizer::HeuristicTokenCounter;
+    use std::sync::Arc;
+
+    async fn conversation() -> SlidingWindowMemory {
+        let mut memory = SlidingWindowMemory::new(10);
+        memory
+            .remember(&ChatMessage::user().content("Hi, I'm Ada").build())
+            .await
+            .unwrap();
+        memory
+            .remember(&ChatMessage::assistant().content("Hello Ada").build())
+            .await
+            .unwrap();
+        memory
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_json_round_trip() {
+        let snapshot = conversation().await.save().await.unwrap();
+        let json = snapshot.to_json().unwrap();
+        assert!(json.contains("\"version\":1"));
+
+        let parsed = MemorySnapshot::from_json(&json).unwrap();
+        assert_eq!(parsed.memory_type, MemoryType::SlidingWindow);
+        assert_eq!(parsed.messages.len(), 2);
+        assert_eq!(parsed.messages[1].content, "Hello Ada");
+    }
+
+    #[tokio::test]
+    async fn test_load_into_another_backend() {
+        let snapshot = conversation().await.save().await.unwrap();
+        let mut memory = TokenWindowMemory::new(Arc::new(HeuristicTokenCounter::default()), 1000);
+        memory
+            .remember(&ChatMessage::user().content("replaced").build())
+            .await
+            .unwrap();
+
+        memory.load(snapshot).await.unwrap();
+        let recalled = memory.recall("", None).await.unwrap();
+        assert_eq!(recalled.len(), 2);
+        assert_eq!(recalled[0].content, "Hi, I'm Ada");
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let json = r#"{"version": 99, "memory_type": "SlidingWindow", "messages": []}"#;
+        assert!(matches!(
+            MemorySnapshot::from_json(json),
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,364 @@
+//! SQLite-backed [`PersistentMemory`] store.
+//!
+//! Messages are kept in a single `memory_messages` table, serialized as JSON,
+//! in insertion order per session. The database runs in WAL mode with a busy
+//! timeout so several processes can share one file.
+use async_trait::async_trait;
+use autoagents_llm::chat::ChatMessage;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use super::{PersistentMemory, PersistentMemoryError};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS memory_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    message TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS memory_messages_session
+    ON memory_messages (session_id, id);
+CREATE INDEX IF NOT EXISTS memory_messages_created
+    ON memory_messages (created_at);
+";
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Milliseconds since the Unix epoch, as stored in `created_at`
+fn unix_millis(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
+
+impl From<rusqlite::Error> for PersistentMemoryError {
+    fn from(error: rusqlite::Error) -> Self {
+        PersistentMemoryError::Backend(error.to_string())
+    }
+}
+
+/// Conversation history stored in a SQLite database.
+///
+/// Cheap to clone; clones share the connection. Queries run on tokio's
+/// blocking pool, so a tokio runtime is required.
+#[derive(Clone)]
+pub struct SqliteMemory {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteMemory {
+    /// Open (or create) the database file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PersistentMemoryError> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        Self::with_connection(conn)
+    }
+
+    /// Private database that disappears with the last clone, mainly for tests.
+    pub fn in_memory() -> Result<Self, PersistentMemoryError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, PersistentMemoryError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn run<R, F>(&self, query: F) -> Result<R, PersistentMemoryError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&Connection) -> Result<R, PersistentMemoryError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            query(&conn)
+        })
+        .await
+        .map_err(|e| PersistentMemoryError::Backend(e.to_string()))?
+    }
+}
+
+impl std::fmt::Debug for SqliteMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self
+            .conn
+            .lock()
+            .ok()
+            .and_then(|conn| conn.path().map(str::to_string));
+        f.debug_struct("SqliteMemory").field("path", &path).finish()
+    }
+}
+
+#[async_trait]
+impl PersistentMemory for SqliteMemory {
+    async fn append(
+        &self,
+        session_id: &str,
+        message: &ChatMessage,
+    ) -> Result<(), PersistentMemoryError> {
+        let session_id = session_id.to_string();
+        let role = message.role.to_string();
+        let message = serde_json::to_string(message)?;
+        let created_at = unix_millis(SystemTime::now());
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT INTO memory_messages (session_id, role, message, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![session_id, role, message, created_at],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load(
+        &self,
+        session_id: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, PersistentMemoryError> {
+        let session_id = session_id.to_string();
+        // A negative LIMIT means no limit in SQLite
+        let limit = limit.map_or(-1, |limit| i64::try_from(limit).unwrap_or(i64::MAX));
+        self.run(move |conn| {
+            let mut statement = conn.prepare(
+                "SELECT message FROM (
+                    SELECT id, message FROM memory_messages
+                    WHERE session_id = ?1 ORDER BY id DESC LIMIT ?2
+                 ) ORDER BY id ASC",
+            )?;
+            let rows =
+                statement.query_map(params![session_id, limit], |row| row.get::<_, String>(0))?;
+            let mut messages = Vec::new();
+            for row in rows {
+                messages.push(serde_json::from_str(&row?)?);
+            }
+            Ok(messages)
+        })
+        .await
+    }
+
+    async fn clear(&self, session_id: &str) -> Result<(), PersistentMemoryError> {
+        let session_id = session_id.to_string();
+        self.run(move |conn| {
+            conn.execute(
+                "DELETE FROM memory_messages WHERE session_id = ?1",
+                params![session_id],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn count(&self, session_id: &str) -> Result<usize, PersistentMemoryError> {
+        let session_id = session_id.to_string();
+        self.run(move |conn| {
+            let count: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM memory_messages WHERE session_id = ?1",
+                params![session_id],
+                |row| row.get(0),
+            )?;
+            Ok(count as usize)
+        })
+        .await
+    }
+
+    async fn sessions(&self) -> Result<Vec<String>, PersistentMemoryError> {
+        self.run(|conn| {
+            let mut statement = conn
+                .prepare("SELECT DISTINCT session_id FROM memory_messages ORDER BY session_id")?;
+            let sessions = statement
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            Ok(sessions)
+        })
+        .await
+    }
+
+    async fn expire_messages_before(
+        &self,
+        cutoff: SystemTime,
+    ) -> Result<usize, PersistentMemoryError> {
+        let cutoff = unix_millis(cutoff);
+        self.run(move |conn| {
+            Ok(conn.execute(
+                "DELETE FROM memory_messages WHERE created_at < ?1",
+                params![cutoff],
+            )?)
+        })
+        .await
+    }
+
+    async fn expire_sessions_idle_since(
+        &self,
+        cutoff: SystemTime,
+    ) -> Result<usize, PersistentMemoryError> {
+        let cutoff = unix_millis(cutoff);
+        self.run(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let idle: Vec<String> = tx
+                .prepare(
+                    "SELECT session_id FROM memory_messages
+                     GROUP BY session_id HAVING MAX(created_at) < ?1",
+                )?
+                .query_map(params![cutoff], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            for session_id in &idle {
+                tx.execute(
+                    "DELETE FROM memory_messages WHERE session_id = ?1",
+                    params![session_id],
+                )?;
+            }
+            tx.commit()?;
+            Ok(idle.len())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::{MemoryProvider, PersistedMemory};
+    use autoagents_llm::chat::{ChatRole, MessageType};
+    use autoagents_llm::ToolCall;
+
+    #[tokio::test]
+    async fn test_history_survives_reopening_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.db");
+
+        let store = SqliteMemory::open(&path).unwrap();
+        store
+            .append("chat", &ChatMessage::user().content("hi").build())
+            .await
+            .unwrap();
+        store
+            .append("chat", &ChatMessage::assistant().content("hello").build())
+            .await
+            .unwrap();
+        drop(store);
+
+        let reopened = SqliteMemory::open(&path).unwrap();
+        let messages = reopened.load("chat", None).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "hi");
+        assert_eq!(messages[1].role, ChatRole::Assistant);
+    }
+
+    #[tokio::test]
+    async fn test_load_limit_and_sessions() {
+        let store = SqliteMemory::in_memory().unwrap();
+        for content in ["one", "two", "three"] {
+            store
+                .append("a", &ChatMessage::user().content(content).build())
+                .await
+                .unwrap();
+        }
+        store
+            .append("b", &ChatMessage::user().content("other").build())
+            .await
+            .unwrap();
+
+        let recent: Vec<_> = store
+            .load("a", Some(2))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(recent, vec!["two", "three"]);
+        assert_eq!(store.count("a").await.unwrap(), 3);
+        assert_eq!(store.sessions().await.unwrap(), vec!["a", "b"]);
+
+        store.clear("a").await.unwrap();
+        assert!(store.load("a", None).await.unwrap().is_empty());
+        assert_eq!(store.count("b").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tool_messages_round_trip() {
+        let store = SqliteMemory::in_memory().unwrap();
+        let call = ToolCall {
+            id: "call_1".into(),
+            call_type: "function".into(),
+            function: autoagents_llm::FunctionCall {
+                name: "search".into(),
+                arguments: "{}".into(),
+            },
+        };
+        let message = ChatMessage {
+            role: ChatRole::Assistant,
+            message_type: MessageType::ToolUse(vec![call.clone()]),
+            content: String::new(),
+            reasoning: Vec::new(),
+        };
+        store.append("tools", &message).await.unwrap();
+
+        let loaded = store.load("tools", None).await.unwrap();
+        assert_eq!(loaded[0].message_type, MessageType::ToolUse(vec![call]));
+    }
+
+    #[tokio::test]
+    async fn test_as_agent_memory() {
+        let store = Arc::new(SqliteMemory::in_memory().unwrap());
+        let mut memory = PersistedMemory::open(store.clone(), "agent").await.unwrap();
+        memory
+            .remember(&ChatMessage::user().content("remember me").build())
+            .await
+            .unwrap();
+
+        let resumed = PersistedMemory::open(store, "agent").await.unwrap();
+        assert_eq!(resumed.size(), 1);
+        assert_eq!(
+            resumed.recall("", None).await.unwrap()[0].content,
+            "remember me"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retention_policy_expires_old_data() {
+        use crate::agent::memory::{RetentionPolicy, RetentionReport};
+
+        let store = SqliteMemory::in_memory().unwrap();
+        for (session, content) in [("old", "a"), ("active", "b"), ("active", "c")] {
+            store
+                .append(session, &ChatMessage::user().content(content).build())
+                .await
+                .unwrap();
+        }
+        // Backdate the idle session and one message of the active one
+        let day = 24 * 60 * 60 * 1000;
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE memory_messages SET created_at = created_at - ?1
+                 WHERE session_id = 'old' OR message LIKE '%\"b\"%'",
+                params![10 * day],
+            )
+            .unwrap();
+
+        let report = RetentionPolicy::days(7)
+            .max_session_idle(Duration::from_secs(3 * 24 * 60 * 60))
+            .apply(&store)
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            RetentionReport {
+                expired_messages: 1,
+                expired_sessions: 1,
+            }
+        );
+        assert_eq!(store.sessions().await.unwrap(), vec!["active"]);
+        assert_eq!(store.load("active", None).await.unwrap()[0].content, "c");
+    }
+}
@@ -0,0 +1,328 @@
+//! Rolling-summary memory for long conversations.
+//!
+//! Recent messages are kept verbatim. Once the window overflows, the oldest
+//! half of it is folded into a running summary by a dedicated summarization
+//! LLM, so facts from early in the conversation are not simply dropped.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::error::LLMError;
+use autoagents_llm::LLMProvider;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use super::{MemoryProvider, MemoryType};
+
+const DEFAULT_SUMMARY_PROMPT: &str = "You maintain the memory of a conversation between a user \
+and an AI assistant. Update the existing summary with the new messages. Keep names, decisions, \
+preferences, open questions and any facts the assistant may need later; drop small talk. Reply \
+with the updated summary only.";
+
+/// Memory that keeps a recent window plus an LLM-written summary of
+/// everything older.
+///
+/// The summarization LLM is independent of the agent's LLM, so a smaller,
+/// cheaper model can be used. Recall returns the summary first, as an
+/// assistant message, followed by the verbatim window.
+#[derive(Clone)]
+pub struct SummaryMemory {
+    llm: Arc<dyn LLMProvider>,
+    window_size: usize,
+    messages: VecDeque<ChatMessage>,
+    summary: Option<String>,
+    prompt: String,
+}
+
+impl SummaryMemory {
+    /// Keep up to `window_size` messages verbatim, summarizing with `llm`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is less than 2
+    pub fn new(llm: Arc<dyn LLMProvider>, window_size: usize) -> Self {
+        assert!(window_size >= 2, "Window size must be at least 2");
+        Self {
+            llm,
+            window_size,
+            messages: VecDeque::with_capacity(window_size + 1),
+            summary: None,
+            prompt: DEFAULT_SUMMARY_PROMPT.to_string(),
+        }
+    }
+
+    /// Replace the instructions given to the summarization LLM
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Start from a summary produced earlier, e.g. restored from storage
+    pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Number of oldest messages to evict so the window has room again.
+    ///
+    /// Evicting half the window at once keeps summarization calls rare, and
+    /// tool results are never left without the call they answer.
+    fn eviction_count(&self) -> usize {
+        let mut count = self.messages.len() - self.window_size / 2;
+        while self
+            .messages
+            .get(count)
+            .is_some_and(|m| matches!(m.message_type, MessageType::ToolResult(_)))
+        {
+            count += 1;
+        }
+        count.min(self.messages.len() - 1)
+    }
+
+    async fn summarize(&self, evicted: &[ChatMessage]) -> Result<String, LLMError> {
+        let mut request = String::new();
+        if let Some(summary) = &self.summary {
+            let _ = writeln!(request, "Existing summary:\n{summary}\n");
+        }
+        request.push_str("New messages:\n");
+        for message in evicted {
+            let _ = writeln!(request, "{}", transcript_line(message));
+        }
+
+        let messages = [
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: self.prompt.clone(),
+                reasoning: Vec::new(),
+            },
+            ChatMessage::user().content(request).build(),
+        ];
+        let response = self.llm.chat(&messages, None, None).await?;
+        response
+            .text()
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| LLMError::ProviderError("Summarization returned no text".into()))
+    }
+
+    fn summary_message(&self) -> Option<ChatMessage> {
+        self.summary.as_ref().map(|summary| {
+            ChatMessage::assistant()
+                .content(format!("Summary of the earlier conversation:\n{summary}"))
+                .build()
+        })
+    }
+}
+
+fn transcript_line(message: &ChatMessage) -> String {
+    match &message.message_type {
+        MessageType::ToolUse(calls) => {
+            let calls: Vec<String> = calls
+                .iter()
+                .map(|c| format!("{}({})", c.function.name, c.function.arguments))
+                .collect();
+            format!("{}: [called {}]", message.role, calls.join(", "))
+        }
+        MessageType::ToolResult(results) => {
+            let results: Vec<String> = results
+                .iter()
+                .map(|r| format!("{} -> {}", r.function.name, r.function.arguments))
+                .collect();
+            format!("Tool: {}", results.join(", "))
+        }
+        MessageType::Image(_) | MessageType::ImageURL(_) => {
+            format!("{}: [image] {}", message.role, message.content)
+        }
+        MessageType::Parts(parts) => {
+            let text: Vec<&str> = std::iter::once(message.content.as_str())
+                .chain(parts.iter().map(|part| part.as_text().unwrap_or("[image]")))
+                .filter(|text| !text.is_empty())
+                .collect();
+            format!("{}: {}", message.role, text.join(" "))
+        }
+        MessageType::Pdf(_) => format!("{}: [pdf] {}", message.role, message.content),
+        MessageType::Text => format!("{}: {}", message.role, message.content),
+    }
+}
+
+impl std::fmt::Debug for SummaryMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SummaryMemory")
+            .field("window_size", &self.window_size)
+            .field("messages", &self.messages.len())
+            .field("summary", &self.summary)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for SummaryMemory {
+    /// Store the message, summarizing evicted messages if the window is full.
+    ///
+    /// If the summarization call fails the message is still stored and the
+    /// window temporarily grows; the next call retries.
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.messages.push_back(message.clone());
+        if self.messages.len() <= self.window_size {
+            return Ok(());
+        }
+
+        let count = self.eviction_count();
+        let evicted: Vec<ChatMessage> = self.messages.range(..count).cloned().collect();
+        let summary = self.summarize(&evicted).await?;
+        self.messages.drain(..count);
+        self.summary = Some(summary);
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        _query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let limit = limit.unwrap_or(self.messages.len());
+        let start = self.messages.len().saturating_sub(limit);
+        Ok(self
+            .summary_message()
+            .into_iter()
+            .chain(self.messages.range(start..).cloned())
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.messages.clear();
+        self.summary = None;
+        Ok(())
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Summary
+    }
+
+    fn size(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.summary_message()
+            .into_iter()
+            .chain(self.messages.iter().cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_llm::{FunctionCall, ToolCall};
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    fn tool_call(name: &str, arguments: &str) -> Vec<ToolCall> {
+        vec![ToolCall {
+            id: "call_1".into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: name.into(),
+                arguments: arguments.into(),
+            },
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_overflow_is_folded_into_summary() {
+        let llm = Arc::new(ScriptedLLMProvider::new(["User is Ada, likes tea"]));
+        let mut memory = SummaryMemory::new(llm.clone(), 4);
+        for content in ["I'm Ada", "I like tea", "ok", "noted", "what's my name?"] {
+            memory.remember(&user(content)).await.unwrap();
+        }
+
+        assert_eq!(llm.calls(), 1);
+        assert_eq!(memory.summary(), Some("User is Ada, likes tea"));
+        assert_eq!(memory.size(), 2);
+
+        let recalled = memory.recall("", None).await.unwrap();
+        assert_eq!(recalled.len(), 3);
+        assert!(recalled[0].content.ends_with("User is Ada, likes tea"));
+        assert_eq!(recalled[2].content, "what's my name?");
+
+        let request = &llm.last_messages()[1].content;
+        assert!(request.contains("User: I'm Ada"));
+        assert!(request.contains("User: ok"));
+        assert!(!request.contains("what's my name?"));
+    }
+
+    #[tokio::test]
+    async fn test_previous_summary_is_extended() {
+        let llm = Arc::new(ScriptedLLMProvider::new(["updated"]));
+        let mut memory = SummaryMemory::new(llm.clone(), 2).with_summary("earlier facts");
+        for content in ["a", "b", "c"] {
+            memory.remember(&user(content)).await.unwrap();
+        }
+
+        assert!(llm.last_messages()[1]
+            .content
+            .starts_with("Existing summary:\nearlier facts"));
+        assert_eq!(memory.summary(), Some("updated"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_stay_with_their_call() {
+        let llm = Arc::new(ScriptedLLMProvider::new(["summary"]));
+        let mut memory = SummaryMemory::new(llm.clone(), 4);
+        memory.remember(&user("hi")).await.unwrap();
+        memory.remember(&user("weather?")).await.unwrap();
+        memory
+            .remember(&ChatMessage {
+                role: ChatRole::Assistant,
+                message_type: MessageType::ToolUse(tool_call("weather", "{}")),
+                content: String::new(),
+                reasoning: Vec::new(),
+            })
+            .await
+            .unwrap();
+        memory
+            .remember(&ChatMessage {
+                role: ChatRole::Tool,
+                message_type: MessageType::ToolResult(tool_call("weather", "sunny")),
+                content: String::new(),
+                reasoning: Vec::new(),
+            })
+            .await
+            .unwrap();
+        memory.remember(&user("bye")).await.unwrap();
+
+        // Evicting half the window would have split the call from its result
+        assert_eq!(memory.size(), 1);
+        assert_eq!(memory.recall("", None).await.unwrap()[1].content, "bye");
+        assert!(llm.last_messages()[1]
+            .content
+            .contains("Tool: weather -> sunny"));
+    }
+
+    #[tokio::test]
+    async fn test_clear_drops_summary() {
+        let llm = Arc::new(ScriptedLLMProvider::new(["summary"]));
+        let mut memory = SummaryMemory::new(llm, 2).with_summary("old");
+        memory.remember(&user("hi")).await.unwrap();
+        memory.clear().await.unwrap();
+
+        assert!(memory.recall("", None).await.unwrap().is_empty());
+        assert_eq!(memory.memory_type(), MemoryType::Summary);
+    }
+}
@@ -0,0 +1,230 @@
+//! Sliding window memory bounded by tokens instead of message count.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, MessageType};
+use autoagents_llm::error::LLMError;
+use autoagents_llm::tokenizer::TokenCounter;
+use autoagents_llm::LLMProvider;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use super::{MemoryProvider, MemoryType};
+
+/// Keeps the most recent messages that fit in a token budget.
+///
+/// Unlike [`SlidingWindowMemory`](super::SlidingWindowMemory), a few large
+/// tool results evict older messages rather than overflowing the model's
+/// context. The newest message is always kept, even if it alone exceeds the
+/// budget, and tool results are evicted together with the call they answer.
+#[derive(Clone)]
+pub struct TokenWindowMemory {
+    counter: Arc<dyn TokenCounter>,
+    max_tokens: usize,
+    messages: VecDeque<(ChatMessage, usize)>,
+    used_tokens: usize,
+}
+
+impl TokenWindowMemory {
+    pub fn new(counter: Arc<dyn TokenCounter>, max_tokens: usize) -> Self {
+        Self {
+            counter,
+            max_tokens,
+            messages: VecDeque::new(),
+            used_tokens: 0,
+        }
+    }
+
+    /// Budget `max_tokens` using the tokenizer of `llm`'s model.
+    pub fn for_llm(llm: &dyn LLMProvider, max_tokens: usize) -> Self {
+        Self::new(llm.token_counter(), max_tokens)
+    }
+
+    pub fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+
+    /// Tokens taken by the messages currently held
+    pub fn used_tokens(&self) -> usize {
+        self.used_tokens
+    }
+
+    fn push(&mut self, message: ChatMessage) {
+        let tokens = self.counter.count_message(&message);
+        self.used_tokens += tokens;
+        self.messages.push_back((message, tokens));
+    }
+
+    fn pop_front(&mut self) {
+        if let Some((_, tokens)) = self.messages.pop_front() {
+            self.used_tokens -= tokens;
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.used_tokens > self.max_tokens && self.messages.len() > 1 {
+            self.pop_front();
+        }
+        // A tool result without its call is rejected by most providers
+        while self.messages.len() > 1
+            && self
+                .messages
+                .front()
+                .is_some_and(|(m, _)| matches!(m.message_type, MessageType::ToolResult(_)))
+        {
+            self.pop_front();
+        }
+    }
+}
+
+impl std::fmt::Debug for TokenWindowMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenWindowMemory")
+            .field("max_tokens", &self.max_tokens)
+            .field("used_tokens", &self.used_tokens)
+            .field("messages", &self.messages.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for TokenWindowMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.push(message.clone());
+        self.trim();
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        _query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let limit = limit.unwrap_or(self.messages.len());
+        let start = self.messages.len().saturating_sub(limit);
+        Ok(self
+            .messages
+            .range(start..)
+            .map(|(message, _)| message.clone())
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.messages.clear();
+        self.used_tokens = 0;
+        Ok(())
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::TokenWindow
+    }
+
+    fn size(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn preload(&mut self, data: Vec<ChatMessage>) -> bool {
+        self.messages.clear();
+        self.used_tokens = 0;
+        for message in data {
+            self.push(message);
+        }
+        self.trim();
+        true
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.messages.iter().map(|(m, _)| m.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_llm::chat::ChatRole;
+    use autoagents_llm::tokenizer::HeuristicTokenCounter;
+    use autoagents_llm::{FunctionCall, ToolCall};
+    use autoagents_test_utils::llm::MockLLMProvider;
+
+    /// One token per character plus the per-message overhead of 4
+    fn memory(max_tokens: usize) -> TokenWindowMemory {
+        TokenWindowMemory::new(Arc::new(HeuristicTokenCounter::new(1.0)), max_tokens)
+    }
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    fn tool_message(role: ChatRole, message_type: fn(Vec<ToolCall>) -> MessageType) -> ChatMessage {
+        ChatMessage {
+            role,
+            message_type: message_type(vec![ToolCall {
+                id: "1".into(),
+                call_type: "function".into(),
+                function: FunctionCall {
+                    name: "f".into(),
+                    arguments: "x".repeat(20),
+                },
+            }]),
+            content: String::new(),
+            reasoning: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_messages_evict_older_ones() {
+        let mut memory = memory(30);
+        memory.remember(&user("aaaaaa")).await.unwrap();
+        memory.remember(&user("bbbbbb")).await.unwrap();
+        assert_eq!(memory.used_tokens(), 20);
+
+        memory.remember(&user(&"c".repeat(16))).await.unwrap();
+        let contents: Vec<_> = memory
+            .recall("", None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(contents, vec!["b".repeat(6), "c".repeat(16)]);
+        assert_eq!(memory.used_tokens(), 30);
+    }
+
+    #[tokio::test]
+    async fn test_newest_message_kept_when_over_budget() {
+        let mut memory = memory(5);
+        memory.remember(&user("short")).await.unwrap();
+        memory.remember(&user(&"x".repeat(50))).await.unwrap();
+        assert_eq!(memory.size(), 1);
+        assert_eq!(memory.used_tokens(), 54);
+    }
+
+    #[tokio::test]
+    async fn test_tool_result_evicted_with_its_call() {
+        let mut memory = memory(60);
+        memory
+            .remember(&tool_message(ChatRole::Assistant, MessageType::ToolUse))
+            .await
+            .unwrap();
+        memory
+            .remember(&tool_message(ChatRole::Tool, MessageType::ToolResult))
+            .await
+            .unwrap();
+        memory.remember(&user(&"y".repeat(20))).await.unwrap();
+
+        let recalled = memory.recall("", None).await.unwrap();
+        assert_eq!(recalled.len(), 1);
+        assert_eq!(recalled[0].role, ChatRole::User);
+    }
+
+    #[tokio::test]
+    async fn test_for_llm_uses_provider_counter() {
+        let mut memory = TokenWindowMemory::for_llm(&MockLLMProvider, 100);
+        memory.remember(&user("abcdefgh")).await.unwrap();
+        // Default heuristic: 8 chars / 4 + overhead
+        assert_eq!(memory.used_tokens(), 6);
+        assert_eq!(memory.memory_type(), MemoryType::TokenWindow);
+    }
+}
@@ -0,0 +1,534 @@
+//! Compression of old tool results.
+//!
+//! Large tool outputs quickly dominate the context window. A
+//! [`CompressedToolMemory`] wraps any memory and, once a tool result is more
+//! than a configured number of user turns old, recalls it truncated or
+//! summarized instead. The full payload stays in a [`ToolResultArchive`]
+//! under a reference id mentioned in the compressed text, and the archive's
+//! [`tool`](ToolResultArchive::tool) lets the LLM fetch it again on demand.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::error::LLMError;
+use autoagents_llm::{LLMProvider, ToolCall};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::{MemoryProvider, MemorySnapshot, MemoryType};
+use crate::tool::{ToolCallError, ToolRuntime, ToolT};
+
+const DEFAULT_MAX_CHARS: usize = 1000;
+
+const SUMMARY_PROMPT: &str = "Summarize the following tool output for an AI assistant that \
+may need it later. Keep identifiers, numbers, names and conclusions; drop boilerplate. Reply \
+with the summary only.";
+
+#[derive(Debug, Default)]
+struct ArchiveState {
+    next_id: usize,
+    payloads: HashMap<String, String>,
+}
+
+/// Full payloads of compressed tool results, by reference id.
+///
+/// Cheap to clone; clones share the stored payloads.
+#[derive(Debug, Clone, Default)]
+pub struct ToolResultArchive {
+    state: Arc<Mutex<ArchiveState>>,
+}
+
+impl ToolResultArchive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Full payload stored under `reference`
+    pub fn get(&self, reference: &str) -> Option<String> {
+        self.lock().payloads.get(reference).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().payloads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.lock().payloads.clear();
+    }
+
+    /// Tool named `expand_tool_result` that returns the full payload for a
+    /// reference id, to register on the agent using the memory.
+    pub fn tool(&self) -> Box<dyn ToolT> {
+        Box::new(ExpandToolResult {
+            archive: self.clone(),
+        })
+    }
+
+    fn insert(&self, payload: String) -> String {
+        let mut state = self.lock();
+        state.next_id += 1;
+        let reference = format!("tool-result-{}", state.next_id);
+        state.payloads.insert(reference.clone(), payload);
+        reference
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ArchiveState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Tool giving the LLM access to a [`ToolResultArchive`].
+#[derive(Debug)]
+struct ExpandToolResult {
+    archive: ToolResultArchive,
+}
+
+#[async_trait]
+impl ToolRuntime for ExpandToolResult {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let reference = args
+            .get("reference")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolCallError::RuntimeError("Missing `reference` argument".into()))?;
+        let payload = self.archive.get(reference).ok_or_else(|| {
+            ToolCallError::RuntimeError(format!("Unknown tool result reference {reference}").into())
+        })?;
+        Ok(serde_json::from_str(&payload).unwrap_or(Value::String(payload)))
+    }
+}
+
+impl ToolT for ExpandToolResult {
+    fn name(&self) -> &'static str {
+        "expand_tool_result"
+    }
+
+    fn description(&self) -> &'static str {
+        "Return the full output of an earlier tool call that was shortened in the conversation, \
+         given the reference id shown in the shortened output."
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "reference": {
+                    "type": "string",
+                    "description": "Reference id of the shortened tool result"
+                }
+            },
+            "required": ["reference"]
+        })
+    }
+}
+
+/// A large tool result seen by the memory
+#[derive(Debug, Clone)]
+struct TrackedResult {
+    reference: String,
+    turn: usize,
+    compressed: Option<String>,
+}
+
+/// Memory middleware that compresses tool results older than a number of
+/// user turns.
+///
+/// Results longer than [`with_max_chars`](Self::with_max_chars) are recalled
+/// verbatim for `keep_turns` user turns, then truncated to that length, or
+/// summarized when a summarization LLM is configured. The wrapped memory
+/// always stores the full messages, so exports and snapshots are lossless.
+/// Clones share the archive.
+pub struct CompressedToolMemory {
+    inner: Box<dyn MemoryProvider>,
+    archive: ToolResultArchive,
+    keep_turns: usize,
+    max_chars: usize,
+    summarizer: Option<Arc<dyn LLMProvider>>,
+    turn: usize,
+    tracked: HashMap<(String, String), TrackedResult>,
+}
+
+impl CompressedToolMemory {
+    /// Wrap `inner`, keeping tool results of the last `keep_turns` user turns
+    /// verbatim.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keep_turns` is 0
+    pub fn new(inner: Box<dyn MemoryProvider>, keep_turns: usize) -> Self {
+        assert!(keep_turns > 0, "keep_turns must be greater than 0");
+        Self {
+            inner,
+            archive: ToolResultArchive::new(),
+            keep_turns,
+            max_chars: DEFAULT_MAX_CHARS,
+            summarizer: None,
+            turn: 0,
+            tracked: HashMap::new(),
+        }
+    }
+
+    /// Only compress results longer than `max_chars`, truncating them to
+    /// that length. Defaults to 1000.
+    pub fn with_max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = max_chars;
+        self
+    }
+
+    /// Summarize old results with `llm` instead of truncating them
+    pub fn with_summarizer(mut self, llm: Arc<dyn LLMProvider>) -> Self {
+        self.summarizer = Some(llm);
+        self
+    }
+
+    /// Store full payloads in `archive`, e.g. one shared with other memories
+    pub fn with_archive(mut self, archive: ToolResultArchive) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    pub fn archive(&self) -> &ToolResultArchive {
+        &self.archive
+    }
+
+    pub fn keep_turns(&self) -> usize {
+        self.keep_turns
+    }
+
+    fn observe(&mut self, message: &ChatMessage) {
+        match &message.message_type {
+            MessageType::ToolResult(results) => {
+                for result in results {
+                    self.track(result);
+                }
+            }
+            _ if message.role == ChatRole::User => self.turn += 1,
+            _ => {}
+        }
+    }
+
+    fn track(&mut self, result: &ToolCall) {
+        let payload = &result.function.arguments;
+        if payload.chars().count() <= self.max_chars {
+            return;
+        }
+        let key = (result.id.clone(), payload.clone());
+        if self.tracked.contains_key(&key) {
+            return;
+        }
+        let reference = self.archive.insert(payload.clone());
+        self.tracked.insert(
+            key,
+            TrackedResult {
+                reference,
+                turn: self.turn,
+                compressed: None,
+            },
+        );
+    }
+
+    fn is_aged(&self, result: &TrackedResult) -> bool {
+        self.turn - result.turn >= self.keep_turns
+    }
+
+    /// Compress every tracked result that has aged out of the kept turns.
+    ///
+    /// A failed summarization falls back to truncation rather than failing
+    /// the write, which has already reached the wrapped memory.
+    async fn compress_aged(&mut self) {
+        let aged: Vec<(String, String)> = self
+            .tracked
+            .iter()
+            .filter(|(_, result)| result.compressed.is_none() && self.is_aged(result))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in aged {
+            let (call_id, payload) = (&key.0, &key.1);
+            let excerpt = match &self.summarizer {
+                Some(llm) => match summarize(llm.as_ref(), payload).await {
+                    Ok(summary) => summary,
+                    Err(e) => {
+                        log::warn!("Failed to summarize tool result {call_id}: {e}");
+                        truncate(payload, self.max_chars)
+                    }
+                },
+                None => truncate(payload, self.max_chars),
+            };
+            if let Some(result) = self.tracked.get_mut(&key) {
+                result.compressed = Some(compressed_payload(&result.reference, payload, &excerpt));
+            }
+        }
+    }
+
+    /// `message` with aged tool results replaced by their compressed form
+    fn compress(&self, mut message: ChatMessage) -> ChatMessage {
+        if let MessageType::ToolResult(results) = &mut message.message_type {
+            for result in results {
+                let key = (result.id.clone(), result.function.arguments.clone());
+                if let Some(compressed) = self
+                    .tracked
+                    .get(&key)
+                    .and_then(|tracked| tracked.compressed.clone())
+                {
+                    result.function.arguments = compressed;
+                }
+            }
+        }
+        message
+    }
+}
+
+async fn summarize(llm: &dyn LLMProvider, payload: &str) -> Result<String, LLMError> {
+    let messages = [
+        ChatMessage {
+            role: ChatRole::System,
+            message_type: MessageType::Text,
+            content: SUMMARY_PROMPT.to_string(),
+            reasoning: Vec::new(),
+        },
+        ChatMessage::user().content(payload).build(),
+    ];
+    let response = llm.chat(&messages, None, None).await?;
+    response
+        .text()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| LLMError::ProviderError("Summarization returned no text".into()))
+}
+
+/// First `max_chars` characters of `payload`
+fn truncate(payload: &str, max_chars: usize) -> String {
+    let mut excerpt: String = payload.chars().take(max_chars).collect();
+    excerpt.push('…');
+    excerpt
+}
+
+fn compressed_payload(reference: &str, payload: &str, excerpt: &str) -> String {
+    format!(
+        "[Shortened tool output of {} characters; call expand_tool_result with reference \
+         \"{reference}\" for the full output]\n{excerpt}",
+        payload.chars().count()
+    )
+}
+
+impl Clone for CompressedToolMemory {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone_box(),
+            archive: self.archive.clone(),
+            keep_turns: self.keep_turns,
+            max_chars: self.max_chars,
+            summarizer: self.summarizer.clone(),
+            turn: self.turn,
+            tracked: self.tracked.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for CompressedToolMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompressedToolMemory")
+            .field("inner", &self.inner.memory_type())
+            .field("keep_turns", &self.keep_turns)
+            .field("max_chars", &self.max_chars)
+            .field("tracked", &self.tracked.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for CompressedToolMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.inner.remember(message).await?;
+        self.observe(message);
+        if message.role == ChatRole::User {
+            self.compress_aged().await;
+        }
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        Ok(self
+            .inner
+            .recall(query, limit)
+            .await?
+            .into_iter()
+            .map(|message| self.compress(message))
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.inner.clear().await?;
+        self.tracked.clear();
+        self.turn = 0;
+        self.archive.clear();
+        Ok(())
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        self.inner.memory_type()
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn id(&self) -> Option<String> {
+        self.inner.id()
+    }
+
+    /// Preload the wrapped memory and rebuild turn tracking from `data`.
+    ///
+    /// Aged results are compressed at the next user turn.
+    fn preload(&mut self, data: Vec<ChatMessage>) -> bool {
+        if !self.inner.preload(data.clone()) {
+            return false;
+        }
+        self.tracked.clear();
+        self.turn = 0;
+        for message in &data {
+            self.observe(message);
+        }
+        true
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.inner.export()
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        self.inner.search(query, limit).await
+    }
+
+    async fn save(&self) -> Result<MemorySnapshot, LLMError> {
+        self.inner.save().await
+    }
+
+    async fn system_context(&self, query: &str) -> Result<Option<String>, LLMError> {
+        self.inner.system_context(query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::SlidingWindowMemory;
+    use autoagents_llm::FunctionCall;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    fn tool_result(id: &str, output: &str) -> ChatMessage {
+        ChatMessage {
+            role: ChatRole::Tool,
+            message_type: MessageType::ToolResult(vec![ToolCall {
+                id: id.into(),
+                call_type: "function".into(),
+                function: FunctionCall {
+                    name: "fetch".into(),
+                    arguments: output.into(),
+                },
+            }]),
+            content: String::new(),
+            reasoning: Vec::new(),
+        }
+    }
+
+    fn payload(message: &ChatMessage) -> &str {
+        match &message.message_type {
+            MessageType::ToolResult(results) => &results[0].function.arguments,
+            _ => panic!("not a tool result"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_old_results_are_truncated_and_archived() {
+        let mut memory =
+            CompressedToolMemory::new(Box::new(SlidingWindowMemory::new(20)), 1).with_max_chars(10);
+        let long = "x".repeat(50);
+        memory.remember(&user("fetch it")).await.unwrap();
+        memory
+            .remember(&tool_result("call_1", &long))
+            .await
+            .unwrap();
+        memory
+            .remember(&tool_result("call_2", "short"))
+            .await
+            .unwrap();
+
+        let recalled = memory.recall("", None).await.unwrap();
+        assert_eq!(payload(&recalled[1]), long);
+
+        memory.remember(&user("thanks")).await.unwrap();
+        let recalled = memory.recall("", None).await.unwrap();
+        let compressed = payload(&recalled[1]);
+        assert!(compressed.contains("reference \"tool-result-1\""));
+        assert!(compressed.ends_with(&format!("{}…", "x".repeat(10))));
+        assert_eq!(payload(&recalled[2]), "short");
+
+        assert_eq!(
+            memory.archive().get("tool-result-1").as_deref(),
+            Some(&*long)
+        );
+        assert_eq!(payload(&memory.export()[1]), long);
+    }
+
+    #[tokio::test]
+    async fn test_summarizer_replaces_aged_results() {
+        let llm = Arc::new(ScriptedLLMProvider::new(["3 open issues, newest #42"]));
+        let mut memory = CompressedToolMemory::new(Box::new(SlidingWindowMemory::new(20)), 2)
+            .with_max_chars(10)
+            .with_summarizer(llm.clone());
+        memory.remember(&user("list issues")).await.unwrap();
+        memory
+            .remember(&tool_result("call_1", &"issue ".repeat(20)))
+            .await
+            .unwrap();
+        memory.remember(&user("and then?")).await.unwrap();
+        assert_eq!(llm.calls(), 0);
+
+        memory.remember(&user("summarize")).await.unwrap();
+        assert_eq!(llm.calls(), 1);
+        let recalled = memory.recall("", None).await.unwrap();
+        assert!(payload(&recalled[1]).ends_with("\n3 open issues, newest #42"));
+    }
+
+    #[tokio::test]
+    async fn test_expand_tool_returns_full_payload() {
+        let mut memory =
+            CompressedToolMemory::new(Box::new(SlidingWindowMemory::new(20)), 1).with_max_chars(5);
+        memory.remember(&user("go")).await.unwrap();
+        memory
+            .remember(&tool_result("call_1", r#"{"rows": [1, 2, 3]}"#))
+            .await
+            .unwrap();
+
+        let tool = memory.archive().tool();
+        assert_eq!(tool.name(), "expand_tool_result");
+        let result = tool
+            .execute(json!({"reference": "tool-result-1"}))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({"rows": [1, 2, 3]}));
+        assert!(tool.execute(json!({"reference": "nope"})).await.is_err());
+
+        memory.clear().await.unwrap();
+        assert!(memory.archive().is_empty());
+    }
+}
@@ -0,0 +1,514 @@
+//! Semantic memory that recalls messages by relevance instead of recency.
+//!
+//! Text messages are embedded as they are remembered and stored in a
+//! [`VectorIndex`]. On recall the query is embedded and the closest past
+//! messages are returned together with the latest few, in conversation order.
+//! With a [`Reranker`] set, a wider set of nearest messages is retrieved and
+//! reranked against the query, keeping the best.
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::embedding::EmbeddingProvider;
+use autoagents_llm::error::LLMError;
+use autoagents_llm::rerank::Reranker;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use super::{MemoryProvider, MemoryType};
+use crate::utils::cosine_similarity;
+
+const DEFAULT_TOP_K: usize = 5;
+const DEFAULT_RECENT: usize = 4;
+/// Candidates retrieved per message kept when reranking
+const RERANK_CANDIDATE_FACTOR: usize = 4;
+
+/// Nearest-neighbour search over message embeddings.
+///
+/// Entries are identified by the position of the message in the owning
+/// [`VectorMemory`]. Implement this to back the memory with an external
+/// vector database; [`InMemoryVectorIndex`] is used by default.
+#[async_trait]
+pub trait VectorIndex: Send + Sync {
+    async fn insert(&mut self, id: usize, embedding: Vec<f32>) -> Result<(), LLMError>;
+
+    /// The `top_k` entries most similar to `query` as `(id, score)`, best first.
+    async fn search(&self, query: &[f32], top_k: usize) -> Result<Vec<(usize, f32)>, LLMError>;
+
+    async fn clear(&mut self) -> Result<(), LLMError>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clone_box(&self) -> Box<dyn VectorIndex>;
+
+    /// An independent copy of the index, or `None` (the default) when
+    /// clones share their entries, as with an external database.
+    fn fork(&self) -> Option<Box<dyn VectorIndex>> {
+        None
+    }
+}
+
+/// Exhaustive cosine-similarity search, fine for a conversation's worth of
+/// messages.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryVectorIndex {
+    entries: Vec<(usize, Vec<f32>)>,
+}
+
+impl InMemoryVectorIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl VectorIndex for InMemoryVectorIndex {
+    async fn insert(&mut self, id: usize, embedding: Vec<f32>) -> Result<(), LLMError> {
+        self.entries.push((id, embedding));
+        Ok(())
+    }
+
+    async fn search(&self, query: &[f32], top_k: usize) -> Result<Vec<(usize, f32)>, LLMError> {
+        let mut scored: Vec<(usize, f32)> = self
+            .entries
+            .iter()
+            .map(|(id, embedding)| (*id, cosine_similarity(query, embedding)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(top_k);
+        Ok(scored)
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.entries.clear();
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn VectorIndex> {
+        Box::new(self.clone())
+    }
+
+    fn fork(&self) -> Option<Box<dyn VectorIndex>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Memory that retrieves the past messages most relevant to the query.
+///
+/// The built-in executors recall with an empty query; in that case the most
+/// recent user message is used as the query. Only text messages are
+/// embedded, so tool calls and results are returned only while they are
+/// among the `recent` latest messages.
+pub struct VectorMemory {
+    embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    index: Box<dyn VectorIndex>,
+    messages: Vec<ChatMessage>,
+    top_k: usize,
+    recent: usize,
+    reranker: Option<Arc<dyn Reranker>>,
+}
+
+impl VectorMemory {
+    pub fn new(embedder: Arc<dyn EmbeddingProvider + Send + Sync>) -> Self {
+        Self {
+            embedder,
+            index: Box::new(InMemoryVectorIndex::new()),
+            messages: Vec::new(),
+            top_k: DEFAULT_TOP_K,
+            recent: DEFAULT_RECENT,
+            reranker: None,
+        }
+    }
+
+    /// Replace the default in-memory index; the index should be empty.
+    pub fn with_index(mut self, index: Box<dyn VectorIndex>) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Number of relevant messages to retrieve when recall gives no limit
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Number of latest messages always included regardless of relevance
+    pub fn recent(mut self, recent: usize) -> Self {
+        self.recent = recent;
+        self
+    }
+
+    /// Retrieve four times as many messages as asked for and keep those
+    /// `reranker` scores highest against the query
+    pub fn reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    fn latest_user_message(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| m.role == ChatRole::User && !m.content.is_empty())
+            .map(|m| m.content.as_str())
+    }
+
+    async fn embed_one(&self, text: &str) -> Result<Vec<f32>, LLMError> {
+        self.embedder
+            .embed(vec![text.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("Embedder returned no embedding".into()))
+    }
+
+    /// Ids of the `top_k` messages most relevant to `query`, best first
+    async fn relevant(&self, query: &str, top_k: usize) -> Result<Vec<usize>, LLMError> {
+        let embedding = self.embed_one(query).await?;
+        let Some(reranker) = &self.reranker else {
+            let hits = self.index.search(&embedding, top_k).await?;
+            return Ok(hits.into_iter().map(|(id, _)| id).collect());
+        };
+
+        let candidates: Vec<usize> = self
+            .index
+            .search(&embedding, top_k.saturating_mul(RERANK_CANDIDATE_FACTOR))
+            .await?
+            .into_iter()
+            .map(|(id, _)| id)
+            .filter(|id| *id < self.messages.len())
+            .collect();
+        let documents: Vec<String> = candidates
+            .iter()
+            .map(|id| self.messages[*id].content.clone())
+            .collect();
+        let ranked = reranker.rerank(query, &documents, Some(top_k)).await?;
+        Ok(ranked
+            .into_iter()
+            .filter_map(|result| candidates.get(result.index).copied())
+            .take(top_k)
+            .collect())
+    }
+}
+
+fn is_embeddable(message: &ChatMessage) -> bool {
+    matches!(message.message_type, MessageType::Text) && !message.content.trim().is_empty()
+}
+
+impl Clone for VectorMemory {
+    fn clone(&self) -> Self {
+        Self {
+            embedder: self.embedder.clone(),
+            index: self.index.clone_box(),
+            messages: self.messages.clone(),
+            top_k: self.top_k,
+            recent: self.recent,
+            reranker: self.reranker.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for VectorMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VectorMemory")
+            .field("messages", &self.messages.len())
+            .field("indexed", &self.index.len())
+            .field("top_k", &self.top_k)
+            .field("recent", &self.recent)
+            .field("reranked", &self.reranker.is_some())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for VectorMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        let id = self.messages.len();
+        self.messages.push(message.clone());
+        if is_embeddable(message) {
+            let embedding = self.embed_one(&message.content).await?;
+            self.index.insert(id, embedding).await?;
+        }
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let recent_start = self.messages.len().saturating_sub(self.recent);
+        let mut selected: BTreeSet<usize> = (recent_start..self.messages.len()).collect();
+
+        let query = match query {
+            "" => self.latest_user_message(),
+            query => Some(query),
+        };
+        if let Some(query) = query.filter(|_| !self.index.is_empty()) {
+            let top_k = limit.unwrap_or(self.top_k);
+            selected.extend(self.relevant(query, top_k).await?);
+        }
+
+        Ok(selected
+            .into_iter()
+            .filter_map(|id| self.messages.get(id).cloned())
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.messages.clear();
+        self.index.clear().await
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Vector
+    }
+
+    fn size(&self) -> usize {
+        self.messages.len()
+    }
+
+    fn clone_box(&self) -> Box<dyn MemoryProvider> {
+        Box::new(self.clone())
+    }
+
+    fn fork(&self) -> Option<Box<dyn MemoryProvider>> {
+        Some(Box::new(Self {
+            embedder: self.embedder.clone(),
+            index: self.index.fork()?,
+            messages: self.messages.clone(),
+            top_k: self.top_k,
+            recent: self.recent,
+            reranker: self.reranker.clone(),
+        }))
+    }
+
+    fn export(&self) -> Vec<ChatMessage> {
+        self.messages.clone()
+    }
+
+    /// The messages closest to `query` by embedding similarity, or by the
+    /// reranker's score when one is set, `top_k` when no limit is given.
+    async fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        if query.trim().is_empty() || self.index.is_empty() {
+            return Ok(Vec::new());
+        }
+        let top_k = limit.unwrap_or(self.top_k);
+        Ok(self
+            .relevant(query, top_k)
+            .await?
+            .into_iter()
+            .filter_map(|id| self.messages.get(id).cloned())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::MemoryCheckpoint;
+    use autoagents_llm::rerank::RerankResult;
+    use autoagents_llm::ToolCall;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Mutex;
+
+    /// Embeds text as counts of a few topic words.
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    ["cat", "rust", "pizza"]
+                        .iter()
+                        .map(|topic| text.matches(topic).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    fn contents(messages: &[ChatMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    async fn memory_with(messages: &[&str]) -> VectorMemory {
+        let mut memory = VectorMemory::new(Arc::new(TopicEmbedder))
+            .top_k(1)
+            .recent(1);
+        for content in messages {
+            memory.remember(&user(content)).await.unwrap();
+        }
+        memory
+    }
+
+    #[tokio::test]
+    async fn test_recall_returns_relevant_and_recent_in_order() {
+        let memory = memory_with(&[
+            "my cat is called Tom",
+            "I love pizza",
+            "rust has lifetimes",
+            "what time is it",
+        ])
+        .await;
+
+        let recalled = memory.recall("tell me about the cat", None).await.unwrap();
+        assert_eq!(
+            contents(&recalled),
+            vec!["my cat is called Tom", "what time is it"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_query_uses_latest_user_message() {
+        let memory = memory_with(&["rust has lifetimes", "I love pizza", "more rust please"]).await;
+
+        let recalled = memory.recall("", None).await.unwrap();
+        // The latest message is both the query and the recent window
+        assert_eq!(
+            contents(&recalled),
+            vec!["rust has lifetimes", "more rust please"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_by_similarity() {
+        let memory = memory_with(&["rust rust", "a cat", "rust and a cat", "pizza"]).await;
+
+        let found = memory.search("rust", Some(2)).await.unwrap();
+        assert_eq!(contents(&found), vec!["rust rust", "rust and a cat"]);
+        assert!(memory.search("", None).await.unwrap().is_empty());
+    }
+
+    /// Scores shorter documents higher.
+    struct ShortestFirst;
+
+    #[async_trait]
+    impl Reranker for ShortestFirst {
+        async fn rerank(
+            &self,
+            _query: &str,
+            documents: &[String],
+            top_n: Option<usize>,
+        ) -> Result<Vec<RerankResult>, LLMError> {
+            let mut results: Vec<RerankResult> = documents
+                .iter()
+                .enumerate()
+                .map(|(index, document)| RerankResult {
+                    index,
+                    score: -(document.len() as f32),
+                })
+                .collect();
+            autoagents_llm::rerank::sort_results(&mut results);
+            results.truncate(top_n.unwrap_or(results.len()));
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reranker_reorders_candidates() {
+        let mut memory = VectorMemory::new(Arc::new(TopicEmbedder))
+            .recent(0)
+            .reranker(Arc::new(ShortestFirst));
+        for content in ["rust rust", "rust and a cat", "rust", "pizza"] {
+            memory.remember(&user(content)).await.unwrap();
+        }
+
+        let found = memory.search("rust", Some(1)).await.unwrap();
+        assert_eq!(contents(&found), vec!["rust"]);
+        let recalled = memory.recall("rust", Some(1)).await.unwrap();
+        assert_eq!(contents(&recalled), vec!["rust"]);
+    }
+
+    #[tokio::test]
+    async fn test_tool_messages_are_not_embedded() {
+        let mut memory = VectorMemory::new(Arc::new(TopicEmbedder)).recent(0);
+        memory.remember(&user("a cat")).await.unwrap();
+        memory
+            .remember(&ChatMessage {
+                role: ChatRole::Assistant,
+                message_type: MessageType::ToolUse(vec![ToolCall {
+                    id: "1".into(),
+                    call_type: "function".into(),
+                    function: autoagents_llm::FunctionCall {
+                        name: "cat_facts".into(),
+                        arguments: "{}".into(),
+                    },
+                }]),
+                content: "cat".into(),
+                reasoning: Vec::new(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(memory.size(), 2);
+        assert_eq!(memory.index.len(), 1);
+        assert_eq!(memory.recall("cat", Some(5)).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_clear_and_clone() {
+        let mut memory = memory_with(&["a cat"]).await;
+        let snapshot = memory.clone_box();
+        memory.clear().await.unwrap();
+
+        assert!(memory.is_empty());
+        assert!(memory.recall("cat", None).await.unwrap().is_empty());
+        assert_eq!(snapshot.recall("cat", None).await.unwrap().len(), 1);
+        assert_eq!(memory.memory_type(), MemoryType::Vector);
+    }
+
+    /// Counts the texts embedded by [`TopicEmbedder`].
+    #[derive(Default)]
+    struct CountingEmbedder(AtomicUsize);
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingEmbedder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            self.0.fetch_add(input.len(), Ordering::SeqCst);
+            TopicEmbedder.embed(input).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollback_does_not_embed_again() {
+        let embedder = Arc::new(CountingEmbedder::default());
+        let mut memory = VectorMemory::new(embedder.clone()).top_k(1).recent(1);
+        memory.remember(&user("a cat")).await.unwrap();
+        memory.remember(&user("rust code")).await.unwrap();
+        let memory: Arc<Mutex<Box<dyn MemoryProvider>>> = Arc::new(Mutex::new(Box::new(memory)));
+
+        let checkpoint = MemoryCheckpoint::take(memory.clone()).await.unwrap();
+        memory
+            .lock()
+            .await
+            .remember(&user("pizza and a cat"))
+            .await
+            .unwrap();
+        checkpoint.rollback().await.unwrap();
+        assert_eq!(embedder.0.load(Ordering::SeqCst), 3);
+
+        let recalled = memory.lock().await.recall("cat", None).await.unwrap();
+        assert_eq!(contents(&recalled), ["a cat", "rust code"]);
+    }
+}
@@ -1,10 +1,15 @@
 // Runtime-independent modules (available on all platforms)
 mod config;
+pub mod definition;
 pub mod error;
+pub mod few_shot;
+pub mod guardrail;
 pub mod memory;
 mod output;
+pub mod prompt;
 mod protocol;
 pub mod task;
+pub mod transcript;
 
 pub mod prebuilt;
 
@@ -22,7 +27,11 @@ mod actor;
 pub(crate) mod constants;
 mod direct;
 mod hooks;
+pub mod registry;
 mod state;
+pub mod subagent;
+#[cfg(not(target_arch = "wasm32"))]
+mod supervisor;
 
 pub use actor::ActorAgent;
 #[cfg(not(target_arch = "wasm32"))]
@@ -30,9 +39,20 @@ pub use actor::ActorAgentHandle;
 pub use base::{AgentDeriveT, BaseAgent};
 pub use builder::AgentBuilder;
 pub use context::{Context, ContextError};
+pub use definition::{AgentDefinition, AgentLoader, DeclarativeAgent, LoadedAgent};
 pub use direct::{DirectAgent, DirectAgentHandle};
 pub use executor::{
     event_helper::EventHelper, memory_helper::MemoryHelper, tool_processor::ToolProcessor,
-    AgentExecutor, ExecutorConfig, TurnResult,
+    validation, AgentExecutor, ExecutorConfig, TurnResult, ValidationPolicy,
 };
+pub use few_shot::{EmbeddingExampleSelector, Example, ExampleSelector, FewShotExamples};
+pub use guardrail::{Guardrail, GuardrailChain, GuardrailOutcome, GuardrailStage};
 pub use hooks::{AgentHooks, HookOutcome};
+pub use prompt::{
+    PromptSection, PromptTemplate, PromptTemplateError, PromptVariables, SystemPromptBuilder,
+};
+pub use registry::{AgentEntry, AgentRegistry, AgentRegistryError, RunnableAgent};
+pub use subagent::{SubAgent, SubAgentError, SubAgentLimits, SubAgentMemory};
+#[cfg(not(target_arch = "wasm32"))]
+pub use supervisor::RestartStrategy;
+pub use transcript::{Transcript, TranscriptFormat};
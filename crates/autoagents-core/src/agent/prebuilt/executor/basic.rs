@@ -1,9 +1,13 @@
+use crate::agent::executor::accumulate_usage;
 use crate::agent::hooks::HookOutcome;
 use crate::agent::task::Task;
-use crate::agent::{AgentDeriveT, AgentExecutor, AgentHooks, Context, EventHelper, ExecutorConfig};
-use crate::tool::{ToolCallResult, ToolT};
+use crate::agent::{
+    validation, AgentDeriveT, AgentExecutor, AgentHooks, Context, EventHelper, ExecutorConfig,
+    SystemPromptBuilder, ValidationPolicy,
+};
+use crate::tool::{ToolCallResult, ToolProgress, ToolT};
 use async_trait::async_trait;
-use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::chat::{ChatMessage, ChatRole, Citation, MessageType, Usage};
 use autoagents_llm::ToolCall;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
@@ -17,6 +21,14 @@ use std::sync::Arc;
 pub struct BasicAgentOutput {
     pub response: String,
     pub done: bool,
+    /// Sources the response cites, for providers that ground their answers
+    /// in documents or tool results
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<Citation>,
+    /// Tokens used by the run, summed over its LLM calls. When streaming,
+    /// only the final chunk carries it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 impl From<BasicAgentOutput> for Value {
@@ -38,18 +50,23 @@ pub enum BasicExecutorError {
 
     #[error("Other error: {0}")]
     Other(String),
+
+    #[error("Output failed schema validation after {attempts} repair attempts: {errors}")]
+    OutputValidationError { attempts: usize, errors: String },
 }
 
 /// Wrapper type for Basic executor
 #[derive(Debug)]
 pub struct BasicAgent<T: AgentDeriveT> {
     inner: Arc<T>,
+    validation: ValidationPolicy,
 }
 
 impl<T: AgentDeriveT> Clone for BasicAgent<T> {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
+            validation: self.validation,
         }
     }
 }
@@ -58,8 +75,16 @@ impl<T: AgentDeriveT> BasicAgent<T> {
     pub fn new(inner: T) -> Self {
         Self {
             inner: Arc::new(inner),
+            validation: ValidationPolicy::default(),
         }
     }
+
+    /// Re-prompt with the schema errors when the response does not match the
+    /// agent's output schema. Streaming is not validated.
+    pub fn with_validation(mut self, policy: ValidationPolicy) -> Self {
+        self.validation = policy;
+        self
+    }
 }
 
 impl<T: AgentDeriveT> Deref for BasicAgent<T> {
@@ -105,6 +130,10 @@ where
         self.inner.on_run_start(task, ctx).await
     }
 
+    async fn on_system_prompt(&self, prompt: &mut SystemPromptBuilder, ctx: &Context) {
+        self.inner.on_system_prompt(prompt, ctx).await
+    }
+
     async fn on_run_complete(&self, task: &Task, result: &Self::Output, ctx: &Context) {
         self.inner.on_run_complete(task, result, ctx).await
     }
@@ -125,6 +154,10 @@ where
         self.inner.on_tool_start(tool_call, ctx).await
     }
 
+    async fn on_tool_progress(&self, tool_call: &ToolCall, progress: &ToolProgress, ctx: &Context) {
+        self.inner.on_tool_progress(tool_call, progress, ctx).await
+    }
+
     async fn on_tool_result(&self, tool_call: &ToolCall, result: &ToolCallResult, ctx: &Context) {
         self.inner.on_tool_result(tool_call, result, ctx).await
     }
@@ -139,12 +172,16 @@ where
 
 /// Implementation of AgentExecutor for the BasicExecutorWrapper
 #[async_trait]
-impl<T: AgentDeriveT> AgentExecutor for BasicAgent<T> {
+impl<T: AgentDeriveT + AgentHooks> AgentExecutor for BasicAgent<T> {
     type Output = BasicAgentOutput;
     type Error = BasicExecutorError;
 
     fn config(&self) -> ExecutorConfig {
-        ExecutorConfig { max_turns: 1 }
+        ExecutorConfig {
+            max_turns: 1,
+            validation: self.validation,
+            ..Default::default()
+        }
     }
 
     async fn execute(
@@ -156,44 +193,71 @@ impl<T: AgentDeriveT> AgentExecutor for BasicAgent<T> {
         EventHelper::send_task_started(
             &tx_event,
             task.submission_id,
+            context.run_id(),
             context.config().id,
             task.prompt.clone(),
             context.config().name.clone(),
+            context.metadata().clone(),
         )
         .await;
 
+        let mut prompt = context.system_prompt_builder();
+        self.on_system_prompt(&mut prompt, &context).await;
         let mut messages = vec![ChatMessage {
             role: ChatRole::System,
             message_type: MessageType::Text,
-            content: context.config().description.clone(),
+            content: prompt.build(),
+            reasoning: Vec::new(),
         }];
 
-        let chat_msg = if let Some((mime, image_data)) = &task.image {
-            // Task has an image, create an Image message
-            ChatMessage {
-                role: ChatRole::User,
-                message_type: MessageType::Image((*mime, image_data.clone())),
-                content: task.prompt.clone(),
+        messages.extend(task.user_messages());
+        let output_schema = context.config().output_schema.clone();
+        let schema = output_schema
+            .as_ref()
+            .and_then(|format| format.schema.as_ref())
+            .filter(|_| self.validation.is_enabled());
+
+        let mut repairs = 0;
+        let mut usage = None;
+        loop {
+            let response = context
+                .llm()
+                .chat(&messages, None, output_schema.clone())
+                .await
+                .map_err(|e| BasicExecutorError::LLMError(e.to_string()))?;
+            let response_text = response.text().unwrap_or_default();
+            accumulate_usage(&mut usage, response.usage());
+
+            let Some(Err(errors)) =
+                schema.map(|schema| validation::validate_output(schema, &response_text))
+            else {
+                return Ok(BasicAgentOutput {
+                    response: response_text,
+                    done: true,
+                    citations: response.citations().unwrap_or_default(),
+                    usage,
+                });
+            };
+            if repairs >= self.validation.max_retries() {
+                return Err(BasicExecutorError::OutputValidationError {
+                    attempts: repairs,
+                    errors: errors.join("; "),
+                });
             }
-        } else {
-            // Text-only task
-            ChatMessage {
+            repairs += 1;
+            messages.push(ChatMessage {
+                role: ChatRole::Assistant,
+                message_type: MessageType::Text,
+                content: response_text,
+                reasoning: Vec::new(),
+            });
+            messages.push(ChatMessage {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
-                content: task.prompt.clone(),
-            }
-        };
-        messages.push(chat_msg);
-        let response = context
-            .llm()
-            .chat(&messages, None, context.config().output_schema.clone())
-            .await
-            .map_err(|e| BasicExecutorError::LLMError(e.to_string()))?;
-        let response_text = response.text().unwrap_or_default();
-        Ok(BasicAgentOutput {
-            response: response_text,
-            done: true,
-        })
+                content: validation::repair_prompt(&errors),
+                reasoning: Vec::new(),
+            });
+        }
     }
 
     async fn execute_stream(
@@ -208,34 +272,24 @@ impl<T: AgentDeriveT> AgentExecutor for BasicAgent<T> {
         EventHelper::send_task_started(
             &tx_event,
             task.submission_id,
+            context.run_id(),
             context.config().id,
             task.prompt.clone(),
             context.config().name.clone(),
+            context.metadata().clone(),
         )
         .await;
 
+        let mut prompt = context.system_prompt_builder();
+        self.on_system_prompt(&mut prompt, &context).await;
         let mut messages = vec![ChatMessage {
             role: ChatRole::System,
             message_type: MessageType::Text,
-            content: context.config().description.clone(),
+            content: prompt.build(),
+            reasoning: Vec::new(),
         }];
 
-        let chat_msg = if let Some((mime, image_data)) = &task.image {
-            // Task has an image, create an Image message
-            ChatMessage {
-                role: ChatRole::User,
-                message_type: MessageType::Image((*mime, image_data.clone())),
-                content: task.prompt.clone(),
-            }
-        } else {
-            // Text-only task
-            ChatMessage {
-                role: ChatRole::User,
-                message_type: MessageType::Text,
-                content: task.prompt.clone(),
-            }
-        };
-        messages.push(chat_msg);
+        messages.extend(task.user_messages());
 
         let stream = context
             .llm()
@@ -255,6 +309,8 @@ impl<T: AgentDeriveT> AgentExecutor for BasicAgent<T> {
                 Ok(BasicAgentOutput {
                     response: content,
                     done: false,
+                    citations: vec![],
+                    usage: chunk.usage,
                 })
             }
             Err(e) => Err(BasicExecutorError::LLMError(e.to_string())),
@@ -296,6 +352,8 @@ mod tests {
         let output = BasicAgentOutput {
             response: "Test response".to_string(),
             done: true,
+            citations: vec![],
+            usage: None,
         };
 
         // Test conversion to Value
@@ -336,6 +394,94 @@ mod tests {
         assert!(output.done);
     }
 
+    fn schema_context(llm: Arc<dyn autoagents_llm::LLMProvider>) -> Arc<Context> {
+        use crate::agent::AgentConfig;
+        use crate::protocol::ActorID;
+        use autoagents_llm::chat::StructuredOutputFormat;
+
+        let config = AgentConfig {
+            id: ActorID::new_v4(),
+            name: "test_agent".to_string(),
+            description: "Test agent description".to_string(),
+            output_schema: Some(StructuredOutputFormat {
+                name: "Answer".to_string(),
+                description: None,
+                schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {"answer": {"type": "integer"}},
+                    "required": ["answer"]
+                })),
+                strict: None,
+            }),
+        };
+        Arc::new(Context::new(llm, None).with_config(config))
+    }
+
+    #[tokio::test]
+    async fn test_basic_agent_repairs_invalid_output() {
+        use crate::agent::task::Task;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+        let llm = Arc::new(ScriptedLLMProvider::new([
+            "The answer is 42",
+            r#"{"answer": "42"}"#,
+            r#"{"answer": 42}"#,
+        ]));
+        let agent = BasicAgent::new(MockAgentImpl::new("test_agent", "Test agent description"))
+            .with_validation(ValidationPolicy::repair(2));
+
+        let output = agent
+            .execute(&Task::new("Answer"), schema_context(llm.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(output.response, r#"{"answer": 42}"#);
+        assert_eq!(llm.calls(), 3);
+        let last = llm.last_messages();
+        assert!(last
+            .last()
+            .unwrap()
+            .content
+            .contains("$.answer: expected integer, found string"));
+    }
+
+    #[tokio::test]
+    async fn test_basic_agent_fails_after_max_retries() {
+        use crate::agent::task::Task;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+        let llm = Arc::new(ScriptedLLMProvider::new(["not json"]));
+        let agent = BasicAgent::new(MockAgentImpl::new("test_agent", "Test agent description"))
+            .with_validation(ValidationPolicy::repair(1));
+
+        let err = agent
+            .execute(&Task::new("Answer"), schema_context(llm.clone()))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            BasicExecutorError::OutputValidationError { attempts: 1, .. }
+        ));
+        assert_eq!(llm.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_basic_agent_skips_validation_when_disabled() {
+        use crate::agent::task::Task;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+        let llm = Arc::new(ScriptedLLMProvider::new(["not json"]));
+        let agent = BasicAgent::new(MockAgentImpl::new("test_agent", "Test agent description"));
+
+        let output = agent
+            .execute(&Task::new("Answer"), schema_context(llm.clone()))
+            .await
+            .unwrap();
+        assert_eq!(output.response, "not json");
+        assert_eq!(llm.calls(), 1);
+    }
+
     #[test]
     fn test_executor_config() {
         let mock_agent = MockAgentImpl::new("test_agent", "Test agent description");
@@ -344,4 +490,59 @@ mod tests {
         let config = basic_agent.config();
         assert_eq!(config.max_turns, 1);
     }
+
+    #[derive(Debug)]
+    struct Barista;
+
+    #[async_trait]
+    impl AgentDeriveT for Barista {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "You are a barista."
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "barista"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    #[async_trait]
+    impl AgentHooks for Barista {
+        async fn on_system_prompt(&self, prompt: &mut SystemPromptBuilder, _ctx: &Context) {
+            prompt.append("Be brief.");
+            prompt.set(crate::agent::PromptSection::Tools, "Use `menu` first.");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_add_system_prompt_sections() {
+        use crate::agent::task::Task;
+        use crate::agent::AgentConfig;
+        use autoagents_llm::chat::ChatRole;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+        let llm = Arc::new(ScriptedLLMProvider::new(["Tea?"]));
+        let config = AgentConfig::new("barista".into(), "You are a barista.".into());
+        let context = Arc::new(Context::new(llm.clone(), None).with_config(config));
+        BasicAgent::new(Barista)
+            .execute(&Task::new("Hi"), context)
+            .await
+            .unwrap();
+
+        let system = &llm.last_messages()[0];
+        assert_eq!(system.role, ChatRole::System);
+        assert_eq!(
+            system.content,
+            "You are a barista.\n\nUse `menu` first.\n\nBe brief."
+        );
+    }
 }
@@ -1,19 +1,24 @@
-use crate::agent::executor::AgentExecutor;
+use crate::agent::executor::{accumulate_usage, AgentExecutor};
 use crate::agent::task::Task;
-use crate::agent::{AgentDeriveT, Context, ExecutorConfig, TurnResult};
+use crate::agent::{
+    validation, AgentDeriveT, Context, ExecutorConfig, SystemPromptBuilder, TurnResult,
+    ValidationPolicy,
+};
 use crate::protocol::{Event, StreamingTurnResult, SubmissionId};
-use crate::tool::{to_llm_tool, ToolCallResult, ToolT};
+use crate::tool::{select_llm_tools, ToolCallResult, ToolProgress, ToolT};
 use async_trait::async_trait;
-use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType, StreamChoice, Tool};
+use autoagents_llm::chat::{
+    ChatMessage, ChatRole, Citation, MessageType, ReasoningBlock, Tool, ToolCallAccumulator, Usage,
+};
 use autoagents_llm::error::LLMError;
-use autoagents_llm::{FunctionCall, ToolCall};
+use autoagents_llm::ToolCall;
 use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
 use std::ops::Deref;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -39,6 +44,14 @@ pub struct ReActAgentOutput {
     pub response: String,
     pub tool_calls: Vec<ToolCallResult>,
     pub done: bool,
+    /// Sources the response cites, for providers that ground their answers
+    /// in documents or tool results
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<Citation>,
+    /// Tokens used by the run, summed over all of its turns. When
+    /// streaming, only the final chunk carries it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 impl From<ReActAgentOutput> for Value {
@@ -87,18 +100,27 @@ pub enum ReActExecutorError {
 
     #[error("Extracting Agent Output Error: {0}")]
     AgentOutputError(String),
+
+    #[error("Output failed schema validation after {attempts} repair attempts: {errors}")]
+    OutputValidationError { attempts: usize, errors: String },
 }
 
 /// Wrapper type for ReAct executor
 #[derive(Debug)]
 pub struct ReActAgent<T: AgentDeriveT> {
     inner: Arc<T>,
+    validation: ValidationPolicy,
+    tool_timeout: Option<Duration>,
+    tool_parallelism: usize,
 }
 
 impl<T: AgentDeriveT> Clone for ReActAgent<T> {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
+            validation: self.validation,
+            tool_timeout: self.tool_timeout,
+            tool_parallelism: self.tool_parallelism,
         }
     }
 }
@@ -107,8 +129,37 @@ impl<T: AgentDeriveT> ReActAgent<T> {
     pub fn new(inner: T) -> Self {
         Self {
             inner: Arc::new(inner),
+            validation: ValidationPolicy::default(),
+            tool_timeout: None,
+            tool_parallelism: 1,
         }
     }
+
+    /// Validate the final answer against the output schema and ask the LLM
+    /// to repair it when it does not match.
+    ///
+    /// Only applies to [`AgentExecutor::execute`]; streamed responses are
+    /// forwarded as they arrive.
+    pub fn with_validation(mut self, policy: ValidationPolicy) -> Self {
+        self.validation = policy;
+        self
+    }
+
+    /// Cancel tool calls that run longer than `timeout`, reporting the
+    /// timeout to the LLM as the tool's result. Tools that set their own
+    /// [`ToolT::timeout`] keep it.
+    pub fn with_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Run up to `parallelism` of the tool calls the LLM makes in one turn
+    /// concurrently. Results are returned to the LLM in call order. The
+    /// default of 1 runs calls one after another.
+    pub fn with_tool_parallelism(mut self, parallelism: usize) -> Self {
+        self.tool_parallelism = parallelism.max(1);
+        self
+    }
 }
 
 impl<T: AgentDeriveT> Deref for ReActAgent<T> {
@@ -154,6 +205,10 @@ where
         self.inner.on_run_start(task, ctx).await
     }
 
+    async fn on_system_prompt(&self, prompt: &mut SystemPromptBuilder, ctx: &Context) {
+        self.inner.on_system_prompt(prompt, ctx).await
+    }
+
     async fn on_run_complete(&self, task: &Task, result: &Self::Output, ctx: &Context) {
         self.inner.on_run_complete(task, result, ctx).await
     }
@@ -174,6 +229,10 @@ where
         self.inner.on_tool_start(tool_call, ctx).await
     }
 
+    async fn on_tool_progress(&self, tool_call: &ToolCall, progress: &ToolProgress, ctx: &Context) {
+        self.inner.on_tool_progress(tool_call, progress, ctx).await
+    }
+
     async fn on_tool_result(&self, tool_call: &ToolCall, result: &ToolCallResult, ctx: &Context) {
         self.inner.on_tool_result(tool_call, result, ctx).await
     }
@@ -196,12 +255,22 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
         let messages = self.prepare_messages(context).await;
         let response = self.get_llm_response(context, &messages, tools).await?;
         let response_text = response.text().unwrap_or_default();
+        let usage = response.usage();
 
         if let Some(tool_calls) = response.tool_calls() {
-            self.handle_tool_calls(context, tools, tool_calls.clone(), response_text)
-                .await
+            self.handle_tool_calls(
+                context,
+                tools,
+                tool_calls.clone(),
+                response_text,
+                response.reasoning(),
+                usage,
+            )
+            .await
         } else {
-            self.handle_text_response(context, response_text).await
+            let citations = response.citations().unwrap_or_default();
+            self.handle_text_response(context, response_text, citations, usage)
+                .await
         }
     }
 
@@ -214,11 +283,12 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
     ) -> Result<Box<dyn autoagents_llm::chat::ChatResponse>, ReActExecutorError> {
         let llm = context.llm();
         let agent_config = context.config();
-        let tools_serialized: Vec<Tool> = tools.iter().map(to_llm_tool).collect();
+        let tools_serialized: Vec<Tool> =
+            select_llm_tools(context.tool_selector(), messages, tools).await;
 
         llm.chat(
             messages,
-            if tools.is_empty() {
+            if tools_serialized.is_empty() {
                 None
             } else {
                 Some(&tools_serialized)
@@ -236,20 +306,22 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
         tools: &[Box<dyn ToolT>],
         tool_calls: Vec<ToolCall>,
         response_text: String,
+        reasoning: Vec<ReasoningBlock>,
+        usage: Option<Usage>,
     ) -> Result<TurnResult<ReActAgentOutput>, ReActExecutorError> {
         let tx_event = context.tx().ok();
 
         // Process tool calls
-        let mut tool_results = Vec::new();
-        for call in &tool_calls {
-            if let Some(result) = ToolProcessor::process_single_tool_call_with_hooks(
-                self, context, tools, call, &tx_event,
-            )
-            .await
-            {
-                tool_results.push(result);
-            }
-        }
+        let tool_results = ToolProcessor::process_tool_calls_with_hooks(
+            self,
+            context,
+            tools,
+            &tool_calls,
+            &tx_event,
+            self.tool_timeout,
+            self.tool_parallelism,
+        )
+        .await;
 
         // Store in memory
         MemoryHelper::store_tool_interaction(
@@ -257,6 +329,7 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
             &tool_calls,
             &tool_results,
             &response_text,
+            reasoning,
         )
         .await;
 
@@ -281,6 +354,8 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
             response: response_text,
             done: true,
             tool_calls: tool_results,
+            citations: vec![],
+            usage,
         })))
     }
 
@@ -289,6 +364,8 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
         &self,
         context: &Context,
         response_text: String,
+        citations: Vec<Citation>,
+        usage: Option<Usage>,
     ) -> Result<TurnResult<ReActAgentOutput>, ReActExecutorError> {
         if !response_text.is_empty() {
             MemoryHelper::store_assistant_response(&context.memory(), response_text.clone()).await;
@@ -298,15 +375,34 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
             response: response_text,
             done: true,
             tool_calls: vec![],
+            citations,
+            usage,
         }))
     }
 
+    /// Schema violations in a final response, if validation is enabled and
+    /// the agent declares an output schema.
+    fn output_errors(&self, context: &Context, response: &str) -> Option<Vec<String>> {
+        if !self.validation.is_enabled() {
+            return None;
+        }
+        let config = context.config();
+        let schema = config.output_schema.as_ref()?.schema.as_ref()?;
+        validation::validate_output(schema, response).err()
+    }
+
     /// Prepare messages for the current turn
     async fn prepare_messages(&self, context: &Context) -> Vec<ChatMessage> {
+        let mut prompt = context.system_prompt_builder();
+        if let Some(memory_context) = MemoryHelper::system_context(&context.memory()).await {
+            prompt = prompt.memory(memory_context);
+        }
+        self.on_system_prompt(&mut prompt, context).await;
         let mut messages = vec![ChatMessage {
             role: ChatRole::System,
             message_type: MessageType::Text,
-            content: context.config().description.clone(),
+            content: prompt.build(),
+            reasoning: Vec::new(),
         }];
 
         let recalled = MemoryHelper::recall_messages(&context.memory()).await;
@@ -316,55 +412,102 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
     }
 
     /// Process a streaming turn with tool support
+    ///
+    /// Text is forwarded as it arrives, and each tool call starts running as
+    /// soon as its arguments are complete while the rest of the response is
+    /// still streaming.
     async fn process_streaming_turn(
         &self,
         context: &Context,
         tools: &[Box<dyn ToolT>],
         tx: &mut Sender<Result<ReActAgentOutput, ReActExecutorError>>,
         submission_id: SubmissionId,
+        usage: &mut Option<Usage>,
     ) -> Result<StreamingTurnResult, ReActExecutorError> {
         let messages = self.prepare_messages(context).await;
         let mut stream = self.get_llm_stream(context, &messages, tools).await?;
 
         let mut response_text = String::new();
-        let mut tool_calls_map: HashMap<usize, (Option<String>, Option<String>, String)> =
-            HashMap::new();
+        let mut reasoning = Vec::new();
+        let mut tool_calls = ToolCallAccumulator::default();
+        let tx_event = context.tx().ok();
+        let (call_tx, call_rx) = futures::channel::mpsc::unbounded::<ToolCall>();
+
+        // Process stream chunks, handing each completed tool call to the runner
+        let read_stream = async {
+            let call_tx = call_tx;
+            while let Some(chunk_result) = stream.next().await {
+                let chunk =
+                    chunk_result.map_err(|e| ReActExecutorError::LLMError(e.to_string()))?;
+                accumulate_usage(usage, chunk.usage.clone());
+
+                if let Some(choice) = chunk.choices.first() {
+                    // Handle content
+                    if let Some(content) = &choice.delta.content {
+                        response_text.push_str(content);
+                        let _ = tx
+                            .send(Ok(ReActAgentOutput {
+                                response: content.to_string(),
+                                tool_calls: vec![],
+                                done: false,
+                                citations: vec![],
+                                usage: None,
+                            }))
+                            .await;
+                    }
 
-        // Process stream chunks
-        while let Some(chunk_result) = stream.next().await {
-            let chunk = chunk_result.map_err(|e| ReActExecutorError::LLMError(e.to_string()))?;
+                    if let Some(block) = &choice.delta.reasoning {
+                        reasoning.push(block.as_ref().clone());
+                    }
 
-            if let Some(choice) = chunk.choices.first() {
-                // Handle content
-                if let Some(content) = &choice.delta.content {
-                    response_text.push_str(content);
-                    let _ = tx
-                        .send(Ok(ReActAgentOutput {
-                            response: content.to_string(),
-                            tool_calls: vec![],
-                            done: false,
-                        }))
-                        .await;
-                }
+                    // Handle tool calls
+                    if let Some(deltas) = &choice.delta.tool_calls {
+                        for call in tool_calls.push(deltas) {
+                            Self::dispatch_stream_tool_call(
+                                &tx_event,
+                                submission_id,
+                                call,
+                                &call_tx,
+                            )
+                            .await;
+                        }
+                    }
 
-                // Handle tool calls
-                self.process_stream_tool_calls(&mut tool_calls_map, choice);
+                    // Send stream chunk event
+                    EventHelper::send_stream_chunk(&tx_event, submission_id, choice.clone()).await;
+                }
+            }
 
-                // Send stream chunk event
-                let tx_event = context.tx().ok();
-                EventHelper::send_stream_chunk(&tx_event, submission_id, choice.clone()).await;
+            for call in tool_calls.finish() {
+                Self::dispatch_stream_tool_call(&tx_event, submission_id, call, &call_tx).await;
             }
-        }
+            Ok::<_, ReActExecutorError>(())
+        };
 
-        // Process collected tool calls if any
-        self.finalize_stream_tool_calls(
-            context,
-            tools,
-            tool_calls_map,
-            submission_id,
-            response_text,
-        )
-        .await
+        // Calls aborted by a hook are left out, as in the non-streaming loop
+        let tx_tools = &tx_event;
+        let run_tools = call_rx
+            .map(|call| async move {
+                let result = ToolProcessor::process_single_tool_call_with_hooks(
+                    self,
+                    context,
+                    tools,
+                    &call,
+                    tx_tools,
+                    self.tool_timeout,
+                )
+                .await;
+                result.map(|result| (call, result))
+            })
+            .buffered(self.tool_parallelism.max(1))
+            .filter_map(|executed| async move { executed })
+            .collect::<Vec<_>>();
+
+        let (streamed, executed) = futures::join!(read_stream, run_tools);
+        streamed?;
+
+        self.finalize_stream_tool_calls(context, executed, response_text, reasoning)
+            .await
     }
 
     /// Get streaming LLM response
@@ -379,11 +522,12 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
     > {
         let llm = context.llm();
         let agent_config = context.config();
-        let tools_serialized: Vec<Tool> = tools.iter().map(to_llm_tool).collect();
+        let tools_serialized: Vec<Tool> =
+            select_llm_tools(context.tool_selector(), messages, tools).await;
 
         llm.chat_stream_struct(
             messages,
-            if tools.is_empty() {
+            if tools_serialized.is_empty() {
                 None
             } else {
                 Some(&tools_serialized)
@@ -394,39 +538,34 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
         .map_err(|e| ReActExecutorError::LLMError(e.to_string()))
     }
 
-    /// Process tool calls from stream chunks
-    fn process_stream_tool_calls(
-        &self,
-        tool_calls_map: &mut HashMap<usize, (Option<String>, Option<String>, String)>,
-        choice: &StreamChoice,
+    /// Announce a tool call whose arguments are complete and queue it for execution
+    async fn dispatch_stream_tool_call(
+        tx_event: &Option<Sender<Event>>,
+        submission_id: SubmissionId,
+        mut call: ToolCall,
+        call_tx: &futures::channel::mpsc::UnboundedSender<ToolCall>,
     ) {
-        if let Some(tool_call_deltas) = &choice.delta.tool_calls {
-            for delta in tool_call_deltas {
-                let entry =
-                    tool_calls_map
-                        .entry(delta.index)
-                        .or_insert((None, None, String::new()));
-
-                if let Some(function) = &delta.function {
-                    if !function.name.is_empty() {
-                        entry.0 = Some(function.name.clone());
-                    }
-                    entry.2.push_str(&function.arguments);
-                }
-            }
+        if call.id.is_empty() {
+            call.id = uuid::Uuid::new_v4().to_string();
         }
+        EventHelper::send_stream_tool_call(
+            tx_event,
+            submission_id,
+            serde_json::to_value(&call).unwrap_or(Value::Null),
+        )
+        .await;
+        let _ = call_tx.unbounded_send(call);
     }
 
-    /// Finalize and process collected tool calls from streaming
+    /// Finalize the tool calls executed during streaming
     async fn finalize_stream_tool_calls(
         &self,
         context: &Context,
-        tools: &[Box<dyn ToolT>],
-        tool_calls_map: HashMap<usize, (Option<String>, Option<String>, String)>,
-        submission_id: SubmissionId,
+        executed: Vec<(ToolCall, ToolCallResult)>,
         response_text: String,
+        reasoning: Vec<ReasoningBlock>,
     ) -> Result<StreamingTurnResult, ReActExecutorError> {
-        if tool_calls_map.is_empty() {
+        if executed.is_empty() {
             if !response_text.is_empty() {
                 MemoryHelper::store_assistant_response(&context.memory(), response_text.clone())
                     .await;
@@ -434,38 +573,8 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
             return Ok(StreamingTurnResult::Complete(response_text));
         }
 
-        // Convert map to tool calls
-        let mut sorted_calls: Vec<_> = tool_calls_map.into_iter().collect();
-        sorted_calls.sort_by_key(|(index, _)| *index);
-
-        let collected_tool_calls: Vec<ToolCall> = sorted_calls
-            .into_iter()
-            .filter_map(|(_, (name, id, args))| {
-                name.map(|name| ToolCall {
-                    id: id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
-                    call_type: "function".to_string(),
-                    function: FunctionCall {
-                        name,
-                        arguments: args,
-                    },
-                })
-            })
-            .collect();
-
-        // Send tool call events
-        let tx_event = context.tx().ok();
-        for tool_call in &collected_tool_calls {
-            EventHelper::send_stream_tool_call(
-                &tx_event,
-                submission_id,
-                serde_json::to_value(tool_call).unwrap_or(Value::Null),
-            )
-            .await;
-        }
-
-        // Process tool calls
-        let tool_results =
-            ToolProcessor::process_tool_calls(tools, collected_tool_calls.clone(), tx_event).await;
+        let (collected_tool_calls, tool_results): (Vec<ToolCall>, Vec<ToolCallResult>) =
+            executed.into_iter().unzip();
 
         // Update memory
         MemoryHelper::store_tool_interaction(
@@ -473,6 +582,7 @@ impl<T: AgentDeriveT + AgentHooks> ReActAgent<T> {
             &collected_tool_calls,
             &tool_results,
             &response_text,
+            reasoning,
         )
         .await;
 
@@ -494,7 +604,12 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
     type Error = ReActExecutorError;
 
     fn config(&self) -> ExecutorConfig {
-        ExecutorConfig { max_turns: 10 }
+        ExecutorConfig {
+            max_turns: 10,
+            validation: self.validation,
+            tool_timeout: self.tool_timeout,
+            tool_parallelism: self.tool_parallelism,
+        }
     }
 
     async fn execute(
@@ -503,12 +618,7 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
         context: Arc<Context>,
     ) -> Result<Self::Output, Self::Error> {
         // Initialize task
-        MemoryHelper::store_user_message(
-            &context.memory(),
-            task.prompt.clone(),
-            task.image.clone(),
-        )
-        .await;
+        MemoryHelper::store_task_input(&context.memory(), task).await;
 
         // Record task in state - use try_lock to avoid deadlock
         {
@@ -528,9 +638,11 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
         EventHelper::send_task_started(
             &tx_event,
             task.submission_id,
+            context.run_id(),
             context.config().id,
             task.prompt.clone(),
             context.config().name.clone(),
+            context.metadata().clone(),
         )
         .await;
 
@@ -538,6 +650,8 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
         let max_turns = self.config().max_turns;
         let mut accumulated_tool_calls = Vec::new();
         let mut final_response = String::new();
+        let mut repairs = 0;
+        let mut usage = None;
 
         for turn_num in 0..max_turns {
             let tools = context.tools();
@@ -547,12 +661,34 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
             self.on_turn_start(turn_num, &context).await;
 
             match self.process_turn(&context, tools).await? {
-                TurnResult::Complete(result) => {
+                TurnResult::Complete(mut result) => {
+                    accumulate_usage(&mut usage, result.usage.take());
+                    result.usage = usage.clone();
+                    if let Some(errors) = self.output_errors(&context, &result.response) {
+                        if repairs >= self.validation.max_retries() {
+                            return Err(ReActExecutorError::OutputValidationError {
+                                attempts: repairs,
+                                errors: errors.join("; "),
+                            });
+                        }
+                        repairs += 1;
+                        MemoryHelper::store_user_message(
+                            &context.memory(),
+                            validation::repair_prompt(&errors),
+                            None,
+                        )
+                        .await;
+                        EventHelper::send_turn_completed(&tx_event, turn_num, false).await;
+                        self.on_turn_complete(turn_num, &context).await;
+                        continue;
+                    }
                     if !accumulated_tool_calls.is_empty() {
                         return Ok(ReActAgentOutput {
                             response: result.response,
                             done: true,
                             tool_calls: accumulated_tool_calls,
+                            citations: result.citations,
+                            usage,
                         });
                     }
                     EventHelper::send_turn_completed(&tx_event, turn_num, false).await;
@@ -561,6 +697,7 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
                     return Ok(result);
                 }
                 TurnResult::Continue(Some(partial_result)) => {
+                    accumulate_usage(&mut usage, partial_result.usage);
                     accumulated_tool_calls.extend(partial_result.tool_calls);
                     if !partial_result.response.is_empty() {
                         final_response = partial_result.response;
@@ -574,6 +711,7 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
             EventHelper::send_task_completed(
                 &tx_event,
                 task.submission_id,
+                context.run_id(),
                 context.config().id,
                 final_response.clone(),
                 context.config().name.clone(),
@@ -583,6 +721,8 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
                 response: final_response,
                 done: true,
                 tool_calls: accumulated_tool_calls,
+                citations: vec![],
+                usage,
             })
         } else {
             Err(ReActExecutorError::MaxTurnsExceeded { max_turns })
@@ -598,12 +738,7 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
         Self::Error,
     > {
         // Initialize task
-        MemoryHelper::store_user_message(
-            &context.memory(),
-            task.prompt.clone(),
-            task.image.clone(),
-        )
-        .await;
+        MemoryHelper::store_task_input(&context.memory(), task).await;
 
         // Record task in state - use try_lock to avoid deadlock
         {
@@ -623,9 +758,11 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
         EventHelper::send_task_started(
             &tx_event,
             task.submission_id,
+            context.run_id(),
             context.config().id,
             task.prompt.clone(),
             context.config().name.clone(),
+            context.metadata().clone(),
         )
         .await;
 
@@ -638,57 +775,73 @@ impl<T: AgentDeriveT + AgentHooks> AgentExecutor for ReActAgent<T> {
         let submission_id = task.submission_id;
         let max_turns = executor.config().max_turns;
 
-        // Spawn streaming task
+        // Spawn streaming task, keeping this run's request headers on the LLM calls
         spawn_future(async move {
-            let mut accumulated_tool_calls = Vec::new();
-            let mut final_response = String::new();
-            let tools = context_clone.tools();
-
-            for turn in 0..max_turns {
-                // Send turn events
-                let tx_event = context_clone.tx().ok();
-                EventHelper::send_turn_started(&tx_event, turn, max_turns).await;
-
-                // Process streaming turn
-                match executor
-                    .process_streaming_turn(&context_clone, tools, &mut tx, submission_id)
-                    .await
-                {
-                    Ok(StreamingTurnResult::Complete(response)) => {
-                        final_response = response;
-                        EventHelper::send_turn_completed(&tx_event, turn, true).await;
-                        break;
-                    }
-                    Ok(StreamingTurnResult::ToolCallsProcessed(tool_results)) => {
-                        accumulated_tool_calls.extend(tool_results);
-
-                        let _ = tx
-                            .send(Ok(ReActAgentOutput {
-                                response: String::new(),
-                                done: false,
-                                tool_calls: accumulated_tool_calls.clone(),
-                            }))
-                            .await;
-
-                        EventHelper::send_turn_completed(&tx_event, turn, false).await;
-                    }
-                    Err(e) => {
-                        let _ = tx.send(Err(e)).await;
-                        return;
+            let run_context = context_clone.clone();
+            run_context
+                .scoped(async move {
+                    let mut accumulated_tool_calls = Vec::new();
+                    let mut final_response = String::new();
+                    let mut usage = None;
+                    let tools = context_clone.tools();
+
+                    for turn in 0..max_turns {
+                        // Send turn events
+                        let tx_event = context_clone.tx().ok();
+                        EventHelper::send_turn_started(&tx_event, turn, max_turns).await;
+
+                        // Process streaming turn
+                        match executor
+                            .process_streaming_turn(
+                                &context_clone,
+                                tools,
+                                &mut tx,
+                                submission_id,
+                                &mut usage,
+                            )
+                            .await
+                        {
+                            Ok(StreamingTurnResult::Complete(response)) => {
+                                final_response = response;
+                                EventHelper::send_turn_completed(&tx_event, turn, true).await;
+                                break;
+                            }
+                            Ok(StreamingTurnResult::ToolCallsProcessed(tool_results)) => {
+                                accumulated_tool_calls.extend(tool_results);
+
+                                let _ = tx
+                                    .send(Ok(ReActAgentOutput {
+                                        response: String::new(),
+                                        done: false,
+                                        tool_calls: accumulated_tool_calls.clone(),
+                                        citations: vec![],
+                                        usage: None,
+                                    }))
+                                    .await;
+
+                                EventHelper::send_turn_completed(&tx_event, turn, false).await;
+                            }
+                            Err(e) => {
+                                let _ = tx.send(Err(e)).await;
+                                return;
+                            }
+                        }
                     }
-                }
-            }
 
-            // Send final result
-            let tx_event = context_clone.tx().ok();
-            EventHelper::send_stream_complete(&tx_event, submission_id).await;
+                    // Send final result
+                    let tx_event = context_clone.tx().ok();
+                    EventHelper::send_stream_complete(&tx_event, submission_id).await;
 
-            let _ = tx
-                .send(Ok(ReActAgentOutput {
-                    response: final_response,
-                    done: true,
-                    tool_calls: accumulated_tool_calls,
-                }))
+                    let _ = tx
+                        .send(Ok(ReActAgentOutput {
+                            response: final_response,
+                            done: true,
+                            tool_calls: accumulated_tool_calls,
+                            citations: vec![],
+                            usage,
+                        }))
+                        .await;
+                })
                 .await;
         });
 
@@ -717,6 +870,8 @@ mod tests {
             response: serde_json::to_string(&agent_output).unwrap(),
             done: true,
             tool_calls: vec![],
+            citations: vec![],
+            usage: None,
         };
 
         let react_value = serde_json::to_value(react_output).unwrap();
@@ -724,4 +879,285 @@ mod tests {
             ReActAgentOutput::extract_agent_output(react_value).unwrap();
         assert_eq!(extracted, agent_output);
     }
+
+    #[test]
+    fn test_citations_are_only_serialized_when_present() {
+        let mut output = ReActAgentOutput {
+            response: "Penguins live in Antarctica.".to_string(),
+            done: true,
+            tool_calls: vec![],
+            citations: vec![],
+            usage: None,
+        };
+        let value = serde_json::to_value(&output).unwrap();
+        assert!(value.get("citations").is_none());
+        let parsed: ReActAgentOutput = serde_json::from_value(value).unwrap();
+        assert!(parsed.citations.is_empty());
+
+        output.citations.push(Citation {
+            start: 17,
+            end: 27,
+            text: "Antarctica".to_string(),
+            sources: vec!["doc-1".to_string()],
+        });
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(value["citations"][0]["sources"][0], "doc-1");
+    }
+
+    fn schema_context(llm: Arc<dyn autoagents_llm::LLMProvider>) -> Arc<Context> {
+        use crate::agent::memory::{MemoryProvider, SlidingWindowMemory};
+        use crate::agent::AgentConfig;
+        use crate::protocol::ActorID;
+        use autoagents_llm::chat::StructuredOutputFormat;
+        use tokio::sync::Mutex;
+
+        let config = AgentConfig {
+            id: ActorID::new_v4(),
+            name: "test_agent".to_string(),
+            description: "Test agent description".to_string(),
+            output_schema: Some(StructuredOutputFormat {
+                name: "Answer".to_string(),
+                description: None,
+                schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {"answer": {"type": "integer"}},
+                    "required": ["answer"]
+                })),
+                strict: None,
+            }),
+        };
+        let memory: Box<dyn MemoryProvider> = Box::new(SlidingWindowMemory::new(10));
+        Arc::new(
+            Context::new(llm, None)
+                .with_config(config)
+                .with_memory(Some(Arc::new(Mutex::new(memory)))),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_react_agent_repairs_invalid_output() {
+        use crate::tests::agent::MockAgentImpl;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+        let llm = Arc::new(ScriptedLLMProvider::new([
+            r#"{"result": 1}"#,
+            r#"{"answer": 1}"#,
+        ]));
+        let agent = ReActAgent::new(MockAgentImpl::new("test_agent", "Test agent"))
+            .with_validation(ValidationPolicy::repair(1));
+        assert_eq!(agent.config().validation, ValidationPolicy::repair(1));
+
+        let output = agent
+            .execute(&Task::new("Answer"), schema_context(llm.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(output.response, r#"{"answer": 1}"#);
+        assert_eq!(llm.calls(), 2);
+        let repair = llm.last_messages().pop().unwrap();
+        assert_eq!(repair.role, ChatRole::User);
+        assert!(repair
+            .content
+            .contains("missing required property `answer`"));
+    }
+
+    #[tokio::test]
+    async fn test_react_agent_reports_validation_failure() {
+        use crate::tests::agent::MockAgentImpl;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+        let llm = Arc::new(ScriptedLLMProvider::new(["plain text"]));
+        let agent = ReActAgent::new(MockAgentImpl::new("test_agent", "Test agent"))
+            .with_validation(ValidationPolicy::repair(2));
+
+        let err = agent
+            .execute(&Task::new("Answer"), schema_context(llm.clone()))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            ReActExecutorError::OutputValidationError { attempts: 2, .. }
+        ));
+        assert_eq!(llm.calls(), 3);
+    }
+    #[tokio::test]
+    async fn test_memory_context_extends_system_prompt() {
+        use crate::agent::memory::{Entity, EntityMemory, MemoryProvider, SlidingWindowMemory};
+        use crate::tests::agent::MockAgentImpl;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+        use tokio::sync::Mutex;
+
+        let extractor = Arc::new(ScriptedLLMProvider::new(["[]"]));
+        let mut entities = EntityMemory::new(extractor, Box::new(SlidingWindowMemory::new(10)));
+        entities.upsert(Entity {
+            name: "user".into(),
+            kind: "preference".into(),
+            attributes: [("tone".to_string(), "formal".to_string())].into(),
+        });
+        let memory: Box<dyn MemoryProvider> = Box::new(entities);
+
+        let llm = Arc::new(ScriptedLLMProvider::new(["Good day."]));
+        let context = Arc::new(
+            Context::new(llm.clone(), None).with_memory(Some(Arc::new(Mutex::new(memory)))),
+        );
+        ReActAgent::new(MockAgentImpl::new("test_agent", "Test agent"))
+            .execute(&Task::new("Say hi"), context)
+            .await
+            .unwrap();
+
+        let system = &llm.last_messages()[0];
+        assert_eq!(system.role, ChatRole::System);
+        assert!(system
+            .content
+            .ends_with("Known entities:\n- user (preference) - tone: formal"));
+    }
+
+    /// Agent whose hooks record every tool call and abort those to `blocked`
+    #[derive(Debug, Default)]
+    struct VettingAgent {
+        hook_calls: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl VettingAgent {
+        fn record(&self, hook: &str, tool_call: &ToolCall) {
+            self.hook_calls
+                .lock()
+                .unwrap()
+                .push(format!("{hook}:{}", tool_call.function.name));
+        }
+    }
+
+    #[async_trait]
+    impl AgentDeriveT for VettingAgent {
+        type Output = crate::tests::agent::TestAgentOutput;
+
+        fn description(&self) -> &'static str {
+            "Vets its tool calls"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "vetting_agent"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    #[async_trait]
+    impl AgentHooks for VettingAgent {
+        async fn on_tool_call(&self, tool_call: &ToolCall, _ctx: &Context) -> HookOutcome {
+            self.record("call", tool_call);
+            if tool_call.function.name == "blocked" {
+                HookOutcome::Abort
+            } else {
+                HookOutcome::Continue
+            }
+        }
+
+        async fn on_tool_start(&self, tool_call: &ToolCall, _ctx: &Context) {
+            self.record("start", tool_call);
+        }
+
+        async fn on_tool_result(
+            &self,
+            tool_call: &ToolCall,
+            _result: &ToolCallResult,
+            _ctx: &Context,
+        ) {
+            self.record("result", tool_call);
+        }
+    }
+
+    #[derive(Debug)]
+    struct CountingTool {
+        name: &'static str,
+        runs: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl crate::tool::ToolRuntime for CountingTool {
+        async fn execute(&self, _args: Value) -> Result<Value, crate::tool::ToolCallError> {
+            self.runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(Value::from("ok"))
+        }
+    }
+
+    impl ToolT for CountingTool {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn description(&self) -> &'static str {
+            "Counts its runs"
+        }
+
+        fn args_schema(&self) -> Value {
+            serde_json::json!({"type": "object"})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_tool_calls_run_hooks() {
+        use autoagents_llm::FunctionCall;
+        use autoagents_test_utils::llm::ScriptedLLMProvider;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let tool_call = |id: &str, name: &str| ToolCall {
+            id: id.to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: "{}".to_string(),
+            },
+        };
+        let llm = Arc::new(
+            ScriptedLLMProvider::new(["Checking.", "Done."]).with_tool_calls([
+                tool_call("call_1", "allowed"),
+                tool_call("call_2", "blocked"),
+            ]),
+        );
+        let allowed_runs = Arc::new(AtomicUsize::new(0));
+        let blocked_runs = Arc::new(AtomicUsize::new(0));
+        let tools: Vec<Box<dyn ToolT>> = vec![
+            Box::new(CountingTool {
+                name: "allowed",
+                runs: allowed_runs.clone(),
+            }),
+            Box::new(CountingTool {
+                name: "blocked",
+                runs: blocked_runs.clone(),
+            }),
+        ];
+        let context = Arc::new(Context::new(llm, None).with_tools(tools));
+        let agent = ReActAgent::new(VettingAgent::default());
+
+        let outputs: Vec<_> = agent
+            .execute_stream(&Task::new("Check"), context)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        let last = outputs.last().unwrap().as_ref().unwrap();
+        assert!(last.done);
+        assert_eq!(last.tool_calls.len(), 1);
+        assert_eq!(last.tool_calls[0].tool_name, "allowed");
+
+        assert_eq!(allowed_runs.load(Ordering::SeqCst), 1);
+        assert_eq!(blocked_runs.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            *agent.hook_calls.lock().unwrap(),
+            [
+                "call:allowed",
+                "start:allowed",
+                "result:allowed",
+                "call:blocked"
+            ]
+        );
+    }
 }
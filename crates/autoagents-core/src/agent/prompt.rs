@@ -0,0 +1,366 @@
+//! Templated agent descriptions.
+//!
+//! An agent description may use `{{ variables }}` and the rest of the
+//! Jinja syntax, rendered with [minijinja] when a run builds its system
+//! prompt:
+//!
+//! ```rust,ignore
+//! #[agent(
+//!     name = "support",
+//!     description = "You help {{ user_id }} of {{ tenant_id }}. Answer in {{ language | default('English') }}.",
+//! )]
+//! struct SupportAgent;
+//! ```
+//!
+//! The variables come from the run, later sources winning:
+//!
+//! - the agent's [`PromptVariables`], state kept across runs,
+//! - the task's metadata attributes,
+//! - `user_id`, `tenant_id`, `tags` and `payload` of the task.
+//!
+//! Missing variables render as empty text. Descriptions without template
+//! syntax are used as written.
+//!
+//! The rendered description is the persona of a [`SystemPromptBuilder`],
+//! which puts the system prompt together from sections in a fixed order.
+//! Executors add what they know, such as memory facts, and the agent's
+//! [`on_system_prompt`](crate::agent::AgentHooks::on_system_prompt) hook can
+//! add or change sections for every run. Few-shot examples attached to the
+//! agent, see [`few_shot`](crate::agent::few_shot), get a section of their
+//! own.
+
+use minijinja::Environment;
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+use crate::agent::few_shot::{render_examples, Example};
+use crate::agent::task::RunMetadata;
+
+/// Error when a prompt template cannot be rendered
+#[derive(Debug, Error)]
+#[error("Failed to render prompt template: {0}")]
+pub struct PromptTemplateError(String);
+
+/// A prompt with `{{ variable }}` placeholders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    source: String,
+}
+
+impl PromptTemplate {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the source uses any template syntax
+    pub fn is_templated(&self) -> bool {
+        ["{{", "{%", "{#"]
+            .iter()
+            .any(|marker| self.source.contains(marker))
+    }
+
+    /// The prompt with `variables`, a JSON object, filled in.
+    pub fn render(&self, variables: &Value) -> Result<String, PromptTemplateError> {
+        if !self.is_templated() {
+            return Ok(self.source.clone());
+        }
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        env.render_str(&self.source, variables)
+            .map_err(|e| PromptTemplateError(e.to_string()))
+    }
+}
+
+/// Variables an agent's prompt templates can use in every run, e.g. state
+/// the application keeps for the session.
+///
+/// Clones share the same variables. Each run renders with the values present
+/// when it started.
+#[derive(Debug, Clone, Default)]
+pub struct PromptVariables {
+    values: Arc<RwLock<Map<String, Value>>>,
+}
+
+impl PromptVariables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key`, returning its previous value
+    pub fn set(&self, key: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        self.values
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.into(), value.into())
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.snapshot().get(key).cloned()
+    }
+
+    /// Remove `key`, returning its value
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        self.values
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key)
+    }
+
+    /// The current variables, copied for a run
+    pub fn snapshot(&self) -> Map<String, Value> {
+        self.values
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// Variables for a run's prompt templates, see the [module docs](self)
+pub(crate) fn template_variables(
+    session: &Map<String, Value>,
+    metadata: &RunMetadata,
+    payload: Option<&Value>,
+) -> Value {
+    let mut variables = session.clone();
+    for (key, value) in &metadata.attributes {
+        variables.insert(key.clone(), Value::String(value.clone()));
+    }
+    if let Some(user_id) = &metadata.user_id {
+        variables.insert("user_id".into(), Value::String(user_id.clone()));
+    }
+    if let Some(tenant_id) = &metadata.tenant_id {
+        variables.insert("tenant_id".into(), Value::String(tenant_id.clone()));
+    }
+    variables.insert("tags".into(), metadata.tags.clone().into());
+    if let Some(payload) = payload {
+        variables.insert("payload".into(), payload.clone());
+    }
+    Value::Object(variables)
+}
+
+/// Section of a system prompt. Sections appear in the order listed here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PromptSection {
+    /// Who the agent is and what it does, by default its description
+    Persona,
+    /// When and how to use the tools
+    Tools,
+    /// The shape the answer has to take
+    OutputSchema,
+    /// Worked examples of inputs and the expected outputs
+    Examples,
+    /// Facts recalled from memory
+    Memory,
+    /// The current date and time
+    DateTime,
+    /// Anything else, in the order added
+    Custom,
+}
+
+/// Assembles a system prompt from sections, in [`PromptSection`] order,
+/// separated by blank lines. Empty sections are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemPromptBuilder {
+    sections: Vec<(PromptSection, String)>,
+}
+
+impl SystemPromptBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn persona(mut self, content: impl Into<String>) -> Self {
+        self.set(PromptSection::Persona, content);
+        self
+    }
+
+    pub fn tools(mut self, content: impl Into<String>) -> Self {
+        self.set(PromptSection::Tools, content);
+        self
+    }
+
+    pub fn output_schema(mut self, content: impl Into<String>) -> Self {
+        self.set(PromptSection::OutputSchema, content);
+        self
+    }
+
+    pub fn examples(mut self, examples: &[Example]) -> Self {
+        self.set(PromptSection::Examples, render_examples(examples));
+        self
+    }
+
+    pub fn memory(mut self, content: impl Into<String>) -> Self {
+        self.set(PromptSection::Memory, content);
+        self
+    }
+
+    /// State `now` as the current date and time, in UTC
+    pub fn date_time(mut self, now: SystemTime) -> Self {
+        self.set(
+            PromptSection::DateTime,
+            format!("Current date and time: {}", format_utc(now)),
+        );
+        self
+    }
+
+    /// Set the content of `section`, replacing what it had. Custom sections
+    /// are added after the others instead.
+    pub fn set(&mut self, section: PromptSection, content: impl Into<String>) -> &mut Self {
+        let content = content.into();
+        match self
+            .sections
+            .iter_mut()
+            .find(|(existing, _)| *existing == section && section != PromptSection::Custom)
+        {
+            Some((_, existing)) => *existing = content,
+            None => self.sections.push((section, content)),
+        }
+        self
+    }
+
+    /// Add a custom section after all others
+    pub fn append(&mut self, content: impl Into<String>) -> &mut Self {
+        self.set(PromptSection::Custom, content)
+    }
+
+    /// Content of `section`; the first one for custom sections
+    pub fn get(&self, section: PromptSection) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|(existing, _)| *existing == section)
+            .map(|(_, content)| content.as_str())
+    }
+
+    /// Drop `section`; all custom sections for [`PromptSection::Custom`]
+    pub fn remove(&mut self, section: PromptSection) -> &mut Self {
+        self.sections.retain(|(existing, _)| *existing != section);
+        self
+    }
+
+    pub fn build(&self) -> String {
+        let mut sections: Vec<_> = self
+            .sections
+            .iter()
+            .filter(|(_, content)| !content.trim().is_empty())
+            .collect();
+        // Stable, so custom sections keep the order they were added in
+        sections.sort_by_key(|(section, _)| *section);
+        sections
+            .into_iter()
+            .map(|(_, content)| content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// `time` as `YYYY-MM-DD HH:MM UTC`
+fn format_utc(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default();
+    let (days, seconds) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_fills_in_variables() {
+        let template = PromptTemplate::new(
+            "Help {{ user_id }}{% if payload.vip %} first{% endif %}. Answer in {{ language | default('English') }}.\n",
+        );
+        let rendered = template
+            .render(&json!({"user_id": "ada", "payload": {"vip": true}}))
+            .unwrap();
+        assert_eq!(rendered, "Help ada first. Answer in English.\n");
+    }
+
+    #[test]
+    fn test_plain_prompts_are_used_as_written() {
+        let template = PromptTemplate::new("Reply with {\"answer\": ...} only.");
+        assert!(!template.is_templated());
+        assert_eq!(
+            template.render(&json!({})).unwrap(),
+            "Reply with {\"answer\": ...} only."
+        );
+        assert!(PromptTemplate::new("Hi {{ name")
+            .render(&json!({}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_task_variables_win_over_session_variables() {
+        let session = PromptVariables::new();
+        session.set("language", "French");
+        session.set("plan", "free");
+        let metadata = RunMetadata {
+            user_id: Some("ada".into()),
+            attributes: [("plan".to_string(), "pro".to_string())].into(),
+            ..Default::default()
+        };
+
+        let variables = template_variables(&session.snapshot(), &metadata, None);
+        assert_eq!(variables["language"], "French");
+        assert_eq!(variables["plan"], "pro");
+        assert_eq!(variables["user_id"], "ada");
+        assert!(variables.get("tenant_id").is_none());
+    }
+
+    #[test]
+    fn test_sections_are_built_in_order() {
+        let mut prompt = SystemPromptBuilder::new()
+            .memory("The user likes tea.")
+            .persona("You are a barista.")
+            .output_schema("");
+        prompt
+            .append("Be brief.")
+            .append("Never mention coffee prices.");
+        prompt.set(PromptSection::Tools, "Use `menu` to look up drinks.");
+        prompt.set(PromptSection::Memory, "The user likes green tea.");
+
+        assert_eq!(
+            prompt.build(),
+            "You are a barista.\n\nUse `menu` to look up drinks.\n\nThe user likes green tea.\n\nBe brief.\n\nNever mention coffee prices."
+        );
+        prompt.remove(PromptSection::Custom);
+        assert!(prompt.build().ends_with("green tea."));
+    }
+
+    #[test]
+    fn test_date_time_section() {
+        let now = UNIX_EPOCH + std::time::Duration::from_secs(1_792_245_780);
+        let prompt = SystemPromptBuilder::new().date_time(now);
+        assert_eq!(
+            prompt.get(PromptSection::DateTime),
+            Some("Current date and time: 2026-10-17 14:03 UTC")
+        );
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01 00:00 UTC");
+    }
+}
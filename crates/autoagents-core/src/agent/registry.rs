@@ -0,0 +1,321 @@
+use crate::agent::error::RunnableAgentError;
+use crate::agent::task::Task;
+use crate::agent::{AgentDeriveT, AgentExecutor, AgentHooks, BaseAgent, DirectAgent};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::agent::ActorAgentHandle;
+
+/// A built agent that can be run without knowing its concrete type.
+///
+/// Implemented for direct agents and actor agent handles; the typed output
+/// is returned as JSON.
+#[async_trait]
+pub trait RunnableAgent: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    async fn run(&self, task: Task) -> Result<Value, RunnableAgentError>;
+}
+
+fn to_value<O: serde::Serialize>(output: O) -> Result<Value, RunnableAgentError> {
+    serde_json::to_value(output).map_err(|e| RunnableAgentError::SerializationError(e.to_string()))
+}
+
+#[async_trait]
+impl<T> RunnableAgent for BaseAgent<T, DirectAgent>
+where
+    T: AgentDeriveT + AgentExecutor + AgentHooks,
+    <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
+{
+    fn name(&self) -> &str {
+        BaseAgent::name(self)
+    }
+
+    fn description(&self) -> &str {
+        BaseAgent::description(self)
+    }
+
+    async fn run(&self, task: Task) -> Result<Value, RunnableAgentError> {
+        let output: <T as AgentDeriveT>::Output = self.run(task).await?;
+        to_value(output)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl<T> RunnableAgent for ActorAgentHandle<T>
+where
+    T: AgentDeriveT + AgentExecutor + AgentHooks,
+    Value: From<<T as AgentExecutor>::Output>,
+    <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
+{
+    fn name(&self) -> &str {
+        self.agent.name()
+    }
+
+    fn description(&self) -> &str {
+        self.agent.description()
+    }
+
+    async fn run(&self, task: Task) -> Result<Value, RunnableAgentError> {
+        let output: <T as AgentDeriveT>::Output = self.agent().run(task).await?;
+        to_value(output)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AgentRegistryError {
+    #[error("Agent '{0}' is already registered")]
+    AlreadyRegistered(String),
+
+    #[error("Agent '{0}' is not registered")]
+    NotFound(String),
+
+    #[error(transparent)]
+    Run(#[from] RunnableAgentError),
+}
+
+/// A registered agent together with the capabilities it advertises.
+#[derive(Clone)]
+pub struct AgentEntry {
+    pub name: String,
+    pub description: String,
+    pub capabilities: Vec<String>,
+    pub agent: Arc<dyn RunnableAgent>,
+}
+
+impl AgentEntry {
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+impl std::fmt::Debug for AgentEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentEntry")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("capabilities", &self.capabilities)
+            .finish()
+    }
+}
+
+/// Name and capability index of running agents.
+///
+/// Supervisors, handoff targets and serving layers look agents up here
+/// instead of holding typed handles. Use [`AgentRegistry::global`] for a
+/// process-wide registry or create scoped ones with [`AgentRegistry::new`].
+#[derive(Debug, Default)]
+pub struct AgentRegistry {
+    agents: RwLock<BTreeMap<String, AgentEntry>>,
+}
+
+impl AgentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry
+    pub fn global() -> &'static AgentRegistry {
+        static GLOBAL: OnceLock<AgentRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(AgentRegistry::new)
+    }
+
+    /// Register an agent under its own name.
+    pub fn register<R, I, S>(&self, agent: R, capabilities: I) -> Result<(), AgentRegistryError>
+    where
+        R: RunnableAgent + 'static,
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let name = agent.name().to_string();
+        self.register_as(name, Arc::new(agent), capabilities)
+    }
+
+    /// Register a shared agent under an explicit name, e.g. to expose the
+    /// same agent type several times with different configuration.
+    pub fn register_as<I, S>(
+        &self,
+        name: impl Into<String>,
+        agent: Arc<dyn RunnableAgent>,
+        capabilities: I,
+    ) -> Result<(), AgentRegistryError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let name = name.into();
+        let mut agents = self.agents.write().unwrap_or_else(|e| e.into_inner());
+        if agents.contains_key(&name) {
+            return Err(AgentRegistryError::AlreadyRegistered(name));
+        }
+        let entry = AgentEntry {
+            name: name.clone(),
+            description: agent.description().to_string(),
+            capabilities: capabilities.into_iter().map(Into::into).collect(),
+            agent,
+        };
+        agents.insert(name, entry);
+        Ok(())
+    }
+
+    pub fn unregister(&self, name: &str) -> Option<AgentEntry> {
+        self.agents
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn RunnableAgent>> {
+        self.entry(name).map(|entry| entry.agent)
+    }
+
+    pub fn entry(&self, name: &str) -> Option<AgentEntry> {
+        self.agents
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.agents
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(name)
+    }
+
+    /// Agents advertising `capability`, ordered by name
+    pub fn find_by_capability(&self, capability: &str) -> Vec<AgentEntry> {
+        self.agents
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|entry| entry.has_capability(capability))
+            .cloned()
+            .collect()
+    }
+
+    pub fn entries(&self) -> Vec<AgentEntry> {
+        self.agents
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.agents
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.agents.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run a task on the agent registered as `name`.
+    pub async fn run(&self, name: &str, task: Task) -> Result<Value, AgentRegistryError> {
+        let agent = self
+            .get(name)
+            .ok_or_else(|| AgentRegistryError::NotFound(name.to_string()))?;
+        Ok(agent.run(task).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentBuilder;
+    use crate::tests::agent::MockAgentImpl;
+    use autoagents_test_utils::llm::MockLLMProvider;
+
+    async fn direct_agent(name: &str) -> BaseAgent<MockAgentImpl, DirectAgent> {
+        AgentBuilder::<_, DirectAgent>::new(MockAgentImpl::new(name, "mock agent"))
+            .llm(Arc::new(MockLLMProvider))
+            .build()
+            .await
+            .unwrap()
+            .agent
+    }
+
+    #[tokio::test]
+    async fn test_register_and_run_by_name() {
+        let registry = AgentRegistry::new();
+        registry
+            .register(direct_agent("writer").await, ["writing"])
+            .unwrap();
+
+        assert!(registry.contains("writer"));
+        assert_eq!(registry.entry("writer").unwrap().description, "mock agent");
+
+        let output = registry.run("writer", Task::new("draft")).await.unwrap();
+        assert_eq!(output["result"], "Processed: draft");
+
+        assert!(matches!(
+            registry.run("missing", Task::new("x")).await,
+            Err(AgentRegistryError::NotFound(name)) if name == "missing"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_names_are_rejected() {
+        let registry = AgentRegistry::new();
+        registry
+            .register(direct_agent("a").await, Vec::<String>::new())
+            .unwrap();
+        let err = registry
+            .register(direct_agent("a").await, Vec::<String>::new())
+            .unwrap_err();
+        assert!(matches!(err, AgentRegistryError::AlreadyRegistered(_)));
+
+        registry
+            .register_as("a-copy", Arc::new(direct_agent("a").await), ["copy"])
+            .unwrap();
+        assert_eq!(registry.names(), vec!["a", "a-copy"]);
+
+        assert!(registry.unregister("a").is_some());
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_by_capability() {
+        let registry = AgentRegistry::new();
+        registry
+            .register(direct_agent("coder").await, ["code", "review"])
+            .unwrap();
+        registry
+            .register(direct_agent("editor").await, ["review"])
+            .unwrap();
+
+        let reviewers: Vec<_> = registry
+            .find_by_capability("review")
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(reviewers, vec!["coder", "editor"]);
+        assert_eq!(registry.find_by_capability("code").len(), 1);
+        assert!(registry.find_by_capability("deploy").is_empty());
+    }
+
+    #[test]
+    fn test_global_registry_is_shared() {
+        assert!(std::ptr::eq(
+            AgentRegistry::global(),
+            AgentRegistry::global()
+        ));
+    }
+}
@@ -0,0 +1,426 @@
+use crate::agent::executor::memory_helper::MemoryHelper;
+use crate::agent::memory::MemoryProvider;
+use crate::agent::task::Task;
+use crate::agent::{AgentConfig, AgentDeriveT, AgentExecutor, Context};
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::LLMProvider;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::Mutex;
+
+#[cfg(target_arch = "wasm32")]
+use futures::lock::Mutex;
+
+/// Bounds on sub-agent spawning, inherited by every child context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubAgentLimits {
+    /// Maximum nesting depth; the top-level run is depth 0
+    pub max_depth: usize,
+    /// Maximum number of children a single run may spawn
+    pub max_children: usize,
+}
+
+impl Default for SubAgentLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            max_children: 8,
+        }
+    }
+}
+
+impl SubAgentLimits {
+    pub fn new(max_depth: usize, max_children: usize) -> Self {
+        Self {
+            max_depth,
+            max_children,
+        }
+    }
+}
+
+/// Memory a sub-agent starts with.
+#[derive(Default)]
+pub enum SubAgentMemory {
+    /// No history, the child only sees its task
+    #[default]
+    Fresh,
+    /// A copy of the parent's memory; the child's own messages are not
+    /// written back to the parent
+    Inherit,
+    /// A dedicated memory provider
+    Custom(Box<dyn MemoryProvider>),
+}
+
+impl std::fmt::Debug for SubAgentMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fresh => f.write_str("Fresh"),
+            Self::Inherit => f.write_str("Inherit"),
+            Self::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// A child agent to run from inside an executor with
+/// [`Context::spawn_sub_agent`].
+pub struct SubAgent<T> {
+    inner: T,
+    memory: SubAgentMemory,
+    llm: Option<Arc<dyn LLMProvider>>,
+    fold_result: bool,
+}
+
+impl<T: AgentDeriveT> std::fmt::Debug for SubAgent<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubAgent")
+            .field("agent", &self.inner)
+            .field("memory", &self.memory)
+            .field("fold_result", &self.fold_result)
+            .finish()
+    }
+}
+
+impl<T: AgentDeriveT + AgentExecutor> SubAgent<T> {
+    /// Child using the parent's LLM with fresh memory, whose result is
+    /// added to the parent's memory.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            memory: SubAgentMemory::Fresh,
+            llm: None,
+            fold_result: true,
+        }
+    }
+
+    pub fn memory(mut self, memory: SubAgentMemory) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    /// Use a different LLM than the parent
+    pub fn llm(mut self, llm: Arc<dyn LLMProvider>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    /// Whether the child's result is stored in the parent's memory as an
+    /// assistant message (default `true`)
+    pub fn fold_result(mut self, fold: bool) -> Self {
+        self.fold_result = fold;
+        self
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SubAgentError {
+    #[error("Sub-agent depth limit of {max_depth} reached")]
+    DepthExceeded { max_depth: usize },
+
+    #[error("Sub-agent fan-out limit of {max_children} reached")]
+    FanOutExceeded { max_children: usize },
+
+    #[error("Sub-agent `{agent}` failed: {error}")]
+    Execution { agent: String, error: String },
+
+    #[error("Failed to serialize sub-agent output: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Record of a finished sub-agent run, kept on the parent context.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubAgentRun {
+    pub agent: String,
+    pub prompt: String,
+    pub depth: usize,
+    pub output: Value,
+}
+
+impl Context {
+    /// Run `sub_agent` on `task` as a child of the current run and wait for
+    /// its output.
+    ///
+    /// The child gets its own context one level deeper than this one,
+    /// sharing the run metadata and event channel. Spawning fails once the
+    /// configured depth or per-run fan-out limit is reached, which stops
+    /// agents that spawn themselves from recursing without bound.
+    pub async fn spawn_sub_agent<T>(
+        &self,
+        sub_agent: SubAgent<T>,
+        task: Task,
+    ) -> Result<<T as AgentDeriveT>::Output, SubAgentError>
+    where
+        T: AgentDeriveT + AgentExecutor,
+        <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
+    {
+        let limits = self.sub_agent_limits();
+        if self.depth() >= limits.max_depth {
+            return Err(SubAgentError::DepthExceeded {
+                max_depth: limits.max_depth,
+            });
+        }
+        if !self.reserve_sub_agent_slot() {
+            return Err(SubAgentError::FanOutExceeded {
+                max_children: limits.max_children,
+            });
+        }
+
+        let SubAgent {
+            inner,
+            memory,
+            llm,
+            fold_result,
+        } = sub_agent;
+        let name = inner.name();
+        let memory = match memory {
+            SubAgentMemory::Fresh => None,
+            SubAgentMemory::Inherit => match self.memory() {
+                Some(parent) => {
+                    let snapshot = parent.lock().await.clone_box();
+                    Some(Arc::new(Mutex::new(snapshot)))
+                }
+                None => None,
+            },
+            SubAgentMemory::Custom(memory) => Some(Arc::new(Mutex::new(memory))),
+        };
+        let config = AgentConfig {
+            name: name.to_string(),
+            description: inner.description().to_string(),
+            id: Uuid::new_v4(),
+            output_schema: inner
+                .output_schema()
+                .and_then(|schema| serde_json::from_value(schema).ok()),
+        };
+        let depth = self.depth() + 1;
+        let child = Context::new(llm.unwrap_or_else(|| self.llm().clone()), self.tx().ok())
+            .with_memory(memory)
+            .with_tools(inner.tools())
+            .with_config(config)
+            .with_metadata(self.metadata().clone())
+            .with_prompt_variables(self.prompt_variables().clone())
+            .with_depth(depth)
+            .with_sub_agent_limits(limits);
+        #[cfg(not(target_arch = "wasm32"))]
+        let child = child.with_event_bus(self.event_bus().cloned());
+        let child = Arc::new(child);
+
+        let output = child
+            .scoped(inner.execute(&task, child.clone()))
+            .await
+            .map_err(|e| SubAgentError::Execution {
+                agent: name.to_string(),
+                error: e.to_string(),
+            })?;
+        let output: <T as AgentDeriveT>::Output = output.into();
+        let value = serde_json::to_value(&output)?;
+
+        if fold_result {
+            let rendered = match &value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            MemoryHelper::store_message(
+                &self.memory(),
+                ChatMessage {
+                    role: ChatRole::Assistant,
+                    message_type: MessageType::Text,
+                    content: format!("Result from sub-agent `{name}`:\n{rendered}"),
+                    reasoning: Vec::new(),
+                },
+            )
+            .await;
+        }
+        self.record_sub_agent_run(SubAgentRun {
+            agent: name.to_string(),
+            prompt: task.prompt,
+            depth,
+            output: value,
+        });
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::SlidingWindowMemory;
+    use crate::agent::{AgentHooks, ExecutorConfig};
+    use crate::tests::agent::MockAgentImpl;
+    use crate::tool::ToolT;
+    use async_trait::async_trait;
+    use autoagents_test_utils::llm::MockLLMProvider;
+    use futures::Stream;
+    use std::pin::Pin;
+
+    fn parent_context(limits: SubAgentLimits) -> Context {
+        let memory: Box<dyn MemoryProvider> = Box::new(SlidingWindowMemory::new(10));
+        Context::new(Arc::new(MockLLMProvider), None)
+            .with_memory(Some(Arc::new(Mutex::new(memory))))
+            .with_sub_agent_limits(limits)
+    }
+
+    async fn memory_len(context: &Context) -> usize {
+        context.memory().unwrap().lock().await.size()
+    }
+
+    /// Spawns a copy of itself until spawning fails, returning how deep it got.
+    #[derive(Debug)]
+    struct Recursive;
+
+    impl AgentDeriveT for Recursive {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "recurses"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "recursive"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    impl AgentHooks for Recursive {}
+
+    #[async_trait]
+    impl AgentExecutor for Recursive {
+        type Output = String;
+        type Error = SubAgentError;
+
+        fn config(&self) -> ExecutorConfig {
+            ExecutorConfig::default()
+        }
+
+        async fn execute(
+            &self,
+            task: &Task,
+            context: Arc<Context>,
+        ) -> Result<Self::Output, Self::Error> {
+            match context
+                .spawn_sub_agent(SubAgent::new(Recursive), task.clone())
+                .await
+            {
+                Ok(deepest) => Ok(deepest),
+                Err(SubAgentError::DepthExceeded { .. }) => Ok(context.depth().to_string()),
+                Err(e) => Err(e),
+            }
+        }
+
+        async fn execute_stream(
+            &self,
+            _task: &Task,
+            _context: Arc<Context>,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<String, SubAgentError>> + Send>>, SubAgentError>
+        {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_result_is_folded_into_parent() {
+        let context = parent_context(SubAgentLimits::default());
+        let output = context
+            .spawn_sub_agent(
+                SubAgent::new(MockAgentImpl::new("researcher", "finds facts")),
+                Task::new("look up rust"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.result, "Processed: look up rust");
+
+        let folded = context
+            .memory()
+            .unwrap()
+            .lock()
+            .await
+            .recall("", None)
+            .await
+            .unwrap();
+        assert_eq!(folded.len(), 1);
+        assert_eq!(folded[0].role, ChatRole::Assistant);
+        assert!(folded[0].content.contains("sub-agent `researcher`"));
+        assert!(folded[0].content.contains("Processed: look up rust"));
+
+        let runs = context.sub_agent_runs();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].depth, 1);
+        assert_eq!(runs[0].output["result"], "Processed: look up rust");
+    }
+
+    #[tokio::test]
+    async fn test_fold_can_be_disabled() {
+        let context = parent_context(SubAgentLimits::default());
+        context
+            .spawn_sub_agent(
+                SubAgent::new(MockAgentImpl::new("a", "b"))
+                    .memory(SubAgentMemory::Inherit)
+                    .fold_result(false),
+                Task::new("quiet"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(memory_len(&context).await, 0);
+        assert_eq!(context.sub_agent_runs().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_limit() {
+        let context = parent_context(SubAgentLimits::new(3, 2));
+        for _ in 0..2 {
+            context
+                .spawn_sub_agent(SubAgent::new(MockAgentImpl::new("a", "b")), Task::new("x"))
+                .await
+                .unwrap();
+        }
+        let err = context
+            .spawn_sub_agent(SubAgent::new(MockAgentImpl::new("a", "b")), Task::new("x"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            SubAgentError::FanOutExceeded { max_children: 2 }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recursion_stops_at_max_depth() {
+        let context = parent_context(SubAgentLimits::new(4, 8));
+        let deepest = context
+            .spawn_sub_agent(SubAgent::new(Recursive), Task::new("go"))
+            .await
+            .unwrap();
+        assert_eq!(deepest, "4");
+
+        let context = parent_context(SubAgentLimits::new(0, 8));
+        assert!(matches!(
+            context
+                .spawn_sub_agent(SubAgent::new(Recursive), Task::new("go"))
+                .await,
+            Err(SubAgentError::DepthExceeded { max_depth: 0 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_executor_errors_are_reported() {
+        let context = parent_context(SubAgentLimits::default());
+        let err = context
+            .spawn_sub_agent(
+                SubAgent::new(MockAgentImpl::new("flaky", "fails").with_failure(true)),
+                Task::new("x"),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SubAgentError::Execution { agent, .. } if agent == "flaky"));
+        assert!(context.sub_agent_runs().is_empty());
+    }
+}
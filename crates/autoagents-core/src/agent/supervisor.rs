@@ -0,0 +1,414 @@
+use crate::agent::actor::AgentActor;
+use crate::agent::task::Task;
+use crate::agent::{ActorAgent, AgentDeriveT, AgentExecutor, AgentHooks, BaseAgent};
+use crate::protocol::Event;
+use async_trait::async_trait;
+use ractor::{Actor, ActorProcessingErr, ActorRef, SupervisionEvent};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How a supervised actor agent reacts when its actor fails.
+///
+/// An actor agent fails when a task run returns an error; without
+/// supervision the agent stops and later messages to it are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartStrategy {
+    /// Stop the agent on its first failure
+    Never,
+    /// Start a fresh actor after each failure, waiting `backoff` first.
+    ///
+    /// Gives up and stops the agent when more than `max_restarts` restarts
+    /// happen within `window`.
+    OnFailure {
+        max_restarts: usize,
+        window: Duration,
+        backoff: Duration,
+    },
+}
+
+impl Default for RestartStrategy {
+    fn default() -> Self {
+        Self::OnFailure {
+            max_restarts: 3,
+            window: Duration::from_secs(60),
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RestartStrategy {
+    pub fn on_failure(max_restarts: usize, window: Duration) -> Self {
+        Self::OnFailure {
+            max_restarts,
+            window,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    pub fn with_backoff(self, backoff: Duration) -> Self {
+        match self {
+            Self::Never => Self::Never,
+            Self::OnFailure {
+                max_restarts,
+                window,
+                ..
+            } => Self::OnFailure {
+                max_restarts,
+                window,
+                backoff,
+            },
+        }
+    }
+}
+
+/// Parent actor that owns an agent's actor and restarts it on failure.
+///
+/// The supervisor receives the agent's tasks and forwards them to the current
+/// child, so the address handed out by the builder stays valid across
+/// restarts. The agent itself, including its memory, is shared by every
+/// incarnation; only the actor is replaced.
+pub(crate) struct AgentSupervisor<T: AgentDeriveT + AgentExecutor + AgentHooks> {
+    agent: Arc<BaseAgent<T, ActorAgent>>,
+    strategy: RestartStrategy,
+}
+
+impl<T: AgentDeriveT + AgentExecutor + AgentHooks> AgentSupervisor<T> {
+    pub(crate) fn new(agent: Arc<BaseAgent<T, ActorAgent>>, strategy: RestartStrategy) -> Self {
+        Self { agent, strategy }
+    }
+}
+
+pub(crate) struct SupervisorState {
+    child: ActorRef<Task>,
+    history: RestartHistory,
+}
+
+/// Restarts performed so far, for enforcing the restart window.
+#[derive(Debug, Default)]
+struct RestartHistory {
+    recent: VecDeque<Instant>,
+    total: usize,
+}
+
+impl RestartHistory {
+    /// Record a restart, returning `false` when `strategy` does not allow it.
+    fn allow(&mut self, strategy: RestartStrategy, now: Instant) -> bool {
+        let RestartStrategy::OnFailure {
+            max_restarts,
+            window,
+            ..
+        } = strategy
+        else {
+            return false;
+        };
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > window)
+        {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= max_restarts {
+            return false;
+        }
+        self.recent.push_back(now);
+        self.total += 1;
+        true
+    }
+}
+
+impl<T> AgentSupervisor<T>
+where
+    T: AgentDeriveT + AgentExecutor + AgentHooks,
+    serde_json::Value: From<<T as AgentExecutor>::Output>,
+    <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
+{
+    async fn spawn_child(
+        &self,
+        supervisor: &ActorRef<Task>,
+    ) -> Result<ActorRef<Task>, ActorProcessingErr> {
+        let (child, _) = Actor::spawn_linked(
+            None,
+            AgentActor(self.agent.clone()),
+            (),
+            supervisor.get_cell(),
+        )
+        .await?;
+        Ok(child)
+    }
+}
+
+#[async_trait]
+impl<T> Actor for AgentSupervisor<T>
+where
+    T: AgentDeriveT + AgentExecutor + AgentHooks + Send + Sync + 'static,
+    serde_json::Value: From<<T as AgentExecutor>::Output>,
+    <T as AgentDeriveT>::Output: From<<T as AgentExecutor>::Output>,
+{
+    type Msg = Task;
+    type State = SupervisorState;
+    type Arguments = ();
+
+    async fn pre_start(
+        &self,
+        myself: ActorRef<Self::Msg>,
+        _args: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        Ok(SupervisorState {
+            child: self.spawn_child(&myself).await?,
+            history: RestartHistory::default(),
+        })
+    }
+
+    async fn post_stop(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        state.child.stop(None);
+        Ok(())
+    }
+
+    async fn handle(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        state.child.cast(message)?;
+        Ok(())
+    }
+
+    async fn handle_supervisor_evt(
+        &self,
+        myself: ActorRef<Self::Msg>,
+        message: SupervisionEvent,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        match message {
+            SupervisionEvent::ActorFailed(cell, error) if cell.get_id() == state.child.get_id() => {
+                if !state.history.allow(self.strategy, Instant::now()) {
+                    log::error!(
+                        "Agent `{}` failed and will not be restarted: {error}",
+                        self.agent.name()
+                    );
+                    myself.stop(Some(format!("agent failed: {error}")));
+                    return Ok(());
+                }
+                log::warn!(
+                    "Agent `{}` failed, restarting (restart #{}): {error}",
+                    self.agent.name(),
+                    state.history.total
+                );
+                if let RestartStrategy::OnFailure { backoff, .. } = self.strategy {
+                    if !backoff.is_zero() {
+                        tokio::time::sleep(backoff).await;
+                    }
+                }
+                state.child = self.spawn_child(&myself).await?;
+                if let Some(tx) = &self.agent.tx {
+                    let _ = tx
+                        .send(Event::AgentRestarted {
+                            actor_id: self.agent.id,
+                            actor_name: self.agent.name().to_string(),
+                            restarts: state.history.total,
+                            error: error.to_string(),
+                        })
+                        .await;
+                }
+            }
+            SupervisionEvent::ActorTerminated(cell, _, reason)
+                if cell.get_id() == state.child.get_id() =>
+            {
+                // A clean stop of the agent ends supervision as well
+                myself.stop(reason);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{AgentBuilder, Context, ExecutorConfig};
+    use crate::runtime::SingleThreadedRuntime;
+    use crate::tool::ToolT;
+    use autoagents_test_utils::llm::MockLLMProvider;
+    use futures::Stream;
+    use serde_json::Value;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("asked to fail")]
+    struct AskedToFail;
+
+    /// Fails on tasks whose prompt is "fail" and counts the tasks it completes.
+    #[derive(Debug)]
+    struct Flaky {
+        name: &'static str,
+        completed: Arc<AtomicUsize>,
+    }
+
+    impl AgentDeriveT for Flaky {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "fails on request"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    impl AgentHooks for Flaky {}
+
+    #[async_trait]
+    impl AgentExecutor for Flaky {
+        type Output = String;
+        type Error = AskedToFail;
+
+        fn config(&self) -> ExecutorConfig {
+            ExecutorConfig::default()
+        }
+
+        async fn execute(
+            &self,
+            task: &Task,
+            _context: Arc<Context>,
+        ) -> Result<String, AskedToFail> {
+            if task.prompt == "fail" {
+                return Err(AskedToFail);
+            }
+            self.completed.fetch_add(1, Ordering::SeqCst);
+            Ok("done".into())
+        }
+
+        async fn execute_stream(
+            &self,
+            _task: &Task,
+            _context: Arc<Context>,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<String, AskedToFail>> + Send>>, AskedToFail>
+        {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+    }
+
+    struct Supervised {
+        addr: ActorRef<Task>,
+        completed: Arc<AtomicUsize>,
+        // Runs fail once the runtime's event channel is gone
+        _runtime: Arc<SingleThreadedRuntime>,
+    }
+
+    /// Actor names are global, so each test gives its agent its own `name`.
+    async fn supervised(name: &'static str, strategy: RestartStrategy) -> Supervised {
+        let completed = Arc::new(AtomicUsize::new(0));
+        let runtime = SingleThreadedRuntime::new(None);
+        let handle = AgentBuilder::<_, ActorAgent>::new(Flaky {
+            name,
+            completed: completed.clone(),
+        })
+        .llm(Arc::new(MockLLMProvider))
+        .runtime(runtime.clone())
+        .supervise(strategy)
+        .build()
+        .await
+        .unwrap();
+        Supervised {
+            addr: handle.addr(),
+            completed,
+            _runtime: runtime,
+        }
+    }
+
+    /// Tasks queued on a failing actor are lost, so let a restart finish first.
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    async fn wait_for(completed: &AtomicUsize, expected: usize) {
+        for _ in 0..100 {
+            if completed.load(Ordering::SeqCst) >= expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!(
+            "expected {expected} completed tasks, got {}",
+            completed.load(Ordering::SeqCst)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_agent_keeps_serving_after_failure() {
+        let Supervised {
+            addr,
+            completed,
+            _runtime,
+        } = supervised(
+            "keeps_serving",
+            RestartStrategy::on_failure(3, Duration::from_secs(60)),
+        )
+        .await;
+
+        addr.cast(Task::new("fail")).unwrap();
+        settle().await;
+        addr.cast(Task::new("work")).unwrap();
+        wait_for(&completed, 1).await;
+
+        addr.cast(Task::new("fail")).unwrap();
+        settle().await;
+        addr.cast(Task::new("work")).unwrap();
+        wait_for(&completed, 2).await;
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_gives_up_after_max_restarts() {
+        let Supervised {
+            addr,
+            completed,
+            _runtime,
+        } = supervised(
+            "gives_up",
+            RestartStrategy::on_failure(1, Duration::from_secs(60)),
+        )
+        .await;
+
+        addr.cast(Task::new("fail")).unwrap();
+        settle().await;
+        addr.cast(Task::new("work")).unwrap();
+        wait_for(&completed, 1).await;
+
+        addr.cast(Task::new("fail")).unwrap();
+        settle().await;
+        assert!(addr.cast(Task::new("work")).is_err());
+        assert_eq!(completed.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_restart_window_expires() {
+        let strategy = RestartStrategy::on_failure(1, Duration::from_secs(10));
+        let start = Instant::now();
+        let mut history = RestartHistory::default();
+
+        assert!(history.allow(strategy, start));
+        assert!(!history.allow(strategy, start + Duration::from_secs(5)));
+        assert!(history.allow(strategy, start + Duration::from_secs(11)));
+        assert_eq!(history.total, 2);
+
+        assert!(!RestartHistory::default().allow(RestartStrategy::Never, start));
+    }
+}
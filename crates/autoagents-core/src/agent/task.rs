@@ -1,18 +1,133 @@
 #[cfg(not(target_arch = "wasm32"))]
 use crate::actor::{ActorMessage, CloneableMessage};
-use crate::protocol::SubmissionId;
-use autoagents_llm::chat::ImageMime;
+use crate::protocol::{RunId, SubmissionId};
+use crate::tool::ToolGroups;
+use autoagents_llm::audio::Audio;
+use autoagents_llm::chat::{ChatMessage, ChatRole, ImageMime, MessageType};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
+/// Header carrying the run ID on outgoing LLM requests
+pub const RUN_ID_HEADER: &str = "x-autoagents-run-id";
+/// Header carrying the caller's user ID on outgoing LLM requests, when
+/// [`RunMetadata::forward_ids`] is set
+pub const USER_ID_HEADER: &str = "x-autoagents-user-id";
+/// Header carrying the tenant ID on outgoing LLM requests, when
+/// [`RunMetadata::forward_ids`] is set
+pub const TENANT_ID_HEADER: &str = "x-autoagents-tenant-id";
+/// Header carrying comma separated run tags on outgoing LLM requests
+pub const TAGS_HEADER: &str = "x-autoagents-tags";
+
+/// Caller supplied metadata attached to a task.
+///
+/// The metadata travels with the task into hooks (via [`crate::agent::Context::metadata`]),
+/// task lifecycle events, and the headers of every LLM request made during the run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub user_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub tags: Vec<String>,
+    pub attributes: HashMap<String, String>,
+    /// Send the user and tenant IDs to the LLM provider as request headers.
+    /// Off by default so caller identities stay with the application.
+    #[serde(default)]
+    pub forward_ids: bool,
+}
+
+impl RunMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.user_id.is_none()
+            && self.tenant_id.is_none()
+            && self.tags.is_empty()
+            && self.attributes.is_empty()
+    }
+
+    /// Key under which a [`NamespacedMemory`](crate::agent::memory::NamespacedMemory)
+    /// keeps this caller's history: `tenant/user`, with the missing part left
+    /// empty, or `None` when neither is set.
+    pub fn memory_namespace(&self) -> Option<String> {
+        if self.tenant_id.is_none() && self.user_id.is_none() {
+            return None;
+        }
+        Some(format!(
+            "{}/{}",
+            self.tenant_id.as_deref().unwrap_or_default(),
+            self.user_id.as_deref().unwrap_or_default()
+        ))
+    }
+
+    /// Build the HTTP headers forwarded to LLM providers for a run.
+    ///
+    /// Free-form attributes are not forwarded since they may hold data the
+    /// caller does not want to leave the process, and user and tenant IDs
+    /// only when [`Self::forward_ids`] is set.
+    pub fn to_headers(&self, run_id: RunId) -> BTreeMap<String, String> {
+        let mut headers = BTreeMap::new();
+        headers.insert(RUN_ID_HEADER.to_string(), run_id.to_string());
+        if self.forward_ids {
+            if let Some(user_id) = &self.user_id {
+                headers.insert(USER_ID_HEADER.to_string(), user_id.clone());
+            }
+            if let Some(tenant_id) = &self.tenant_id {
+                headers.insert(TENANT_ID_HEADER.to_string(), tenant_id.clone());
+            }
+        }
+        if !self.tags.is_empty() {
+            headers.insert(TAGS_HEADER.to_string(), self.tags.join(","));
+        }
+        headers
+    }
+}
+
+/// Binary input attached to a task, such as a file or image
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub name: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    pub fn new<N: Into<String>, M: Into<String>>(name: N, mime_type: M, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            mime_type: mime_type.into(),
+            data,
+        }
+    }
+
+    /// Interpret the data as UTF-8 text, if it is valid
+    pub fn as_text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub prompt: String,
     pub image: Option<(ImageMime, Vec<u8>)>,
+    /// Remote image passed to the model by URL
+    #[serde(default)]
+    pub image_url: Option<String>,
+    /// Spoken input, transcribed into the prompt before the run starts
+    #[serde(default)]
+    pub audio: Option<Audio>,
     pub submission_id: SubmissionId,
     pub completed: bool,
     pub result: Option<Value>,
+    #[serde(default)]
+    pub metadata: RunMetadata,
+    /// Structured input alongside the prompt
+    #[serde(default)]
+    pub payload: Option<Value>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Tool groups available to this task
+    #[serde(default)]
+    pub tool_groups: ToolGroups,
 }
 
 impl Task {
@@ -20,9 +135,15 @@ impl Task {
         Self {
             prompt: task.into(),
             image: None,
+            image_url: None,
+            audio: None,
             submission_id: Uuid::new_v4(),
             completed: false,
             result: None,
+            metadata: RunMetadata::default(),
+            payload: None,
+            attachments: Vec::new(),
+            tool_groups: ToolGroups::default(),
         }
     }
 
@@ -34,11 +155,166 @@ impl Task {
         Self {
             prompt: task.into(),
             image: Some((image_mime, image_data)),
+            image_url: None,
+            audio: None,
             submission_id: Uuid::new_v4(),
             completed: false,
             result: None,
+            metadata: RunMetadata::default(),
+            payload: None,
+            attachments: Vec::new(),
+            tool_groups: ToolGroups::default(),
         }
     }
+
+    pub fn new_with_image_url<T: Into<String>, U: Into<String>>(task: T, url: U) -> Self {
+        Self::new(task).with_image_url(url)
+    }
+
+    pub fn with_image(mut self, image_mime: ImageMime, image_data: Vec<u8>) -> Self {
+        self.image = Some((image_mime, image_data));
+        self
+    }
+
+    pub fn with_image_url<U: Into<String>>(mut self, url: U) -> Self {
+        self.image_url = Some(url.into());
+        self
+    }
+
+    /// A task given entirely by voice, the transcript becoming its prompt
+    pub fn new_with_audio(audio: Audio) -> Self {
+        Self::new(String::new()).with_audio(audio)
+    }
+
+    pub fn with_audio(mut self, audio: Audio) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+
+    /// User messages that carry this task to the LLM.
+    ///
+    /// The first message holds the prompt together with the task image, or the
+    /// image URL when there are no image bytes. Any remaining images, including
+    /// attachments with an image MIME type, follow as separate messages so
+    /// providers receive them as native image parts.
+    pub fn user_messages(&self) -> Vec<ChatMessage> {
+        let mut images: Vec<MessageType> = Vec::new();
+        if let Some((mime, data)) = &self.image {
+            images.push(MessageType::Image((*mime, data.clone())));
+        }
+        if let Some(url) = &self.image_url {
+            images.push(MessageType::ImageURL(url.clone()));
+        }
+        images.extend(self.attachments.iter().filter_map(|attachment| {
+            ImageMime::from_mime_type(&attachment.mime_type)
+                .map(|mime| MessageType::Image((mime, attachment.data.clone())))
+        }));
+
+        let mut images = images.into_iter();
+        let mut messages = vec![ChatMessage {
+            role: ChatRole::User,
+            message_type: images.next().unwrap_or_default(),
+            content: self.user_content(),
+            reasoning: Vec::new(),
+        }];
+        messages.extend(images.map(|message_type| ChatMessage {
+            role: ChatRole::User,
+            message_type,
+            content: String::new(),
+            reasoning: Vec::new(),
+        }));
+        messages
+    }
+
+    /// Attach a structured payload to the task
+    pub fn with_payload<P: Serialize>(mut self, payload: P) -> Result<Self, serde_json::Error> {
+        self.payload = Some(serde_json::to_value(payload)?);
+        Ok(self)
+    }
+
+    /// Deserialize the payload into `P`, or `None` if the task has no payload
+    pub fn payload_as<P: DeserializeOwned>(&self) -> Option<Result<P, serde_json::Error>> {
+        self.payload
+            .as_ref()
+            .map(|payload| serde_json::from_value(payload.clone()))
+    }
+
+    pub fn with_attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    pub fn attachment(&self, name: &str) -> Option<&Attachment> {
+        self.attachments.iter().find(|a| a.name == name)
+    }
+
+    /// Text sent to the LLM for this task.
+    ///
+    /// The prompt is followed by the payload as JSON and a list of attachment
+    /// names, so the model knows about inputs it can refer to.
+    pub fn user_content(&self) -> String {
+        let mut content = self.prompt.clone();
+        if let Some(payload) = &self.payload {
+            let json = serde_json::to_string_pretty(payload).unwrap_or_default();
+            content.push_str(&format!("\n\nPayload:\n```json\n{json}\n```"));
+        }
+        if !self.attachments.is_empty() {
+            content.push_str("\n\nAttachments:");
+            for attachment in &self.attachments {
+                content.push_str(&format!(
+                    "\n- {} ({}, {} bytes)",
+                    attachment.name,
+                    attachment.mime_type,
+                    attachment.data.len()
+                ));
+            }
+        }
+        content
+    }
+
+    pub fn with_metadata(mut self, metadata: RunMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_user_id<S: Into<String>>(mut self, user_id: S) -> Self {
+        self.metadata.user_id = Some(user_id.into());
+        self
+    }
+
+    pub fn with_tenant_id<S: Into<String>>(mut self, tenant_id: S) -> Self {
+        self.metadata.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Send the user and tenant IDs to the LLM provider, see
+    /// [`RunMetadata::forward_ids`]
+    pub fn with_forwarded_ids(mut self) -> Self {
+        self.metadata.forward_ids = true;
+        self
+    }
+
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.metadata.tags.push(tag.into());
+        self
+    }
+
+    pub fn with_attribute<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.metadata.attributes.insert(key.into(), value.into());
+        self
+    }
+
+    /// Offer only the enabled tool groups, plus tools without a group
+    pub fn enable_tool_group<S: Into<String>>(mut self, group: S) -> Self {
+        self.tool_groups = self.tool_groups.enable(group);
+        self
+    }
+
+    /// Hide every tool of `group` for this task
+    pub fn disable_tool_group<S: Into<String>>(mut self, group: S) -> Self {
+        self.tool_groups = self.tool_groups.disable(group);
+        self
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -166,4 +442,181 @@ mod tests {
         assert_eq!(deserialized.image, task.image);
         assert_eq!(deserialized.submission_id, task.submission_id);
     }
+
+    #[test]
+    fn test_task_metadata_builders() {
+        let task = Task::new("With metadata")
+            .with_user_id("user-1")
+            .with_tenant_id("acme")
+            .with_tag("beta")
+            .with_tag("eval")
+            .with_attribute("region", "eu");
+
+        assert_eq!(task.metadata.user_id.as_deref(), Some("user-1"));
+        assert_eq!(task.metadata.tenant_id.as_deref(), Some("acme"));
+        assert_eq!(task.metadata.tags, vec!["beta", "eval"]);
+        assert_eq!(task.metadata.attributes.get("region").unwrap(), "eu");
+        assert!(!task.metadata.is_empty());
+        assert!(Task::new("plain").metadata.is_empty());
+    }
+
+    #[test]
+    fn test_memory_namespace() {
+        assert_eq!(Task::new("plain").metadata.memory_namespace(), None);
+        let task = Task::new("t").with_user_id("u1");
+        assert_eq!(task.metadata.memory_namespace().as_deref(), Some("/u1"));
+        let task = task.with_tenant_id("acme");
+        assert_eq!(task.metadata.memory_namespace().as_deref(), Some("acme/u1"));
+    }
+
+    #[test]
+    fn test_task_deserialize_without_metadata() {
+        let task = Task::new("Legacy");
+        let mut value = serde_json::to_value(&task).unwrap();
+        value.as_object_mut().unwrap().remove("metadata");
+
+        let deserialized: Task = serde_json::from_value(value).unwrap();
+        assert!(deserialized.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_run_metadata_headers() {
+        let run_id = Uuid::new_v4();
+        let metadata = RunMetadata {
+            user_id: Some("user-1".into()),
+            tenant_id: None,
+            tags: vec!["a".into(), "b".into()],
+            attributes: HashMap::from([("secret".into(), "x".into())]),
+            forward_ids: false,
+        };
+
+        let headers = metadata.to_headers(run_id);
+        assert_eq!(headers.get(RUN_ID_HEADER).unwrap(), &run_id.to_string());
+        assert_eq!(headers.get(TAGS_HEADER).unwrap(), "a,b");
+        assert!(!headers.contains_key(USER_ID_HEADER));
+        assert_eq!(headers.len(), 2);
+
+        let headers = RunMetadata {
+            forward_ids: true,
+            ..metadata
+        }
+        .to_headers(run_id);
+        assert_eq!(headers.get(USER_ID_HEADER).unwrap(), "user-1");
+        assert!(!headers.contains_key(TENANT_ID_HEADER));
+        assert_eq!(headers.len(), 3);
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Order {
+        id: u32,
+        items: Vec<String>,
+    }
+
+    #[test]
+    fn test_task_typed_payload_roundtrip() {
+        let order = Order {
+            id: 7,
+            items: vec!["apple".into()],
+        };
+        let task = Task::new("Process order").with_payload(&order).unwrap();
+
+        let parsed: Order = task.payload_as().unwrap().unwrap();
+        assert_eq!(parsed, order);
+        assert!(Task::new("none").payload_as::<Order>().is_none());
+        assert!(task.payload_as::<Vec<u8>>().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_task_attachments() {
+        let task = Task::new("Summarize")
+            .with_attachment(Attachment::new(
+                "notes.txt",
+                "text/plain",
+                b"hello".to_vec(),
+            ))
+            .with_attachment(Attachment::new(
+                "blob.bin",
+                "application/octet-stream",
+                vec![0xff],
+            ));
+
+        assert_eq!(task.attachments.len(), 2);
+        assert_eq!(
+            task.attachment("notes.txt").unwrap().as_text(),
+            Some("hello")
+        );
+        assert!(task.attachment("blob.bin").unwrap().as_text().is_none());
+        assert!(task.attachment("missing").is_none());
+    }
+
+    #[test]
+    fn test_task_user_content() {
+        assert_eq!(Task::new("Plain").user_content(), "Plain");
+
+        let task = Task::new("Check")
+            .with_payload(json!({"a": 1}))
+            .unwrap()
+            .with_attachment(Attachment::new("f.txt", "text/plain", vec![1, 2, 3]));
+        let content = task.user_content();
+        assert!(content.starts_with("Check"));
+        assert!(content.contains("\"a\": 1"));
+        assert!(content.contains("- f.txt (text/plain, 3 bytes)"));
+    }
+
+    #[test]
+    fn test_task_user_messages_text_only() {
+        let messages = Task::new("Hello").user_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, ChatRole::User);
+        assert_eq!(messages[0].message_type, MessageType::Text);
+        assert_eq!(messages[0].content, "Hello");
+    }
+
+    #[test]
+    fn test_task_user_messages_with_image_url() {
+        let task = Task::new_with_image_url("Describe", "https://example.com/cat.png");
+        let messages = task.user_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].message_type,
+            MessageType::ImageURL("https://example.com/cat.png".into())
+        );
+        assert_eq!(messages[0].content, "Describe");
+    }
+
+    #[test]
+    fn test_task_user_messages_with_multiple_images() {
+        let task = Task::new("Compare")
+            .with_image(ImageMime::PNG, vec![1])
+            .with_image_url("https://example.com/b.jpg")
+            .with_attachment(Attachment::new("c.webp", "image/webp", vec![2]))
+            .with_attachment(Attachment::new("notes.txt", "text/plain", vec![3]));
+        let messages = task.user_messages();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[0].message_type,
+            MessageType::Image((ImageMime::PNG, vec![1]))
+        );
+        assert!(messages[0].content.starts_with("Compare"));
+        assert_eq!(
+            messages[1].message_type,
+            MessageType::ImageURL("https://example.com/b.jpg".into())
+        );
+        assert_eq!(
+            messages[2].message_type,
+            MessageType::Image((ImageMime::WEBP, vec![2]))
+        );
+        assert!(messages[1].content.is_empty());
+    }
+
+    #[test]
+    fn test_task_tool_groups() {
+        let task = Task::new("Groups")
+            .enable_tool_group("fs")
+            .disable_tool_group("github");
+        assert_eq!(task.tool_groups.enabled, vec!["fs"]);
+        assert_eq!(task.tool_groups.disabled, vec!["github"]);
+        assert!(Task::new("plain").tool_groups.is_empty());
+    }
 }
@@ -0,0 +1,348 @@
+//! Export a run's messages for fine-tuning datasets and human review.
+//!
+//! A [`Transcript`] holds the messages of a run, typically recalled from the
+//! agent's memory, and renders them in one of the [`TranscriptFormat`]s:
+//!
+//! - OpenAI chat fine-tuning records, one JSON object per line,
+//! - ShareGPT conversations, as read by most open-source training tools,
+//! - a markdown transcript for people to read.
+//!
+//! ```rust,ignore
+//! let memory = handle.agent.memory().unwrap();
+//! let transcript = Transcript::from_memory(&**memory.lock().await)
+//!     .await?
+//!     .with_system_prompt("You are a helpful assistant.");
+//! std::fs::write("run.jsonl", transcript.export(TranscriptFormat::OpenAI))?;
+//! ```
+//!
+//! Image and PDF bytes are not embedded; they appear as placeholders naming
+//! their type. Images given by URL are kept.
+
+use crate::agent::memory::MemoryProvider;
+use autoagents_llm::chat::{ChatMessage, ChatRole, ContentPart, MessageType};
+use autoagents_llm::error::LLMError;
+use autoagents_llm::ToolCall;
+use serde_json::{json, Value};
+
+/// Format a [`Transcript`] is exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscriptFormat {
+    /// `{"messages": [...]}` records of the OpenAI chat fine-tuning format,
+    /// with tool calls and tool results in OpenAI's shape
+    OpenAI,
+    /// `{"conversations": [{"from": ..., "value": ...}]}` records, with tool
+    /// calls as `function_call` turns and their results as `observation`
+    ShareGPT,
+    /// A markdown document with a heading per message
+    Markdown,
+}
+
+/// The messages of a run, ready to export.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    messages: Vec<ChatMessage>,
+}
+
+impl Transcript {
+    pub fn new(messages: impl IntoIterator<Item = ChatMessage>) -> Self {
+        Self {
+            messages: messages.into_iter().collect(),
+        }
+    }
+
+    /// Everything `memory` holds, oldest first
+    pub async fn from_memory(memory: &dyn MemoryProvider) -> Result<Self, LLMError> {
+        Ok(Self::new(memory.recall("", None).await?))
+    }
+
+    /// Start the transcript with `prompt` as system message, replacing one
+    /// already there. Memory does not keep the system prompt of a run.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        let system = ChatMessage {
+            role: ChatRole::System,
+            message_type: MessageType::Text,
+            content: prompt.into(),
+            reasoning: Vec::new(),
+        };
+        match self.messages.first_mut() {
+            Some(first) if first.role == ChatRole::System => *first = system,
+            _ => self.messages.insert(0, system),
+        }
+        self
+    }
+
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// The transcript in `format`. JSON formats are a single line, so
+    /// transcripts of several runs joined by newlines form a JSONL file.
+    pub fn export(&self, format: TranscriptFormat) -> String {
+        match format {
+            TranscriptFormat::OpenAI => self.to_openai().to_string(),
+            TranscriptFormat::ShareGPT => self.to_sharegpt().to_string(),
+            TranscriptFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// A fine-tuning record in the OpenAI chat format
+    pub fn to_openai(&self) -> Value {
+        let mut messages = Vec::with_capacity(self.messages.len());
+        for message in &self.messages {
+            match &message.message_type {
+                MessageType::ToolResult(results) => {
+                    messages.extend(results.iter().map(|result| {
+                        json!({
+                            "role": "tool",
+                            "tool_call_id": result.id,
+                            "content": result.function.arguments,
+                        })
+                    }));
+                }
+                MessageType::ToolUse(calls) => {
+                    let content = (!message.content.is_empty()).then(|| message.content.clone());
+                    messages.push(json!({
+                        "role": "assistant",
+                        "content": content,
+                        "tool_calls": calls,
+                    }));
+                }
+                MessageType::ImageURL(url) => messages.push(json!({
+                    "role": role(&message.role),
+                    "content": [
+                        {"type": "text", "text": message.content},
+                        {"type": "image_url", "image_url": {"url": url}},
+                    ],
+                })),
+                MessageType::Parts(parts) => {
+                    let mut content = Vec::with_capacity(parts.len() + 1);
+                    if !message.content.is_empty() {
+                        content.push(json!({"type": "text", "text": message.content}));
+                    }
+                    content.extend(parts.iter().map(|part| match part {
+                        ContentPart::ImageUrl { url, .. } => {
+                            json!({"type": "image_url", "image_url": {"url": url}})
+                        }
+                        part => json!({"type": "text", "text": part_text(part)}),
+                    }));
+                    messages.push(json!({"role": role(&message.role), "content": content}));
+                }
+                _ => messages.push(json!({
+                    "role": role(&message.role),
+                    "content": text(message),
+                })),
+            }
+        }
+        json!({ "messages": messages })
+    }
+
+    /// A conversation in the ShareGPT format
+    pub fn to_sharegpt(&self) -> Value {
+        let mut turns = Vec::with_capacity(self.messages.len());
+        for message in &self.messages {
+            match &message.message_type {
+                MessageType::ToolUse(calls) => {
+                    if !message.content.is_empty() {
+                        turns.push(json!({"from": "gpt", "value": message.content}));
+                    }
+                    turns.extend(calls.iter().map(|call| {
+                        let value = json!({
+                            "name": call.function.name,
+                            "arguments": arguments(call),
+                        });
+                        json!({"from": "function_call", "value": value.to_string()})
+                    }));
+                }
+                MessageType::ToolResult(results) => {
+                    turns.extend(results.iter().map(
+                        |result| json!({"from": "observation", "value": result.function.arguments}),
+                    ));
+                }
+                _ => {
+                    let from = match message.role {
+                        ChatRole::System => "system",
+                        ChatRole::User => "human",
+                        ChatRole::Assistant => "gpt",
+                        ChatRole::Tool => "observation",
+                    };
+                    turns.push(json!({"from": from, "value": text(message)}));
+                }
+            }
+        }
+        json!({ "conversations": turns })
+    }
+
+    /// A markdown transcript, a `##` heading per message
+    pub fn to_markdown(&self) -> String {
+        let mut sections = Vec::with_capacity(self.messages.len());
+        for message in &self.messages {
+            let heading = match message.role {
+                ChatRole::System => "## System",
+                ChatRole::User => "## User",
+                ChatRole::Assistant => "## Assistant",
+                ChatRole::Tool => "## Tool",
+            };
+            let mut body = Vec::new();
+            match &message.message_type {
+                MessageType::ToolUse(calls) => {
+                    if !message.content.is_empty() {
+                        body.push(message.content.clone());
+                    }
+                    body.extend(calls.iter().map(|call| {
+                        format!(
+                            "Called `{}`:\n\n```json\n{}\n```",
+                            call.function.name, call.function.arguments
+                        )
+                    }));
+                }
+                MessageType::ToolResult(results) => {
+                    body.extend(results.iter().map(|result| {
+                        format!(
+                            "Result of `{}`:\n\n```\n{}\n```",
+                            result.function.name, result.function.arguments
+                        )
+                    }));
+                }
+                MessageType::ImageURL(url) => {
+                    body.push(message.content.clone());
+                    body.push(format!("![image]({url})"));
+                }
+                MessageType::Parts(parts) => {
+                    body.push(message.content.clone());
+                    body.extend(parts.iter().map(|part| match part {
+                        ContentPart::ImageUrl { url, .. } => format!("![image]({url})"),
+                        part => part_text(part),
+                    }));
+                }
+                _ => body.push(text(message)),
+            }
+            body.retain(|block| !block.is_empty());
+            sections.push(format!("{heading}\n\n{}", body.join("\n\n")));
+        }
+        format!("{}\n", sections.join("\n\n"))
+    }
+}
+
+fn role(role: &ChatRole) -> &'static str {
+    match role {
+        ChatRole::System => "system",
+        ChatRole::User => "user",
+        ChatRole::Assistant => "assistant",
+        ChatRole::Tool => "tool",
+    }
+}
+
+/// The content of a message, with a placeholder for an attachment that
+/// cannot be written as text
+fn text(message: &ChatMessage) -> String {
+    let placeholder = match &message.message_type {
+        MessageType::Image((mime, _)) => format!("[image: {}]", mime.mime_type()),
+        MessageType::Pdf(_) => "[pdf]".to_string(),
+        MessageType::ImageURL(url) => format!("[image: {url}]"),
+        MessageType::Parts(parts) => parts.iter().map(part_text).collect::<Vec<_>>().join("\n"),
+        _ => return message.content.clone(),
+    };
+    if message.content.is_empty() {
+        placeholder
+    } else {
+        format!("{}\n{placeholder}", message.content)
+    }
+}
+
+fn part_text(part: &ContentPart) -> String {
+    match part {
+        ContentPart::Text(text) => text.clone(),
+        ContentPart::Image { mime, .. } => format!("[image: {}]", mime.mime_type()),
+        ContentPart::ImageUrl { url, .. } => format!("[image: {url}]"),
+    }
+}
+
+/// Arguments of a tool call as JSON, or as the raw text when they don't parse
+fn arguments(call: &ToolCall) -> Value {
+    serde_json::from_str(&call.function.arguments)
+        .unwrap_or_else(|_| Value::String(call.function.arguments.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_llm::FunctionCall;
+
+    fn tool_call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    fn transcript() -> Transcript {
+        Transcript::new([
+            ChatMessage::user().content("What time is it?").build(),
+            ChatMessage {
+                role: ChatRole::Assistant,
+                message_type: MessageType::ToolUse(vec![tool_call("call_1", "clock", "{}")]),
+                content: String::new(),
+                reasoning: Vec::new(),
+            },
+            ChatMessage {
+                role: ChatRole::Tool,
+                message_type: MessageType::ToolResult(vec![tool_call(
+                    "call_1",
+                    "clock",
+                    "\"12:00\"",
+                )]),
+                content: String::new(),
+                reasoning: Vec::new(),
+            },
+            ChatMessage::assistant().content("It is noon.").build(),
+        ])
+        .with_system_prompt("Tell the time.")
+    }
+
+    #[test]
+    fn test_openai_format_keeps_tool_calls() {
+        let record = transcript().to_openai();
+        assert_eq!(
+            record,
+            json!({"messages": [
+                {"role": "system", "content": "Tell the time."},
+                {"role": "user", "content": "What time is it?"},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "clock", "arguments": "{}"}},
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "\"12:00\""},
+                {"role": "assistant", "content": "It is noon."},
+            ]})
+        );
+        assert!(!transcript().export(TranscriptFormat::OpenAI).contains('\n'));
+    }
+
+    #[test]
+    fn test_sharegpt_format() {
+        assert_eq!(
+            transcript().to_sharegpt(),
+            json!({"conversations": [
+                {"from": "system", "value": "Tell the time."},
+                {"from": "human", "value": "What time is it?"},
+                {"from": "function_call", "value": "{\"arguments\":{},\"name\":\"clock\"}"},
+                {"from": "observation", "value": "\"12:00\""},
+                {"from": "gpt", "value": "It is noon."},
+            ]})
+        );
+    }
+
+    #[test]
+    fn test_markdown_transcript() {
+        let markdown = transcript()
+            .with_system_prompt("Tell the time, briefly.")
+            .to_markdown();
+        assert_eq!(
+            markdown,
+            "## System\n\nTell the time, briefly.\n\n## User\n\nWhat time is it?\n\n## Assistant\n\nCalled `clock`:\n\n```json\n{}\n```\n\n## Tool\n\nResult of `clock`:\n\n```\n\"12:00\"\n```\n\n## Assistant\n\nIt is noon.\n"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! In-process publish/subscribe between agents.
+//!
+//! Unlike actor topics, the bus does not need a runtime: any code holding an
+//! [`EventBus`] can publish, and any number of subscribers receive every event
+//! published to a topic after they subscribed. Executors and hooks reach the
+//! agent's bus through [`Context::event_bus`](crate::agent::Context::event_bus).
+
+use crate::actor::{ActorMessage, Topic};
+use futures::Stream;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+const DEFAULT_BUS_CAPACITY: usize = 256;
+
+type ChannelKey = (String, TypeId);
+
+/// Shared, cloneable handle to a set of typed topics.
+///
+/// Topics are identified by name and event type, so two [`Topic`] values with
+/// the same name and type reach the same subscribers. Slow subscribers that
+/// fall more than the bus capacity behind skip the oldest events.
+#[derive(Clone)]
+pub struct EventBus {
+    channels: Arc<Mutex<HashMap<ChannelKey, Box<dyn Any + Send + Sync>>>>,
+    capacity: usize,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_BUS_CAPACITY)
+    }
+
+    /// Bus buffering up to `capacity` events per topic for lagging subscribers
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            channels: Arc::new(Mutex::new(HashMap::new())),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn sender<E: ActorMessage>(&self, topic: &Topic<E>) -> broadcast::Sender<Arc<E>> {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels
+            .entry((topic.name().to_string(), topic.type_id()))
+            .or_insert_with(|| Box::new(broadcast::channel::<Arc<E>>(self.capacity).0))
+            .downcast_ref::<broadcast::Sender<Arc<E>>>()
+            .expect("channel keyed by its event type")
+            .clone()
+    }
+
+    /// Publish `event` to every current subscriber of `topic`.
+    ///
+    /// Returns the number of subscribers that will receive it; publishing to
+    /// a topic nobody listens on is not an error.
+    pub fn publish<E: ActorMessage>(&self, topic: &Topic<E>, event: E) -> usize {
+        self.sender(topic).send(Arc::new(event)).unwrap_or(0)
+    }
+
+    pub fn subscribe<E: ActorMessage>(&self, topic: &Topic<E>) -> EventSubscription<E> {
+        EventSubscription {
+            topic: topic.name().to_string(),
+            rx: self.sender(topic).subscribe(),
+        }
+    }
+
+    pub fn subscriber_count<E: ActorMessage>(&self, topic: &Topic<E>) -> usize {
+        self.sender(topic).receiver_count()
+    }
+}
+
+impl Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let topics = self
+            .channels
+            .lock()
+            .map(|channels| channels.len())
+            .unwrap_or_default();
+        f.debug_struct("EventBus")
+            .field("topics", &topics)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// Receiving end of a topic subscription.
+pub struct EventSubscription<E> {
+    topic: String,
+    rx: broadcast::Receiver<Arc<E>>,
+}
+
+impl<E: ActorMessage> EventSubscription<E> {
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Wait for the next event; `None` once the bus has been dropped.
+    pub async fn recv(&mut self) -> Option<Arc<E>> {
+        loop {
+            match self.rx.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!(
+                        "Subscriber of topic `{}` lagged behind, skipped {skipped} events",
+                        self.topic
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Next event if one is already queued
+    pub fn try_recv(&mut self) -> Option<Arc<E>> {
+        loop {
+            match self.rx.try_recv() {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    pub fn into_stream(self) -> Pin<Box<dyn Stream<Item = Arc<E>> + Send>> {
+        Box::pin(futures::stream::unfold(self, |mut sub| async move {
+            sub.recv().await.map(|event| (event, sub))
+        }))
+    }
+}
+
+impl<E> Debug for EventSubscription<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventSubscription")
+            .field("topic", &self.topic)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::task::Task;
+    use crate::agent::{
+        AgentBuilder, AgentDeriveT, AgentExecutor, AgentHooks, Context, ContextError, DirectAgent,
+        ExecutorConfig,
+    };
+    use crate::tool::ToolT;
+    use async_trait::async_trait;
+    use autoagents_test_utils::llm::MockLLMProvider;
+
+    #[derive(Debug, PartialEq)]
+    struct ToolFailed {
+        tool: String,
+    }
+    impl ActorMessage for ToolFailed {}
+
+    #[derive(Debug, PartialEq)]
+    struct Heartbeat(u32);
+    impl ActorMessage for Heartbeat {}
+
+    #[tokio::test]
+    async fn test_every_subscriber_receives_events() {
+        let bus = EventBus::new();
+        let topic = Topic::<ToolFailed>::new("tool_failures");
+        let mut first = bus.subscribe(&topic);
+        let mut second = bus.subscribe(&Topic::<ToolFailed>::new("tool_failures"));
+
+        let delivered = bus.publish(
+            &topic,
+            ToolFailed {
+                tool: "search".into(),
+            },
+        );
+        assert_eq!(delivered, 2);
+        assert_eq!(first.recv().await.unwrap().tool, "search");
+        assert_eq!(second.recv().await.unwrap().tool, "search");
+        assert!(first.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_topics_are_isolated_by_name_and_type() {
+        let bus = EventBus::new();
+        let mut beats = bus.subscribe(&Topic::<Heartbeat>::new("a"));
+        let mut other_name = bus.subscribe(&Topic::<Heartbeat>::new("b"));
+        let mut other_type = bus.subscribe(&Topic::<ToolFailed>::new("a"));
+
+        bus.publish(&Topic::<Heartbeat>::new("a"), Heartbeat(1));
+        assert_eq!(*beats.recv().await.unwrap(), Heartbeat(1));
+        assert!(other_name.try_recv().is_none());
+        assert!(other_type.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let bus = EventBus::new();
+        let topic = Topic::<Heartbeat>::new("idle");
+        assert_eq!(bus.publish(&topic, Heartbeat(0)), 0);
+        assert_eq!(bus.subscriber_count(&topic), 0);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_skips_oldest() {
+        let bus = EventBus::with_capacity(2);
+        let topic = Topic::<Heartbeat>::new("beats");
+        let mut sub = bus.subscribe(&topic);
+        for i in 0..5 {
+            bus.publish(&topic, Heartbeat(i));
+        }
+        assert_eq!(*sub.recv().await.unwrap(), Heartbeat(3));
+        assert_eq!(*sub.recv().await.unwrap(), Heartbeat(4));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_as_stream() {
+        use futures::StreamExt as _;
+
+        let bus = EventBus::new();
+        let topic = Topic::<Heartbeat>::new("beats");
+        let stream = bus.subscribe(&topic).into_stream();
+        bus.publish(&topic, Heartbeat(7));
+        bus.publish(&topic, Heartbeat(8));
+        drop(bus);
+
+        let received: Vec<u32> = stream.map(|beat| beat.0).collect().await;
+        assert_eq!(received, vec![7, 8]);
+    }
+
+    /// Reports a failing tool on the bus instead of failing the run.
+    #[derive(Debug)]
+    struct Worker;
+
+    impl AgentDeriveT for Worker {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "works"
+        }
+
+        fn output_schema(&self) -> Option<serde_json::Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "worker"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    impl AgentHooks for Worker {}
+
+    #[async_trait]
+    impl AgentExecutor for Worker {
+        type Output = String;
+        type Error = ContextError;
+
+        fn config(&self) -> ExecutorConfig {
+            ExecutorConfig::default()
+        }
+
+        async fn execute(
+            &self,
+            _task: &Task,
+            context: Arc<Context>,
+        ) -> Result<String, Self::Error> {
+            if let Some(bus) = context.event_bus() {
+                bus.publish(
+                    &Topic::new("tool_failures"),
+                    ToolFailed {
+                        tool: "search".into(),
+                    },
+                );
+            }
+            Ok("partial answer".into())
+        }
+
+        async fn execute_stream(
+            &self,
+            _task: &Task,
+            _context: Arc<Context>,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<String, Self::Error>> + Send>>, Self::Error>
+        {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agents_publish_through_context() {
+        let bus = EventBus::new();
+        let mut monitor = bus.subscribe(&Topic::<ToolFailed>::new("tool_failures"));
+
+        let worker = AgentBuilder::<_, DirectAgent>::new(Worker)
+            .llm(Arc::new(MockLLMProvider))
+            .event_bus(bus.clone())
+            .build()
+            .await
+            .unwrap();
+        worker.agent.run(Task::new("do it")).await.unwrap();
+
+        assert_eq!(monitor.recv().await.unwrap().tool, "search");
+    }
+}
@@ -4,6 +4,8 @@ pub mod actor;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod environment;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod event_bus;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod runtime;
 
 // Agent module with conditional compilation
@@ -13,6 +15,7 @@ pub mod agent;
 mod channel;
 pub mod error;
 pub mod protocol;
+pub mod rag;
 pub mod tool;
 pub mod utils;
 
@@ -1,5 +1,8 @@
-use crate::agent::task::Task;
-use crate::tool::ToolCallResult;
+use crate::agent::guardrail::GuardrailViolation;
+use crate::agent::task::{RunMetadata, Task};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::tool::HumanQuestion;
+use crate::tool::{ToolCallResult, ToolProgress};
 use autoagents_llm::chat::StreamChoice;
 use serde::{Deserialize, Serialize};
 use std::any::{Any, TypeId};
@@ -19,26 +22,33 @@ pub type RuntimeID = Uuid;
 /// Event IDs are used to correlate events with their responses
 pub type EventId = Uuid;
 
+/// Run IDs identify a single execution of a task
+pub type RunId = Uuid;
+
 /// Protocol events represent the various events that can occur during actor execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Event {
     // /// A new task has been submitted to an agent
     NewTask {
         actor_id: ActorID,
-        task: Task,
+        task: Box<Task>,
     },
 
     /// A task has started execution
     TaskStarted {
         sub_id: SubmissionId,
+        run_id: RunId,
         actor_id: ActorID,
         actor_name: String,
         task_description: String,
+        #[serde(default)]
+        metadata: Box<RunMetadata>,
     },
 
     /// A task has been completed
     TaskComplete {
         sub_id: SubmissionId,
+        run_id: RunId,
         actor_id: ActorID,
         actor_name: String,
         result: String,
@@ -47,6 +57,7 @@ pub enum Event {
     /// A task encountered an error
     TaskError {
         sub_id: SubmissionId,
+        run_id: RunId,
         actor_id: ActorID,
         error: String,
     },
@@ -84,6 +95,29 @@ pub enum Event {
         error: String,
     },
 
+    /// A running tool call reported how far it has got
+    ToolCallProgress {
+        id: String,
+        tool_name: String,
+        progress: ToolProgress,
+    },
+
+    /// A tool call is waiting for a person to answer a question, see
+    /// [`crate::tool::HumanInput::answer`]
+    #[cfg(not(target_arch = "wasm32"))]
+    HumanInputRequested {
+        id: String,
+        tool_name: String,
+        question: HumanQuestion,
+    },
+
+    /// A chunk of output from a streaming tool call
+    ToolCallChunk {
+        id: String,
+        tool_name: String,
+        chunk: serde_json::Value,
+    },
+
     /// A turn has started
     TurnStarted {
         turn_number: usize,
@@ -96,7 +130,8 @@ pub enum Event {
         final_turn: bool,
     },
 
-    /// Streaming chunk from agent
+    /// Streaming chunk from agent. Thinking the model streams is carried in
+    /// the `thinking` part of its delta, apart from the content.
     StreamChunk {
         sub_id: SubmissionId,
         chunk: StreamChoice,
@@ -112,6 +147,21 @@ pub enum Event {
     StreamComplete {
         sub_id: SubmissionId,
     },
+
+    /// A guardrail blocked, rewrote or annotated a run
+    GuardrailTriggered {
+        sub_id: SubmissionId,
+        run_id: RunId,
+        violation: Box<GuardrailViolation>,
+    },
+
+    /// A supervised actor agent failed and was restarted
+    AgentRestarted {
+        actor_id: ActorID,
+        actor_name: String,
+        restarts: usize,
+        error: String,
+    },
 }
 
 /// Internal events that are processed within the runtime
@@ -139,7 +189,7 @@ mod tests {
         let _ = Uuid::new_v4();
         let event = Event::NewTask {
             actor_id: Default::default(),
-            task: Task::new(String::from("test")),
+            task: Box::new(Task::new(String::from("test"))),
         };
 
         //Check if serialization and deserilization works properly
@@ -158,9 +208,11 @@ mod tests {
     fn test_event_serialization_task_started() {
         let event = Event::TaskStarted {
             sub_id: Uuid::new_v4(),
+            run_id: Uuid::new_v4(),
             actor_id: Default::default(),
             actor_name: String::from("test"),
             task_description: "Started task".to_string(),
+            metadata: Box::default(),
         };
 
         let serialized = serde_json::to_string(&event).unwrap();
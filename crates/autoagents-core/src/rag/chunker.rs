@@ -0,0 +1,174 @@
+use super::{Chunker, Document};
+use serde_json::Value;
+
+/// Separators text is split at, the ones keeping more context together first
+const SEPARATORS: [&str; 4] = ["\n\n", "\n", ". ", " "];
+
+/// Splits text into chunks of at most `chunk_size` characters, at paragraph
+/// breaks where possible, then at line breaks, sentences and words.
+///
+/// With an overlap, each chunk starts with up to `overlap` characters from
+/// the end of the previous one, so text cut at a chunk boundary keeps some
+/// context; the overlap counts towards `chunk_size`. Chunks get the id of
+/// their document followed by `#` and their position, and a `document_id`
+/// metadata entry.
+#[derive(Debug, Clone)]
+pub struct TextChunker {
+    chunk_size: usize,
+    overlap: usize,
+}
+
+impl TextChunker {
+    pub fn new(chunk_size: usize, overlap: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            overlap: overlap.min(chunk_size / 2),
+        }
+    }
+
+    /// Pieces of `text` of at most `size` characters, adjacent pieces merged
+    /// as long as they fit
+    fn split(&self, text: &str, separators: &[&str], size: usize) -> Vec<String> {
+        if len(text) <= size {
+            return vec![text.to_string()];
+        }
+        let Some((position, separator)) = separators
+            .iter()
+            .enumerate()
+            .find(|(_, separator)| text.contains(**separator))
+        else {
+            let chars: Vec<char> = text.chars().collect();
+            return chars
+                .chunks(size)
+                .map(|chunk| chunk.iter().collect())
+                .collect();
+        };
+
+        let mut pieces = Vec::new();
+        let mut current = String::new();
+        for part in text
+            .split_inclusive(separator)
+            .filter(|part| !part.trim().is_empty())
+        {
+            if len(part) > size {
+                pieces.extend((!current.is_empty()).then(|| std::mem::take(&mut current)));
+                pieces.extend(self.split(part, &separators[position + 1..], size));
+            } else if len(&current) + len(part) <= size {
+                current.push_str(part);
+            } else {
+                pieces.push(std::mem::replace(&mut current, part.to_string()));
+            }
+        }
+        pieces.extend((!current.is_empty()).then_some(current));
+        pieces
+    }
+}
+
+impl Default for TextChunker {
+    fn default() -> Self {
+        Self::new(1000, 100)
+    }
+}
+
+impl Chunker for TextChunker {
+    fn chunk(&self, document: &Document) -> Vec<Document> {
+        let pieces = self.split(
+            &document.content,
+            &SEPARATORS,
+            self.chunk_size - self.overlap,
+        );
+        let mut chunks = Vec::with_capacity(pieces.len());
+        let mut previous: Option<&str> = None;
+        for piece in pieces.iter().map(|piece| piece.trim()) {
+            if piece.is_empty() {
+                continue;
+            }
+            // One character of the overlap goes to the space joining the tail
+            let overlap = self.overlap.saturating_sub(1);
+            let content = match previous.map(|previous| tail(previous, overlap)) {
+                Some(tail) if !tail.is_empty() => format!("{tail} {piece}"),
+                _ => piece.to_string(),
+            };
+            previous = Some(piece);
+
+            let mut chunk = Document::new(format!("{}#{}", document.id, chunks.len()), content);
+            chunk.metadata = document.metadata.clone();
+            chunk
+                .metadata
+                .insert("document_id".into(), Value::String(document.id.clone()));
+            chunks.push(chunk);
+        }
+        chunks
+    }
+}
+
+fn len(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// The last `overlap` characters of `text`, starting at a word
+fn tail(text: &str, overlap: usize) -> &str {
+    if overlap == 0 {
+        return "";
+    }
+    let start = text
+        .char_indices()
+        .rev()
+        .nth(overlap - 1)
+        .map_or(0, |(i, _)| i);
+    if start == 0 {
+        return text;
+    }
+    let tail = &text[start..];
+    if text[..start].ends_with(char::is_whitespace) {
+        return tail.trim_start();
+    }
+    // Skip the word cut in half
+    match tail.find(char::is_whitespace) {
+        Some(space) => tail[space..].trim_start(),
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_split_at_natural_boundaries() {
+        let document = Document::new(
+            "doc",
+            "First paragraph here.\n\nSecond one is a little longer than that. It has two sentences.",
+        );
+        let chunks = TextChunker::new(45, 0).chunk(&document);
+        let contents: Vec<&str> = chunks.iter().map(|chunk| chunk.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "First paragraph here.",
+                "Second one is a little longer than that.",
+                "It has two sentences."
+            ]
+        );
+        assert_eq!(chunks[2].id, "doc#2");
+        assert_eq!(chunks[2].metadata["document_id"], "doc");
+        assert!(chunks.iter().all(|chunk| len(&chunk.content) <= 45));
+    }
+
+    #[test]
+    fn test_chunks_overlap() {
+        let document = Document::new("doc", "one two three four five six seven eight nine ten");
+        let chunks = TextChunker::new(24, 10).chunk(&document);
+        let contents: Vec<&str> = chunks.iter().map(|chunk| chunk.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "one two three",
+                "two three four five six",
+                "five six seven eight",
+                "eight nine ten"
+            ]
+        );
+        assert!(chunks.iter().all(|chunk| len(&chunk.content) <= 24));
+    }
+}
@@ -0,0 +1,383 @@
+//! [LanceDB](https://lancedb.com) as an embedded vector store.
+//!
+//! LanceDB keeps its tables in a local directory and runs in-process, so
+//! desktop and local agents get persistent semantic search without a
+//! server. [`LanceDocumentStore`] keeps RAG chunks in a table and
+//! [`LanceVectorIndex`](crate::agent::memory::LanceVectorIndex) backs a
+//! [`VectorMemory`](crate::agent::memory::VectorMemory). Tables are created
+//! on open when missing.
+//!
+//! ```rust,ignore
+//! let store = LanceDocumentStore::open("data/lancedb", "handbook", 768, embedder)
+//!     .await?
+//!     .with_filter("id LIKE 'handbook#%'");
+//! let store = Arc::new(store);
+//! let rag = RagPipeline::new(Arc::new(TextChunker::default()), store.clone(), store);
+//! ```
+
+use super::{Document, Indexer, Retriever, ScoredDocument};
+use arrow_array::cast::AsArray;
+use arrow_array::types::Float32Type;
+use arrow_array::{
+    ArrayRef, FixedSizeListArray, RecordBatch, RecordBatchIterator, RecordBatchReader, StringArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use async_trait::async_trait;
+use autoagents_llm::embedding::{EmbeddingOptions, EmbeddingProvider};
+use autoagents_llm::error::LLMError;
+use futures::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::{Connection, DistanceType, Table};
+use serde_json::{Map, Value};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+pub(crate) fn lance_error(error: lancedb::Error) -> LLMError {
+    LLMError::ProviderError(format!("LanceDB error: {error}"))
+}
+
+fn arrow_error(error: ArrowError) -> LLMError {
+    LLMError::ProviderError(format!("Arrow error: {error}"))
+}
+
+/// The `vector` column of a table holding embeddings of `dimensions`
+pub(crate) fn vector_field(dimensions: usize) -> Field {
+    Field::new(
+        "vector",
+        DataType::FixedSizeList(
+            Arc::new(Field::new("item", DataType::Float32, true)),
+            dimensions as i32,
+        ),
+        false,
+    )
+}
+
+pub(crate) fn vector_array(vectors: &[Vec<f32>], dimensions: usize) -> FixedSizeListArray {
+    FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+        vectors
+            .iter()
+            .map(|vector| Some(vector.iter().copied().map(Some))),
+        dimensions as i32,
+    )
+}
+
+/// `batch` as a reader, the form LanceDB takes new rows in
+pub(crate) fn batch_reader(batch: RecordBatch) -> Box<dyn RecordBatchReader + Send> {
+    let schema = batch.schema();
+    Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema))
+}
+
+/// Open table `name`, creating it with `schema` when missing
+pub(crate) async fn open_or_create_table(
+    db: &Connection,
+    name: &str,
+    schema: SchemaRef,
+) -> Result<Table, LLMError> {
+    let names = db.table_names().execute().await.map_err(lance_error)?;
+    if names.iter().any(|table| table == name) {
+        db.open_table(name).execute().await
+    } else {
+        db.create_empty_table(name, schema).execute().await
+    }
+    .map_err(lance_error)
+}
+
+/// Vector search results are scored `1 - distance`, the cosine similarity
+pub(crate) fn distances(batch: &RecordBatch) -> Result<Vec<f32>, LLMError> {
+    let distances = batch
+        .column_by_name("_distance")
+        .and_then(|column| column.as_primitive_opt::<Float32Type>())
+        .ok_or_else(|| missing_column("_distance"))?;
+    Ok(distances
+        .values()
+        .iter()
+        .map(|distance| 1.0 - distance)
+        .collect())
+}
+
+pub(crate) fn missing_column(name: &str) -> LLMError {
+    LLMError::ProviderError(format!("LanceDB result has no {name} column"))
+}
+
+/// SQL string literal of `text`
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn document_schema(dimensions: usize) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("content", DataType::Utf8, false),
+        Field::new("metadata", DataType::Utf8, false),
+        vector_field(dimensions),
+    ]))
+}
+
+fn document_batch(
+    documents: Vec<Document>,
+    embeddings: &[Vec<f32>],
+    dimensions: usize,
+) -> Result<RecordBatch, LLMError> {
+    let mut ids = Vec::with_capacity(documents.len());
+    let mut contents = Vec::with_capacity(documents.len());
+    let mut metadata = Vec::with_capacity(documents.len());
+    for document in documents {
+        ids.push(document.id);
+        contents.push(document.content);
+        metadata.push(Value::Object(document.metadata).to_string());
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(ids)),
+        Arc::new(StringArray::from(contents)),
+        Arc::new(StringArray::from(metadata)),
+        Arc::new(vector_array(embeddings, dimensions)),
+    ];
+    RecordBatch::try_new(document_schema(dimensions), columns).map_err(arrow_error)
+}
+
+fn batch_documents(batch: &RecordBatch) -> Result<Vec<ScoredDocument>, LLMError> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .and_then(|column| column.as_string_opt::<i32>())
+            .ok_or_else(|| missing_column(name))
+    };
+    let (ids, contents, metadata) = (column("id")?, column("content")?, column("metadata")?);
+    distances(batch)?
+        .into_iter()
+        .enumerate()
+        .map(|(row, score)| {
+            Ok(ScoredDocument {
+                document: Document {
+                    id: ids.value(row).to_string(),
+                    content: contents.value(row).to_string(),
+                    metadata: serde_json::from_str::<Map<String, Value>>(metadata.value(row))?,
+                },
+                score,
+            })
+        })
+        .collect()
+}
+
+/// RAG chunks stored in a LanceDB table.
+///
+/// The table has `id`, `content`, `metadata` (JSON text) and `vector`
+/// columns; filters are SQL predicates over them.
+#[derive(Clone)]
+pub struct LanceDocumentStore {
+    table: Table,
+    dimensions: usize,
+    embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    options: EmbeddingOptions,
+    filter: Option<String>,
+}
+
+impl LanceDocumentStore {
+    /// Open table `table` of the database in the directory `path`, storing
+    /// embeddings of `dimensions`.
+    pub async fn open(
+        path: &str,
+        table: &str,
+        dimensions: usize,
+        embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    ) -> Result<Self, LLMError> {
+        let db = lancedb::connect(path)
+            .execute()
+            .await
+            .map_err(lance_error)?;
+        Self::from_connection(&db, table, dimensions, embedder).await
+    }
+
+    /// Open table `table` of an existing connection.
+    pub async fn from_connection(
+        db: &Connection,
+        table: &str,
+        dimensions: usize,
+        embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    ) -> Result<Self, LLMError> {
+        let table = open_or_create_table(db, table, document_schema(dimensions)).await?;
+        Ok(Self {
+            table,
+            dimensions,
+            embedder,
+            options: EmbeddingOptions::new(),
+            filter: None,
+        })
+    }
+
+    /// How documents are batched when embedded
+    pub fn embedding_options(mut self, options: EmbeddingOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Only retrieve, and clear, documents matching the SQL predicate
+    /// `filter`, e.g. `id LIKE 'handbook#%'`
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// The underlying table
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    /// The `top_k` documents best matching `query` among those matching the
+    /// SQL predicate `filter`, in place of the store's filter.
+    pub async fn retrieve_filtered(
+        &self,
+        query: &str,
+        top_k: usize,
+        filter: Option<&str>,
+    ) -> Result<Vec<ScoredDocument>, LLMError> {
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query_embedding = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("empty query embedding".into()))?;
+
+        let mut search = self
+            .table
+            .query()
+            .nearest_to(query_embedding.as_slice())
+            .map_err(lance_error)?
+            .distance_type(DistanceType::Cosine)
+            .limit(top_k);
+        if let Some(filter) = filter {
+            search = search.only_if(filter);
+        }
+        let batches: Vec<RecordBatch> = search
+            .execute()
+            .await
+            .map_err(lance_error)?
+            .try_collect()
+            .await
+            .map_err(lance_error)?;
+
+        let mut documents = Vec::new();
+        for batch in &batches {
+            documents.extend(batch_documents(batch)?);
+        }
+        Ok(documents)
+    }
+}
+
+impl Debug for LanceDocumentStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LanceDocumentStore")
+            .field("table", &self.table.name())
+            .field("dimensions", &self.dimensions)
+            .field("filter", &self.filter)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Indexer for LanceDocumentStore {
+    async fn index(&self, documents: Vec<Document>) -> Result<(), LLMError> {
+        let contents = documents
+            .iter()
+            .map(|document| document.content.clone())
+            .collect::<Vec<_>>();
+        let embeddings = self.embedder.embed_with(contents, &self.options).await?;
+        if embeddings.len() != documents.len() {
+            return Err(LLMError::ProviderError(format!(
+                "expected {} embeddings, got {}",
+                documents.len(),
+                embeddings.len()
+            )));
+        }
+        if let Some(embedding) = embeddings.iter().find(|e| e.len() != self.dimensions) {
+            return Err(LLMError::InvalidRequest(format!(
+                "expected embeddings of {} dimensions, got {}",
+                self.dimensions,
+                embedding.len()
+            )));
+        }
+
+        let batch = document_batch(documents, &embeddings, self.dimensions)?;
+        let mut merge = self.table.merge_insert(&["id"]);
+        merge
+            .when_matched_update_all(None)
+            .when_not_matched_insert_all();
+        merge
+            .execute(batch_reader(batch))
+            .await
+            .map_err(lance_error)?;
+        Ok(())
+    }
+
+    async fn remove(&self, ids: &[String]) -> Result<(), LLMError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<String> = ids.iter().map(|id| quote(id)).collect();
+        self.table
+            .delete(&format!("id IN ({})", ids.join(", ")))
+            .await
+            .map_err(lance_error)?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), LLMError> {
+        self.table
+            .delete(self.filter.as_deref().unwrap_or("true"))
+            .await
+            .map_err(lance_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Retriever for LanceDocumentStore {
+    async fn retrieve(&self, query: &str, top_k: usize) -> Result<Vec<ScoredDocument>, LLMError> {
+        self.retrieve_filtered(query, top_k, self.filter.as_deref())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Float32Array;
+
+    #[test]
+    fn test_ids_are_quoted() {
+        assert_eq!(quote("notes#0"), "'notes#0'");
+        assert_eq!(quote("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn test_documents_round_trip_through_batches() {
+        let documents = vec![
+            Document::new("a#0", "first").with_metadata("source", "a.md"),
+            Document::new("b#0", "second"),
+        ];
+        let batch =
+            document_batch(documents.clone(), &[vec![1.0, 0.0], vec![0.0, 1.0]], 2).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        // Search results carry the distance to the query
+        let mut columns = batch.columns().to_vec();
+        columns.push(Arc::new(Float32Array::from(vec![0.25, 1.0])));
+        let mut fields: Vec<Field> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.as_ref().clone())
+            .collect();
+        fields.push(Field::new("_distance", DataType::Float32, true));
+        let results = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+
+        let scored = batch_documents(&results).unwrap();
+        assert_eq!(scored[0].document, documents[0]);
+        assert_eq!(scored[0].score, 0.75);
+        assert_eq!(scored[1].document, documents[1]);
+    }
+}
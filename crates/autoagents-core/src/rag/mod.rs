@@ -0,0 +1,263 @@
+//! Retrieval-augmented generation: split documents into chunks, index them
+//! and retrieve the chunks relevant to a query.
+//!
+//! The steps are separate traits so each can be swapped: a [`Chunker`]
+//! splits [`Document`]s, an [`Indexer`] stores the chunks and a
+//! [`Retriever`] finds the ones matching a query. [`RagPipeline`] ties them
+//! together, and [`InMemoryDocumentStore`] is an [`Indexer`] and
+//! [`Retriever`] backed by an embedding model, fine for a few thousand
+//! chunks. Agents search the pipeline through a [`RetrievalTool`], or get
+//! the context for a prompt with [`RagPipeline::context`]:
+//!
+//! ```rust,ignore
+//! let store = Arc::new(InMemoryDocumentStore::new(embedder));
+//! let rag = RagPipeline::new(Arc::new(TextChunker::new(1000, 100)), store.clone(), store);
+//! rag.ingest(vec![Document::new("handbook", handbook)]).await?;
+//! let rag = Arc::new(rag);
+//!
+//! // In the agent's `AgentDeriveT` implementation
+//! fn tools(&self) -> Vec<Box<dyn ToolT>> {
+//!     vec![Box::new(RetrievalTool::new(self.rag.clone()))]
+//! }
+//! ```
+
+use async_trait::async_trait;
+use autoagents_llm::error::LLMError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+mod chunker;
+#[cfg(all(feature = "lancedb", not(target_arch = "wasm32")))]
+mod lance;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+mod pgvector;
+#[cfg(all(feature = "qdrant", not(target_arch = "wasm32")))]
+mod qdrant;
+mod store;
+mod tool;
+
+pub use chunker::TextChunker;
+#[cfg(all(feature = "lancedb", not(target_arch = "wasm32")))]
+pub use lance::LanceDocumentStore;
+#[cfg(all(feature = "lancedb", not(target_arch = "wasm32")))]
+pub(crate) use lance::{
+    batch_reader, distances, lance_error, missing_column, open_or_create_table, vector_array,
+    vector_field,
+};
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+pub use pgvector::PgVectorStore;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+pub(crate) use pgvector::{pg_error, validate_table, vector_literal};
+#[cfg(all(feature = "qdrant", not(target_arch = "wasm32")))]
+pub use qdrant::{
+    PointId, QdrantClient, QdrantDocumentStore, QdrantFilter, QdrantPoint, QdrantScoredPoint,
+};
+pub use store::InMemoryDocumentStore;
+pub use tool::RetrievalTool;
+
+const DEFAULT_TOP_K: usize = 4;
+
+/// A piece of text to retrieve from, with metadata such as its source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub metadata: Map<String, Value>,
+}
+
+impl Document {
+    pub fn new(id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            content: content.into(),
+            metadata: Map::new(),
+        }
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// A retrieved document and how well it matches the query, higher is better.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredDocument {
+    pub document: Document,
+    pub score: f32,
+}
+
+/// Splits documents into chunks small enough to embed and to fit a prompt.
+pub trait Chunker: Send + Sync + Debug {
+    /// The chunks of `document`, in order. Chunks keep the metadata of the
+    /// document they come from.
+    fn chunk(&self, document: &Document) -> Vec<Document>;
+}
+
+/// Stores chunks so a [`Retriever`] can find them.
+#[async_trait]
+pub trait Indexer: Send + Sync {
+    /// Add `documents`, replacing stored documents with the same id.
+    async fn index(&self, documents: Vec<Document>) -> Result<(), LLMError>;
+
+    /// Remove the documents with the given ids.
+    async fn remove(&self, ids: &[String]) -> Result<(), LLMError>;
+
+    async fn clear(&self) -> Result<(), LLMError>;
+}
+
+/// Finds the documents relevant to a query.
+#[async_trait]
+pub trait Retriever: Send + Sync {
+    /// The `top_k` documents best matching `query`, best first.
+    async fn retrieve(&self, query: &str, top_k: usize) -> Result<Vec<ScoredDocument>, LLMError>;
+}
+
+/// Chunks, indexes and retrieves documents.
+///
+/// The indexer and retriever are usually the same store, given twice.
+#[derive(Clone)]
+pub struct RagPipeline {
+    chunker: Arc<dyn Chunker>,
+    indexer: Arc<dyn Indexer>,
+    retriever: Arc<dyn Retriever>,
+    top_k: usize,
+    min_score: Option<f32>,
+}
+
+impl RagPipeline {
+    pub fn new(
+        chunker: Arc<dyn Chunker>,
+        indexer: Arc<dyn Indexer>,
+        retriever: Arc<dyn Retriever>,
+    ) -> Self {
+        Self {
+            chunker,
+            indexer,
+            retriever,
+            top_k: DEFAULT_TOP_K,
+            min_score: None,
+        }
+    }
+
+    /// Number of chunks retrieved per query, 4 by default
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Drop retrieved chunks scoring below `min_score`
+    pub fn min_score(mut self, min_score: f32) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    /// Chunk and index `documents`, returning the number of chunks indexed.
+    pub async fn ingest(&self, documents: Vec<Document>) -> Result<usize, LLMError> {
+        let chunks: Vec<Document> = documents
+            .iter()
+            .flat_map(|document| self.chunker.chunk(document))
+            .collect();
+        let count = chunks.len();
+        if count > 0 {
+            self.indexer.index(chunks).await?;
+        }
+        Ok(count)
+    }
+
+    /// The chunks relevant to `query`, best first.
+    pub async fn retrieve(&self, query: &str) -> Result<Vec<ScoredDocument>, LLMError> {
+        let mut documents = self.retriever.retrieve(query, self.top_k).await?;
+        if let Some(min_score) = self.min_score {
+            documents.retain(|document| document.score >= min_score);
+        }
+        Ok(documents)
+    }
+
+    /// The chunks relevant to `query`, rendered to add to a prompt. Empty
+    /// when nothing relevant was found.
+    pub async fn context(&self, query: &str) -> Result<String, LLMError> {
+        Ok(render_documents(&self.retrieve(query).await?))
+    }
+}
+
+impl Debug for RagPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RagPipeline")
+            .field("chunker", &self.chunker)
+            .field("top_k", &self.top_k)
+            .field("min_score", &self.min_score)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Retrieved documents as numbered sources
+pub(crate) fn render_documents(documents: &[ScoredDocument]) -> String {
+    documents
+        .iter()
+        .enumerate()
+        .map(|(i, scored)| {
+            format!(
+                "[{}] {}\n{}",
+                i + 1,
+                scored.document.id,
+                scored.document.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_llm::embedding::EmbeddingProvider;
+
+    /// Embeds text as counts of a few topic words.
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    ["rust", "python", "cooking"]
+                        .iter()
+                        .map(|topic| text.matches(topic).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_retrieves_relevant_chunks() {
+        let store = Arc::new(InMemoryDocumentStore::new(Arc::new(TopicEmbedder)));
+        let rag = RagPipeline::new(Arc::new(TextChunker::new(30, 0)), store.clone(), store)
+            .top_k(1)
+            .min_score(0.5);
+        let indexed = rag
+            .ingest(vec![Document::new(
+                "notes",
+                "rust has a borrow checker.\n\npython has a GIL.\n\ncooking takes time.",
+            )
+            .with_metadata("source", "notes.md")])
+            .await
+            .unwrap();
+        assert_eq!(indexed, 3);
+
+        let retrieved = rag.retrieve("tell me about python").await.unwrap();
+        assert_eq!(retrieved.len(), 1);
+        assert_eq!(retrieved[0].document.id, "notes#1");
+        assert_eq!(retrieved[0].document.metadata["source"], "notes.md");
+        assert_eq!(
+            rag.context("python").await.unwrap(),
+            "[1] notes#1\npython has a GIL."
+        );
+        assert!(rag.retrieve("gardening").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,498 @@
+//! [Qdrant](https://qdrant.tech) as a vector store, over its REST API.
+//!
+//! [`QdrantClient`] talks to one collection. [`QdrantDocumentStore`] keeps
+//! RAG chunks in it, with their content and metadata as payload so searches
+//! can be filtered on them, and
+//! [`QdrantVectorIndex`](crate::agent::memory::QdrantVectorIndex) backs a
+//! [`VectorMemory`](crate::agent::memory::VectorMemory).
+//!
+//! ```rust,ignore
+//! let client = QdrantClient::new("http://localhost:6333", "handbook").with_api_key(key);
+//! let store = Arc::new(
+//!     QdrantDocumentStore::new(client, embedder)
+//!         .with_filter(QdrantFilter::new().must_match("metadata.lang", "en")),
+//! );
+//! let rag = RagPipeline::new(Arc::new(TextChunker::default()), store.clone(), store);
+//! ```
+
+use super::{Document, Indexer, Retriever, ScoredDocument};
+use async_trait::async_trait;
+use autoagents_llm::embedding::{EmbeddingOptions, EmbeddingProvider};
+use autoagents_llm::error::LLMError;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Id of a Qdrant point, an unsigned integer or a UUID.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PointId {
+    Num(u64),
+    Uuid(String),
+}
+
+impl From<u64> for PointId {
+    fn from(id: u64) -> Self {
+        PointId::Num(id)
+    }
+}
+
+impl From<Uuid> for PointId {
+    fn from(id: Uuid) -> Self {
+        PointId::Uuid(id.to_string())
+    }
+}
+
+/// A vector and its payload, as upserted into a collection.
+#[derive(Debug, Clone, Serialize)]
+pub struct QdrantPoint {
+    pub id: PointId,
+    pub vector: Vec<f32>,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub payload: Map<String, Value>,
+}
+
+/// A point found by a search.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QdrantScoredPoint {
+    pub id: PointId,
+    pub score: f32,
+    #[serde(default)]
+    pub payload: Option<Map<String, Value>>,
+}
+
+/// Conditions on point payloads that searches and deletes are limited to.
+///
+/// Keys are payload paths such as `metadata.source`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QdrantFilter {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    must: Vec<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    must_not: Vec<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    should: Vec<Value>,
+}
+
+impl QdrantFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only points whose payload `key` equals `value`
+    pub fn must_match(self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.must(match_condition(key.into(), value.into()))
+    }
+
+    /// Only points whose payload `key` does not equal `value`
+    pub fn must_not_match(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.must_not
+            .push(match_condition(key.into(), value.into()));
+        self
+    }
+
+    /// Points matching any of the `should` conditions
+    pub fn should_match(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.should.push(match_condition(key.into(), value.into()));
+        self
+    }
+
+    /// Add a condition written in Qdrant's filter syntax, such as a range
+    pub fn must(mut self, condition: Value) -> Self {
+        self.must.push(condition);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.must.is_empty() && self.must_not.is_empty() && self.should.is_empty()
+    }
+}
+
+fn match_condition(key: String, value: Value) -> Value {
+    json!({ "key": key, "match": { "value": value } })
+}
+
+/// A collection on a Qdrant server.
+#[derive(Clone)]
+pub struct QdrantClient {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    collection: String,
+}
+
+impl QdrantClient {
+    /// The collection `collection` on the server at `url`, e.g.
+    /// `http://localhost:6333`.
+    pub fn new(url: impl Into<String>, collection: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            collection: collection.into(),
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    /// Create the collection for cosine search over vectors of `dimensions`
+    /// unless it exists.
+    pub async fn ensure_collection(&self, dimensions: usize) -> Result<(), LLMError> {
+        if self
+            .send(Method::GET, "", None)
+            .await?
+            .status()
+            .is_success()
+        {
+            return Ok(());
+        }
+        let body = json!({ "vectors": { "size": dimensions, "distance": "Cosine" } });
+        self.request(Method::PUT, "", Some(body)).await?;
+        Ok(())
+    }
+
+    /// Insert `points`, replacing points with the same id.
+    pub async fn upsert(&self, points: Vec<QdrantPoint>) -> Result<(), LLMError> {
+        if points.is_empty() {
+            return Ok(());
+        }
+        let body = json!({ "points": points });
+        self.request(Method::PUT, "/points?wait=true", Some(body))
+            .await?;
+        Ok(())
+    }
+
+    /// The `limit` points nearest to `vector` among those matching
+    /// `filter`, nearest first, with their payload.
+    pub async fn search(
+        &self,
+        vector: &[f32],
+        limit: usize,
+        filter: Option<&QdrantFilter>,
+    ) -> Result<Vec<QdrantScoredPoint>, LLMError> {
+        let mut body = json!({ "vector": vector, "limit": limit, "with_payload": true });
+        if let Some(filter) = filter.filter(|filter| !filter.is_empty()) {
+            body["filter"] = serde_json::to_value(filter)?;
+        }
+        let result = self
+            .request(Method::POST, "/points/search", Some(body))
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Delete the points with the given ids.
+    pub async fn delete(&self, ids: Vec<PointId>) -> Result<(), LLMError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let body = json!({ "points": ids });
+        self.request(Method::POST, "/points/delete?wait=true", Some(body))
+            .await?;
+        Ok(())
+    }
+
+    /// Delete the points matching `filter`, all of them for an empty filter.
+    pub async fn delete_matching(&self, filter: &QdrantFilter) -> Result<(), LLMError> {
+        let body = json!({ "filter": filter });
+        self.request(Method::POST, "/points/delete?wait=true", Some(body))
+            .await?;
+        Ok(())
+    }
+
+    /// Number of points in the collection
+    pub async fn count(&self) -> Result<usize, LLMError> {
+        let result = self
+            .request(
+                Method::POST,
+                "/points/count",
+                Some(json!({ "exact": true })),
+            )
+            .await?;
+        Ok(result["count"].as_u64().unwrap_or_default() as usize)
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<reqwest::Response, LLMError> {
+        let url = format!("{}/collections/{}{path}", self.url, self.collection);
+        let mut request = self.http.request(method, url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("api-key", api_key);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        Ok(request.send().await?)
+    }
+
+    /// The `result` of a request, or the error Qdrant answered with
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, LLMError> {
+        let response = self.send(method, path, body).await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(qdrant_error(status, text));
+        }
+        let mut response: Value = serde_json::from_str(&text)?;
+        Ok(response["result"].take())
+    }
+}
+
+impl Debug for QdrantClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QdrantClient")
+            .field("url", &self.url)
+            .field("collection", &self.collection)
+            .finish_non_exhaustive()
+    }
+}
+
+fn qdrant_error(status: StatusCode, body: String) -> LLMError {
+    LLMError::HttpStatus {
+        status: status.as_u16(),
+        message: format!("Qdrant: {body}"),
+    }
+}
+
+/// Id of the point a document is stored as, derived from the document id so
+/// indexing a document again replaces it
+fn document_point_id(id: &str) -> PointId {
+    let hash = Sha256::digest(id.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    uuid::Builder::from_random_bytes(bytes).into_uuid().into()
+}
+
+fn document_payload(document: Document) -> Map<String, Value> {
+    let mut payload = Map::new();
+    payload.insert("document_id".into(), Value::String(document.id));
+    payload.insert("content".into(), Value::String(document.content));
+    payload.insert("metadata".into(), Value::Object(document.metadata));
+    payload
+}
+
+fn payload_document(mut payload: Map<String, Value>) -> Document {
+    let mut text = |key: &str| match payload.remove(key) {
+        Some(Value::String(text)) => text,
+        _ => String::new(),
+    };
+    let id = text("document_id");
+    let content = text("content");
+    let metadata = match payload.remove("metadata") {
+        Some(Value::Object(metadata)) => metadata,
+        _ => Map::new(),
+    };
+    Document {
+        id,
+        content,
+        metadata,
+    }
+}
+
+/// RAG chunks stored in a Qdrant collection.
+///
+/// The collection is created on first use with the size of the first
+/// embedding. Each chunk is a point whose payload holds `document_id`,
+/// `content` and `metadata`, so searches can be filtered on metadata with
+/// keys like `metadata.source`.
+pub struct QdrantDocumentStore {
+    client: QdrantClient,
+    embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    options: EmbeddingOptions,
+    filter: Option<QdrantFilter>,
+    collection_ready: AtomicBool,
+}
+
+impl QdrantDocumentStore {
+    pub fn new(client: QdrantClient, embedder: Arc<dyn EmbeddingProvider + Send + Sync>) -> Self {
+        Self {
+            client,
+            embedder,
+            options: EmbeddingOptions::new(),
+            filter: None,
+            collection_ready: AtomicBool::new(false),
+        }
+    }
+
+    /// How documents are batched when embedded
+    pub fn embedding_options(mut self, options: EmbeddingOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Only retrieve, and clear, documents matching `filter`
+    pub fn with_filter(mut self, filter: QdrantFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn client(&self) -> &QdrantClient {
+        &self.client
+    }
+
+    /// The `top_k` documents best matching `query` among those matching
+    /// `filter`, in place of the store's filter.
+    pub async fn retrieve_filtered(
+        &self,
+        query: &str,
+        top_k: usize,
+        filter: Option<&QdrantFilter>,
+    ) -> Result<Vec<ScoredDocument>, LLMError> {
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query_embedding = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("empty query embedding".into()))?;
+        if !self.collection_ready.load(Ordering::Acquire) {
+            self.client.ensure_collection(query_embedding.len()).await?;
+            self.collection_ready.store(true, Ordering::Release);
+        }
+
+        let points = self.client.search(&query_embedding, top_k, filter).await?;
+        Ok(points
+            .into_iter()
+            .map(|point| ScoredDocument {
+                document: payload_document(point.payload.unwrap_or_default()),
+                score: point.score,
+            })
+            .collect())
+    }
+}
+
+impl Debug for QdrantDocumentStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QdrantDocumentStore")
+            .field("client", &self.client)
+            .field("filter", &self.filter)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Indexer for QdrantDocumentStore {
+    async fn index(&self, documents: Vec<Document>) -> Result<(), LLMError> {
+        let contents = documents
+            .iter()
+            .map(|document| document.content.clone())
+            .collect::<Vec<_>>();
+        let embeddings = self.embedder.embed_with(contents, &self.options).await?;
+        if embeddings.len() != documents.len() {
+            return Err(LLMError::ProviderError(format!(
+                "expected {} embeddings, got {}",
+                documents.len(),
+                embeddings.len()
+            )));
+        }
+        let Some(dimensions) = embeddings.first().map(Vec::len) else {
+            return Ok(());
+        };
+        if !self.collection_ready.load(Ordering::Acquire) {
+            self.client.ensure_collection(dimensions).await?;
+            self.collection_ready.store(true, Ordering::Release);
+        }
+
+        let points = documents
+            .into_iter()
+            .zip(embeddings)
+            .map(|(document, vector)| QdrantPoint {
+                id: document_point_id(&document.id),
+                vector,
+                payload: document_payload(document),
+            })
+            .collect();
+        self.client.upsert(points).await
+    }
+
+    async fn remove(&self, ids: &[String]) -> Result<(), LLMError> {
+        self.client
+            .delete(ids.iter().map(|id| document_point_id(id)).collect())
+            .await
+    }
+
+    async fn clear(&self) -> Result<(), LLMError> {
+        self.client
+            .delete_matching(&self.filter.clone().unwrap_or_default())
+            .await
+    }
+}
+
+#[async_trait]
+impl Retriever for QdrantDocumentStore {
+    async fn retrieve(&self, query: &str, top_k: usize) -> Result<Vec<ScoredDocument>, LLMError> {
+        self.retrieve_filtered(query, top_k, self.filter.as_ref())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_serializes_to_qdrant_syntax() {
+        let filter = QdrantFilter::new()
+            .must_match("metadata.source", "notes.md")
+            .must_not_match("metadata.draft", true);
+        assert_eq!(
+            serde_json::to_value(&filter).unwrap(),
+            json!({
+                "must": [{"key": "metadata.source", "match": {"value": "notes.md"}}],
+                "must_not": [{"key": "metadata.draft", "match": {"value": true}}],
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(QdrantFilter::new()).unwrap(),
+            json!({})
+        );
+    }
+
+    #[test]
+    fn test_documents_round_trip_through_payload() {
+        let document = Document::new("notes#0", "Rust has a borrow checker.")
+            .with_metadata("source", "notes.md");
+        assert_eq!(
+            document_point_id("notes#0"),
+            document_point_id(&document.id)
+        );
+        assert_ne!(document_point_id("notes#0"), document_point_id("notes#1"));
+        assert_eq!(
+            payload_document(document_payload(document.clone())),
+            document
+        );
+
+        let point: QdrantScoredPoint =
+            serde_json::from_value(json!({"id": 3, "version": 1, "score": 0.5})).unwrap();
+        assert_eq!(point.id, PointId::Num(3));
+        assert!(point.payload.is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use super::{Document, Indexer, Retriever, ScoredDocument};
+use crate::utils::cosine_similarity;
+use async_trait::async_trait;
+use autoagents_llm::embedding::{EmbeddingOptions, EmbeddingProvider};
+use autoagents_llm::error::LLMError;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Documents and their embeddings kept in memory, retrieved by cosine
+/// similarity to the query embedding.
+pub struct InMemoryDocumentStore {
+    embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    options: EmbeddingOptions,
+    entries: RwLock<Vec<(Document, Vec<f32>)>>,
+}
+
+impl InMemoryDocumentStore {
+    pub fn new(embedder: Arc<dyn EmbeddingProvider + Send + Sync>) -> Self {
+        Self {
+            embedder,
+            options: EmbeddingOptions::new(),
+            entries: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// How documents are batched when embedded
+    pub fn embedding_options(mut self, options: EmbeddingOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Vec<(Document, Vec<f32>)>> {
+        self.entries.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Vec<(Document, Vec<f32>)>> {
+        self.entries.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Debug for InMemoryDocumentStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemoryDocumentStore")
+            .field("documents", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Indexer for InMemoryDocumentStore {
+    async fn index(&self, documents: Vec<Document>) -> Result<(), LLMError> {
+        let contents = documents
+            .iter()
+            .map(|document| document.content.clone())
+            .collect::<Vec<_>>();
+        let embeddings = self.embedder.embed_with(contents, &self.options).await?;
+        if embeddings.len() != documents.len() {
+            return Err(LLMError::ProviderError(format!(
+                "expected {} embeddings, got {}",
+                documents.len(),
+                embeddings.len()
+            )));
+        }
+
+        let mut entries = self.write();
+        entries.retain(|(stored, _)| !documents.iter().any(|new| new.id == stored.id));
+        entries.extend(documents.into_iter().zip(embeddings));
+        Ok(())
+    }
+
+    async fn remove(&self, ids: &[String]) -> Result<(), LLMError> {
+        self.write()
+            .retain(|(document, _)| !ids.contains(&document.id));
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), LLMError> {
+        self.write().clear();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Retriever for InMemoryDocumentStore {
+    async fn retrieve(&self, query: &str, top_k: usize) -> Result<Vec<ScoredDocument>, LLMError> {
+        if query.is_empty() || self.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query_embedding = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("empty query embedding".into()))?;
+
+        let mut scored: Vec<ScoredDocument> = self
+            .read()
+            .iter()
+            .map(|(document, embedding)| ScoredDocument {
+                document: document.clone(),
+                score: cosine_similarity(&query_embedding, embedding),
+            })
+            .collect();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(top_k);
+        Ok(scored)
+    }
+}
@@ -0,0 +1,74 @@
+use super::RagPipeline;
+use crate::tool::{ToolCallError, ToolRuntime, ToolT};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Lets an agent search a [`RagPipeline`] for the documents it needs.
+#[derive(Debug, Clone)]
+pub struct RetrievalTool {
+    pipeline: Arc<RagPipeline>,
+}
+
+impl RetrievalTool {
+    pub fn new(pipeline: Arc<RagPipeline>) -> Self {
+        Self { pipeline }
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    query: String,
+}
+
+#[async_trait]
+impl ToolRuntime for RetrievalTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let SearchArgs { query } = serde_json::from_value(args)?;
+        let documents = self
+            .pipeline
+            .retrieve(&query)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let documents: Vec<Value> = documents
+            .into_iter()
+            .map(|scored| {
+                json!({
+                    "id": scored.document.id,
+                    "content": scored.document.content,
+                    "metadata": scored.document.metadata,
+                    "score": scored.score,
+                })
+            })
+            .collect();
+        Ok(json!({ "documents": documents }))
+    }
+}
+
+impl ToolT for RetrievalTool {
+    fn name(&self) -> &'static str {
+        "search_documents"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search the indexed documents for passages relevant to a query. Use it to look up facts before answering instead of relying on memory."
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "What to search for, as a question or a few keywords"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn idempotent(&self) -> bool {
+        true
+    }
+}
@@ -115,13 +115,16 @@ impl AgentExecutor for MockAgentImpl {
     }
     async fn execute_stream(
         &self,
-        _task: &Task,
+        task: &Task,
         _context: Arc<Context>,
     ) -> Result<
         std::pin::Pin<Box<dyn Stream<Item = Result<Self::Output, Self::Error>> + Send>>,
         Self::Error,
     > {
-        unimplemented!()
+        let chunks = ["Working".to_string(), format!("Processed: {}", task.prompt)];
+        Ok(Box::pin(futures::stream::iter(
+            chunks.map(|result| Ok(TestAgentOutput { result })),
+        )))
     }
 }
 
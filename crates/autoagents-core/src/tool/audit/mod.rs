@@ -0,0 +1,528 @@
+//! Append-only audit log of tool invocations.
+//!
+//! An agent built with an [`AuditSink`] records every call its tools make:
+//! who made it, with which arguments, how long it took and a hash of what
+//! came back. Sinks only ever append; records are never rewritten.
+
+use super::{
+    buffer_chunks, canonicalize, ToolCallError, ToolRetryPolicy, ToolRuntime, ToolStream, ToolT,
+};
+use crate::protocol::RunId;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteAuditSink;
+
+/// One tool invocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolAuditRecord {
+    /// Milliseconds since the Unix epoch when the call started
+    pub timestamp_ms: u64,
+    /// Name of the agent that made the call
+    pub agent: String,
+    pub run_id: RunId,
+    pub tool: String,
+    pub args: Value,
+    /// `false` for calls that failed or were cancelled before finishing,
+    /// for instance by the executor's tool timeout
+    pub success: bool,
+    /// SHA-256 of the result, of the error message for failed calls, or of
+    /// [`Self::CANCELLED`] for cancelled ones
+    pub result_hash: String,
+    pub latency_ms: u64,
+}
+
+impl ToolAuditRecord {
+    /// Outcome hashed into the record of a call cancelled before it finished
+    pub const CANCELLED: &'static str = "Tool call cancelled before it finished";
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuditError {
+    #[error("Audit IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Audit serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Audit backend error: {0}")]
+    Backend(String),
+}
+
+/// Destination of audit records
+#[async_trait]
+pub trait AuditSink: Send + Sync + std::fmt::Debug {
+    async fn append(&self, record: &ToolAuditRecord) -> Result<(), AuditError>;
+}
+
+/// Audit records written as JSON lines to a file that is only appended to.
+#[derive(Debug)]
+pub struct FileAuditSink {
+    file: Mutex<tokio::fs::File>,
+}
+
+impl FileAuditSink {
+    /// Open `path` for appending, creating it if needed
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+#[async_trait]
+impl AuditSink for FileAuditSink {
+    async fn append(&self, record: &ToolAuditRecord) -> Result<(), AuditError> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Hex encoded SHA-256 of `result`, stable across runs for equal values
+fn result_hash(result: &Result<Value, ToolCallError>) -> String {
+    let digest = match result {
+        Ok(value) => Sha256::digest(canonicalize(value)),
+        Err(error) => Sha256::digest(error.to_string()),
+    };
+    format!("{digest:x}")
+}
+
+/// Milliseconds since the Unix epoch
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+fn elapsed_millis(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+/// A broken audit sink is reported but does not fail the call
+async fn append(sink: &dyn AuditSink, record: &ToolAuditRecord) {
+    if let Err(e) = sink.append(record).await {
+        log::error!(
+            "Failed to write audit record for tool '{}': {e}",
+            record.tool
+        );
+    }
+}
+
+/// Record of a call in flight.
+///
+/// Starts out as a cancelled call and is completed once the tool returns.
+/// If the call's future is dropped first, as happens when the executor's
+/// tool timeout fires, the cancelled record is written from a spawned task.
+struct PendingRecord {
+    sink: Arc<dyn AuditSink>,
+    record: Option<ToolAuditRecord>,
+    started: Instant,
+    finished: bool,
+}
+
+impl PendingRecord {
+    fn complete(&mut self, result: &Result<Value, ToolCallError>) {
+        if let Some(record) = &mut self.record {
+            record.success = result.is_ok();
+            record.result_hash = result_hash(result);
+            record.latency_ms = elapsed_millis(self.started);
+        }
+        self.finished = true;
+    }
+
+    async fn write(mut self) {
+        if let Some(record) = &self.record {
+            append(self.sink.as_ref(), record).await;
+        }
+        self.record = None;
+    }
+}
+
+impl Drop for PendingRecord {
+    fn drop(&mut self) {
+        let Some(mut record) = self.record.take() else {
+            return;
+        };
+        if !self.finished {
+            record.latency_ms = elapsed_millis(self.started);
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            log::error!(
+                "Failed to write audit record for tool '{}': no runtime",
+                record.tool
+            );
+            return;
+        };
+        let sink = Arc::clone(&self.sink);
+        runtime.spawn(async move { append(sink.as_ref(), &record).await });
+    }
+}
+
+/// Tool wrapper that appends a [`ToolAuditRecord`] for every call.
+///
+/// Streamed calls are recorded when their stream ends, with the hash of the
+/// buffered output, or as cancelled if the stream is dropped first.
+#[derive(Debug)]
+pub(crate) struct AuditedTool {
+    inner: Box<dyn ToolT>,
+    sink: Arc<dyn AuditSink>,
+    agent: String,
+    run_id: RunId,
+}
+
+impl AuditedTool {
+    pub(crate) fn wrap(
+        sink: &Arc<dyn AuditSink>,
+        agent: &str,
+        run_id: RunId,
+        tool: Box<dyn ToolT>,
+    ) -> Box<dyn ToolT> {
+        Box::new(Self {
+            inner: tool,
+            sink: Arc::clone(sink),
+            agent: agent.to_string(),
+            run_id,
+        })
+    }
+
+    /// Record of a call with `args` that has just started
+    fn pending(&self, args: Value) -> PendingRecord {
+        PendingRecord {
+            sink: Arc::clone(&self.sink),
+            record: Some(ToolAuditRecord {
+                timestamp_ms: unix_millis(SystemTime::now()),
+                agent: self.agent.clone(),
+                run_id: self.run_id,
+                tool: self.inner.name().to_string(),
+                args,
+                success: false,
+                result_hash: format!("{:x}", Sha256::digest(ToolAuditRecord::CANCELLED)),
+                latency_ms: 0,
+            }),
+            started: Instant::now(),
+            finished: false,
+        }
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for AuditedTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let mut pending = self.pending(args.clone());
+        let result = self.inner.execute(args).await;
+        pending.complete(&result);
+        pending.write().await;
+        result
+    }
+
+    fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+        let stream = self.inner.execute_stream(args.clone())?;
+        let state = (stream, Some(self.pending(args)), Vec::new());
+        Some(Box::pin(futures::stream::unfold(
+            state,
+            |(mut stream, mut pending, mut chunks)| async move {
+                let mut record = pending.take()?;
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        chunks.push(chunk.clone());
+                        Some((Ok(chunk), (stream, Some(record), chunks)))
+                    }
+                    Some(Err(error)) => {
+                        let result = Err(error);
+                        record.complete(&result);
+                        record.write().await;
+                        Some((result, (stream, None, chunks)))
+                    }
+                    None => {
+                        record.complete(&Ok(buffer_chunks(chunks)));
+                        record.write().await;
+                        None
+                    }
+                }
+            },
+        )))
+    }
+}
+
+impl ToolT for AuditedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.inner.cache_ttl()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.inner.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::ToolProcessor;
+    use autoagents_llm::{FunctionCall, ToolCall};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[derive(Debug)]
+    struct Echo;
+
+    #[async_trait]
+    impl ToolRuntime for Echo {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            match args.get("fail") {
+                Some(_) => Err(ToolCallError::RuntimeError("refused".into())),
+                None => Ok(args),
+            }
+        }
+    }
+
+    impl ToolT for Echo {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn description(&self) -> &'static str {
+            "echoes its arguments"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[derive(Debug)]
+    struct Sleepy;
+
+    #[async_trait]
+    impl ToolRuntime for Sleepy {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(json!("done"))
+        }
+    }
+
+    impl ToolT for Sleepy {
+        fn name(&self) -> &'static str {
+            "sleepy"
+        }
+
+        fn description(&self) -> &'static str {
+            "sleeps for ten seconds"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[derive(Debug)]
+    struct Lines;
+
+    #[async_trait]
+    impl ToolRuntime for Lines {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!("one\ntwo\n"))
+        }
+
+        fn execute_stream(&self, _args: Value) -> Option<ToolStream<'_>> {
+            Some(Box::pin(futures::stream::iter([
+                Ok(json!("one\n")),
+                Ok(json!("two\n")),
+            ])))
+        }
+    }
+
+    impl ToolT for Lines {
+        fn name(&self) -> &'static str {
+            "lines"
+        }
+
+        fn description(&self) -> &'static str {
+            "streams two lines"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct MemorySink(Mutex<Vec<ToolAuditRecord>>);
+
+    #[async_trait]
+    impl AuditSink for MemorySink {
+        async fn append(&self, record: &ToolAuditRecord) -> Result<(), AuditError> {
+            self.0.lock().await.push(record.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_call_is_appended_to_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let sink: Arc<dyn AuditSink> = Arc::new(FileAuditSink::open(&path).await.unwrap());
+        let run_id = Uuid::new_v4();
+        let tool = AuditedTool::wrap(&sink, "ops_agent", run_id, Box::new(Echo));
+
+        tool.execute(json!({"b": 1, "a": 2})).await.unwrap();
+        assert!(tool.execute(json!({"fail": true})).await.is_err());
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<ToolAuditRecord> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].agent, "ops_agent");
+        assert_eq!(records[0].run_id, run_id);
+        assert_eq!(records[0].tool, "echo");
+        assert!(records[0].success);
+        assert_eq!(
+            records[0].result_hash,
+            result_hash(&Ok(json!({"a": 2, "b": 1})))
+        );
+        assert!(!records[1].success);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_call_is_recorded() {
+        let memory = Arc::new(MemorySink::default());
+        let sink: Arc<dyn AuditSink> = memory.clone();
+        let tools = vec![AuditedTool::wrap(
+            &sink,
+            "ops_agent",
+            Uuid::new_v4(),
+            Box::new(Sleepy),
+        )];
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "sleepy".to_string(),
+                arguments: "{}".to_string(),
+            },
+        };
+
+        let result = ToolProcessor::process_single_tool_call(
+            &tools,
+            &call,
+            &None,
+            Some(Duration::from_millis(20)),
+        )
+        .await;
+        assert_eq!(result.result["timed_out"], true);
+
+        // The record of a cancelled call is written from a spawned task
+        let records = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                let records = memory.0.lock().await.clone();
+                if !records.is_empty() {
+                    return records;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tool, "sleepy");
+        assert!(!records[0].success);
+        assert_eq!(
+            records[0].result_hash,
+            format!("{:x}", Sha256::digest(ToolAuditRecord::CANCELLED))
+        );
+        assert!(records[0].latency_ms >= 20);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_call_is_recorded_when_the_stream_ends() {
+        let memory = Arc::new(MemorySink::default());
+        let sink: Arc<dyn AuditSink> = memory.clone();
+        let tool = AuditedTool::wrap(&sink, "ops_agent", Uuid::new_v4(), Box::new(Lines));
+
+        let mut stream = tool.execute_stream(json!({})).unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!("one\n"));
+        assert!(memory.0.lock().await.is_empty());
+        assert_eq!(stream.next().await.unwrap().unwrap(), json!("two\n"));
+        assert!(stream.next().await.is_none());
+
+        let records = memory.0.lock().await.clone();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].success);
+        assert_eq!(
+            records[0].result_hash,
+            result_hash(&Ok(json!("one\ntwo\n")))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_stream_is_recorded_as_cancelled() {
+        let memory = Arc::new(MemorySink::default());
+        let sink: Arc<dyn AuditSink> = memory.clone();
+        let tool = AuditedTool::wrap(&sink, "ops_agent", Uuid::new_v4(), Box::new(Lines));
+
+        let mut stream = tool.execute_stream(json!({})).unwrap();
+        stream.next().await.unwrap().unwrap();
+        drop(stream);
+
+        let records = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                let records = memory.0.lock().await.clone();
+                if !records.is_empty() {
+                    return records;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(!records[0].success);
+        assert_eq!(
+            records[0].result_hash,
+            format!("{:x}", Sha256::digest(ToolAuditRecord::CANCELLED))
+        );
+    }
+}
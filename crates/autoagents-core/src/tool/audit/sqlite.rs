@@ -0,0 +1,207 @@
+use super::{AuditError, AuditSink, ToolAuditRecord};
+use async_trait::async_trait;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+// Triggers reject changes to existing rows so the table stays append-only
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tool_audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp_ms INTEGER NOT NULL,
+    agent TEXT NOT NULL,
+    run_id TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    args TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    result_hash TEXT NOT NULL,
+    latency_ms INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tool_audit_log_run
+    ON tool_audit_log (run_id, id);
+CREATE TRIGGER IF NOT EXISTS tool_audit_log_no_update
+    BEFORE UPDATE ON tool_audit_log
+    BEGIN SELECT RAISE(ABORT, 'tool_audit_log is append-only'); END;
+CREATE TRIGGER IF NOT EXISTS tool_audit_log_no_delete
+    BEFORE DELETE ON tool_audit_log
+    BEGIN SELECT RAISE(ABORT, 'tool_audit_log is append-only'); END;
+";
+
+impl From<rusqlite::Error> for AuditError {
+    fn from(error: rusqlite::Error) -> Self {
+        AuditError::Backend(error.to_string())
+    }
+}
+
+/// Audit records stored in the `tool_audit_log` table of a SQLite database.
+///
+/// Cheap to clone; clones share the connection. Writes run on tokio's
+/// blocking pool.
+#[derive(Clone)]
+pub struct SqliteAuditSink {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteAuditSink {
+    /// Open (or create) the database file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Private database that disappears with the last clone, mainly for tests.
+    pub fn in_memory() -> Result<Self, AuditError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, AuditError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Records of the run `run_id`, oldest first
+    pub async fn records(&self, run_id: Uuid) -> Result<Vec<ToolAuditRecord>, AuditError> {
+        let run_id = run_id.to_string();
+        self.run(move |conn| {
+            let mut statement = conn.prepare(
+                "SELECT timestamp_ms, agent, run_id, tool, args, success, result_hash, latency_ms
+                 FROM tool_audit_log WHERE run_id = ?1 ORDER BY id ASC",
+            )?;
+            let rows = statement.query_map(params![run_id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, bool>(5)?,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, i64>(7)?,
+                ))
+            })?;
+            let mut records = Vec::new();
+            for row in rows {
+                let (timestamp_ms, agent, run_id, tool, args, success, result_hash, latency_ms) =
+                    row?;
+                records.push(ToolAuditRecord {
+                    timestamp_ms: timestamp_ms as u64,
+                    agent,
+                    run_id: run_id
+                        .parse()
+                        .map_err(|e: uuid::Error| AuditError::Backend(e.to_string()))?,
+                    tool,
+                    args: serde_json::from_str(&args)?,
+                    success,
+                    result_hash,
+                    latency_ms: latency_ms as u64,
+                });
+            }
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn run<R, F>(&self, query: F) -> Result<R, AuditError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&Connection) -> Result<R, AuditError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            query(&conn)
+        })
+        .await
+        .map_err(|e| AuditError::Backend(e.to_string()))?
+    }
+}
+
+impl std::fmt::Debug for SqliteAuditSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self
+            .conn
+            .lock()
+            .ok()
+            .and_then(|conn| conn.path().map(str::to_string));
+        f.debug_struct("SqliteAuditSink")
+            .field("path", &path)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl AuditSink for SqliteAuditSink {
+    async fn append(&self, record: &ToolAuditRecord) -> Result<(), AuditError> {
+        let record = record.clone();
+        let args = serde_json::to_string(&record.args)?;
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT INTO tool_audit_log
+                    (timestamp_ms, agent, run_id, tool, args, success, result_hash, latency_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    i64::try_from(record.timestamp_ms).unwrap_or(i64::MAX),
+                    record.agent,
+                    record.run_id.to_string(),
+                    record.tool,
+                    args,
+                    record.success,
+                    record.result_hash,
+                    i64::try_from(record.latency_ms).unwrap_or(i64::MAX),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(run_id: Uuid, tool: &str) -> ToolAuditRecord {
+        ToolAuditRecord {
+            timestamp_ms: 1_700_000_000_000,
+            agent: "ops_agent".into(),
+            run_id,
+            tool: tool.into(),
+            args: json!({"path": "/tmp"}),
+            success: true,
+            result_hash: "abc".into(),
+            latency_ms: 12,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_are_stored_per_run() {
+        let sink = SqliteAuditSink::in_memory().unwrap();
+        let run = Uuid::new_v4();
+        sink.append(&record(run, "list_dir")).await.unwrap();
+        sink.append(&record(run, "read_file")).await.unwrap();
+        sink.append(&record(Uuid::new_v4(), "other")).await.unwrap();
+
+        let records = sink.records(run).await.unwrap();
+        assert_eq!(
+            records,
+            vec![record(run, "list_dir"), record(run, "read_file")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_log_cannot_be_rewritten() {
+        let sink = SqliteAuditSink::in_memory().unwrap();
+        sink.append(&record(Uuid::new_v4(), "list_dir"))
+            .await
+            .unwrap();
+
+        let conn = sink.conn.lock().unwrap();
+        assert!(conn
+            .execute("UPDATE tool_audit_log SET success = 0", [])
+            .is_err());
+        assert!(conn.execute("DELETE FROM tool_audit_log", []).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use super::{buffer_chunks, ToolCallError, ToolRetryPolicy, ToolRuntime, ToolStream, ToolT};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long cached tool results are shared for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCacheScope {
+    /// A fresh cache is created for every task run
+    Run,
+    /// One cache is shared by every run of the agent
+    Session,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    value: Value,
+    expires_at: Instant,
+}
+
+/// Cache of successful tool results keyed by tool name and canonicalized arguments.
+///
+/// Only tools that opt in through [`ToolT::cache_ttl`] are cached, each with its
+/// own time-to-live.
+#[derive(Debug, Default)]
+pub struct ToolResultCache {
+    entries: Mutex<HashMap<(String, String), CacheEntry>>,
+}
+
+impl ToolResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look up a live entry, dropping it if it has expired.
+    pub fn get(&self, tool_name: &str, args: &Value) -> Option<Value> {
+        let key = (tool_name.to_string(), canonicalize(args));
+        let mut entries = self.entries.lock().ok()?;
+        match entries.get(&key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.value.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, tool_name: &str, args: &Value, value: Value, ttl: Duration) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                (tool_name.to_string(), canonicalize(args)),
+                CacheEntry {
+                    value,
+                    expires_at: Instant::now() + ttl,
+                },
+            );
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    /// Number of stored entries, including ones that have expired but not been evicted yet.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wrap `tool` so its results go through this cache.
+    ///
+    /// Tools without a [`ToolT::cache_ttl`] are returned unchanged.
+    pub fn wrap(self: &Arc<Self>, tool: Box<dyn ToolT>) -> Box<dyn ToolT> {
+        match tool.cache_ttl() {
+            Some(ttl) => Box::new(CachedTool {
+                inner: tool,
+                cache: Arc::clone(self),
+                ttl,
+            }),
+            None => tool,
+        }
+    }
+}
+
+/// Serialize `value` with object keys sorted at every level so that
+/// semantically equal arguments produce the same cache key.
+pub fn canonicalize(value: &Value) -> String {
+    fn sort(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                Value::Object(
+                    keys.into_iter()
+                        .map(|k| (k.clone(), sort(&map[k])))
+                        .collect(),
+                )
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sort).collect()),
+            other => other.clone(),
+        }
+    }
+    sort(value).to_string()
+}
+
+/// Tool wrapper that serves repeated calls from a [`ToolResultCache`].
+///
+/// Streamed calls are forwarded on a miss and their buffered output is
+/// stored once the stream ends without error. A hit is not streamed; it is
+/// served by `execute`.
+#[derive(Debug)]
+struct CachedTool {
+    inner: Box<dyn ToolT>,
+    cache: Arc<ToolResultCache>,
+    ttl: Duration,
+}
+
+#[async_trait]
+impl ToolRuntime for CachedTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        if let Some(hit) = self.cache.get(self.inner.name(), &args) {
+            return Ok(hit);
+        }
+        let result = self.inner.execute(args.clone()).await?;
+        self.cache
+            .insert(self.inner.name(), &args, result.clone(), self.ttl);
+        Ok(result)
+    }
+
+    fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+        if self.cache.get(self.inner.name(), &args).is_some() {
+            return None;
+        }
+        let stream = self.inner.execute_stream(args.clone())?;
+        Some(Box::pin(futures::stream::unfold(
+            (stream, Some(Vec::new()), args),
+            move |(mut stream, mut chunks, args)| async move {
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        if let Some(chunks) = &mut chunks {
+                            chunks.push(chunk.clone());
+                        }
+                        Some((Ok(chunk), (stream, chunks, args)))
+                    }
+                    Some(Err(error)) => Some((Err(error), (stream, None, args))),
+                    None => {
+                        if let Some(chunks) = chunks {
+                            let value = buffer_chunks(chunks);
+                            self.cache.insert(self.inner.name(), &args, value, self.ttl);
+                        }
+                        None
+                    }
+                }
+            },
+        )))
+    }
+}
+
+impl ToolT for CachedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        Some(self.ttl)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.inner.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    struct CountingTool {
+        calls: Arc<AtomicUsize>,
+        ttl: Option<Duration>,
+    }
+
+    #[async_trait]
+    impl ToolRuntime for CountingTool {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if args.get("fail").is_some() {
+                return Err(ToolCallError::RuntimeError("failed".to_string().into()));
+            }
+            Ok(json!({ "call": n }))
+        }
+
+        fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let last = match args.get("fail") {
+                Some(_) => Err(ToolCallError::RuntimeError("failed".to_string().into())),
+                None => Ok(json!(n.to_string())),
+            };
+            Some(Box::pin(futures::stream::iter([Ok(json!("call ")), last])))
+        }
+    }
+
+    impl ToolT for CountingTool {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn description(&self) -> &'static str {
+            "counts calls"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        fn cache_ttl(&self) -> Option<Duration> {
+            self.ttl
+        }
+    }
+
+    fn counting(ttl: Option<Duration>) -> (Box<dyn ToolT>, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        (
+            Box::new(CountingTool {
+                calls: calls.clone(),
+                ttl,
+            }),
+            calls,
+        )
+    }
+
+    #[test]
+    fn test_canonicalize_sorts_nested_keys() {
+        let a = json!({"b": 1, "a": {"y": [1, {"d": 0, "c": 0}], "x": null}});
+        let b = json!({"a": {"x": null, "y": [1, {"c": 0, "d": 0}]}, "b": 1});
+        assert_eq!(canonicalize(&a), canonicalize(&b));
+    }
+
+    #[tokio::test]
+    async fn test_cached_tool_reuses_result_for_equal_args() {
+        let cache = Arc::new(ToolResultCache::new());
+        let (tool, calls) = counting(Some(Duration::from_secs(60)));
+        let tool = cache.wrap(tool);
+
+        let first = tool.execute(json!({"a": 1, "b": 2})).await.unwrap();
+        let second = tool.execute(json!({"b": 2, "a": 1})).await.unwrap();
+        let other = tool.execute(json!({"a": 2})).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_refreshed() {
+        let cache = Arc::new(ToolResultCache::new());
+        let (tool, calls) = counting(Some(Duration::ZERO));
+        let tool = cache.wrap(tool);
+
+        tool.execute(json!({})).await.unwrap();
+        tool.execute(json!({})).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let cache = Arc::new(ToolResultCache::new());
+        let (tool, calls) = counting(Some(Duration::from_secs(60)));
+        let tool = cache.wrap(tool);
+
+        assert!(tool.execute(json!({"fail": true})).await.is_err());
+        assert!(tool.execute(json!({"fail": true})).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tools_without_ttl_are_not_wrapped() {
+        let cache = Arc::new(ToolResultCache::new());
+        let (tool, calls) = counting(None);
+        let tool = cache.wrap(tool);
+
+        tool.execute(json!({})).await.unwrap();
+        tool.execute(json!({})).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_result_is_cached_when_the_stream_ends() {
+        let cache = Arc::new(ToolResultCache::new());
+        let (tool, calls) = counting(Some(Duration::from_secs(60)));
+        let tool = cache.wrap(tool);
+
+        let chunks: Vec<Value> = tool
+            .execute_stream(json!({}))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(chunks, vec![json!("call "), json!("1")]);
+        assert_eq!(cache.get("counting", &json!({})), Some(json!("call 1")));
+
+        // A hit is not streamed but served by execute
+        assert!(tool.execute_stream(json!({})).is_none());
+        assert_eq!(tool.execute(json!({})).await.unwrap(), json!("call 1"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let failed: Vec<_> = tool
+            .execute_stream(json!({"fail": true}))
+            .unwrap()
+            .collect()
+            .await;
+        assert!(failed[1].is_err());
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,303 @@
+//! Secrets handed to tools when they run.
+//!
+//! Instead of holding API keys in their own fields, tools look them up by
+//! name with [`credential`] while executing. The agent running the call
+//! decides where they come from through the [`CredentialProvider`] it was
+//! built with, so the same tool can act with different keys in different
+//! agents.
+
+use super::{ToolCallError, ToolRetryPolicy, ToolRuntime, ToolStream, ToolT};
+use async_trait::async_trait;
+use futures::Stream;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+tokio::task_local! {
+    static PROVIDER: Arc<dyn CredentialProvider>;
+}
+
+/// A secret value, kept out of `Debug` output and logs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The secret itself, for putting into a request
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    #[error("Credential '{0}' is not available")]
+    NotFound(String),
+
+    #[error("No credential provider is set for this agent")]
+    NoProvider,
+
+    #[error("Credential provider error: {0}")]
+    Provider(String),
+}
+
+impl From<CredentialError> for ToolCallError {
+    fn from(error: CredentialError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+/// Source of the secrets an agent's tools may use.
+#[async_trait]
+pub trait CredentialProvider: Send + Sync + fmt::Debug {
+    /// Look up the secret called `name`, `None` when there is no such secret
+    /// or the agent may not use it.
+    async fn get(&self, name: &str) -> Result<Option<Secret>, CredentialError>;
+}
+
+/// Fetch the secret called `name` for the tool call running on the current
+/// task, from the provider of the agent that made the call.
+pub async fn credential(name: &str) -> Result<Secret, CredentialError> {
+    let provider = PROVIDER
+        .try_with(Arc::clone)
+        .map_err(|_| CredentialError::NoProvider)?;
+    provider
+        .get(name)
+        .await?
+        .ok_or_else(|| CredentialError::NotFound(name.to_string()))
+}
+
+/// Fixed set of secrets, for tests and for values loaded at startup.
+#[derive(Debug, Default, Clone)]
+pub struct StaticCredentials {
+    secrets: HashMap<String, Secret>,
+}
+
+impl StaticCredentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.secrets.insert(name.into(), Secret::new(value));
+        self
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for StaticCredentials {
+    async fn get(&self, name: &str) -> Result<Option<Secret>, CredentialError> {
+        Ok(self.secrets.get(name).cloned())
+    }
+}
+
+/// Secrets read from environment variables.
+///
+/// Only the names passed to [`Self::allow`] can be read, so an agent cannot
+/// reach every variable of the process. With a prefix, `GITHUB_TOKEN` is
+/// read from `{prefix}GITHUB_TOKEN`, which gives agents separate keys.
+#[derive(Debug, Default, Clone)]
+pub struct EnvCredentials {
+    prefix: String,
+    allowed: Vec<String>,
+}
+
+impl EnvCredentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    pub fn allow(mut self, name: impl Into<String>) -> Self {
+        self.allowed.push(name.into());
+        self
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for EnvCredentials {
+    async fn get(&self, name: &str) -> Result<Option<Secret>, CredentialError> {
+        if !self.allowed.iter().any(|allowed| allowed == name) {
+            return Ok(None);
+        }
+        Ok(std::env::var(format!("{}{name}", self.prefix))
+            .ok()
+            .map(Secret::new))
+    }
+}
+
+/// Tool wrapper that makes an agent's credentials available to the tool
+/// while it runs.
+#[derive(Debug)]
+pub(crate) struct CredentialScopedTool {
+    inner: Box<dyn ToolT>,
+    provider: Arc<dyn CredentialProvider>,
+}
+
+impl CredentialScopedTool {
+    pub(crate) fn wrap(
+        provider: &Arc<dyn CredentialProvider>,
+        tool: Box<dyn ToolT>,
+    ) -> Box<dyn ToolT> {
+        Box::new(Self {
+            inner: tool,
+            provider: Arc::clone(provider),
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for CredentialScopedTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        PROVIDER
+            .scope(Arc::clone(&self.provider), self.inner.execute(args))
+            .await
+    }
+
+    fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+        let stream = self.inner.execute_stream(args)?;
+        Some(Box::pin(ScopedStream {
+            inner: stream,
+            provider: Arc::clone(&self.provider),
+        }))
+    }
+}
+
+impl ToolT for CredentialScopedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.inner.cache_ttl()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.inner.namespace()
+    }
+}
+
+/// Polls a tool's output stream with the credentials in scope
+struct ScopedStream<'a> {
+    inner: ToolStream<'a>,
+    provider: Arc<dyn CredentialProvider>,
+}
+
+impl Stream for ScopedStream<'_> {
+    type Item = Result<Value, ToolCallError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        PROVIDER.sync_scope(Arc::clone(&this.provider), || {
+            this.inner.as_mut().poll_next(cx)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct Deploy;
+
+    #[async_trait]
+    impl ToolRuntime for Deploy {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            let token = credential("DEPLOY_TOKEN").await?;
+            Ok(json!(token.expose()))
+        }
+    }
+
+    impl ToolT for Deploy {
+        fn name(&self) -> &'static str {
+            "deploy"
+        }
+
+        fn description(&self) -> &'static str {
+            "deploys with a token"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_agent_gets_its_own_credentials() {
+        let staging: Arc<dyn CredentialProvider> =
+            Arc::new(StaticCredentials::new().with("DEPLOY_TOKEN", "staging-key"));
+        let production: Arc<dyn CredentialProvider> =
+            Arc::new(StaticCredentials::new().with("DEPLOY_TOKEN", "production-key"));
+
+        let tool = CredentialScopedTool::wrap(&staging, Box::new(Deploy));
+        assert_eq!(tool.execute(json!({})).await.unwrap(), "staging-key");
+        let tool = CredentialScopedTool::wrap(&production, Box::new(Deploy));
+        assert_eq!(tool.execute(json!({})).await.unwrap(), "production-key");
+    }
+
+    #[tokio::test]
+    async fn test_missing_credentials_fail_the_call() {
+        assert!(Deploy.execute(json!({})).await.is_err());
+
+        let empty: Arc<dyn CredentialProvider> = Arc::new(StaticCredentials::new());
+        let tool = CredentialScopedTool::wrap(&empty, Box::new(Deploy));
+        assert!(tool.execute(json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_env_credentials_only_read_allowed_names() {
+        std::env::set_var("AUTOAGENTS_TEST_AGENT_DEPLOY_TOKEN", "from-env");
+        let env = EnvCredentials::new().with_prefix("AUTOAGENTS_TEST_AGENT_");
+        assert_eq!(env.get("DEPLOY_TOKEN").await.unwrap(), None);
+
+        let env = env.allow("DEPLOY_TOKEN");
+        assert_eq!(
+            env.get("DEPLOY_TOKEN").await.unwrap(),
+            Some(Secret::new("from-env"))
+        );
+    }
+
+    #[test]
+    fn test_secret_is_redacted_in_debug_output() {
+        assert_eq!(format!("{:?}", Secret::new("hunter2")), "Secret(***)");
+    }
+}
@@ -0,0 +1,103 @@
+use serde_json::{json, Value};
+
+/// A tool failure the tool has classified itself, so the executor can tell
+/// the model whether and how to try again.
+///
+/// Return it from `execute` with `?` or `.into()`; it converts into
+/// [`ToolCallError::Tool`](super::ToolCallError::Tool). Errors that are not
+/// classified are reported to the model as a bare message.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ToolError {
+    /// The arguments were wrong; the call may succeed once they are fixed
+    #[error("Invalid arguments: {0}")]
+    InvalidArgs(String),
+
+    /// A temporary failure, such as a rate limit or an unreachable service;
+    /// the same call may succeed later
+    #[error("Temporary failure: {0}")]
+    Transient(String),
+
+    /// The call is not allowed, and repeating it will not change that
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    /// The tool cannot complete the request at all
+    #[error("Fatal error: {0}")]
+    Fatal(String),
+}
+
+impl ToolError {
+    /// Stable name of the variant, as reported to the model
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ToolError::InvalidArgs(_) => "invalid_args",
+            ToolError::Transient(_) => "transient",
+            ToolError::PermissionDenied(_) => "permission_denied",
+            ToolError::Fatal(_) => "fatal",
+        }
+    }
+
+    /// Whether repeating the same call may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ToolError::Transient(_))
+    }
+
+    /// The observation the model gets for this error, with a hint on what
+    /// to do next. `args_schema` is included for argument errors so the
+    /// model can correct the call.
+    pub(crate) fn feedback(&self, tool_name: &str, args_schema: Value) -> Value {
+        let hint = match self {
+            ToolError::InvalidArgs(_) => {
+                "Fix the arguments to match the schema and call the tool again."
+            }
+            ToolError::Transient(_) => {
+                "This failure is temporary. The same call may succeed if you try again."
+            }
+            ToolError::PermissionDenied(_) => {
+                "This call is not allowed. Do not retry it; continue without it or tell the user."
+            }
+            ToolError::Fatal(_) => {
+                "The tool cannot complete this request. Do not retry it; continue without it or tell the user."
+            }
+        };
+        let mut feedback = json!({
+            "error": format!("Tool '{tool_name}' failed: {self}"),
+            "kind": self.kind(),
+            "retryable": self.is_retryable(),
+            "hint": hint,
+        });
+        if let ToolError::InvalidArgs(_) = self {
+            feedback["schema"] = args_schema;
+        }
+        feedback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feedback_depends_on_the_kind() {
+        let schema = json!({"type": "object"});
+
+        let invalid = ToolError::InvalidArgs("'date' must be in the future".to_string())
+            .feedback("book", schema.clone());
+        assert_eq!(invalid["kind"], "invalid_args");
+        assert_eq!(invalid["schema"], schema);
+        assert_eq!(
+            invalid["error"],
+            "Tool 'book' failed: Invalid arguments: 'date' must be in the future"
+        );
+
+        let transient =
+            ToolError::Transient("rate limited".to_string()).feedback("book", json!({}));
+        assert_eq!(transient["retryable"], true);
+        assert!(transient.get("schema").is_none());
+
+        let denied =
+            ToolError::PermissionDenied("read only".to_string()).feedback("book", json!({}));
+        assert_eq!(denied["retryable"], false);
+        assert!(denied["hint"].as_str().unwrap().contains("Do not retry"));
+    }
+}
@@ -0,0 +1,167 @@
+use super::{ToolCallError, ToolRetryPolicy, ToolRuntime, ToolStream, ToolT};
+use crate::utils::intern;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// The tool groups a task may use, by [`ToolT::namespace`].
+///
+/// With no group enabled every group is available; otherwise only the
+/// enabled ones are. Disabled groups are never available. Tools without a
+/// namespace are always available.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolGroups {
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+impl ToolGroups {
+    pub fn is_empty(&self) -> bool {
+        self.enabled.is_empty() && self.disabled.is_empty()
+    }
+
+    pub fn enable(mut self, group: impl Into<String>) -> Self {
+        self.enabled.push(group.into());
+        self
+    }
+
+    pub fn disable(mut self, group: impl Into<String>) -> Self {
+        self.disabled.push(group.into());
+        self
+    }
+
+    pub fn allows(&self, tool: &dyn ToolT) -> bool {
+        let Some(namespace) = tool.namespace() else {
+            return true;
+        };
+        let listed = |groups: &[String]| groups.iter().any(|group| group == namespace);
+        !listed(&self.disabled) && (self.enabled.is_empty() || listed(&self.enabled))
+    }
+}
+
+/// Puts a tool into a namespace, for tools that do not declare one
+/// themselves, such as those loaded from an MCP server.
+///
+/// The tool is renamed to `namespace_name`.
+#[derive(Debug)]
+pub struct NamespacedTool {
+    inner: Box<dyn ToolT>,
+    namespace: &'static str,
+    name: &'static str,
+}
+
+impl NamespacedTool {
+    pub fn new(namespace: impl Into<String>, tool: Box<dyn ToolT>) -> Self {
+        let namespace = namespace.into();
+        let name = format!("{}_{}", namespace, tool.name());
+        Self {
+            inner: tool,
+            namespace: intern(&namespace),
+            name: intern(&name),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for NamespacedTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        self.inner.execute(args).await
+    }
+
+    fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+        self.inner.execute_stream(args)
+    }
+}
+
+impl ToolT for NamespacedTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.inner.cache_ttl()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        Some(self.namespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct Search;
+
+    #[async_trait]
+    impl ToolRuntime for Search {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!("found"))
+        }
+    }
+
+    impl ToolT for Search {
+        fn name(&self) -> &'static str {
+            "search"
+        }
+
+        fn description(&self) -> &'static str {
+            "searches"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_tool() {
+        let tool = NamespacedTool::new("github", Box::new(Search));
+        assert_eq!(tool.name(), "github_search");
+        assert_eq!(tool.namespace(), Some("github"));
+        assert_eq!(tool.execute(json!({})).await.unwrap(), "found");
+    }
+
+    #[test]
+    fn test_groups_filter_by_namespace() {
+        let github = NamespacedTool::new("github", Box::new(Search));
+        let fs = NamespacedTool::new("fs", Box::new(Search));
+
+        let all = ToolGroups::default();
+        assert!(all.allows(&github) && all.allows(&fs) && all.allows(&Search));
+
+        let only_fs = ToolGroups::default().enable("fs");
+        assert!(!only_fs.allows(&github));
+        assert!(only_fs.allows(&fs));
+        assert!(only_fs.allows(&Search));
+
+        let no_fs = ToolGroups::default().disable("fs");
+        assert!(no_fs.allows(&github));
+        assert!(!no_fs.allows(&fs));
+    }
+}
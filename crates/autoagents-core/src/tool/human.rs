@@ -0,0 +1,237 @@
+use super::progress::{signal, ToolSignal};
+use super::{ToolCallError, ToolRuntime, ToolT};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// A question an agent is waiting on a person to answer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HumanQuestion {
+    /// Pass this back to [`HumanInput::answer`]
+    pub id: String,
+    pub question: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HumanInputError {
+    #[error("No question with id '{0}' is waiting for an answer")]
+    UnknownQuestion(String),
+
+    #[error("The question was withdrawn before it was answered")]
+    Withdrawn,
+}
+
+#[derive(Debug)]
+struct Pending {
+    question: HumanQuestion,
+    reply: oneshot::Sender<String>,
+}
+
+/// Handle through which the questions of an [`AskHumanTool`] are answered.
+///
+/// Questions are announced as [`Event::HumanInputRequested`] on the agent's
+/// event stream and can also be listed with [`Self::pending`]. Clones share
+/// the same questions.
+///
+/// [`Event::HumanInputRequested`]: crate::protocol::Event::HumanInputRequested
+#[derive(Debug, Clone, Default)]
+pub struct HumanInput {
+    pending: Arc<Mutex<HashMap<String, Pending>>>,
+}
+
+impl HumanInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Questions still waiting for an answer
+    pub fn pending(&self) -> Vec<HumanQuestion> {
+        self.lock()
+            .values()
+            .map(|pending| pending.question.clone())
+            .collect()
+    }
+
+    /// Answer the question with `id`, resuming the run that asked it
+    pub fn answer(&self, id: &str, answer: impl Into<String>) -> Result<(), HumanInputError> {
+        let pending = self
+            .lock()
+            .remove(id)
+            .ok_or_else(|| HumanInputError::UnknownQuestion(id.to_string()))?;
+        pending
+            .reply
+            .send(answer.into())
+            .map_err(|_| HumanInputError::Withdrawn)
+    }
+
+    /// Register `question` and wait until it is answered. The question is
+    /// withdrawn if the returned future is dropped first, for instance when
+    /// the tool call times out.
+    async fn ask(&self, question: String) -> Result<String, HumanInputError> {
+        let question = HumanQuestion {
+            id: Uuid::new_v4().to_string(),
+            question,
+        };
+        let (reply, answer) = oneshot::channel();
+        self.lock().insert(
+            question.id.clone(),
+            Pending {
+                question: question.clone(),
+                reply,
+            },
+        );
+        let _withdraw = Withdraw {
+            input: self,
+            id: &question.id,
+        };
+        signal(ToolSignal::Question(question.clone()));
+        answer.await.map_err(|_| HumanInputError::Withdrawn)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Pending>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Drops a question from the pending list once nobody waits for its answer
+struct Withdraw<'a> {
+    input: &'a HumanInput,
+    id: &'a str,
+}
+
+impl Drop for Withdraw<'_> {
+    fn drop(&mut self) {
+        self.input.lock().remove(self.id);
+    }
+}
+
+/// Lets the model ask a person a clarifying question. The run is suspended
+/// until the question is answered through the tool's [`HumanInput`] handle.
+#[derive(Debug, Clone)]
+pub struct AskHumanTool {
+    input: HumanInput,
+    timeout: Option<Duration>,
+}
+
+impl AskHumanTool {
+    pub fn new() -> Self {
+        Self {
+            input: HumanInput::new(),
+            timeout: None,
+        }
+    }
+
+    /// Handle for answering the questions this tool asks
+    pub fn handle(&self) -> HumanInput {
+        self.input.clone()
+    }
+
+    /// Give up on a question after `timeout`. Without one the executor's
+    /// `tool_timeout` applies, and a run without that waits indefinitely.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl Default for AskHumanTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Deserialize)]
+struct AskHumanArgs {
+    question: String,
+}
+
+#[async_trait]
+impl ToolRuntime for AskHumanTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let AskHumanArgs { question } = serde_json::from_value(args)?;
+        let answer = self
+            .input
+            .ask(question)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        Ok(json!({ "answer": answer }))
+    }
+}
+
+impl ToolT for AskHumanTool {
+    fn name(&self) -> &'static str {
+        "ask_human"
+    }
+
+    fn description(&self) -> &'static str {
+        "Ask the user a question and wait for their answer. Use it when the task is ambiguous or needs information only the user has."
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "question": {
+                    "type": "string",
+                    "description": "The question to ask, phrased so it can be answered on its own"
+                }
+            },
+            "required": ["question"]
+        })
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn first_question(input: &HumanInput) -> HumanQuestion {
+        loop {
+            if let Some(question) = input.pending().pop() {
+                return question;
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_resumes_with_the_answer() {
+        let tool = AskHumanTool::new();
+        let input = tool.handle();
+
+        let ask = tool.execute(json!({"question": "Which region?"}));
+        let reply = async {
+            let question = first_question(&input).await;
+            assert_eq!(question.question, "Which region?");
+            input.answer(&question.id, "eu-west-1").unwrap();
+        };
+        let (result, ()) = tokio::join!(ask, reply);
+
+        assert_eq!(result.unwrap(), json!({"answer": "eu-west-1"}));
+        assert!(input.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_questions_are_withdrawn() {
+        let tool = AskHumanTool::new();
+        let input = tool.handle();
+
+        let ask = tool.execute(json!({"question": "Proceed?"}));
+        let timed_out = tokio::time::timeout(Duration::from_millis(20), ask).await;
+        assert!(timed_out.is_err());
+        assert!(input.pending().is_empty());
+        assert!(matches!(
+            input.answer("missing", "yes"),
+            Err(HumanInputError::UnknownQuestion(_))
+        ));
+    }
+}
@@ -0,0 +1,358 @@
+//! Middleware around tool calls.
+//!
+//! An agent runs every call of its tools through its middleware, in the
+//! order they were added. Each middleware gets the arguments first and the
+//! result last, so it can rewrite arguments, refuse calls, redact or reshape
+//! results, or time the call, then hands over to the rest of the chain with
+//! [`Next::run`].
+
+use super::{ToolCallError, ToolRetryPolicy, ToolRuntime, ToolStream, ToolT};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Code run around each tool call of an agent.
+///
+/// ```ignore
+/// #[derive(Debug)]
+/// struct NoDeletes;
+///
+/// #[async_trait]
+/// impl ToolMiddleware for NoDeletes {
+///     async fn call(&self, tool: &dyn ToolT, args: Value, next: Next<'_>) -> Result<Value, ToolCallError> {
+///         if tool.name().starts_with("delete") {
+///             return Err(ToolCallError::RuntimeError("deletes are not allowed".into()));
+///         }
+///         next.run(args).await
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait ToolMiddleware: Send + Sync + Debug {
+    /// Handle a call of `tool`. Call `next.run` to continue down the chain
+    /// to the tool, or return without it to answer the call directly.
+    async fn call(
+        &self,
+        tool: &dyn ToolT,
+        args: Value,
+        next: Next<'_>,
+    ) -> Result<Value, ToolCallError>;
+
+    /// Handle a streamed call of `tool`, see
+    /// [`ToolRuntime::execute_stream`]. Call `next.run_stream` to continue
+    /// down the chain and return its stream, mapped as needed. Do any other
+    /// work inside the returned stream: when the tool does not stream, the
+    /// call is run again through [`call`](Self::call).
+    ///
+    /// The default returns `None`, so streaming tools run through `call` and
+    /// the middleware sees their whole output.
+    fn call_stream<'a>(
+        &'a self,
+        _tool: &'a dyn ToolT,
+        _args: Value,
+        _next: Next<'a>,
+    ) -> Option<ToolStream<'a>> {
+        None
+    }
+}
+
+/// The rest of a middleware chain, ending with the tool itself
+pub struct Next<'a> {
+    tool: &'a dyn ToolT,
+    middleware: &'a [Arc<dyn ToolMiddleware>],
+}
+
+impl<'a> Next<'a> {
+    /// Run the remaining middleware and the tool with `args`
+    pub async fn run(self, args: Value) -> Result<Value, ToolCallError> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => {
+                let next = Next {
+                    tool: self.tool,
+                    middleware: rest,
+                };
+                first.call(self.tool, args, next).await
+            }
+            None => self.tool.execute(args).await,
+        }
+    }
+
+    /// Run the remaining middleware and the tool with `args` as a stream,
+    /// `None` when the tool or a middleware does not stream
+    pub fn run_stream(self, args: Value) -> Option<ToolStream<'a>> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => {
+                let next = Next {
+                    tool: self.tool,
+                    middleware: rest,
+                };
+                first.call_stream(self.tool, args, next)
+            }
+            None => self.tool.execute_stream(args),
+        }
+    }
+}
+
+/// Tool wrapper that runs its calls through a middleware chain.
+///
+/// Streaming tools stream only when every middleware handles streamed calls,
+/// and run through `execute` otherwise.
+#[derive(Debug)]
+pub(crate) struct MiddlewareTool {
+    inner: Box<dyn ToolT>,
+    middleware: Arc<[Arc<dyn ToolMiddleware>]>,
+}
+
+impl MiddlewareTool {
+    pub(crate) fn wrap(
+        middleware: &Arc<[Arc<dyn ToolMiddleware>]>,
+        tool: Box<dyn ToolT>,
+    ) -> Box<dyn ToolT> {
+        Box::new(Self {
+            inner: tool,
+            middleware: Arc::clone(middleware),
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for MiddlewareTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        Next {
+            tool: self.inner.as_ref(),
+            middleware: &self.middleware,
+        }
+        .run(args)
+        .await
+    }
+
+    fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+        Next {
+            tool: self.inner.as_ref(),
+            middleware: &self.middleware,
+        }
+        .run_stream(args)
+    }
+}
+
+impl ToolT for MiddlewareTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.inner.cache_ttl()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.inner.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct Lookup;
+
+    #[async_trait]
+    impl ToolRuntime for Lookup {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!({"user": args["user"], "ssn": "123-45-6789"}))
+        }
+    }
+
+    impl ToolT for Lookup {
+        fn name(&self) -> &'static str {
+            "lookup"
+        }
+
+        fn description(&self) -> &'static str {
+            "looks up a user"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    /// Records the order it sees calls in, and lowercases the user name
+    #[derive(Debug)]
+    struct Normalize(Arc<Mutex<Vec<&'static str>>>);
+
+    #[async_trait]
+    impl ToolMiddleware for Normalize {
+        async fn call(
+            &self,
+            _tool: &dyn ToolT,
+            mut args: Value,
+            next: Next<'_>,
+        ) -> Result<Value, ToolCallError> {
+            self.0.lock().unwrap().push("normalize");
+            if let Some(user) = args["user"].as_str() {
+                args["user"] = json!(user.to_lowercase());
+            }
+            next.run(args).await
+        }
+    }
+
+    /// Hides the `ssn` field of results and refuses calls for `root`
+    #[derive(Debug)]
+    struct Redact(Arc<Mutex<Vec<&'static str>>>);
+
+    #[async_trait]
+    impl ToolMiddleware for Redact {
+        async fn call(
+            &self,
+            _tool: &dyn ToolT,
+            args: Value,
+            next: Next<'_>,
+        ) -> Result<Value, ToolCallError> {
+            self.0.lock().unwrap().push("redact");
+            if args["user"] == "root" {
+                return Err(ToolCallError::RuntimeError("root is off limits".into()));
+            }
+            let mut result = next.run(args).await?;
+            result["ssn"] = json!("[redacted]");
+            Ok(result)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_runs_in_order_around_the_tool() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let chain: Arc<[Arc<dyn ToolMiddleware>]> = Arc::new([
+            Arc::new(Normalize(seen.clone())) as Arc<dyn ToolMiddleware>,
+            Arc::new(Redact(seen.clone())),
+        ]);
+        let tool = MiddlewareTool::wrap(&chain, Box::new(Lookup));
+
+        let result = tool.execute(json!({"user": "Ada"})).await.unwrap();
+        assert_eq!(result, json!({"user": "ada", "ssn": "[redacted]"}));
+        assert_eq!(*seen.lock().unwrap(), vec!["normalize", "redact"]);
+
+        let error = tool.execute(json!({"user": "ROOT"})).await.unwrap_err();
+        assert!(error.to_string().contains("off limits"));
+    }
+
+    /// Streams the lines of a log
+    #[derive(Debug)]
+    struct Tail;
+
+    #[async_trait]
+    impl ToolRuntime for Tail {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!("started\nready\n"))
+        }
+
+        fn execute_stream(&self, _args: Value) -> Option<ToolStream<'_>> {
+            Some(Box::pin(futures::stream::iter([
+                Ok(json!("started\n")),
+                Ok(json!("ready\n")),
+            ])))
+        }
+    }
+
+    impl ToolT for Tail {
+        fn name(&self) -> &'static str {
+            "tail"
+        }
+
+        fn description(&self) -> &'static str {
+            "tails a log"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    /// Uppercases string results, chunk by chunk when streaming
+    #[derive(Debug)]
+    struct Shout;
+
+    fn shout(value: Value) -> Value {
+        match value {
+            Value::String(text) => json!(text.to_uppercase()),
+            other => other,
+        }
+    }
+
+    #[async_trait]
+    impl ToolMiddleware for Shout {
+        async fn call(
+            &self,
+            _tool: &dyn ToolT,
+            args: Value,
+            next: Next<'_>,
+        ) -> Result<Value, ToolCallError> {
+            next.run(args).await.map(shout)
+        }
+
+        fn call_stream<'a>(
+            &'a self,
+            _tool: &'a dyn ToolT,
+            args: Value,
+            next: Next<'a>,
+        ) -> Option<ToolStream<'a>> {
+            use futures::StreamExt;
+            let stream = next.run_stream(args)?;
+            Some(Box::pin(stream.map(|chunk| chunk.map(shout))))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_calls_run_through_the_middleware() {
+        use futures::StreamExt;
+
+        let chain: Arc<[Arc<dyn ToolMiddleware>]> = Arc::new([Arc::new(Shout) as _]);
+        let tool = MiddlewareTool::wrap(&chain, Box::new(Tail));
+        let chunks: Vec<Value> = tool
+            .execute_stream(json!({}))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(chunks, vec![json!("STARTED\n"), json!("READY\n")]);
+
+        // Tools that do not stream run through `execute`
+        let tool = MiddlewareTool::wrap(&chain, Box::new(Lookup));
+        assert!(tool.execute_stream(json!({"user": "ada"})).is_none());
+
+        // So do streaming tools behind middleware that does not stream
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let chain: Arc<[Arc<dyn ToolMiddleware>]> =
+            Arc::new([Arc::new(Shout) as _, Arc::new(Normalize(seen.clone()))]);
+        let tool = MiddlewareTool::wrap(&chain, Box::new(Tail));
+        assert!(tool.execute_stream(json!({})).is_none());
+        assert_eq!(
+            tool.execute(json!({})).await.unwrap(),
+            json!("STARTED\nREADY\n")
+        );
+    }
+}
@@ -3,9 +3,60 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+mod cache;
+#[cfg(not(target_arch = "wasm32"))]
+mod credentials;
+mod error;
+mod group;
+#[cfg(not(target_arch = "wasm32"))]
+mod human;
+mod middleware;
+mod pipeline;
+mod progress;
+#[cfg(not(target_arch = "wasm32"))]
+mod recording;
+mod registry;
+mod retry;
 mod runtime;
+mod selector;
 use async_trait::async_trait;
-pub use runtime::ToolRuntime;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use audit::AuditedTool;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use audit::SqliteAuditSink;
+#[cfg(not(target_arch = "wasm32"))]
+pub use audit::{AuditError, AuditSink, FileAuditSink, ToolAuditRecord};
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::{canonicalize, ToolCacheScope, ToolResultCache};
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use credentials::CredentialScopedTool;
+#[cfg(not(target_arch = "wasm32"))]
+pub use credentials::{
+    credential, CredentialError, CredentialProvider, EnvCredentials, Secret, StaticCredentials,
+};
+pub use error::ToolError;
+pub use group::{NamespacedTool, ToolGroups};
+#[cfg(not(target_arch = "wasm32"))]
+pub use human::{AskHumanTool, HumanInput, HumanInputError, HumanQuestion};
+pub(crate) use middleware::MiddlewareTool;
+pub use middleware::{Next, ToolMiddleware};
+pub use pipeline::ToolPipeline;
+pub(crate) use progress::{observe as observe_signals, ToolSignal};
+pub use progress::{report_progress, ToolProgress};
+#[cfg(not(target_arch = "wasm32"))]
+pub use recording::{RecordedCall, RecordedOutcome, ToolRecorder, ToolReplayer};
+pub use registry::{RuntimeToolRegistry, ToolRegistry, ToolRegistryError};
+pub use retry::ToolRetryPolicy;
+pub(crate) use runtime::buffer_chunks;
+pub use runtime::{ToolRuntime, ToolStream};
+pub use selector::{
+    select_llm_tools, EmbeddingToolSelector, LLMToolSelector, RerankToolSelector, ToolSelector,
+    ToolSelectorError,
+};
 
 #[cfg(feature = "wasmtime")]
 pub use runtime::{WasmRuntime, WasmRuntimeError};
@@ -25,6 +76,12 @@ pub enum ToolCallError {
 
     #[error("Serde Error {0}")]
     SerdeError(#[from] serde_json::Error),
+
+    #[error("Tool timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error(transparent)]
+    Tool(#[from] ToolError),
 }
 
 pub trait ToolT: Send + Sync + Debug + ToolRuntime {
@@ -34,6 +91,31 @@ pub trait ToolT: Send + Sync + Debug + ToolRuntime {
     fn description(&self) -> &'static str;
     /// Return a description of the expected arguments.
     fn args_schema(&self) -> Value;
+    /// How long results of this tool may be reused for identical arguments.
+    /// `None` (the default) disables caching for the tool.
+    fn cache_ttl(&self) -> Option<Duration> {
+        None
+    }
+    /// Longest a single call may run before it is cancelled. `None` (the
+    /// default) falls back to the executor's `tool_timeout`.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+    /// Whether repeating a call has no effect beyond the first one. Only
+    /// idempotent tools are retried.
+    fn idempotent(&self) -> bool {
+        false
+    }
+    /// How failed calls are retried; `None` (the default) never retries.
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        None
+    }
+    /// Group the tool belongs to, such as `github` or `fs`, so whole groups
+    /// can be turned on or off per task. `None` (the default) keeps the
+    /// tool available in every task.
+    fn namespace(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 pub trait ToolInputT {
@@ -59,6 +141,10 @@ impl ToolRuntime for SharedTool {
     async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
         self.inner.execute(args).await
     }
+
+    fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+        self.inner.execute_stream(args)
+    }
 }
 
 impl ToolT for SharedTool {
@@ -73,6 +159,26 @@ impl ToolT for SharedTool {
     fn args_schema(&self) -> Value {
         self.inner.args_schema()
     }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.inner.cache_ttl()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.inner.namespace()
+    }
 }
 
 /// Helper function to convert Vec<Arc<dyn ToolT>> to Vec<Box<dyn ToolT>>
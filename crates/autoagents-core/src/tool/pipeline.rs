@@ -0,0 +1,228 @@
+use super::{ToolCallError, ToolRuntime, ToolT};
+use crate::utils::intern;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fmt;
+
+/// Turns the output of one step, and the pipeline's own arguments, into the
+/// arguments of the next step
+type StepMapping = Box<dyn Fn(Value, &Value) -> Result<Value, ToolCallError> + Send + Sync>;
+
+struct Step {
+    tool: Box<dyn ToolT>,
+    /// `None` for the first step, which gets the pipeline's arguments as they are
+    map: Option<StepMapping>,
+}
+
+/// Several tools run one after the other, offered to the LLM as a single
+/// tool so a fixed sequence of calls costs one round trip.
+///
+/// The first tool gets the pipeline's arguments and, by default, provides
+/// its schema. Every later tool gets the previous output passed through its
+/// mapping closure; the last output is the pipeline's result.
+///
+/// ```ignore
+/// let pipeline = ToolPipeline::new("fetch_summary", "Fetch a page and summarize it", Box::new(Fetch))
+///     .then(Box::new(Summarize), |page, _args| Ok(json!({ "text": page["body"] })));
+/// ```
+pub struct ToolPipeline {
+    name: &'static str,
+    description: &'static str,
+    args_schema: Option<Value>,
+    steps: Vec<Step>,
+}
+
+impl ToolPipeline {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        first: Box<dyn ToolT>,
+    ) -> Self {
+        let name: String = name.into();
+        let description: String = description.into();
+        Self {
+            name: intern(&name),
+            description: intern(&description),
+            args_schema: None,
+            steps: vec![Step {
+                tool: first,
+                map: None,
+            }],
+        }
+    }
+
+    /// Run `tool` next, with the arguments `map` builds from the previous
+    /// output and the pipeline's arguments
+    pub fn then<F>(mut self, tool: Box<dyn ToolT>, map: F) -> Self
+    where
+        F: Fn(Value, &Value) -> Result<Value, ToolCallError> + Send + Sync + 'static,
+    {
+        self.steps.push(Step {
+            tool,
+            map: Some(Box::new(map)),
+        });
+        self
+    }
+
+    /// Offer `schema` to the LLM instead of the first tool's
+    pub fn with_args_schema(mut self, schema: Value) -> Self {
+        self.args_schema = Some(schema);
+        self
+    }
+}
+
+impl fmt::Debug for ToolPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<&str> = self.steps.iter().map(|step| step.tool.name()).collect();
+        f.debug_struct("ToolPipeline")
+            .field("name", &self.name)
+            .field("steps", &steps)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for ToolPipeline {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let mut output = Value::Null;
+        for (index, step) in self.steps.iter().enumerate() {
+            let step_args = match &step.map {
+                Some(map) => map(output, &args),
+                None => Ok(args.clone()),
+            };
+            output = match step_args {
+                Ok(step_args) => step.tool.execute(step_args).await,
+                Err(e) => Err(e),
+            }
+            .map_err(|e| {
+                ToolCallError::RuntimeError(
+                    format!(
+                        "Step {} ('{}') of pipeline '{}' failed: {e}",
+                        index + 1,
+                        step.tool.name(),
+                        self.name
+                    )
+                    .into(),
+                )
+            })?;
+        }
+        Ok(output)
+    }
+}
+
+impl ToolT for ToolPipeline {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn args_schema(&self) -> Value {
+        match &self.args_schema {
+            Some(schema) => schema.clone(),
+            None => self.steps[0].tool.args_schema(),
+        }
+    }
+
+    /// Repeating the pipeline is harmless only if repeating every step is
+    fn idempotent(&self) -> bool {
+        self.steps.iter().all(|step| step.tool.idempotent())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct Search;
+
+    #[async_trait]
+    impl ToolRuntime for Search {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            let query = args["query"].as_str().unwrap_or_default();
+            Ok(json!({"hits": [format!("{query}-1"), format!("{query}-2")]}))
+        }
+    }
+
+    impl ToolT for Search {
+        fn name(&self) -> &'static str {
+            "search"
+        }
+
+        fn description(&self) -> &'static str {
+            "searches documents"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object", "properties": {"query": {"type": "string"}}})
+        }
+
+        fn idempotent(&self) -> bool {
+            true
+        }
+    }
+
+    #[derive(Debug)]
+    struct Fetch;
+
+    #[async_trait]
+    impl ToolRuntime for Fetch {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            match args["id"].as_str() {
+                Some(id) => Ok(json!(format!("contents of {id}, {}", args["format"]))),
+                None => Err(ToolCallError::RuntimeError("missing id".into())),
+            }
+        }
+    }
+
+    impl ToolT for Fetch {
+        fn name(&self) -> &'static str {
+            "fetch"
+        }
+
+        fn description(&self) -> &'static str {
+            "fetches a document"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    fn pipeline() -> ToolPipeline {
+        ToolPipeline::new("search_and_fetch", "Fetch the best match", Box::new(Search))
+            .then(Box::new(Fetch), |found, args| {
+                Ok(json!({"id": found["hits"][0], "format": args["format"]}))
+            })
+    }
+
+    #[tokio::test]
+    async fn test_outputs_feed_the_next_step() {
+        let pipeline = pipeline();
+        assert_eq!(pipeline.name(), "search_and_fetch");
+        assert_eq!(pipeline.args_schema(), Search.args_schema());
+        assert!(!pipeline.idempotent());
+
+        let result = pipeline
+            .execute(json!({"query": "rust", "format": "md"}))
+            .await
+            .unwrap();
+        assert_eq!(result, json!("contents of rust-1, \"md\""));
+    }
+
+    #[tokio::test]
+    async fn test_failures_name_the_step() {
+        let pipeline = pipeline().then(Box::new(Fetch), |_, _| Ok(json!({})));
+        let error = pipeline
+            .execute(json!({"query": "rust"}))
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Step 3 ('fetch') of pipeline 'search_and_fetch' failed"));
+    }
+}
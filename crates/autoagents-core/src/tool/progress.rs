@@ -0,0 +1,138 @@
+//! Progress updates from tools that take a while to run.
+//!
+//! Tools call [`report_progress`] from inside `execute`; the executor running
+//! the call forwards each update as it arrives, so a UI can show how far a
+//! long call has got instead of looking frozen until it returns.
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::HumanQuestion;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+#[cfg(not(target_arch = "wasm32"))]
+tokio::task_local! {
+    static REPORTER: tokio::sync::mpsc::UnboundedSender<ToolSignal>;
+}
+
+/// Something a running tool tells the executor about before it returns
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ToolSignal {
+    Progress(ToolProgress),
+    /// The tool is waiting for a person to answer
+    #[cfg(not(target_arch = "wasm32"))]
+    Question(HumanQuestion),
+}
+
+/// How far a running tool call has got
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolProgress {
+    /// Share of the work done, from `0.0` to `100.0`, when it is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f32>,
+    /// What the tool is currently doing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ToolProgress {
+    pub fn percent(percent: f32) -> Self {
+        Self {
+            percent: Some(percent.clamp(0.0, 100.0)),
+            message: None,
+        }
+    }
+
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            percent: None,
+            message: Some(message.into()),
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// Report progress of the tool call running on the current task.
+///
+/// Does nothing outside of a tool call, or from tasks the tool spawned itself.
+pub fn report_progress(progress: ToolProgress) {
+    signal(ToolSignal::Progress(progress));
+}
+
+/// Pass `signal` to the executor running the current tool call, dropping it
+/// outside of one.
+pub(crate) fn signal(signal: ToolSignal) {
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = REPORTER.try_with(|reporter| reporter.send(signal));
+    // Signals are dropped on wasm, where there is no task-local storage
+    #[cfg(target_arch = "wasm32")]
+    let _ = signal;
+}
+
+/// Drive `fut`, passing every signal sent while it runs to `on_signal` as it
+/// arrives.
+pub(crate) async fn observe<T, F, Fut>(fut: impl Future<Output = T>, mut on_signal: F) -> T
+where
+    F: FnMut(ToolSignal) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (reporter, mut updates) = tokio::sync::mpsc::unbounded_channel();
+        // The reporter is dropped with the scope, which ends the forwarding loop
+        let run = REPORTER.scope(reporter, fut);
+        let forward = async {
+            while let Some(signal) = updates.recv().await {
+                on_signal(signal).await;
+            }
+        };
+        let (output, ()) = futures::join!(run, forward);
+        output
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = &mut on_signal;
+        fut.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_updates_reach_the_observer_in_order() {
+        let mut seen = Vec::new();
+        let output = observe(
+            async {
+                report_progress(ToolProgress::percent(50.0).with_message("halfway"));
+                report_progress(ToolProgress::percent(150.0));
+                "done"
+            },
+            |signal| {
+                if let ToolSignal::Progress(progress) = signal {
+                    seen.push(progress);
+                }
+                async {}
+            },
+        )
+        .await;
+
+        assert_eq!(output, "done");
+        assert_eq!(
+            seen,
+            vec![
+                ToolProgress::percent(50.0).with_message("halfway"),
+                ToolProgress::percent(100.0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reporting_outside_a_call_is_ignored() {
+        report_progress(ToolProgress::message("nobody is listening"));
+    }
+}
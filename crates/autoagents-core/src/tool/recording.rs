@@ -0,0 +1,319 @@
+//! Record tool calls to a fixture file and replay them in tests.
+//!
+//! Run an agent once with its tools wrapped by a [`ToolRecorder`] to capture
+//! what the real services returned, then wrap the same tools with a
+//! [`ToolReplayer`] so integration tests get those responses back without
+//! calling out.
+
+use super::{canonicalize, ToolCallError, ToolRetryPolicy, ToolRuntime, ToolT};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// One tool call and what it returned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub tool: String,
+    pub args: Value,
+    pub outcome: RecordedOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedOutcome {
+    Result(Value),
+    Error(String),
+}
+
+/// Captures the calls of the tools it wraps, for [`Self::save`] to write
+/// out as a fixture. Clones share the same recording.
+#[derive(Debug, Clone)]
+pub struct ToolRecorder {
+    path: PathBuf,
+    calls: Arc<Mutex<Vec<RecordedCall>>>,
+}
+
+impl ToolRecorder {
+    /// Record into the fixture at `path`, which is written by [`Self::save`]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            calls: Arc::default(),
+        }
+    }
+
+    pub fn record(&self, tool: Box<dyn ToolT>) -> Box<dyn ToolT> {
+        Box::new(RecordingTool {
+            inner: tool,
+            calls: Arc::clone(&self.calls),
+        })
+    }
+
+    /// Calls recorded so far, in the order they finished
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// Write the recorded calls to the fixture file, creating its directory
+    pub fn save(&self) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.calls())?;
+        std::fs::write(&self.path, json)
+    }
+}
+
+/// Responses not replayed yet, by tool name and canonical arguments
+type Outcomes = Arc<Mutex<HashMap<(String, String), VecDeque<RecordedOutcome>>>>;
+
+/// Answers calls of the tools it wraps from a recorded fixture, without
+/// running them.
+///
+/// Calls are matched by tool name and arguments, regardless of object key
+/// order. Identical calls get the recorded responses back in the order they
+/// were recorded; a call with nothing left to replay fails.
+#[derive(Debug, Clone, Default)]
+pub struct ToolReplayer {
+    outcomes: Outcomes,
+}
+
+impl ToolReplayer {
+    /// Replay the fixture written by [`ToolRecorder::save`] at `path`
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let calls: Vec<RecordedCall> = serde_json::from_str(&json)?;
+        Ok(Self::from_calls(calls))
+    }
+
+    pub fn from_calls(calls: Vec<RecordedCall>) -> Self {
+        let mut outcomes: HashMap<_, VecDeque<_>> = HashMap::new();
+        for call in calls {
+            outcomes
+                .entry((call.tool, canonicalize(&call.args)))
+                .or_default()
+                .push_back(call.outcome);
+        }
+        Self {
+            outcomes: Arc::new(Mutex::new(outcomes)),
+        }
+    }
+
+    /// Wrap `tool` so calls are answered from the fixture. The tool itself
+    /// only provides its name, description and schema.
+    pub fn replay(&self, tool: Box<dyn ToolT>) -> Box<dyn ToolT> {
+        Box::new(ReplayingTool {
+            inner: tool,
+            outcomes: Arc::clone(&self.outcomes),
+        })
+    }
+
+    /// Number of recorded responses not replayed yet, `0` once a test made
+    /// every call that was recorded
+    pub fn remaining(&self) -> usize {
+        self.outcomes
+            .lock()
+            .map(|o| o.values().map(VecDeque::len).sum())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+struct RecordingTool {
+    inner: Box<dyn ToolT>,
+    calls: Arc<Mutex<Vec<RecordedCall>>>,
+}
+
+#[async_trait]
+impl ToolRuntime for RecordingTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let result = self.inner.execute(args.clone()).await;
+        let outcome = match &result {
+            Ok(value) => RecordedOutcome::Result(value.clone()),
+            Err(e) => RecordedOutcome::Error(e.to_string()),
+        };
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(RecordedCall {
+                tool: self.inner.name().to_string(),
+                args,
+                outcome,
+            });
+        }
+        result
+    }
+}
+
+#[derive(Debug)]
+struct ReplayingTool {
+    inner: Box<dyn ToolT>,
+    outcomes: Outcomes,
+}
+
+#[async_trait]
+impl ToolRuntime for ReplayingTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let key = (self.inner.name().to_string(), canonicalize(&args));
+        let outcome = self
+            .outcomes
+            .lock()
+            .ok()
+            .and_then(|mut outcomes| outcomes.get_mut(&key)?.pop_front());
+        match outcome {
+            Some(RecordedOutcome::Result(value)) => Ok(value),
+            Some(RecordedOutcome::Error(error)) => Err(ToolCallError::RuntimeError(error.into())),
+            None => Err(ToolCallError::RuntimeError(
+                format!(
+                    "No recorded response for tool '{}' with arguments {args}",
+                    self.inner.name()
+                )
+                .into(),
+            )),
+        }
+    }
+}
+
+impl ToolT for RecordingTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.inner.cache_ttl()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.inner.namespace()
+    }
+}
+
+impl ToolT for ReplayingTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.inner.cache_ttl()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.inner.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<ToolRetryPolicy> {
+        self.inner.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.inner.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, Default)]
+    struct Weather {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ToolRuntime for Weather {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            match args["city"].as_str() {
+                Some("Atlantis") => Err(ToolCallError::RuntimeError("unknown city".into())),
+                _ => Ok(json!({"city": args["city"], "reading": n})),
+            }
+        }
+    }
+
+    impl ToolT for Weather {
+        fn name(&self) -> &'static str {
+            "weather"
+        }
+
+        fn description(&self) -> &'static str {
+            "current weather"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorded_calls_replay_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures/weather.json");
+
+        let recorder = ToolRecorder::new(&path);
+        let tool = recorder.record(Box::<Weather>::default());
+        let first = tool.execute(json!({"city": "Oslo"})).await.unwrap();
+        let second = tool.execute(json!({"city": "Oslo"})).await.unwrap();
+        assert!(tool.execute(json!({"city": "Atlantis"})).await.is_err());
+        recorder.save().unwrap();
+
+        let replayer = ToolReplayer::load(&path).unwrap();
+        let weather = Arc::new(Weather::default());
+        let tool = replayer.replay(Box::new(crate::tool::SharedTool::new(weather.clone())));
+        assert_eq!(tool.execute(json!({"city": "Oslo"})).await.unwrap(), first);
+        assert_eq!(tool.execute(json!({"city": "Oslo"})).await.unwrap(), second);
+        let error = tool.execute(json!({"city": "Atlantis"})).await.unwrap_err();
+        assert!(error.to_string().contains("unknown city"));
+        assert_eq!(replayer.remaining(), 0);
+        assert_eq!(weather.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unrecorded_calls_fail() {
+        let replayer = ToolReplayer::from_calls(vec![RecordedCall {
+            tool: "weather".into(),
+            args: json!({"city": "Oslo", "unit": "C"}),
+            outcome: RecordedOutcome::Result(json!("cold")),
+        }]);
+        let tool = replayer.replay(Box::<Weather>::default());
+
+        assert!(tool.execute(json!({"city": "Lima"})).await.is_err());
+        let reordered = json!({"unit": "C", "city": "Oslo"});
+        assert_eq!(tool.execute(reordered).await.unwrap(), "cold");
+    }
+}
@@ -0,0 +1,344 @@
+use super::{shared_tools_to_boxes, SharedTool, ToolT};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ToolRegistryError {
+    #[error("Tool '{0}' is not registered")]
+    NotFound(String),
+
+    #[error("Tool '{0}' is already registered")]
+    AlreadyRegistered(String),
+
+    #[error("Failed to create tool '{name}': {reason}")]
+    Factory { name: String, reason: String },
+}
+
+type ToolFactory = dyn Fn(&Value) -> Result<Arc<dyn ToolT>, String> + Send + Sync;
+
+/// Resolves tools by name, for agents that are described in configuration
+/// rather than code.
+///
+/// A name either maps to a ready-made tool instance shared by every agent,
+/// or to a factory that builds a fresh tool from per-agent options.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    factories: BTreeMap<String, Arc<ToolFactory>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool instance under its own [`ToolT::name`].
+    pub fn register(&mut self, tool: impl ToolT + 'static) -> &mut Self {
+        self.register_shared(Arc::new(tool))
+    }
+
+    /// Register an already shared tool under its own [`ToolT::name`].
+    pub fn register_shared(&mut self, tool: Arc<dyn ToolT>) -> &mut Self {
+        let name = tool.name().to_string();
+        self.factories
+            .insert(name, Arc::new(move |_: &Value| Ok(Arc::clone(&tool))));
+        self
+    }
+
+    /// Register a factory that builds the tool from the options given in the
+    /// agent definition (`Value::Null` when none are given).
+    pub fn register_factory<F>(&mut self, name: impl Into<String>, factory: F) -> &mut Self
+    where
+        F: Fn(&Value) -> Result<Arc<dyn ToolT>, String> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Arc::new(factory));
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Registered names, in sorted order
+    pub fn names(&self) -> Vec<&str> {
+        self.factories.keys().map(String::as_str).collect()
+    }
+
+    pub fn resolve(
+        &self,
+        name: &str,
+        options: &Value,
+    ) -> Result<Arc<dyn ToolT>, ToolRegistryError> {
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or_else(|| ToolRegistryError::NotFound(name.to_string()))?;
+        factory(options).map_err(|reason| ToolRegistryError::Factory {
+            name: name.to_string(),
+            reason,
+        })
+    }
+
+    /// Resolve a tool as a boxed [`ToolT`] ready to hand to an agent.
+    pub fn resolve_boxed(
+        &self,
+        name: &str,
+        options: &Value,
+    ) -> Result<Box<dyn ToolT>, ToolRegistryError> {
+        self.resolve(name, options)
+            .map(|tool| Box::new(SharedTool::new(tool)) as Box<dyn ToolT>)
+    }
+}
+
+impl Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.names())
+            .finish()
+    }
+}
+
+/// The tools of a built agent, which can be changed while it runs, e.g.
+/// once an MCP server connects.
+///
+/// Clones share the same tools. Each run works on the tools present when it
+/// started, so changes apply from the next run on.
+#[derive(Clone, Default)]
+pub struct RuntimeToolRegistry {
+    tools: Arc<RwLock<Vec<Arc<dyn ToolT>>>>,
+}
+
+impl RuntimeToolRegistry {
+    pub fn new(tools: Vec<Box<dyn ToolT>>) -> Self {
+        Self {
+            tools: Arc::new(RwLock::new(tools.into_iter().map(Arc::from).collect())),
+        }
+    }
+
+    /// Add a tool, failing if one with the same name is present
+    pub fn add(&self, tool: Arc<dyn ToolT>) -> Result<(), ToolRegistryError> {
+        let mut tools = self.tools.write().unwrap_or_else(|e| e.into_inner());
+        if tools.iter().any(|existing| existing.name() == tool.name()) {
+            return Err(ToolRegistryError::AlreadyRegistered(
+                tool.name().to_string(),
+            ));
+        }
+        tools.push(tool);
+        Ok(())
+    }
+
+    /// Add `tool`, or swap it in for the tool of the same name, keeping its
+    /// position. Returns the replaced tool.
+    pub fn replace(&self, tool: Arc<dyn ToolT>) -> Option<Arc<dyn ToolT>> {
+        let mut tools = self.tools.write().unwrap_or_else(|e| e.into_inner());
+        match tools
+            .iter_mut()
+            .find(|existing| existing.name() == tool.name())
+        {
+            Some(existing) => Some(std::mem::replace(existing, tool)),
+            None => {
+                tools.push(tool);
+                None
+            }
+        }
+    }
+
+    /// Remove the tool called `name`, returning it
+    pub fn remove(&self, name: &str) -> Option<Arc<dyn ToolT>> {
+        let mut tools = self.tools.write().unwrap_or_else(|e| e.into_inner());
+        let position = tools.iter().position(|tool| tool.name() == name)?;
+        Some(tools.remove(position))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn ToolT>> {
+        self.read().iter().find(|tool| tool.name() == name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Tool names, in the order the tools were added
+    pub fn names(&self) -> Vec<&'static str> {
+        self.read().iter().map(|tool| tool.name()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// The current tools, boxed for a run
+    pub fn snapshot(&self) -> Vec<Box<dyn ToolT>> {
+        shared_tools_to_boxes(&self.read())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<Arc<dyn ToolT>>> {
+        self.tools.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Debug for RuntimeToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RuntimeToolRegistry")
+            .field("tools", &self.names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::{ToolCallError, ToolRuntime};
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct Greeter {
+        greeting: String,
+    }
+
+    #[async_trait]
+    impl ToolRuntime for Greeter {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!(format!(
+                "{} {}",
+                self.greeting,
+                args["name"].as_str().unwrap_or("?")
+            )))
+        }
+    }
+
+    impl ToolT for Greeter {
+        fn name(&self) -> &'static str {
+            "greeter"
+        }
+
+        fn description(&self) -> &'static str {
+            "greets"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_instance_is_shared() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Greeter {
+            greeting: "hi".into(),
+        });
+
+        assert!(registry.contains("greeter"));
+        let a = registry.resolve("greeter", &Value::Null).unwrap();
+        let b = registry
+            .resolve("greeter", &json!({"ignored": true}))
+            .unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+
+        let boxed = registry.resolve_boxed("greeter", &Value::Null).unwrap();
+        assert_eq!(
+            boxed.execute(json!({"name": "Ada"})).await.unwrap(),
+            "hi Ada"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_factory_receives_options() {
+        let mut registry = ToolRegistry::new();
+        registry.register_factory("greeter", |options| {
+            let greeting = options["greeting"]
+                .as_str()
+                .ok_or("missing `greeting` option")?;
+            Ok(Arc::new(Greeter {
+                greeting: greeting.to_string(),
+            }))
+        });
+
+        let tool = registry
+            .resolve("greeter", &json!({"greeting": "hello"}))
+            .unwrap();
+        assert_eq!(
+            tool.execute(json!({"name": "Bo"})).await.unwrap(),
+            "hello Bo"
+        );
+
+        let err = registry.resolve("greeter", &Value::Null).unwrap_err();
+        assert!(matches!(err, ToolRegistryError::Factory { .. }));
+    }
+
+    #[test]
+    fn test_unknown_tool() {
+        let registry = ToolRegistry::new();
+        assert!(matches!(
+            registry.resolve("missing", &Value::Null),
+            Err(ToolRegistryError::NotFound(name)) if name == "missing"
+        ));
+    }
+
+    #[derive(Debug)]
+    struct Ping;
+
+    #[async_trait]
+    impl ToolRuntime for Ping {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!("pong"))
+        }
+    }
+
+    impl ToolT for Ping {
+        fn name(&self) -> &'static str {
+            "ping"
+        }
+
+        fn description(&self) -> &'static str {
+            "pings"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    fn greeter(greeting: &str) -> Arc<dyn ToolT> {
+        Arc::new(Greeter {
+            greeting: greeting.into(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_runtime_registry_add_replace_remove() {
+        let registry = RuntimeToolRegistry::new(vec![Box::new(Ping)]);
+        let shared = registry.clone();
+
+        registry.add(greeter("hi")).unwrap();
+        assert!(matches!(
+            registry.add(greeter("hello")),
+            Err(ToolRegistryError::AlreadyRegistered(name)) if name == "greeter"
+        ));
+        assert_eq!(shared.names(), vec!["ping", "greeter"]);
+
+        let snapshot = shared.snapshot();
+        assert!(registry.replace(greeter("hello")).is_some());
+        assert_eq!(
+            snapshot[1].execute(json!({"name": "Ada"})).await.unwrap(),
+            "hi Ada"
+        );
+        assert_eq!(
+            shared.snapshot()[1]
+                .execute(json!({"name": "Ada"}))
+                .await
+                .unwrap(),
+            "hello Ada"
+        );
+
+        assert!(registry.remove("ping").is_some());
+        assert!(registry.remove("ping").is_none());
+        assert_eq!(shared.names(), vec!["greeter"]);
+    }
+}
@@ -0,0 +1,92 @@
+use super::ToolCallError;
+use std::time::Duration;
+
+/// How often a failing tool call is retried and how long to wait in between.
+///
+/// Only applies to tools that report themselves as
+/// [`idempotent`](super::ToolT::idempotent); calls with side effects are
+/// never repeated. The wait doubles after every attempt, up to
+/// `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolRetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Wait before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the wait between retries
+    pub max_backoff: Duration,
+}
+
+impl Default for ToolRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ToolRetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Wait before retry number `retry`, counting from 0
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+
+    /// Whether `error` may go away on another attempt. Argument errors
+    /// will not, so they are returned straight away, and errors the tool
+    /// classified itself are retried only when transient.
+    pub fn is_retryable(error: &ToolCallError) -> bool {
+        match error {
+            ToolCallError::SerdeError(_) => false,
+            ToolCallError::Tool(error) => error.is_retryable(),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = ToolRetryPolicy::new(5)
+            .with_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(350));
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_only_transient_tool_errors_are_retried() {
+        use crate::tool::ToolError;
+
+        let transient = ToolCallError::from(ToolError::Transient("busy".into()));
+        let fatal = ToolCallError::from(ToolError::Fatal("gone".into()));
+        assert!(ToolRetryPolicy::is_retryable(&transient));
+        assert!(!ToolRetryPolicy::is_retryable(&fatal));
+    }
+}
@@ -1,6 +1,8 @@
 use super::ToolCallError;
 use async_trait::async_trait;
+use futures::Stream;
 use std::fmt::Debug;
+use std::pin::Pin;
 
 #[cfg(feature = "wasmtime")]
 #[cfg(not(target_arch = "wasm32"))]
@@ -10,7 +12,38 @@ mod wasm;
 #[cfg(not(target_arch = "wasm32"))]
 pub use wasm::{WasmRuntime, WasmRuntimeError};
 
+/// Output of a streaming tool, yielded chunk by chunk
+pub type ToolStream<'a> =
+    Pin<Box<dyn Stream<Item = Result<serde_json::Value, ToolCallError>> + Send + 'a>>;
+
+/// Join string chunks of a [`ToolStream`] into one string, collecting
+/// anything else into an array
+pub(crate) fn buffer_chunks(chunks: Vec<serde_json::Value>) -> serde_json::Value {
+    if chunks.iter().all(serde_json::Value::is_string) {
+        serde_json::Value::String(
+            chunks
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect(),
+        )
+    } else {
+        serde_json::Value::Array(chunks)
+    }
+}
+
 #[async_trait]
 pub trait ToolRuntime: Send + Sync + Debug {
     async fn execute(&self, args: serde_json::Value) -> Result<serde_json::Value, ToolCallError>;
+
+    /// Run the tool, yielding output as it is produced, for long running
+    /// tools such as shell commands or log tailing. Executors forward each
+    /// chunk as it arrives and hand the buffered output to the LLM: string
+    /// chunks are concatenated, anything else is collected into an array.
+    ///
+    /// The default returns `None` and the tool is run with `execute`, which
+    /// streaming tools still implement for callers that need the whole
+    /// result at once, such as tool pipelines.
+    fn execute_stream(&self, _args: serde_json::Value) -> Option<ToolStream<'_>> {
+        None
+    }
 }
@@ -0,0 +1,484 @@
+use super::{to_llm_tool, ToolT};
+use crate::utils::cosine_similarity;
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType, Tool};
+use autoagents_llm::embedding::EmbeddingProvider;
+use autoagents_llm::error::LLMError;
+use autoagents_llm::rerank::Reranker;
+use autoagents_llm::LLMProvider;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::Mutex;
+
+#[cfg(target_arch = "wasm32")]
+use futures::lock::Mutex;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ToolSelectorError {
+    #[error("LLM error: {0}")]
+    LLMError(#[from] LLMError),
+
+    #[error("Invalid selector response: {0}")]
+    InvalidResponse(String),
+}
+
+/// Picks the subset of an agent's tools that is sent to the LLM on a turn.
+///
+/// Agents with many tools can register a selector so that only the schemas
+/// relevant to the current request are included, instead of all of them.
+#[async_trait]
+pub trait ToolSelector: Send + Sync + Debug {
+    /// Return the indices into `tools` that should be exposed for `query`,
+    /// most relevant first.
+    async fn select(
+        &self,
+        query: &str,
+        tools: &[Box<dyn ToolT>],
+    ) -> Result<Vec<usize>, ToolSelectorError>;
+}
+
+/// Serialize the tools to send for a turn, consulting `selector` if present.
+///
+/// The query is the most recent user message. Selection errors are logged
+/// and fall back to sending every tool so a flaky selector never blocks a run.
+pub async fn select_llm_tools(
+    selector: Option<&Arc<dyn ToolSelector>>,
+    messages: &[ChatMessage],
+    tools: &[Box<dyn ToolT>],
+) -> Vec<Tool> {
+    let Some(selector) = selector else {
+        return tools.iter().map(to_llm_tool).collect();
+    };
+
+    let query = messages
+        .iter()
+        .rev()
+        .find(|m| m.role == ChatRole::User && !m.content.is_empty())
+        .map(|m| m.content.as_str())
+        .unwrap_or_default();
+
+    match selector.select(query, tools).await {
+        Ok(indices) => indices
+            .into_iter()
+            .filter_map(|i| tools.get(i))
+            .map(to_llm_tool)
+            .collect(),
+        Err(e) => {
+            log::warn!("Tool selection failed, sending all tools: {e}");
+            tools.iter().map(to_llm_tool).collect()
+        }
+    }
+}
+
+fn tool_document(tool: &dyn ToolT) -> String {
+    format!("{}: {}", tool.name(), tool.description())
+}
+
+/// Ranks tools by cosine similarity between the query embedding and the
+/// embedding of each tool's name and description.
+///
+/// Tool embeddings are computed once and cached by name and description, so
+/// a tool replaced with a new description is embedded again.
+pub struct EmbeddingToolSelector {
+    embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    top_k: usize,
+    cache: Mutex<HashMap<String, Vec<f32>>>,
+}
+
+impl EmbeddingToolSelector {
+    pub fn new(embedder: Arc<dyn EmbeddingProvider + Send + Sync>, top_k: usize) -> Self {
+        Self {
+            embedder,
+            top_k,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Debug for EmbeddingToolSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingToolSelector")
+            .field("top_k", &self.top_k)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl ToolSelector for EmbeddingToolSelector {
+    async fn select(
+        &self,
+        query: &str,
+        tools: &[Box<dyn ToolT>],
+    ) -> Result<Vec<usize>, ToolSelectorError> {
+        if tools.len() <= self.top_k || query.is_empty() {
+            return Ok((0..tools.len()).collect());
+        }
+
+        let documents: Vec<String> = tools.iter().map(|t| tool_document(t.as_ref())).collect();
+        // The lock is not held across the embedder calls
+        let missing: Vec<String> = {
+            let cache = self.cache.lock().await;
+            documents
+                .iter()
+                .filter(|document| !cache.contains_key(*document))
+                .cloned()
+                .collect()
+        };
+        if !missing.is_empty() {
+            let embeddings = self.embedder.embed(missing.clone()).await?;
+            if embeddings.len() != missing.len() {
+                return Err(ToolSelectorError::InvalidResponse(format!(
+                    "expected {} embeddings, got {}",
+                    missing.len(),
+                    embeddings.len()
+                )));
+            }
+            self.cache
+                .lock()
+                .await
+                .extend(missing.into_iter().zip(embeddings));
+        }
+
+        let query_embedding = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ToolSelectorError::InvalidResponse("empty query embedding".into()))?;
+
+        let cache = self.cache.lock().await;
+        let mut scored: Vec<(usize, f32)> = documents
+            .iter()
+            .enumerate()
+            .map(|(i, document)| {
+                let score = cache
+                    .get(document)
+                    .map(|e| cosine_similarity(&query_embedding, e))
+                    .unwrap_or_default();
+                (i, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored
+            .into_iter()
+            .take(self.top_k)
+            .map(|(i, _)| i)
+            .collect())
+    }
+}
+
+/// Ranks tools by a [`Reranker`]'s score of each tool's name and description
+/// against the query.
+pub struct RerankToolSelector {
+    reranker: Arc<dyn Reranker>,
+    top_k: usize,
+}
+
+impl RerankToolSelector {
+    pub fn new(reranker: Arc<dyn Reranker>, top_k: usize) -> Self {
+        Self { reranker, top_k }
+    }
+}
+
+impl Debug for RerankToolSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RerankToolSelector")
+            .field("top_k", &self.top_k)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl ToolSelector for RerankToolSelector {
+    async fn select(
+        &self,
+        query: &str,
+        tools: &[Box<dyn ToolT>],
+    ) -> Result<Vec<usize>, ToolSelectorError> {
+        if tools.len() <= self.top_k || query.is_empty() {
+            return Ok((0..tools.len()).collect());
+        }
+
+        let documents: Vec<String> = tools.iter().map(|t| tool_document(t.as_ref())).collect();
+        let ranked = self
+            .reranker
+            .rerank(query, &documents, Some(self.top_k))
+            .await?;
+        Ok(ranked
+            .into_iter()
+            .map(|result| result.index)
+            .filter(|index| *index < tools.len())
+            .take(self.top_k)
+            .collect())
+    }
+}
+
+/// Asks an LLM to pick the tools relevant to the query.
+///
+/// The model is given the name and description of every tool and must reply
+/// with a JSON array of tool names.
+pub struct LLMToolSelector {
+    llm: Arc<dyn LLMProvider>,
+    top_k: usize,
+}
+
+impl LLMToolSelector {
+    pub fn new(llm: Arc<dyn LLMProvider>, top_k: usize) -> Self {
+        Self { llm, top_k }
+    }
+
+    fn build_prompt(&self, query: &str, tools: &[Box<dyn ToolT>]) -> String {
+        let catalog = tools
+            .iter()
+            .map(|t| format!("- {}", tool_document(t.as_ref())))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "Select at most {} tools that are relevant to the request below.\n\
+             Reply only with a JSON array of tool names, most relevant first.\n\n\
+             Tools:\n{catalog}\n\nRequest:\n{query}",
+            self.top_k
+        )
+    }
+}
+
+impl Debug for LLMToolSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LLMToolSelector")
+            .field("top_k", &self.top_k)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl ToolSelector for LLMToolSelector {
+    async fn select(
+        &self,
+        query: &str,
+        tools: &[Box<dyn ToolT>],
+    ) -> Result<Vec<usize>, ToolSelectorError> {
+        if tools.len() <= self.top_k || query.is_empty() {
+            return Ok((0..tools.len()).collect());
+        }
+
+        let message = ChatMessage {
+            role: ChatRole::User,
+            message_type: MessageType::Text,
+            content: self.build_prompt(query, tools),
+            reasoning: Vec::new(),
+        };
+        let response = self.llm.chat(&[message], None, None).await?;
+        let text = response.text().unwrap_or_default();
+
+        let start = text.find('[');
+        let end = text.rfind(']');
+        let names: Vec<String> = match (start, end) {
+            (Some(start), Some(end)) if start < end => serde_json::from_str(&text[start..=end])
+                .map_err(|e| ToolSelectorError::InvalidResponse(e.to_string()))?,
+            _ => return Err(ToolSelectorError::InvalidResponse(text)),
+        };
+
+        let mut selected = Vec::new();
+        for name in names {
+            if let Some(index) = tools.iter().position(|t| t.name() == name) {
+                if !selected.contains(&index) {
+                    selected.push(index);
+                }
+            }
+            if selected.len() == self.top_k {
+                break;
+            }
+        }
+        Ok(selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::{ToolCallError, ToolRuntime};
+    use async_trait::async_trait;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use serde_json::{json, Value};
+
+    #[derive(Debug)]
+    struct NamedTool(&'static str, &'static str);
+
+    impl ToolT for NamedTool {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn description(&self) -> &'static str {
+            self.1
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+    }
+
+    #[async_trait]
+    impl ToolRuntime for NamedTool {
+        async fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(Value::Null)
+        }
+    }
+
+    fn tools() -> Vec<Box<dyn ToolT>> {
+        vec![
+            Box::new(NamedTool("weather", "weather forecast")),
+            Box::new(NamedTool("math", "calculator arithmetic")),
+            Box::new(NamedTool("search", "web search")),
+        ]
+    }
+
+    /// Embeds text as keyword counts so similarity is predictable.
+    #[derive(Debug)]
+    struct KeywordEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for KeywordEmbedder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    ["weather", "math", "search"]
+                        .iter()
+                        .map(|k| text.matches(k).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    /// Scores tools by how many words of the query they contain.
+    struct KeywordReranker;
+
+    #[async_trait]
+    impl Reranker for KeywordReranker {
+        async fn rerank(
+            &self,
+            query: &str,
+            documents: &[String],
+            top_n: Option<usize>,
+        ) -> Result<Vec<autoagents_llm::rerank::RerankResult>, LLMError> {
+            let mut results: Vec<_> = documents
+                .iter()
+                .enumerate()
+                .map(|(index, document)| autoagents_llm::rerank::RerankResult {
+                    index,
+                    score: query
+                        .split_whitespace()
+                        .filter(|word| document.contains(word))
+                        .count() as f32,
+                })
+                .collect();
+            autoagents_llm::rerank::sort_results(&mut results);
+            results.truncate(top_n.unwrap_or(results.len()));
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedding_selector_ranks_by_similarity() {
+        let selector = EmbeddingToolSelector::new(Arc::new(KeywordEmbedder), 1);
+        let selected = selector
+            .select("what is the weather tomorrow", &tools())
+            .await
+            .unwrap();
+        assert_eq!(selected, vec![0]);
+    }
+
+    /// Records the texts passed to [`KeywordEmbedder`].
+    #[derive(Debug, Default)]
+    struct RecordingEmbedder(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl EmbeddingProvider for RecordingEmbedder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            self.0.lock().unwrap().extend(input.iter().cloned());
+            KeywordEmbedder.embed(input).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedding_selector_reembeds_changed_descriptions() {
+        let embedder = Arc::new(RecordingEmbedder::default());
+        let selector = EmbeddingToolSelector::new(embedder.clone(), 1);
+        selector.select("weather", &tools()).await.unwrap();
+        assert_eq!(embedder.0.lock().unwrap().len(), 4);
+        embedder.0.lock().unwrap().clear();
+
+        let mut replaced = tools();
+        replaced[2] = Box::new(NamedTool("search", "web search engine"));
+        selector.select("weather", &replaced).await.unwrap();
+        assert_eq!(
+            *embedder.0.lock().unwrap(),
+            ["search: web search engine", "weather"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_selector_returns_all_when_under_top_k() {
+        let selector = EmbeddingToolSelector::new(Arc::new(KeywordEmbedder), 5);
+        let selected = selector.select("math", &tools()).await.unwrap();
+        assert_eq!(selected, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_rerank_selector_keeps_top_scored() {
+        let selector = RerankToolSelector::new(Arc::new(KeywordReranker), 2);
+        let selected = selector
+            .select("search web calculator", &tools())
+            .await
+            .unwrap();
+        assert_eq!(selected, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_llm_selector_parses_names() {
+        let llm = Arc::new(ScriptedLLMProvider::new([
+            "Sure: [\"search\", \"unknown\", \"math\", \"weather\"]",
+        ]));
+        let selector = LLMToolSelector::new(llm, 2);
+        let selected = selector.select("find and add", &tools()).await.unwrap();
+        assert_eq!(selected, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_llm_selector_rejects_non_json() {
+        let selector = LLMToolSelector::new(Arc::new(ScriptedLLMProvider::new(["no idea"])), 1);
+        let err = selector.select("anything", &tools()).await.unwrap_err();
+        assert!(matches!(err, ToolSelectorError::InvalidResponse(_)));
+    }
+
+    #[tokio::test]
+    async fn test_select_llm_tools_falls_back_on_error() {
+        let selector: Arc<dyn ToolSelector> = Arc::new(LLMToolSelector::new(
+            Arc::new(ScriptedLLMProvider::new(["nope"])),
+            1,
+        ));
+        let messages = vec![ChatMessage::user().content("hi").build()];
+        let selected = select_llm_tools(Some(&selector), &messages, &tools()).await;
+        assert_eq!(selected.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_select_llm_tools_uses_latest_user_message() {
+        let selector: Arc<dyn ToolSelector> =
+            Arc::new(EmbeddingToolSelector::new(Arc::new(KeywordEmbedder), 1));
+        let messages = vec![
+            ChatMessage::user().content("weather please").build(),
+            ChatMessage::assistant().content("ok").build(),
+            ChatMessage::user().content("now some math").build(),
+        ];
+        let selected = select_llm_tools(Some(&selector), &messages, &tools()).await;
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].function.name, "math");
+    }
+}
@@ -35,6 +35,20 @@ pub(crate) fn receiver_into_stream<T: 'static + Send>(rx: Receiver<T>) -> BoxEve
     rx.boxed()
 }
 
+// -----------------------------
+// Embedding helpers
+// -----------------------------
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
 // Platform-specific spawn functions
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) fn spawn_future<F>(fut: F) -> tokio::task::JoinHandle<F::Output>
@@ -52,3 +66,42 @@ where
 {
     wasm_bindgen_futures::spawn_local(fut)
 }
+
+// -----------------------------
+// String interning
+// -----------------------------
+/// A `&'static str` equal to `text`, for names and descriptions only known
+/// at runtime.
+///
+/// Each distinct string is leaked once and shared by every later call, so
+/// rebuilding tools from the same definitions does not keep leaking memory.
+pub fn intern(text: &str) -> &'static str {
+    use std::collections::HashSet;
+    use std::sync::{Mutex, OnceLock};
+
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut interned = INTERNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(&existing) = interned.get(text) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(text.to_string().into_boxed_str());
+    interned.insert(leaked);
+    leaked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_leaks_each_string_once() {
+        // Separate allocations with the same text share one interned copy
+        let name = String::from("weather_lookup");
+        let first = intern(&name);
+        assert_eq!(first, "weather_lookup");
+        assert!(std::ptr::eq(first, intern(&name.clone())));
+    }
+}
@@ -1,13 +1,18 @@
 use strum::{Display, EnumString};
 use syn::{
     parse::{Parse, ParseStream},
-    Ident, LitStr, Result, Token, Type,
+    Ident, LitBool, LitInt, LitStr, Result, Token, Type,
 };
 
 pub(crate) struct ToolAttributes {
-    pub(crate) name: LitStr,
-    pub(crate) description: LitStr,
-    pub(crate) input: Type,
+    pub(crate) name: Option<LitStr>,
+    pub(crate) description: Option<LitStr>,
+    pub(crate) input: Option<Type>,
+    pub(crate) cache_ttl: Option<LitInt>,
+    pub(crate) timeout: Option<LitInt>,
+    pub(crate) idempotent: Option<LitBool>,
+    pub(crate) retries: Option<LitInt>,
+    pub(crate) namespace: Option<LitStr>,
 }
 
 #[derive(EnumString, Display)]
@@ -18,6 +23,16 @@ pub(crate) enum ToolAttributeKeys {
     Description,
     #[strum(serialize = "input")]
     Input,
+    #[strum(serialize = "cache_ttl")]
+    CacheTtl,
+    #[strum(serialize = "timeout")]
+    Timeout,
+    #[strum(serialize = "idempotent")]
+    Idempotent,
+    #[strum(serialize = "retries")]
+    Retries,
+    #[strum(serialize = "namespace")]
+    Namespace,
     Unknown(String),
 }
 
@@ -27,16 +42,36 @@ impl From<Ident> for ToolAttributeKeys {
             "name" => Self::Name,
             "description" => Self::Description,
             "input" => Self::Input,
+            "cache_ttl" => Self::CacheTtl,
+            "timeout" => Self::Timeout,
+            "idempotent" => Self::Idempotent,
+            "retries" => Self::Retries,
+            "namespace" => Self::Namespace,
             other => Self::Unknown(other.to_string()),
         }
     }
 }
 
+impl ToolAttributes {
+    /// Error for a key that has to be given, pointing at the attribute
+    pub(crate) fn missing(key: ToolAttributeKeys) -> syn::Error {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!("Missing attribute: {key}"),
+        )
+    }
+}
+
 impl Parse for ToolAttributes {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut name = None;
         let mut description = None;
         let mut args = None;
+        let mut cache_ttl = None;
+        let mut timeout = None;
+        let mut idempotent = None;
+        let mut retries = None;
+        let mut namespace = None;
         while !input.is_empty() {
             let key: Ident = input.parse()?;
             let key_span = key.span();
@@ -54,6 +89,21 @@ impl Parse for ToolAttributes {
                 ToolAttributeKeys::Input => {
                     args = Some(input.parse::<Type>()?);
                 }
+                ToolAttributeKeys::CacheTtl => {
+                    cache_ttl = Some(input.parse::<LitInt>()?);
+                }
+                ToolAttributeKeys::Timeout => {
+                    timeout = Some(input.parse::<LitInt>()?);
+                }
+                ToolAttributeKeys::Idempotent => {
+                    idempotent = Some(input.parse::<LitBool>()?);
+                }
+                ToolAttributeKeys::Retries => {
+                    retries = Some(input.parse::<LitInt>()?);
+                }
+                ToolAttributeKeys::Namespace => {
+                    namespace = Some(input.parse::<LitStr>()?);
+                }
                 ToolAttributeKeys::Unknown(other) => {
                     return Err(syn::Error::new(
                         key_span,
@@ -66,24 +116,14 @@ impl Parse for ToolAttributes {
             }
         }
         Ok(ToolAttributes {
-            name: name.ok_or_else(|| {
-                syn::Error::new(
-                    input.span(),
-                    format!("Missing attribute: {}", ToolAttributeKeys::Name),
-                )
-            })?,
-            description: description.ok_or_else(|| {
-                syn::Error::new(
-                    input.span(),
-                    format!("Missing attribute: {}", ToolAttributeKeys::Description),
-                )
-            })?,
-            input: args.ok_or_else(|| {
-                syn::Error::new(
-                    input.span(),
-                    format!("Missing attribute: {}", ToolAttributeKeys::Input),
-                )
-            })?,
+            name,
+            description,
+            input: args,
+            cache_ttl,
+            timeout,
+            idempotent,
+            retries,
+            namespace,
         })
     }
 }
@@ -0,0 +1,195 @@
+use super::attr::{ToolAttributeKeys, ToolAttributes};
+use super::input::InputParser;
+use super::{metadata, tool_name};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_quote, Error, FnArg, Ident, ItemFn, LitStr, Pat, Result, ReturnType, Type};
+
+/// Expand `#[tool]` on an async fn into a unit struct implementing `ToolT`.
+///
+/// The struct is named after the function in UpperCamelCase. The tool name
+/// defaults to the function name and the description to its doc comment.
+/// Each parameter becomes a required argument, described with the same
+/// `#[input(...)]` attribute `ToolInput` fields take.
+pub(crate) fn expand(tool_attrs: ToolAttributes, mut input_fn: ItemFn) -> Result<TokenStream> {
+    if tool_attrs.input.is_some() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`input` is only used on structs; the arguments of a tool fn are its parameters",
+        ));
+    }
+    let sig = &input_fn.sig;
+    if sig.asyncness.is_none() {
+        return Err(Error::new_spanned(
+            sig.fn_token,
+            "tool functions must be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "tool functions cannot be generic",
+        ));
+    }
+
+    let fn_name = sig.ident.clone();
+    let name = tool_attrs
+        .name
+        .clone()
+        .unwrap_or_else(|| LitStr::new(&fn_name.to_string(), fn_name.span()));
+    let tool_name_literal = tool_name(&tool_attrs, &name);
+    let description = match (&tool_attrs.description, doc_comment(&input_fn.attrs)) {
+        (Some(description), _) => description.clone(),
+        (None, Some(doc)) => LitStr::new(&doc, fn_name.span()),
+        (None, None) => return Err(ToolAttributes::missing(ToolAttributeKeys::Description)),
+    };
+
+    let mut params: Vec<(Ident, Type)> = Vec::new();
+    let mut fields = Vec::new();
+    for arg in input_fn.sig.inputs.iter_mut() {
+        let arg = match arg {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new_spanned(
+                    receiver,
+                    "tool functions cannot take `self`",
+                ))
+            }
+        };
+        let ident = match arg.pat.as_ref() {
+            Pat::Ident(pat) => pat.ident.clone(),
+            other => {
+                return Err(Error::new_spanned(
+                    other,
+                    "tool function parameters must be plain identifiers",
+                ))
+            }
+        };
+        // `#[input]` only describes the argument, the function itself must not see it
+        let (input_attrs, attrs): (Vec<syn::Attribute>, Vec<syn::Attribute>) = arg
+            .attrs
+            .drain(..)
+            .partition(|attr| attr.path().is_ident("input"));
+        arg.attrs = attrs;
+        let ty = arg.ty.as_ref().clone();
+        fields.push(quote! { #(#input_attrs)* #ident: #ty });
+        params.push((ident, ty));
+    }
+    let args_struct: syn::ItemStruct = parse_quote! {
+        struct Args { #(#fields),* }
+    };
+    let schema = InputParser::default().fields_schema(&args_struct.fields)?;
+    let schema = LitStr::new(&schema, fn_name.span());
+
+    let struct_name = format_ident!("{}", upper_camel_case(&fn_name.to_string()));
+    let vis = &input_fn.vis;
+    let metadata = metadata(&tool_attrs);
+    let idents = params.iter().map(|(ident, _)| ident);
+    let bindings = params.iter().map(|(ident, ty)| {
+        let key = ident.to_string();
+        quote! {
+            let #ident: #ty = serde_json::from_value(
+                args.remove(#key).unwrap_or(serde_json::Value::Null),
+            )?;
+        }
+    });
+    // Fallible functions propagate their error, which must convert into `ToolCallError`
+    let call = if returns_result(&input_fn.sig.output) {
+        quote! { #fn_name(#(#idents),*).await? }
+    } else {
+        quote! { #fn_name(#(#idents),*).await }
+    };
+    let doc = format!("Tool calling [`{fn_name}`], generated by `#[tool]`");
+
+    Ok(quote! {
+        #input_fn
+
+        #[doc = #doc]
+        #[derive(Clone, Copy, Default)]
+        #vis struct #struct_name;
+
+        impl autoagents::core::tool::ToolT for #struct_name {
+            fn name(&self) -> &'static str {
+                #tool_name_literal
+            }
+            fn description(&self) -> &'static str {
+                #description
+            }
+            fn args_schema(&self) -> serde_json::Value {
+                serde_json::from_str(#schema).expect("Failed to parse parameters schema")
+            }
+            #metadata
+        }
+
+        #[::autoagents::async_trait]
+        impl autoagents::core::tool::ToolRuntime for #struct_name {
+            async fn execute(
+                &self,
+                args: serde_json::Value,
+            ) -> Result<serde_json::Value, autoagents::core::tool::ToolCallError> {
+                let mut args = match args {
+                    serde_json::Value::Object(map) => map,
+                    _ => serde_json::Map::new(),
+                };
+                #(#bindings)*
+                let output = #call;
+                Ok(serde_json::to_value(output)?)
+            }
+        }
+
+        impl std::fmt::Debug for #struct_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", autoagents::core::tool::ToolT::name(self))
+            }
+        }
+    })
+}
+
+/// Text of the `///` comments in `attrs`, one line per comment
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(text),
+                        ..
+                    }),
+                ..
+            }) => Some(text.value().trim().to_string()),
+            _ => None,
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn returns_result(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+/// `get_weather` becomes `GetWeather`
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -20,6 +20,7 @@ enum InputAttrIdent {
 
 #[derive(Debug, Serialize)]
 pub(crate) struct InputToolProperty {
+    #[serde(skip_serializing_if = "Option::is_none")]
     description: Option<String>,
     #[serde(rename = "type")]
     _type: String,
@@ -78,6 +79,17 @@ impl InputParser {
         TokenStream::from(expanded)
     }
 
+    /// JSON schema of an object with the named `fields` as its properties
+    pub(crate) fn fields_schema(&mut self, fields: &syn::Fields) -> Result<String> {
+        self.parse_struct(&DataStruct {
+            struct_token: Default::default(),
+            fields: fields.clone(),
+            semi_token: None,
+        })?;
+        serde_json::to_string(&self.tool_parse_data)
+            .map_err(|e| Error::new(proc_macro2::Span::call_site(), e.to_string()))
+    }
+
     fn parse_data(&mut self, input: Data) -> Result<()> {
         match &input {
             Data::Struct(struct_data) => self.parse_struct(struct_data)?,
@@ -134,23 +146,21 @@ impl InputParser {
             }
         }
 
-        if let Some(property) = tool_property {
-            Ok(InputToolProperty {
-                description: property
-                    .description
-                    .map_or_else(|| None, |f| Some(f.value())),
-                _enum: property.choice.map_or_else(
-                    || None,
-                    |f| Some(f.iter().map(|f| f.to_string()).collect::<Vec<String>>()),
-                ),
-                _type: json_type.to_string(),
-            })
-        } else {
-            Err(Error::new(
-                proc_macro2::Span::call_site(),
-                "Coudn't Create the tool arg property",
-            ))
-        }
+        // Fields without `#[input]` get neither a description nor choices
+        let property = tool_property.unwrap_or(FieldSchemaAttr {
+            description: None,
+            choice: None,
+        });
+        Ok(InputToolProperty {
+            description: property
+                .description
+                .map_or_else(|| None, |f| Some(f.value())),
+            _enum: property.choice.map_or_else(
+                || None,
+                |f| Some(f.iter().map(|f| f.to_string()).collect::<Vec<String>>()),
+            ),
+            _type: json_type.to_string(),
+        })
     }
 
     fn get_json_type(&mut self, field_type: &Type) -> Result<JsonType> {
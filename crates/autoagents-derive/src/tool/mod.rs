@@ -1,8 +1,9 @@
 mod attr;
 pub(crate) mod field;
+mod function;
 pub(crate) mod input;
 pub(crate) mod json;
-use attr::ToolAttributes;
+use attr::{ToolAttributeKeys, ToolAttributes};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse_macro_input;
@@ -13,15 +14,42 @@ pub(crate) struct ToolParser {}
 impl ToolParser {
     pub fn parse(&self, attr: TokenStream, item: TokenStream) -> TokenStream {
         let tool_attrs = parse_macro_input!(attr as ToolAttributes);
-        // Parse the struct, not a function
-        let input_struct = parse_macro_input!(item as syn::ItemStruct);
+        // Tools are either a struct with a hand-written `ToolRuntime`, or an async fn
+        let expanded = match parse_macro_input!(item as syn::Item) {
+            syn::Item::Struct(input_struct) => self.parse_struct(tool_attrs, input_struct),
+            syn::Item::Fn(input_fn) => function::expand(tool_attrs, input_fn),
+            other => Err(syn::Error::new_spanned(
+                other,
+                "#[tool] can only be applied to a struct or an async fn",
+            )),
+        };
+        expanded
+            .unwrap_or_else(syn::Error::into_compile_error)
+            .into()
+    }
 
+    fn parse_struct(
+        &self,
+        tool_attrs: ToolAttributes,
+        input_struct: syn::ItemStruct,
+    ) -> syn::Result<proc_macro2::TokenStream> {
         let struct_name = &input_struct.ident;
-        let tool_name_literal = tool_attrs.name.clone();
-        let tool_description = tool_attrs.description;
-        let args_type = tool_attrs.input;
+        let name = tool_attrs
+            .name
+            .clone()
+            .ok_or_else(|| ToolAttributes::missing(ToolAttributeKeys::Name))?;
+        let tool_description = tool_attrs
+            .description
+            .clone()
+            .ok_or_else(|| ToolAttributes::missing(ToolAttributeKeys::Description))?;
+        let args_type = tool_attrs
+            .input
+            .clone()
+            .ok_or_else(|| ToolAttributes::missing(ToolAttributeKeys::Input))?;
+        let tool_name_literal = tool_name(&tool_attrs, &name);
+        let metadata = metadata(&tool_attrs);
 
-        let expanded = quote! {
+        Ok(quote! {
             #input_struct
 
             impl autoagents::core::tool::ToolT for #struct_name {
@@ -37,6 +65,7 @@ impl ToolParser {
                     serde_json::from_str(params_str)
                         .expect("Failed to parse parameters schema")
                 }
+                #metadata
             }
 
             impl std::fmt::Debug for #struct_name {
@@ -44,8 +73,70 @@ impl ToolParser {
                     write!(f, "{}", self.name())
                 }
             }
-        };
+        })
+    }
+}
+
+/// Tools in a namespace are named `namespace_name`
+fn tool_name(tool_attrs: &ToolAttributes, name: &syn::LitStr) -> syn::LitStr {
+    match &tool_attrs.namespace {
+        Some(namespace) => syn::LitStr::new(
+            &format!("{}_{}", namespace.value(), name.value()),
+            name.span(),
+        ),
+        None => name.clone(),
+    }
+}
+
+/// `ToolT` methods for the optional attributes, left to their defaults when
+/// the attribute is absent
+fn metadata(tool_attrs: &ToolAttributes) -> proc_macro2::TokenStream {
+    let namespace = tool_attrs.namespace.as_ref().map(|namespace| {
+        quote! {
+            fn namespace(&self) -> Option<&'static str> {
+                Some(#namespace)
+            }
+        }
+    });
+    // `cache_ttl` is given in seconds
+    let cache_ttl = tool_attrs.cache_ttl.as_ref().map(|secs| {
+        quote! {
+            fn cache_ttl(&self) -> Option<std::time::Duration> {
+                Some(std::time::Duration::from_secs(#secs))
+            }
+        }
+    });
+
+    // `timeout` is given in seconds
+    let timeout = tool_attrs.timeout.as_ref().map(|secs| {
+        quote! {
+            fn timeout(&self) -> Option<std::time::Duration> {
+                Some(std::time::Duration::from_secs(#secs))
+            }
+        }
+    });
+
+    let idempotent = tool_attrs.idempotent.as_ref().map(|idempotent| {
+        quote! {
+            fn idempotent(&self) -> bool {
+                #idempotent
+            }
+        }
+    });
+    // `retries` uses the default backoff; implement `retry_policy` by hand to tune it
+    let retry_policy = tool_attrs.retries.as_ref().map(|retries| {
+        quote! {
+            fn retry_policy(&self) -> Option<autoagents::core::tool::ToolRetryPolicy> {
+                Some(autoagents::core::tool::ToolRetryPolicy::new(#retries))
+            }
+        }
+    });
 
-        expanded.into()
+    quote! {
+        #cache_ttl
+        #timeout
+        #idempotent
+        #retry_policy
+        #namespace
     }
 }
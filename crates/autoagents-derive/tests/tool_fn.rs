@@ -0,0 +1,124 @@
+use autoagents::core::tool::{ToolCallError, ToolError, ToolRuntime, ToolT};
+use autoagents_derive::tool;
+use serde_json::json;
+
+/// Multiply two numbers together
+#[tool]
+async fn multiply(
+    #[input(description = "Left operand")] left: i64,
+    #[input(description = "Right operand")] right: i64,
+) -> i64 {
+    left * right
+}
+
+#[tool(name = "greet", description = "Greet someone by name")]
+async fn greeting(
+    #[input(description = "Who to greet")] name: String,
+    #[input(description = "Greeting to use")] salutation: String,
+) -> String {
+    format!("{salutation} {name}")
+}
+
+/// Divide two numbers
+#[tool]
+async fn divide(
+    #[input(description = "Dividend")] left: i64,
+    #[input(description = "Divisor")] right: i64,
+) -> Result<i64, ToolError> {
+    if right == 0 {
+        return Err(ToolError::InvalidArgs("division by zero".to_string()));
+    }
+    Ok(left / right)
+}
+
+#[test]
+fn test_name_and_description_come_from_the_fn() {
+    assert_eq!(Multiply.name(), "multiply");
+    assert_eq!(Multiply.description(), "Multiply two numbers together");
+    assert_eq!(format!("{:?}", Multiply), "multiply");
+}
+
+#[test]
+fn test_attributes_override_name_and_description() {
+    assert_eq!(Greeting.name(), "greet");
+    assert_eq!(Greeting.description(), "Greet someone by name");
+}
+
+#[test]
+fn test_schema_describes_the_parameters() {
+    let schema = Multiply.args_schema();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["properties"]["left"]["type"], "number");
+    assert_eq!(schema["properties"]["left"]["description"], "Left operand");
+    assert_eq!(
+        schema["properties"]["right"]["description"],
+        "Right operand"
+    );
+    let required = schema["required"].as_array().unwrap();
+    assert!(required.contains(&json!("left")));
+    assert!(required.contains(&json!("right")));
+
+    let schema = Greeting.args_schema();
+    assert_eq!(schema["properties"]["name"]["type"], "string");
+    assert_eq!(
+        schema["properties"]["salutation"]["description"],
+        "Greeting to use"
+    );
+}
+
+#[tokio::test]
+async fn test_call_passes_the_arguments() {
+    let result = Multiply
+        .execute(json!({"left": 6, "right": 7}))
+        .await
+        .unwrap();
+    assert_eq!(result, json!(42));
+}
+
+#[tokio::test]
+async fn test_call_matches_arguments_by_name() {
+    let result = Greeting
+        .execute(json!({"salutation": "Hi", "name": "Ada"}))
+        .await
+        .unwrap();
+    assert_eq!(result, json!("Hi Ada"));
+}
+
+#[tokio::test]
+async fn test_non_object_arguments_are_an_error() {
+    let error = Greeting.execute(json!(["Ada", "Hi"])).await.unwrap_err();
+    assert!(matches!(error, ToolCallError::SerdeError(_)));
+}
+
+#[tokio::test]
+async fn test_missing_argument_is_an_error() {
+    let error = Multiply.execute(json!({"left": 6})).await.unwrap_err();
+    assert!(matches!(error, ToolCallError::SerdeError(_)));
+}
+
+#[tokio::test]
+async fn test_mistyped_argument_is_an_error() {
+    let error = Multiply
+        .execute(json!({"left": "six", "right": 7}))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ToolCallError::SerdeError(_)));
+}
+
+#[tokio::test]
+async fn test_fallible_fn_propagates_its_error() {
+    let result = Divide
+        .execute(json!({"left": 8, "right": 2}))
+        .await
+        .unwrap();
+    assert_eq!(result, json!(4));
+
+    let error = Divide
+        .execute(json!({"left": 8, "right": 0}))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ToolCallError::Tool(ToolError::InvalidArgs(_))
+    ));
+}
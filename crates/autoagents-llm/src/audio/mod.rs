@@ -0,0 +1,265 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::error::LLMError;
+
+/// Container format of a piece of audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum AudioFormat {
+    /// MPEG-1 Audio Layer III
+    #[default]
+    Mp3,
+    /// Uncompressed WAVE
+    Wav,
+    /// Free Lossless Audio Codec
+    Flac,
+    /// Ogg Vorbis
+    Ogg,
+    /// Opus in an Ogg container
+    Opus,
+    /// Advanced Audio Coding
+    Aac,
+    /// Raw 16-bit little-endian samples
+    Pcm,
+    /// WebM, as recorded by browsers
+    Webm,
+    /// AAC in an MPEG-4 container
+    M4a,
+}
+
+impl AudioFormat {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Ogg => "audio/ogg",
+            AudioFormat::Opus => "audio/opus",
+            AudioFormat::Aac => "audio/aac",
+            AudioFormat::Pcm => "audio/pcm",
+            AudioFormat::Webm => "audio/webm",
+            AudioFormat::M4a => "audio/mp4",
+        }
+    }
+
+    /// File extension, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Pcm => "pcm",
+            AudioFormat::Webm => "webm",
+            AudioFormat::M4a => "m4a",
+        }
+    }
+
+    /// Parse a MIME type string such as `audio/wav`, ignoring any parameters
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        let essence = mime_type.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "audio/mpeg" | "audio/mp3" => Some(AudioFormat::Mp3),
+            "audio/wav" | "audio/x-wav" | "audio/wave" => Some(AudioFormat::Wav),
+            "audio/flac" | "audio/x-flac" => Some(AudioFormat::Flac),
+            "audio/ogg" => Some(AudioFormat::Ogg),
+            "audio/opus" => Some(AudioFormat::Opus),
+            "audio/aac" => Some(AudioFormat::Aac),
+            "audio/pcm" | "audio/l16" => Some(AudioFormat::Pcm),
+            "audio/webm" => Some(AudioFormat::Webm),
+            "audio/mp4" | "audio/m4a" | "audio/x-m4a" => Some(AudioFormat::M4a),
+            _ => None,
+        }
+    }
+}
+
+/// Encoded audio together with its format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Audio {
+    pub data: Vec<u8>,
+    pub format: AudioFormat,
+}
+
+impl Audio {
+    pub fn new(data: Vec<u8>, format: AudioFormat) -> Self {
+        Self { data, format }
+    }
+}
+
+/// Audio to turn into text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptionRequest {
+    pub audio: Audio,
+    /// Model to use instead of the provider's default
+    pub model: Option<String>,
+    /// ISO-639-1 code of the spoken language, detected when unset
+    pub language: Option<String>,
+    /// Text to guide the style or spelling of the transcript
+    pub prompt: Option<String>,
+}
+
+impl TranscriptionRequest {
+    pub fn new(audio: Audio) -> Self {
+        Self {
+            audio,
+            model: None,
+            language: None,
+            prompt: None,
+        }
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+}
+
+/// Text recognized in a piece of audio.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcription {
+    pub text: String,
+    /// Spoken language, when the provider reports it
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Length of the audio in seconds, when the provider reports it
+    #[serde(default)]
+    pub duration: Option<f32>,
+}
+
+/// Text to turn into speech.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeechRequest {
+    pub text: String,
+    /// Voice to use instead of the provider's default
+    pub voice: Option<String>,
+    /// Model to use instead of the provider's default
+    pub model: Option<String>,
+    /// Format of the returned audio
+    pub format: AudioFormat,
+    /// Playback speed, where 1.0 is normal
+    pub speed: Option<f32>,
+    /// Guidance on tone and delivery, for models that follow instructions
+    pub instructions: Option<String>,
+}
+
+impl SpeechRequest {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            voice: None,
+            model: None,
+            format: AudioFormat::default(),
+            speed: None,
+            instructions: None,
+        }
+    }
+
+    pub fn voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = Some(voice.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+}
+
+/// Transcribes speech into text.
+#[async_trait]
+pub trait SpeechToTextProvider: Send + Sync {
+    async fn transcribe(&self, request: &TranscriptionRequest) -> Result<Transcription, LLMError>;
+}
+
+/// Synthesizes speech from text.
+#[async_trait]
+pub trait TextToSpeechProvider: Send + Sync {
+    async fn speech(&self, request: &SpeechRequest) -> Result<Audio, LLMError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoSpeech;
+
+    #[async_trait]
+    impl TextToSpeechProvider for EchoSpeech {
+        async fn speech(&self, request: &SpeechRequest) -> Result<Audio, LLMError> {
+            Ok(Audio::new(request.text.as_bytes().to_vec(), request.format))
+        }
+    }
+
+    #[async_trait]
+    impl SpeechToTextProvider for EchoSpeech {
+        async fn transcribe(
+            &self,
+            request: &TranscriptionRequest,
+        ) -> Result<Transcription, LLMError> {
+            Ok(Transcription {
+                text: String::from_utf8_lossy(&request.audio.data).into_owned(),
+                language: request.language.clone(),
+                duration: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_speech_round_trips_through_transcription() {
+        let audio = EchoSpeech
+            .speech(&SpeechRequest::new("hello there").format(AudioFormat::Wav))
+            .await
+            .unwrap();
+        assert_eq!(audio.format, AudioFormat::Wav);
+
+        let transcription = EchoSpeech
+            .transcribe(&TranscriptionRequest::new(audio).language("en"))
+            .await
+            .unwrap();
+        assert_eq!(transcription.text, "hello there");
+        assert_eq!(transcription.language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_audio_format_from_mime_type() {
+        assert_eq!(
+            AudioFormat::from_mime_type("audio/x-wav"),
+            Some(AudioFormat::Wav)
+        );
+        assert_eq!(
+            AudioFormat::from_mime_type("Audio/MPEG; rate=44100"),
+            Some(AudioFormat::Mp3)
+        );
+        assert_eq!(AudioFormat::from_mime_type("image/png"), None);
+        assert_eq!(AudioFormat::M4a.extension(), "m4a");
+    }
+}
@@ -1,13 +1,23 @@
 //! Anthropic API client implementation for chat and completion functionality.
 //!
-//! This module provides integration with Anthropic's Claude models through their API.
+//! This module provides integration with Anthropic's Claude models through their API,
+//! including extended thinking: with reasoning enabled, Claude thinks within a token
+//! budget before it answers, and its thinking is returned apart from the answer in
+//! both responses and streams.
+//!
+//! Anthropic has no response format setting, so a structured output schema is
+//! sent as a tool the model is made to answer through; its input comes back
+//! as the response text.
 
 use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
 use crate::{
     builder::{LLMBackend, LLMBuilder},
     chat::{
-        ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, StructuredOutputFormat,
-        Tool, ToolChoice,
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, ContentPart, MessageType,
+        ReasoningBlock, StreamChoice, StreamDelta, StreamResponse, StreamToolCallDelta,
+        StreamToolCallFunction, StructuredOutputFormat, Tool, ToolChoice, Usage,
     },
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
@@ -18,12 +28,19 @@ use crate::{
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::{DateTime, Utc};
-use futures::stream::Stream;
+use futures::stream::{Stream, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{collections::HashMap, sync::Arc};
 
+/// Thinking budget used when reasoning is enabled without one
+const DEFAULT_THINKING_BUDGET_TOKENS: u32 = 16000;
+
+/// Tool carrying the answer when a structured output schema is given
+const OUTPUT_TOOL: &str = "structured_output";
+const OUTPUT_TOOL_DESCRIPTION: &str = "Give the final answer in the required format.";
+
 /// Client for interacting with Anthropic's API.
 ///
 /// Provides methods for chat and completion requests using Anthropic's models.
@@ -40,6 +57,9 @@ pub struct Anthropic {
     pub tool_choice: Option<ToolChoice>,
     pub reasoning: bool,
     pub thinking_budget_tokens: Option<u32>,
+    /// Sequences that end generation when the model produces them
+    pub stop_sequences: Vec<String>,
+    pub retry_policy: RetryPolicy,
     client: Client,
 }
 
@@ -83,6 +103,8 @@ struct AnthropicCompleteRequest<'a> {
     tool_choice: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     thinking: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    stop_sequences: &'a [String],
 }
 
 /// Individual message in an Anthropic chat conversation.
@@ -99,8 +121,6 @@ struct MessageContent<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     text: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    image_url: Option<ImageUrlContent<'a>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     source: Option<ImageSource<'a>>,
     // tool use
     #[serde(skip_serializing_if = "Option::is_none", rename = "id")]
@@ -114,25 +134,117 @@ struct MessageContent<'a> {
     tool_result_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "content")]
     tool_output: Option<String>,
-}
-
-#[derive(Serialize, Debug)]
-struct ImageUrlContent<'a> {
-    url: &'a str,
+    // thinking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<&'a str>,
 }
 
 #[derive(Serialize, Debug)]
 struct ImageSource<'a> {
     #[serde(rename = "type")]
     source_type: &'a str,
-    media_type: &'a str,
-    data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+}
+
+impl<'a> MessageContent<'a> {
+    fn image(source: ImageSource<'a>) -> Self {
+        MessageContent {
+            message_type: Some("image"),
+            text: None,
+            source: Some(source),
+            tool_use_id: None,
+            tool_input: None,
+            tool_name: None,
+            tool_result_id: None,
+            tool_output: None,
+            thinking: None,
+            signature: None,
+            data: None,
+        }
+    }
+
+    /// Block for a part of a multimodal message, omitting empty text
+    fn part(part: &'a ContentPart) -> Option<Self> {
+        match part {
+            ContentPart::Text(text) => Self::non_empty_text(text),
+            ContentPart::Image { mime, data } => Some(Self::image(ImageSource {
+                source_type: "base64",
+                media_type: Some(mime.mime_type()),
+                data: Some(BASE64.encode(data)),
+                url: None,
+            })),
+            ContentPart::ImageUrl { url, .. } => Some(Self::image(ImageSource {
+                source_type: "url",
+                media_type: None,
+                data: None,
+                url: Some(url),
+            })),
+        }
+    }
+
+    /// Thinking block sent back with the tool calls it led to
+    fn reasoning(block: &'a ReasoningBlock) -> Self {
+        let (message_type, thinking, signature, data) = match block {
+            ReasoningBlock::Thinking {
+                thinking,
+                signature,
+            } => (
+                "thinking",
+                Some(thinking.as_str()),
+                Some(signature.as_str()),
+                None,
+            ),
+            ReasoningBlock::Redacted { data } => {
+                ("redacted_thinking", None, None, Some(data.as_str()))
+            }
+        };
+        MessageContent {
+            message_type: Some(message_type),
+            text: None,
+            source: None,
+            tool_use_id: None,
+            tool_input: None,
+            tool_name: None,
+            tool_result_id: None,
+            tool_output: None,
+            thinking,
+            signature,
+            data,
+        }
+    }
+
+    /// Text part accompanying an image, omitted when the message has no text
+    fn non_empty_text(text: &'a str) -> Option<Self> {
+        (!text.is_empty()).then_some(MessageContent {
+            message_type: Some("text"),
+            text: Some(text),
+            source: None,
+            tool_use_id: None,
+            tool_input: None,
+            tool_name: None,
+            tool_result_id: None,
+            tool_output: None,
+            thinking: None,
+            signature: None,
+            data: None,
+        })
+    }
 }
 
 /// Response from Anthropic's messages API endpoint.
 #[derive(Deserialize, Debug)]
 struct AnthropicCompleteResponse {
     content: Vec<AnthropicContent>,
+    usage: Option<AnthropicUsage>,
 }
 
 /// Content block within an Anthropic API response.
@@ -142,26 +254,96 @@ struct AnthropicContent {
     #[serde(rename = "type")]
     content_type: Option<String>,
     thinking: Option<String>,
+    signature: Option<String>,
+    /// Encrypted content of a redacted thinking block
+    data: Option<String>,
     name: Option<String>,
     input: Option<serde_json::Value>,
     id: Option<String>,
 }
 
-/// Response from Anthropic's streaming messages API endpoint.
+/// Event of Anthropic's streaming messages API endpoint.
 #[derive(Deserialize, Debug)]
-struct AnthropicStreamResponse {
+struct AnthropicStreamEvent {
     #[serde(rename = "type")]
-    response_type: String,
+    event_type: String,
+    /// Index of the content block the event belongs to
+    index: Option<usize>,
+    message: Option<AnthropicStreamMessage>,
+    content_block: Option<AnthropicContent>,
     delta: Option<AnthropicDelta>,
+    usage: Option<AnthropicUsage>,
+    error: Option<AnthropicStreamError>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicStreamMessage {
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: u32,
+    #[serde(default)]
+    cache_read_input_tokens: u32,
+}
+
+impl AnthropicUsage {
+    /// Prompt tokens including the ones written to and read from the cache,
+    /// which Anthropic leaves out of `input_tokens`
+    fn prompt_tokens(&self) -> u32 {
+        self.input_tokens
+            .saturating_add(self.cache_creation_input_tokens)
+            .saturating_add(self.cache_read_input_tokens)
+    }
+}
+
+impl From<AnthropicUsage> for Usage {
+    fn from(usage: AnthropicUsage) -> Self {
+        let normalized = Usage::new(usage.prompt_tokens(), usage.output_tokens);
+        if usage.cache_read_input_tokens > 0 {
+            normalized.with_cached_tokens(usage.cache_read_input_tokens)
+        } else {
+            normalized
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicStreamError {
+    message: String,
 }
 
 /// Delta content within an Anthropic streaming response.
 #[derive(Deserialize, Debug)]
 struct AnthropicDelta {
-    #[allow(dead_code)]
     #[serde(rename = "type")]
     delta_type: Option<String>,
     text: Option<String>,
+    thinking: Option<String>,
+    signature: Option<String>,
+    partial_json: Option<String>,
+}
+
+impl AnthropicContent {
+    /// The block as reasoning to send back, if it is a thinking block
+    fn reasoning(&self) -> Option<ReasoningBlock> {
+        match self.content_type.as_deref()? {
+            "thinking" => Some(ReasoningBlock::Thinking {
+                thinking: self.thinking.clone().unwrap_or_default(),
+                signature: self.signature.clone().unwrap_or_default(),
+            }),
+            "redacted_thinking" => Some(ReasoningBlock::Redacted {
+                data: self.data.clone()?,
+            }),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for AnthropicCompleteResponse {
@@ -192,6 +374,27 @@ impl std::fmt::Display for AnthropicCompleteResponse {
     }
 }
 
+impl AnthropicCompleteResponse {
+    /// Turn the call of the structured output tool into the answer text
+    fn output_tool_as_text(&mut self) {
+        for block in &mut self.content {
+            if block.content_type.as_deref() == Some("tool_use")
+                && block.name.as_deref() == Some(OUTPUT_TOOL)
+            {
+                block.content_type = Some("text".to_string());
+                block.text = block.input.take().map(|input| input.to_string());
+                block.name = None;
+                block.id = None;
+            }
+        }
+    }
+}
+
+/// Whether `json_schema` is sent as the structured output tool
+fn has_output_schema(json_schema: Option<&StructuredOutputFormat>) -> bool {
+    json_schema.is_some_and(|format| format.schema.is_some())
+}
+
 impl ChatResponse for AnthropicCompleteResponse {
     fn text(&self) -> Option<String> {
         Some(
@@ -209,11 +412,23 @@ impl ChatResponse for AnthropicCompleteResponse {
         )
     }
 
+    /// The thinking blocks of the response, joined by newlines. Redacted
+    /// thinking is left out.
     fn thinking(&self) -> Option<String> {
+        let blocks = self
+            .content
+            .iter()
+            .filter(|c| c.content_type.as_deref() == Some("thinking"))
+            .filter_map(|c| c.thinking.clone())
+            .collect::<Vec<_>>();
+        (!blocks.is_empty()).then(|| blocks.join("\n"))
+    }
+
+    fn reasoning(&self) -> Vec<ReasoningBlock> {
         self.content
             .iter()
-            .find(|c| c.content_type == Some("thinking".to_string()))
-            .and_then(|c| c.thinking.clone())
+            .filter_map(AnthropicContent::reasoning)
+            .collect()
     }
 
     fn tool_calls(&self) -> Option<Vec<ToolCall>> {
@@ -243,6 +458,10 @@ impl ChatResponse for AnthropicCompleteResponse {
             v => Some(v),
         }
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone().map(Usage::from)
+    }
 }
 
 impl Anthropic {
@@ -273,10 +492,6 @@ impl Anthropic {
         reasoning: Option<bool>,
         thinking_budget_tokens: Option<u32>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
         Self {
             api_key: api_key.into(),
             model: model.unwrap_or_else(|| "claude-3-sonnet-20240229".to_string()),
@@ -289,7 +504,9 @@ impl Anthropic {
             tool_choice,
             reasoning: reasoning.unwrap_or(false),
             thinking_budget_tokens,
-            client: builder.build().expect("Failed to build reqwest Client"),
+            stop_sequences: Vec::new(),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
         }
     }
 
@@ -297,7 +514,7 @@ impl Anthropic {
         &'a self,
         messages: &'a [ChatMessage],
         tools: Option<&'a [Tool]>,
-        _json_schema: Option<StructuredOutputFormat>,
+        json_schema: Option<&'a StructuredOutputFormat>,
         stream: bool,
     ) -> Result<AnthropicCompleteRequest<'a>, LLMError> {
         let mut anthropic_messages = Vec::new();
@@ -314,72 +531,79 @@ impl Anthropic {
                 MessageType::Text => vec![MessageContent {
                     message_type: Some("text"),
                     text: Some(&message.content),
-                    image_url: None,
                     source: None,
                     tool_use_id: None,
                     tool_input: None,
                     tool_name: None,
                     tool_result_id: None,
                     tool_output: None,
+                    thinking: None,
+                    signature: None,
+                    data: None,
                 }],
                 MessageType::Pdf(_) => unimplemented!(),
                 MessageType::Image((image_mime, raw_bytes)) => {
-                    vec![MessageContent {
-                        message_type: Some("image"),
-                        text: None,
-                        image_url: None,
-                        source: Some(ImageSource {
-                            source_type: "base64",
-                            media_type: image_mime.mime_type(),
-                            data: BASE64.encode(raw_bytes),
-                        }),
-                        tool_use_id: None,
-                        tool_input: None,
-                        tool_name: None,
-                        tool_result_id: None,
-                        tool_output: None,
-                    }]
+                    let mut parts = vec![MessageContent::image(ImageSource {
+                        source_type: "base64",
+                        media_type: Some(image_mime.mime_type()),
+                        data: Some(BASE64.encode(raw_bytes)),
+                        url: None,
+                    })];
+                    parts.extend(MessageContent::non_empty_text(&message.content));
+                    parts
                 }
-                MessageType::ImageURL(ref url) => vec![MessageContent {
-                    message_type: Some("image_url"),
-                    text: None,
-                    image_url: Some(ImageUrlContent { url }),
-                    source: None,
-                    tool_use_id: None,
-                    tool_input: None,
-                    tool_name: None,
-                    tool_result_id: None,
-                    tool_output: None,
-                }],
-                MessageType::ToolUse(calls) => calls
+                MessageType::ImageURL(ref url) => {
+                    let mut parts = vec![MessageContent::image(ImageSource {
+                        source_type: "url",
+                        media_type: None,
+                        data: None,
+                        url: Some(url),
+                    })];
+                    parts.extend(MessageContent::non_empty_text(&message.content));
+                    parts
+                }
+                MessageType::Parts(parts) => MessageContent::non_empty_text(&message.content)
+                    .into_iter()
+                    .chain(parts.iter().filter_map(MessageContent::part))
+                    .collect(),
+                // Thinking has to come back ahead of the tool calls it led to
+                MessageType::ToolUse(calls) => message
+                    .reasoning
                     .iter()
-                    .map(|c| MessageContent {
-                        message_type: Some("tool_use"),
-                        text: None,
-                        image_url: None,
-                        source: None,
-                        tool_use_id: Some(c.id.clone()),
-                        tool_input: Some(
-                            serde_json::from_str(&c.function.arguments)
-                                .unwrap_or(c.function.arguments.clone().into()),
-                        ),
-                        tool_name: Some(c.function.name.clone()),
-                        tool_result_id: None,
-                        tool_output: None,
-                    })
+                    .map(MessageContent::reasoning)
+                    .chain(calls.iter().map(|c| {
+                        MessageContent {
+                            message_type: Some("tool_use"),
+                            text: None,
+                            source: None,
+                            tool_use_id: Some(c.id.clone()),
+                            tool_input: Some(
+                                serde_json::from_str(&c.function.arguments)
+                                    .unwrap_or(c.function.arguments.clone().into()),
+                            ),
+                            tool_name: Some(c.function.name.clone()),
+                            tool_result_id: None,
+                            tool_output: None,
+                            thinking: None,
+                            signature: None,
+                            data: None,
+                        }
+                    }))
                     .collect(),
                 MessageType::ToolResult(responses) => responses
                     .iter()
                     .map(|r| MessageContent {
                         message_type: Some("tool_result"),
                         text: None,
-                        image_url: None,
                         source: None,
                         tool_use_id: None,
                         tool_input: None,
                         tool_name: None,
                         tool_result_id: Some(r.id.clone()),
                         tool_output: Some(r.function.arguments.clone()),
+                        thinking: None,
+                        signature: None,
+                        data: None,
                     })
                     .collect(),
             };
@@ -387,7 +611,7 @@ impl Anthropic {
             anthropic_messages.push(AnthropicMessage { role, content });
         }
 
-        let anthropic_tools = tools.map(|slice| {
+        let mut anthropic_tools = tools.map(|slice| {
             slice
                 .iter()
                 .map(|tool| AnthropicTool {
@@ -397,6 +621,20 @@ impl Anthropic {
                 })
                 .collect::<Vec<_>>()
         });
+        let output_tool = json_schema.and_then(|format| {
+            Some(AnthropicTool {
+                name: OUTPUT_TOOL,
+                description: format
+                    .description
+                    .as_deref()
+                    .unwrap_or(OUTPUT_TOOL_DESCRIPTION),
+                schema: format.schema.as_ref()?,
+            })
+        });
+        let structured = output_tool.is_some();
+        if let Some(tool) = output_tool {
+            anthropic_tools.get_or_insert_with(Vec::new).push(tool);
+        }
 
         let system_content = messages
             .iter()
@@ -419,7 +657,16 @@ impl Anthropic {
             None => None,
         };
 
-        let final_tool_choice = if anthropic_tools.is_some() {
+        // Without other tools the answer has to go through the output tool.
+        // Thinking only allows the model to pick tools itself.
+        let final_tool_choice = if structured && tools.unwrap_or_default().is_empty() {
+            (!self.reasoning).then(|| {
+                HashMap::from([
+                    ("type".to_string(), "tool".to_string()),
+                    ("name".to_string(), OUTPUT_TOOL.to_string()),
+                ])
+            })
+        } else if anthropic_tools.is_some() {
             tool_choice.clone()
         } else {
             None
@@ -428,24 +675,41 @@ impl Anthropic {
         let thinking = if self.reasoning {
             Some(ThinkingConfig {
                 thinking_type: "enabled".to_string(),
-                budget_tokens: self.thinking_budget_tokens.unwrap_or(16000),
+                budget_tokens: self
+                    .thinking_budget_tokens
+                    .unwrap_or(DEFAULT_THINKING_BUDGET_TOKENS),
             })
         } else {
             None
         };
 
+        // The thinking budget counts towards max_tokens, so a limit within
+        // the budget is given to the answer on top of it. Thinking also
+        // rejects changes to temperature and top_k.
+        let max_tokens = match &thinking {
+            Some(config) if self.max_tokens <= config.budget_tokens => {
+                config.budget_tokens + self.max_tokens
+            }
+            _ => self.max_tokens,
+        };
+        let (temperature, top_k) = match thinking {
+            Some(_) => (None, None),
+            None => (Some(self.temperature), self.top_k),
+        };
+
         Ok(AnthropicCompleteRequest {
             messages: anthropic_messages,
             model: &self.model,
-            max_tokens: Some(self.max_tokens),
-            temperature: Some(self.temperature),
+            max_tokens: Some(max_tokens),
+            temperature,
             system: Some(system_content),
             stream: Some(stream),
             top_p: self.top_p,
-            top_k: self.top_k,
+            top_k,
             tools: anthropic_tools,
             tool_choice: final_tool_choice,
             thinking,
+            stop_sequences: &self.stop_sequences,
         })
     }
 
@@ -469,7 +733,8 @@ impl Anthropic {
             return Err(LLMError::AuthError("Missing Anthropic API key".to_string()));
         }
 
-        let req_body = self.build_completion_request(messages, tools, json_schema, false)?;
+        let req_body =
+            self.build_completion_request(messages, tools, json_schema.as_ref(), false)?;
 
         let mut request = self
             .client
@@ -491,15 +756,21 @@ impl Anthropic {
 
         log::debug!("Anthropic request: POST /v1/messages");
 
-        let resp = request.send().await?;
+        let resp = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
 
         log::debug!("Anthropic HTTP status: {}", resp.status());
 
         let resp = check_response_status(resp).await?;
 
         let body = resp.text().await?;
-        let json_resp: AnthropicCompleteResponse = serde_json::from_str(&body)
+        let mut json_resp: AnthropicCompleteResponse = serde_json::from_str(&body)
             .map_err(|e| LLMError::HttpError(format!("Failed to parse JSON: {e}")))?;
+        if has_output_schema(json_schema.as_ref()) {
+            json_resp.output_tool_as_text();
+        }
 
         Ok(Box::new(json_resp))
     }
@@ -525,15 +796,6 @@ impl ChatProvider for Anthropic {
         self.chat_with_tools(messages, tools, json_schema).await
     }
 
-    /// Sends a streaming chat request to Anthropic's API.
-    ///
-    /// # Arguments
-    ///
-    /// * `messages` - Slice of chat messages representing the conversation
-    ///
-    /// # Returns
-    ///
-    /// A stream of text tokens or an error
     async fn chat_stream(
         &self,
         messages: &[ChatMessage],
@@ -541,11 +803,44 @@ impl ChatProvider for Anthropic {
         json_schema: Option<StructuredOutputFormat>,
     ) -> Result<std::pin::Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError>
     {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text, thinking and tool calls; the last chunk carries the
+    /// token usage. Tool calls are numbered by their content block.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<
+        std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>,
+        LLMError,
+    > {
         if self.api_key.is_empty() {
             return Err(LLMError::AuthError("Missing Anthropic API key".to_string()));
         }
 
-        let req_body = self.build_completion_request(messages, tools, json_schema, true)?;
+        let req_body =
+            self.build_completion_request(messages, tools, json_schema.as_ref(), true)?;
+        let parser = AnthropicStreamParser {
+            structured: has_output_schema(json_schema.as_ref()),
+            ..Default::default()
+        };
 
         let mut request = self
             .client
@@ -559,13 +854,27 @@ impl ChatProvider for Anthropic {
             request = request.timeout(std::time::Duration::from_secs(self.timeout_seconds));
         }
 
-        let response = request.send().await?;
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
         let response = check_response_status(response).await?;
 
-        Ok(crate::chat::create_sse_stream(
-            response,
-            parse_anthropic_sse_chunk,
-        ))
+        let stream = response
+            .bytes_stream()
+            .scan(parser, |parser, chunk| {
+                let results = match chunk {
+                    Ok(bytes) => parser.push(&bytes),
+                    Err(e) => vec![Err(LLMError::HttpError(e.to_string()))],
+                };
+                futures::future::ready(Some(results))
+            })
+            .flat_map(futures::stream::iter);
+        Ok(Box::pin(stream))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
     }
 }
 
@@ -652,50 +961,190 @@ impl ModelsProvider for Anthropic {
     }
 }
 
-impl crate::LLMProvider for Anthropic {}
+impl crate::LLMProvider for Anthropic {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
 
-/// Parses a Server-Sent Events (SSE) chunk from Anthropic's streaming API.
-///
-/// # Arguments
-///
-/// * `chunk` - The raw SSE chunk text
-///
-/// # Returns
-///
-/// * `Ok(Some(String))` - Content token if found
-/// * `Ok(None)` - If chunk should be skipped (e.g., ping, done signal)
-/// * `Err(LLMError)` - If parsing fails
-fn parse_anthropic_sse_chunk(chunk: &str) -> Result<Option<String>, LLMError> {
-    for line in chunk.lines() {
-        let line = line.trim();
-
-        if let Some(data) = line.strip_prefix("data: ") {
-            match serde_json::from_str::<AnthropicStreamResponse>(data) {
-                Ok(response) => {
-                    if response.response_type == "content_block_delta" {
-                        if let Some(delta) = response.delta {
-                            if let Some(text) = delta.text {
-                                return Ok(Some(text));
-                            }
+/// Splits the server-sent events of a streaming response into stream chunks
+#[derive(Default)]
+struct AnthropicStreamParser {
+    /// Bytes of a line not yet terminated
+    line_buffer: Vec<u8>,
+    /// Prompt usage, reported when the message starts
+    prompt_usage: AnthropicUsage,
+    /// Whether the request carried the structured output tool
+    structured: bool,
+    /// Content block of the output tool call, streamed as text
+    output_block: Option<usize>,
+    /// Thinking block being streamed and its index, sent whole once it stops
+    thinking_block: Option<(Option<usize>, ReasoningBlock)>,
+}
+
+impl AnthropicStreamParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<Result<StreamResponse, LLMError>> {
+        self.line_buffer.extend_from_slice(bytes);
+        let mut results = Vec::new();
+        while let Some(end) = self.line_buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                results.extend(self.parse_event(data.trim()));
+            }
+        }
+        results
+    }
+
+    fn parse_event(&mut self, data: &str) -> Option<Result<StreamResponse, LLMError>> {
+        let event: AnthropicStreamEvent = match serde_json::from_str(data) {
+            Ok(event) => event,
+            Err(e) => {
+                return Some(Err(LLMError::ResponseFormatError {
+                    message: format!("Failed to decode Anthropic stream event: {e}"),
+                    raw_response: data.to_string(),
+                }))
+            }
+        };
+
+        let chunk = |delta: StreamDelta, usage: Option<Usage>| StreamResponse {
+            choices: vec![StreamChoice { delta }],
+            usage,
+        };
+        let reasoning = |block: ReasoningBlock| StreamDelta {
+            content: None,
+            tool_calls: None,
+            thinking: None,
+            reasoning: Some(Box::new(block)),
+        };
+        let tool_call = |id: Option<String>, name: String, arguments: String| StreamDelta {
+            content: None,
+            tool_calls: Some(vec![StreamToolCallDelta {
+                index: event.index.unwrap_or_default(),
+                id,
+                function: Some(StreamToolCallFunction { arguments, name }),
+            }]),
+            thinking: None,
+            reasoning: None,
+        };
+
+        match event.event_type.as_str() {
+            "message_start" => {
+                if let Some(usage) = event.message.and_then(|message| message.usage) {
+                    self.prompt_usage = usage;
+                }
+                None
+            }
+            // The start of a tool call names it, later deltas add to its input
+            "content_block_start" => {
+                let block = event.content_block.as_ref()?;
+                match block.content_type.as_deref() {
+                    Some("tool_use") => {}
+                    Some("thinking") => {
+                        self.thinking_block = Some((event.index, block.reasoning()?));
+                        return None;
+                    }
+                    Some("redacted_thinking") => {
+                        return Some(Ok(chunk(reasoning(block.reasoning()?), None)));
+                    }
+                    _ => return None,
+                }
+                if self.structured && block.name.as_deref() == Some(OUTPUT_TOOL) {
+                    self.output_block = event.index;
+                    return None;
+                }
+                let name = block.name.clone().unwrap_or_default();
+                Some(Ok(chunk(
+                    tool_call(block.id.clone(), name, String::new()),
+                    None,
+                )))
+            }
+            "content_block_delta" => {
+                let delta = event.delta.as_ref()?;
+                let delta = match delta.delta_type.as_deref() {
+                    Some("text_delta") => StreamDelta {
+                        content: delta.text.clone(),
+                        tool_calls: None,
+                        thinking: None,
+                        reasoning: None,
+                    },
+                    Some("thinking_delta") => {
+                        if let Some((_, ReasoningBlock::Thinking { thinking, .. })) =
+                            &mut self.thinking_block
+                        {
+                            thinking.push_str(delta.thinking.as_deref().unwrap_or_default());
+                        }
+                        StreamDelta {
+                            content: None,
+                            tool_calls: None,
+                            thinking: delta.thinking.clone(),
+                            reasoning: None,
+                        }
+                    }
+                    Some("signature_delta") => {
+                        if let Some((_, ReasoningBlock::Thinking { signature, .. })) =
+                            &mut self.thinking_block
+                        {
+                            signature.push_str(delta.signature.as_deref().unwrap_or_default());
                         }
+                        return None;
+                    }
+                    Some("input_json_delta") if self.output_block == event.index => StreamDelta {
+                        content: delta.partial_json.clone(),
+                        tool_calls: None,
+                        thinking: None,
+                        reasoning: None,
+                    },
+                    Some("input_json_delta") => {
+                        tool_call(None, String::new(), delta.partial_json.clone()?)
                     }
-                    return Ok(None);
+                    _ => return None,
+                };
+                Some(Ok(chunk(delta, None)))
+            }
+            "content_block_stop" => {
+                if self.thinking_block.as_ref()?.0 != event.index {
+                    return None;
                 }
-                Err(_) => continue,
+                let (_, block) = self.thinking_block.take()?;
+                Some(Ok(chunk(reasoning(block), None)))
             }
+            "message_delta" => {
+                let usage = Usage::from(AnthropicUsage {
+                    output_tokens: event.usage?.output_tokens,
+                    ..self.prompt_usage.clone()
+                });
+                let delta = StreamDelta {
+                    content: None,
+                    tool_calls: None,
+                    thinking: None,
+                    reasoning: None,
+                };
+                Some(Ok(chunk(delta, Some(usage))))
+            }
+            "error" => {
+                let message = event
+                    .error
+                    .map(|error| error.message)
+                    .unwrap_or_else(|| data.to_string());
+                Some(Err(LLMError::ProviderError(message)))
+            }
+            _ => None,
         }
     }
-
-    Ok(None)
 }
 
 impl LLMBuilder<Anthropic> {
     pub fn build(self) -> Result<Arc<Anthropic>, LLMError> {
+        self.generation.reject_unsupported(
+            "Anthropic",
+            &["seed", "frequency_penalty", "presence_penalty"],
+        )?;
         let api_key = self.api_key.ok_or_else(|| {
             LLMError::InvalidRequest("No API key provided for Anthropic".to_string())
         })?;
 
-        let anthro = Anthropic::new(
+        let mut anthro = Anthropic::new(
             api_key,
             self.model,
             self.max_tokens,
@@ -708,7 +1157,327 @@ impl LLMBuilder<Anthropic> {
             self.reasoning,
             self.reasoning_budget_tokens,
         );
+        anthro.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut anthro.client, self.timeout_seconds)?;
+        anthro.stop_sequences = self.generation.stop_sequences;
 
         Ok(Arc::new(anthro))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ImageMime;
+    use serde_json::json;
+
+    fn anthropic(reasoning: bool) -> Anthropic {
+        Anthropic::new(
+            "test-key",
+            None,
+            Some(1024),
+            Some(0.2),
+            None,
+            None,
+            None,
+            Some(40),
+            None,
+            Some(reasoning),
+            Some(2048),
+        )
+    }
+
+    #[test]
+    fn test_stop_sequences_are_sent_and_seed_is_rejected() {
+        let messages = [ChatMessage::user().content("Count to ten").build()];
+        let mut client = anthropic(false);
+        client.stop_sequences = vec!["5".to_string()];
+        let request = client
+            .build_completion_request(&messages, None, None, false)
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(request).unwrap()["stop_sequences"],
+            json!(["5"])
+        );
+
+        let err = LLMBuilder::<Anthropic>::new()
+            .api_key("test-key")
+            .seed(7)
+            .build()
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, LLMError::InvalidRequest(ref msg) if msg == "Anthropic does not support seed"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_thinking_request_leaves_room_for_the_answer() {
+        let messages = [ChatMessage::user().content("Why is the sky blue?").build()];
+
+        let client = anthropic(true);
+        let request = client
+            .build_completion_request(&messages, None, None, false)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(
+            request["thinking"],
+            json!({"type": "enabled", "budget_tokens": 2048})
+        );
+        assert_eq!(request["max_tokens"], 3072);
+        assert!(request.get("temperature").is_none());
+        assert!(request.get("top_k").is_none());
+
+        let client = anthropic(false);
+        let request = client
+            .build_completion_request(&messages, None, None, false)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert!(request.get("thinking").is_none());
+        assert_eq!(request["max_tokens"], 1024);
+        assert_eq!(request["top_k"], 40);
+    }
+
+    #[test]
+    fn test_message_parts_become_image_blocks() {
+        let messages = [ChatMessage::user()
+            .part(ContentPart::text("What is in these?"))
+            .part(ContentPart::image(ImageMime::JPEG, vec![1, 2, 3]))
+            .part(ContentPart::image_url("https://example.com/cat.png"))
+            .build()];
+
+        let client = anthropic(false);
+        let request = client
+            .build_completion_request(&messages, None, None, false)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(
+            request["messages"][0]["content"],
+            json!([
+                {"type": "text", "text": "What is in these?"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "AQID"}},
+                {"type": "image", "source": {"type": "url", "url": "https://example.com/cat.png"}},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_response_joins_thinking_blocks() {
+        let response: AnthropicCompleteResponse = serde_json::from_value(json!({
+            "content": [
+                {"type": "thinking", "thinking": "Light scatters.", "signature": "sig"},
+                {"type": "redacted_thinking", "data": "abc"},
+                {"type": "thinking", "thinking": "Blue scatters most.", "signature": "sig"},
+                {"type": "text", "text": "Rayleigh scattering."}
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            response.thinking().as_deref(),
+            Some("Light scatters.\nBlue scatters most.")
+        );
+        assert_eq!(response.text().as_deref(), Some("Rayleigh scattering."));
+        assert_eq!(
+            response.reasoning(),
+            vec![
+                ReasoningBlock::Thinking {
+                    thinking: "Light scatters.".into(),
+                    signature: "sig".into(),
+                },
+                ReasoningBlock::Redacted { data: "abc".into() },
+                ReasoningBlock::Thinking {
+                    thinking: "Blue scatters most.".into(),
+                    signature: "sig".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tool_use_sends_thinking_back_first() {
+        let call = ToolCall {
+            id: "toolu_1".into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: "weather".into(),
+                arguments: r#"{"city":"Paris"}"#.into(),
+            },
+        };
+        let messages = [
+            ChatMessage::user().content("Weather in Paris?").build(),
+            ChatMessage::assistant()
+                .tool_use(vec![call])
+                .reasoning(vec![
+                    ReasoningBlock::Thinking {
+                        thinking: "Check the weather.".into(),
+                        signature: "sig".into(),
+                    },
+                    ReasoningBlock::Redacted { data: "abc".into() },
+                ])
+                .build(),
+        ];
+
+        let client = anthropic(true);
+        let request = client
+            .build_completion_request(&messages, None, None, false)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(
+            request["messages"][1]["content"],
+            json!([
+                {"type": "thinking", "thinking": "Check the weather.", "signature": "sig"},
+                {"type": "redacted_thinking", "data": "abc"},
+                {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {"city": "Paris"}},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_stream_separates_thinking_text_and_tool_calls() {
+        let events = [
+            json!({"type": "message_start", "message": {"usage": {"input_tokens": 12, "output_tokens": 1}}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "Check the weather."}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "sig"}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "redacted_thinking", "data": "abc"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "text_delta", "text": "Looking it up."}}),
+            json!({"type": "content_block_start", "index": 3, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 3, "delta": {"type": "input_json_delta", "partial_json": "{\"city\":"}}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 30}}),
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("event: x\ndata: {event}\n\n"))
+            .collect();
+
+        let mut parser = AnthropicStreamParser::default();
+        let (first, rest) = body.as_bytes().split_at(100);
+        let mut chunks = parser.push(first);
+        chunks.extend(parser.push(rest));
+        let chunks: Vec<StreamResponse> = chunks.into_iter().map(Result::unwrap).collect();
+        assert_eq!(chunks.len(), 7);
+
+        let delta = |i: usize| &chunks[i].choices[0].delta;
+        assert_eq!(delta(0).thinking.as_deref(), Some("Check the weather."));
+        assert!(delta(0).content.is_none());
+        assert_eq!(
+            delta(1).reasoning.as_deref(),
+            Some(&ReasoningBlock::Thinking {
+                thinking: "Check the weather.".into(),
+                signature: "sig".into(),
+            })
+        );
+        assert_eq!(
+            delta(2).reasoning.as_deref(),
+            Some(&ReasoningBlock::Redacted { data: "abc".into() })
+        );
+        assert_eq!(delta(3).content.as_deref(), Some("Looking it up."));
+        let call = &delta(4).tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.index, 3);
+        assert_eq!(call.id.as_deref(), Some("toolu_1"));
+        assert_eq!(call.function.as_ref().unwrap().name, "weather");
+        let args = &delta(5).tool_calls.as_ref().unwrap()[0];
+        assert_eq!(args.function.as_ref().unwrap().arguments, "{\"city\":");
+        assert_eq!(chunks[6].usage.as_ref().unwrap().total_tokens, 42);
+    }
+
+    #[test]
+    fn test_output_schema_is_forced_through_a_tool() {
+        let messages = [ChatMessage::user().content("Capital of France?").build()];
+        let format = StructuredOutputFormat {
+            name: "answer".to_string(),
+            description: None,
+            schema: Some(json!({"type": "object", "properties": {"city": {"type": "string"}}})),
+            strict: None,
+        };
+
+        let client = anthropic(false);
+        let request = client
+            .build_completion_request(&messages, None, Some(&format), false)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(request["tools"][0]["name"], OUTPUT_TOOL);
+        assert_eq!(
+            request["tools"][0]["input_schema"],
+            format.schema.clone().unwrap()
+        );
+        assert_eq!(
+            request["tool_choice"],
+            json!({"type": "tool", "name": OUTPUT_TOOL})
+        );
+
+        let client = anthropic(true);
+        let request = client
+            .build_completion_request(&messages, None, Some(&format), false)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(request["tools"][0]["name"], OUTPUT_TOOL);
+        assert!(request.get("tool_choice").is_none());
+    }
+
+    #[test]
+    fn test_output_tool_call_becomes_the_answer() {
+        let mut response: AnthropicCompleteResponse = serde_json::from_value(json!({
+            "content": [
+                {"type": "tool_use", "id": "toolu_1", "name": OUTPUT_TOOL, "input": {"city": "Paris"}}
+            ]
+        }))
+        .unwrap();
+        response.output_tool_as_text();
+
+        assert_eq!(response.text().as_deref(), Some(r#"{"city":"Paris"}"#));
+        assert!(response.tool_calls().is_none());
+    }
+
+    #[test]
+    fn test_stream_turns_output_tool_input_into_text() {
+        let events = [
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use", "id": "toolu_1", "name": OUTPUT_TOOL, "input": {}}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "{\"city\":"}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "\"Paris\"}"}}),
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("data: {event}\n"))
+            .collect();
+
+        let mut parser = AnthropicStreamParser {
+            structured: true,
+            ..Default::default()
+        };
+        let text: String = parser
+            .push(body.as_bytes())
+            .into_iter()
+            .map(|chunk| {
+                let delta = &chunk.unwrap().choices[0].delta;
+                assert!(delta.tool_calls.is_none());
+                delta.content.clone().unwrap_or_default()
+            })
+            .collect();
+        assert_eq!(text, r#"{"city":"Paris"}"#);
+    }
+
+    #[test]
+    fn test_usage_includes_cached_prompt_tokens() {
+        let response: AnthropicCompleteResponse = serde_json::from_value(json!({
+            "content": [{"type": "text", "text": "Hi"}],
+            "usage": {
+                "input_tokens": 20,
+                "cache_creation_input_tokens": 30,
+                "cache_read_input_tokens": 50,
+                "output_tokens": 5
+            }
+        }))
+        .unwrap();
+
+        let usage = response.usage().unwrap();
+        assert_eq!(usage.prompt_tokens, 100);
+        assert_eq!(usage.cached_tokens(), 50);
+        assert_eq!(usage.total_tokens, 105);
+    }
+}
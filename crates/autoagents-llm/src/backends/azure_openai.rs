@@ -1,10 +1,19 @@
 //! Azure OpenAI API client implementation for chat and completion functionality.
 //!
 //! This module provides integration with Azure OpenAI's GPT models through their API.
+//! Requests go to a deployment and authenticate with either an API key or a
+//! Microsoft Entra ID access token from an [`AzureTokenProvider`].
 
+use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
 use crate::{
     builder::LLMBuilder,
-    chat::{ChatResponse, ToolChoice},
+    chat::{
+        ChatResponse, ContentPart, GenerationOptions, StreamResponse, TokenLogprob, ToolChoice,
+        Usage,
+    },
+    providers::openai_compatible::{create_sse_stream, OpenAIStreamOptions},
     FunctionCall, ToolCall,
 };
 use crate::{
@@ -19,9 +28,18 @@ use crate::{
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine as _};
 use either::*;
-use reqwest::{Client, Url};
+use futures::{Stream, StreamExt};
+use reqwest::{Client, RequestBuilder, Url};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// OAuth scope of the Azure OpenAI data plane
+const COGNITIVE_SERVICES_SCOPE: &str = "https://cognitiveservices.azure.com/.default";
+
+/// Tokens are renewed this long before they expire
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(300);
 
 /// Client for interacting with Azure OpenAI's API.
 ///
@@ -42,6 +60,14 @@ pub struct AzureOpenAI {
     pub embedding_encoding_format: Option<String>,
     pub embedding_dimensions: Option<u32>,
     pub reasoning_effort: Option<String>,
+    /// Alternatives returned with each token's log probability; no log
+    /// probabilities are requested if unset
+    pub top_logprobs: Option<u8>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    /// Entra ID token source, used instead of `api_key` when set
+    token_provider: Option<Arc<dyn AzureTokenProvider>>,
+    pub retry_policy: RetryPolicy,
     client: Client,
 }
 
@@ -75,29 +101,19 @@ impl<'a> From<&'a ChatMessage> for AzureOpenAIChatMessage<'a> {
                 MessageType::Text => Some(Right(chat_msg.content.clone())),
                 MessageType::Image((image_mime, raw_bytes)) => {
                     // Convert raw bytes to base64 data URL
-                    let base64_data = BASE64_STANDARD.encode(raw_bytes);
-                    let data_url =
-                        format!("data:{};base64,{}", image_mime.mime_type(), base64_data);
-                    let data_url_str = Box::leak(data_url.into_boxed_str());
-                    Some(Left(vec![AzureMessageContent {
-                        message_type: Some("image_url"),
-                        text: None,
-                        image_url: Some(ImageUrlContent { url: data_url_str }),
-                        tool_output: None,
-                        tool_call_id: None,
-                    }]))
+                    let data_url = format!(
+                        "data:{};base64,{}",
+                        image_mime.mime_type(),
+                        BASE64_STANDARD.encode(raw_bytes)
+                    );
+                    Some(Left(image_message_parts(&chat_msg.content, data_url)))
                 }
                 MessageType::Pdf(_) => unimplemented!(),
                 MessageType::ImageURL(url) => {
-                    // Clone the URL to create an owned version
-
-                    Some(Left(vec![AzureMessageContent {
-                        message_type: Some("image_url"),
-                        text: None,
-                        image_url: Some(ImageUrlContent { url }),
-                        tool_output: None,
-                        tool_call_id: None,
-                    }]))
+                    Some(Left(image_message_parts(&chat_msg.content, url.clone())))
+                }
+                MessageType::Parts(parts) => {
+                    Some(Left(multimodal_message_parts(&chat_msg.content, parts)))
                 }
                 MessageType::ToolUse(_) => None,
                 MessageType::ToolResult(_) => None,
@@ -154,7 +170,7 @@ struct AzureMessageContent<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     text: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    image_url: Option<ImageUrlContent<'a>>,
+    image_url: Option<ImageUrlContent>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "tool_call_id")]
     tool_call_id: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "content")]
@@ -163,8 +179,61 @@ struct AzureMessageContent<'a> {
 
 /// Individual image message in an OpenAI chat conversation.
 #[derive(Serialize, Debug)]
-struct ImageUrlContent<'a> {
-    url: &'a str,
+struct ImageUrlContent {
+    url: String,
+}
+
+/// Content parts for an image message: the accompanying text, if any, then the image
+fn image_message_parts(text: &str, url: String) -> Vec<AzureMessageContent<'_>> {
+    let mut parts = Vec::with_capacity(2);
+    if !text.is_empty() {
+        parts.push(AzureMessageContent {
+            message_type: Some("text"),
+            text: Some(text),
+            image_url: None,
+            tool_output: None,
+            tool_call_id: None,
+        });
+    }
+    parts.push(AzureMessageContent {
+        message_type: Some("image_url"),
+        text: None,
+        image_url: Some(ImageUrlContent { url }),
+        tool_output: None,
+        tool_call_id: None,
+    });
+    parts
+}
+
+/// Content parts for a multimodal message: the message text, if any, then each part in order
+fn multimodal_message_parts<'a>(
+    text: &'a str,
+    parts: &'a [ContentPart],
+) -> Vec<AzureMessageContent<'a>> {
+    let text_part = |text| AzureMessageContent {
+        message_type: Some("text"),
+        text: Some(text),
+        image_url: None,
+        tool_output: None,
+        tool_call_id: None,
+    };
+    let mut content = Vec::with_capacity(parts.len() + 1);
+    if !text.is_empty() {
+        content.push(text_part(text));
+    }
+    for part in parts {
+        content.push(match part {
+            ContentPart::Text(text) => text_part(text),
+            image => AzureMessageContent {
+                message_type: Some("image_url"),
+                text: None,
+                image_url: image.to_image_url().map(|url| ImageUrlContent { url }),
+                tool_output: None,
+                tool_call_id: None,
+            },
+        });
+    }
+    content
 }
 
 #[derive(Serialize)]
@@ -188,6 +257,8 @@ struct AzureOpenAIChatRequest<'a> {
     temperature: Option<f32>,
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAIStreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     top_k: Option<u32>,
@@ -199,18 +270,32 @@ struct AzureOpenAIChatRequest<'a> {
     reasoning_effort: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     response_format: Option<OpenAIResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
+    #[serde(flatten)]
+    generation: &'a GenerationOptions,
 }
 
 /// Response from OpenAI's chat API endpoint.
 #[derive(Deserialize, Debug)]
 struct AzureOpenAIChatResponse {
     choices: Vec<AzureOpenAIChatChoice>,
+    usage: Option<Usage>,
 }
 
 /// Individual choice within an OpenAI chat API response.
 #[derive(Deserialize, Debug)]
 struct AzureOpenAIChatChoice {
     message: AzureOpenAIChatMsg,
+    #[serde(default)]
+    logprobs: Option<AzureOpenAIChoiceLogprobs>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AzureOpenAIChoiceLogprobs {
+    content: Option<Vec<TokenLogprob>>,
 }
 
 /// Message content within an OpenAI chat API response.
@@ -296,6 +381,16 @@ impl ChatResponse for AzureOpenAIChatResponse {
             .first()
             .and_then(|c| c.message.tool_calls.clone())
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
+
+    fn logprobs(&self) -> Option<Vec<TokenLogprob>> {
+        self.choices
+            .first()
+            .and_then(|c| c.logprobs.as_ref()?.content.clone())
+    }
 }
 
 impl std::fmt::Display for AzureOpenAIChatResponse {
@@ -360,11 +455,6 @@ impl AzureOpenAI {
         tool_choice: Option<ToolChoice>,
         reasoning_effort: Option<String>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
-
         let endpoint = endpoint.into();
         let deployment_id = deployment_id.into();
 
@@ -383,8 +473,31 @@ impl AzureOpenAI {
             tool_choice,
             embedding_encoding_format,
             embedding_dimensions,
-            client: builder.build().expect("Failed to build reqwest Client"),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
             reasoning_effort,
+            top_logprobs: None,
+            generation: GenerationOptions::default(),
+            token_provider: None,
+        }
+    }
+
+    /// Authenticate with Entra ID access tokens from `provider` instead of
+    /// the API key
+    pub fn with_token_provider(mut self, provider: Arc<dyn AzureTokenProvider>) -> Self {
+        self.token_provider = Some(provider);
+        self
+    }
+
+    /// Add the credentials to `request`: a bearer token when a token
+    /// provider is set, the API key otherwise
+    async fn authorize(&self, request: RequestBuilder) -> Result<RequestBuilder, LLMError> {
+        match &self.token_provider {
+            Some(provider) => Ok(request.bearer_auth(provider.token().await?)),
+            None if self.api_key.is_empty() => Err(LLMError::AuthError(
+                "Missing Azure OpenAI API key".to_string(),
+            )),
+            None => Ok(request.header("api-key", &self.api_key)),
         }
     }
 
@@ -393,8 +506,7 @@ impl AzureOpenAI {
         messages: &'a [ChatMessage],
         tools: Option<&'a [Tool]>,
         json_schema: Option<StructuredOutputFormat>,
-        _stream: bool,
-        _stream_options: Option<AzureOpenAIChatRequest>,
+        stream: bool,
     ) -> Result<AzureOpenAIChatRequest<'a>, LLMError> {
         let mut openai_msgs: Vec<AzureOpenAIChatMessage> = vec![];
 
@@ -449,41 +561,29 @@ impl AzureOpenAI {
             messages: openai_msgs,
             max_tokens: self.max_tokens,
             temperature: self.temperature,
-            stream: false,
+            stream,
+            stream_options: stream.then_some(OpenAIStreamOptions {
+                include_usage: true,
+            }),
             top_p: self.top_p,
             top_k: self.top_k,
             tools: request_tools,
             tool_choice: request_tool_choice,
             reasoning_effort: self.reasoning_effort.clone(),
             response_format,
+            logprobs: self.top_logprobs.filter(|_| !stream).map(|_| true),
+            top_logprobs: self.top_logprobs.filter(|_| !stream),
+            generation: &self.generation,
         })
     }
 
-    /// Sends a chat request to OpenAI's API.
-    ///
-    /// # Arguments
-    ///
-    /// * `messages` - Slice of chat messages representing the conversation
-    /// * `tools` - Optional slice of tools to use in the chat
-    /// # Returns
-    ///
-    /// The model's response text or an error
-    async fn chat_with_tools(
+    /// Posts a chat request to the deployment and returns the successful response.
+    async fn send_chat(
         &self,
-        messages: &[ChatMessage],
-        tools: Option<&[Tool]>,
-        json_schema: Option<StructuredOutputFormat>,
-    ) -> Result<Box<dyn ChatResponse>, LLMError> {
-        if self.api_key.is_empty() {
-            return Err(LLMError::AuthError(
-                "Missing Azure OpenAI API key".to_string(),
-            ));
-        }
-
-        let body = self.build_chat_completion_request(messages, tools, json_schema, false, None)?;
-
+        body: &AzureOpenAIChatRequest<'_>,
+    ) -> Result<reqwest::Response, LLMError> {
         if log::log_enabled!(log::Level::Trace) {
-            if let Ok(json) = serde_json::to_string(&body) {
+            if let Ok(json) = serde_json::to_string(body) {
                 log::trace!("Azure OpenAI request payload: {json}");
             }
         }
@@ -496,30 +596,40 @@ impl AzureOpenAI {
         url.query_pairs_mut()
             .append_pair("api-version", &self.api_version);
 
-        let mut request = self
-            .client
-            .post(url)
-            .header("api-key", &self.api_key)
-            .json(&body);
+        let mut request = self.authorize(self.client.post(url).json(body)).await?;
 
         if let Some(timeout) = self.timeout_seconds {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
 
         // Send the request
-        let response = request.send().await?;
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
 
         log::debug!("Azure OpenAI HTTP status: {}", response.status());
 
-        // If we got a non-200 response, let's get the error details
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(LLMError::ResponseFormatError {
-                message: format!("OpenAI API returned error status: {status}"),
-                raw_response: error_text,
-            });
-        }
+        check_response_status(response).await
+    }
+
+    /// Sends a chat request to OpenAI's API.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - Slice of chat messages representing the conversation
+    /// * `tools` - Optional slice of tools to use in the chat
+    /// # Returns
+    ///
+    /// The model's response text or an error
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let body = self.build_chat_completion_request(messages, tools, json_schema, false)?;
+        let response = self.send_chat(&body).await?;
 
         // Parse the successful response
         let resp_text = response.text().await?;
@@ -546,6 +656,50 @@ impl ChatProvider for AzureOpenAI {
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
         self.chat_with_tools(messages, tools, json_schema).await
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<std::pin::Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError>
+    {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .filter(|content| !content.is_empty())
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text and completed tool calls, with the token usage in the last chunk.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<
+        std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>,
+        LLMError,
+    > {
+        let body = self.build_chat_completion_request(messages, tools, json_schema, true)?;
+        let response = self.send_chat(&body).await?;
+        Ok(create_sse_stream(response, true))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
 }
 
 #[async_trait]
@@ -560,6 +714,7 @@ impl CompletionProvider for AzureOpenAI {
     ) -> Result<CompletionResponse, LLMError> {
         Ok(CompletionResponse {
             text: "OpenAI completion not implemented.".into(),
+            usage: None,
         })
     }
 }
@@ -568,10 +723,6 @@ impl CompletionProvider for AzureOpenAI {
 #[async_trait]
 impl EmbeddingProvider for AzureOpenAI {
     async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
-        if self.api_key.is_empty() {
-            return Err(LLMError::AuthError("Missing OpenAI API key".into()));
-        }
-
         let emb_format = self
             .embedding_encoding_format
             .clone()
@@ -593,11 +744,10 @@ impl EmbeddingProvider for AzureOpenAI {
             .append_pair("api-version", &self.api_version);
 
         let resp = self
-            .client
-            .post(url)
-            .header("api-key", &self.api_key)
-            .json(&body)
-            .send()
+            .authorize(self.client.post(url).json(&body))
+            .await?
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
 
@@ -608,20 +758,38 @@ impl EmbeddingProvider for AzureOpenAI {
     }
 }
 
-impl LLMProvider for AzureOpenAI {}
+impl LLMProvider for AzureOpenAI {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
 
 #[async_trait]
 impl ModelsProvider for AzureOpenAI {}
 
 impl LLMBuilder<AzureOpenAI> {
+    /// Authenticate with Entra ID access tokens from `provider`; no API key
+    /// is needed then
+    pub fn token_provider(mut self, provider: impl AzureTokenProvider + 'static) -> Self {
+        self.azure_token_provider = Some(Arc::new(provider));
+        self
+    }
+
     pub fn build(self) -> Result<Arc<AzureOpenAI>, LLMError> {
         let endpoint = self.base_url.ok_or_else(|| {
             LLMError::InvalidRequest("No API endpoint provided for Azure OpenAI".into())
         })?;
 
-        let key = self.api_key.ok_or_else(|| {
-            LLMError::InvalidRequest("No API key provided for Azure OpenAI".to_string())
-        })?;
+        let key = match (self.api_key, &self.azure_token_provider) {
+            (Some(key), _) => key,
+            (None, Some(_)) => String::new(),
+            (None, None) => {
+                return Err(LLMError::InvalidRequest(
+                    "No API key provided for Azure OpenAI, and no Entra ID token provider"
+                        .to_string(),
+                ))
+            }
+        };
 
         let api_version = self.api_version.ok_or_else(|| {
             LLMError::InvalidRequest("No API version provided for Azure OpenAI".to_string())
@@ -648,7 +816,251 @@ impl LLMBuilder<AzureOpenAI> {
             self.tool_choice,
             self.reasoning_effort,
         );
+        let mut provider = match self.azure_token_provider {
+            Some(token_provider) => provider.with_token_provider(token_provider),
+            None => provider,
+        };
+        provider.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut provider.client, self.timeout_seconds)?;
+        provider.top_logprobs = self.top_logprobs;
+        provider.generation = self.generation;
 
         Ok(Arc::new(provider))
     }
 }
+
+/// Source of Microsoft Entra ID access tokens for Azure OpenAI.
+///
+/// Implementations are asked for a token before every request, so they
+/// should cache tokens until shortly before they expire, as the provided
+/// [`ClientSecretCredential`] and [`ManagedIdentityCredential`] do.
+#[async_trait]
+pub trait AzureTokenProvider: Send + Sync {
+    /// A valid access token for the `https://cognitiveservices.azure.com` scope
+    async fn token(&self) -> Result<String, LLMError>;
+}
+
+/// Entra ID token response. Managed identity endpoints send `expires_in`
+/// as a string, the login endpoint as a number.
+#[derive(Deserialize)]
+struct EntraTokenResponse {
+    access_token: String,
+    expires_in: serde_json::Value,
+}
+
+impl EntraTokenResponse {
+    fn lifetime(&self) -> Duration {
+        let secs = match &self.expires_in {
+            serde_json::Value::Number(secs) => secs.as_u64(),
+            serde_json::Value::String(secs) => secs.parse().ok(),
+            _ => None,
+        };
+        Duration::from_secs(secs.unwrap_or(0))
+    }
+}
+
+/// The last token fetched and when it should be replaced
+#[derive(Default)]
+struct TokenCache(Mutex<Option<(String, Instant)>>);
+
+impl TokenCache {
+    /// The cached token, or a new one from `fetch` once it is about to expire
+    async fn get_or_fetch<F, Fut>(&self, fetch: F) -> Result<String, LLMError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<EntraTokenResponse, LLMError>>,
+    {
+        // Held across the fetch so concurrent requests share one token request
+        let mut cached = self.0.lock().await;
+        if let Some((token, renew_at)) = cached.as_ref() {
+            if Instant::now() < *renew_at {
+                return Ok(token.clone());
+            }
+        }
+        let response = fetch().await?;
+        let renew_at = Instant::now() + response.lifetime().saturating_sub(TOKEN_REFRESH_MARGIN);
+        *cached = Some((response.access_token.clone(), renew_at));
+        Ok(response.access_token)
+    }
+}
+
+async fn read_token_response(response: reqwest::Response) -> Result<EntraTokenResponse, LLMError> {
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(LLMError::AuthError(format!(
+            "Entra ID token request failed with status {status}: {text}"
+        )));
+    }
+    serde_json::from_str(&text).map_err(|e| LLMError::ResponseFormatError {
+        message: format!("Failed to decode Entra ID token response: {e}"),
+        raw_response: text,
+    })
+}
+
+/// Tokens for a service principal, via the OAuth client credentials flow
+pub struct ClientSecretCredential {
+    tenant_id: String,
+    client_id: String,
+    client_secret: String,
+    authority: String,
+    cache: TokenCache,
+    client: Client,
+}
+
+impl ClientSecretCredential {
+    pub fn new(
+        tenant_id: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            tenant_id: tenant_id.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            authority: "https://login.microsoftonline.com".to_string(),
+            cache: TokenCache::default(),
+            client: Client::new(),
+        }
+    }
+
+    /// Read `AZURE_TENANT_ID`, `AZURE_CLIENT_ID` and `AZURE_CLIENT_SECRET`,
+    /// the variables the Azure SDKs use
+    pub fn from_env() -> Result<Self, LLMError> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .map_err(|_| LLMError::AuthError(format!("Environment variable {name} is not set")))
+        };
+        Ok(Self::new(
+            var("AZURE_TENANT_ID")?,
+            var("AZURE_CLIENT_ID")?,
+            var("AZURE_CLIENT_SECRET")?,
+        ))
+    }
+
+    /// Use another login endpoint, such as a sovereign cloud's
+    pub fn authority(mut self, authority: impl Into<String>) -> Self {
+        self.authority = authority.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl AzureTokenProvider for ClientSecretCredential {
+    async fn token(&self) -> Result<String, LLMError> {
+        self.cache
+            .get_or_fetch(|| async {
+                let url = format!("{}/{}/oauth2/v2.0/token", self.authority, self.tenant_id);
+                let response = self
+                    .client
+                    .post(url)
+                    .form(&[
+                        ("grant_type", "client_credentials"),
+                        ("client_id", self.client_id.as_str()),
+                        ("client_secret", self.client_secret.as_str()),
+                        ("scope", COGNITIVE_SERVICES_SCOPE),
+                    ])
+                    .send()
+                    .await?;
+                read_token_response(response).await
+            })
+            .await
+    }
+}
+
+/// Tokens for the managed identity of the Azure VM, container or app the
+/// agent runs on
+pub struct ManagedIdentityCredential {
+    client_id: Option<String>,
+    endpoint: String,
+    cache: TokenCache,
+    client: Client,
+}
+
+impl Default for ManagedIdentityCredential {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManagedIdentityCredential {
+    /// The system-assigned identity
+    pub fn new() -> Self {
+        Self {
+            client_id: None,
+            endpoint: "http://169.254.169.254/metadata/identity/oauth2/token".to_string(),
+            cache: TokenCache::default(),
+            client: Client::new(),
+        }
+    }
+
+    /// The user-assigned identity with this client id
+    pub fn user_assigned(client_id: impl Into<String>) -> Self {
+        Self {
+            client_id: Some(client_id.into()),
+            ..Self::new()
+        }
+    }
+
+    /// Use another token endpoint than the instance metadata service
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+}
+
+#[async_trait]
+impl AzureTokenProvider for ManagedIdentityCredential {
+    async fn token(&self) -> Result<String, LLMError> {
+        self.cache
+            .get_or_fetch(|| async {
+                let resource = COGNITIVE_SERVICES_SCOPE.trim_end_matches("/.default");
+                let mut query = vec![("api-version", "2018-02-01"), ("resource", resource)];
+                if let Some(client_id) = &self.client_id {
+                    query.push(("client_id", client_id.as_str()));
+                }
+                let response = self
+                    .client
+                    .get(&self.endpoint)
+                    .header("Metadata", "true")
+                    .query(&query)
+                    .send()
+                    .await?;
+                read_token_response(response).await
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_tokens_are_reused_until_they_are_about_to_expire() {
+        let fetches = AtomicU32::new(0);
+        let fetch = |expires_in: serde_json::Value| {
+            let fetches = &fetches;
+            move || async move {
+                let n = fetches.fetch_add(1, Ordering::SeqCst);
+                Ok(EntraTokenResponse {
+                    access_token: format!("token-{n}"),
+                    expires_in,
+                })
+            }
+        };
+
+        let cache = TokenCache::default();
+        let first = cache.get_or_fetch(fetch("3600".into())).await.unwrap();
+        let second = cache.get_or_fetch(fetch(3600.into())).await.unwrap();
+        assert_eq!((first.as_str(), second.as_str()), ("token-0", "token-0"));
+
+        // Tokens within the refresh margin are replaced straight away
+        let cache = TokenCache::default();
+        cache.get_or_fetch(fetch(60.into())).await.unwrap();
+        let renewed = cache.get_or_fetch(fetch(60.into())).await.unwrap();
+        assert_eq!(renewed, "token-2");
+    }
+}
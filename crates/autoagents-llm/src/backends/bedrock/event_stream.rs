@@ -0,0 +1,207 @@
+//! Decoder for the `application/vnd.amazon.eventstream` framing of
+//! `InvokeModelWithResponseStream` responses.
+//!
+//! Each message is a 12 byte prelude (total length, headers length and the
+//! prelude's CRC32), the headers, the payload and a CRC32 of the whole
+//! message.
+
+use crate::error::LLMError;
+use std::collections::HashMap;
+
+const PRELUDE_LEN: usize = 12;
+const CHECKSUM_LEN: usize = 4;
+/// Larger messages are treated as a corrupt stream rather than buffered
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// A decoded message. Only string headers are kept, which are all Bedrock
+/// sends.
+#[derive(Debug, PartialEq)]
+pub(super) struct Message {
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+impl Message {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+/// Collects response bytes and splits them into messages
+#[derive(Debug, Default)]
+pub(super) struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Whether bytes of an incomplete message are left over
+    pub fn has_partial_message(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /// The next complete message, if one has been received
+    pub fn next_message(&mut self) -> Result<Option<Message>, LLMError> {
+        if self.buffer.len() < PRELUDE_LEN {
+            return Ok(None);
+        }
+        let total_len = read_u32(&self.buffer[0..4]) as usize;
+        let headers_len = read_u32(&self.buffer[4..8]) as usize;
+        if read_u32(&self.buffer[8..12]) != crc32(&self.buffer[..8]) {
+            return Err(corrupt("prelude checksum mismatch"));
+        }
+        if total_len > MAX_MESSAGE_LEN || PRELUDE_LEN + headers_len + CHECKSUM_LEN > total_len {
+            return Err(corrupt("invalid message length"));
+        }
+        if self.buffer.len() < total_len {
+            return Ok(None);
+        }
+
+        let frame: Vec<u8> = self.buffer.drain(..total_len).collect();
+        let body_end = total_len - CHECKSUM_LEN;
+        if read_u32(&frame[body_end..]) != crc32(&frame[..body_end]) {
+            return Err(corrupt("message checksum mismatch"));
+        }
+        let headers_end = PRELUDE_LEN + headers_len;
+        Ok(Some(Message {
+            headers: parse_headers(&frame[PRELUDE_LEN..headers_end])?,
+            payload: frame[headers_end..body_end].to_vec(),
+        }))
+    }
+}
+
+fn parse_headers(mut bytes: &[u8]) -> Result<HashMap<String, String>, LLMError> {
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_len = bytes[0] as usize;
+        let name = take(&mut bytes, 1 + name_len)?[1..].to_vec();
+        let value_type = take(&mut bytes, 1)?[0];
+        // Value lengths by type: booleans have none, bytes and strings a
+        // length prefix, and the rest a fixed size
+        let value_len = match value_type {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            6 | 7 => match bytes {
+                [high, low, ..] => 2 + u16::from_be_bytes([*high, *low]) as usize,
+                _ => return Err(corrupt("truncated headers")),
+            },
+            other => return Err(corrupt(&format!("unknown header type {other}"))),
+        };
+        let value = take(&mut bytes, value_len)?;
+        if value_type == 7 {
+            headers.insert(
+                String::from_utf8_lossy(&name).into_owned(),
+                String::from_utf8_lossy(&value[2..]).into_owned(),
+            );
+        }
+    }
+    Ok(headers)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], LLMError> {
+    if bytes.len() < len {
+        return Err(corrupt("truncated headers"));
+    }
+    let (head, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(head)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn corrupt(reason: &str) -> LLMError {
+    LLMError::ResponseFormatError {
+        message: format!("Malformed Bedrock event stream: {reason}"),
+        raw_response: String::new(),
+    }
+}
+
+/// CRC-32 (IEEE), as used by the event stream checksums
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Frame `payload` as an event stream message with string headers
+    pub(in crate::backends::bedrock) fn encode(
+        headers: &[(&str, &str)],
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let total_len = PRELUDE_LEN + header_bytes.len() + payload.len() + CHECKSUM_LEN;
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(total_len as u32).to_be_bytes());
+        frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&crc32(&frame).to_be_bytes());
+        frame.extend_from_slice(&header_bytes);
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(&crc32(&frame).to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_messages_split_across_chunks_are_reassembled() {
+        let first = encode(&[(":event-type", "chunk")], b"{\"bytes\":\"e30=\"}");
+        let second = encode(&[(":message-type", "exception")], b"{}");
+        let bytes = [first.clone(), second].concat();
+
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&bytes[..first.len() - 3]);
+        assert_eq!(decoder.next_message().unwrap(), None);
+
+        decoder.push(&bytes[first.len() - 3..]);
+        let message = decoder.next_message().unwrap().unwrap();
+        assert_eq!(message.header(":event-type"), Some("chunk"));
+        assert_eq!(message.payload, b"{\"bytes\":\"e30=\"}");
+
+        let message = decoder.next_message().unwrap().unwrap();
+        assert_eq!(message.header(":message-type"), Some("exception"));
+        assert!(!decoder.has_partial_message());
+    }
+
+    #[test]
+    fn test_corrupt_messages_are_rejected() {
+        let mut frame = encode(&[(":event-type", "chunk")], b"payload");
+        let last = frame.len() - 6;
+        frame[last] ^= 0xFF;
+
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&frame);
+        assert!(decoder.next_message().is_err());
+    }
+}
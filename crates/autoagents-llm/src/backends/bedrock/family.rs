@@ -0,0 +1,437 @@
+//! Native request and response formats of the model families Bedrock hosts.
+
+use super::Bedrock;
+use crate::chat::{
+    ChatMessage, ChatResponse, ChatRole, ContentPart, ImageMime, MessageType, StreamChoice,
+    StreamDelta, StreamResponse, StreamToolCallDelta, StreamToolCallFunction, Tool, ToolChoice,
+    Usage,
+};
+use crate::error::LLMError;
+use crate::{FunctionCall, ToolCall};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::{json, Value};
+
+/// Model families with their own `InvokeModel` body format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    /// Anthropic Claude, through the Messages API. The only family with tool use.
+    Anthropic,
+    /// Meta Llama 3, prompted with its chat template
+    Llama,
+    /// Amazon Titan Text, prompted with a `User:`/`Bot:` transcript
+    Titan,
+}
+
+impl ModelFamily {
+    /// The family of a model id, cross-region inference profile id such as
+    /// `us.anthropic.claude-3-5-sonnet-20241022-v2:0`, or model ARN
+    pub fn from_model_id(model: &str) -> Option<Self> {
+        let id = model.rsplit('/').next().unwrap_or(model);
+        if id.contains("anthropic.claude") {
+            Some(ModelFamily::Anthropic)
+        } else if id.contains("meta.llama") {
+            Some(ModelFamily::Llama)
+        } else if id.contains("amazon.titan-text") {
+            Some(ModelFamily::Titan)
+        } else {
+            None
+        }
+    }
+
+    pub fn supports_tools(self) -> bool {
+        self == ModelFamily::Anthropic
+    }
+
+    /// The `InvokeModel` body for a chat
+    pub(super) fn request_body(
+        self,
+        bedrock: &Bedrock,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Value, LLMError> {
+        let tools = tools.filter(|tools| !tools.is_empty());
+        if tools.is_some() && !self.supports_tools() {
+            return Err(LLMError::NoToolSupport(format!(
+                "Bedrock model '{}' does not support tool use",
+                bedrock.model
+            )));
+        }
+        if self == ModelFamily::Llama && !bedrock.stop_sequences.is_empty() {
+            return Err(LLMError::InvalidRequest(format!(
+                "Bedrock model '{}' does not support stop sequences",
+                bedrock.model
+            )));
+        }
+        let system = system_prompt(bedrock, messages);
+        let mut body = match self {
+            ModelFamily::Anthropic => {
+                let mut body = json!({
+                    "anthropic_version": "bedrock-2023-05-31",
+                    "max_tokens": bedrock.max_tokens,
+                    "messages": claude_messages(messages)?,
+                });
+                if let Some(system) = system {
+                    body["system"] = json!(system);
+                }
+                if let Some(top_k) = bedrock.top_k {
+                    body["top_k"] = json!(top_k);
+                }
+                // Choosing no tool is done by not offering any
+                if let Some(tools) =
+                    tools.filter(|_| !matches!(bedrock.tool_choice, Some(ToolChoice::None)))
+                {
+                    body["tools"] = tools
+                        .iter()
+                        .map(|tool| {
+                            json!({
+                                "name": tool.function.name,
+                                "description": tool.function.description,
+                                "input_schema": tool.function.parameters,
+                            })
+                        })
+                        .collect();
+                    match &bedrock.tool_choice {
+                        Some(ToolChoice::Any) => body["tool_choice"] = json!({"type": "any"}),
+                        Some(ToolChoice::Tool(name)) => {
+                            body["tool_choice"] = json!({"type": "tool", "name": name})
+                        }
+                        _ => {}
+                    }
+                }
+                body
+            }
+            ModelFamily::Llama => json!({
+                "prompt": llama_prompt(system.as_deref(), messages)?,
+                "max_gen_len": bedrock.max_tokens,
+            }),
+            ModelFamily::Titan => json!({
+                "inputText": titan_prompt(system.as_deref(), messages)?,
+                "textGenerationConfig": {"maxTokenCount": bedrock.max_tokens},
+            }),
+        };
+
+        let sampling = match self {
+            ModelFamily::Titan => &mut body["textGenerationConfig"],
+            _ => &mut body,
+        };
+        if let Some(temperature) = bedrock.temperature {
+            sampling["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = bedrock.top_p {
+            let key = if self == ModelFamily::Titan {
+                "topP"
+            } else {
+                "top_p"
+            };
+            sampling[key] = json!(top_p);
+        }
+        if !bedrock.stop_sequences.is_empty() {
+            let key = if self == ModelFamily::Titan {
+                "stopSequences"
+            } else {
+                "stop_sequences"
+            };
+            sampling[key] = json!(bedrock.stop_sequences);
+        }
+        Ok(body)
+    }
+
+    /// Read an `InvokeModel` response
+    pub(super) fn parse_response(self, body: &Value) -> BedrockChatResponse {
+        match self {
+            ModelFamily::Anthropic => {
+                let blocks = body["content"].as_array().cloned().unwrap_or_default();
+                let text: Vec<&str> = blocks
+                    .iter()
+                    .filter(|block| block["type"] == "text")
+                    .filter_map(|block| block["text"].as_str())
+                    .collect();
+                let tool_calls = blocks
+                    .iter()
+                    .filter(|block| block["type"] == "tool_use")
+                    .map(|block| ToolCall {
+                        id: block["id"].as_str().unwrap_or_default().to_string(),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: block["name"].as_str().unwrap_or_default().to_string(),
+                            arguments: block["input"].to_string(),
+                        },
+                    })
+                    .collect();
+                BedrockChatResponse {
+                    text: (!text.is_empty()).then(|| text.join("\n")),
+                    tool_calls,
+                    usage: usage(
+                        &body["usage"]["input_tokens"],
+                        &body["usage"]["output_tokens"],
+                    ),
+                }
+            }
+            ModelFamily::Llama => BedrockChatResponse {
+                text: body["generation"].as_str().map(str::to_string),
+                tool_calls: Vec::new(),
+                usage: usage(&body["prompt_token_count"], &body["generation_token_count"]),
+            },
+            ModelFamily::Titan => BedrockChatResponse {
+                text: body["results"][0]["outputText"]
+                    .as_str()
+                    .map(str::to_string),
+                tool_calls: Vec::new(),
+                usage: usage(
+                    &body["inputTextTokenCount"],
+                    &body["results"][0]["tokenCount"],
+                ),
+            },
+        }
+    }
+
+    /// Read a chunk of an `InvokeModelWithResponseStream` response, `None`
+    /// if it carries nothing of interest
+    pub(super) fn parse_stream_chunk(self, chunk: &Value) -> Option<StreamResponse> {
+        let mut delta = StreamDelta {
+            content: None,
+            tool_calls: None,
+            thinking: None,
+            reasoning: None,
+        };
+        match self {
+            ModelFamily::Anthropic => {
+                let index = chunk["index"].as_u64().unwrap_or_default() as usize;
+                let tool_call = |id: Option<&str>, name: &str, arguments: &str| {
+                    Some(vec![StreamToolCallDelta {
+                        index,
+                        id: id.map(str::to_string),
+                        function: Some(StreamToolCallFunction {
+                            name: name.to_string(),
+                            arguments: arguments.to_string(),
+                        }),
+                    }])
+                };
+                match chunk["type"].as_str() {
+                    Some("content_block_start") if chunk["content_block"]["type"] == "tool_use" => {
+                        let block = &chunk["content_block"];
+                        let name = block["name"].as_str().unwrap_or_default();
+                        delta.tool_calls = tool_call(block["id"].as_str(), name, "");
+                    }
+                    Some("content_block_delta") => match chunk["delta"]["type"].as_str() {
+                        Some("text_delta") => {
+                            delta.content = chunk["delta"]["text"].as_str().map(str::to_string)
+                        }
+                        Some("input_json_delta") => {
+                            let json = chunk["delta"]["partial_json"].as_str().unwrap_or_default();
+                            delta.tool_calls = tool_call(None, "", json);
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            ModelFamily::Llama => delta.content = chunk["generation"].as_str().map(str::to_string),
+            ModelFamily::Titan => delta.content = chunk["outputText"].as_str().map(str::to_string),
+        }
+        delta.content = delta.content.filter(|content| !content.is_empty());
+
+        // Bedrock adds the token counts to the last chunk of every model
+        let metrics = &chunk["amazon-bedrock-invocationMetrics"];
+        let usage = usage(&metrics["inputTokenCount"], &metrics["outputTokenCount"]);
+        let choices = if delta.content.is_some() || delta.tool_calls.is_some() {
+            vec![StreamChoice { delta }]
+        } else {
+            Vec::new()
+        };
+        (!choices.is_empty() || usage.is_some()).then_some(StreamResponse { choices, usage })
+    }
+}
+
+/// Chat response of any model family
+#[derive(Debug)]
+pub(super) struct BedrockChatResponse {
+    text: Option<String>,
+    tool_calls: Vec<ToolCall>,
+    usage: Option<Usage>,
+}
+
+impl ChatResponse for BedrockChatResponse {
+    fn text(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        (!self.tool_calls.is_empty()).then(|| self.tool_calls.clone())
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
+}
+
+impl std::fmt::Display for BedrockChatResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(text) = &self.text {
+            write!(f, "{text}")?;
+        }
+        for call in &self.tool_calls {
+            write!(
+                f,
+                "{{\n \"name\": {}, \"input\": {}\n}}",
+                call.function.name, call.function.arguments
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn usage(input: &Value, output: &Value) -> Option<Usage> {
+    Some(Usage::new(input.as_u64()? as u32, output.as_u64()? as u32))
+}
+
+/// System messages of the conversation, or else the configured prompt
+fn system_prompt(bedrock: &Bedrock, messages: &[ChatMessage]) -> Option<String> {
+    let system: Vec<&str> = messages
+        .iter()
+        .filter(|message| message.role == ChatRole::System)
+        .map(|message| message.content.as_str())
+        .collect();
+    if system.is_empty() {
+        bedrock.system.clone()
+    } else {
+        Some(system.join("\n\n"))
+    }
+}
+
+fn claude_messages(messages: &[ChatMessage]) -> Result<Vec<Value>, LLMError> {
+    let mut claude = Vec::new();
+    for message in messages {
+        let role = match message.role {
+            ChatRole::System => continue,
+            ChatRole::User | ChatRole::Tool => "user",
+            ChatRole::Assistant => "assistant",
+        };
+        let text =
+            (!message.content.is_empty()).then(|| json!({"type": "text", "text": message.content}));
+        let content: Vec<Value> = match &message.message_type {
+            MessageType::Text => vec![json!({"type": "text", "text": message.content})],
+            MessageType::Image((mime, bytes)) => std::iter::once(claude_image(mime, bytes))
+                .chain(text)
+                .collect(),
+            MessageType::Parts(parts) => text
+                .into_iter()
+                .map(Ok)
+                .chain(parts.iter().map(|part| match part {
+                    ContentPart::Text(text) => Ok(json!({"type": "text", "text": text})),
+                    ContentPart::Image { mime, data } => Ok(claude_image(mime, data)),
+                    ContentPart::ImageUrl { .. } => Err(image_url_unsupported()),
+                }))
+                .collect::<Result<_, _>>()?,
+            MessageType::Pdf(bytes) => {
+                let document = json!({
+                    "type": "document",
+                    "source": {
+                        "type": "base64",
+                        "media_type": "application/pdf",
+                        "data": BASE64.encode(bytes),
+                    },
+                });
+                std::iter::once(document).chain(text).collect()
+            }
+            MessageType::ImageURL(_) => return Err(image_url_unsupported()),
+            MessageType::ToolUse(calls) => text
+                .into_iter()
+                .chain(calls.iter().map(|call| {
+                    json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.function.name,
+                        "input": serde_json::from_str::<Value>(&call.function.arguments)
+                            .unwrap_or_else(|_| json!({})),
+                    })
+                }))
+                .collect(),
+            MessageType::ToolResult(results) => results
+                .iter()
+                .map(|result| {
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": result.id,
+                        "content": result.function.arguments,
+                    })
+                })
+                .collect(),
+        };
+        claude.push(json!({"role": role, "content": content}));
+    }
+    Ok(claude)
+}
+
+/// Base64 image block of a Claude message
+fn claude_image(mime: &ImageMime, bytes: &[u8]) -> Value {
+    json!({
+        "type": "image",
+        "source": {
+            "type": "base64",
+            "media_type": mime.mime_type(),
+            "data": BASE64.encode(bytes),
+        },
+    })
+}
+
+/// Bedrock takes images inline only
+fn image_url_unsupported() -> LLMError {
+    LLMError::InvalidRequest(
+        "Bedrock does not fetch image URLs; send the image bytes instead".to_string(),
+    )
+}
+
+/// Text of a message for the families prompted with plain text
+fn plain_text(message: &ChatMessage) -> Result<String, LLMError> {
+    match &message.message_type {
+        MessageType::Text | MessageType::ToolUse(_) => Ok(message.content.clone()),
+        MessageType::ToolResult(results) => Ok(results
+            .iter()
+            .map(|result| result.function.arguments.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")),
+        _ => Err(LLMError::InvalidRequest(
+            "Only Claude models accept images and documents on Bedrock".to_string(),
+        )),
+    }
+}
+
+/// The Llama 3 chat template, ending with the assistant's turn
+fn llama_prompt(system: Option<&str>, messages: &[ChatMessage]) -> Result<String, LLMError> {
+    let turn = |role: &str, text: &str| {
+        format!("<|start_header_id|>{role}<|end_header_id|>\n\n{text}<|eot_id|>")
+    };
+    let mut prompt = "<|begin_of_text|>".to_string();
+    if let Some(system) = system {
+        prompt.push_str(&turn("system", system));
+    }
+    for message in messages {
+        let role = match message.role {
+            ChatRole::System => continue,
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+            ChatRole::Tool => "ipython",
+        };
+        prompt.push_str(&turn(role, &plain_text(message)?));
+    }
+    prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+    Ok(prompt)
+}
+
+/// A `User:`/`Bot:` transcript, ending with the bot's turn
+fn titan_prompt(system: Option<&str>, messages: &[ChatMessage]) -> Result<String, LLMError> {
+    let mut prompt = system
+        .map(|system| format!("{system}\n\n"))
+        .unwrap_or_default();
+    for message in messages {
+        let speaker = match message.role {
+            ChatRole::System => continue,
+            ChatRole::User | ChatRole::Tool => "User",
+            ChatRole::Assistant => "Bot",
+        };
+        prompt.push_str(&format!("{speaker}: {}\n", plain_text(message)?));
+    }
+    prompt.push_str("Bot:");
+    Ok(prompt)
+}
@@ -0,0 +1,743 @@
+//! AWS Bedrock client implementation for chat, streaming and embeddings.
+//!
+//! Calls the Bedrock runtime `InvokeModel` and `InvokeModelWithResponseStream`
+//! APIs with SigV4-signed requests. Anthropic Claude, Meta Llama and Amazon
+//! Titan text models are supported, each in its native request format, and
+//! tool use is available with Claude. Embeddings use Amazon Titan embedding
+//! models.
+
+mod event_stream;
+mod family;
+mod sigv4;
+
+pub use family::ModelFamily;
+pub use sigv4::AwsCredentials;
+
+use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
+use crate::{
+    builder::LLMBuilder,
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, StreamResponse, StructuredOutputFormat, Tool,
+        ToolChoice,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    LLMProvider,
+};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::Utc;
+use event_stream::{EventStreamDecoder, Message};
+use futures::stream::{self, Stream, StreamExt};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::Value;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Client for models hosted on AWS Bedrock.
+pub struct Bedrock {
+    pub region: String,
+    /// Model id, inference profile id or model ARN
+    pub model: String,
+    pub max_tokens: u32,
+    pub temperature: Option<f32>,
+    pub system: Option<String>,
+    pub timeout_seconds: Option<u64>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub tool_choice: Option<ToolChoice>,
+    /// Embedding parameters
+    pub embedding_dimensions: Option<u32>,
+    /// Sequences that end generation, which Llama models do not take
+    pub stop_sequences: Vec<String>,
+    credentials: AwsCredentials,
+    endpoint: Url,
+    pub retry_policy: RetryPolicy,
+    client: Client,
+}
+
+/// Parse an endpoint URL, with or without a trailing slash
+fn parse_endpoint(endpoint: &str) -> Result<Url, LLMError> {
+    Url::parse(&format!("{}/", endpoint.trim_end_matches('/')))
+        .map_err(|e| LLMError::InvalidRequest(format!("Invalid Bedrock endpoint: {e}")))
+}
+
+#[derive(Deserialize)]
+struct TitanEmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+impl Bedrock {
+    /// Creates a new Bedrock client with the specified configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `credentials` - AWS credentials requests are signed with
+    /// * `region` - AWS region of the Bedrock runtime, e.g. "us-east-1"
+    /// * `endpoint` - Bedrock runtime endpoint, see [`Bedrock::region_endpoint`]
+    /// * `model` - Model to use (defaults to "anthropic.claude-3-5-sonnet-20240620-v1:0")
+    /// * `max_tokens` - Maximum tokens to generate (defaults to 1024)
+    /// * `temperature` - Sampling temperature
+    /// * `timeout_seconds` - Request timeout in seconds
+    /// * `system` - System prompt
+    /// * `top_p` - Top-p sampling parameter
+    /// * `top_k` - Top-k sampling parameter
+    /// * `tool_choice` - Determines how the model uses tools
+    /// * `embedding_dimensions` - Dimensions for embedding vectors
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        credentials: AwsCredentials,
+        region: impl Into<String>,
+        endpoint: Url,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        timeout_seconds: Option<u64>,
+        system: Option<String>,
+        top_p: Option<f32>,
+        top_k: Option<u32>,
+        tool_choice: Option<ToolChoice>,
+        embedding_dimensions: Option<u32>,
+    ) -> Self {
+        Self {
+            endpoint,
+            region: region.into(),
+            model: model.unwrap_or_else(|| "anthropic.claude-3-5-sonnet-20240620-v1:0".into()),
+            max_tokens: max_tokens.unwrap_or(1024),
+            temperature,
+            system,
+            timeout_seconds,
+            top_p,
+            top_k,
+            tool_choice,
+            embedding_dimensions,
+            stop_sequences: Vec::new(),
+            credentials,
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
+        }
+    }
+
+    /// The public Bedrock runtime endpoint of `region`
+    pub fn region_endpoint(region: &str) -> Result<Url, LLMError> {
+        parse_endpoint(&format!("https://bedrock-runtime.{region}.amazonaws.com"))
+    }
+
+    /// Send requests to `endpoint` instead of the region's public endpoint,
+    /// e.g. a VPC endpoint
+    pub fn with_endpoint(mut self, endpoint: &str) -> Result<Self, LLMError> {
+        self.endpoint = parse_endpoint(endpoint)?;
+        Ok(self)
+    }
+
+    fn family(&self) -> Result<ModelFamily, LLMError> {
+        ModelFamily::from_model_id(&self.model).ok_or_else(|| {
+            LLMError::InvalidRequest(format!(
+                "Bedrock model '{}' is not a supported chat model; use Anthropic Claude, Meta Llama or Amazon Titan Text",
+                self.model
+            ))
+        })
+    }
+
+    /// Sign and send `body` to the model's `action` endpoint
+    async fn invoke(
+        &self,
+        action: &str,
+        accept: &str,
+        body: &Value,
+    ) -> Result<reqwest::Response, LLMError> {
+        let payload = serde_json::to_vec(body)?;
+        if log::log_enabled!(log::Level::Trace) {
+            log::trace!("Bedrock request payload: {body}");
+        }
+
+        let mut url = self.endpoint.clone();
+        // Model ids contain `:`, which must be sent encoded
+        url.set_path(&format!(
+            "{}model/{}/{action}",
+            self.endpoint.path(),
+            sigv4::uri_encode(&self.model)
+        ));
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(LLMError::InvalidRequest(
+                    "Bedrock endpoint has no host".into(),
+                ))
+            }
+        };
+        let signature = sigv4::sign(
+            &self.credentials,
+            &sigv4::SigningRequest {
+                method: "POST",
+                host: &host,
+                path: url.path(),
+                payload: &payload,
+                region: &self.region,
+                service: "bedrock",
+            },
+            Utc::now(),
+        );
+
+        log::debug!("Bedrock request: POST {}", url.path());
+        let mut request = self
+            .client
+            .post(url)
+            .header("content-type", "application/json")
+            .header("accept", accept)
+            .body(payload);
+        for (name, value) in signature {
+            request = request.header(name, value);
+        }
+        if let Some(timeout) = self.timeout_seconds {
+            request = request.timeout(std::time::Duration::from_secs(timeout));
+        }
+
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        log::debug!("Bedrock HTTP status: {}", response.status());
+        check_response_status(response).await
+    }
+}
+
+/// Turn an event stream response into the model's stream chunks
+fn stream_chunks(
+    response: reqwest::Response,
+    family: ModelFamily,
+) -> Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>> {
+    let state = Some((
+        Box::pin(response.bytes_stream()),
+        EventStreamDecoder::default(),
+    ));
+    let messages = stream::unfold(state, |state| async move {
+        let (mut body, mut decoder) = state?;
+        loop {
+            match decoder.next_message() {
+                Ok(Some(message)) => return Some((Ok(message), Some((body, decoder)))),
+                Ok(None) => {}
+                Err(e) => return Some((Err(e), None)),
+            }
+            match body.next().await {
+                Some(Ok(bytes)) => decoder.push(&bytes),
+                Some(Err(e)) => return Some((Err(e.into()), None)),
+                None if decoder.has_partial_message() => {
+                    let error = LLMError::HttpError("Bedrock stream ended mid-message".into());
+                    return Some((Err(error), None));
+                }
+                None => return None,
+            }
+        }
+    });
+    let chunks = messages.filter_map(move |message| async move {
+        match message.and_then(|message| chunk_payload(&message)) {
+            Ok(Some(chunk)) => family.parse_stream_chunk(&chunk).map(Ok),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    });
+    Box::pin(chunks)
+}
+
+/// The model's JSON chunk carried by an event stream message, failing on
+/// exceptions sent mid-stream
+fn chunk_payload(message: &Message) -> Result<Option<Value>, LLMError> {
+    if message.header(":message-type") == Some("exception") {
+        return Err(LLMError::ProviderError(format!(
+            "Bedrock {}: {}",
+            message.header(":exception-type").unwrap_or("exception"),
+            String::from_utf8_lossy(&message.payload)
+        )));
+    }
+    if message.header(":event-type") != Some("chunk") {
+        return Ok(None);
+    }
+
+    #[derive(Deserialize)]
+    struct Chunk {
+        bytes: String,
+    }
+    let chunk: Chunk = serde_json::from_slice(&message.payload)?;
+    let bytes = BASE64
+        .decode(chunk.bytes)
+        .map_err(|e| LLMError::ResponseFormatError {
+            message: format!("Failed to decode Bedrock stream chunk: {e}"),
+            raw_response: String::from_utf8_lossy(&message.payload).into_owned(),
+        })?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
+
+#[async_trait]
+impl ChatProvider for Bedrock {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        _json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let family = self.family()?;
+        let body = family.request_body(self, messages, tools)?;
+        let response = self.invoke("invoke", "application/json", &body).await?;
+
+        let text = response.text().await?;
+        let json: Value =
+            serde_json::from_str(&text).map_err(|e| LLMError::ResponseFormatError {
+                message: format!("Failed to decode Bedrock response: {e}"),
+                raw_response: text.clone(),
+            })?;
+        Ok(Box::new(family.parse_response(&json)))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text and, for Claude, tool calls. The last chunk carries the
+    /// token usage.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        _json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        let family = self.family()?;
+        let body = family.request_body(self, messages, tools)?;
+        let response = self
+            .invoke(
+                "invoke-with-response-stream",
+                "application/vnd.amazon.eventstream",
+                &body,
+            )
+            .await?;
+        Ok(stream_chunks(response, family))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for Bedrock {
+    /// Sends the prompt as a single user message.
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let messages = [ChatMessage::user().content(req.prompt.clone()).build()];
+        let response = self.chat(&messages, None, json_schema).await?;
+        Ok(CompletionResponse::from_chat(response.as_ref()))
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for Bedrock {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        if !self.model.contains("amazon.titan-embed") {
+            return Err(LLMError::ProviderError(format!(
+                "Bedrock embeddings need an Amazon Titan embedding model, not '{}'",
+                self.model
+            )));
+        }
+
+        // Titan embeds one text per request
+        let mut embeddings = Vec::with_capacity(input.len());
+        for text in input {
+            let mut body = serde_json::json!({ "inputText": text });
+            if let Some(dimensions) = self.embedding_dimensions {
+                body["dimensions"] = dimensions.into();
+            }
+            let response: TitanEmbeddingResponse = self
+                .invoke("invoke", "application/json", &body)
+                .await?
+                .json()
+                .await?;
+            embeddings.push(response.embedding);
+        }
+        Ok(embeddings)
+    }
+}
+
+impl LLMProvider for Bedrock {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for Bedrock {}
+
+impl LLMBuilder<Bedrock> {
+    /// Sign requests with `credentials` instead of those in the `AWS_*`
+    /// environment variables
+    pub fn aws_credentials(mut self, credentials: AwsCredentials) -> Self {
+        self.aws_credentials = Some(credentials);
+        self
+    }
+
+    /// AWS region to call, instead of `AWS_REGION` or `AWS_DEFAULT_REGION`
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.aws_region = Some(region.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Arc<Bedrock>, LLMError> {
+        self.generation.reject_unsupported(
+            "Bedrock",
+            &["seed", "frequency_penalty", "presence_penalty"],
+        )?;
+        let credentials = self
+            .aws_credentials
+            .or_else(AwsCredentials::from_env)
+            .ok_or_else(|| {
+                LLMError::InvalidRequest("No AWS credentials provided for Bedrock".to_string())
+            })?;
+
+        let region = self
+            .aws_region
+            .or_else(|| std::env::var("AWS_REGION").ok())
+            .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+            .ok_or_else(|| {
+                LLMError::InvalidRequest("No AWS region provided for Bedrock".to_string())
+            })?;
+
+        let endpoint = match self.base_url {
+            Some(endpoint) => parse_endpoint(&endpoint)?,
+            None => Bedrock::region_endpoint(&region)?,
+        };
+
+        let mut bedrock = Bedrock::new(
+            credentials,
+            region,
+            endpoint,
+            self.model,
+            self.max_tokens,
+            self.temperature,
+            self.timeout_seconds,
+            self.system,
+            self.top_p,
+            self.top_k,
+            self.tool_choice,
+            self.embedding_dimensions,
+        );
+        bedrock.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut bedrock.client, self.timeout_seconds)?;
+        bedrock.stop_sequences = self.generation.stop_sequences;
+
+        Ok(Arc::new(bedrock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ChatRole, FunctionTool, MessageType};
+    use crate::{FunctionCall, ToolCall};
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn bedrock(model: &str) -> Bedrock {
+        Bedrock::new(
+            AwsCredentials::new("AKIDEXAMPLE", "secret"),
+            "us-east-1",
+            Bedrock::region_endpoint("us-east-1").unwrap(),
+            Some(model.to_string()),
+            Some(256),
+            Some(0.5),
+            None,
+            Some("Be brief.".to_string()),
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn weather_tool() -> Tool {
+        Tool {
+            tool_type: "function".to_string(),
+            function: FunctionTool {
+                name: "get_weather".to_string(),
+                description: "Current weather".to_string(),
+                parameters: json!({"type": "object"}),
+            },
+        }
+    }
+
+    #[test]
+    fn test_model_families_are_detected() {
+        let family = ModelFamily::from_model_id;
+        assert_eq!(
+            family("us.anthropic.claude-3-5-sonnet-20241022-v2:0"),
+            Some(ModelFamily::Anthropic)
+        );
+        assert_eq!(
+            family("arn:aws:bedrock:us-east-1::foundation-model/meta.llama3-8b-instruct-v1:0"),
+            Some(ModelFamily::Llama)
+        );
+        assert_eq!(
+            family("amazon.titan-text-express-v1"),
+            Some(ModelFamily::Titan)
+        );
+        assert_eq!(family("amazon.titan-embed-text-v2:0"), None);
+    }
+
+    #[test]
+    fn test_claude_requests_carry_tools_and_tool_results() {
+        let client = bedrock("anthropic.claude-3-haiku-20240307-v1:0");
+        let call = ToolCall {
+            id: "toolu_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "get_weather".to_string(),
+                arguments: json!({"city": "Oslo"}).to_string(),
+            },
+        };
+        let messages = [
+            ChatMessage::user().content("Weather in Oslo?").build(),
+            ChatMessage::assistant()
+                .tool_use(vec![call.clone()])
+                .build(),
+            ChatMessage {
+                role: ChatRole::Tool,
+                message_type: MessageType::ToolResult(vec![ToolCall {
+                    function: FunctionCall {
+                        arguments: "Sunny".to_string(),
+                        ..call.function.clone()
+                    },
+                    ..call
+                }]),
+                content: String::new(),
+                reasoning: Vec::new(),
+            },
+        ];
+
+        let body = ModelFamily::Anthropic
+            .request_body(&client, &messages, Some(&[weather_tool()]))
+            .unwrap();
+        assert_eq!(body["anthropic_version"], "bedrock-2023-05-31");
+        assert_eq!(body["system"], "Be brief.");
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["tools"][0]["name"], "get_weather");
+        assert_eq!(
+            body["messages"][1]["content"][0],
+            json!({"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Oslo"}})
+        );
+        assert_eq!(
+            body["messages"][2],
+            json!({"role": "user", "content": [{"type": "tool_result", "tool_use_id": "toolu_1", "content": "Sunny"}]})
+        );
+    }
+
+    #[test]
+    fn test_llama_and_titan_are_prompted_with_text() {
+        let messages = [ChatMessage::user().content("Hi").build()];
+
+        let body = ModelFamily::Llama
+            .request_body(&bedrock("meta.llama3-8b-instruct-v1:0"), &messages, None)
+            .unwrap();
+        assert_eq!(
+            body["prompt"],
+            "<|begin_of_text|><|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+        assert_eq!(body["max_gen_len"], 256);
+
+        let titan = bedrock("amazon.titan-text-express-v1");
+        let body = ModelFamily::Titan
+            .request_body(&titan, &messages, None)
+            .unwrap();
+        assert_eq!(body["inputText"], "Be brief.\n\nUser: Hi\nBot:");
+        assert_eq!(body["textGenerationConfig"]["temperature"], 0.5);
+
+        let mut titan = titan;
+        titan.stop_sequences = vec!["User:".to_string()];
+        let body = ModelFamily::Titan
+            .request_body(&titan, &messages, None)
+            .unwrap();
+        assert_eq!(
+            body["textGenerationConfig"]["stopSequences"],
+            json!(["User:"])
+        );
+
+        let tools = [weather_tool()];
+        let error = ModelFamily::Titan
+            .request_body(&titan, &messages, Some(&tools))
+            .unwrap_err();
+        assert!(matches!(error, LLMError::NoToolSupport(_)));
+    }
+
+    #[test]
+    fn test_claude_responses_are_parsed() {
+        let response = ModelFamily::Anthropic.parse_response(&json!({
+            "content": [
+                {"type": "text", "text": "Checking."},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Oslo"}},
+            ],
+            "usage": {"input_tokens": 12, "output_tokens": 8},
+        }));
+        assert_eq!(response.text().as_deref(), Some("Checking."));
+        let calls = response.tool_calls().unwrap();
+        assert_eq!(calls[0].function.arguments, r#"{"city":"Oslo"}"#);
+        assert_eq!(response.usage().unwrap().total_tokens, 20);
+    }
+
+    /// Serve one canned HTTP response on a local port, handing back the
+    /// request it got
+    async fn serve_once(
+        content_type: &'static str,
+        body: Vec<u8>,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            // Read the headers and as much of the body as they announce
+            loop {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .and_then(|length| length.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        (endpoint, server)
+    }
+
+    #[test]
+    fn test_invalid_region_is_rejected_by_the_builder() {
+        let err = LLMBuilder::<Bedrock>::new()
+            .aws_credentials(AwsCredentials::new("AKIDEXAMPLE", "secret"))
+            .region("us east 1")
+            .build()
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, LLMError::InvalidRequest(ref msg) if msg.starts_with("Invalid Bedrock endpoint")),
+            "{err}"
+        );
+
+        let client = LLMBuilder::<Bedrock>::new()
+            .aws_credentials(AwsCredentials::new("AKIDEXAMPLE", "secret"))
+            .region("eu-west-1")
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.endpoint.as_str(),
+            "https://bedrock-runtime.eu-west-1.amazonaws.com/"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streams_are_signed_and_decoded() {
+        let chunk = |value: Value| {
+            let payload = json!({"bytes": BASE64.encode(value.to_string())}).to_string();
+            event_stream::tests::encode(
+                &[(":event-type", "chunk"), (":message-type", "event")],
+                payload.as_bytes(),
+            )
+        };
+        let body = [
+            chunk(json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hel"}})),
+            chunk(json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "lo"}})),
+            chunk(json!({"type": "message_stop", "amazon-bedrock-invocationMetrics": {"inputTokenCount": 5, "outputTokenCount": 2}})),
+        ]
+        .concat();
+        let (endpoint, server) = serve_once("application/vnd.amazon.eventstream", body).await;
+
+        let client = bedrock("anthropic.claude-3-haiku-20240307-v1:0")
+            .with_endpoint(&endpoint)
+            .unwrap();
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let chunks: Vec<StreamResponse> = client
+            .chat_stream_struct(&messages, None, None)
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let text: String = chunks
+            .iter()
+            .filter_map(|chunk| chunk.choices.first()?.delta.content.clone())
+            .collect();
+        assert_eq!(text, "Hello");
+        assert_eq!(
+            chunks.last().unwrap().usage.as_ref().unwrap().total_tokens,
+            7
+        );
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with(
+            "POST /model/anthropic.claude-3-haiku-20240307-v1%3A0/invoke-with-response-stream "
+        ));
+        assert!(request.contains("authorization: AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_exceptions_become_errors() {
+        let body = event_stream::tests::encode(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "throttlingException"),
+            ],
+            br#"{"message":"Too many requests"}"#,
+        );
+        let (endpoint, _server) = serve_once("application/vnd.amazon.eventstream", body).await;
+
+        let client = bedrock("meta.llama3-8b-instruct-v1:0")
+            .with_endpoint(&endpoint)
+            .unwrap();
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let mut stream = client.chat_stream(&messages, None, None).await.unwrap();
+        match stream.next().await {
+            Some(Err(LLMError::ProviderError(message))) => {
+                assert!(message.contains("throttlingException"))
+            }
+            other => panic!("Expected a provider error, got {other:?}"),
+        }
+    }
+}
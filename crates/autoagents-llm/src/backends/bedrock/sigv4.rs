@@ -0,0 +1,232 @@
+//! AWS Signature Version 4 signing for Bedrock runtime requests.
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// AWS credentials Bedrock requests are signed with.
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Token of temporary credentials, such as those from STS or SSO
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn new(access_key_id: impl Into<String>, secret_access_key: impl Into<String>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+        }
+    }
+
+    pub fn with_session_token(mut self, session_token: impl Into<String>) -> Self {
+        self.session_token = Some(session_token.into());
+        self
+    }
+
+    /// Read `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and, if set,
+    /// `AWS_SESSION_TOKEN`
+    pub fn from_env() -> Option<Self> {
+        let credentials = Self::new(
+            std::env::var("AWS_ACCESS_KEY_ID").ok()?,
+            std::env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+        );
+        Some(match std::env::var("AWS_SESSION_TOKEN") {
+            Ok(token) => credentials.with_session_token(token),
+            Err(_) => credentials,
+        })
+    }
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A request to sign. `path` must already be URI encoded, as it is sent.
+pub(super) struct SigningRequest<'a> {
+    pub method: &'a str,
+    pub host: &'a str,
+    pub path: &'a str,
+    pub payload: &'a [u8],
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+/// Headers that authenticate `request`, to be sent along with it
+pub(super) fn sign(
+    credentials: &AwsCredentials,
+    request: &SigningRequest<'_>,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &amz_date[..8];
+
+    let mut headers = vec![
+        ("host", request.host.to_string()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    // Already in order, as the canonical request requires
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+
+    // Services other than S3 encode the already encoded path a second time
+    let canonical_uri = request
+        .path
+        .split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/");
+    let canonical_request = format!(
+        "{}\n{canonical_uri}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        request.method,
+        hex(&Sha256::digest(request.payload)),
+    );
+
+    let scope = format!("{date}/{}/{}/aws4_request", request.region, request.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [date, request.region, request.service, "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part.as_bytes()).to_vec(),
+        );
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    headers.remove(0); // reqwest sets `host` from the URL
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            credentials.access_key_id
+        ),
+    ));
+    headers
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986
+pub(super) fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x36))
+        .chain_update(data)
+        .finalize();
+    Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn credentials() -> AwsCredentials {
+        AwsCredentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY")
+    }
+
+    fn header<'a>(headers: &'a [(&str, String)], name: &str) -> &'a str {
+        headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_matches_the_aws_test_suite() {
+        // `get-vanilla` from the AWS Signature Version 4 test suite
+        let headers = sign(
+            &credentials(),
+            &SigningRequest {
+                method: "GET",
+                host: "example.amazonaws.com",
+                path: "/",
+                payload: b"",
+                region: "us-east-1",
+                service: "service",
+            },
+            Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap(),
+        );
+
+        assert_eq!(header(&headers, "x-amz-date"), "20150830T123600Z");
+        assert_eq!(
+            header(&headers, "authorization"),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_model_ids_are_encoded_twice_and_session_tokens_signed() {
+        let model = uri_encode("anthropic.claude-3-haiku-20240307-v1:0");
+        let path = format!("/model/{model}/invoke");
+        assert_eq!(
+            path,
+            "/model/anthropic.claude-3-haiku-20240307-v1%3A0/invoke"
+        );
+
+        let headers = sign(
+            &credentials().with_session_token("SESSION"),
+            &SigningRequest {
+                method: "POST",
+                host: "bedrock-runtime.us-west-2.amazonaws.com",
+                path: &path,
+                payload: br#"{"prompt":"hi"}"#,
+                region: "us-west-2",
+                service: "bedrock",
+            },
+            Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap(),
+        );
+
+        assert_eq!(header(&headers, "x-amz-security-token"), "SESSION");
+        assert!(header(&headers, "authorization").ends_with(
+            "SignedHeaders=host;x-amz-date;x-amz-security-token, \
+             Signature=387f676274ddd70ff4c2a1c40cf446af9a1a4c89b4e8bd917988f186ade7f1e1"
+        ));
+    }
+}
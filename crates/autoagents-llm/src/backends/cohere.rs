@@ -0,0 +1,1095 @@
+//! Cohere API client implementation for chat, completion and embedding functionality.
+//!
+//! This module provides integration with Cohere's Command models through the
+//! v2 API. Besides tool calling and streaming it supports Cohere's
+//! retrieval-augmented generation: documents passed with a request, and tool
+//! results, ground the answer, and the response reports which spans of the
+//! text cite which sources through [`ChatResponse::citations`].
+
+use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
+use crate::{
+    builder::LLMBuilder,
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, Citation, ContentPart,
+        GenerationOptions, MessageType, StreamChoice, StreamDelta, StreamResponse,
+        StreamToolCallDelta, StreamToolCallFunction, StructuredOutputFormat, Tool, ToolChoice,
+        Usage,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    rerank::{RerankResponse, RerankResult, Reranker},
+    LLMProvider, ToolCall,
+};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::stream::{Stream, StreamExt};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+const DEFAULT_BASE_URL: &str = "https://api.cohere.com/v2/";
+const DEFAULT_MODEL: &str = "command-r-plus";
+const EMBEDDING_MODEL: &str = "embed-v4.0";
+/// Vector length of [`EMBEDDING_MODEL`] when no output dimension is set
+const DEFAULT_EMBEDDING_DIMENSIONS: usize = 1536;
+/// Most texts the embed endpoint accepts per request
+const MAX_EMBEDDING_BATCH: usize = 96;
+const RERANK_MODEL: &str = "rerank-v3.5";
+
+/// Client for interacting with Cohere's API.
+///
+/// Documents set on the client are sent with every chat request for the
+/// model to ground its answers in.
+#[derive(Debug)]
+pub struct Cohere {
+    pub api_key: String,
+    pub base_url: Url,
+    pub model: String,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub timeout_seconds: Option<u64>,
+    pub system: Option<String>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub tool_choice: Option<ToolChoice>,
+    pub embedding_dimensions: Option<u32>,
+    pub documents: Vec<CohereDocument>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    pub retry_policy: RetryPolicy,
+    client: Client,
+}
+
+/// A document for Cohere to ground its answer in.
+///
+/// Citations name the document by its `id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CohereDocument {
+    pub id: String,
+    /// Fields of the document, e.g. `title` and `snippet`
+    pub data: serde_json::Map<String, Value>,
+}
+
+impl CohereDocument {
+    /// A document holding a single `text` field
+    pub fn new(id: impl Into<String>, text: impl Into<String>) -> Self {
+        let mut data = serde_json::Map::new();
+        data.insert("text".to_string(), Value::String(text.into()));
+        Self {
+            id: id.into(),
+            data,
+        }
+    }
+
+    /// A document with the given fields
+    pub fn with_data(id: impl Into<String>, data: serde_json::Map<String, Value>) -> Self {
+        Self {
+            id: id.into(),
+            data,
+        }
+    }
+}
+
+/// Request payload for Cohere's chat endpoint.
+#[derive(Serialize, Debug)]
+struct CohereChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<CohereMessage<'a>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    documents: &'a [CohereDocument],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<&'a [Tool]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<CohereResponseFormat>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    k: Option<u32>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    stop_sequences: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+}
+
+/// Individual message in a Cohere chat conversation.
+#[derive(Serialize, Debug)]
+struct CohereMessage<'a> {
+    role: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<CohereContent<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<&'a [ToolCall]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_plan: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+enum CohereContent<'a> {
+    Text(&'a str),
+    Parts(Vec<CohereContentPart<'a>>),
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CohereContentPart<'a> {
+    Text { text: &'a str },
+    ImageUrl { image_url: CohereImageUrl },
+    Document { document: CohereToolDocument<'a> },
+}
+
+#[derive(Serialize, Debug)]
+struct CohereImageUrl {
+    url: String,
+}
+
+/// A tool result, which Cohere cites like any other document
+#[derive(Serialize, Debug)]
+struct CohereToolDocument<'a> {
+    id: &'a str,
+    data: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct CohereResponseFormat {
+    #[serde(rename = "type")]
+    format_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_schema: Option<Value>,
+}
+
+impl<'a> CohereMessage<'a> {
+    fn new(role: &'a str, content: CohereContent<'a>) -> Self {
+        Self {
+            role,
+            content: Some(content),
+            tool_calls: None,
+            tool_plan: None,
+            tool_call_id: None,
+        }
+    }
+}
+
+/// Response from Cohere's chat endpoint.
+#[derive(Deserialize, Debug)]
+struct CohereChatResponse {
+    message: CohereResponseMessage,
+    usage: Option<CohereUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereResponseMessage {
+    #[serde(default)]
+    content: Vec<CohereResponseContent>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+    tool_plan: Option<String>,
+    #[serde(default)]
+    citations: Vec<CohereCitation>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereResponseContent {
+    #[serde(rename = "type")]
+    content_type: String,
+    text: Option<String>,
+    thinking: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CohereCitation {
+    start: usize,
+    end: usize,
+    text: String,
+    #[serde(default)]
+    sources: Vec<CohereCitationSource>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CohereCitationSource {
+    id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereUsage {
+    tokens: Option<CohereTokens>,
+}
+
+/// Token counts, which Cohere reports as numbers that may carry a fraction
+#[derive(Deserialize, Debug, Clone)]
+struct CohereTokens {
+    #[serde(default)]
+    input_tokens: f64,
+    #[serde(default)]
+    output_tokens: f64,
+}
+
+impl From<CohereTokens> for Usage {
+    fn from(tokens: CohereTokens) -> Self {
+        Usage::new(tokens.input_tokens as u32, tokens.output_tokens as u32)
+    }
+}
+
+impl From<CohereCitation> for Citation {
+    fn from(citation: CohereCitation) -> Self {
+        Citation {
+            start: citation.start,
+            end: citation.end,
+            text: citation.text,
+            sources: citation
+                .sources
+                .into_iter()
+                .filter_map(|source| source.id)
+                .collect(),
+        }
+    }
+}
+
+impl CohereResponseMessage {
+    fn content_of(&self, content_type: &str) -> Vec<&str> {
+        self.content
+            .iter()
+            .filter(|c| c.content_type == content_type)
+            .filter_map(|c| match content_type {
+                "thinking" => c.thinking.as_deref(),
+                _ => c.text.as_deref(),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for CohereChatResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.text(), self.tool_calls()) {
+            (Some(text), _) => write!(f, "{text}"),
+            (None, Some(calls)) => {
+                for call in calls {
+                    write!(f, "{call}")?;
+                }
+                Ok(())
+            }
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl ChatResponse for CohereChatResponse {
+    fn text(&self) -> Option<String> {
+        let text = self.message.content_of("text");
+        if text.is_empty() {
+            // A turn that only calls tools explains its plan instead
+            return self.message.tool_plan.clone();
+        }
+        Some(text.concat())
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        (!self.message.tool_calls.is_empty()).then(|| self.message.tool_calls.clone())
+    }
+
+    fn thinking(&self) -> Option<String> {
+        let thinking = self.message.content_of("thinking");
+        (!thinking.is_empty()).then(|| thinking.concat())
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        let tokens = self.usage.as_ref()?.tokens.clone()?;
+        Some(tokens.into())
+    }
+
+    fn citations(&self) -> Option<Vec<Citation>> {
+        let citations = &self.message.citations;
+        (!citations.is_empty()).then(|| citations.iter().cloned().map(Citation::from).collect())
+    }
+}
+
+/// A server-sent event of a streaming chat response.
+#[derive(Deserialize, Debug)]
+struct CohereStreamEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    index: Option<usize>,
+    delta: Option<CohereStreamDelta>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereStreamDelta {
+    message: Option<CohereStreamMessage>,
+    usage: Option<CohereUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereStreamMessage {
+    content: Option<CohereStreamContent>,
+    tool_calls: Option<CohereStreamToolCall>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereStreamContent {
+    text: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereStreamToolCall {
+    id: Option<String>,
+    function: Option<CohereStreamFunction>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereStreamFunction {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+#[derive(Serialize, Debug)]
+struct CohereEmbeddingRequest<'a> {
+    model: &'a str,
+    texts: Vec<String>,
+    input_type: &'static str,
+    embedding_types: [&'static str; 1],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dimension: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereEmbeddingResponse {
+    embeddings: CohereEmbeddings,
+}
+
+#[derive(Deserialize, Debug)]
+struct CohereEmbeddings {
+    float: Vec<Vec<f32>>,
+}
+
+#[derive(Serialize, Debug)]
+struct CohereRerankRequest<'a> {
+    model: &'a str,
+    query: &'a str,
+    documents: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_n: Option<usize>,
+}
+
+impl Cohere {
+    /// Creates a new Cohere client with the specified configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - Cohere API key for authentication
+    /// * `base_url` - Base URL of the v2 API (defaults to "https://api.cohere.com/v2/")
+    /// * `model` - Model identifier (defaults to "command-r-plus")
+    /// * `tool_choice` - `Any` and `Tool` both require a tool call, as Cohere
+    ///   cannot be asked for a particular tool
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        api_key: impl Into<String>,
+        base_url: Option<String>,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        timeout_seconds: Option<u64>,
+        system: Option<String>,
+        top_p: Option<f32>,
+        top_k: Option<u32>,
+        tool_choice: Option<ToolChoice>,
+        embedding_dimensions: Option<u32>,
+    ) -> Self {
+        let base_url = base_url
+            .map(|url| {
+                if url.ends_with('/') {
+                    url
+                } else {
+                    format!("{url}/")
+                }
+            })
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Self {
+            api_key: api_key.into(),
+            base_url: Url::parse(&base_url).expect("Failed to parse base URL"),
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            max_tokens,
+            temperature,
+            timeout_seconds,
+            system,
+            top_p,
+            top_k,
+            tool_choice,
+            embedding_dimensions,
+            documents: Vec::new(),
+            generation: GenerationOptions::default(),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
+        }
+    }
+
+    /// Ground every chat request in `documents`
+    pub fn with_documents(mut self, documents: Vec<CohereDocument>) -> Self {
+        self.documents = documents;
+        self
+    }
+
+    fn check_credentials(&self) -> Result<(), LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing Cohere API key".to_string()));
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url, LLMError> {
+        self.base_url
+            .join(path)
+            .map_err(|e| LLMError::HttpError(e.to_string()))
+    }
+
+    fn chat_request<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        tools: Option<&'a [Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+        stream: bool,
+    ) -> Result<CohereChatRequest<'a>, LLMError> {
+        let mut cohere_messages = Vec::new();
+        if let Some(system) = &self.system {
+            cohere_messages.push(CohereMessage::new("system", CohereContent::Text(system)));
+        }
+
+        for message in messages {
+            let role = match message.role {
+                ChatRole::System => "system",
+                ChatRole::User => "user",
+                ChatRole::Assistant => "assistant",
+                ChatRole::Tool => "tool",
+            };
+
+            match &message.message_type {
+                MessageType::Text => {
+                    cohere_messages.push(CohereMessage::new(
+                        role,
+                        CohereContent::Text(&message.content),
+                    ));
+                }
+                MessageType::Image((image_mime, raw_bytes)) => {
+                    let url = format!(
+                        "data:{};base64,{}",
+                        image_mime.mime_type(),
+                        BASE64.encode(raw_bytes)
+                    );
+                    cohere_messages
+                        .push(CohereMessage::new(role, image_parts(&message.content, url)));
+                }
+                MessageType::ImageURL(url) => {
+                    cohere_messages.push(CohereMessage::new(
+                        role,
+                        image_parts(&message.content, url.clone()),
+                    ));
+                }
+                MessageType::Parts(parts) => {
+                    let text = (!message.content.is_empty()).then_some(CohereContentPart::Text {
+                        text: &message.content,
+                    });
+                    let parts = text.into_iter().chain(parts.iter().map(|part| match part {
+                        ContentPart::Text(text) => CohereContentPart::Text { text },
+                        image => CohereContentPart::ImageUrl {
+                            image_url: CohereImageUrl {
+                                url: image.to_image_url().unwrap_or_default(),
+                            },
+                        },
+                    }));
+                    cohere_messages.push(CohereMessage::new(
+                        role,
+                        CohereContent::Parts(parts.collect()),
+                    ));
+                }
+                MessageType::Pdf(_) => {
+                    return Err(LLMError::InvalidRequest(
+                        "Cohere does not accept PDF messages".to_string(),
+                    ));
+                }
+                MessageType::ToolUse(calls) => cohere_messages.push(CohereMessage {
+                    role: "assistant",
+                    content: None,
+                    tool_calls: Some(calls),
+                    tool_plan: (!message.content.is_empty()).then_some(message.content.as_str()),
+                    tool_call_id: None,
+                }),
+                MessageType::ToolResult(results) => {
+                    // Each result is its own tool message, holding the
+                    // output as a document named after the call
+                    cohere_messages.extend(results.iter().map(|result| CohereMessage {
+                        role: "tool",
+                        content: Some(CohereContent::Parts(vec![CohereContentPart::Document {
+                            document: CohereToolDocument {
+                                id: &result.id,
+                                data: &result.function.arguments,
+                            },
+                        }])),
+                        tool_calls: None,
+                        tool_plan: None,
+                        tool_call_id: Some(&result.id),
+                    }));
+                }
+            }
+        }
+
+        let tools = tools.filter(|tools| !tools.is_empty());
+        let tool_choice = tools.and(match self.tool_choice {
+            Some(ToolChoice::Any) | Some(ToolChoice::Tool(_)) => Some("REQUIRED"),
+            Some(ToolChoice::None) => Some("NONE"),
+            Some(ToolChoice::Auto) | None => None,
+        });
+
+        let response_format = json_schema.map(|format| CohereResponseFormat {
+            format_type: "json_object",
+            json_schema: format.schema,
+        });
+
+        Ok(CohereChatRequest {
+            model: &self.model,
+            messages: cohere_messages,
+            documents: &self.documents,
+            tools,
+            tool_choice,
+            response_format,
+            stream,
+            max_tokens: self.max_tokens,
+            temperature: self.temperature,
+            p: self.top_p,
+            k: self.top_k,
+            stop_sequences: &self.generation.stop_sequences,
+            seed: self.generation.seed,
+            frequency_penalty: self.generation.frequency_penalty,
+            presence_penalty: self.generation.presence_penalty,
+        })
+    }
+
+    async fn send_chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+        stream: bool,
+    ) -> Result<reqwest::Response, LLMError> {
+        self.check_credentials()?;
+
+        let req_body = self.chat_request(messages, tools, json_schema, stream)?;
+
+        if log::log_enabled!(log::Level::Trace) {
+            if let Ok(json) = serde_json::to_string(&req_body) {
+                log::trace!("Cohere request payload: {json}");
+            }
+        }
+
+        let response = self
+            .client
+            .post(self.endpoint("chat")?)
+            .bearer_auth(&self.api_key)
+            .json(&req_body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+
+        log::debug!("Cohere HTTP status: {}", response.status());
+
+        check_response_status(response).await
+    }
+}
+
+/// Content of a message with an image, and its text unless empty
+fn image_parts(text: &str, url: String) -> CohereContent<'_> {
+    let mut parts = vec![CohereContentPart::ImageUrl {
+        image_url: CohereImageUrl { url },
+    }];
+    if !text.is_empty() {
+        parts.push(CohereContentPart::Text { text });
+    }
+    CohereContent::Parts(parts)
+}
+
+#[async_trait]
+impl ChatProvider for Cohere {
+    /// Sends a chat request to Cohere's API, grounded in the client's
+    /// documents and any tool results in the conversation.
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let response = self.send_chat(messages, tools, json_schema, false).await?;
+
+        let body = response.text().await?;
+        let json_resp: CohereChatResponse =
+            serde_json::from_str(&body).map_err(|e| LLMError::ResponseFormatError {
+                message: format!("Failed to decode Cohere API response: {e}"),
+                raw_response: body.clone(),
+            })?;
+
+        Ok(Box::new(json_resp))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<std::pin::Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError>
+    {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text and tool calls. Citations are only returned by
+    /// [`chat`](Self::chat); the last chunk carries the token usage.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<
+        std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>,
+        LLMError,
+    > {
+        let response = self.send_chat(messages, tools, json_schema, true).await?;
+        Ok(create_stream(response))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for Cohere {
+    /// Sends the prompt as a single user message.
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let messages = [ChatMessage::user().content(req.prompt.clone()).build()];
+        let response = self.chat(&messages, None, json_schema).await?;
+        Ok(CompletionResponse::from_chat(response.as_ref()))
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for Cohere {
+    /// Embeds the texts as documents for search with "embed-v4.0".
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.check_credentials()?;
+
+        let body = CohereEmbeddingRequest {
+            model: EMBEDDING_MODEL,
+            texts,
+            input_type: "search_document",
+            embedding_types: ["float"],
+            output_dimension: self.embedding_dimensions,
+        };
+
+        let resp = self
+            .client
+            .post(self.endpoint("embed")?)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let resp = check_response_status(resp).await?;
+
+        let json_resp: CohereEmbeddingResponse = resp.json().await?;
+        Ok(json_resp.embeddings.float)
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        Some(
+            self.embedding_dimensions
+                .map_or(DEFAULT_EMBEDDING_DIMENSIONS, |dims| dims as usize),
+        )
+    }
+
+    fn max_embedding_batch(&self) -> Option<usize> {
+        Some(MAX_EMBEDDING_BATCH)
+    }
+}
+
+#[async_trait]
+impl Reranker for Cohere {
+    /// Ranks the documents with "rerank-v3.5", scores ranging from 0 to 1.
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_n: Option<usize>,
+    ) -> Result<Vec<RerankResult>, LLMError> {
+        self.check_credentials()?;
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = CohereRerankRequest {
+            model: RERANK_MODEL,
+            query,
+            documents,
+            top_n,
+        };
+        let resp = self
+            .client
+            .post(self.endpoint("rerank")?)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let resp = check_response_status(resp).await?;
+
+        let json_resp: RerankResponse = resp.json().await?;
+        Ok(json_resp.into_results(documents.len()))
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for Cohere {}
+
+impl LLMProvider for Cohere {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
+
+/// Turns the server-sent events of a streaming response into stream chunks
+fn create_stream(
+    response: reqwest::Response,
+) -> std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>> {
+    let stream = response
+        .bytes_stream()
+        .scan(CohereStreamParser::default(), |parser, chunk| {
+            let results = match chunk {
+                Ok(bytes) => parser.push(&bytes),
+                Err(e) => vec![Err(LLMError::HttpError(e.to_string()))],
+            };
+            futures::future::ready(Some(results))
+        })
+        .flat_map(futures::stream::iter);
+    Box::pin(stream)
+}
+
+/// Splits a streaming response into `data:` lines, each holding one event
+#[derive(Default)]
+struct CohereStreamParser {
+    /// Bytes of a line not yet terminated
+    line_buffer: Vec<u8>,
+}
+
+impl CohereStreamParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<Result<StreamResponse, LLMError>> {
+        self.line_buffer.extend_from_slice(bytes);
+        let mut results = Vec::new();
+        while let Some(end) = self.line_buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                results.extend(parse_event(data.trim()));
+            }
+        }
+        results
+    }
+}
+
+fn parse_event(data: &str) -> Option<Result<StreamResponse, LLMError>> {
+    let event: CohereStreamEvent = match serde_json::from_str(data) {
+        Ok(event) => event,
+        Err(e) => {
+            return Some(Err(LLMError::ResponseFormatError {
+                message: format!("Failed to decode Cohere stream event: {e}"),
+                raw_response: data.to_string(),
+            }))
+        }
+    };
+    let delta = event.delta?;
+
+    let chunk = |delta: StreamDelta| StreamResponse {
+        choices: vec![StreamChoice { delta }],
+        usage: None,
+    };
+    let tool_call = |id: Option<String>, function: CohereStreamFunction| StreamDelta {
+        content: None,
+        tool_calls: Some(vec![StreamToolCallDelta {
+            index: event.index.unwrap_or_default(),
+            id,
+            function: Some(StreamToolCallFunction {
+                name: function.name,
+                arguments: function.arguments,
+            }),
+        }]),
+        thinking: None,
+        reasoning: None,
+    };
+
+    match event.event_type.as_str() {
+        "content-delta" => {
+            let text = delta.message?.content?.text?;
+            Some(Ok(chunk(StreamDelta {
+                content: Some(text),
+                tool_calls: None,
+                thinking: None,
+                reasoning: None,
+            })))
+        }
+        // The start of a call names the function, later deltas add to its
+        // arguments
+        "tool-call-start" | "tool-call-delta" => {
+            let call = delta.message?.tool_calls?;
+            Some(Ok(chunk(tool_call(call.id, call.function?))))
+        }
+        "message-end" => {
+            let usage = delta.usage?.tokens?;
+            Some(Ok(StreamResponse {
+                choices: Vec::new(),
+                usage: Some(usage.into()),
+            }))
+        }
+        _ => None,
+    }
+}
+
+impl LLMBuilder<Cohere> {
+    /// Ground every chat request in `documents`
+    pub fn documents(mut self, documents: Vec<CohereDocument>) -> Self {
+        self.cohere_documents = documents;
+        self
+    }
+
+    pub fn build(self) -> Result<Arc<Cohere>, LLMError> {
+        let api_key = self.api_key.ok_or_else(|| {
+            LLMError::InvalidRequest("No API key provided for Cohere".to_string())
+        })?;
+
+        let mut cohere = Cohere::new(
+            api_key,
+            self.base_url,
+            self.model,
+            self.max_tokens,
+            self.temperature,
+            self.timeout_seconds,
+            self.system,
+            self.top_p,
+            self.top_k,
+            self.tool_choice,
+            self.embedding_dimensions,
+        )
+        .with_documents(self.cohere_documents);
+        cohere.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut cohere.client, self.timeout_seconds)?;
+        cohere.generation = self.generation;
+
+        Ok(Arc::new(cohere))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FunctionCall;
+    use serde_json::json;
+
+    fn cohere() -> Cohere {
+        Cohere::new(
+            "test-key",
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("Answer from the documents.".to_string()),
+            None,
+            None,
+            Some(ToolChoice::Any),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_request_carries_documents_and_tool_results() {
+        let cohere = cohere().with_documents(vec![CohereDocument::new(
+            "doc-1",
+            "Emperor penguins are the tallest.",
+        )]);
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "search".to_string(),
+                arguments: r#"{"query":"penguins"}"#.to_string(),
+            },
+        };
+        let result = ToolCall {
+            function: FunctionCall {
+                name: "search".to_string(),
+                arguments: r#"{"hits":["Adelie penguins dive"]}"#.to_string(),
+            },
+            ..call.clone()
+        };
+        let messages = [
+            ChatMessage::user()
+                .content("Which penguins are tallest?")
+                .build(),
+            ChatMessage::assistant()
+                .tool_use(vec![call])
+                .content("I will search.")
+                .build(),
+            ChatMessage::user().tool_result(vec![result]).build(),
+        ];
+        let tools = [Tool {
+            tool_type: "function".to_string(),
+            function: crate::chat::FunctionTool {
+                name: "search".to_string(),
+                description: "Search the web".to_string(),
+                parameters: json!({"type": "object"}),
+            },
+        }];
+
+        let request = cohere
+            .chat_request(&messages, Some(&tools), None, false)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+
+        assert_eq!(
+            request["documents"],
+            json!([{"id": "doc-1", "data": {"text": "Emperor penguins are the tallest."}}])
+        );
+        assert_eq!(request["tool_choice"], "REQUIRED");
+        assert_eq!(
+            request["messages"],
+            json!([
+                {"role": "system", "content": "Answer from the documents."},
+                {"role": "user", "content": "Which penguins are tallest?"},
+                {
+                    "role": "assistant",
+                    "tool_calls": [{
+                        "id": "call-1",
+                        "type": "function",
+                        "function": {"name": "search", "arguments": "{\"query\":\"penguins\"}"}
+                    }],
+                    "tool_plan": "I will search."
+                },
+                {
+                    "role": "tool",
+                    "tool_call_id": "call-1",
+                    "content": [{
+                        "type": "document",
+                        "document": {"id": "call-1", "data": "{\"hits\":[\"Adelie penguins dive\"]}"}
+                    }]
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_response_citations_name_their_sources() {
+        let response: CohereChatResponse = serde_json::from_value(json!({
+            "id": "resp-1",
+            "finish_reason": "COMPLETE",
+            "message": {
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Emperor penguins are the tallest."}],
+                "citations": [{
+                    "start": 0,
+                    "end": 16,
+                    "text": "Emperor penguins",
+                    "sources": [
+                        {"type": "document", "id": "doc-1", "document": {"id": "doc-1"}},
+                        {"type": "tool", "id": "call-1:0", "tool_output": {}}
+                    ]
+                }]
+            },
+            "usage": {
+                "billed_units": {"input_tokens": 12, "output_tokens": 7},
+                "tokens": {"input_tokens": 240, "output_tokens": 9}
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            response.text().as_deref(),
+            Some("Emperor penguins are the tallest.")
+        );
+        assert_eq!(
+            response.citations(),
+            Some(vec![Citation {
+                start: 0,
+                end: 16,
+                text: "Emperor penguins".to_string(),
+                sources: vec!["doc-1".to_string(), "call-1:0".to_string()],
+            }])
+        );
+        let usage = response.usage().unwrap();
+        assert_eq!(usage.prompt_tokens, 240);
+        assert_eq!(usage.total_tokens, 249);
+        assert!(response.tool_calls().is_none());
+    }
+
+    #[test]
+    fn test_stream_events_carry_text_tool_calls_and_usage() {
+        let mut parser = CohereStreamParser::default();
+        let events = concat!(
+            "event: message-start\n",
+            "data: {\"type\":\"message-start\",\"id\":\"resp-1\"}\n\n",
+            "event: content-delta\n",
+            "data: {\"type\":\"content-delta\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"text\":\"Hel\"}}}}\n\n",
+            "event: tool-call-start\n",
+            "data: {\"type\":\"tool-call-start\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"id\":\"call-1\",\"type\":\"function\",\"function\":{\"name\":\"search\",\"arguments\":\"\"}}}}}\n\n",
+            "event: tool-call-delta\n",
+            "data: {\"type\":\"tool-call-delta\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"function\":{\"arguments\":\"{}\"}}}}}\n\n",
+            "event: message-end\n",
+            "data: {\"type\":\"message-end\",\"delta\":{\"finish_reason\":\"TOOL_CALL\",\"usage\":{\"tokens\":{\"input_tokens\":5,\"output_tokens\":3}}}}\n\n",
+        );
+        // Split mid-line to check lines are reassembled
+        let (first, second) = events.split_at(120);
+        let mut chunks = parser.push(first.as_bytes());
+        chunks.extend(parser.push(second.as_bytes()));
+        let chunks: Vec<StreamResponse> = chunks.into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[0].choices[0].delta.content.as_deref(), Some("Hel"));
+        let start = &chunks[1].choices[0].delta.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(start.function.as_ref().unwrap().name, "search");
+        let delta = &chunks[2].choices[0].delta.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(delta.index, 0);
+        assert_eq!(delta.function.as_ref().unwrap().arguments, "{}");
+        assert_eq!(chunks[3].usage.as_ref().unwrap().total_tokens, 8);
+    }
+}
@@ -3,10 +3,12 @@
 //! This module provides integration with DeepSeek's models through their API.
 
 use crate::chat::StructuredOutputFormat;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
 use crate::ToolCall;
 use crate::{
     builder::LLMBuilder,
-    chat::{ChatResponse, Tool},
+    chat::{ChatResponse, GenerationOptions, Tool, Usage},
 };
 use crate::{
     chat::{ChatMessage, ChatProvider, ChatRole},
@@ -28,6 +30,9 @@ pub struct DeepSeek {
     pub temperature: Option<f32>,
     pub system: Option<String>,
     pub timeout_seconds: Option<u64>,
+    /// Stop sequences and penalties; DeepSeek takes no seed
+    pub generation: GenerationOptions,
+    pub retry_policy: RetryPolicy,
     client: Client,
 }
 
@@ -44,11 +49,14 @@ struct DeepSeekChatRequest<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     stream: bool,
+    #[serde(flatten)]
+    generation: &'a GenerationOptions,
 }
 
 #[derive(Deserialize, Debug)]
 struct DeepSeekChatResponse {
     choices: Vec<DeepSeekChatChoice>,
+    usage: Option<Usage>,
 }
 
 impl std::fmt::Display for DeepSeekChatResponse {
@@ -80,6 +88,10 @@ impl ChatResponse for DeepSeekChatResponse {
     fn tool_calls(&self) -> Option<Vec<ToolCall>> {
         None
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
 }
 
 impl DeepSeek {
@@ -91,10 +103,6 @@ impl DeepSeek {
         timeout_seconds: Option<u64>,
         system: Option<String>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
         Self {
             api_key: api_key.into(),
             model: model.unwrap_or("deepseek-chat".to_string()),
@@ -102,7 +110,9 @@ impl DeepSeek {
             temperature,
             system,
             timeout_seconds,
-            client: builder.build().expect("Failed to build reqwest Client"),
+            generation: GenerationOptions::default(),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
         }
     }
 }
@@ -162,6 +172,7 @@ impl ChatProvider for DeepSeek {
             messages: deepseek_msgs,
             temperature: self.temperature,
             stream: false,
+            generation: &self.generation,
         };
 
         if log::log_enabled!(log::Level::Trace) {
@@ -180,7 +191,10 @@ impl ChatProvider for DeepSeek {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
 
-        let resp = request.send().await?;
+        let resp = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
 
         log::debug!("DeepSeek HTTP status: {}", resp.status());
 
@@ -220,15 +234,20 @@ impl EmbeddingProvider for DeepSeek {
 #[async_trait]
 impl ModelsProvider for DeepSeek {}
 
-impl LLMProvider for DeepSeek {}
+impl LLMProvider for DeepSeek {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
 
 impl LLMBuilder<DeepSeek> {
     pub fn build(self) -> Result<Arc<DeepSeek>, LLMError> {
+        self.generation.reject_unsupported("DeepSeek", &["seed"])?;
         let api_key = self.api_key.ok_or_else(|| {
             LLMError::InvalidRequest("No API key provided for DeepSeek".to_string())
         })?;
 
-        let deepseek = DeepSeek::new(
+        let mut deepseek = DeepSeek::new(
             api_key,
             self.model,
             self.max_tokens,
@@ -236,6 +255,10 @@ impl LLMBuilder<DeepSeek> {
             self.timeout_seconds,
             self.system,
         );
+        deepseek.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut deepseek.client, self.timeout_seconds)?;
+        deepseek.generation = self.generation;
 
         Ok(Arc::new(deepseek))
     }
@@ -0,0 +1,1424 @@
+//! Google Gemini API client implementation for chat and completion functionality.
+//!
+//! This module provides integration with Google's Gemini models, either through
+//! the Gemini API with an AI Studio API key or through Vertex AI with Google
+//! Cloud access tokens from a [`GoogleTokenProvider`].
+//! It implements chat, completion and embedding capabilities.
+//!
+//! # Features
+//! - Chat conversations with system instructions and message history
+//! - Function calling, also while streaming
+//! - Text completion requests
+//! - Configuration options for temperature, tokens, top_p, top_k etc.
+//! - Streaming support
+
+mod vertex;
+
+pub use vertex::{GoogleTokenProvider, MetadataServerCredential, ServiceAccountCredential};
+
+use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
+use crate::{
+    builder::LLMBuilder,
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, ContentPart, GenerationOptions,
+        MessageType, StreamChoice, StreamDelta, StreamResponse, StreamToolCallDelta,
+        StreamToolCallFunction, StructuredOutputFormat, Tool, Usage,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    FunctionCall, LLMProvider, ToolCall,
+};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::stream::{Stream, StreamExt};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Client for interacting with Google's Gemini API.
+///
+/// This struct holds the configuration and state needed to make requests to the Gemini API.
+/// It implements the [`ChatProvider`], [`CompletionProvider`], and [`EmbeddingProvider`] traits.
+#[derive(Debug)]
+pub struct Google {
+    /// API key for authentication with Google's API
+    pub api_key: String,
+    /// Model identifier (e.g. "gemini-1.5-flash")
+    pub model: String,
+    /// Maximum number of tokens to generate in responses
+    pub max_tokens: Option<u32>,
+    /// Sampling temperature between 0.0 and 1.0
+    pub temperature: Option<f32>,
+    /// Optional system prompt to set context
+    pub system: Option<String>,
+    /// Request timeout in seconds
+    pub timeout_seconds: Option<u64>,
+    /// Top-p sampling parameter
+    pub top_p: Option<f32>,
+    /// Top-k sampling parameter
+    pub top_k: Option<u32>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    /// Vertex AI project and credentials, used instead of `api_key` when set
+    vertex: Option<Vertex>,
+    /// HTTP client for making API requests
+    pub retry_policy: RetryPolicy,
+    client: Client,
+}
+
+/// Where and how to reach Gemini on Vertex AI
+struct Vertex {
+    project: String,
+    location: String,
+    token_provider: Arc<dyn GoogleTokenProvider>,
+}
+
+impl std::fmt::Debug for Vertex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vertex")
+            .field("project", &self.project)
+            .field("location", &self.location)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Request body for chat completions
+#[derive(Serialize)]
+struct GoogleChatRequest<'a> {
+    /// List of conversation messages
+    contents: Vec<GoogleChatContent<'a>>,
+    /// System prompt, kept apart from the conversation
+    #[serde(skip_serializing_if = "Option::is_none", rename = "systemInstruction")]
+    system_instruction: Option<GoogleSystemInstruction>,
+    /// Optional generation parameters
+    #[serde(skip_serializing_if = "Option::is_none", rename = "generationConfig")]
+    generation_config: Option<GoogleGenerationConfig>,
+    /// Tools that the model can use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<GoogleTool>>,
+}
+
+/// Individual message in a chat conversation
+#[derive(Serialize)]
+struct GoogleChatContent<'a> {
+    /// Role of the message sender ("user" or "model")
+    role: &'a str,
+    /// Content parts of the message
+    parts: Vec<GoogleContentPart<'a>>,
+}
+
+/// System instruction of a chat request
+#[derive(Serialize)]
+struct GoogleSystemInstruction {
+    parts: Vec<GoogleSystemPart>,
+}
+
+#[derive(Serialize)]
+struct GoogleSystemPart {
+    text: String,
+}
+
+/// Text content within a chat message
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum GoogleContentPart<'a> {
+    /// The actual text content
+    #[serde(rename = "text")]
+    Text(&'a str),
+    InlineData(GoogleInlineData),
+    FileData(GoogleFileData),
+    FunctionCall(GoogleFunctionCall),
+    #[serde(rename = "functionResponse")]
+    FunctionResponse(GoogleFunctionResponse),
+}
+
+#[derive(Serialize)]
+struct GoogleInlineData {
+    mime_type: String,
+    data: String,
+}
+
+/// Reference to media hosted outside the request body
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleFileData {
+    mime_type: String,
+    file_uri: String,
+}
+
+/// Guess an image MIME type from the URL's file extension, defaulting to JPEG
+fn image_mime_from_url(url: &str) -> &'static str {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .to_ascii_lowercase();
+    if path.ends_with(".png") {
+        "image/png"
+    } else if path.ends_with(".gif") {
+        "image/gif"
+    } else if path.ends_with(".webp") {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+/// Parts for an image message: the accompanying text, if any, then the image itself
+fn image_parts<'a>(text: &'a str, image: GoogleContentPart<'a>) -> Vec<GoogleContentPart<'a>> {
+    let mut parts = Vec::with_capacity(2);
+    if !text.is_empty() {
+        parts.push(GoogleContentPart::Text(text));
+    }
+    parts.push(image);
+    parts
+}
+
+/// Gemini part for a part of a multimodal message
+fn google_part(part: &ContentPart) -> GoogleContentPart<'_> {
+    match part {
+        ContentPart::Text(text) => GoogleContentPart::Text(text),
+        ContentPart::Image { mime, data } => GoogleContentPart::InlineData(GoogleInlineData {
+            mime_type: mime.mime_type().to_string(),
+            data: BASE64.encode(data),
+        }),
+        ContentPart::ImageUrl { url, mime } => GoogleContentPart::FileData(GoogleFileData {
+            mime_type: mime
+                .map_or_else(|| image_mime_from_url(url), |mime| mime.mime_type())
+                .to_string(),
+            file_uri: url.clone(),
+        }),
+    }
+}
+
+/// Configuration parameters for text generation
+#[derive(Serialize)]
+struct GoogleGenerationConfig {
+    /// Maximum tokens to generate
+    #[serde(skip_serializing_if = "Option::is_none", rename = "maxOutputTokens")]
+    max_output_tokens: Option<u32>,
+    /// Sampling temperature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    /// Top-p sampling parameter
+    #[serde(skip_serializing_if = "Option::is_none", rename = "topP")]
+    top_p: Option<f32>,
+    /// Top-k sampling parameter
+    #[serde(skip_serializing_if = "Option::is_none", rename = "topK")]
+    top_k: Option<u32>,
+    /// The MIME type of the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<GoogleResponseMimeType>,
+    /// A schema for structured output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_schema: Option<Value>,
+    /// Sequences that end generation
+    #[serde(skip_serializing_if = "<[String]>::is_empty", rename = "stopSequences")]
+    stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "frequencyPenalty")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "presencePenalty")]
+    presence_penalty: Option<f32>,
+}
+
+/// Response from the chat completion API
+#[derive(Deserialize, Debug)]
+struct GoogleChatResponse {
+    /// Generated completion candidates
+    candidates: Vec<GoogleCandidate>,
+    /// Token counts of the request and response
+    #[serde(rename = "usageMetadata")]
+    usage_metadata: Option<GoogleUsageMetadata>,
+}
+
+/// Response from the streaming chat completion API
+#[derive(Deserialize, Debug)]
+struct GoogleStreamResponse {
+    /// Generated completion candidates
+    candidates: Option<Vec<GoogleCandidate>>,
+    /// Token counts so far
+    #[serde(rename = "usageMetadata")]
+    usage_metadata: Option<GoogleUsageMetadata>,
+    /// Error reported in place of a chunk
+    error: Option<Value>,
+}
+
+/// Token counts reported by the API
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct GoogleUsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+    #[serde(default)]
+    total_token_count: u32,
+    /// Prompt tokens served from cached content
+    #[serde(default)]
+    cached_content_token_count: u32,
+}
+
+impl From<GoogleUsageMetadata> for Usage {
+    fn from(usage: GoogleUsageMetadata) -> Self {
+        let normalized = Usage {
+            total_tokens: usage.total_token_count,
+            ..Usage::new(usage.prompt_token_count, usage.candidates_token_count)
+        };
+        if usage.cached_content_token_count > 0 {
+            normalized.with_cached_tokens(usage.cached_content_token_count)
+        } else {
+            normalized
+        }
+    }
+}
+
+impl std::fmt::Display for GoogleChatResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.text(), self.tool_calls()) {
+            (Some(text), Some(tool_calls)) => {
+                for call in tool_calls {
+                    write!(f, "{call}")?;
+                }
+                write!(f, "{text}")
+            }
+            (Some(text), None) => write!(f, "{text}"),
+            (None, Some(tool_calls)) => {
+                for call in tool_calls {
+                    write!(f, "{call}")?;
+                }
+                Ok(())
+            }
+            (None, None) => write!(f, ""),
+        }
+    }
+}
+
+/// Individual completion candidate
+#[derive(Deserialize, Debug)]
+struct GoogleCandidate {
+    /// Content of the candidate response (absent when generation was blocked)
+    #[serde(default)]
+    content: GoogleResponseContent,
+    /// Why generation stopped; set on the last chunk of a stream
+    #[serde(rename = "finishReason")]
+    finish_reason: Option<String>,
+}
+
+/// Content block within a response
+#[derive(Deserialize, Debug, Default)]
+struct GoogleResponseContent {
+    /// Parts making up the content (might be absent when only function calls are present)
+    #[serde(default)]
+    parts: Vec<GoogleResponsePart>,
+    /// Function calls if any are used - can be a single object or array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_call: Option<GoogleFunctionCall>,
+    /// Function calls as array (newer format in some responses)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_calls: Option<Vec<GoogleFunctionCall>>,
+}
+
+impl ChatResponse for GoogleChatResponse {
+    fn text(&self) -> Option<String> {
+        self.candidates
+            .first()
+            .map(|c| c.content.parts.iter().map(|p| p.text.clone()).collect())
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        self.candidates.first().and_then(|c| {
+            // First check for function calls at the part level (new API format)
+            let part_function_calls: Vec<ToolCall> = c
+                .content
+                .parts
+                .iter()
+                .filter_map(|part| {
+                    part.function_call.as_ref().map(|f| ToolCall {
+                        id: format!("call_{}", f.name),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: f.name.clone(),
+                            arguments: serde_json::to_string(&f.args).unwrap_or_default(),
+                        },
+                    })
+                })
+                .collect();
+
+            if !part_function_calls.is_empty() {
+                return Some(part_function_calls);
+            }
+
+            // Otherwise check for function_calls/function_call at the content level (older format)
+            if let Some(fc) = &c.content.function_calls {
+                // Process array of function calls
+                Some(
+                    fc.iter()
+                        .map(|f| ToolCall {
+                            id: format!("call_{}", f.name),
+                            call_type: "function".to_string(),
+                            function: FunctionCall {
+                                name: f.name.clone(),
+                                arguments: serde_json::to_string(&f.args).unwrap_or_default(),
+                            },
+                        })
+                        .collect(),
+                )
+            } else {
+                c.content.function_call.as_ref().map(|f| {
+                    vec![ToolCall {
+                        id: format!("call_{}", f.name),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: f.name.clone(),
+                            arguments: serde_json::to_string(&f.args).unwrap_or_default(),
+                        },
+                    }]
+                })
+            }
+        })
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage_metadata.clone().map(Usage::from)
+    }
+}
+
+/// Individual part of response content
+#[derive(Deserialize, Debug)]
+struct GoogleResponsePart {
+    /// Text content of this part (may be absent if functionCall is present)
+    #[serde(default)]
+    text: String,
+    /// Function call contained in this part
+    #[serde(rename = "functionCall")]
+    function_call: Option<GoogleFunctionCall>,
+}
+
+/// MIME type of the response
+#[derive(Deserialize, Debug, Serialize)]
+enum GoogleResponseMimeType {
+    /// Plain text response
+    #[serde(rename = "text/plain")]
+    PlainText,
+    /// JSON response
+    #[serde(rename = "application/json")]
+    Json,
+    /// ENUM as a string response in the response candidates.
+    #[serde(rename = "text/x.enum")]
+    Enum,
+}
+
+/// Google's function calling tool definition
+#[derive(Serialize, Debug)]
+struct GoogleTool {
+    /// The function declarations array
+    #[serde(rename = "functionDeclarations")]
+    function_declarations: Vec<GoogleFunctionDeclaration>,
+}
+
+/// Google function declaration, similar to OpenAI's function definition
+#[derive(Serialize, Debug)]
+struct GoogleFunctionDeclaration {
+    /// Name of the function
+    name: String,
+    /// Description of what the function does
+    description: String,
+    /// Parameters for the function
+    parameters: GoogleFunctionParameters,
+}
+
+impl From<&crate::chat::Tool> for GoogleFunctionDeclaration {
+    fn from(tool: &crate::chat::Tool) -> Self {
+        let properties_value = tool
+            .function
+            .parameters
+            .get("properties")
+            .cloned()
+            .unwrap_or_else(|| serde_json::Value::Object(serde_json::Map::new()));
+
+        GoogleFunctionDeclaration {
+            name: tool.function.name.clone(),
+            description: tool.function.description.clone(),
+            parameters: GoogleFunctionParameters {
+                schema_type: "object".to_string(),
+                properties: properties_value,
+                required: tool
+                    .function
+                    .parameters
+                    .get("required")
+                    .and_then(|v| v.as_array())
+                    .map(|arr| {
+                        arr.iter()
+                            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                            .collect::<Vec<String>>()
+                    })
+                    .unwrap_or_default(),
+            },
+        }
+    }
+}
+
+/// Google function parameters schema
+#[derive(Serialize, Debug)]
+struct GoogleFunctionParameters {
+    /// The type of parameters object (usually "object")
+    #[serde(rename = "type")]
+    schema_type: String,
+    /// Map of parameter names to their properties
+    properties: Value,
+    /// List of required parameter names
+    required: Vec<String>,
+}
+
+/// Google function call object in response
+#[derive(Deserialize, Debug, Serialize)]
+struct GoogleFunctionCall {
+    /// Name of the function to call
+    name: String,
+    /// Arguments for the function call as structured JSON
+    #[serde(default)]
+    args: Value,
+}
+
+/// Google function response wrapper for function results
+///
+/// Format follows Google's Gemini API specification for function calling results:
+/// https://ai.google.dev/docs/function_calling
+///
+/// The expected format is:
+/// {
+///   "role": "user",
+///   "parts": [{
+///     "functionResponse": {
+///       "name": "function_name",
+///       "response": {
+///         "name": "function_name",
+///         "content": { ... } // JSON content returned by the function
+///       }
+///     }
+///   }]
+/// }
+#[derive(Deserialize, Debug, Serialize)]
+struct GoogleFunctionResponse {
+    /// Name of the function that was called
+    name: String,
+    /// Response from the function as structured JSON
+    response: GoogleFunctionResponseContent,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+struct GoogleFunctionResponseContent {
+    /// Name of the function that was called
+    name: String,
+    /// Content of the function response
+    content: Value,
+}
+
+/// Request body for embedding content
+#[derive(Serialize)]
+struct GoogleEmbeddingRequest<'a> {
+    model: &'a str,
+    content: GoogleEmbeddingContent<'a>,
+}
+
+#[derive(Serialize)]
+struct GoogleEmbeddingContent<'a> {
+    parts: Vec<GoogleContentPart<'a>>,
+}
+
+/// Response from the embedding API
+#[derive(Deserialize)]
+struct GoogleEmbeddingResponse {
+    embedding: GoogleEmbedding,
+}
+
+#[derive(Deserialize)]
+struct GoogleEmbedding {
+    values: Vec<f32>,
+}
+
+/// Response from the Vertex AI embedding API
+#[derive(Deserialize)]
+struct VertexEmbeddingResponse {
+    predictions: Vec<VertexEmbeddingPrediction>,
+}
+
+#[derive(Deserialize)]
+struct VertexEmbeddingPrediction {
+    embeddings: GoogleEmbedding,
+}
+
+/// Embedding model used by [`EmbeddingProvider::embed`]
+const EMBEDDING_MODEL: &str = "text-embedding-004";
+
+impl Google {
+    /// Creates a new Google Gemini client with the specified configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - Google API key for authentication
+    /// * `model` - Model identifier (defaults to "gemini-1.5-flash")
+    /// * `max_tokens` - Maximum tokens in response
+    /// * `temperature` - Sampling temperature between 0.0 and 1.0
+    /// * `timeout_seconds` - Request timeout in seconds
+    /// * `system` - System prompt to set context
+    /// * `top_p` - Top-p sampling parameter
+    /// * `top_k` - Top-k sampling parameter
+    ///
+    /// # Returns
+    ///
+    /// A new `Google` client instance
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        api_key: impl Into<String>,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        timeout_seconds: Option<u64>,
+        system: Option<String>,
+        top_p: Option<f32>,
+        top_k: Option<u32>,
+    ) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: model.unwrap_or_else(|| "gemini-1.5-flash".to_string()),
+            max_tokens,
+            temperature,
+            system,
+            timeout_seconds,
+            top_p,
+            top_k,
+            generation: GenerationOptions::default(),
+            vertex: None,
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
+        }
+    }
+
+    /// Call Gemini through Vertex AI in `project` and `location` (a region
+    /// such as "us-central1", or "global"), authenticating with access tokens
+    /// from `token_provider` instead of the API key
+    pub fn with_vertex(
+        mut self,
+        project: impl Into<String>,
+        location: impl Into<String>,
+        token_provider: Arc<dyn GoogleTokenProvider>,
+    ) -> Self {
+        self.vertex = Some(Vertex {
+            project: project.into(),
+            location: location.into(),
+            token_provider,
+        });
+        self
+    }
+
+    /// URL of a method of `model`, on Vertex AI or the Gemini API
+    fn model_url(&self, model: &str, method: &str) -> String {
+        match &self.vertex {
+            Some(vertex) => {
+                let host = match vertex.location.as_str() {
+                    "global" => "aiplatform.googleapis.com".to_string(),
+                    location => format!("{location}-aiplatform.googleapis.com"),
+                };
+                format!(
+                    "https://{host}/v1/projects/{}/locations/{}/publishers/google/models/{model}:{method}",
+                    vertex.project, vertex.location
+                )
+            }
+            None => {
+                format!("https://generativelanguage.googleapis.com/v1beta/models/{model}:{method}")
+            }
+        }
+    }
+
+    /// Fail early when no credentials are configured
+    fn check_credentials(&self) -> Result<(), LLMError> {
+        if self.vertex.is_none() && self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing Google API key".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Add the credentials to `request`: a bearer token on Vertex AI, the
+    /// API key otherwise
+    async fn authorize(&self, request: RequestBuilder) -> Result<RequestBuilder, LLMError> {
+        self.check_credentials()?;
+        match &self.vertex {
+            Some(vertex) => Ok(request.bearer_auth(vertex.token_provider.token().await?)),
+            None => Ok(request.header("x-goog-api-key", &self.api_key)),
+        }
+    }
+
+    /// Builds the request body shared by the streaming and non-streaming
+    /// endpoints
+    fn chat_request<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<&StructuredOutputFormat>,
+    ) -> GoogleChatRequest<'a> {
+        // Gemini takes the system prompt separately from the conversation
+        let system: Vec<GoogleSystemPart> = self
+            .system
+            .iter()
+            .map(String::as_str)
+            .chain(
+                messages
+                    .iter()
+                    .filter(|msg| msg.role == ChatRole::System)
+                    .map(|msg| msg.content.as_str()),
+            )
+            .map(|text| GoogleSystemPart {
+                text: text.to_string(),
+            })
+            .collect();
+        let system_instruction =
+            (!system.is_empty()).then_some(GoogleSystemInstruction { parts: system });
+
+        let mut chat_contents = Vec::with_capacity(messages.len());
+        for msg in messages {
+            // Function results are sent back in user turns
+            let role = match msg.role {
+                ChatRole::System => continue,
+                ChatRole::Assistant => "model",
+                ChatRole::User | ChatRole::Tool => "user",
+            };
+
+            chat_contents.push(GoogleChatContent {
+                role,
+                parts: match &msg.message_type {
+                    MessageType::Text => vec![GoogleContentPart::Text(&msg.content)],
+                    MessageType::Image((image_mime, raw_bytes)) => image_parts(
+                        &msg.content,
+                        GoogleContentPart::InlineData(GoogleInlineData {
+                            mime_type: image_mime.mime_type().to_string(),
+                            data: BASE64.encode(raw_bytes),
+                        }),
+                    ),
+                    MessageType::ImageURL(url) => image_parts(
+                        &msg.content,
+                        GoogleContentPart::FileData(GoogleFileData {
+                            mime_type: image_mime_from_url(url).to_string(),
+                            file_uri: url.clone(),
+                        }),
+                    ),
+                    MessageType::Parts(parts) => (!msg.content.is_empty())
+                        .then_some(GoogleContentPart::Text(&msg.content))
+                        .into_iter()
+                        .chain(parts.iter().map(google_part))
+                        .collect(),
+                    MessageType::Pdf(raw_bytes) => {
+                        vec![GoogleContentPart::InlineData(GoogleInlineData {
+                            mime_type: "application/pdf".to_string(),
+                            data: BASE64.encode(raw_bytes),
+                        })]
+                    }
+                    MessageType::ToolUse(calls) => calls
+                        .iter()
+                        .map(|call| {
+                            GoogleContentPart::FunctionCall(GoogleFunctionCall {
+                                name: call.function.name.clone(),
+                                args: serde_json::from_str(&call.function.arguments)
+                                    .unwrap_or(serde_json::Value::Null),
+                            })
+                        })
+                        .collect(),
+                    MessageType::ToolResult(result) => result
+                        .iter()
+                        .map(|result| {
+                            let parsed_args =
+                                serde_json::from_str::<Value>(&result.function.arguments)
+                                    .unwrap_or(serde_json::Value::Null);
+
+                            GoogleContentPart::FunctionResponse(GoogleFunctionResponse {
+                                name: result.function.name.clone(),
+                                response: GoogleFunctionResponseContent {
+                                    name: result.function.name.clone(),
+                                    content: parsed_args,
+                                },
+                            })
+                        })
+                        .collect(),
+                },
+            });
+        }
+
+        // Convert tools to Google's format if provided
+        let google_tools = tools.map(|t| {
+            vec![GoogleTool {
+                function_declarations: t.iter().map(GoogleFunctionDeclaration::from).collect(),
+            }]
+        });
+
+        // Build generation config
+        let generation_config = {
+            // If json_schema and json_schema.schema are not None, use json_schema.schema as the response schema and set response_mime_type to JSON
+            // Google's API doesn't need the schema to have a "name" field, so we can just use the schema directly.
+            let (response_mime_type, response_schema) = if let Some(json_schema) = json_schema {
+                if let Some(schema) = &json_schema.schema {
+                    // If the schema has an "additionalProperties" field (as required by OpenAI), remove it as Google's API doesn't support it
+                    let mut schema = schema.clone();
+
+                    if let Some(obj) = schema.as_object_mut() {
+                        obj.remove("additionalProperties");
+                    }
+
+                    (Some(GoogleResponseMimeType::Json), Some(schema))
+                } else {
+                    (None, None)
+                }
+            } else {
+                (None, None)
+            };
+
+            Some(GoogleGenerationConfig {
+                max_output_tokens: self.max_tokens,
+                temperature: self.temperature,
+                top_p: self.top_p,
+                top_k: self.top_k,
+                response_mime_type,
+                response_schema,
+                stop_sequences: self.generation.stop_sequences.clone(),
+                seed: self.generation.seed,
+                frequency_penalty: self.generation.frequency_penalty,
+                presence_penalty: self.generation.presence_penalty,
+            })
+        };
+
+        GoogleChatRequest {
+            contents: chat_contents,
+            system_instruction,
+            generation_config,
+            tools: google_tools,
+        }
+    }
+
+    /// Sends a chat request to Google's Gemini API with tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation history as a slice of chat messages
+    /// * `tools` - Optional slice of tools to use in the chat
+    ///
+    /// # Returns
+    ///
+    /// The provider's response text or an error
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.check_credentials()?;
+
+        let req_body = self.chat_request(messages, tools, json_schema.as_ref());
+
+        if log::log_enabled!(log::Level::Trace) {
+            if let Ok(json) = serde_json::to_string(&req_body) {
+                log::trace!("Google Gemini request payload (tool): {json}");
+            }
+        }
+
+        let url = self.model_url(&self.model, "generateContent");
+        let mut request = self
+            .authorize(self.client.post(&url))
+            .await?
+            .json(&req_body);
+
+        if let Some(timeout) = self.timeout_seconds {
+            request = request.timeout(std::time::Duration::from_secs(timeout));
+        }
+
+        let resp = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+
+        log::debug!("Google Gemini HTTP status (tool): {}", resp.status());
+
+        let resp = resp.error_for_status()?;
+
+        // Get the raw response text for debugging
+        let resp_text = resp.text().await?;
+
+        // Try to parse the response
+        let json_resp: Result<GoogleChatResponse, serde_json::Error> =
+            serde_json::from_str(&resp_text);
+
+        match json_resp {
+            Ok(response) => Ok(Box::new(response)),
+            Err(e) => {
+                // Return a more descriptive error with the raw response
+                Err(LLMError::ResponseFormatError {
+                    message: format!("Failed to decode Google API response: {e}"),
+                    raw_response: resp_text,
+                })
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for Google {
+    /// Sends a chat request to Google's Gemini API.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - Slice of chat messages representing the conversation
+    ///
+    /// # Returns
+    ///
+    /// The model's response text or an error
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.chat_with_tools(messages, tools, json_schema).await
+    }
+
+    /// Sends a streaming chat request to Google's Gemini API.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - Slice of chat messages representing the conversation
+    ///
+    /// # Returns
+    ///
+    /// A stream of text tokens or an error
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<std::pin::Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError>
+    {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text and function calls. Gemini sends each function call
+    /// whole, so every tool call delta carries the complete arguments. The
+    /// last chunk carries the token usage.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<
+        std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>,
+        LLMError,
+    > {
+        self.check_credentials()?;
+
+        let req_body = self.chat_request(messages, tools, json_schema.as_ref());
+
+        let url = format!(
+            "{}?alt=sse",
+            self.model_url(&self.model, "streamGenerateContent")
+        );
+        let mut request = self
+            .authorize(self.client.post(&url))
+            .await?
+            .json(&req_body);
+
+        if let Some(timeout) = self.timeout_seconds {
+            request = request.timeout(std::time::Duration::from_secs(timeout));
+        }
+
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+
+        let response = check_response_status(response).await?;
+
+        Ok(create_stream(response))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for Google {
+    /// Performs a completion request using the chat endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - Completion request parameters
+    ///
+    /// # Returns
+    ///
+    /// The completion response or an error
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let chat_message = ChatMessage::user().content(req.prompt.clone()).build();
+        let response = self.chat(&[chat_message], None, json_schema).await?;
+        if response.text().is_some() {
+            Ok(CompletionResponse::from_chat(response.as_ref()))
+        } else {
+            Err(LLMError::ProviderError(
+                "No answer returned by Google".to_string(),
+            ))
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for Google {
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.check_credentials()?;
+
+        // Vertex AI embeds all texts in one request
+        if self.vertex.is_some() {
+            if texts.is_empty() {
+                return Ok(Vec::new());
+            }
+            let instances: Vec<Value> = texts
+                .iter()
+                .map(|text| serde_json::json!({ "content": text }))
+                .collect();
+            let url = self.model_url(EMBEDDING_MODEL, "predict");
+            let resp: VertexEmbeddingResponse = self
+                .authorize(self.client.post(&url))
+                .await?
+                .json(&serde_json::json!({ "instances": instances }))
+                .with_scoped_headers()
+                .send_with_retry(&self.retry_policy)
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            return Ok(resp
+                .predictions
+                .into_iter()
+                .map(|prediction| prediction.embeddings.values)
+                .collect());
+        }
+
+        let mut embeddings = Vec::new();
+
+        // Process each text separately as Gemini API accepts one text at a time
+        for text in texts {
+            let req_body = GoogleEmbeddingRequest {
+                model: "models/text-embedding-004",
+                content: GoogleEmbeddingContent {
+                    parts: vec![GoogleContentPart::Text(&text)],
+                },
+            };
+
+            let url = self.model_url(EMBEDDING_MODEL, "embedContent");
+
+            let resp = self
+                .authorize(self.client.post(&url))
+                .await?
+                .json(&req_body)
+                .with_scoped_headers()
+                .send_with_retry(&self.retry_policy)
+                .await?
+                .error_for_status()?;
+
+            let embedding_resp: GoogleEmbeddingResponse = resp.json().await?;
+            embeddings.push(embedding_resp.embedding.values);
+        }
+
+        Ok(embeddings)
+    }
+}
+
+impl LLMProvider for Google {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
+
+/// Turns the server-sent events of a streaming response into stream chunks
+fn create_stream(
+    response: reqwest::Response,
+) -> std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>> {
+    let stream = response
+        .bytes_stream()
+        .scan(GoogleStreamParser::default(), |parser, chunk| {
+            let results = match chunk {
+                Ok(bytes) => parser.push(&bytes),
+                Err(e) => vec![Err(LLMError::HttpError(e.to_string()))],
+            };
+            futures::future::ready(Some(results))
+        })
+        .flat_map(futures::stream::iter);
+    Box::pin(stream)
+}
+
+/// Splits a streaming response into `data:` lines, each holding one
+/// complete JSON response
+#[derive(Default)]
+struct GoogleStreamParser {
+    /// Bytes of a line not yet terminated
+    line_buffer: Vec<u8>,
+    /// Function calls seen so far, numbering the tool call deltas
+    tool_calls: usize,
+}
+
+impl GoogleStreamParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<Result<StreamResponse, LLMError>> {
+        self.line_buffer.extend_from_slice(bytes);
+        let mut results = Vec::new();
+        while let Some(end) = self.line_buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                results.extend(self.parse_data(data.trim()));
+            }
+        }
+        results
+    }
+
+    fn parse_data(&mut self, data: &str) -> Option<Result<StreamResponse, LLMError>> {
+        let response: GoogleStreamResponse = match serde_json::from_str(data) {
+            Ok(response) => response,
+            Err(e) => {
+                return Some(Err(LLMError::ResponseFormatError {
+                    message: format!("Failed to decode Google stream chunk: {e}"),
+                    raw_response: data.to_string(),
+                }))
+            }
+        };
+        if let Some(error) = response.error {
+            return Some(Err(LLMError::ProviderError(format!(
+                "Google API stream error: {error}"
+            ))));
+        }
+
+        let candidate = response.candidates.and_then(|c| c.into_iter().next());
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        let mut finished = false;
+        if let Some(candidate) = candidate {
+            finished = candidate.finish_reason.is_some();
+            for part in candidate.content.parts {
+                content.push_str(&part.text);
+                if let Some(call) = part.function_call {
+                    tool_calls.push(StreamToolCallDelta {
+                        index: self.tool_calls,
+                        id: Some(format!("call_{}", call.name)),
+                        function: Some(StreamToolCallFunction {
+                            name: call.name,
+                            arguments: serde_json::to_string(&call.args).unwrap_or_default(),
+                        }),
+                    });
+                    self.tool_calls += 1;
+                }
+            }
+        }
+
+        // Every chunk carries the running token counts; only the final ones
+        // are reported
+        let usage = response
+            .usage_metadata
+            .filter(|_| finished)
+            .map(Usage::from);
+        let delta = StreamDelta {
+            content: (!content.is_empty()).then_some(content),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            thinking: None,
+            reasoning: None,
+        };
+        let choices = if delta.content.is_some() || delta.tool_calls.is_some() {
+            vec![StreamChoice { delta }]
+        } else {
+            Vec::new()
+        };
+        (!choices.is_empty() || usage.is_some()).then_some(Ok(StreamResponse { choices, usage }))
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for Google {}
+
+impl LLMBuilder<Google> {
+    /// Call Gemini through Vertex AI in `project` and `location` (a region
+    /// such as "us-central1", or "global") instead of the Gemini API.
+    ///
+    /// No API key is needed then. Access tokens come from the provider set
+    /// with [`token_provider`](Self::token_provider), or else from the
+    /// service account key file named by `GOOGLE_APPLICATION_CREDENTIALS`,
+    /// or else from the metadata server of the Google Cloud resource the
+    /// agent runs on.
+    pub fn vertex(mut self, project: impl Into<String>, location: impl Into<String>) -> Self {
+        self.google_vertex = Some((project.into(), location.into()));
+        self
+    }
+
+    /// Authenticate to Vertex AI with access tokens from `provider`
+    pub fn token_provider(mut self, provider: impl GoogleTokenProvider + 'static) -> Self {
+        self.google_token_provider = Some(Arc::new(provider));
+        self
+    }
+
+    pub fn build(self) -> Result<Arc<Google>, LLMError> {
+        let Some((project, location)) = self.google_vertex else {
+            if self.google_token_provider.is_some() {
+                return Err(LLMError::InvalidRequest(
+                    "A Google token provider is only used with Vertex AI; set vertex(project, location)"
+                        .to_string(),
+                ));
+            }
+            let api_key = self.api_key.ok_or_else(|| {
+                LLMError::InvalidRequest("No API key provided for Google".to_string())
+            })?;
+
+            let mut google = Google::new(
+                api_key,
+                self.model,
+                self.max_tokens,
+                self.temperature,
+                self.timeout_seconds,
+                self.system,
+                self.top_p,
+                self.top_k,
+            );
+            google.retry_policy = self.retry_policy;
+            self.http
+                .configure(&mut google.client, self.timeout_seconds)?;
+            google.generation = self.generation;
+            return Ok(Arc::new(google));
+        };
+
+        let token_provider: Arc<dyn GoogleTokenProvider> = match self.google_token_provider {
+            Some(provider) => provider,
+            None if std::env::var_os("GOOGLE_APPLICATION_CREDENTIALS").is_some() => {
+                Arc::new(ServiceAccountCredential::from_env()?)
+            }
+            None => Arc::new(MetadataServerCredential::new()),
+        };
+
+        let mut google = Google::new(
+            self.api_key.unwrap_or_default(),
+            self.model,
+            self.max_tokens,
+            self.temperature,
+            self.timeout_seconds,
+            self.system,
+            self.top_p,
+            self.top_k,
+        )
+        .with_vertex(project, location, token_provider);
+        google.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut google.client, self.timeout_seconds)?;
+        google.generation = self.generation;
+
+        Ok(Arc::new(google))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{FunctionTool, ImageMime};
+    use serde_json::json;
+
+    struct StaticToken;
+
+    #[async_trait]
+    impl GoogleTokenProvider for StaticToken {
+        async fn token(&self) -> Result<String, LLMError> {
+            Ok("token".to_string())
+        }
+    }
+
+    fn google() -> Google {
+        Google::new(
+            "key",
+            Some("gemini-2.0-flash".to_string()),
+            None,
+            None,
+            None,
+            Some("Be brief.".to_string()),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_system_prompts_become_the_system_instruction() {
+        let client = google();
+        let call = ToolCall {
+            id: "call_lookup".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "lookup".to_string(),
+                arguments: r#"{"q":"rust"}"#.to_string(),
+            },
+        };
+        let messages = [
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: "Answer in French.".to_string(),
+                reasoning: Vec::new(),
+            },
+            ChatMessage::user().content("Look up rust").build(),
+            ChatMessage::assistant()
+                .tool_use(vec![call.clone()])
+                .build(),
+            ChatMessage::user().tool_result(vec![call]).build(),
+        ];
+        let tools = [Tool {
+            tool_type: "function".to_string(),
+            function: FunctionTool {
+                name: "lookup".to_string(),
+                description: "Search".to_string(),
+                parameters: json!({"type": "object", "properties": {"q": {"type": "string"}}}),
+            },
+        }];
+
+        let body =
+            serde_json::to_value(client.chat_request(&messages, Some(&tools), None)).unwrap();
+        assert_eq!(
+            body["systemInstruction"],
+            json!({"parts": [{"text": "Be brief."}, {"text": "Answer in French."}]})
+        );
+        let roles: Vec<&str> = body["contents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|content| content["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["user", "model", "user"]);
+        assert_eq!(
+            body["contents"][1]["parts"][0]["functionCall"],
+            json!({"name": "lookup", "args": {"q": "rust"}})
+        );
+        assert_eq!(
+            body["tools"][0]["functionDeclarations"][0]["name"],
+            "lookup"
+        );
+    }
+
+    #[test]
+    fn test_message_parts_become_inline_and_file_data() {
+        let messages = [ChatMessage::user()
+            .content("Describe both")
+            .part(ContentPart::image(ImageMime::WEBP, vec![1, 2, 3]))
+            .part(ContentPart::ImageUrl {
+                url: "gs://bucket/photo".to_string(),
+                mime: Some(ImageMime::PNG),
+            })
+            .build()];
+
+        let body = serde_json::to_value(google().chat_request(&messages, None, None)).unwrap();
+        assert_eq!(
+            body["contents"][0]["parts"],
+            json!([
+                {"text": "Describe both"},
+                {"inlineData": {"mime_type": "image/webp", "data": "AQID"}},
+                {"fileData": {"mimeType": "image/png", "fileUri": "gs://bucket/photo"}},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_generation_options_go_in_the_generation_config() {
+        let mut client = google();
+        client.generation.stop_sequences = vec!["END".to_string()];
+        client.generation.seed = Some(7);
+        client.generation.presence_penalty = Some(0.5);
+        let messages = [ChatMessage::user().content("Hi").build()];
+
+        let body = serde_json::to_value(client.chat_request(&messages, None, None)).unwrap();
+        assert_eq!(
+            body["generationConfig"],
+            json!({"stopSequences": ["END"], "seed": 7, "presencePenalty": 0.5})
+        );
+    }
+
+    #[test]
+    fn test_vertex_urls_name_the_project_and_location() {
+        let client = google().with_vertex("my-project", "europe-west4", Arc::new(StaticToken));
+        assert_eq!(
+            client.model_url(&client.model, "generateContent"),
+            "https://europe-west4-aiplatform.googleapis.com/v1/projects/my-project/locations/europe-west4/publishers/google/models/gemini-2.0-flash:generateContent"
+        );
+
+        let client = google().with_vertex("my-project", "global", Arc::new(StaticToken));
+        assert!(client
+            .model_url(&client.model, "generateContent")
+            .starts_with(
+                "https://aiplatform.googleapis.com/v1/projects/my-project/locations/global/"
+            ));
+
+        assert_eq!(
+            google().model_url("gemini-2.0-flash", "streamGenerateContent"),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:streamGenerateContent"
+        );
+    }
+
+    #[test]
+    fn test_stream_chunks_carry_text_function_calls_and_usage() {
+        let events = [
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"text": "Let me "}]}}],
+                   "usageMetadata": {"promptTokenCount": 9, "totalTokenCount": 9}}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [
+                {"functionCall": {"name": "lookup", "args": {"q": "rust"}}},
+                {"functionCall": {"name": "lookup", "args": {"q": "gemini"}}},
+            ]}, "finishReason": "STOP"}],
+                   "usageMetadata": {"promptTokenCount": 9, "candidatesTokenCount": 6, "totalTokenCount": 15}}),
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("data: {event}\r\n\r\n"))
+            .collect();
+
+        // Split mid-line to check that lines are reassembled
+        let mut parser = GoogleStreamParser::default();
+        let (head, tail) = body.as_bytes().split_at(20);
+        let mut chunks = parser.push(head);
+        assert!(chunks.is_empty());
+        chunks.extend(parser.push(tail));
+        let chunks: Vec<StreamResponse> = chunks.into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].choices[0].delta.content.as_deref(),
+            Some("Let me ")
+        );
+        assert!(chunks[0].usage.is_none());
+
+        let calls = chunks[1].choices[0].delta.tool_calls.as_ref().unwrap();
+        assert_eq!(
+            calls.iter().map(|call| call.index).collect::<Vec<_>>(),
+            [0, 1]
+        );
+        let function = calls[1].function.as_ref().unwrap();
+        assert_eq!(function.name, "lookup");
+        assert_eq!(function.arguments, r#"{"q":"gemini"}"#);
+        assert_eq!(chunks[1].usage.as_ref().unwrap().total_tokens, 15);
+    }
+}
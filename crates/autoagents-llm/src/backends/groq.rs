@@ -3,14 +3,19 @@
 //! This module provides integration with Groq's LLM models through their API.
 
 use crate::builder::LLMBuilder;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::RetryRequestExt;
 use crate::{
+    audio::{SpeechToTextProvider, Transcription, TranscriptionRequest},
     builder::LLMBackend,
     chat::{StructuredOutputFormat, ToolChoice},
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
     error::LLMError,
     models::{ModelListRequest, ModelListResponse, ModelsProvider, StandardModelListResponse},
-    providers::openai_compatible::{OpenAICompatibleProvider, OpenAIProviderConfig},
+    providers::openai_compatible::{
+        transcribe_audio, OpenAICompatibleProvider, OpenAIProviderConfig,
+    },
     LLMProvider,
 };
 use async_trait::async_trait;
@@ -85,7 +90,35 @@ impl Groq {
     }
 }
 
-impl LLMProvider for Groq {}
+impl LLMProvider for Groq {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for Groq {
+    /// Transcribes with `whisper-large-v3-turbo` unless the request names
+    /// another model.
+    async fn transcribe(&self, request: &TranscriptionRequest) -> Result<Transcription, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing Groq API key".to_string()));
+        }
+        transcribe_audio(
+            &self.client,
+            &self.base_url,
+            &self.api_key,
+            request.model.as_deref().unwrap_or("whisper-large-v3-turbo"),
+            request,
+            &self.retry_policy,
+        )
+        .await
+    }
+}
 
 #[async_trait]
 impl CompletionProvider for Groq {
@@ -96,6 +129,7 @@ impl CompletionProvider for Groq {
     ) -> Result<CompletionResponse, LLMError> {
         Ok(CompletionResponse {
             text: "Groq completion not implemented.".into(),
+            usage: None,
         })
     }
 }
@@ -125,7 +159,8 @@ impl ModelsProvider for Groq {
             .client
             .get(&url)
             .bearer_auth(&self.api_key)
-            .send()
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
 
@@ -139,11 +174,13 @@ impl ModelsProvider for Groq {
 
 impl LLMBuilder<Groq> {
     pub fn build(self) -> Result<Arc<Groq>, LLMError> {
+        self.generation
+            .reject_unsupported("Groq", &["frequency_penalty", "presence_penalty"])?;
         let api_key = self
             .api_key
             .ok_or_else(|| LLMError::InvalidRequest("No API key provided for Groq".to_string()))?;
 
-        let groq = Groq::with_config(
+        let mut groq = Groq::with_config(
             api_key,
             self.base_url,
             self.model,
@@ -160,6 +197,10 @@ impl LLMBuilder<Groq> {
             self.enable_parallel_tool_use,
             self.normalize_response,
         );
+        groq.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut groq.client, self.timeout_seconds)?;
+        groq.generation = self.generation;
 
         Ok(Arc::new(groq))
     }
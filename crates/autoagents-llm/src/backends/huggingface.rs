@@ -0,0 +1,576 @@
+//! Hugging Face Text Generation Inference client implementation.
+//!
+//! This module provides integration with servers running Text Generation
+//! Inference (TGI), which backs Hugging Face Inference Endpoints as well as
+//! self-hosted deployments. Chat goes through TGI's OpenAI-compatible
+//! `/v1/chat/completions` endpoint; completions use the native `/generate`
+//! endpoint, and [`HuggingFace::generate_stream`] streams tokens from
+//! `/generate_stream`. Stop sequences, the seed and the frequency penalty
+//! apply to both; the presence penalty only to chat.
+
+use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
+use crate::{
+    builder::LLMBuilder,
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, GenerationOptions, StreamResponse,
+        StructuredOutputFormat, Tool, ToolChoice,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    providers::openai_compatible::{
+        create_sse_stream, openai_messages, OpenAIChatMessage, OpenAIChatResponse,
+        OpenAIResponseFormat, OpenAIStreamOptions,
+    },
+    LLMProvider,
+};
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use reqwest::{Client, RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Client for a Text Generation Inference server.
+///
+/// TGI serves a single model, which its chat endpoint calls "tgi".
+pub struct HuggingFace {
+    /// Hugging Face access token, if the endpoint is protected
+    pub api_key: Option<String>,
+    /// URL of the endpoint, e.g. "https://xyz.endpoints.huggingface.cloud/"
+    pub base_url: Url,
+    pub model: String,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub timeout_seconds: Option<u64>,
+    pub system: Option<String>,
+    pub top_p: Option<f32>,
+    /// Top-k sampling, which only the native endpoints accept
+    pub top_k: Option<u32>,
+    pub tool_choice: Option<ToolChoice>,
+    /// Whether streamed tool calls are sent whole rather than in fragments
+    pub normalize_response: bool,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    pub retry_policy: RetryPolicy,
+    client: Client,
+}
+
+/// Request payload for the chat completions endpoint.
+#[derive(Serialize)]
+struct HuggingFaceChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<OpenAIChatMessage<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(flatten)]
+    generation: &'a GenerationOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<&'a [Tool]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'a ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<OpenAIResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAIStreamOptions>,
+}
+
+/// Request payload for the `/generate` and `/generate_stream` endpoints.
+#[derive(Serialize)]
+struct TgiGenerateRequest<'a> {
+    inputs: &'a str,
+    parameters: TgiParameters<'a>,
+}
+
+#[derive(Serialize)]
+struct TgiParameters<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_new_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    stop: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    /// Only return the generated text, without the prompt
+    return_full_text: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grammar: Option<TgiGrammar>,
+}
+
+/// Constrains generation to JSON matching a schema.
+#[derive(Serialize)]
+struct TgiGrammar {
+    #[serde(rename = "type")]
+    grammar_type: &'static str,
+    value: Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct TgiGenerateResponse {
+    generated_text: String,
+}
+
+/// An event of the `/generate_stream` endpoint.
+#[derive(Deserialize, Debug)]
+struct TgiStreamEvent {
+    token: Option<TgiToken>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TgiToken {
+    text: String,
+    /// Whether the token is a special token such as end-of-sequence
+    #[serde(default)]
+    special: bool,
+}
+
+impl HuggingFace {
+    /// Creates a new client for a Text Generation Inference server.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - URL of the endpoint or server
+    /// * `api_key` - Hugging Face access token, if the endpoint needs one
+    /// * `model` - Model name sent to the chat endpoint (defaults to "tgi")
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        base_url: impl Into<String>,
+        api_key: Option<String>,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        timeout_seconds: Option<u64>,
+        system: Option<String>,
+        top_p: Option<f32>,
+        top_k: Option<u32>,
+    ) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Self {
+            api_key,
+            base_url: Url::parse(&base_url).expect("Failed to parse base URL"),
+            model: model.unwrap_or_else(|| "tgi".to_string()),
+            max_tokens,
+            temperature,
+            timeout_seconds,
+            system,
+            top_p,
+            top_k,
+            tool_choice: None,
+            normalize_response: true,
+            generation: GenerationOptions::default(),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
+        }
+    }
+
+    /// Starts a POST request to an API path, authenticating when a token is set
+    fn post(&self, path: &str) -> Result<RequestBuilder, LLMError> {
+        let url = self
+            .base_url
+            .join(path)
+            .map_err(|e| LLMError::HttpError(e.to_string()))?;
+        let mut request = self.client.post(url);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        if let Some(timeout) = self.timeout_seconds {
+            request = request.timeout(std::time::Duration::from_secs(timeout));
+        }
+        Ok(request)
+    }
+
+    fn chat_request<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        tools: Option<&'a [Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+        stream: bool,
+    ) -> HuggingFaceChatRequest<'a> {
+        let tools = tools.filter(|tools| !tools.is_empty());
+
+        HuggingFaceChatRequest {
+            model: &self.model,
+            messages: openai_messages(messages, self.system.as_deref()),
+            max_tokens: self.max_tokens,
+            temperature: self.temperature,
+            stream,
+            top_p: self.top_p,
+            generation: &self.generation,
+            tools,
+            tool_choice: tools.and(self.tool_choice.as_ref()),
+            response_format: json_schema.map(OpenAIResponseFormat::from),
+            stream_options: stream.then_some(OpenAIStreamOptions {
+                include_usage: true,
+            }),
+        }
+    }
+
+    async fn send_chat(
+        &self,
+        body: &HuggingFaceChatRequest<'_>,
+    ) -> Result<reqwest::Response, LLMError> {
+        if log::log_enabled!(log::Level::Trace) {
+            if let Ok(json) = serde_json::to_string(body) {
+                log::trace!("Hugging Face request payload: {json}");
+            }
+        }
+
+        let response = self
+            .post("v1/chat/completions")?
+            .json(body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+
+        log::debug!("Hugging Face HTTP status: {}", response.status());
+
+        check_response_status(response).await
+    }
+
+    fn generate_request<'a>(
+        &'a self,
+        req: &'a CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<TgiGenerateRequest<'a>, LLMError> {
+        self.generation
+            .reject_unsupported("Hugging Face /generate", &["presence_penalty"])?;
+        Ok(TgiGenerateRequest {
+            inputs: &req.prompt,
+            parameters: TgiParameters {
+                max_new_tokens: req.max_tokens.or(self.max_tokens),
+                temperature: req.temperature.or(self.temperature),
+                top_p: self.top_p,
+                top_k: self.top_k,
+                stop: &self.generation.stop_sequences,
+                seed: self.generation.seed,
+                frequency_penalty: self.generation.frequency_penalty,
+                return_full_text: false,
+                grammar: json_schema
+                    .and_then(|format| format.schema)
+                    .map(|schema| TgiGrammar {
+                        grammar_type: "json",
+                        value: schema,
+                    }),
+            },
+        })
+    }
+
+    /// Streams the tokens generated for a raw prompt from `/generate_stream`,
+    /// leaving out special tokens such as end-of-sequence.
+    pub async fn generate_stream(
+        &self,
+        req: &CompletionRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        let body = self.generate_request(req, None)?;
+        let response = self
+            .post("generate_stream")?
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let response = check_response_status(response).await?;
+
+        let stream = response
+            .bytes_stream()
+            .scan(TgiStreamParser::default(), |parser, chunk| {
+                let results = match chunk {
+                    Ok(bytes) => parser.push(&bytes),
+                    Err(e) => vec![Err(LLMError::HttpError(e.to_string()))],
+                };
+                futures::future::ready(Some(results))
+            })
+            .flat_map(futures::stream::iter);
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Splits the server-sent events of `/generate_stream` into token texts
+#[derive(Default)]
+struct TgiStreamParser {
+    /// Bytes of a line not yet terminated
+    line_buffer: Vec<u8>,
+}
+
+impl TgiStreamParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<Result<String, LLMError>> {
+        self.line_buffer.extend_from_slice(bytes);
+        let mut results = Vec::new();
+        while let Some(end) = self.line_buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                results.extend(parse_event(data.trim()));
+            }
+        }
+        results
+    }
+}
+
+fn parse_event(data: &str) -> Option<Result<String, LLMError>> {
+    let event: TgiStreamEvent = match serde_json::from_str(data) {
+        Ok(event) => event,
+        Err(e) => {
+            return Some(Err(LLMError::ResponseFormatError {
+                message: format!("Failed to decode TGI stream event: {e}"),
+                raw_response: data.to_string(),
+            }))
+        }
+    };
+    if let Some(error) = event.error {
+        return Some(Err(LLMError::ProviderError(error)));
+    }
+    event
+        .token
+        .filter(|token| !token.special)
+        .map(|token| Ok(token.text))
+}
+
+#[async_trait]
+impl ChatProvider for HuggingFace {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let body = self.chat_request(messages, tools, json_schema, false);
+        let response = self.send_chat(&body).await?;
+
+        let resp_text = response.text().await?;
+        let json_resp: OpenAIChatResponse =
+            serde_json::from_str(&resp_text).map_err(|e| LLMError::ResponseFormatError {
+                message: format!("Failed to decode Hugging Face response: {e}"),
+                raw_response: resp_text.clone(),
+            })?;
+        Ok(Box::new(json_resp))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .filter(|content| !content.is_empty())
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text and tool calls, with the token usage in the last chunk.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        let body = self.chat_request(messages, tools, json_schema, true);
+        let response = self.send_chat(&body).await?;
+        Ok(create_sse_stream(response, self.normalize_response))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for HuggingFace {
+    /// Completes the raw prompt through `/generate`, without applying the
+    /// model's chat template. A schema constrains the output with a JSON
+    /// grammar.
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let body = self.generate_request(req, json_schema)?;
+        let response = self
+            .post("generate")?
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let response = check_response_status(response).await?;
+
+        let json_resp: TgiGenerateResponse = response.json().await?;
+        Ok(CompletionResponse {
+            text: json_resp.generated_text,
+            usage: None,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HuggingFace {
+    async fn embed(&self, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Err(LLMError::ProviderError(
+            "Text Generation Inference does not serve embeddings".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for HuggingFace {}
+
+impl LLMProvider for HuggingFace {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
+
+impl LLMBuilder<HuggingFace> {
+    pub fn build(self) -> Result<Arc<HuggingFace>, LLMError> {
+        let base_url = self.base_url.ok_or_else(|| {
+            LLMError::InvalidRequest("No base URL provided for Hugging Face".to_string())
+        })?;
+
+        let mut huggingface = HuggingFace::new(
+            base_url,
+            self.api_key,
+            self.model,
+            self.max_tokens,
+            self.temperature,
+            self.timeout_seconds,
+            self.system,
+            self.top_p,
+            self.top_k,
+        );
+        huggingface.tool_choice = self.tool_choice;
+        huggingface.normalize_response = self.normalize_response.unwrap_or(true);
+        huggingface.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut huggingface.client, self.timeout_seconds)?;
+        huggingface.generation = self.generation;
+
+        Ok(Arc::new(huggingface))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn huggingface() -> HuggingFace {
+        let mut huggingface = HuggingFace::new(
+            "http://localhost:3000",
+            None,
+            None,
+            Some(64),
+            None,
+            None,
+            None,
+            None,
+            Some(40),
+        );
+        huggingface.generation.stop_sequences = vec!["\nUser:".to_string()];
+        huggingface
+    }
+
+    #[test]
+    fn test_generate_request_sends_stop_sequences_and_grammar() {
+        let huggingface = huggingface();
+        let req = CompletionRequest::new("Q: 2+2?\nA:");
+        let format = StructuredOutputFormat {
+            name: "answer".to_string(),
+            description: None,
+            schema: Some(json!({"type": "object"})),
+            strict: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(huggingface.generate_request(&req, Some(format)).unwrap())
+                .unwrap(),
+            json!({
+                "inputs": "Q: 2+2?\nA:",
+                "parameters": {
+                    "max_new_tokens": 64,
+                    "top_k": 40,
+                    "stop": ["\nUser:"],
+                    "return_full_text": false,
+                    "grammar": {"type": "json", "value": {"type": "object"}}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_request_sends_stop_sequences() {
+        let huggingface = huggingface();
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let body =
+            serde_json::to_value(huggingface.chat_request(&messages, None, None, false)).unwrap();
+
+        assert_eq!(body["model"], "tgi");
+        assert_eq!(body["stop"], json!(["\nUser:"]));
+        assert!(body.get("top_k").is_none());
+    }
+
+    #[test]
+    fn test_generate_request_rejects_presence_penalty() {
+        let mut huggingface = huggingface();
+        huggingface.generation.presence_penalty = Some(0.5);
+        let req = CompletionRequest::new("Hi");
+
+        let err = huggingface.generate_request(&req, None).err().unwrap();
+        assert!(matches!(err, LLMError::InvalidRequest(msg) if msg.contains("presence_penalty")));
+
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let body =
+            serde_json::to_value(huggingface.chat_request(&messages, None, None, false)).unwrap();
+        assert_eq!(body["presence_penalty"], 0.5);
+    }
+
+    #[test]
+    fn test_stream_parser_skips_special_tokens() {
+        let mut parser = TgiStreamParser::default();
+        let mut results = parser.push(
+            b"data:{\"token\":{\"id\":1,\"text\":\"Hel\",\"logprob\":-0.1,\"special\":false}}\n\ndata:{\"token\":{\"id\":2,\"text\":\"lo\"",
+        );
+        results.extend(parser.push(
+            b",\"logprob\":-0.2,\"special\":false}}\n\ndata:{\"token\":{\"id\":3,\"text\":\"</s>\",\"logprob\":0.0,\"special\":true},\"generated_text\":\"Hello\"}\n\n",
+        ));
+        let tokens: Vec<String> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(tokens, vec!["Hel", "lo"]);
+
+        let error = parser
+            .push(b"data:{\"error\":\"Input validation error\",\"error_type\":\"validation\"}\n");
+        assert!(
+            matches!(&error[..], [Err(LLMError::ProviderError(msg))] if msg == "Input validation error")
+        );
+    }
+}
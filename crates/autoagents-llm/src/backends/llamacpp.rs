@@ -0,0 +1,656 @@
+//! llama.cpp server client implementation for chat and completion functionality.
+//!
+//! This module provides integration with the HTTP server that ships with
+//! llama.cpp (`llama-server`), which serves local GGUF models. Chat goes
+//! through its OpenAI-compatible `/v1/chat/completions` endpoint and plain
+//! completions through the native `/completion` endpoint. Both accept a GBNF
+//! grammar that constrains what the model may generate, and can be pinned to
+//! one of the server's slots to reuse its prompt cache.
+
+use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
+use crate::{
+    builder::{LLMBackend, LLMBuilder},
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, GenerationOptions, StreamResponse,
+        StructuredOutputFormat, Tool, ToolChoice, Usage,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider, StandardModelListResponse},
+    providers::openai_compatible::{
+        create_sse_stream, openai_messages, OpenAIChatMessage, OpenAIChatResponse,
+        OpenAIResponseFormat, OpenAIStreamOptions,
+    },
+    LLMProvider,
+};
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use reqwest::{Client, RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+const DEFAULT_BASE_URL: &str = "http://localhost:8080/";
+
+/// Client for a llama.cpp server.
+///
+/// The server runs a single model, so `model` is only reported back in
+/// responses.
+pub struct LlamaCpp {
+    /// Key the server was started with through `--api-key`, if any
+    pub api_key: Option<String>,
+    pub base_url: Url,
+    pub model: String,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub timeout_seconds: Option<u64>,
+    pub system: Option<String>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub tool_choice: Option<ToolChoice>,
+    /// Whether streamed tool calls are sent whole rather than in fragments
+    pub normalize_response: bool,
+    /// GBNF grammar that constrains generation. It is not sent along with
+    /// tools or a structured output schema, which the server turns into
+    /// grammars of their own.
+    pub grammar: Option<String>,
+    /// Slot that processes requests, to reuse the prompt it has cached
+    pub slot_id: Option<u32>,
+    /// Whether the server reuses the cached prompt of the slot when a new
+    /// prompt shares its prefix
+    pub cache_prompt: Option<bool>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    pub retry_policy: RetryPolicy,
+    client: Client,
+}
+
+/// Request payload for the chat completions endpoint.
+#[derive(Serialize)]
+struct LlamaCppChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<OpenAIChatMessage<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<&'a [Tool]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'a ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<OpenAIResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAIStreamOptions>,
+    #[serde(flatten)]
+    server: LlamaCppServerParams<'a>,
+}
+
+/// Request payload for the native completion endpoint.
+#[derive(Serialize)]
+struct LlamaCppCompletionRequest<'a> {
+    prompt: &'a str,
+    /// Maximum number of tokens to generate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_schema: Option<Value>,
+    stream: bool,
+    #[serde(flatten)]
+    server: LlamaCppServerParams<'a>,
+}
+
+/// Parameters both endpoints accept on top of temperature, top-p and top-k.
+#[derive(Serialize)]
+struct LlamaCppServerParams<'a> {
+    #[serde(flatten)]
+    generation: &'a GenerationOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grammar: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_slot: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_prompt: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LlamaCppCompletionResponse {
+    content: String,
+    /// Prompt tokens evaluated
+    tokens_evaluated: Option<u32>,
+    /// Tokens generated
+    tokens_predicted: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct LlamaCppEmbeddingRequest<'a> {
+    model: &'a str,
+    input: Vec<String>,
+    encoding_format: &'a str,
+}
+
+#[derive(Deserialize)]
+struct LlamaCppEmbeddingData {
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct LlamaCppEmbeddingResponse {
+    data: Vec<LlamaCppEmbeddingData>,
+}
+
+/// State of one of the server's slots, which each process one request at a
+/// time and keep its prompt cached.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LlamaCppSlot {
+    pub id: u32,
+    /// Size of the slot's context window
+    pub n_ctx: Option<u32>,
+    /// Whether the slot is busy with a request
+    #[serde(default)]
+    pub is_processing: bool,
+    /// Remaining fields reported by the server, which vary between versions
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+#[derive(Serialize)]
+struct LlamaCppSlotFile<'a> {
+    filename: &'a str,
+}
+
+impl LlamaCpp {
+    /// Creates a new llama.cpp client with the specified configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Base URL of the server (defaults to "http://localhost:8080/")
+    /// * `api_key` - Key the server was started with, if any
+    /// * `model` - Model name reported in responses (defaults to "default")
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        timeout_seconds: Option<u64>,
+        system: Option<String>,
+        top_p: Option<f32>,
+        top_k: Option<u32>,
+    ) -> Self {
+        let base_url = base_url
+            .map(|url| {
+                if url.ends_with('/') {
+                    url
+                } else {
+                    format!("{url}/")
+                }
+            })
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Self {
+            api_key,
+            base_url: Url::parse(&base_url).expect("Failed to parse base URL"),
+            model: model.unwrap_or_else(|| "default".to_string()),
+            max_tokens,
+            temperature,
+            timeout_seconds,
+            system,
+            top_p,
+            top_k,
+            tool_choice: None,
+            normalize_response: true,
+            grammar: None,
+            slot_id: None,
+            cache_prompt: None,
+            generation: GenerationOptions::default(),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
+        }
+    }
+
+    /// Starts a request to an API path, authenticating when an API key is set
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<RequestBuilder, LLMError> {
+        let url = self
+            .base_url
+            .join(path)
+            .map_err(|e| LLMError::HttpError(e.to_string()))?;
+        let request = self.client.request(method, url);
+        Ok(match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        })
+    }
+
+    /// The server parameters of a request; the grammar is left out when
+    /// the server derives one from tools or a schema
+    fn server_params(&self, constrained: bool) -> LlamaCppServerParams<'_> {
+        LlamaCppServerParams {
+            generation: &self.generation,
+            grammar: self.grammar.as_deref().filter(|_| !constrained),
+            id_slot: self.slot_id,
+            cache_prompt: self.cache_prompt,
+        }
+    }
+
+    fn chat_request<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        tools: Option<&'a [Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+        stream: bool,
+    ) -> LlamaCppChatRequest<'a> {
+        let response_format = json_schema.map(OpenAIResponseFormat::from);
+        let tools = tools.filter(|tools| !tools.is_empty());
+
+        LlamaCppChatRequest {
+            model: &self.model,
+            messages: openai_messages(messages, self.system.as_deref()),
+            max_tokens: self.max_tokens,
+            temperature: self.temperature,
+            stream,
+            top_p: self.top_p,
+            top_k: self.top_k,
+            tools,
+            tool_choice: tools.and(self.tool_choice.as_ref()),
+            server: self.server_params(tools.is_some() || response_format.is_some()),
+            response_format,
+            stream_options: stream.then_some(OpenAIStreamOptions {
+                include_usage: true,
+            }),
+        }
+    }
+
+    async fn send_chat(
+        &self,
+        body: &LlamaCppChatRequest<'_>,
+    ) -> Result<reqwest::Response, LLMError> {
+        if log::log_enabled!(log::Level::Trace) {
+            if let Ok(json) = serde_json::to_string(body) {
+                log::trace!("llama.cpp request payload: {json}");
+            }
+        }
+
+        let mut request = self
+            .request(reqwest::Method::POST, "v1/chat/completions")?
+            .json(body);
+
+        if let Some(timeout) = self.timeout_seconds {
+            request = request.timeout(std::time::Duration::from_secs(timeout));
+        }
+
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+
+        log::debug!("llama.cpp HTTP status: {}", response.status());
+
+        check_response_status(response).await
+    }
+
+    /// Lists the server's slots and whether they are busy.
+    ///
+    /// The server only serves this when started with `--slots`.
+    pub async fn slots(&self) -> Result<Vec<LlamaCppSlot>, LLMError> {
+        let resp = self
+            .request(reqwest::Method::GET, "slots")?
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let resp = check_response_status(resp).await?;
+        Ok(resp.json().await?)
+    }
+
+    /// Saves the prompt cache of a slot to `filename`, in the directory the
+    /// server was given through `--slot-save-path`.
+    pub async fn save_slot(&self, slot_id: u32, filename: &str) -> Result<(), LLMError> {
+        self.slot_action(slot_id, "save", Some(filename)).await
+    }
+
+    /// Loads a prompt cache saved by [`save_slot`](Self::save_slot) into a slot.
+    pub async fn restore_slot(&self, slot_id: u32, filename: &str) -> Result<(), LLMError> {
+        self.slot_action(slot_id, "restore", Some(filename)).await
+    }
+
+    /// Clears the prompt cache of a slot.
+    pub async fn erase_slot(&self, slot_id: u32) -> Result<(), LLMError> {
+        self.slot_action(slot_id, "erase", None).await
+    }
+
+    async fn slot_action(
+        &self,
+        slot_id: u32,
+        action: &str,
+        filename: Option<&str>,
+    ) -> Result<(), LLMError> {
+        let mut request = self
+            .request(reqwest::Method::POST, &format!("slots/{slot_id}"))?
+            .query(&[("action", action)]);
+        if let Some(filename) = filename {
+            request = request.json(&LlamaCppSlotFile { filename });
+        }
+        let resp = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        check_response_status(resp).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChatProvider for LlamaCpp {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let body = self.chat_request(messages, tools, json_schema, false);
+        let response = self.send_chat(&body).await?;
+
+        let resp_text = response.text().await?;
+        let json_resp: OpenAIChatResponse =
+            serde_json::from_str(&resp_text).map_err(|e| LLMError::ResponseFormatError {
+                message: format!("Failed to decode llama.cpp response: {e}"),
+                raw_response: resp_text.clone(),
+            })?;
+        Ok(Box::new(json_resp))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<std::pin::Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError>
+    {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .filter(|content| !content.is_empty())
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text and tool calls, with the token usage in the last chunk.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<
+        std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>,
+        LLMError,
+    > {
+        let body = self.chat_request(messages, tools, json_schema, true);
+        let response = self.send_chat(&body).await?;
+        Ok(create_sse_stream(response, self.normalize_response))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for LlamaCpp {
+    /// Completes the raw prompt through the native `/completion` endpoint,
+    /// without applying the model's chat template.
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let json_schema = json_schema.and_then(|format| format.schema);
+        let body = LlamaCppCompletionRequest {
+            prompt: &req.prompt,
+            n_predict: req.max_tokens.or(self.max_tokens),
+            temperature: req.temperature.or(self.temperature),
+            top_p: self.top_p,
+            top_k: self.top_k,
+            server: self.server_params(json_schema.is_some()),
+            json_schema,
+            stream: false,
+        };
+
+        let mut request = self
+            .request(reqwest::Method::POST, "completion")?
+            .json(&body);
+        if let Some(timeout) = self.timeout_seconds {
+            request = request.timeout(std::time::Duration::from_secs(timeout));
+        }
+        let resp = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let resp = check_response_status(resp).await?;
+
+        let json_resp: LlamaCppCompletionResponse = resp.json().await?;
+        let usage = match (json_resp.tokens_evaluated, json_resp.tokens_predicted) {
+            (None, None) => None,
+            (prompt, completion) => Some(Usage::new(
+                prompt.unwrap_or_default(),
+                completion.unwrap_or_default(),
+            )),
+        };
+        Ok(CompletionResponse {
+            text: json_resp.content,
+            usage,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for LlamaCpp {
+    /// Embeds the texts, which needs a server started with `--embeddings`.
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        let body = LlamaCppEmbeddingRequest {
+            model: &self.model,
+            input,
+            encoding_format: "float",
+        };
+
+        let resp = self
+            .request(reqwest::Method::POST, "v1/embeddings")?
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let resp = check_response_status(resp).await?;
+
+        let json_resp: LlamaCppEmbeddingResponse = resp.json().await?;
+        Ok(json_resp.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for LlamaCpp {
+    /// Lists the model the server was started with.
+    async fn list_models(
+        &self,
+        _request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        let resp = self
+            .request(reqwest::Method::GET, "v1/models")?
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let resp = check_response_status(resp).await?;
+
+        let result = StandardModelListResponse {
+            inner: resp.json().await?,
+            backend: LLMBackend::LlamaCpp,
+        };
+        Ok(Box::new(result))
+    }
+}
+
+impl LLMProvider for LlamaCpp {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
+
+impl LLMBuilder<LlamaCpp> {
+    /// Constrains generation with a GBNF grammar.
+    pub fn grammar(mut self, grammar: impl Into<String>) -> Self {
+        self.llamacpp_grammar = Some(grammar.into());
+        self
+    }
+
+    /// Pins requests to one of the server's slots.
+    pub fn slot_id(mut self, slot_id: u32) -> Self {
+        self.llamacpp_slot_id = Some(slot_id);
+        self
+    }
+
+    /// Sets whether the server reuses the cached prompt of the slot.
+    pub fn cache_prompt(mut self, cache_prompt: bool) -> Self {
+        self.llamacpp_cache_prompt = Some(cache_prompt);
+        self
+    }
+
+    pub fn build(self) -> Result<Arc<LlamaCpp>, LLMError> {
+        let mut llamacpp = LlamaCpp::new(
+            self.base_url,
+            self.api_key,
+            self.model,
+            self.max_tokens,
+            self.temperature,
+            self.timeout_seconds,
+            self.system,
+            self.top_p,
+            self.top_k,
+        );
+        llamacpp.tool_choice = self.tool_choice;
+        llamacpp.normalize_response = self.normalize_response.unwrap_or(true);
+        llamacpp.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut llamacpp.client, self.timeout_seconds)?;
+        llamacpp.grammar = self.llamacpp_grammar;
+        llamacpp.slot_id = self.llamacpp_slot_id;
+        llamacpp.cache_prompt = self.llamacpp_cache_prompt;
+        llamacpp.generation = self.generation;
+
+        Ok(Arc::new(llamacpp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::FunctionTool;
+    use serde_json::json;
+
+    const GRAMMAR: &str = r#"root ::= "yes" | "no""#;
+
+    fn llamacpp() -> LlamaCpp {
+        let mut llamacpp = LlamaCpp::new(
+            Some("http://127.0.0.1:8081".to_string()),
+            None,
+            None,
+            Some(16),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        llamacpp.grammar = Some(GRAMMAR.to_string());
+        llamacpp.slot_id = Some(1);
+        llamacpp.cache_prompt = Some(true);
+        llamacpp
+    }
+
+    #[test]
+    fn test_chat_request_sends_grammar_and_slot() {
+        let llamacpp = llamacpp();
+        assert_eq!(
+            llamacpp.base_url.join("completion").unwrap().as_str(),
+            "http://127.0.0.1:8081/completion"
+        );
+
+        let messages = [ChatMessage::user().content("Is it raining?").build()];
+        let body =
+            serde_json::to_value(llamacpp.chat_request(&messages, None, None, false)).unwrap();
+        assert_eq!(body["grammar"], GRAMMAR);
+        assert_eq!(body["id_slot"], 1);
+        assert_eq!(body["cache_prompt"], true);
+        assert_eq!(body["max_tokens"], 16);
+        assert!(body.get("stream_options").is_none());
+    }
+
+    #[test]
+    fn test_grammar_is_dropped_for_tools() {
+        let llamacpp = llamacpp();
+        let tools = [Tool {
+            tool_type: "function".to_string(),
+            function: FunctionTool {
+                name: "weather".to_string(),
+                description: "Current weather".to_string(),
+                parameters: json!({"type": "object", "properties": {}}),
+            },
+        }];
+
+        let messages = [ChatMessage::user().content("Is it raining?").build()];
+        let body = serde_json::to_value(llamacpp.chat_request(&messages, Some(&tools), None, true))
+            .unwrap();
+        assert!(body.get("grammar").is_none());
+        assert_eq!(body["id_slot"], 1);
+        assert_eq!(body["tools"][0]["function"]["name"], "weather");
+        assert_eq!(body["stream_options"]["include_usage"], true);
+    }
+
+    #[test]
+    fn test_completion_request_prefers_schema_to_grammar() {
+        let llamacpp = llamacpp();
+        let schema = json!({"type": "object"});
+        let body = LlamaCppCompletionRequest {
+            prompt: "Answer:",
+            n_predict: llamacpp.max_tokens,
+            temperature: None,
+            top_p: None,
+            top_k: None,
+            server: llamacpp.server_params(true),
+            json_schema: Some(schema.clone()),
+            stream: false,
+        };
+        assert_eq!(
+            serde_json::to_value(body).unwrap(),
+            json!({
+                "prompt": "Answer:",
+                "n_predict": 16,
+                "json_schema": schema,
+                "stream": false,
+                "id_slot": 1,
+                "cache_prompt": true
+            })
+        );
+    }
+}
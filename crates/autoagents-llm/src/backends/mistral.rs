@@ -0,0 +1,255 @@
+//! Mistral API client implementation for chat and embedding functionality.
+//!
+//! This module provides integration with Mistral's models through their
+//! OpenAI-compatible API, including function calling, JSON mode and
+//! structured output.
+
+use crate::builder::LLMBuilder;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::RetryRequestExt;
+use crate::{
+    builder::LLMBackend,
+    chat::{ChatMessage, ChatProvider, StructuredOutputFormat, ToolChoice},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider, StandardModelListResponse},
+    providers::openai_compatible::{OpenAICompatibleProvider, OpenAIProviderConfig},
+    LLMProvider,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Mistral configuration for the generic provider
+pub struct MistralConfig;
+
+impl OpenAIProviderConfig for MistralConfig {
+    const PROVIDER_NAME: &'static str = "Mistral";
+    const DEFAULT_BASE_URL: &'static str = "https://api.mistral.ai/v1/";
+    const DEFAULT_MODEL: &'static str = "mistral-small-latest";
+    const SUPPORTS_REASONING_EFFORT: bool = false;
+    const SUPPORTS_STRUCTURED_OUTPUT: bool = true;
+    const SUPPORTS_PARALLEL_TOOL_CALLS: bool = true;
+    const SUPPORTS_JSON_MODE: bool = true;
+    const SEED_AS_RANDOM_SEED: bool = true;
+}
+
+pub type Mistral = OpenAICompatibleProvider<MistralConfig>;
+
+#[derive(Serialize)]
+struct MistralEmbeddingRequest<'a> {
+    model: &'a str,
+    input: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dimension: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct MistralEmbeddingData {
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct MistralEmbeddingResponse {
+    data: Vec<MistralEmbeddingData>,
+}
+
+impl Mistral {
+    /// Creates a new Mistral client with the specified configuration.
+    ///
+    /// A structured output format without a schema turns on JSON mode.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_config(
+        api_key: impl Into<String>,
+        base_url: Option<String>,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        timeout_seconds: Option<u64>,
+        system: Option<String>,
+        top_p: Option<f32>,
+        tool_choice: Option<ToolChoice>,
+        embedding_dimensions: Option<u32>,
+        parallel_tool_calls: Option<bool>,
+        normalize_response: Option<bool>,
+    ) -> Self {
+        OpenAICompatibleProvider::<MistralConfig>::new(
+            api_key,
+            base_url,
+            model,
+            max_tokens,
+            temperature,
+            timeout_seconds,
+            system,
+            top_p,
+            None, // top_k - rejected by Mistral
+            tool_choice,
+            None, // reasoning_effort - not supported by Mistral
+            None, // voice - not supported by Mistral
+            parallel_tool_calls,
+            normalize_response,
+            None, // embedding_encoding_format - Mistral returns floats
+            embedding_dimensions,
+        )
+    }
+}
+
+impl LLMProvider for Mistral {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for Mistral {
+    /// Sends the prompt as a single user message.
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let messages = [ChatMessage::user().content(req.prompt.clone()).build()];
+        let response = self.chat(&messages, None, json_schema).await?;
+        Ok(CompletionResponse::from_chat(response.as_ref()))
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for Mistral {
+    /// Embeds with the configured model, e.g. "mistral-embed".
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing Mistral API key".to_string()));
+        }
+
+        let body = MistralEmbeddingRequest {
+            model: &self.model,
+            input,
+            output_dimension: self.embedding_dimensions,
+        };
+
+        let url = self
+            .base_url
+            .join("embeddings")
+            .map_err(|e| LLMError::HttpError(e.to_string()))?;
+
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?
+            .error_for_status()?;
+
+        let json_resp: MistralEmbeddingResponse = resp.json().await?;
+        Ok(json_resp.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for Mistral {
+    async fn list_models(
+        &self,
+        _request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing Mistral API key".to_string()));
+        }
+
+        let url = self
+            .base_url
+            .join("models")
+            .map_err(|e| LLMError::HttpError(e.to_string()))?;
+
+        let resp = self
+            .client
+            .get(url)
+            .bearer_auth(&self.api_key)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?
+            .error_for_status()?;
+
+        let result = StandardModelListResponse {
+            inner: resp.json().await?,
+            backend: LLMBackend::Mistral,
+        };
+        Ok(Box::new(result))
+    }
+}
+
+impl LLMBuilder<Mistral> {
+    pub fn build(self) -> Result<Arc<Mistral>, LLMError> {
+        let api_key = self.api_key.ok_or_else(|| {
+            LLMError::InvalidRequest("No API key provided for Mistral".to_string())
+        })?;
+
+        let mut mistral = Mistral::with_config(
+            api_key,
+            self.base_url,
+            self.model,
+            self.max_tokens,
+            self.temperature,
+            self.timeout_seconds,
+            self.system,
+            self.top_p,
+            self.tool_choice,
+            self.embedding_dimensions,
+            self.enable_parallel_tool_use,
+            self.normalize_response,
+        );
+        mistral.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut mistral.client, self.timeout_seconds)?;
+        mistral.generation = self.generation;
+
+        Ok(Arc::new(mistral))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_formats_without_a_schema_use_json_mode() {
+        let format = |schema| StructuredOutputFormat {
+            name: "answer".to_string(),
+            description: None,
+            schema,
+            strict: None,
+        };
+
+        let json_mode = Mistral::response_format(Some(format(None)));
+        assert_eq!(
+            serde_json::to_value(json_mode).unwrap(),
+            json!({"type": "json_object"})
+        );
+
+        let schema = json!({"type": "object", "properties": {"answer": {"type": "string"}}});
+        let structured = Mistral::response_format(Some(format(Some(schema))));
+        let structured = serde_json::to_value(structured).unwrap();
+        assert_eq!(structured["type"], "json_schema");
+        assert_eq!(structured["json_schema"]["name"], "answer");
+    }
+
+    #[test]
+    fn test_seed_is_sent_as_random_seed() {
+        let mut mistral = Mistral::with_config(
+            "test-key", None, None, None, None, None, None, None, None, None, None, None,
+        );
+        mistral.generation.seed = Some(7);
+        mistral.generation.stop_sequences = vec!["END".to_string()];
+
+        let (generation, random_seed) = mistral.generation_options();
+        assert_eq!(random_seed, Some(7));
+        assert_eq!(
+            serde_json::to_value(generation).unwrap(),
+            json!({"stop": ["END"]})
+        );
+    }
+}
@@ -27,3 +27,18 @@ pub mod azure_openai;
 
 #[cfg(feature = "openrouter")]
 pub mod openrouter;
+
+#[cfg(feature = "mistral")]
+pub mod mistral;
+
+#[cfg(feature = "bedrock")]
+pub mod bedrock;
+
+#[cfg(feature = "cohere")]
+pub mod cohere;
+
+#[cfg(feature = "llamacpp")]
+pub mod llamacpp;
+
+#[cfg(feature = "huggingface")]
+pub mod huggingface;
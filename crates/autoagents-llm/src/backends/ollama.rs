@@ -1,22 +1,38 @@
 //! Ollama API client implementation for chat and completion functionality.
 //!
-//! This module provides integration with Ollama's local LLM server through its API.
+//! This module provides integration with Ollama's local LLM server through its API,
+//! including streaming chat, listing the models available locally and pulling
+//! missing ones with progress reports.
 
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
 use crate::{
-    builder::LLMBuilder,
-    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, StructuredOutputFormat, Tool},
+    builder::{LLMBackend, LLMBuilder},
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, ContentPart, GenerationOptions,
+        MessageType, StreamChoice, StreamDelta, StreamResponse, StreamToolCallDelta,
+        StreamToolCallFunction, StructuredOutputFormat, Tool, Usage,
+    },
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
     error::LLMError,
-    models::ModelsProvider,
+    models::{ModelListRawEntry, ModelListRequest, ModelListResponse, ModelsProvider},
     FunctionCall, ToolCall,
 };
 use async_trait::async_trait;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use futures::stream::{Stream, StreamExt};
+use reqwest::{Client, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
+use std::pin::Pin;
 use std::sync::Arc;
 
+/// Pulls download gigabytes, so they get far longer than the request timeout
+const PULL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
 /// Client for interacting with Ollama's API.
 ///
 /// Provides methods for chat and completion requests using Ollama's models.
@@ -30,6 +46,14 @@ pub struct Ollama {
     pub timeout_seconds: Option<u64>,
     pub top_p: Option<f32>,
     pub top_k: Option<u32>,
+    /// Size of the context window, overriding the model's default
+    pub num_ctx: Option<u32>,
+    /// How long the model stays loaded after a request, as a duration such
+    /// as "10m" or "24h"; a negative duration keeps it loaded indefinitely
+    pub keep_alive: Option<String>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    pub retry_policy: RetryPolicy,
     client: Client,
 }
 
@@ -43,19 +67,35 @@ struct OllamaChatRequest<'a> {
     format: Option<OllamaResponseFormat>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OllamaTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a str>,
 }
 
 #[derive(Serialize)]
 struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    /// Maximum number of tokens to generate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+    #[serde(flatten)]
+    generation: GenerationOptions,
 }
 
 /// Individual message in an Ollama chat conversation.
 #[derive(Serialize)]
 struct OllamaChatMessage<'a> {
     role: &'a str,
-    content: &'a str,
+    content: Cow<'a, str>,
+    /// Base64 encoded images for multimodal models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    images: Option<Vec<String>>,
 }
 
 /// Response from Ollama's API endpoints.
@@ -64,6 +104,8 @@ struct OllamaResponse {
     content: Option<String>,
     response: Option<String>,
     message: Option<OllamaChatResponseMessage>,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
 }
 
 impl std::fmt::Display for OllamaResponse {
@@ -120,15 +162,123 @@ impl ChatResponse for OllamaResponse {
             })
         })
     }
+
+    fn usage(&self) -> Option<Usage> {
+        usage(self.prompt_eval_count, self.eval_count)
+    }
+}
+
+/// Token usage from the prompt and response token counts of a response
+fn usage(prompt_eval_count: Option<u32>, eval_count: Option<u32>) -> Option<Usage> {
+    if prompt_eval_count.is_none() && eval_count.is_none() {
+        return None;
+    }
+    Some(Usage::new(
+        prompt_eval_count.unwrap_or_default(),
+        eval_count.unwrap_or_default(),
+    ))
 }
 
 /// Message content within an Ollama chat API response.
 #[derive(Deserialize, Debug)]
 struct OllamaChatResponseMessage {
+    #[serde(default)]
     content: String,
     tool_calls: Option<Vec<OllamaToolCall>>,
 }
 
+/// A line of a streaming chat response.
+#[derive(Deserialize, Debug)]
+struct OllamaStreamChunk {
+    message: Option<OllamaChatResponseMessage>,
+    #[serde(default)]
+    done: bool,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    error: Option<String>,
+}
+
+/// Progress of a model pull, as reported by the Ollama server.
+///
+/// While layers download, `digest` names the layer and `total` and
+/// `completed` count its bytes. The last report of a successful pull has the
+/// status "success".
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OllamaPullProgress {
+    #[serde(default)]
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+    error: Option<String>,
+}
+
+impl OllamaPullProgress {
+    /// Share of the current layer downloaded so far, from 0.0 to 1.0
+    pub fn fraction(&self) -> Option<f64> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => Some(completed as f64 / total as f64),
+            _ => None,
+        }
+    }
+
+    /// Whether this report ends a successful pull
+    pub fn is_success(&self) -> bool {
+        self.status == "success"
+    }
+}
+
+#[derive(Serialize)]
+struct OllamaPullRequest<'a> {
+    model: &'a str,
+    stream: bool,
+}
+
+/// Models available on an Ollama server, from its `/api/tags` endpoint.
+#[derive(Clone, Debug, Deserialize)]
+pub struct OllamaModelListResponse {
+    models: Vec<OllamaModelEntry>,
+}
+
+impl ModelListResponse for OllamaModelListResponse {
+    fn get_models(&self) -> Vec<String> {
+        self.models.iter().map(|m| m.name.clone()).collect()
+    }
+
+    fn get_models_raw(&self) -> Vec<Box<dyn ModelListRawEntry>> {
+        self.models
+            .iter()
+            .map(|e| Box::new(e.clone()) as Box<dyn ModelListRawEntry>)
+            .collect()
+    }
+
+    fn get_backend(&self) -> LLMBackend {
+        LLMBackend::Ollama
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct OllamaModelEntry {
+    name: String,
+    modified_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    extra: Value,
+}
+
+impl ModelListRawEntry for OllamaModelEntry {
+    fn get_id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_created_at(&self) -> DateTime<Utc> {
+        self.modified_at.unwrap_or_default()
+    }
+
+    fn get_raw(&self) -> Value {
+        self.extra.clone()
+    }
+}
+
 /// Request payload for Ollama's generate API endpoint.
 #[derive(Serialize)]
 struct OllamaGenerateRequest<'a> {
@@ -136,12 +286,17 @@ struct OllamaGenerateRequest<'a> {
     prompt: &'a str,
     raw: bool,
     stream: bool,
+    options: OllamaOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a str>,
 }
 
 #[derive(Serialize)]
-struct OllamaEmbeddingRequest {
+struct OllamaEmbeddingRequest<'a> {
     model: String,
     input: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a str>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -271,10 +426,6 @@ impl Ollama {
         top_p: Option<f32>,
         top_k: Option<u32>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
         Self {
             base_url: base_url.into(),
             api_key,
@@ -285,20 +436,46 @@ impl Ollama {
             system,
             top_p,
             top_k,
-            client: builder.build().expect("Failed to build reqwest Client"),
+            num_ctx: None,
+            keep_alive: None,
+            generation: GenerationOptions::default(),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
         }
     }
 
-    async fn chat_with_tools(
-        &self,
-        messages: &[ChatMessage],
-        tools: Option<&[Tool]>,
-        json_schema: Option<StructuredOutputFormat>,
-    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+    /// Starts a request to an API path, authenticating when an API key is set
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<RequestBuilder, LLMError> {
         if self.base_url.is_empty() {
             return Err(LLMError::InvalidRequest("Missing base_url".to_string()));
         }
+        let request = self
+            .client
+            .request(method, format!("{}/{path}", self.base_url));
+        Ok(match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        })
+    }
 
+    fn options(&self) -> OllamaOptions {
+        OllamaOptions {
+            top_p: self.top_p,
+            top_k: self.top_k,
+            temperature: self.temperature,
+            num_predict: self.max_tokens,
+            num_ctx: self.num_ctx,
+            generation: self.generation.clone(),
+        }
+    }
+
+    async fn send_chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+        stream: bool,
+    ) -> Result<reqwest::Response, LLMError> {
         let mut chat_messages: Vec<OllamaChatMessage> = messages
             .iter()
             .map(|msg| OllamaChatMessage {
@@ -308,7 +485,31 @@ impl Ollama {
                     ChatRole::Tool => "tool",
                     ChatRole::System => "system",
                 },
-                content: &msg.content,
+                content: match &msg.message_type {
+                    // Ollama takes the text of a message as a single string
+                    MessageType::Parts(parts) => Cow::Owned(
+                        std::iter::once(msg.content.as_str())
+                            .chain(parts.iter().filter_map(ContentPart::as_text))
+                            .filter(|text| !text.is_empty())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
+                    _ => Cow::Borrowed(&msg.content),
+                },
+                images: match &msg.message_type {
+                    MessageType::Image((_, raw_bytes)) => Some(vec![BASE64.encode(raw_bytes)]),
+                    MessageType::Parts(parts) => {
+                        let images: Vec<String> = parts
+                            .iter()
+                            .filter_map(|part| match part {
+                                ContentPart::Image { data, .. } => Some(BASE64.encode(data)),
+                                _ => None,
+                            })
+                            .collect();
+                        (!images.is_empty()).then_some(images)
+                    }
+                    _ => None,
+                },
             })
             .collect();
 
@@ -317,7 +518,8 @@ impl Ollama {
                 0,
                 OllamaChatMessage {
                     role: "system",
-                    content: system,
+                    content: Cow::Borrowed(system),
+                    images: None,
                 },
             );
         }
@@ -337,13 +539,11 @@ impl Ollama {
         let req_body = OllamaChatRequest {
             model: self.model.clone(),
             messages: chat_messages,
-            stream: false,
-            options: Some(OllamaOptions {
-                top_p: self.top_p,
-                top_k: self.top_k,
-            }),
+            stream,
+            options: Some(self.options()),
             format,
             tools: ollama_tools,
+            keep_alive: self.keep_alive.as_deref(),
         };
 
         if log::log_enabled!(log::Level::Trace) {
@@ -352,23 +552,195 @@ impl Ollama {
             }
         }
 
-        let url = format!("{}/api/chat", self.base_url);
-
-        let mut request = self.client.post(&url).json(&req_body);
+        let mut request = self
+            .request(reqwest::Method::POST, "api/chat")?
+            .json(&req_body);
 
         if let Some(timeout) = self.timeout_seconds {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
 
-        let resp = request.send().await?;
+        let resp = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
 
         log::debug!("Ollama HTTP status (tools): {}", resp.status());
 
-        let resp = resp.error_for_status()?;
+        Ok(resp.error_for_status()?)
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let resp = self.send_chat(messages, tools, json_schema, false).await?;
         let json_resp = resp.json::<OllamaResponse>().await?;
 
         Ok(Box::new(json_resp))
     }
+
+    /// Whether the configured model is available on the server.
+    ///
+    /// A model named without a tag matches its "latest" tag.
+    pub async fn has_model(&self) -> Result<bool, LLMError> {
+        let models = self.list_models(None).await?.get_models();
+        let latest = format!("{}:latest", self.model);
+        Ok(models
+            .iter()
+            .any(|name| *name == self.model || *name == latest))
+    }
+
+    /// Downloads a model to the server, streaming its progress.
+    ///
+    /// The stream ends after the report whose status is "success"; an error
+    /// reported by the server ends it with a [`LLMError::ProviderError`].
+    pub async fn pull_model(
+        &self,
+        model: &str,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<OllamaPullProgress, LLMError>> + Send>>, LLMError>
+    {
+        let body = OllamaPullRequest {
+            model,
+            stream: true,
+        };
+        let resp = self
+            .request(reqwest::Method::POST, "api/pull")?
+            .json(&body)
+            .timeout(PULL_TIMEOUT)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?
+            .error_for_status()?;
+
+        let stream = ndjson_stream::<OllamaPullProgress>(resp).map(|progress| {
+            let progress = progress?;
+            match progress.error {
+                Some(error) => Err(LLMError::ProviderError(error)),
+                None => Ok(progress),
+            }
+        });
+        Ok(Box::pin(stream))
+    }
+
+    /// Pulls the configured model unless the server already has it, passing
+    /// each progress report to `on_progress`.
+    pub async fn ensure_model(
+        &self,
+        mut on_progress: impl FnMut(&OllamaPullProgress) + Send,
+    ) -> Result<(), LLMError> {
+        if self.has_model().await? {
+            return Ok(());
+        }
+
+        let mut progress = self.pull_model(&self.model).await?;
+        while let Some(report) = progress.next().await {
+            let report = report?;
+            on_progress(&report);
+            if report.is_success() {
+                return Ok(());
+            }
+        }
+        Err(LLMError::ProviderError(format!(
+            "Pull of {} ended before it succeeded",
+            self.model
+        )))
+    }
+}
+
+/// Decodes a newline-delimited JSON response, one value per line
+fn ndjson_stream<T: DeserializeOwned + Send + 'static>(
+    response: reqwest::Response,
+) -> impl Stream<Item = Result<T, LLMError>> + Send {
+    response
+        .bytes_stream()
+        .scan(Vec::new(), |line_buffer: &mut Vec<u8>, chunk| {
+            let bytes = match chunk {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    return futures::future::ready(Some(vec![Err(LLMError::HttpError(
+                        e.to_string(),
+                    ))]))
+                }
+            };
+            line_buffer.extend_from_slice(&bytes);
+            let mut results = Vec::new();
+            while let Some(end) = line_buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = line_buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                results.push(serde_json::from_str(line).map_err(|e| {
+                    LLMError::ResponseFormatError {
+                        message: format!("Failed to decode Ollama stream line: {e}"),
+                        raw_response: line.to_string(),
+                    }
+                }));
+            }
+            futures::future::ready(Some(results))
+        })
+        .flat_map(futures::stream::iter)
+}
+
+/// Converts a line of a streaming chat response into a stream chunk, numbering
+/// tool calls from `next_tool_index` since Ollama sends each one whole
+fn stream_chunk(
+    chunk: OllamaStreamChunk,
+    next_tool_index: &mut usize,
+) -> Option<Result<StreamResponse, LLMError>> {
+    if let Some(error) = chunk.error {
+        return Some(Err(LLMError::ProviderError(error)));
+    }
+
+    let (content, tool_calls) = match chunk.message {
+        Some(message) => {
+            let tool_calls = message.tool_calls.map(|calls| {
+                calls
+                    .into_iter()
+                    .map(|call| {
+                        let index = *next_tool_index;
+                        *next_tool_index += 1;
+                        StreamToolCallDelta {
+                            index,
+                            id: Some(format!("call_{}", call.function.name)),
+                            function: Some(StreamToolCallFunction {
+                                name: call.function.name,
+                                arguments: serde_json::to_string(&call.function.arguments)
+                                    .unwrap_or_default(),
+                            }),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            });
+            let content = Some(message.content).filter(|content| !content.is_empty());
+            (content, tool_calls.filter(|calls| !calls.is_empty()))
+        }
+        None => (None, None),
+    };
+    let usage = if chunk.done {
+        usage(chunk.prompt_eval_count, chunk.eval_count)
+    } else {
+        None
+    };
+
+    if content.is_none() && tool_calls.is_none() && usage.is_none() {
+        return None;
+    }
+    Some(Ok(StreamResponse {
+        choices: vec![StreamChoice {
+            delta: StreamDelta {
+                content,
+                tool_calls,
+                thinking: None,
+                reasoning: None,
+            },
+        }],
+        usage,
+    }))
 }
 
 #[async_trait]
@@ -390,6 +762,54 @@ impl ChatProvider for Ollama {
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
         self.chat_with_tools(messages, tools, json_schema).await
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
+
+    /// Streams text and tool calls; the last chunk carries the token usage.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        let resp = self.send_chat(messages, tools, json_schema, true).await?;
+        let stream = ndjson_stream::<OllamaStreamChunk>(resp)
+            .scan(0usize, |next_tool_index, chunk| {
+                let result = match chunk {
+                    Ok(chunk) => stream_chunk(chunk, next_tool_index),
+                    Err(e) => Some(Err(e)),
+                };
+                futures::future::ready(Some(result))
+            })
+            .filter_map(futures::future::ready);
+        Ok(Box::pin(stream))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
 }
 
 #[async_trait]
@@ -408,29 +828,30 @@ impl CompletionProvider for Ollama {
         req: &CompletionRequest,
         _json_schema: Option<StructuredOutputFormat>,
     ) -> Result<CompletionResponse, LLMError> {
-        if self.base_url.is_empty() {
-            return Err(LLMError::InvalidRequest("Missing base_url".to_string()));
-        }
-        let url = format!("{}/api/generate", self.base_url);
-
         let req_body = OllamaGenerateRequest {
             model: self.model.clone(),
             prompt: &req.prompt,
             raw: true,
             stream: false,
+            options: self.options(),
+            keep_alive: self.keep_alive.as_deref(),
         };
 
         let resp = self
-            .client
-            .post(&url)
+            .request(reqwest::Method::POST, "api/generate")?
             .json(&req_body)
-            .send()
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
         let json_resp: OllamaResponse = resp.json().await?;
 
+        let usage = usage(json_resp.prompt_eval_count, json_resp.eval_count);
         if let Some(answer) = json_resp.response.or(json_resp.content) {
-            Ok(CompletionResponse { text: answer })
+            Ok(CompletionResponse {
+                text: answer,
+                usage,
+            })
         } else {
             Err(LLMError::ProviderError(
                 "No answer returned by Ollama".to_string(),
@@ -442,40 +863,80 @@ impl CompletionProvider for Ollama {
 #[async_trait]
 impl EmbeddingProvider for Ollama {
     async fn embed(&self, text: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
-        if self.base_url.is_empty() {
-            return Err(LLMError::InvalidRequest("Missing base_url".to_string()));
-        }
-        let url = format!("{}/api/embed", self.base_url);
-
         let body = OllamaEmbeddingRequest {
             model: self.model.clone(),
             input: text,
+            keep_alive: self.keep_alive.as_deref(),
         };
 
         let resp = self
-            .client
-            .post(&url)
+            .request(reqwest::Method::POST, "api/embed")?
             .json(&body)
-            .send()
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
 
         let json_resp: OllamaEmbeddingResponse = resp.json().await?;
         Ok(json_resp.embeddings)
     }
+
+    /// Known for the common embedding models in the Ollama library.
+    fn embedding_dimensions(&self) -> Option<usize> {
+        let name = self.model.split(':').next().unwrap_or_default();
+        match name {
+            "all-minilm" => Some(384),
+            "nomic-embed-text" | "paraphrase-multilingual" => Some(768),
+            "mxbai-embed-large" | "bge-m3" | "bge-large" | "snowflake-arctic-embed" => Some(1024),
+            _ => None,
+        }
+    }
 }
 
 #[async_trait]
-impl ModelsProvider for Ollama {}
+impl ModelsProvider for Ollama {
+    /// Lists the models pulled to the server.
+    async fn list_models(
+        &self,
+        _request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        let resp = self
+            .request(reqwest::Method::GET, "api/tags")?
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?
+            .error_for_status()?;
 
-impl crate::LLMProvider for Ollama {}
+        let result: OllamaModelListResponse = resp.json().await?;
+        Ok(Box::new(result))
+    }
+}
+
+impl crate::LLMProvider for Ollama {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
 
 impl LLMBuilder<Ollama> {
+    /// Sets the size of the context window, overriding the model's default.
+    pub fn num_ctx(mut self, num_ctx: u32) -> Self {
+        self.ollama_num_ctx = Some(num_ctx);
+        self
+    }
+
+    /// Sets how long the model stays loaded after a request, as a duration
+    /// such as "10m" or "24h". A negative duration keeps it loaded.
+    pub fn keep_alive(mut self, keep_alive: impl Into<String>) -> Self {
+        self.ollama_keep_alive = Some(keep_alive.into());
+        self
+    }
+
     pub fn build(self) -> Result<Arc<Ollama>, LLMError> {
         let url = self
             .base_url
             .unwrap_or("http://localhost:11434".to_string());
-        let ollama = Ollama::new(
+        let mut ollama = Ollama::new(
             url,
             self.api_key,
             self.model,
@@ -486,7 +947,113 @@ impl LLMBuilder<Ollama> {
             self.top_p,
             self.top_k,
         );
+        ollama.num_ctx = self.ollama_num_ctx;
+        ollama.keep_alive = self.ollama_keep_alive;
+        ollama.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut ollama.client, self.timeout_seconds)?;
+        ollama.generation = self.generation;
 
         Ok(Arc::new(ollama))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chunk(value: Value) -> OllamaStreamChunk {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_options_skip_unset_values() {
+        let mut ollama = Ollama::new(
+            "http://localhost:11434",
+            None,
+            None,
+            Some(256),
+            Some(0.2),
+            None,
+            None,
+            None,
+            None,
+        );
+        ollama.num_ctx = Some(4096);
+
+        assert_eq!(
+            serde_json::to_value(ollama.options()).unwrap(),
+            json!({"temperature": 0.2f32, "num_predict": 256, "num_ctx": 4096})
+        );
+
+        ollama.generation.stop_sequences = vec!["END".to_string()];
+        ollama.generation.seed = Some(7);
+        let options = serde_json::to_value(ollama.options()).unwrap();
+        assert_eq!(options["stop"], json!(["END"]));
+        assert_eq!(options["seed"], 7);
+    }
+
+    #[test]
+    fn test_stream_chunks_number_tool_calls_and_report_usage() {
+        let mut next_tool_index = 0;
+        let text = stream_chunk(
+            chunk(json!({"message": {"role": "assistant", "content": "Hi"}, "done": false})),
+            &mut next_tool_index,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(text.choices[0].delta.content.as_deref(), Some("Hi"));
+
+        let calls = stream_chunk(
+            chunk(
+                json!({"message": {"role": "assistant", "content": "", "tool_calls": [
+                {"function": {"name": "a", "arguments": {}}},
+                {"function": {"name": "b", "arguments": {"x": 1}}}
+            ]}, "done": false}),
+            ),
+            &mut next_tool_index,
+        )
+        .unwrap()
+        .unwrap();
+        let calls = calls.choices[0].delta.tool_calls.clone().unwrap();
+        assert_eq!(calls[1].index, 1);
+        assert_eq!(calls[1].function.as_ref().unwrap().arguments, r#"{"x":1}"#);
+        assert_eq!(next_tool_index, 2);
+
+        let last = stream_chunk(
+            chunk(
+                json!({"message": {"role": "assistant", "content": ""}, "done": true,
+                "prompt_eval_count": 12, "eval_count": 5}),
+            ),
+            &mut next_tool_index,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(last.usage.unwrap().total_tokens, 17);
+
+        assert!(stream_chunk(
+            chunk(json!({"message": {"role": "assistant", "content": ""}, "done": false})),
+            &mut next_tool_index,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_pull_progress() {
+        let progress: OllamaPullProgress = serde_json::from_value(json!({
+            "status": "pulling 6a0746a1ec1a",
+            "digest": "sha256:6a0746a1ec1a",
+            "total": 400,
+            "completed": 100
+        }))
+        .unwrap();
+        assert_eq!(progress.fraction(), Some(0.25));
+        assert!(!progress.is_success());
+
+        let done: OllamaPullProgress =
+            serde_json::from_value(json!({"status": "success"})).unwrap();
+        assert_eq!(done.fraction(), None);
+        assert!(done.is_success());
+    }
+}
@@ -2,10 +2,18 @@
 //!
 //! This module provides integration with OpenAI's GPT models through their API.
 
+use crate::audio::{
+    Audio, SpeechRequest, SpeechToTextProvider, TextToSpeechProvider, Transcription,
+    TranscriptionRequest,
+};
 use crate::chat::utils::check_response_status;
 use crate::chat::{
-    StreamChoice, StreamDelta, StreamResponse, StreamToolCallDelta, StreamToolCallFunction, Usage,
+    ContentPart, GenerationOptions, StreamChoice, StreamDelta, StreamResponse, StreamToolCallDelta,
+    StreamToolCallFunction, TokenLogprob, Usage,
 };
+use crate::providers::openai_compatible::{synthesize_speech, transcribe_audio};
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
 use crate::{
     builder::LLMBackend,
     chat::Tool,
@@ -14,12 +22,13 @@ use crate::{
     embedding::EmbeddingProvider,
     error::LLMError,
     models::{ModelListRawEntry, ModelListRequest, ModelListResponse, ModelsProvider},
+    moderation::{ModerationProvider, ModerationResult},
     LLMProvider,
 };
 use crate::{
     builder::LLMBuilder,
     chat::{ChatResponse, ToolChoice},
-    FunctionCall, ToolCall,
+    ToolCall,
 };
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine as _};
@@ -32,6 +41,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
 
+/// Bulk chat requests through the Batch API
+pub mod batch;
+
 /// Client for interacting with OpenAI's API.
 ///
 /// Provides methods for chat and completion requests using OpenAI's models.
@@ -40,6 +52,9 @@ pub struct OpenAI {
     pub base_url: Url,
     pub model: String,
     pub max_tokens: Option<u32>,
+    /// Limit on generated tokens including reasoning, sent instead of
+    /// `max_tokens`
+    pub max_completion_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub system: Option<String>,
     pub timeout_seconds: Option<u64>,
@@ -57,6 +72,14 @@ pub struct OpenAI {
     pub web_search_user_location_approximate_country: Option<String>,
     pub web_search_user_location_approximate_city: Option<String>,
     pub web_search_user_location_approximate_region: Option<String>,
+    /// Model for the moderations endpoint, `omni-moderation-latest` if unset
+    pub moderation_model: Option<String>,
+    /// Alternatives returned with each token's log probability; no log
+    /// probabilities are requested if unset
+    pub top_logprobs: Option<u8>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    pub retry_policy: RetryPolicy,
     client: Client,
 }
 
@@ -97,7 +120,7 @@ struct MessageContent<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     text: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    image_url: Option<ImageUrlContent<'a>>,
+    image_url: Option<ImageUrlContent>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "tool_call_id")]
     tool_call_id: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "content")]
@@ -106,8 +129,8 @@ struct MessageContent<'a> {
 
 /// Individual image message in an OpenAI chat conversation.
 #[derive(Serialize, Debug)]
-struct ImageUrlContent<'a> {
-    url: &'a str,
+struct ImageUrlContent {
+    url: String,
 }
 
 #[derive(Serialize)]
@@ -128,6 +151,8 @@ struct OpenAIChatRequest<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -146,38 +171,32 @@ struct OpenAIChatRequest<'a> {
     web_search_options: Option<OpenAIWebSearchOptions>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream_options: Option<OpenAIStreamOptions>,
-}
-
-impl std::fmt::Display for ToolCall {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{{\n  \"id\": \"{}\",\n  \"type\": \"{}\",\n  \"function\": {}\n}}",
-            self.id, self.call_type, self.function
-        )
-    }
-}
-
-impl std::fmt::Display for FunctionCall {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{{\n  \"name\": \"{}\",\n  \"arguments\": {}\n}}",
-            self.name, self.arguments
-        )
-    }
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
+    #[serde(flatten)]
+    generation: &'a GenerationOptions,
 }
 
 /// Response from OpenAI's chat API endpoint.
 #[derive(Deserialize, Debug)]
 struct OpenAIChatResponse {
     choices: Vec<OpenAIChatChoice>,
+    usage: Option<Usage>,
 }
 
 /// Individual choice within an OpenAI chat API response.
 #[derive(Deserialize, Debug)]
 struct OpenAIChatChoice {
     message: OpenAIChatMsg,
+    #[serde(default)]
+    logprobs: Option<OpenAIChoiceLogprobs>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenAIChoiceLogprobs {
+    content: Option<Vec<TokenLogprob>>,
 }
 
 /// Message content within an OpenAI chat API response.
@@ -336,6 +355,16 @@ impl ChatResponse for OpenAIChatResponse {
             .first()
             .and_then(|c| c.message.tool_calls.clone())
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
+
+    fn logprobs(&self) -> Option<Vec<TokenLogprob>> {
+        self.choices
+            .first()
+            .and_then(|c| c.logprobs.as_ref()?.content.clone())
+    }
 }
 
 impl std::fmt::Display for OpenAIChatResponse {
@@ -405,10 +434,6 @@ impl OpenAI {
         web_search_user_location_approximate_city: Option<String>,
         web_search_user_location_approximate_region: Option<String>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
         Self {
             api_key: api_key.into(),
             base_url: Url::parse(
@@ -417,6 +442,7 @@ impl OpenAI {
             .expect("Failed to prase base Url"),
             model: model.unwrap_or("gpt-3.5-turbo".to_string()),
             max_tokens,
+            max_completion_tokens: None,
             temperature,
             system,
             timeout_seconds,
@@ -425,7 +451,8 @@ impl OpenAI {
             tool_choice,
             embedding_encoding_format,
             embedding_dimensions,
-            client: builder.build().expect("Failed to build reqwest Client"),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
             reasoning_effort,
             voice,
             enable_web_search,
@@ -434,6 +461,51 @@ impl OpenAI {
             web_search_user_location_approximate_country,
             web_search_user_location_approximate_city,
             web_search_user_location_approximate_region,
+            moderation_model: None,
+            top_logprobs: None,
+            generation: GenerationOptions::default(),
+        }
+    }
+
+    /// Whether the model is a reasoning model of the o-series or GPT-5
+    /// class, which takes developer instead of system messages, counts
+    /// reasoning against `max_completion_tokens` and only samples at the
+    /// default temperature.
+    pub fn is_reasoning_model(&self) -> bool {
+        let model = self.model.as_str();
+        let family = model.split('-').next().unwrap_or(model);
+        matches!(family, "o1" | "o3" | "o4")
+            || (model.starts_with("gpt-5") && !model.contains("-chat"))
+    }
+
+    /// Whether the model follows a `json_schema` response format. Models
+    /// before `gpt-4o-2024-08-06` only have JSON mode, and the o1 previews
+    /// take no response format at all.
+    pub fn supports_json_schema(&self) -> bool {
+        let model = self.model.as_str();
+        !(model.starts_with("gpt-3.5")
+            || model == "gpt-4"
+            || model.starts_with("gpt-4-")
+            || model == "gpt-4o-2024-05-13"
+            || Self::lacks_response_format(model))
+    }
+
+    fn lacks_response_format(model: &str) -> bool {
+        model.starts_with("o1-mini") || model.starts_with("o1-preview")
+    }
+
+    /// The response format for `format`, falling back to JSON mode on models
+    /// without structured outputs.
+    fn response_format(&self, format: StructuredOutputFormat) -> Option<OpenAIResponseFormat> {
+        if self.supports_json_schema() {
+            Some(format.into())
+        } else if Self::lacks_response_format(&self.model) {
+            None
+        } else {
+            Some(OpenAIResponseFormat {
+                response_type: OpenAIResponseType::JsonObject,
+                json_schema: None,
+            })
         }
     }
 
@@ -445,9 +517,6 @@ impl OpenAI {
         stream: bool,
         stream_options: Option<OpenAIStreamOptions>,
     ) -> Result<OpenAIChatRequest<'a>, LLMError> {
-        // Clone the messages to have an owned mutable vector.
-        let messages = messages.to_vec();
-
         let mut openai_msgs: Vec<OpenAIChatMessage> = vec![];
 
         for msg in messages {
@@ -486,7 +555,20 @@ impl OpenAI {
             );
         }
 
-        let response_format: Option<OpenAIResponseFormat> = json_schema.clone().map(|s| s.into());
+        let reasoning_model = self.is_reasoning_model();
+        if reasoning_model {
+            for msg in openai_msgs.iter_mut().filter(|msg| msg.role == "system") {
+                msg.role = "developer";
+            }
+        }
+
+        // Reasoning models reject max_tokens, as it leaves out the reasoning
+        let max_completion_tokens = self
+            .max_completion_tokens
+            .or(self.max_tokens.filter(|_| reasoning_model));
+        let max_tokens = self.max_tokens.filter(|_| max_completion_tokens.is_none());
+
+        let response_format = json_schema.and_then(|format| self.response_format(format));
 
         let request_tools = tools.map(|t| t.to_vec());
 
@@ -532,7 +614,8 @@ impl OpenAI {
         Ok(OpenAIChatRequest {
             model: &self.model,
             messages: openai_msgs,
-            max_tokens: self.max_tokens,
+            max_tokens,
+            max_completion_tokens,
             temperature: self.temperature,
             stream,
             top_p: self.top_p,
@@ -543,6 +626,9 @@ impl OpenAI {
             response_format,
             web_search_options,
             stream_options,
+            logprobs: self.top_logprobs.filter(|_| !stream).map(|_| true),
+            top_logprobs: self.top_logprobs.filter(|_| !stream),
+            generation: &self.generation,
         })
     }
 
@@ -584,7 +670,10 @@ impl OpenAI {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
 
-        let response = request.send().await?;
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
 
         log::debug!("OpenAI HTTP status: {}", response.status());
 
@@ -739,10 +828,17 @@ impl ChatProvider for OpenAI {
         if let Some(timeout) = self.timeout_seconds {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
-        let response = request.send().await?;
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
         let response = check_response_status(response).await?;
         Ok(create_struct_sse_stream(response))
     }
+
+    fn supports_structured_output(&self) -> bool {
+        self.supports_json_schema()
+    }
 }
 
 /// Creates a structured SSE stream that returns StreamResponse objects
@@ -777,9 +873,8 @@ fn create_struct_sse_stream(
     Box::pin(stream)
 }
 
-// Create an owned OpenAIChatMessage that doesn't borrow from any temporary variables
-fn chat_message_to_api_message(chat_msg: ChatMessage) -> OpenAIChatMessage<'static> {
-    // For other message types, create an owned OpenAIChatMessage
+/// Convert a chat message into the OpenAI wire format, borrowing from the source message
+fn chat_message_to_api_message(chat_msg: &ChatMessage) -> OpenAIChatMessage<'_> {
     OpenAIChatMessage {
         role: match chat_msg.role {
             ChatRole::User => "user",
@@ -792,67 +887,95 @@ fn chat_message_to_api_message(chat_msg: ChatMessage) -> OpenAIChatMessage<'stat
             MessageType::Text => Some(Right(chat_msg.content.clone())),
             MessageType::Image((image_mime, raw_bytes)) => {
                 // Convert raw bytes to base64 data URL
-                let base64_data = BASE64_STANDARD.encode(raw_bytes);
-                let data_url = format!("data:{};base64,{}", image_mime.mime_type(), base64_data);
-                let data_url_str = Box::leak(data_url.into_boxed_str());
-                Some(Left(vec![MessageContent {
-                    message_type: Some("image_url"),
-                    text: None,
-                    image_url: Some(ImageUrlContent { url: data_url_str }),
-                    tool_output: None,
-                    tool_call_id: None,
-                }]))
+                let data_url = format!(
+                    "data:{};base64,{}",
+                    image_mime.mime_type(),
+                    BASE64_STANDARD.encode(raw_bytes)
+                );
+                Some(Left(image_message_parts(&chat_msg.content, data_url)))
             }
             MessageType::Pdf(_) => unimplemented!(),
             MessageType::ImageURL(url) => {
-                // Clone the URL to create an owned version
-                let owned_url = url.clone();
-                // Leak the string to get a 'static reference
-                let url_str = Box::leak(owned_url.into_boxed_str());
-                Some(Left(vec![MessageContent {
-                    message_type: Some("image_url"),
-                    text: None,
-                    image_url: Some(ImageUrlContent { url: url_str }),
-                    tool_output: None,
-                    tool_call_id: None,
-                }]))
+                Some(Left(image_message_parts(&chat_msg.content, url.clone())))
+            }
+            MessageType::Parts(parts) => {
+                Some(Left(multimodal_message_parts(&chat_msg.content, parts)))
             }
             MessageType::ToolUse(_) => None,
             MessageType::ToolResult(_) => None,
         },
         tool_calls: match &chat_msg.message_type {
-            MessageType::ToolUse(calls) => {
-                let owned_calls: Vec<OpenAIFunctionCall<'static>> = calls
+            MessageType::ToolUse(calls) => Some(
+                calls
                     .iter()
-                    .map(|c| {
-                        let owned_id = c.id.clone();
-                        let owned_name = c.function.name.clone();
-                        let owned_args = c.function.arguments.clone();
-
-                        // Need to leak these strings to create 'static references
-                        // This is a deliberate choice to solve the lifetime issue
-                        // The small memory leak is acceptable in this context
-                        let id_str = Box::leak(owned_id.into_boxed_str());
-                        let name_str = Box::leak(owned_name.into_boxed_str());
-                        let args_str = Box::leak(owned_args.into_boxed_str());
-
-                        OpenAIFunctionCall {
-                            id: id_str,
-                            content_type: "function",
-                            function: OpenAIFunctionPayload {
-                                name: name_str,
-                                arguments: args_str,
-                            },
-                        }
+                    .map(|c| OpenAIFunctionCall {
+                        id: &c.id,
+                        content_type: "function",
+                        function: OpenAIFunctionPayload {
+                            name: &c.function.name,
+                            arguments: &c.function.arguments,
+                        },
                     })
-                    .collect();
-                Some(owned_calls)
-            }
+                    .collect(),
+            ),
             _ => None,
         },
     }
 }
 
+/// Content parts for an image message: the accompanying text, if any, then the image
+fn image_message_parts(text: &str, url: String) -> Vec<MessageContent<'_>> {
+    let mut parts = Vec::with_capacity(2);
+    if !text.is_empty() {
+        parts.push(MessageContent {
+            message_type: Some("text"),
+            text: Some(text),
+            image_url: None,
+            tool_output: None,
+            tool_call_id: None,
+        });
+    }
+    parts.push(MessageContent {
+        message_type: Some("image_url"),
+        text: None,
+        image_url: Some(ImageUrlContent { url }),
+        tool_output: None,
+        tool_call_id: None,
+    });
+    parts
+}
+
+/// Content parts for a multimodal message: the message text, if any, then each part in order
+fn multimodal_message_parts<'a>(
+    text: &'a str,
+    parts: &'a [ContentPart],
+) -> Vec<MessageContent<'a>> {
+    let text_part = |text| MessageContent {
+        message_type: Some("text"),
+        text: Some(text),
+        image_url: None,
+        tool_output: None,
+        tool_call_id: None,
+    };
+    let mut content = Vec::with_capacity(parts.len() + 1);
+    if !text.is_empty() {
+        content.push(text_part(text));
+    }
+    for part in parts {
+        content.push(match part {
+            ContentPart::Text(text) => text_part(text),
+            image => MessageContent {
+                message_type: Some("image_url"),
+                text: None,
+                image_url: image.to_image_url().map(|url| ImageUrlContent { url }),
+                tool_output: None,
+                tool_call_id: None,
+            },
+        });
+    }
+    content
+}
+
 #[async_trait]
 impl CompletionProvider for OpenAI {
     /// Sends a completion request to OpenAI's API.
@@ -873,6 +996,18 @@ impl CompletionProvider for OpenAI {
     }
 }
 
+/// Most inputs the embeddings endpoint accepts per request
+const OPENAI_MAX_EMBEDDING_BATCH: usize = 2048;
+
+/// Default vector length of OpenAI's embedding models
+fn openai_embedding_dimensions(model: &str) -> Option<usize> {
+    match model {
+        "text-embedding-3-large" => Some(3072),
+        "text-embedding-3-small" | "text-embedding-ada-002" => Some(1536),
+        _ => None,
+    }
+}
+
 #[cfg(feature = "openai")]
 #[async_trait]
 impl EmbeddingProvider for OpenAI {
@@ -903,7 +1038,8 @@ impl EmbeddingProvider for OpenAI {
             .post(url)
             .bearer_auth(&self.api_key)
             .json(&body)
-            .send()
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
 
@@ -912,6 +1048,85 @@ impl EmbeddingProvider for OpenAI {
         let embeddings = json_resp.data.into_iter().map(|d| d.embedding).collect();
         Ok(embeddings)
     }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.embedding_dimensions
+            .map(|dims| dims as usize)
+            .or_else(|| openai_embedding_dimensions(&self.model))
+    }
+
+    fn max_embedding_batch(&self) -> Option<usize> {
+        Some(OPENAI_MAX_EMBEDDING_BATCH)
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct OpenAIModerationRequest<'a> {
+    model: &'a str,
+    input: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenAIModerationResponse {
+    results: Vec<OpenAIModerationResult>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenAIModerationResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: std::collections::BTreeMap<String, bool>,
+    #[serde(default)]
+    category_scores: std::collections::BTreeMap<String, f32>,
+}
+
+impl From<OpenAIModerationResult> for ModerationResult {
+    fn from(result: OpenAIModerationResult) -> Self {
+        ModerationResult {
+            flagged: result.flagged,
+            categories: result
+                .categories
+                .into_iter()
+                .filter_map(|(name, hit)| hit.then_some(name))
+                .collect(),
+            category_scores: result.category_scores,
+        }
+    }
+}
+
+#[async_trait]
+impl ModerationProvider for OpenAI {
+    async fn moderate(&self, input: Vec<String>) -> Result<Vec<ModerationResult>, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing OpenAI API key".into()));
+        }
+
+        let body = OpenAIModerationRequest {
+            model: self
+                .moderation_model
+                .as_deref()
+                .unwrap_or("omni-moderation-latest"),
+            input,
+        };
+
+        let url = self
+            .base_url
+            .join("moderations")
+            .map_err(|e| LLMError::HttpError(e.to_string()))?;
+
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?
+            .error_for_status()?;
+
+        let json_resp: OpenAIModerationResponse = resp.json().await?;
+        Ok(json_resp.results.into_iter().map(Into::into).collect())
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -978,7 +1193,8 @@ impl ModelsProvider for OpenAI {
             .client
             .get(url)
             .bearer_auth(&self.api_key)
-            .send()
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
 
@@ -988,7 +1204,64 @@ impl ModelsProvider for OpenAI {
     }
 }
 
-impl LLMProvider for OpenAI {}
+impl LLMProvider for OpenAI {
+    fn token_counter(&self) -> Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        Some(self)
+    }
+
+    fn text_to_speech(&self) -> Option<&dyn TextToSpeechProvider> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for OpenAI {
+    /// Transcribes with `whisper-1` unless the request names another model.
+    async fn transcribe(&self, request: &TranscriptionRequest) -> Result<Transcription, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing OpenAI API key".into()));
+        }
+        transcribe_audio(
+            &self.client,
+            &self.base_url,
+            &self.api_key,
+            request.model.as_deref().unwrap_or("whisper-1"),
+            request,
+            &self.retry_policy,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for OpenAI {
+    /// Speaks with `gpt-4o-mini-tts` in the configured voice, `alloy` if
+    /// unset, unless the request names others.
+    async fn speech(&self, request: &SpeechRequest) -> Result<Audio, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing OpenAI API key".into()));
+        }
+        let voice = request
+            .voice
+            .as_deref()
+            .or(self.voice.as_deref())
+            .unwrap_or("alloy");
+        synthesize_speech(
+            &self.client,
+            &self.base_url,
+            &self.api_key,
+            request.model.as_deref().unwrap_or("gpt-4o-mini-tts"),
+            voice,
+            request,
+            &self.retry_policy,
+        )
+        .await
+    }
+}
 
 /// Parse SSE chunk and convert to StreamResponse format
 ///
@@ -1005,8 +1278,10 @@ fn parse_sse_chunk(chunk: &str) -> Result<Option<StreamResponse>, LLMError> {
     let mut collected_content = String::new();
     let mut has_content = false;
     // Use a HashMap to track multiple tool calls by index
-    let mut tool_calls_map: std::collections::HashMap<usize, (Option<String>, String)> =
-        std::collections::HashMap::new();
+    let mut tool_calls_map: std::collections::HashMap<
+        usize,
+        (Option<String>, Option<String>, String),
+    > = std::collections::HashMap::new();
     let mut has_tool_calls = false;
 
     for line in chunk.lines() {
@@ -1025,6 +1300,8 @@ fn parse_sse_chunk(chunk: &str) -> Result<Option<StreamResponse>, LLMError> {
                                 delta: StreamDelta {
                                     content: None,
                                     tool_calls: None,
+                                    thinking: None,
+                                    reasoning: None,
                                 },
                             }],
                             usage: Some(usage),
@@ -1045,9 +1322,15 @@ fn parse_sse_chunk(chunk: &str) -> Result<Option<StreamResponse>, LLMError> {
                                 let index = delta_tool_call.index.unwrap_or(0);
 
                                 // Get or create entry for this index
-                                let entry =
-                                    tool_calls_map.entry(index).or_insert((None, String::new()));
-
+                                let entry = tool_calls_map.entry(index).or_insert((
+                                    None,
+                                    None,
+                                    String::new(),
+                                ));
+
+                                if delta_tool_call.id.is_some() {
+                                    entry.1 = delta_tool_call.id.clone();
+                                }
                                 if let Some(function) = &delta_tool_call.function {
                                     // Update function name if provided
                                     if let Some(name) = &function.name {
@@ -1055,7 +1338,7 @@ fn parse_sse_chunk(chunk: &str) -> Result<Option<StreamResponse>, LLMError> {
                                     }
                                     // Append function arguments if provided
                                     if let Some(args) = &function.arguments {
-                                        entry.1.push_str(args);
+                                        entry.2.push_str(args);
                                     }
                                 }
                             }
@@ -1080,11 +1363,12 @@ fn parse_sse_chunk(chunk: &str) -> Result<Option<StreamResponse>, LLMError> {
             let mut sorted_calls: Vec<_> = tool_calls_map.iter().collect();
             sorted_calls.sort_by_key(|(index, _)| *index);
 
-            for (index, (name, args)) in sorted_calls {
+            for (index, (name, id, args)) in sorted_calls {
                 // Only include tool calls that have either a name or arguments
                 if name.is_some() || !args.is_empty() {
                     deltas.push(StreamToolCallDelta {
                         index: *index,
+                        id: id.clone(),
                         function: if let Some(name) = name {
                             Some(StreamToolCallFunction {
                                 name: name.clone(),
@@ -1121,6 +1405,8 @@ fn parse_sse_chunk(chunk: &str) -> Result<Option<StreamResponse>, LLMError> {
                         None
                     },
                     tool_calls,
+                    thinking: None,
+                    reasoning: None,
                 },
             }],
             usage: None,
@@ -1137,11 +1423,31 @@ impl LLMBuilder<OpenAI> {
         self
     }
 
+    /// Set the model used by [`ModerationProvider::moderate`].
+    pub fn moderation_model(mut self, model: impl Into<String>) -> Self {
+        self.moderation_model = Some(model.into());
+        self
+    }
+
+    /// Limit the generated tokens, reasoning included. Reasoning models get
+    /// `max_tokens` sent this way too.
+    pub fn max_completion_tokens(mut self, max_completion_tokens: u32) -> Self {
+        self.openai_max_completion_tokens = Some(max_completion_tokens);
+        self
+    }
+
+    /// Builds the client, rejecting settings the model does not support:
+    /// reasoning models sample neither with a temperature nor with top-p.
     pub fn build(self) -> Result<Arc<OpenAI>, LLMError> {
         let key = self.api_key.ok_or_else(|| {
             LLMError::InvalidRequest("No API key provided for OpenAI".to_string())
         })?;
-        let openai = OpenAI::new(
+        if self.max_tokens.is_some() && self.openai_max_completion_tokens.is_some() {
+            return Err(LLMError::InvalidRequest(
+                "Set either max_tokens or max_completion_tokens, not both".to_string(),
+            ));
+        }
+        let mut openai = OpenAI::new(
             key,
             self.base_url,
             self.model,
@@ -1163,7 +1469,232 @@ impl LLMBuilder<OpenAI> {
             None,
             None,
         );
+        openai.moderation_model = self.moderation_model;
+        openai.max_completion_tokens = self.openai_max_completion_tokens;
+        openai.top_logprobs = self.top_logprobs;
+        openai.generation = self.generation;
+        openai.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut openai.client, self.timeout_seconds)?;
+
+        if openai.is_reasoning_model() {
+            let unsupported = [
+                ("temperature", openai.temperature.is_some()),
+                ("top_p", openai.top_p.is_some()),
+                ("logprobs", openai.top_logprobs.is_some()),
+                (
+                    "stop_sequences",
+                    !openai.generation.stop_sequences.is_empty(),
+                ),
+                (
+                    "frequency_penalty",
+                    openai.generation.frequency_penalty.is_some(),
+                ),
+                (
+                    "presence_penalty",
+                    openai.generation.presence_penalty.is_some(),
+                ),
+            ];
+            if let Some((setting, _)) = unsupported.iter().find(|(_, set)| *set) {
+                return Err(LLMError::InvalidRequest(format!(
+                    "{} is a reasoning model and does not support {setting}",
+                    openai.model
+                )));
+            }
+        }
 
         Ok(Arc::new(openai))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ImageMime;
+
+    fn openai(model: &str) -> OpenAI {
+        OpenAI::new(
+            "test-key",
+            None,
+            Some(model.to_string()),
+            Some(512),
+            None,
+            None,
+            Some("Be brief.".to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("low".to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_message_parts_become_text_and_image_url_content() {
+        let message = ChatMessage::user()
+            .content("Compare these")
+            .part(ContentPart::image(ImageMime::PNG, vec![1, 2, 3]))
+            .part(ContentPart::image_url("https://example.com/b.jpg"))
+            .part(ContentPart::text("Which is brighter?"))
+            .build();
+
+        let body = serde_json::to_value(chat_message_to_api_message(&message)).unwrap();
+        assert_eq!(
+            body["content"],
+            serde_json::json!([
+                {"type": "text", "text": "Compare these"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,AQID"}},
+                {"type": "image_url", "image_url": {"url": "https://example.com/b.jpg"}},
+                {"type": "text", "text": "Which is brighter?"},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_reasoning_models() {
+        for model in ["o1", "o3-mini", "o4-mini-2025-04-16", "gpt-5", "gpt-5-mini"] {
+            assert!(openai(model).is_reasoning_model(), "{model}");
+        }
+        for model in [
+            "gpt-4o",
+            "gpt-4.1-mini",
+            "gpt-5-chat-latest",
+            "omni-moderation",
+        ] {
+            assert!(!openai(model).is_reasoning_model(), "{model}");
+        }
+    }
+
+    #[test]
+    fn test_generation_options_are_sent() {
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let mut client = openai("gpt-4o-mini");
+        client.generation.stop_sequences = vec!["END".to_string()];
+        client.generation.seed = Some(42);
+        client.generation.frequency_penalty = Some(0.5);
+
+        let request = client
+            .build_chat_completion_request(&messages, None, None, false, None)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(request["stop"], serde_json::json!(["END"]));
+        assert_eq!(request["seed"], 42);
+        assert_eq!(request["frequency_penalty"], 0.5);
+        assert!(request.get("presence_penalty").is_none());
+    }
+
+    #[test]
+    fn test_reasoning_model_rejects_penalties() {
+        let err = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .model("o3-mini")
+            .presence_penalty(0.5)
+            .build()
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, LLMError::InvalidRequest(ref msg) if msg.contains("presence_penalty")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_reasoning_request_uses_developer_role_and_completion_tokens() {
+        let messages = [ChatMessage::user().content("Hi").build()];
+
+        let client = openai("o3-mini");
+        let request = client
+            .build_chat_completion_request(&messages, None, None, false, None)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(request["messages"][0]["role"], "developer");
+        assert_eq!(request["max_completion_tokens"], 512);
+        assert!(request.get("max_tokens").is_none());
+        assert_eq!(request["reasoning_effort"], "low");
+
+        let client = openai("gpt-4o");
+        let request = client
+            .build_chat_completion_request(&messages, None, None, false, None)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(request["messages"][0]["role"], "system");
+        assert_eq!(request["max_tokens"], 512);
+        assert!(request.get("max_completion_tokens").is_none());
+    }
+    #[test]
+    fn test_logprobs_are_requested_and_returned() {
+        let messages = [ChatMessage::user().content("Yes or no?").build()];
+        let mut client = openai("gpt-4o-mini");
+        client.top_logprobs = Some(2);
+
+        let request = client
+            .build_chat_completion_request(&messages, None, None, false, None)
+            .unwrap();
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(request["logprobs"], true);
+        assert_eq!(request["top_logprobs"], 2);
+        let request = client
+            .build_chat_completion_request(&messages, None, None, true, None)
+            .unwrap();
+        assert!(serde_json::to_value(request)
+            .unwrap()
+            .get("logprobs")
+            .is_none());
+
+        let response: OpenAIChatResponse = serde_json::from_value(serde_json::json!({
+            "choices": [{
+                "message": {"role": "assistant", "content": "Yes"},
+                "logprobs": {"content": [{
+                    "token": "Yes",
+                    "logprob": -0.1,
+                    "bytes": [89, 101, 115],
+                    "top_logprobs": [
+                        {"token": "Yes", "logprob": -0.1, "bytes": [89, 101, 115]},
+                        {"token": "No", "logprob": -2.4, "bytes": [78, 111]}
+                    ]
+                }]}
+            }]
+        }))
+        .unwrap();
+        let logprobs = response.logprobs().unwrap();
+        assert_eq!(logprobs[0].token, "Yes");
+        assert_eq!(logprobs[0].top_logprobs[1].token, "No");
+        assert_eq!(logprobs[0].top_logprobs[1].logprob, -2.4);
+    }
+
+    #[test]
+    fn test_structured_output_falls_back_to_json_mode_on_older_models() {
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let format = || StructuredOutputFormat {
+            name: "answer".to_string(),
+            description: None,
+            schema: Some(serde_json::json!({"type": "object"})),
+            strict: Some(true),
+        };
+        let response_format = |model: &str| {
+            let client = openai(model);
+            let request = client
+                .build_chat_completion_request(&messages, None, Some(format()), false, None)
+                .unwrap();
+            serde_json::to_value(request).unwrap()["response_format"].clone()
+        };
+
+        assert_eq!(response_format("gpt-4o-mini")["type"], "json_schema");
+        assert_eq!(response_format("o3-mini")["type"], "json_schema");
+        assert_eq!(
+            response_format("gpt-4-turbo"),
+            serde_json::json!({"type": "json_object"})
+        );
+        assert!(response_format("o1-mini").is_null());
+        assert!(openai("gpt-4.1").supports_structured_output());
+        assert!(!openai("gpt-3.5-turbo").supports_structured_output());
+    }
+}
@@ -0,0 +1,622 @@
+//! Bulk chat requests through OpenAI's Batch API.
+//!
+//! Requests collected in an [`OpenAIBatch`] are uploaded as one JSONL file
+//! and run by OpenAI in the background, at half the price of sending them
+//! one by one, within 24 hours. Every request added hands back a
+//! [`BatchTicket`] that claims its response from the [`BatchResults`] once
+//! the job is done, so results find their way back to whoever asked.
+//!
+//! ```no_run
+//! # async fn run(openai: &autoagents_llm::backends::openai::OpenAI) -> Result<(), autoagents_llm::error::LLMError> {
+//! use autoagents_llm::chat::ChatMessage;
+//!
+//! let mut batch = openai.batch();
+//! let tickets = ["2 + 2", "3 * 3"]
+//!     .iter()
+//!     .map(|q| batch.chat(&[ChatMessage::user().content(*q).build()], None, None))
+//!     .collect::<Result<Vec<_>, _>>()?;
+//!
+//! let job = batch.submit().await?;
+//! let mut results = job.wait().await?;
+//! for ticket in &tickets {
+//!     println!("{:?}", results.take(ticket)?.text());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{OpenAI, OpenAIChatResponse};
+use crate::chat::utils::check_response_status;
+use crate::chat::{ChatMessage, ChatResponse, StructuredOutputFormat, Tool};
+use crate::error::LLMError;
+use crate::providers::openai_compatible::multipart_form;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::RetryRequestExt;
+
+const CHAT_COMPLETIONS_ENDPOINT: &str = "/v1/chat/completions";
+const COMPLETION_WINDOW: &str = "24h";
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Claim on the response to one request of a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BatchTicket(String);
+
+impl BatchTicket {
+    /// The `custom_id` the request was uploaded with
+    pub fn custom_id(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Chat requests collected for one batch job, see [`OpenAI::batch`].
+pub struct OpenAIBatch<'a> {
+    openai: &'a OpenAI,
+    lines: Vec<u8>,
+    requests: usize,
+}
+
+#[derive(Serialize)]
+struct BatchLine<'a, B> {
+    custom_id: &'a str,
+    method: &'static str,
+    url: &'static str,
+    body: B,
+}
+
+impl<'a> OpenAIBatch<'a> {
+    /// Add a chat request, built as [`ChatProvider::chat`] would send it.
+    ///
+    /// [`ChatProvider::chat`]: crate::chat::ChatProvider::chat
+    pub fn chat(
+        &mut self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<BatchTicket, LLMError> {
+        let body =
+            self.openai
+                .build_chat_completion_request(messages, tools, json_schema, false, None)?;
+        let ticket = BatchTicket(format!("request-{}", self.requests));
+        serde_json::to_writer(
+            &mut self.lines,
+            &BatchLine {
+                custom_id: &ticket.0,
+                method: "POST",
+                url: CHAT_COMPLETIONS_ENDPOINT,
+                body,
+            },
+        )?;
+        self.lines.push(b'\n');
+        self.requests += 1;
+        Ok(ticket)
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests == 0
+    }
+
+    /// Upload the requests and start the batch job.
+    pub async fn submit(self) -> Result<OpenAIBatchJob<'a>, LLMError> {
+        if self.is_empty() {
+            return Err(LLMError::InvalidRequest(
+                "A batch needs at least one request".to_string(),
+            ));
+        }
+        let openai = self.openai;
+
+        let (content_type, body) = multipart_form(
+            &[("purpose", "batch")],
+            "batch.jsonl",
+            "application/jsonl",
+            &self.lines,
+        );
+        let file: UploadedFile = openai
+            .batch_api(
+                openai
+                    .client
+                    .post(openai.endpoint("files")?)
+                    .header(reqwest::header::CONTENT_TYPE, content_type)
+                    .body(body),
+            )
+            .await?;
+
+        let info: OpenAIBatchInfo = openai
+            .batch_api(
+                openai
+                    .client
+                    .post(openai.endpoint("batches")?)
+                    .json(&json!({
+                        "input_file_id": file.id,
+                        "endpoint": CHAT_COMPLETIONS_ENDPOINT,
+                        "completion_window": COMPLETION_WINDOW,
+                    })),
+            )
+            .await?;
+        log::debug!(
+            "Submitted OpenAI batch {} with {} requests",
+            info.id,
+            self.requests
+        );
+        Ok(openai.batch_job(info.id))
+    }
+}
+
+#[derive(Deserialize)]
+struct UploadedFile {
+    id: String,
+}
+
+/// Lifecycle of a batch job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Validating,
+    Failed,
+    InProgress,
+    Finalizing,
+    Completed,
+    Expired,
+    Cancelling,
+    Cancelled,
+}
+
+impl BatchStatus {
+    /// Whether the job has stopped, successfully or not
+    pub fn is_done(self) -> bool {
+        matches!(
+            self,
+            Self::Failed | Self::Completed | Self::Expired | Self::Cancelled
+        )
+    }
+}
+
+/// Progress counts of a batch job.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct BatchRequestCounts {
+    pub total: u32,
+    pub completed: u32,
+    pub failed: u32,
+}
+
+/// State of a batch job as reported by OpenAI.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenAIBatchInfo {
+    pub id: String,
+    pub status: BatchStatus,
+    /// File with the successful responses, once the job is done
+    #[serde(default)]
+    pub output_file_id: Option<String>,
+    /// File with the failed requests, once the job is done
+    #[serde(default)]
+    pub error_file_id: Option<String>,
+    #[serde(default)]
+    pub request_counts: BatchRequestCounts,
+    #[serde(default)]
+    errors: Option<BatchErrors>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BatchErrors {
+    #[serde(default)]
+    data: Vec<BatchErrorMessage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BatchErrorMessage {
+    message: String,
+}
+
+impl OpenAIBatchInfo {
+    /// Why the job failed, if OpenAI said so
+    pub fn error_messages(&self) -> Vec<&str> {
+        self.errors
+            .iter()
+            .flat_map(|errors| &errors.data)
+            .map(|error| error.message.as_str())
+            .collect()
+    }
+}
+
+/// A submitted batch job, see [`OpenAI::batch_job`].
+pub struct OpenAIBatchJob<'a> {
+    openai: &'a OpenAI,
+    id: String,
+    poll_interval: Duration,
+}
+
+impl OpenAIBatchJob<'_> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Time between status checks in [`wait`](Self::wait), 30 seconds by
+    /// default
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub async fn status(&self) -> Result<OpenAIBatchInfo, LLMError> {
+        let url = self.openai.endpoint(&format!("batches/{}", self.id))?;
+        self.openai.batch_api(self.openai.client.get(url)).await
+    }
+
+    /// Ask OpenAI to stop the job; requests already answered stay available.
+    pub async fn cancel(&self) -> Result<OpenAIBatchInfo, LLMError> {
+        let url = self
+            .openai
+            .endpoint(&format!("batches/{}/cancel", self.id))?;
+        self.openai.batch_api(self.openai.client.post(url)).await
+    }
+
+    /// Poll until the job is done and collect its responses.
+    ///
+    /// Expired and cancelled jobs return the responses they got to; a job
+    /// OpenAI rejected as a whole is an error.
+    pub async fn wait(&self) -> Result<BatchResults, LLMError> {
+        loop {
+            let info = self.status().await?;
+            if info.status == BatchStatus::Failed {
+                return Err(LLMError::ProviderError(format!(
+                    "OpenAI batch {} failed: {}",
+                    info.id,
+                    info.error_messages().join("; ")
+                )));
+            }
+            if info.status.is_done() {
+                return self.results(&info).await;
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    async fn results(&self, info: &OpenAIBatchInfo) -> Result<BatchResults, LLMError> {
+        let mut responses = HashMap::new();
+        let files = [&info.output_file_id, &info.error_file_id];
+        for file_id in files.into_iter().flatten() {
+            let url = self.openai.endpoint(&format!("files/{file_id}/content"))?;
+            let response = self
+                .openai
+                .client
+                .get(url)
+                .bearer_auth(&self.openai.api_key)
+                .with_scoped_headers()
+                .send_with_retry(&self.openai.retry_policy)
+                .await?;
+            let content = check_response_status(response).await?.text().await?;
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                let line: ResultLine = serde_json::from_str(line)?;
+                responses.insert(line.custom_id.clone(), line.into_response());
+            }
+        }
+        Ok(BatchResults {
+            status: info.status,
+            responses,
+        })
+    }
+}
+
+/// One line of a batch output or error file
+#[derive(Deserialize)]
+struct ResultLine {
+    custom_id: String,
+    #[serde(default)]
+    response: Option<ResultResponse>,
+    #[serde(default)]
+    error: Option<BatchErrorMessage>,
+}
+
+#[derive(Deserialize)]
+struct ResultResponse {
+    status_code: u16,
+    body: Value,
+}
+
+impl ResultLine {
+    fn into_response(self) -> Result<OpenAIChatResponse, LLMError> {
+        if let Some(error) = self.error {
+            return Err(LLMError::ProviderError(error.message));
+        }
+        let Some(response) = self.response else {
+            return Err(LLMError::ProviderError(format!(
+                "Batch request {} has no response",
+                self.custom_id
+            )));
+        };
+        if response.status_code != 200 {
+            let message = response.body["error"]["message"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| response.body.to_string());
+            return Err(LLMError::ProviderError(format!(
+                "Batch request {} failed with status {}: {message}",
+                self.custom_id, response.status_code
+            )));
+        }
+        serde_json::from_value(response.body.clone()).map_err(|e| LLMError::ResponseFormatError {
+            message: format!("Failed to decode OpenAI API response: {e}"),
+            raw_response: response.body.to_string(),
+        })
+    }
+}
+
+/// Responses of a finished batch job, claimed with the tickets handed out
+/// when the requests were added.
+#[derive(Debug)]
+pub struct BatchResults {
+    status: BatchStatus,
+    responses: HashMap<String, Result<OpenAIChatResponse, LLMError>>,
+}
+
+impl BatchResults {
+    /// How the job ended
+    pub fn status(&self) -> BatchStatus {
+        self.status
+    }
+
+    /// Number of responses not yet taken
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// The response to the request `ticket` stands for. A request without a
+    /// response, for example one left over by an expired job, is an error.
+    pub fn take(&mut self, ticket: &BatchTicket) -> Result<Box<dyn ChatResponse>, LLMError> {
+        match self.responses.remove(&ticket.0) {
+            Some(Ok(response)) => Ok(Box::new(response)),
+            Some(Err(error)) => Err(error),
+            None => Err(LLMError::ProviderError(format!(
+                "No response to batch request {} (batch {:?})",
+                ticket.0, self.status
+            ))),
+        }
+    }
+}
+
+impl OpenAI {
+    /// Collect chat requests to run together as one batch job.
+    pub fn batch(&self) -> OpenAIBatch<'_> {
+        OpenAIBatch {
+            openai: self,
+            lines: Vec::new(),
+            requests: 0,
+        }
+    }
+
+    /// Handle on a batch job submitted earlier, for example by another
+    /// process.
+    pub fn batch_job(&self, id: impl Into<String>) -> OpenAIBatchJob<'_> {
+        OpenAIBatchJob {
+            openai: self,
+            id: id.into(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> Result<reqwest::Url, LLMError> {
+        self.base_url
+            .join(path)
+            .map_err(|e| LLMError::HttpError(e.to_string()))
+    }
+
+    /// Send an authenticated files or batches API request and decode its JSON
+    /// response.
+    async fn batch_api<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing OpenAI API key".to_string()));
+        }
+        let response = request
+            .bearer_auth(&self.api_key)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let text = check_response_status(response).await?.text().await?;
+        serde_json::from_str(&text).map_err(|e| LLMError::ResponseFormatError {
+            message: format!("Failed to decode OpenAI API response: {e}"),
+            raw_response: text,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::LLMBuilder;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answers one request per reply, in order, and returns the request line
+    /// and body of each
+    async fn serve(
+        replies: Vec<String>,
+    ) -> (String, tokio::task::JoinHandle<Vec<(String, String)>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                let header_end = loop {
+                    let n = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..n]);
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&request[..header_end]).to_ascii_lowercase();
+                let length: usize = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length: "))
+                    .map_or(0, |value| value.trim().parse().unwrap());
+                while request.len() < header_end + length {
+                    let n = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    reply.len()
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(reply.as_bytes()).await.unwrap();
+                let request_line = headers.lines().next().unwrap_or_default().to_string();
+                let body = String::from_utf8_lossy(&request[header_end..]).into_owned();
+                requests.push((request_line, body));
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn completion(text: &str) -> Value {
+        json!({
+            "choices": [{"message": {"role": "assistant", "content": text}}],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 1, "total_tokens": 6}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_batch_round_trip_maps_results_to_tickets() {
+        let output = json!({
+            "custom_id": "request-1",
+            "response": {"status_code": 200, "body": completion("9")},
+            "error": null
+        });
+        let errors = json!({
+            "custom_id": "request-0",
+            "response": {
+                "status_code": 400,
+                "body": {"error": {"message": "Invalid model"}}
+            },
+            "error": null
+        });
+        let (url, server) = serve(vec![
+            json!({"id": "file-in", "object": "file"}).to_string(),
+            json!({"id": "batch_1", "status": "validating"}).to_string(),
+            json!({"id": "batch_1", "status": "in_progress"}).to_string(),
+            json!({
+                "id": "batch_1",
+                "status": "completed",
+                "output_file_id": "file-out",
+                "error_file_id": "file-err",
+                "request_counts": {"total": 2, "completed": 1, "failed": 1}
+            })
+            .to_string(),
+            format!("{output}\n"),
+            format!("{errors}\n"),
+        ])
+        .await;
+        let openai = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .base_url(url)
+            .model("gpt-4o-mini")
+            .build()
+            .unwrap();
+
+        let mut batch = openai.batch();
+        let first = batch
+            .chat(&[ChatMessage::user().content("2 + 2").build()], None, None)
+            .unwrap();
+        let second = batch
+            .chat(&[ChatMessage::user().content("3 * 3").build()], None, None)
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+
+        let job = batch
+            .submit()
+            .await
+            .unwrap()
+            .poll_interval(Duration::from_millis(1));
+        assert_eq!(job.id(), "batch_1");
+        let mut results = job.wait().await.unwrap();
+        assert_eq!(results.status(), BatchStatus::Completed);
+        assert_eq!(results.take(&second).unwrap().text().as_deref(), Some("9"));
+        let err = results.take(&first).unwrap_err();
+        assert!(err.to_string().contains("Invalid model"), "{err}");
+        assert!(results.take(&first).is_err());
+
+        let requests = server.await.unwrap();
+        let lines: Vec<&str> = requests.iter().map(|(line, _)| line.as_str()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "post /v1/files http/1.1",
+                "post /v1/batches http/1.1",
+                "get /v1/batches/batch_1 http/1.1",
+                "get /v1/batches/batch_1 http/1.1",
+                "get /v1/files/file-out/content http/1.1",
+                "get /v1/files/file-err/content http/1.1",
+            ]
+        );
+
+        let upload = &requests[0].1;
+        assert!(upload.contains("name=\"purpose\"\r\n\r\nbatch\r\n"));
+        let uploaded: Vec<Value> = upload
+            .lines()
+            .filter(|line| line.starts_with('{'))
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(uploaded.len(), 2);
+        assert_eq!(uploaded[1]["custom_id"], "request-1");
+        assert_eq!(uploaded[1]["url"], CHAT_COMPLETIONS_ENDPOINT);
+        assert_eq!(uploaded[1]["body"]["model"], "gpt-4o-mini");
+        assert_eq!(uploaded[1]["body"]["messages"][0]["content"], "3 * 3");
+
+        let created: Value = serde_json::from_str(&requests[1].1).unwrap();
+        assert_eq!(
+            created,
+            json!({
+                "input_file_id": "file-in",
+                "endpoint": CHAT_COMPLETIONS_ENDPOINT,
+                "completion_window": "24h"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_reports_its_errors() {
+        let (url, _server) = serve(vec![json!({
+            "id": "batch_2",
+            "status": "failed",
+            "errors": {"data": [{"code": "invalid_json", "message": "Line 1 is not JSON"}]}
+        })
+        .to_string()])
+        .await;
+        let openai = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .base_url(url)
+            .build()
+            .unwrap();
+
+        let err = openai.batch_job("batch_2").wait().await.unwrap_err();
+        assert!(err.to_string().contains("Line 1 is not JSON"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_empty_batch_is_rejected() {
+        let openai = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .build()
+            .unwrap();
+        assert!(matches!(
+            openai.batch().submit().await,
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+}
@@ -3,6 +3,8 @@
 //! This module provides integration with OpenRouter's LLM models through their API.
 
 use crate::builder::LLMBuilder;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::RetryRequestExt;
 use crate::{
     builder::LLMBackend,
     chat::{StructuredOutputFormat, ToolChoice},
@@ -25,6 +27,7 @@ impl OpenAIProviderConfig for OpenRouterConfig {
     const DEFAULT_MODEL: &'static str = "moonshotai/kimi-k2:free";
     const SUPPORTS_REASONING_EFFORT: bool = false;
     const SUPPORTS_STRUCTURED_OUTPUT: bool = true;
+    const SUPPORTS_LOGPROBS: bool = true;
     const SUPPORTS_PARALLEL_TOOL_CALLS: bool = false;
 }
 
@@ -71,7 +74,11 @@ impl OpenRouter {
     }
 }
 
-impl LLMProvider for OpenRouter {}
+impl LLMProvider for OpenRouter {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
 
 #[async_trait]
 impl CompletionProvider for OpenRouter {
@@ -82,6 +89,7 @@ impl CompletionProvider for OpenRouter {
     ) -> Result<CompletionResponse, LLMError> {
         Ok(CompletionResponse {
             text: "OpenRouter completion not implemented.".into(),
+            usage: None,
         })
     }
 }
@@ -113,7 +121,8 @@ impl ModelsProvider for OpenRouter {
             .client
             .get(&url)
             .bearer_auth(&self.api_key)
-            .send()
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
 
@@ -131,7 +140,7 @@ impl LLMBuilder<OpenRouter> {
             LLMError::InvalidRequest("No API key provided for OpenRouter".to_string())
         })?;
 
-        let openrouter = OpenRouter::with_config(
+        let mut openrouter = OpenRouter::with_config(
             api_key,
             self.base_url,
             self.model,
@@ -148,6 +157,11 @@ impl LLMBuilder<OpenRouter> {
             self.enable_parallel_tool_use,
             self.normalize_response,
         );
+        openrouter.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut openrouter.client, self.timeout_seconds)?;
+        openrouter.generation = self.generation;
+        openrouter.top_logprobs = self.top_logprobs;
 
         Ok(Arc::new(openrouter))
     }
@@ -11,6 +11,8 @@ use crate::{
     embedding::EmbeddingProvider,
     error::LLMError,
     models::ModelsProvider,
+    request_context::RequestHeadersExt,
+    retry::{RetryPolicy, RetryRequestExt},
     LLMProvider,
 };
 use async_trait::async_trait;
@@ -39,6 +41,7 @@ pub struct Phind {
     /// Base URL for the Phind API
     pub api_base_url: String,
     /// HTTP client for making requests
+    pub retry_policy: RetryPolicy,
     client: Client,
 }
 
@@ -76,10 +79,6 @@ impl Phind {
         top_k: Option<u32>,
         api_base_url: Option<String>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
         Self {
             model: model.unwrap_or_else(|| "Phind-70B".to_string()),
             max_tokens,
@@ -90,7 +89,8 @@ impl Phind {
             top_k,
             api_base_url: api_base_url
                 .unwrap_or_else(|| "https://extension.phind.com/agent/".to_string()),
-            client: builder.build().expect("Failed to build reqwest Client"),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
         }
     }
 
@@ -157,9 +157,10 @@ impl Phind {
                     .unwrap_or("Unexpected error from Phind")
                     .to_string();
 
-                Err(LLMError::ProviderError(format!(
-                    "APIError {status}: {error_message}"
-                )))
+                Err(LLMError::HttpStatus {
+                    status: status.as_u16(),
+                    message: error_message,
+                })
             }
         }
     }
@@ -239,7 +240,10 @@ impl ChatProvider for Phind {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
 
-        let response = request.send().await?;
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
 
         log::debug!("Phind HTTP status: {}", response.status());
 
@@ -264,8 +268,8 @@ impl CompletionProvider for Phind {
                 json_schema,
             )
             .await?;
-        if let Some(text) = chat_resp.text() {
-            Ok(CompletionResponse { text })
+        if chat_resp.text().is_some() {
+            Ok(CompletionResponse::from_chat(chat_resp.as_ref()))
         } else {
             Err(LLMError::ProviderError(
                 "No completion text returned by Phind".to_string(),
@@ -289,11 +293,24 @@ impl EmbeddingProvider for Phind {
 impl ModelsProvider for Phind {}
 
 /// Implementation of the LLMProvider trait for Phind.
-impl LLMProvider for Phind {}
+impl LLMProvider for Phind {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
+}
 
 impl LLMBuilder<Phind> {
     pub fn build(self) -> Result<Arc<Phind>, LLMError> {
-        let phind = crate::backends::phind::Phind::new(
+        self.generation.reject_unsupported(
+            "Phind",
+            &[
+                "stop_sequences",
+                "seed",
+                "frequency_penalty",
+                "presence_penalty",
+            ],
+        )?;
+        let mut phind = crate::backends::phind::Phind::new(
             self.model,
             self.max_tokens,
             self.temperature,
@@ -303,6 +320,9 @@ impl LLMBuilder<Phind> {
             self.top_k,
             self.base_url,
         );
+        phind.retry_policy = self.retry_policy;
+        self.http
+            .configure(&mut phind.client, self.timeout_seconds)?;
 
         Ok(Arc::new(phind))
     }
@@ -1,27 +1,39 @@
 //! X.AI API client implementation for chat and completion functionality.
 //!
-//! This module provides integration with X.AI's models through their API.
-//! It implements chat and completion capabilities using the X.AI API endpoints.
-
+//! This module provides integration with X.AI's Grok models through their
+//! OpenAI-compatible API, including function calling, structured output and
+//! streaming. On top of that it supports Grok's live search: the model can
+//! search the web, X, news and RSS feeds while answering, and return the URLs
+//! it used as citations.
+
+use crate::chat::utils::check_response_status;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
 use crate::{
-    builder::LLMBuilder,
-    chat::{ChatResponse, Tool},
+    builder::{LLMBackend, LLMBuilder},
+    chat::{ChatResponse, Citation, GenerationOptions, StreamResponse, Tool, ToolChoice, Usage},
+    providers::openai_compatible::{
+        create_sse_stream, openai_messages, OpenAIChatMessage, OpenAIChatResponse,
+        OpenAIResponseFormat, OpenAIStreamOptions,
+    },
     ToolCall,
 };
 use crate::{
-    chat::{ChatMessage, ChatProvider, ChatRole, StructuredOutputFormat},
+    chat::{ChatMessage, ChatProvider, StructuredOutputFormat},
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
     error::LLMError,
-    models::ModelsProvider,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider, StandardModelListResponse},
     LLMProvider,
 };
 use async_trait::async_trait;
-use futures::stream::Stream;
+use futures::stream::{Stream, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+const XAI_API_URL: &str = "https://api.x.ai/v1/";
+
 /// Client for interacting with X.AI's API.
 ///
 /// This struct provides methods for making chat and completion requests to X.AI's language models.
@@ -43,6 +55,10 @@ pub struct XAI {
     pub top_p: Option<f32>,
     /// Top-k sampling parameter for controlling response diversity
     pub top_k: Option<u32>,
+    /// How the model picks among the tools it is given
+    pub tool_choice: Option<ToolChoice>,
+    /// Whether streamed tool calls are sent whole rather than in fragments
+    pub normalize_response: bool,
     /// Embedding encoding format
     pub embedding_encoding_format: Option<String>,
     /// Embedding dimensions
@@ -53,48 +69,111 @@ pub struct XAI {
     pub xai_search_source_type: Option<String>,
     /// XAI search excluded websites
     pub xai_search_excluded_websites: Option<Vec<String>>,
+    /// XAI search sources, used instead of the single source above when set
+    pub xai_search_sources: Vec<XaiSearchSource>,
     /// XAI search max results
     pub xai_search_max_results: Option<u32>,
     /// XAI search from date
     pub xai_search_from_date: Option<String>,
     /// XAI search to date
     pub xai_search_to_date: Option<String>,
+    /// Whether XAI search returns the URLs of its sources
+    pub xai_search_return_citations: Option<bool>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
     /// HTTP client for making API requests
+    pub retry_policy: RetryPolicy,
     client: Client,
 }
 
 /// Search source configuration for search parameters
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct XaiSearchSource {
-    /// Type of source: "web" or "news"
+    /// Type of source: "web", "x", "news" or "rss"
     #[serde(rename = "type")]
     pub source_type: String,
-    /// List of websites to exclude from this source
+    /// List of websites to exclude from this source ("web" and "news")
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub excluded_websites: Option<Vec<String>>,
+    /// Only search these websites ("web")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_websites: Option<Vec<String>>,
+    /// ISO alpha-2 code of the country to search in ("web" and "news")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Whether to filter out unsafe content ("web" and "news")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safe_search: Option<bool>,
+    /// Only search posts by these handles ("x")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub included_x_handles: Option<Vec<String>>,
+    /// Feed URLs to search ("rss")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Vec<String>>,
+}
+
+impl XaiSearchSource {
+    /// A source of the given type with no further options
+    pub fn new(source_type: impl Into<String>) -> Self {
+        Self {
+            source_type: source_type.into(),
+            excluded_websites: None,
+            allowed_websites: None,
+            country: None,
+            safe_search: None,
+            included_x_handles: None,
+            links: None,
+        }
+    }
+
+    /// Search the web
+    pub fn web() -> Self {
+        Self::new("web")
+    }
+
+    /// Search news sites
+    pub fn news() -> Self {
+        Self::new("news")
+    }
+
+    /// Search posts on X, limited to `handles` unless empty
+    pub fn x(handles: Vec<String>) -> Self {
+        Self {
+            included_x_handles: (!handles.is_empty()).then_some(handles),
+            ..Self::new("x")
+        }
+    }
+
+    /// Search the RSS feed at `link`
+    pub fn rss(link: impl Into<String>) -> Self {
+        Self {
+            links: Some(vec![link.into()]),
+            ..Self::new("rss")
+        }
+    }
 }
 
 /// Search parameters for LLM providers that support search functionality
-#[derive(Debug, Clone, Default, serde::Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
 pub struct XaiSearchParameters {
-    /// Search mode (e.g., "auto")
+    /// Search mode: "auto", "on" or "off"
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mode: Option<String>,
     /// List of search sources with exclusions
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sources: Option<Vec<XaiSearchSource>>,
     /// Maximum number of search results to return
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_search_results: Option<u32>,
     /// Start date for search results (format: "YYYY-MM-DD")
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub from_date: Option<String>,
     /// End date for search results (format: "YYYY-MM-DD")
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub to_date: Option<String>,
-}
-
-/// Individual message in an X.AI chat conversation.
-#[derive(Serialize)]
-struct XAIChatMessage<'a> {
-    /// Role of the message sender (user, assistant, or system)
-    role: &'a str,
-    /// Content of the message
-    content: &'a str,
+    /// Whether the response lists the URLs of the sources used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_citations: Option<bool>,
 }
 
 /// Request payload for X.AI's chat API endpoint.
@@ -103,7 +182,7 @@ struct XAIChatRequest<'a> {
     /// Model identifier to use
     model: &'a str,
     /// Array of conversation messages
-    messages: Vec<XAIChatMessage<'a>>,
+    messages: Vec<OpenAIChatMessage<'a>>,
     /// Maximum tokens to generate
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
@@ -118,48 +197,65 @@ struct XAIChatRequest<'a> {
     /// Top-k sampling parameter
     #[serde(skip_serializing_if = "Option::is_none")]
     top_k: Option<u32>,
+    /// Functions the model may call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<&'a [Tool]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'a ToolChoice>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    response_format: Option<XAIResponseFormat>,
+    response_format: Option<OpenAIResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAIStreamOptions>,
     /// Search parameters for search functionality
     #[serde(skip_serializing_if = "Option::is_none")]
-    search_parameters: Option<&'a XaiSearchParameters>,
+    search_parameters: Option<XaiSearchParameters>,
+    #[serde(flatten)]
+    generation: &'a GenerationOptions,
 }
 
 /// Response from X.AI's chat API endpoint.
 #[derive(Deserialize, Debug)]
 struct XAIChatResponse {
-    /// Array of generated responses
-    choices: Vec<XAIChatChoice>,
+    #[serde(flatten)]
+    inner: OpenAIChatResponse,
+    /// URLs of the sources live search used
+    #[serde(default)]
+    citations: Vec<String>,
 }
 
 impl std::fmt::Display for XAIChatResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.text().unwrap_or_default())
+        write!(f, "{}", self.inner)
     }
 }
 
 impl ChatResponse for XAIChatResponse {
     fn text(&self) -> Option<String> {
-        self.choices.first().map(|c| c.message.content.clone())
+        self.inner.text()
     }
 
     fn tool_calls(&self) -> Option<Vec<ToolCall>> {
-        None
+        self.inner.tool_calls()
     }
-}
 
-/// Individual response choice from the chat API.
-#[derive(Deserialize, Debug)]
-struct XAIChatChoice {
-    /// Message content and metadata
-    message: XAIChatMsg,
-}
+    fn usage(&self) -> Option<Usage> {
+        self.inner.usage()
+    }
 
-/// Message content from a chat response.
-#[derive(Deserialize, Debug)]
-struct XAIChatMsg {
-    /// Generated text content
-    content: String,
+    /// Live search cites its sources for the response as a whole, so the
+    /// single citation spans all of the text.
+    fn citations(&self) -> Option<Vec<Citation>> {
+        if self.citations.is_empty() {
+            return None;
+        }
+        let text = self.text().unwrap_or_default();
+        Some(vec![Citation {
+            start: 0,
+            end: text.chars().count(),
+            text,
+            sources: self.citations.clone(),
+        }])
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -177,54 +273,11 @@ struct XAIEmbeddingData {
     embedding: Vec<f32>,
 }
 
-/// Response from X.AI's streaming chat API endpoint.
-#[derive(Deserialize, Debug)]
-struct XAIStreamResponse {
-    /// Array of generated responses
-    choices: Vec<XAIStreamChoice>,
-}
-
-/// Individual response choice from the streaming chat API.
-#[derive(Deserialize, Debug)]
-struct XAIStreamChoice {
-    /// Delta content
-    delta: XAIStreamDelta,
-}
-
-/// Delta content from a streaming chat response.
-#[derive(Deserialize, Debug)]
-struct XAIStreamDelta {
-    /// Generated text content
-    content: Option<String>,
-}
-
 #[derive(Deserialize)]
 struct XAIEmbeddingResponse {
     data: Vec<XAIEmbeddingData>,
 }
 
-#[derive(Deserialize, Debug, Serialize)]
-enum XAIResponseType {
-    #[serde(rename = "text")]
-    Text,
-    #[serde(rename = "json_schema")]
-    JsonSchema,
-    #[serde(rename = "json_object")]
-    JsonObject,
-}
-
-/// An object specifying the format that the model must output.
-/// Setting to `{ "type": "json_schema", "json_schema": {...} }` enables Structured Outputs which ensures the model will match your supplied JSON schema. Learn more in the [Structured Outputs guide](https://platform.openai.com/docs/guides/structured-outputs).
-/// Setting to `{ "type": "json_object" }` enables the older JSON mode, which ensures the message the model generates is valid JSON. Using `json_schema` is preferred for models that support it.
-/// The structured outputs feature is only supported for the `grok-2-latest` model.
-#[derive(Deserialize, Debug, Serialize)]
-struct XAIResponseFormat {
-    #[serde(rename = "type")]
-    response_type: XAIResponseType,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    json_schema: Option<StructuredOutputFormat>,
-}
-
 impl XAI {
     /// Creates a new X.AI client with the specified configuration.
     ///
@@ -264,10 +317,6 @@ impl XAI {
         xai_search_from_date: Option<String>,
         xai_search_to_date: Option<String>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
         Self {
             api_key: api_key.into(),
             model: model.unwrap_or("grok-2-latest".to_string()),
@@ -277,15 +326,21 @@ impl XAI {
             timeout_seconds,
             top_p,
             top_k,
+            tool_choice: None,
+            normalize_response: true,
             embedding_encoding_format,
             embedding_dimensions,
             xai_search_mode,
             xai_search_source_type,
             xai_search_excluded_websites,
+            xai_search_sources: Vec::new(),
             xai_search_max_results,
             xai_search_from_date,
             xai_search_to_date,
-            client: builder.build().expect("Failed to build reqwest Client"),
+            xai_search_return_citations: None,
+            generation: GenerationOptions::default(),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
         }
     }
 
@@ -306,6 +361,13 @@ impl XAI {
         self
     }
 
+    /// Adds a fully configured search source. Once any is added, these
+    /// sources replace the one set with [`set_search_source`](Self::set_search_source).
+    pub fn add_search_source(mut self, source: XaiSearchSource) -> Self {
+        self.xai_search_sources.push(source);
+        self
+    }
+
     /// Sets the maximum number of search results.
     pub fn set_max_search_results(mut self, max: u32) -> Self {
         self.xai_search_max_results = Some(max);
@@ -330,110 +392,148 @@ impl XAI {
         self.xai_search_to_date = Some(date.into());
         self
     }
-}
 
-#[async_trait]
-impl ChatProvider for XAI {
-    /// Sends a chat request to the X.AI API and returns the response.
-    ///
-    /// # Arguments
-    ///
-    /// * `messages` - Array of chat messages representing the conversation
-    ///
-    /// # Returns
-    ///
-    /// The generated response text, or an error if the request fails.
-    async fn chat(
-        &self,
-        messages: &[ChatMessage],
-        _tools: Option<&[Tool]>, // XAI Does not support tools yet
-        json_schema: Option<StructuredOutputFormat>,
-    ) -> Result<Box<dyn ChatResponse>, LLMError> {
-        if self.api_key.is_empty() {
-            return Err(LLMError::AuthError("Missing X.AI API key".to_string()));
-        }
+    /// Sets whether responses list the URLs search used, surfaced through
+    /// [`ChatResponse::citations`].
+    pub fn set_return_citations(mut self, return_citations: bool) -> Self {
+        self.xai_search_return_citations = Some(return_citations);
+        self
+    }
 
-        let mut xai_msgs: Vec<XAIChatMessage> = messages
-            .iter()
-            .map(|m| XAIChatMessage {
-                role: match m.role {
-                    ChatRole::User => "user",
-                    ChatRole::Assistant => "assistant",
-                    ChatRole::Tool => "tool",
-                    ChatRole::System => "system",
-                },
-                content: &m.content,
-            })
-            .collect();
-
-        if let Some(system) = &self.system {
-            xai_msgs.insert(
-                0,
-                XAIChatMessage {
-                    role: "system",
-                    content: system,
-                },
-            );
-        }
+    /// Replaces all search settings with `parameters`.
+    pub fn with_search_parameters(mut self, parameters: XaiSearchParameters) -> Self {
+        self.xai_search_mode = parameters.mode;
+        self.xai_search_source_type = None;
+        self.xai_search_excluded_websites = None;
+        self.xai_search_sources = parameters.sources.unwrap_or_default();
+        self.xai_search_max_results = parameters.max_search_results;
+        self.xai_search_from_date = parameters.from_date;
+        self.xai_search_to_date = parameters.to_date;
+        self.xai_search_return_citations = parameters.return_citations;
+        self
+    }
 
-        // OpenAI's structured output has some [odd requirements](https://platform.openai.com/docs/guides/structured-outputs?api-mode=chat&lang=curl#supported-schemas).
-        // There's currently no check for these, so we'll leave it up to the user to provide a valid schema.
-        // Unknown if XAI requires these too, but since it copies everything else from OpenAI, it's likely.
-        let response_format: Option<XAIResponseFormat> =
-            json_schema.as_ref().map(|s| XAIResponseFormat {
-                response_type: XAIResponseType::JsonSchema,
-                json_schema: Some(s.clone()),
-            });
+    /// The search parameters sent with chat requests, or `None` if no search
+    /// setting is configured, which leaves live search off.
+    pub fn search_parameters(&self) -> Option<XaiSearchParameters> {
+        let sources = if !self.xai_search_sources.is_empty() {
+            Some(self.xai_search_sources.clone())
+        } else if self.xai_search_source_type.is_some()
+            || self.xai_search_excluded_websites.is_some()
+        {
+            Some(vec![XaiSearchSource {
+                excluded_websites: self.xai_search_excluded_websites.clone(),
+                ..XaiSearchSource::new(
+                    self.xai_search_source_type
+                        .clone()
+                        .unwrap_or("web".to_string()),
+                )
+            }])
+        } else {
+            None
+        };
 
-        let search_parameters = XaiSearchParameters {
+        let parameters = XaiSearchParameters {
             mode: self.xai_search_mode.clone(),
-            sources: Some(vec![XaiSearchSource {
-                source_type: self
-                    .xai_search_source_type
-                    .clone()
-                    .unwrap_or("web".to_string()),
-                excluded_websites: self.xai_search_excluded_websites.clone(),
-            }]),
+            sources,
             max_search_results: self.xai_search_max_results,
             from_date: self.xai_search_from_date.clone(),
             to_date: self.xai_search_to_date.clone(),
+            return_citations: self.xai_search_return_citations,
         };
+        (parameters != XaiSearchParameters::default()).then_some(parameters)
+    }
+
+    fn chat_request<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+        tools: Option<&'a [Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+        stream: bool,
+    ) -> XAIChatRequest<'a> {
+        // OpenAI's structured output has some [odd requirements](https://platform.openai.com/docs/guides/structured-outputs?api-mode=chat&lang=curl#supported-schemas).
+        // There's currently no check for these, so we'll leave it up to the user to provide a valid schema.
+        // Unknown if XAI requires these too, but since it copies everything else from OpenAI, it's likely.
+        let response_format = json_schema.map(OpenAIResponseFormat::from);
+        let tools = tools.filter(|tools| !tools.is_empty());
 
-        let body = XAIChatRequest {
+        XAIChatRequest {
             model: &self.model,
-            messages: xai_msgs,
+            messages: openai_messages(messages, self.system.as_deref()),
             max_tokens: self.max_tokens,
             temperature: self.temperature,
-            stream: false,
+            stream,
             top_p: self.top_p,
             top_k: self.top_k,
+            tools,
+            tool_choice: tools.and(self.tool_choice.as_ref()),
             response_format,
-            search_parameters: Some(&search_parameters),
-        };
+            stream_options: stream.then_some(OpenAIStreamOptions {
+                include_usage: true,
+            }),
+            search_parameters: self.search_parameters(),
+            generation: &self.generation,
+        }
+    }
+
+    async fn send_chat(&self, body: &XAIChatRequest<'_>) -> Result<reqwest::Response, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing X.AI API key".to_string()));
+        }
 
         if log::log_enabled!(log::Level::Trace) {
-            if let Ok(json) = serde_json::to_string(&body) {
+            if let Ok(json) = serde_json::to_string(body) {
                 log::trace!("XAI request payload: {json}");
             }
         }
 
         let mut request = self
             .client
-            .post("https://api.x.ai/v1/chat/completions")
+            .post(format!("{XAI_API_URL}chat/completions"))
             .bearer_auth(&self.api_key)
-            .json(&body);
+            .json(body);
 
         if let Some(timeout) = self.timeout_seconds {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
 
-        let resp = request.send().await?;
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
 
-        log::debug!("XAI HTTP status: {}", resp.status());
+        log::debug!("XAI HTTP status: {}", response.status());
 
-        let resp = resp.error_for_status()?;
+        check_response_status(response).await
+    }
+}
 
-        let json_resp: XAIChatResponse = resp.json().await?;
+#[async_trait]
+impl ChatProvider for XAI {
+    /// Sends a chat request to the X.AI API and returns the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - Array of chat messages representing the conversation
+    ///
+    /// # Returns
+    ///
+    /// The generated response text, or an error if the request fails.
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let body = self.chat_request(messages, tools, json_schema, false);
+        let response = self.send_chat(&body).await?;
+
+        let resp_text = response.text().await?;
+        let json_resp: XAIChatResponse =
+            serde_json::from_str(&resp_text).map_err(|e| LLMError::ResponseFormatError {
+                message: format!("Failed to decode X.AI API response: {e}"),
+                raw_response: resp_text.clone(),
+            })?;
         Ok(Box::new(json_resp))
     }
 
@@ -449,101 +549,59 @@ impl ChatProvider for XAI {
     async fn chat_stream(
         &self,
         messages: &[ChatMessage],
-        _tools: Option<&[Tool]>,
-        _json_schema: Option<StructuredOutputFormat>,
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
     ) -> Result<std::pin::Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError>
     {
-        if self.api_key.is_empty() {
-            return Err(LLMError::AuthError("Missing X.AI API key".to_string()));
-        }
-
-        let mut xai_msgs: Vec<XAIChatMessage> = messages
-            .iter()
-            .map(|m| XAIChatMessage {
-                role: match m.role {
-                    ChatRole::User => "user",
-                    ChatRole::Assistant => "assistant",
-                    ChatRole::System => "system",
-                    ChatRole::Tool => "tool",
-                },
-                content: &m.content,
-            })
-            .collect();
-
-        if let Some(system) = &self.system {
-            xai_msgs.insert(
-                0,
-                XAIChatMessage {
-                    role: "system",
-                    content: system,
-                },
-            );
-        }
-
-        let body = XAIChatRequest {
-            model: &self.model,
-            messages: xai_msgs,
-            max_tokens: self.max_tokens,
-            temperature: self.temperature,
-            stream: true,
-            top_p: self.top_p,
-            top_k: self.top_k,
-            response_format: None,
-            search_parameters: None,
-        };
-
-        let mut request = self
-            .client
-            .post("https://api.x.ai/v1/chat/completions")
-            .bearer_auth(&self.api_key)
-            .json(&body);
-
-        if let Some(timeout) = self.timeout_seconds {
-            request = request.timeout(std::time::Duration::from_secs(timeout));
-        }
-
-        let response = request.send().await?;
+        let struct_stream = self
+            .chat_stream_struct(messages, tools, json_schema)
+            .await?;
+        let content_stream = struct_stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .filter(|content| !content.is_empty())
+                    .map(Ok),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(content_stream))
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(LLMError::ResponseFormatError {
-                message: format!("X.AI API returned error status: {status}"),
-                raw_response: error_text,
-            });
-        }
+    /// Streams text and tool calls, with the token usage in the last chunk.
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<
+        std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>,
+        LLMError,
+    > {
+        let body = self.chat_request(messages, tools, json_schema, true);
+        let response = self.send_chat(&body).await?;
+        Ok(create_sse_stream(response, self.normalize_response))
+    }
 
-        Ok(crate::chat::create_sse_stream(
-            response,
-            parse_xai_sse_chunk,
-        ))
+    fn supports_structured_output(&self) -> bool {
+        true
     }
 }
 
 #[async_trait]
 impl CompletionProvider for XAI {
-    /// Sends a completion request to X.AI's API.
-    ///
-    /// This functionality is currently not implemented.
-    ///
-    /// # Arguments
-    ///
-    /// * `_req` - The completion request parameters
-    ///
-    /// # Returns
-    ///
-    /// A placeholder response indicating the functionality is not implemented.
+    /// Sends the prompt as a single user message.
     async fn complete(
         &self,
-        _req: &CompletionRequest,
-        _json_schema: Option<StructuredOutputFormat>,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
     ) -> Result<CompletionResponse, LLMError> {
-        if self.api_key.is_empty() {
-            return Err(LLMError::AuthError("Missing X.AI API key".into()));
-        }
-        Err(LLMError::ProviderError(
-            "X.AI completion not implemented yet".into(),
-        ))
+        let messages = [ChatMessage::user().content(req.prompt.clone()).build()];
+        let response = self.chat(&messages, None, json_schema).await?;
+        Ok(CompletionResponse::from_chat(response.as_ref()))
     }
 }
 
@@ -568,10 +626,11 @@ impl EmbeddingProvider for XAI {
 
         let resp = self
             .client
-            .post("https://api.x.ai/v1/embeddings")
+            .post(format!("{XAI_API_URL}embeddings"))
             .bearer_auth(&self.api_key)
             .json(&body)
-            .send()
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
             .await?
             .error_for_status()?;
 
@@ -586,61 +645,48 @@ impl EmbeddingProvider for XAI {
 impl ModelsProvider for XAI {
     async fn list_models(
         &self,
-        _request: Option<&crate::models::ModelListRequest>,
-    ) -> Result<Box<dyn crate::models::ModelListResponse>, LLMError> {
+        _request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
         if self.api_key.is_empty() {
             return Err(LLMError::AuthError("Missing X.AI API key".into()));
         }
-        Err(LLMError::ProviderError("List Models not supported".into()))
-    }
-}
 
-impl LLMProvider for XAI {}
-
-/// Parses a Server-Sent Events (SSE) chunk from X.AI's streaming API.
-///
-/// # Arguments
-///
-/// * `chunk` - The raw SSE chunk text
-///
-/// # Returns
-///
-/// * `Ok(Some(String))` - Content token if found
-/// * `Ok(None)` - If chunk should be skipped (e.g., ping, done signal)
-/// * `Err(LLMError)` - If parsing fails
-fn parse_xai_sse_chunk(chunk: &str) -> Result<Option<String>, LLMError> {
-    for line in chunk.lines() {
-        let line = line.trim();
-
-        if let Some(data) = line.strip_prefix("data: ") {
-            if data == "[DONE]" {
-                return Ok(None);
-            }
+        let resp = self
+            .client
+            .get(format!("{XAI_API_URL}models"))
+            .bearer_auth(&self.api_key)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?
+            .error_for_status()?;
 
-            match serde_json::from_str::<XAIStreamResponse>(data) {
-                Ok(response) => {
-                    if let Some(choice) = response.choices.first() {
-                        if let Some(content) = &choice.delta.content {
-                            return Ok(Some(content.clone()));
-                        }
-                    }
-                    return Ok(None);
-                }
-                Err(_) => continue,
-            }
-        }
+        let result = StandardModelListResponse {
+            inner: resp.json().await?,
+            backend: LLMBackend::XAI,
+        };
+        Ok(Box::new(result))
     }
+}
 
-    Ok(None)
+impl LLMProvider for XAI {
+    fn token_counter(&self) -> std::sync::Arc<dyn crate::tokenizer::TokenCounter> {
+        crate::tokenizer::counter_for_model(&self.model)
+    }
 }
 
 impl LLMBuilder<XAI> {
+    /// Let Grok search while answering, with the given live search settings
+    pub fn search_parameters(mut self, parameters: XaiSearchParameters) -> Self {
+        self.xai_search = Some(parameters);
+        self
+    }
+
     pub fn build(self) -> Result<Arc<XAI>, LLMError> {
         let api_key = self
             .api_key
             .ok_or_else(|| LLMError::InvalidRequest("No API key provided for XAI".to_string()))?;
 
-        let xai = crate::backends::xai::XAI::new(
+        let mut xai = crate::backends::xai::XAI::new(
             api_key,
             self.model,
             self.max_tokens,
@@ -658,7 +704,134 @@ impl LLMBuilder<XAI> {
             None,
             None,
         );
+        xai.tool_choice = self.tool_choice;
+        xai.normalize_response = self.normalize_response.unwrap_or(true);
+        xai.retry_policy = self.retry_policy;
+        self.http.configure(&mut xai.client, self.timeout_seconds)?;
+        xai.generation = self.generation;
+        if let Some(parameters) = self.xai_search {
+            xai = xai.with_search_parameters(parameters);
+        }
 
         Ok(Arc::new(xai))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::FunctionTool;
+    use crate::FunctionCall;
+    use serde_json::json;
+
+    fn xai() -> XAI {
+        XAI::new(
+            "test-key", None, None, None, None, None, None, None, None, None, None, None, None,
+            None, None, None,
+        )
+    }
+
+    #[test]
+    fn test_search_parameters_are_only_sent_when_configured() {
+        assert!(xai().search_parameters().is_none());
+
+        let client = xai()
+            .set_search_mode("on")
+            .add_search_source(XaiSearchSource::x(vec!["xai".to_string()]))
+            .add_search_source(XaiSearchSource::rss("https://status.x.ai/feed.xml"))
+            .set_return_citations(true);
+        let parameters = serde_json::to_value(client.search_parameters()).unwrap();
+        assert_eq!(
+            parameters,
+            json!({
+                "mode": "on",
+                "sources": [
+                    {"type": "x", "included_x_handles": ["xai"]},
+                    {"type": "rss", "links": ["https://status.x.ai/feed.xml"]}
+                ],
+                "return_citations": true
+            })
+        );
+
+        // The single source setter still works on its own
+        let client = xai().set_search_source("news", Some(vec!["example.com".to_string()]));
+        let parameters = serde_json::to_value(client.search_parameters()).unwrap();
+        assert_eq!(
+            parameters["sources"],
+            json!([{"type": "news", "excluded_websites": ["example.com"]}])
+        );
+    }
+
+    #[test]
+    fn test_request_carries_tools_and_tool_results() {
+        let mut client = xai();
+        client.tool_choice = Some(ToolChoice::Auto);
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "weather".to_string(),
+                arguments: r#"{"city":"Austin"}"#.to_string(),
+            },
+        };
+        let result = ToolCall {
+            function: FunctionCall {
+                name: "weather".to_string(),
+                arguments: r#"{"celsius":31}"#.to_string(),
+            },
+            ..call.clone()
+        };
+        let messages = [
+            ChatMessage::user().content("Weather in Austin?").build(),
+            ChatMessage::assistant().tool_use(vec![call]).build(),
+            ChatMessage::user().tool_result(vec![result]).build(),
+        ];
+        let tools = [Tool {
+            tool_type: "function".to_string(),
+            function: FunctionTool {
+                name: "weather".to_string(),
+                description: "Current weather".to_string(),
+                parameters: json!({"type": "object"}),
+            },
+        }];
+
+        let request = client.chat_request(&messages, Some(&tools), None, true);
+        let request = serde_json::to_value(request).unwrap();
+
+        assert_eq!(request["tools"][0]["function"]["name"], "weather");
+        assert_eq!(request["tool_choice"], "auto");
+        assert_eq!(request["stream_options"]["include_usage"], true);
+        assert!(request.get("search_parameters").is_none());
+        assert_eq!(request["messages"][1]["tool_calls"][0]["id"], "call-1");
+        assert_eq!(request["messages"][2]["role"], "tool");
+        assert_eq!(request["messages"][2]["tool_call_id"], "call-1");
+        assert_eq!(request["messages"][2]["content"], r#"{"celsius":31}"#);
+    }
+
+    #[test]
+    fn test_live_search_citations_span_the_response() {
+        let response: XAIChatResponse = serde_json::from_value(json!({
+            "id": "resp-1",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Grok 4 shipped."},
+                "finish_reason": "stop"
+            }],
+            "citations": ["https://x.ai/news/grok-4"],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 4, "total_tokens": 14}
+        }))
+        .unwrap();
+
+        assert_eq!(response.text().as_deref(), Some("Grok 4 shipped."));
+        assert_eq!(response.usage().unwrap().total_tokens, 14);
+        assert_eq!(
+            response.citations(),
+            Some(vec![Citation {
+                start: 0,
+                end: 15,
+                text: "Grok 4 shipped.".to_string(),
+                sources: vec!["https://x.ai/news/grok-4".to_string()],
+            }])
+        );
+    }
+}
@@ -37,6 +37,16 @@ pub enum LLMBackend {
     AzureOpenAI,
     /// OpenRouter API provider for various models
     OpenRouter,
+    /// Mistral API provider
+    Mistral,
+    /// AWS Bedrock provider
+    Bedrock,
+    /// Cohere API provider
+    Cohere,
+    /// llama.cpp server for local GGUF models
+    LlamaCpp,
+    /// Hugging Face Text Generation Inference, as served by Inference Endpoints
+    HuggingFace,
 }
 
 /// Implements string parsing for LLMBackend enum.
@@ -80,6 +90,11 @@ impl std::str::FromStr for LLMBackend {
             "groq" => Ok(LLMBackend::Groq),
             "azure-openai" => Ok(LLMBackend::AzureOpenAI),
             "openrouter" => Ok(LLMBackend::OpenRouter),
+            "mistral" => Ok(LLMBackend::Mistral),
+            "bedrock" => Ok(LLMBackend::Bedrock),
+            "cohere" => Ok(LLMBackend::Cohere),
+            "llamacpp" => Ok(LLMBackend::LlamaCpp),
+            "huggingface" => Ok(LLMBackend::HuggingFace),
             _ => Err(LLMError::InvalidRequest(format!(
                 "Unknown LLM backend: {s}"
             ))),
@@ -108,6 +123,15 @@ pub struct LLMBuilder<L: LLMProvider> {
     pub system: Option<String>,
     /// Request timeout duration in seconds
     pub(crate) timeout_seconds: Option<u64>,
+    /// Retry behavior for failed requests
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(dead_code)]
+    pub(crate) retry_policy: crate::retry::RetryPolicy,
+    /// Timeouts, pooling, proxy, default headers and TLS settings of the
+    /// HTTP client
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(dead_code)]
+    pub(crate) http: crate::http::HttpOptions,
     /// Top-p (nucleus) sampling parameter
     pub top_p: Option<f32>,
     /// Top-k sampling parameter
@@ -134,9 +158,59 @@ pub struct LLMBuilder<L: LLMProvider> {
     pub(crate) api_version: Option<String>,
     /// Deployment Id
     pub(crate) deployment_id: Option<String>,
+    /// Entra ID token source for Azure OpenAI, replacing the API key
+    #[cfg(feature = "azure_openai")]
+    pub(crate) azure_token_provider:
+        Option<std::sync::Arc<dyn crate::backends::azure_openai::AzureTokenProvider>>,
+    /// Vertex AI project and location for Google
+    #[cfg(feature = "google")]
+    pub(crate) google_vertex: Option<(String, String)>,
+    /// Access token source for Google on Vertex AI
+    #[cfg(feature = "google")]
+    pub(crate) google_token_provider:
+        Option<std::sync::Arc<dyn crate::backends::google::GoogleTokenProvider>>,
+    /// AWS credentials for Bedrock, instead of those in the environment
+    #[cfg(feature = "bedrock")]
+    pub(crate) aws_credentials: Option<crate::backends::bedrock::AwsCredentials>,
+    /// AWS region for Bedrock
+    #[cfg(feature = "bedrock")]
+    pub(crate) aws_region: Option<String>,
+    /// Token limit for OpenAI that includes reasoning
+    #[cfg(feature = "openai")]
+    pub(crate) openai_max_completion_tokens: Option<u32>,
+    /// Live search settings for xAI
+    #[cfg(feature = "xai")]
+    pub(crate) xai_search: Option<crate::backends::xai::XaiSearchParameters>,
+    /// Documents Cohere grounds its answers in
+    #[cfg(feature = "cohere")]
+    pub(crate) cohere_documents: Vec<crate::backends::cohere::CohereDocument>,
+    /// Context window size for Ollama
+    #[cfg(feature = "ollama")]
+    pub(crate) ollama_num_ctx: Option<u32>,
+    /// How long Ollama keeps the model loaded after a request
+    #[cfg(feature = "ollama")]
+    pub(crate) ollama_keep_alive: Option<String>,
+    /// GBNF grammar for llama.cpp
+    #[cfg(feature = "llamacpp")]
+    pub(crate) llamacpp_grammar: Option<String>,
+    /// Slot that processes llama.cpp requests
+    #[cfg(feature = "llamacpp")]
+    pub(crate) llamacpp_slot_id: Option<u32>,
+    /// Whether llama.cpp reuses the cached prompt of the slot
+    #[cfg(feature = "llamacpp")]
+    pub(crate) llamacpp_cache_prompt: Option<bool>,
     /// Voice
     #[allow(dead_code)]
     pub(crate) voice: Option<String>,
+    /// Model used for moderation requests
+    #[allow(dead_code)]
+    pub(crate) moderation_model: Option<String>,
+    /// Alternatives returned with each token's log probability
+    #[allow(dead_code)]
+    pub(crate) top_logprobs: Option<u8>,
+    /// Stop sequences, seed and penalties
+    #[allow(dead_code)]
+    pub(crate) generation: crate::chat::GenerationOptions,
     /// Whether to normalize response format
     #[allow(dead_code)]
     pub(crate) normalize_response: Option<bool>,
@@ -153,6 +227,10 @@ impl<L: LLMProvider> Default for LLMBuilder<L> {
             temperature: None,
             system: None,
             timeout_seconds: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            retry_policy: crate::retry::RetryPolicy::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            http: crate::http::HttpOptions::default(),
             top_p: None,
             top_k: None,
             embedding_encoding_format: None,
@@ -166,7 +244,36 @@ impl<L: LLMProvider> Default for LLMBuilder<L> {
             reasoning_budget_tokens: None,
             api_version: None,
             deployment_id: None,
+            #[cfg(feature = "azure_openai")]
+            azure_token_provider: None,
+            #[cfg(feature = "google")]
+            google_vertex: None,
+            #[cfg(feature = "google")]
+            google_token_provider: None,
+            #[cfg(feature = "bedrock")]
+            aws_credentials: None,
+            #[cfg(feature = "bedrock")]
+            aws_region: None,
+            #[cfg(feature = "openai")]
+            openai_max_completion_tokens: None,
+            #[cfg(feature = "xai")]
+            xai_search: None,
+            #[cfg(feature = "cohere")]
+            cohere_documents: Vec::new(),
+            #[cfg(feature = "ollama")]
+            ollama_num_ctx: None,
+            #[cfg(feature = "ollama")]
+            ollama_keep_alive: None,
+            #[cfg(feature = "llamacpp")]
+            llamacpp_grammar: None,
+            #[cfg(feature = "llamacpp")]
+            llamacpp_slot_id: None,
+            #[cfg(feature = "llamacpp")]
+            llamacpp_cache_prompt: None,
             voice: None,
+            moderation_model: None,
+            top_logprobs: None,
+            generation: crate::chat::GenerationOptions::default(),
             normalize_response: None,
         }
     }
@@ -238,6 +345,84 @@ impl<L: LLMProvider> LLMBuilder<L> {
         self
     }
 
+    /// Sets how requests failing with a rate limit, server error, timeout or
+    /// connection error are retried. Requests are not retried by default.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn retry_policy(mut self, policy: crate::retry::RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Retries failed requests up to `max_retries` times with the default
+    /// exponential backoff.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry_policy.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the longest wait for a connection to the provider.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn connect_timeout_seconds(mut self, seconds: u64) -> Self {
+        self.http.connect_timeout = Some(std::time::Duration::from_secs(seconds));
+        self
+    }
+
+    /// Sets the longest wait between two reads of a response. Unlike
+    /// [`timeout_seconds`](Self::timeout_seconds) it leaves long streams
+    /// alone as long as they keep producing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_timeout_seconds(mut self, seconds: u64) -> Self {
+        self.http.read_timeout = Some(std::time::Duration::from_secs(seconds));
+        self
+    }
+
+    /// Sets how long unused connections stay open for reuse.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_idle_timeout_seconds(mut self, seconds: u64) -> Self {
+        self.http.pool_idle_timeout = Some(std::time::Duration::from_secs(seconds));
+        self
+    }
+
+    /// Sets the most unused connections kept open per host.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.http.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sends all requests through the HTTP(S) proxy at `url`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.http.proxy = Some(url.into());
+        self
+    }
+
+    /// Adds a header to every request, such as the attribution headers of
+    /// OpenRouter or the credentials of a corporate gateway.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.http.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Trusts the PEM-encoded root certificate `pem` in addition to the
+    /// system roots, e.g. the certificate authority of an internal endpoint.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.http.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Turns off verification of server certificates. Only meant for
+    /// internal endpoints with self-signed certificates, as anyone on the
+    /// network path can then read and alter the traffic.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.http.danger_accept_invalid_certs = accept;
+        self
+    }
+
     /// Sets the top-p (nucleus) sampling parameter.
     pub fn top_p(mut self, top_p: f32) -> Self {
         self.top_p = Some(top_p);
@@ -294,6 +479,41 @@ impl<L: LLMProvider> LLMBuilder<L> {
         self
     }
 
+    /// Return the log probability of every generated token along with the
+    /// `top` most likely alternatives at each position, up to 20. Supported
+    /// by OpenAI, Azure OpenAI and OpenRouter on non-streaming chat; other
+    /// backends ignore it.
+    pub fn logprobs(mut self, top: u8) -> Self {
+        self.top_logprobs = Some(top);
+        self
+    }
+
+    /// Sets sequences that end generation when the model produces them.
+    pub fn stop_sequences(mut self, stop_sequences: Vec<String>) -> Self {
+        self.generation.stop_sequences = stop_sequences;
+        self
+    }
+
+    /// Sets the sampling seed, making outputs repeatable where the backend
+    /// allows it.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.generation.seed = Some(seed);
+        self
+    }
+
+    /// Sets the penalty on tokens in proportion to how often they already
+    /// appear in the text.
+    pub fn frequency_penalty(mut self, penalty: f32) -> Self {
+        self.generation.frequency_penalty = Some(penalty);
+        self
+    }
+
+    /// Sets the penalty on tokens that already appear in the text at all.
+    pub fn presence_penalty(mut self, penalty: f32) -> Self {
+        self.generation.presence_penalty = Some(penalty);
+        self
+    }
+
     /// Set tool choice.  Note that if the choice is given as Tool(name), and that
     /// tool isn't available, the builder will fail.
     pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
@@ -514,6 +734,26 @@ mod tests {
             LLMBackend::from_str("azure-openai").unwrap(),
             LLMBackend::AzureOpenAI
         ));
+        assert!(matches!(
+            LLMBackend::from_str("mistral").unwrap(),
+            LLMBackend::Mistral
+        ));
+        assert!(matches!(
+            LLMBackend::from_str("bedrock").unwrap(),
+            LLMBackend::Bedrock
+        ));
+        assert!(matches!(
+            LLMBackend::from_str("cohere").unwrap(),
+            LLMBackend::Cohere
+        ));
+        assert!(matches!(
+            LLMBackend::from_str("llamacpp").unwrap(),
+            LLMBackend::LlamaCpp
+        ));
+        assert!(matches!(
+            LLMBackend::from_str("huggingface").unwrap(),
+            LLMBackend::HuggingFace
+        ));
 
         let result = LLMBackend::from_str("invalid");
         assert!(result.is_err());
@@ -822,6 +1062,19 @@ mod tests {
         assert_eq!(builder.validator_attempts, 3);
     }
 
+    #[test]
+    fn test_llm_builder_retry_policy() {
+        let builder = LLMBuilder::<MockLLMProvider>::new();
+        assert_eq!(builder.retry_policy.max_retries, 0);
+
+        let builder = builder.max_retries(4);
+        assert_eq!(builder.retry_policy.max_retries, 4);
+
+        let policy = crate::retry::RetryPolicy::new(2).with_jitter(false);
+        let builder = builder.retry_policy(policy.clone());
+        assert_eq!(builder.retry_policy, policy);
+    }
+
     #[test]
     fn test_llm_builder_enable_parallel_tool_use() {
         let builder = LLMBuilder::<MockLLMProvider>::new().enable_parallel_tool_use(true);
@@ -0,0 +1,851 @@
+//! Opt-in caching of LLM responses.
+//!
+//! [`CachedLLM`] wraps a provider and serves repeated requests from a
+//! [`CacheStore`] instead of calling the provider again, so repeated
+//! evaluation runs and deterministic tests only pay for a completion once.
+//!
+//! Entries are keyed by the full request: messages, tools, structured output
+//! schema and a namespace (usually the model name, see
+//! [`CachedLLM::with_namespace`]). Chat, completion and embedding requests
+//! are cached; streaming requests always go to the provider.
+//!
+//! Two stores are included, [`InMemoryCacheStore`] and [`DiskCacheStore`].
+//! Other backends such as Redis plug in by implementing [`CacheStore`], which
+//! only needs string get and set.
+//!
+//! With [`CachedLLM::with_semantic_matching`] a request that misses the exact
+//! key is matched against earlier requests by embedding similarity, so a
+//! rephrased prompt can reuse an answer. The similarity index lives in memory
+//! and only covers requests made through the same `CachedLLM`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    audio::{SpeechToTextProvider, TextToSpeechProvider},
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, Citation, ReasoningBlock, StreamResponse,
+        StructuredOutputFormat, TokenLogprob, Tool, Usage,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    tokenizer::TokenCounter,
+    LLMProvider, ToolCall,
+};
+
+/// Key-value storage for cached responses.
+///
+/// Keys are short hex digests and values are JSON documents, so any string
+/// store (Redis, a database table, object storage) can back the cache.
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>, LLMError>;
+    async fn set(&self, key: &str, value: String) -> Result<(), LLMError>;
+}
+
+/// A [`CacheStore`] that keeps entries in process memory.
+#[derive(Debug, Default)]
+pub struct InMemoryCacheStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl InMemoryCacheStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached entries.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl CacheStore for InMemoryCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, LLMError> {
+        Ok(self.entries().get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: String) -> Result<(), LLMError> {
+        self.entries().insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// A [`CacheStore`] that writes one JSON file per entry into a directory,
+/// so the cache survives across runs and can be checked into test fixtures.
+#[derive(Debug, Clone)]
+pub struct DiskCacheStore {
+    dir: PathBuf,
+}
+
+impl DiskCacheStore {
+    /// Uses `dir` for the cache files; it is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+#[async_trait]
+impl CacheStore for DiskCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, LLMError> {
+        match tokio::fs::read_to_string(self.path(key)).await {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(LLMError::Generic(format!(
+                "Failed to read cache entry {key}: {err}"
+            ))),
+        }
+    }
+
+    async fn set(&self, key: &str, value: String) -> Result<(), LLMError> {
+        let write = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            // Write then rename so readers never see a partial entry
+            let tmp = self.dir.join(format!("{key}.json.tmp"));
+            tokio::fs::write(&tmp, value).await?;
+            tokio::fs::rename(&tmp, self.path(key)).await
+        };
+        write
+            .await
+            .map_err(|err| LLMError::Generic(format!("Failed to write cache entry {key}: {err}")))
+    }
+}
+
+/// A response as stored in the cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum CachedResponse {
+    Chat(CachedChatResponse),
+    Completion { text: String },
+    Embeddings { vectors: Vec<Vec<f32>> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// The full request, compared on lookup to rule out digest collisions
+    request: Value,
+    response: CachedResponse,
+}
+
+/// A chat response served from the cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedChatResponse {
+    pub text: Option<String>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    pub thinking: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasoning: Vec<ReasoningBlock>,
+    pub usage: Option<Usage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub citations: Option<Vec<Citation>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Vec<TokenLogprob>>,
+}
+
+impl ChatResponse for CachedChatResponse {
+    fn text(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        self.tool_calls.clone()
+    }
+
+    fn thinking(&self) -> Option<String> {
+        self.thinking.clone()
+    }
+
+    fn reasoning(&self) -> Vec<ReasoningBlock> {
+        self.reasoning.clone()
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
+
+    fn citations(&self) -> Option<Vec<Citation>> {
+        self.citations.clone()
+    }
+
+    fn logprobs(&self) -> Option<Vec<TokenLogprob>> {
+        self.logprobs.clone()
+    }
+}
+
+impl std::fmt::Display for CachedChatResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.text, &self.tool_calls) {
+            (Some(text), _) => write!(f, "{text}"),
+            (None, Some(calls)) => {
+                for call in calls {
+                    write!(f, "{call}")?;
+                }
+                Ok(())
+            }
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Cache hit and miss counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Requests answered with the exact same request's cached response.
+    pub hits: u64,
+    /// Requests answered with a similar request's cached response.
+    pub semantic_hits: u64,
+    /// Requests forwarded to the provider.
+    pub misses: u64,
+}
+
+struct SemanticIndexEntry {
+    /// Digest of the parts of the request that must match exactly
+    context: String,
+    embedding: Vec<f32>,
+    key: String,
+}
+
+struct SemanticMatcher {
+    embedder: Arc<dyn LLMProvider>,
+    threshold: f32,
+    index: Mutex<Vec<SemanticIndexEntry>>,
+}
+
+impl SemanticMatcher {
+    fn index(&self) -> std::sync::MutexGuard<'_, Vec<SemanticIndexEntry>> {
+        self.index
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn best_match(&self, context: &str, embedding: &[f32]) -> Option<String> {
+        self.index()
+            .iter()
+            .filter(|entry| entry.context == context)
+            .map(|entry| (cosine_similarity(&entry.embedding, embedding), entry))
+            .filter(|(similarity, _)| *similarity >= self.threshold)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, entry)| entry.key.clone())
+    }
+}
+
+/// A request broken down for cache lookups.
+struct CacheRequest {
+    /// Everything that identifies the request
+    request: Value,
+    /// Text compared by embedding in semantic mode
+    text: String,
+    /// Everything besides `text` that a semantic match must share
+    context: Value,
+}
+
+/// An [`LLMProvider`] that caches responses of the provider it wraps.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use autoagents_llm::{
+///     cache::{CachedLLM, DiskCacheStore},
+///     LLMProvider,
+/// };
+///
+/// fn cached(llm: Arc<dyn LLMProvider>) -> CachedLLM {
+///     CachedLLM::new(llm, Arc::new(DiskCacheStore::new(".llm-cache")))
+///         .with_namespace("gpt-4o-mini")
+/// }
+/// ```
+///
+/// Failing to read or write the store never fails a request; the provider
+/// is called instead and the error is logged.
+pub struct CachedLLM {
+    provider: Arc<dyn LLMProvider>,
+    store: Arc<dyn CacheStore>,
+    namespace: String,
+    semantic: Option<SemanticMatcher>,
+    hits: AtomicU64,
+    semantic_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedLLM {
+    pub fn new(provider: Arc<dyn LLMProvider>, store: Arc<dyn CacheStore>) -> Self {
+        Self {
+            provider,
+            store,
+            namespace: String::new(),
+            semantic: None,
+            hits: AtomicU64::new(0),
+            semantic_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Scopes cache keys, typically to the provider and model, so a store
+    /// shared between providers never mixes up their responses.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Also reuses the response of an earlier request whose text embedding,
+    /// computed with `embedder`, has a cosine similarity of at least
+    /// `threshold` with the new request. Tools, schema and namespace must
+    /// still match exactly.
+    pub fn with_semantic_matching(
+        mut self,
+        embedder: Arc<dyn LLMProvider>,
+        threshold: f32,
+    ) -> Self {
+        self.semantic = Some(SemanticMatcher {
+            embedder,
+            threshold,
+            index: Mutex::new(Vec::new()),
+        });
+        self
+    }
+
+    /// Returns the hit and miss counters.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            semantic_hits: self.semantic_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn chat_request(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<&StructuredOutputFormat>,
+    ) -> CacheRequest {
+        let context = json!({
+            "namespace": self.namespace,
+            "kind": "chat",
+            "tools": tools,
+            "json_schema": json_schema,
+        });
+        let text = messages
+            .iter()
+            .map(|message| format!("{}: {}", message.role, message.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        CacheRequest {
+            request: json!({ "context": context, "messages": messages }),
+            text,
+            context,
+        }
+    }
+
+    fn completion_request(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<&StructuredOutputFormat>,
+    ) -> CacheRequest {
+        let context = json!({
+            "namespace": self.namespace,
+            "kind": "completion",
+            "max_tokens": req.max_tokens,
+            "temperature": req.temperature,
+            "json_schema": json_schema,
+        });
+        CacheRequest {
+            request: json!({ "context": context, "prompt": req.prompt }),
+            text: req.prompt.clone(),
+            context,
+        }
+    }
+
+    /// Looks the request up by exact key, then by similarity. Returns the
+    /// cached response, or the embedding to index the response under once
+    /// it has been fetched.
+    async fn lookup(&self, request: &CacheRequest) -> Result<CachedResponse, Option<Vec<f32>>> {
+        let key = fingerprint(&request.request);
+        if let Some(response) = self.read(&key, Some(&request.request)).await {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(response);
+        }
+
+        let Some(semantic) = &self.semantic else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return Err(None);
+        };
+        let embedding = match semantic.embedder.embed(vec![request.text.clone()]).await {
+            Ok(mut embeddings) if !embeddings.is_empty() => embeddings.swap_remove(0),
+            Ok(_) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return Err(None);
+            }
+            Err(err) => {
+                log::warn!("Failed to embed request for semantic cache lookup: {err}");
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return Err(None);
+            }
+        };
+        let context = fingerprint(&request.context);
+        if let Some(similar) = semantic.best_match(&context, &embedding) {
+            if let Some(response) = self.read(&similar, None).await {
+                self.semantic_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(response);
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        Err(Some(embedding))
+    }
+
+    async fn read(&self, key: &str, expected: Option<&Value>) -> Option<CachedResponse> {
+        let value = match self.store.get(key).await {
+            Ok(value) => value?,
+            Err(err) => {
+                log::warn!("Failed to read LLM cache: {err}");
+                return None;
+            }
+        };
+        let entry: CacheEntry = serde_json::from_str(&value)
+            .map_err(|err| log::warn!("Ignoring malformed LLM cache entry {key}: {err}"))
+            .ok()?;
+        match expected {
+            Some(expected) if *expected != entry.request => None,
+            _ => Some(entry.response),
+        }
+    }
+
+    async fn write(
+        &self,
+        request: CacheRequest,
+        response: CachedResponse,
+        embedding: Option<Vec<f32>>,
+    ) {
+        let key = fingerprint(&request.request);
+        let entry = CacheEntry {
+            request: request.request,
+            response,
+        };
+        let value = match serde_json::to_string(&entry) {
+            Ok(value) => value,
+            Err(err) => {
+                log::warn!("Failed to serialize LLM cache entry: {err}");
+                return;
+            }
+        };
+        if let Err(err) = self.store.set(&key, value).await {
+            log::warn!("Failed to write LLM cache: {err}");
+            return;
+        }
+        if let (Some(semantic), Some(embedding)) = (&self.semantic, embedding) {
+            semantic.index().push(SemanticIndexEntry {
+                context: fingerprint(&request.context),
+                embedding,
+                key,
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for CachedLLM {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let request = self.chat_request(messages, tools, json_schema.as_ref());
+        let embedding = match self.lookup(&request).await {
+            Ok(CachedResponse::Chat(response)) => return Ok(Box::new(response)),
+            Ok(_) => None,
+            Err(embedding) => embedding,
+        };
+
+        let response = self.provider.chat(messages, tools, json_schema).await?;
+        let cached = CachedChatResponse {
+            text: response.text(),
+            tool_calls: response.tool_calls(),
+            thinking: response.thinking(),
+            reasoning: response.reasoning(),
+            usage: response.usage(),
+            citations: response.citations(),
+            logprobs: response.logprobs(),
+        };
+        self.write(request, CachedResponse::Chat(cached), embedding)
+            .await;
+        Ok(response)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.provider
+            .chat_stream(messages, tools, json_schema)
+            .await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.provider
+            .chat_stream_struct(messages, tools, json_schema)
+            .await
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.provider.supports_structured_output()
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for CachedLLM {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let request = self.completion_request(req, json_schema.as_ref());
+        let embedding = match self.lookup(&request).await {
+            Ok(CachedResponse::Completion { text }) => {
+                return Ok(CompletionResponse { text, usage: None })
+            }
+            Ok(_) => None,
+            Err(embedding) => embedding,
+        };
+
+        let response = self.provider.complete(req, json_schema).await?;
+        let cached = CachedResponse::Completion {
+            text: response.text.clone(),
+        };
+        self.write(request, cached, embedding).await;
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CachedLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        // Embeddings are only ever matched exactly
+        let request = json!({
+            "context": { "namespace": self.namespace, "kind": "embeddings" },
+            "input": input,
+        });
+        let key = fingerprint(&request);
+        if let Some(CachedResponse::Embeddings { vectors }) = self.read(&key, Some(&request)).await
+        {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(vectors);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let vectors = self.provider.embed(input).await?;
+        let request = CacheRequest {
+            request,
+            text: String::new(),
+            context: Value::Null,
+        };
+        let cached = CachedResponse::Embeddings {
+            vectors: vectors.clone(),
+        };
+        self.write(request, cached, None).await;
+        Ok(vectors)
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.provider.embedding_dimensions()
+    }
+
+    fn max_embedding_batch(&self) -> Option<usize> {
+        self.provider.max_embedding_batch()
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for CachedLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.provider.list_models(request).await
+    }
+}
+
+impl LLMProvider for CachedLLM {
+    fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        self.provider.token_counter()
+    }
+
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        self.provider.speech_to_text()
+    }
+
+    fn text_to_speech(&self) -> Option<&dyn TextToSpeechProvider> {
+        self.provider.text_to_speech()
+    }
+}
+
+/// Stable 64-bit FNV-1a digest of a JSON value, as 16 hex characters.
+///
+/// Unlike the standard library hashers the result does not change between
+/// runs or Rust versions, which the disk store relies on.
+fn fingerprint(value: &Value) -> String {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let hash = value.to_string().bytes().fold(OFFSET, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+    format!("{hash:016x}")
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    impl CountingProvider {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl ChatProvider for CountingProvider {
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            _tools: Option<&[Tool]>,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<Box<dyn ChatResponse>, LLMError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(CachedChatResponse {
+                text: Some(format!("answer {call} to {}", messages[0].content)),
+                tool_calls: None,
+                thinking: None,
+                reasoning: Vec::new(),
+                usage: None,
+                citations: Some(vec![Citation {
+                    start: 0,
+                    end: 6,
+                    text: "answer".into(),
+                    sources: vec![format!("doc-{call}")],
+                }]),
+                logprobs: Some(vec![TokenLogprob {
+                    token: "answer".into(),
+                    logprob: -(call as f32) - 0.5,
+                    top_logprobs: vec![],
+                }]),
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl CompletionProvider for CountingProvider {
+        async fn complete(
+            &self,
+            req: &CompletionRequest,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<CompletionResponse, LLMError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(CompletionResponse {
+                text: format!("{} world", req.prompt),
+                usage: None,
+            })
+        }
+    }
+
+    /// Embeds text as letter counts of "a", "b" and "c", which makes the
+    /// similarity of test prompts easy to reason about
+    #[async_trait]
+    impl EmbeddingProvider for CountingProvider {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    ['a', 'b', 'c']
+                        .iter()
+                        .map(|letter| text.matches(*letter).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[async_trait]
+    impl ModelsProvider for CountingProvider {}
+
+    impl LLMProvider for CountingProvider {}
+
+    fn user(content: &str) -> Vec<ChatMessage> {
+        vec![ChatMessage::user().content(content).build()]
+    }
+
+    #[tokio::test]
+    async fn test_identical_chat_requests_are_served_from_cache() {
+        let provider = CountingProvider::new();
+        let llm = CachedLLM::new(provider.clone(), Arc::new(InMemoryCacheStore::new()));
+
+        let first = llm.chat(&user("hello"), None, None).await.unwrap();
+        let second = llm.chat(&user("hello"), None, None).await.unwrap();
+        assert_eq!(first.text(), second.text());
+        assert_eq!(first.citations(), second.citations());
+        assert_eq!(first.logprobs(), second.logprobs());
+        assert_eq!(provider.calls(), 1);
+
+        llm.chat(&user("goodbye"), None, None).await.unwrap();
+        assert_eq!(provider.calls(), 2);
+        assert_eq!(
+            llm.stats(),
+            CacheStats {
+                hits: 1,
+                semantic_hits: 0,
+                misses: 2
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_do_not_share_entries() {
+        let provider = CountingProvider::new();
+        let store: Arc<dyn CacheStore> = Arc::new(InMemoryCacheStore::new());
+        let a = CachedLLM::new(provider.clone(), store.clone()).with_namespace("model-a");
+        let b = CachedLLM::new(provider.clone(), store).with_namespace("model-b");
+
+        a.complete(&CompletionRequest::new("hello"), None)
+            .await
+            .unwrap();
+        b.complete(&CompletionRequest::new("hello"), None)
+            .await
+            .unwrap();
+        assert_eq!(provider.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disk_store_persists_across_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = CountingProvider::new();
+
+        let llm = CachedLLM::new(provider.clone(), Arc::new(DiskCacheStore::new(dir.path())));
+        let first = llm.chat(&user("hello"), None, None).await.unwrap();
+
+        let llm = CachedLLM::new(provider.clone(), Arc::new(DiskCacheStore::new(dir.path())));
+        let second = llm.chat(&user("hello"), None, None).await.unwrap();
+
+        assert_eq!(first.text(), second.text());
+        assert_eq!(provider.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_entry_for_other_request_is_ignored() {
+        let provider = CountingProvider::new();
+        let store = Arc::new(InMemoryCacheStore::new());
+        let llm = CachedLLM::new(provider.clone(), store.clone());
+        let request = llm.chat_request(&user("hello"), None, None);
+
+        // Simulate a digest collision with another request's entry
+        let entry = CacheEntry {
+            request: json!("something else"),
+            response: CachedResponse::Completion {
+                text: "wrong".to_string(),
+            },
+        };
+        store
+            .set(
+                &fingerprint(&request.request),
+                serde_json::to_string(&entry).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let response = llm.chat(&user("hello"), None, None).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("answer 0 to hello"));
+    }
+
+    #[tokio::test]
+    async fn test_semantic_matching_reuses_similar_requests() {
+        let provider = CountingProvider::new();
+        let llm = CachedLLM::new(provider.clone(), Arc::new(InMemoryCacheStore::new()))
+            .with_semantic_matching(provider.clone(), 0.99);
+
+        llm.chat(&user("aab"), None, None).await.unwrap();
+        let similar = llm.chat(&user("aaaabb"), None, None).await.unwrap();
+        assert_eq!(similar.text().as_deref(), Some("answer 0 to aab"));
+        assert_eq!(provider.calls(), 1);
+
+        llm.chat(&user("ccc"), None, None).await.unwrap();
+        assert_eq!(provider.calls(), 2);
+        assert_eq!(llm.stats().semantic_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_are_cached() {
+        let provider = CountingProvider::new();
+        let llm = CachedLLM::new(provider.clone(), Arc::new(InMemoryCacheStore::new()));
+
+        let first = llm.embed(vec!["abc".to_string()]).await.unwrap();
+        let second = llm.embed(vec!["abc".to_string()]).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(llm.stats().hits, 1);
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        assert_eq!(fingerprint(&Value::Null), "5b9bc4ba528108e4");
+        assert_ne!(fingerprint(&json!({"a": 1})), fingerprint(&json!({"a": 2})));
+    }
+}
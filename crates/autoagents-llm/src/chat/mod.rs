@@ -3,11 +3,15 @@ use std::fmt;
 
 use crate::{error::LLMError, ToolCall};
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use futures::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use strum_macros::Display;
 
+mod partial_json;
+pub use partial_json::{parse_partial_json, PartialJson};
+
 /// Role of a participant in a chat conversation.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Display)]
 pub enum ChatRole {
@@ -44,6 +48,18 @@ impl ImageMime {
             ImageMime::WEBP => "image/webp",
         }
     }
+
+    /// Parse a MIME type string such as `image/png`, ignoring any parameters
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        let essence = mime_type.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "image/jpeg" | "image/jpg" => Some(ImageMime::JPEG),
+            "image/png" => Some(ImageMime::PNG),
+            "image/gif" => Some(ImageMime::GIF),
+            "image/webp" => Some(ImageMime::WEBP),
+            _ => None,
+        }
+    }
 }
 
 /// The type of a message in a chat conversation.
@@ -58,12 +74,71 @@ pub enum MessageType {
     Pdf(Vec<u8>),
     /// An image URL message
     ImageURL(String),
+    /// A message made of text and image parts, sent in order after the
+    /// message content when that is not empty
+    Parts(Vec<ContentPart>),
     /// A tool use
     ToolUse(Vec<ToolCall>),
     /// Tool result
     ToolResult(Vec<ToolCall>),
 }
 
+/// One part of a multimodal message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ContentPart {
+    /// A block of text
+    Text(String),
+    /// Image bytes, sent base64 encoded
+    Image { mime: ImageMime, data: Vec<u8> },
+    /// An image the provider downloads, with its MIME type when known
+    ImageUrl {
+        url: String,
+        mime: Option<ImageMime>,
+    },
+}
+
+impl ContentPart {
+    /// A text part
+    pub fn text(text: impl Into<String>) -> Self {
+        ContentPart::Text(text.into())
+    }
+
+    /// An image part holding the raw image bytes
+    pub fn image(mime: ImageMime, data: Vec<u8>) -> Self {
+        ContentPart::Image { mime, data }
+    }
+
+    /// An image part pointing at a URL
+    pub fn image_url(url: impl Into<String>) -> Self {
+        ContentPart::ImageUrl {
+            url: url.into(),
+            mime: None,
+        }
+    }
+
+    /// The text of a text part
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            ContentPart::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The image as a URL: the URL of a linked image, or a base64 `data:`
+    /// URL for image bytes. `None` for text parts.
+    pub fn to_image_url(&self) -> Option<String> {
+        match self {
+            ContentPart::Text(_) => None,
+            ContentPart::Image { mime, data } => Some(format!(
+                "data:{};base64,{}",
+                mime.mime_type(),
+                BASE64.encode(data)
+            )),
+            ContentPart::ImageUrl { url, .. } => Some(url.clone()),
+        }
+    }
+}
+
 /// The type of reasoning effort for a message in a chat conversation.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ReasoningEffort {
@@ -75,6 +150,18 @@ pub enum ReasoningEffort {
     High,
 }
 
+/// A block of reasoning the model did before an assistant message.
+///
+/// Providers such as Anthropic with extended thinking check the reasoning
+/// that led to a tool call, so it is sent back with the message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReasoningBlock {
+    /// Reasoning in plain text, with the signature the provider verifies
+    Thinking { thinking: String, signature: String },
+    /// Reasoning the provider returned encrypted
+    Redacted { data: String },
+}
+
 /// A single message in a chat conversation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -84,6 +171,10 @@ pub struct ChatMessage {
     pub message_type: MessageType,
     /// The text content of the message
     pub content: String,
+    /// Reasoning the model did before an assistant message, sent back to
+    /// providers that need it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasoning: Vec<ReasoningBlock>,
 }
 
 /// Represents a parameter in a function tool
@@ -243,7 +334,7 @@ impl Serialize for ToolChoice {
 }
 
 /// Breakdown of completion tokens.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CompletionTokensDetails {
     /// Tokens used for reasoning (for reasoning models)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -254,7 +345,7 @@ pub struct CompletionTokensDetails {
 }
 
 /// Breakdown of prompt tokens.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PromptTokensDetails {
     /// Tokens used for cached content
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -265,7 +356,12 @@ pub struct PromptTokensDetails {
 }
 
 /// Usage metadata for a chat response.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Backends normalize their provider's usage block into this shape. Prompt
+/// tokens always include tokens served from the provider's prompt cache,
+/// which are broken out in `prompt_tokens_details` and returned by
+/// [`Usage::cached_tokens`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Usage {
     /// Number of tokens in the prompt
     pub prompt_tokens: u32,
@@ -279,6 +375,235 @@ pub struct Usage {
     /// Breakdown of prompt tokens, if available
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prompt_tokens_details: Option<PromptTokensDetails>,
+    /// Cost of the request in US dollars, when the provider reports it or a
+    /// [`TokenPricing`] has been applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+impl Usage {
+    /// Usage of a request with the given prompt and completion tokens.
+    pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens.saturating_add(completion_tokens),
+            ..Self::default()
+        }
+    }
+
+    /// Records how many of the prompt tokens were read from the cache.
+    pub fn with_cached_tokens(mut self, cached_tokens: u32) -> Self {
+        self.prompt_tokens_details
+            .get_or_insert_with(PromptTokensDetails::default)
+            .cached_tokens = Some(cached_tokens);
+        self
+    }
+
+    /// Prompt tokens read from the provider's prompt cache.
+    pub fn cached_tokens(&self) -> u32 {
+        self.prompt_tokens_details
+            .as_ref()
+            .and_then(|details| details.cached_tokens)
+            .unwrap_or(0)
+    }
+
+    /// Sets the cost from `pricing`, unless the provider already reported it.
+    pub fn priced(mut self, pricing: &TokenPricing) -> Self {
+        if self.cost.is_none() {
+            self.cost = Some(pricing.cost(&self));
+        }
+        self
+    }
+}
+
+fn add_counts(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.saturating_add(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    /// Accumulates the usage of several requests, e.g. the turns of an agent
+    /// run.
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens = self.prompt_tokens.saturating_add(other.prompt_tokens);
+        self.completion_tokens = self
+            .completion_tokens
+            .saturating_add(other.completion_tokens);
+        self.total_tokens = self.total_tokens.saturating_add(other.total_tokens);
+        self.completion_tokens_details = match (
+            self.completion_tokens_details.take(),
+            other.completion_tokens_details,
+        ) {
+            (Some(a), Some(b)) => Some(CompletionTokensDetails {
+                reasoning_tokens: add_counts(a.reasoning_tokens, b.reasoning_tokens),
+                audio_tokens: add_counts(a.audio_tokens, b.audio_tokens),
+            }),
+            (a, b) => a.or(b),
+        };
+        self.prompt_tokens_details = match (
+            self.prompt_tokens_details.take(),
+            other.prompt_tokens_details,
+        ) {
+            (Some(a), Some(b)) => Some(PromptTokensDetails {
+                cached_tokens: add_counts(a.cached_tokens, b.cached_tokens),
+                audio_tokens: add_counts(a.audio_tokens, b.audio_tokens),
+            }),
+            (a, b) => a.or(b),
+        };
+        self.cost = match (self.cost, other.cost) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+impl std::ops::Add for Usage {
+    type Output = Usage;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+/// Prices of a model in US dollars per million tokens, used to fill in
+/// [`Usage::cost`] for providers that do not report it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenPricing {
+    /// Price of a million prompt tokens
+    pub input: f64,
+    /// Price of a million completion tokens
+    pub output: f64,
+    /// Price of a million prompt tokens read from the cache; the input price
+    /// applies when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input: Option<f64>,
+}
+
+impl TokenPricing {
+    pub fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cached_input: None,
+        }
+    }
+
+    pub fn with_cached_input(mut self, cached_input: f64) -> Self {
+        self.cached_input = Some(cached_input);
+        self
+    }
+
+    /// Cost in US dollars of a request with the given usage.
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let cached = usage.cached_tokens().min(usage.prompt_tokens);
+        let uncached = usage.prompt_tokens - cached;
+        let cached_price = self.cached_input.unwrap_or(self.input);
+        (uncached as f64 * self.input
+            + cached as f64 * cached_price
+            + usage.completion_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// A span of a response grounded in the documents or tool results it was
+/// generated from, as returned by providers with retrieval-augmented generation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Citation {
+    /// Start of the cited span, as a character offset into the response text
+    pub start: usize,
+    /// End of the cited span, exclusive
+    pub end: usize,
+    /// The cited text
+    pub text: String,
+    /// Ids of the documents or tool calls supporting the span
+    pub sources: Vec<String>,
+}
+
+/// Log probability of one generated token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenLogprob {
+    pub token: String,
+    /// Natural logarithm of the probability the model gave the token
+    pub logprob: f32,
+    /// The most likely tokens at this position, most likely first, when
+    /// alternatives were asked for
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// A candidate token at a position of the response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f32,
+}
+
+impl TokenLogprob {
+    pub fn probability(&self) -> f32 {
+        self.logprob.exp()
+    }
+
+    /// Geometric mean of the token probabilities of a response, between 0
+    /// and 1, a confidence score that does not shrink with the length of the
+    /// response. `None` for an empty response.
+    pub fn confidence(tokens: &[TokenLogprob]) -> Option<f32> {
+        if tokens.is_empty() {
+            return None;
+        }
+        let total: f32 = tokens.iter().map(|token| token.logprob).sum();
+        Some((total / tokens.len() as f32).exp())
+    }
+}
+
+/// Generation settings beyond temperature and top-p, set through
+/// [`LLMBuilder`](crate::builder::LLMBuilder).
+///
+/// Serializes to the OpenAI request fields, leaving out what is unset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GenerationOptions {
+    /// Sequences that end generation when produced
+    #[serde(rename = "stop", skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    /// Seed for sampling, for repeatable outputs where the backend allows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+}
+
+impl GenerationOptions {
+    /// Fail on the first set setting named in `unsupported`, rather than
+    /// send a request that silently ignores it.
+    ///
+    /// Names are those of the fields, such as `"seed"`.
+    #[allow(dead_code)]
+    pub(crate) fn reject_unsupported(
+        &self,
+        backend: &str,
+        unsupported: &[&str],
+    ) -> Result<(), LLMError> {
+        let set = [
+            ("stop_sequences", !self.stop_sequences.is_empty()),
+            ("seed", self.seed.is_some()),
+            ("frequency_penalty", self.frequency_penalty.is_some()),
+            ("presence_penalty", self.presence_penalty.is_some()),
+        ];
+        match set
+            .iter()
+            .find(|(name, is_set)| *is_set && unsupported.contains(name))
+        {
+            Some((name, _)) => Err(LLMError::InvalidRequest(format!(
+                "{backend} does not support {name}"
+            ))),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Stream response chunk that mimics OpenAI's streaming response format
@@ -306,10 +631,92 @@ pub struct StreamToolCallFunction {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamToolCallDelta {
+    /// Position of the tool call in the response; deltas with the same
+    /// index belong to the same call
     pub index: usize,
+    /// Id the provider assigned to the call, usually sent with its first
+    /// delta
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub function: Option<StreamToolCallFunction>,
 }
 
+/// Assembles the tool calls of a streamed response from their deltas.
+///
+/// Providers stream the calls of a response one after the other, so a call
+/// is complete once a delta for a later index arrives or the stream ends.
+/// [`push`](Self::push) returns the calls completed so far, letting callers
+/// dispatch them while the rest of the response is still streaming.
+///
+/// ```
+/// use autoagents_llm::chat::{StreamToolCallDelta, StreamToolCallFunction, ToolCallAccumulator};
+///
+/// let delta = |index: usize, name: &str, arguments: &str| StreamToolCallDelta {
+///     index,
+///     id: (!name.is_empty()).then(|| format!("call_{index}")),
+///     function: Some(StreamToolCallFunction {
+///         name: name.to_string(),
+///         arguments: arguments.to_string(),
+///     }),
+/// };
+///
+/// let mut calls = ToolCallAccumulator::default();
+/// assert!(calls.push(&[delta(0, "weather", "{\"city\":")]).is_empty());
+/// assert!(calls.push(&[delta(0, "", "\"Paris\"}")]).is_empty());
+/// let completed = calls.push(&[delta(1, "time", "{}")]);
+/// assert_eq!(completed[0].function.arguments, "{\"city\":\"Paris\"}");
+/// assert_eq!(calls.finish()[0].function.name, "time");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ToolCallAccumulator {
+    /// Calls still receiving deltas, by index
+    pending: std::collections::BTreeMap<usize, ToolCall>,
+}
+
+impl ToolCallAccumulator {
+    /// Adds the deltas of a chunk and returns the calls they complete.
+    pub fn push(&mut self, deltas: &[StreamToolCallDelta]) -> Vec<ToolCall> {
+        let mut completed = Vec::new();
+        for delta in deltas {
+            let later = self.pending.split_off(&delta.index);
+            completed.extend(std::mem::replace(&mut self.pending, later).into_values());
+
+            let call = self.pending.entry(delta.index).or_insert_with(|| ToolCall {
+                id: String::new(),
+                call_type: crate::default_call_type(),
+                function: crate::FunctionCall {
+                    name: String::new(),
+                    arguments: String::new(),
+                },
+            });
+            if let Some(id) = delta.id.as_ref().filter(|id| !id.is_empty()) {
+                call.id = id.clone();
+            }
+            if let Some(function) = &delta.function {
+                if !function.name.is_empty() {
+                    call.function.name = function.name.clone();
+                }
+                call.function.arguments.push_str(&function.arguments);
+            }
+        }
+        completed.retain(|call| !call.function.name.is_empty());
+        completed
+    }
+
+    /// Whether no call is waiting for more deltas.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Ends the stream and returns the calls not yet completed.
+    pub fn finish(&mut self) -> Vec<ToolCall> {
+        std::mem::take(&mut self.pending)
+            .into_values()
+            .filter(|call| !call.function.name.is_empty())
+            .collect()
+    }
+}
+
 /// Delta content in a streaming response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamDelta {
@@ -318,6 +725,14 @@ pub struct StreamDelta {
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<StreamToolCallDelta>>,
+    /// Incremental reasoning the model does before it answers, kept apart
+    /// from the content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+    /// A reasoning block, once it is complete, to send back with the
+    /// assistant message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<Box<ReasoningBlock>>,
 }
 
 pub trait ChatResponse: std::fmt::Debug + std::fmt::Display + Send + Sync {
@@ -327,9 +742,27 @@ pub trait ChatResponse: std::fmt::Debug + std::fmt::Display + Send + Sync {
         None
     }
 
+    /// The reasoning blocks of the response, for providers that need them
+    /// sent back with the assistant message
+    fn reasoning(&self) -> Vec<ReasoningBlock> {
+        Vec::new()
+    }
+
     fn usage(&self) -> Option<Usage> {
         None
     }
+
+    /// Sources the response text cites, for providers that ground their
+    /// answers in documents
+    fn citations(&self) -> Option<Vec<Citation>> {
+        None
+    }
+
+    /// Log probability of each generated token, for requests that asked for
+    /// them with [`LLMBuilder::logprobs`](crate::builder::LLMBuilder::logprobs)
+    fn logprobs(&self) -> Option<Vec<TokenLogprob>> {
+        None
+    }
 }
 
 /// Trait for providers that support chat-style interactions.
@@ -400,6 +833,14 @@ pub trait ChatProvider: Sync + Send {
             "Structured streaming not supported for this provider".to_string(),
         ))
     }
+
+    /// Whether the provider makes responses follow the schema of a
+    /// `json_schema` passed to [`chat`](Self::chat) itself. When it does not,
+    /// the schema is ignored and callers have to ask for the format in the
+    /// prompt.
+    fn supports_structured_output(&self) -> bool {
+        false
+    }
 }
 
 impl fmt::Display for ReasoningEffort {
@@ -430,6 +871,7 @@ pub struct ChatMessageBuilder {
     role: ChatRole,
     message_type: MessageType,
     content: String,
+    reasoning: Vec<ReasoningBlock>,
 }
 
 impl ChatMessageBuilder {
@@ -439,6 +881,7 @@ impl ChatMessageBuilder {
             role,
             message_type: MessageType::default(),
             content: String::new(),
+            reasoning: Vec::new(),
         }
     }
 
@@ -466,6 +909,21 @@ impl ChatMessageBuilder {
         self
     }
 
+    /// Add a text or image part, making this a [`MessageType::Parts`] message
+    pub fn part(mut self, part: ContentPart) -> Self {
+        match &mut self.message_type {
+            MessageType::Parts(parts) => parts.push(part),
+            message_type => *message_type = MessageType::Parts(vec![part]),
+        }
+        self
+    }
+
+    /// Set the message type as Parts
+    pub fn parts(mut self, parts: Vec<ContentPart>) -> Self {
+        self.message_type = MessageType::Parts(parts);
+        self
+    }
+
     /// Set the message type as ToolUse
     pub fn tool_use(mut self, tools: Vec<ToolCall>) -> Self {
         self.message_type = MessageType::ToolUse(tools);
@@ -478,12 +936,19 @@ impl ChatMessageBuilder {
         self
     }
 
+    /// Set the reasoning that led to the message
+    pub fn reasoning(mut self, reasoning: Vec<ReasoningBlock>) -> Self {
+        self.reasoning = reasoning;
+        self
+    }
+
     /// Build the ChatMessage
     pub fn build(self) -> ChatMessage {
         ChatMessage {
             role: self.role,
             message_type: self.message_type,
             content: self.content,
+            reasoning: self.reasoning,
         }
     }
 }
@@ -535,9 +1000,9 @@ pub mod utils {
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await?;
-            return Err(LLMError::ResponseFormatError {
-                message: format!("API returned error status: {status}"),
-                raw_response: error_text,
+            return Err(LLMError::HttpStatus {
+                status: status.as_u16(),
+                message: error_text,
             });
         }
         Ok(response)
@@ -591,6 +1056,20 @@ mod tests {
         assert_eq!(ImageMime::WEBP.mime_type(), "image/webp");
     }
 
+    #[test]
+    fn test_image_mime_from_mime_type() {
+        assert_eq!(ImageMime::from_mime_type("image/png"), Some(ImageMime::PNG));
+        assert_eq!(
+            ImageMime::from_mime_type("IMAGE/JPG"),
+            Some(ImageMime::JPEG)
+        );
+        assert_eq!(
+            ImageMime::from_mime_type("image/webp; q=0.9"),
+            Some(ImageMime::WEBP)
+        );
+        assert_eq!(ImageMime::from_mime_type("text/plain"), None);
+    }
+
     #[test]
     fn test_message_type_default() {
         let default_type = MessageType::default();
@@ -678,6 +1157,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_chat_message_builder_parts() {
+        let message = ChatMessage::user()
+            .part(ContentPart::text("Compare"))
+            .part(ContentPart::image(ImageMime::GIF, vec![0, 1]))
+            .build();
+
+        assert_eq!(
+            message.message_type,
+            MessageType::Parts(vec![
+                ContentPart::Text("Compare".to_string()),
+                ContentPart::Image {
+                    mime: ImageMime::GIF,
+                    data: vec![0, 1],
+                },
+            ])
+        );
+        assert_eq!(
+            ContentPart::image(ImageMime::GIF, vec![0, 1]).to_image_url(),
+            Some("data:image/gif;base64,AAE=".to_string())
+        );
+        assert_eq!(ContentPart::text("Compare").to_image_url(), None);
+    }
+
     #[test]
     fn test_chat_message_builder_tool_use() {
         let tool_calls = vec![crate::ToolCall {
@@ -793,6 +1296,56 @@ mod tests {
         assert_eq!(parsed["function"]["name"], "my_function");
     }
 
+    #[test]
+    fn test_token_logprob_confidence() {
+        let token = |logprob: f32| TokenLogprob {
+            token: "a".to_string(),
+            logprob,
+            top_logprobs: Vec::new(),
+        };
+
+        assert_eq!(TokenLogprob::confidence(&[]), None);
+        assert!((token(0.5f32.ln()).probability() - 0.5).abs() < 1e-6);
+        let confidence = TokenLogprob::confidence(&[token(0.0), token(0.25f32.ln())]).unwrap();
+        assert!((confidence - 0.5).abs() < 1e-6, "{confidence}");
+    }
+
+    #[test]
+    fn test_generation_options_serialize_only_set_fields() {
+        assert_eq!(
+            serde_json::to_value(GenerationOptions::default()).unwrap(),
+            serde_json::json!({})
+        );
+        let options = GenerationOptions {
+            stop_sequences: vec!["END".to_string()],
+            seed: Some(7),
+            frequency_penalty: None,
+            presence_penalty: Some(0.5),
+        };
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            serde_json::json!({"stop": ["END"], "seed": 7, "presence_penalty": 0.5})
+        );
+    }
+
+    #[test]
+    fn test_generation_options_reject_unsupported() {
+        let options = GenerationOptions {
+            seed: Some(7),
+            ..Default::default()
+        };
+        assert!(options
+            .reject_unsupported("Backend", &["stop_sequences", "frequency_penalty"])
+            .is_ok());
+        let err = options
+            .reject_unsupported("Backend", &["seed"])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            LLMError::InvalidRequest("Backend does not support seed".to_string()).to_string()
+        );
+    }
+
     #[test]
     fn test_tool_choice_default() {
         let default_choice = ToolChoice::default();
@@ -890,6 +1443,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "Hello, world!".to_string(),
+            reasoning: Vec::new(),
         };
 
         let serialized = serde_json::to_string(&message).unwrap();
@@ -899,6 +1453,56 @@ mod tests {
         assert_eq!(deserialized.message_type, MessageType::Text);
         assert_eq!(deserialized.content, "Hello, world!");
     }
+
+    #[test]
+    fn test_usage_deserializes_openai_shape() {
+        let usage: Usage = serde_json::from_value(json!({
+            "prompt_tokens": 120,
+            "completion_tokens": 30,
+            "total_tokens": 150,
+            "prompt_tokens_details": {"cached_tokens": 100},
+            "cost": 0.0012
+        }))
+        .unwrap();
+
+        assert_eq!(usage.cached_tokens(), 100);
+        assert_eq!(usage.cost, Some(0.0012));
+        assert!(serde_json::to_value(Usage::new(1, 2))
+            .unwrap()
+            .get("cost")
+            .is_none());
+    }
+
+    #[test]
+    fn test_usage_accumulates() {
+        let mut total = Usage::new(100, 10).with_cached_tokens(40);
+        total += Usage {
+            cost: Some(0.5),
+            ..Usage::new(50, 5)
+        };
+
+        assert_eq!(total.prompt_tokens, 150);
+        assert_eq!(total.completion_tokens, 15);
+        assert_eq!(total.total_tokens, 165);
+        assert_eq!(total.cached_tokens(), 40);
+        assert_eq!(total.cost, Some(0.5));
+    }
+
+    #[test]
+    fn test_token_pricing_discounts_cached_tokens() {
+        let pricing = TokenPricing::new(2.0, 8.0).with_cached_input(0.5);
+        let usage = Usage::new(1_000_000, 500_000).with_cached_tokens(400_000);
+
+        let cost = pricing.cost(&usage);
+        assert!((cost - (1.2 + 0.2 + 4.0)).abs() < 1e-9, "{cost}");
+        assert_eq!(usage.clone().priced(&pricing).cost, Some(cost));
+
+        let reported = Usage {
+            cost: Some(1.0),
+            ..usage
+        };
+        assert_eq!(reported.priced(&pricing).cost, Some(1.0));
+    }
     //
     // #[tokio::test]
     // async fn test_chat_provider_summarize_history() {
@@ -0,0 +1,424 @@
+use super::StreamResponse;
+use crate::error::LLMError;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::marker::PhantomData;
+
+/// Parses the JSON received so far of a streamed structured output.
+///
+/// Open objects, arrays and strings are closed where the text ends. A key
+/// still being streamed is left out, as are numbers, `true`, `false` and
+/// `null` until the character after them arrives, since a number cut short
+/// would show a wrong value. With `complete_values_only`, strings and
+/// containers still being streamed are left out too, so only the fields
+/// that are done appear. Returns `None` when nothing can be shown yet or the
+/// text is not JSON.
+///
+/// ```
+/// use autoagents_llm::chat::parse_partial_json;
+/// use serde_json::json;
+///
+/// let text = r#"{"title": "Rust", "tags": ["fast", "saf"#;
+/// assert_eq!(
+///     parse_partial_json(text, false),
+///     Some(json!({"title": "Rust", "tags": ["fast", "saf"]}))
+/// );
+/// assert_eq!(parse_partial_json(text, true), Some(json!({"title": "Rust"})));
+/// ```
+pub fn parse_partial_json(text: &str, complete_values_only: bool) -> Option<Value> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        complete_values_only,
+    };
+    match parser.value(true).ok()? {
+        Parsed::Complete(value) => {
+            parser.skip_whitespace();
+            (parser.pos == text.len()).then_some(value)
+        }
+        Parsed::Partial(value) => value,
+    }
+}
+
+/// Accumulates the deltas of a streamed structured output and deserializes
+/// the partial object after each one, so UIs can render a result while it
+/// streams.
+///
+/// Partial objects miss the fields not streamed yet, so `T` should give
+/// them a default, e.g. with `Option` fields or `#[serde(default)]`. A
+/// partial that does not deserialize into `T` is skipped.
+///
+/// ```
+/// use autoagents_llm::chat::PartialJson;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Default, Deserialize)]
+/// #[serde(default)]
+/// struct Weather {
+///     city: String,
+///     degrees: Option<f64>,
+/// }
+///
+/// let mut weather = PartialJson::<Weather>::new();
+/// assert_eq!(weather.push(r#"{"city": "Par"#).unwrap().city, "Par");
+/// let partial = weather.push(r#"is", "degrees": 2"#).unwrap();
+/// assert_eq!((partial.city.as_str(), partial.degrees), ("Paris", None));
+/// assert_eq!(weather.push("1}").unwrap().degrees, Some(21.0));
+/// assert_eq!(weather.finish().unwrap().degrees, Some(21.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PartialJson<T = Value> {
+    buffer: String,
+    /// Last partial value pushed out, to skip deltas that change nothing
+    last: Option<Value>,
+    complete_values_only: bool,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for PartialJson<T> {
+    fn default() -> Self {
+        Self {
+            buffer: String::new(),
+            last: None,
+            complete_values_only: false,
+            _output: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> PartialJson<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only show fields whose value is complete, leaving out strings, arrays
+    /// and objects still being streamed.
+    pub fn complete_values_only(mut self) -> Self {
+        self.complete_values_only = true;
+        self
+    }
+
+    /// Adds a delta of the output and returns the partial object when it
+    /// changed.
+    pub fn push(&mut self, delta: &str) -> Option<T> {
+        self.buffer.push_str(delta);
+        let value = parse_partial_json(&self.buffer, self.complete_values_only)?;
+        if self.last.as_ref() == Some(&value) {
+            return None;
+        }
+        let partial = serde_json::from_value(value.clone()).ok()?;
+        self.last = Some(value);
+        Some(partial)
+    }
+
+    /// Adds the content of a chunk of a structured stream.
+    pub fn push_chunk(&mut self, chunk: &StreamResponse) -> Option<T> {
+        let content = chunk.choices.first()?.delta.content.as_deref()?;
+        self.push(content)
+    }
+
+    /// The text received so far
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    /// The parsed output, once the stream has ended.
+    pub fn finish(self) -> Result<T, LLMError> {
+        serde_json::from_str(self.buffer.trim()).map_err(|err| LLMError::ResponseFormatError {
+            message: format!("Failed to parse structured output: {err}"),
+            raw_response: self.buffer,
+        })
+    }
+}
+
+enum Parsed {
+    Complete(Value),
+    /// The text ended inside the value, with what can be shown of it
+    Partial(Option<Value>),
+}
+
+/// The text is not JSON
+struct Invalid;
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    complete_values_only: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// What to keep of a value the text ended in. The outermost value is
+    /// always kept.
+    fn partial(&self, value: Value, root: bool) -> Parsed {
+        Parsed::Partial((root || !self.complete_values_only).then_some(value))
+    }
+
+    fn value(&mut self, root: bool) -> Result<Parsed, Invalid> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(Parsed::Partial(None)),
+            Some(b'{') => self.object(root),
+            Some(b'[') => self.array(root),
+            Some(b'"') => Ok(match self.string()? {
+                (string, true) => Parsed::Complete(Value::String(string)),
+                (string, false) => self.partial(Value::String(string), root),
+            }),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(Invalid),
+        }
+    }
+
+    fn object(&mut self, root: bool) -> Result<Parsed, Invalid> {
+        self.pos += 1;
+        let mut object = Map::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Parsed::Complete(Value::Object(object)));
+        }
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(self.partial(Value::Object(object), root)),
+                Some(b'"') => {}
+                Some(_) => return Err(Invalid),
+            }
+            let (key, complete) = self.string()?;
+            self.skip_whitespace();
+            if !complete || self.peek().is_none() {
+                return Ok(self.partial(Value::Object(object), root));
+            }
+            if self.peek() != Some(b':') {
+                return Err(Invalid);
+            }
+            self.pos += 1;
+            match self.value(false)? {
+                Parsed::Complete(value) => {
+                    object.insert(key, value);
+                }
+                Parsed::Partial(value) => {
+                    if let Some(value) = value {
+                        object.insert(key, value);
+                    }
+                    return Ok(self.partial(Value::Object(object), root));
+                }
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(self.partial(Value::Object(object), root)),
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Parsed::Complete(Value::Object(object)));
+                }
+                Some(_) => return Err(Invalid),
+            }
+        }
+    }
+
+    fn array(&mut self, root: bool) -> Result<Parsed, Invalid> {
+        self.pos += 1;
+        let mut array = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Parsed::Complete(Value::Array(array)));
+        }
+        loop {
+            match self.value(false)? {
+                Parsed::Complete(value) => array.push(value),
+                Parsed::Partial(value) => {
+                    array.extend(value);
+                    return Ok(self.partial(Value::Array(array), root));
+                }
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(self.partial(Value::Array(array), root)),
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Parsed::Complete(Value::Array(array)));
+                }
+                Some(_) => return Err(Invalid),
+            }
+        }
+    }
+
+    /// A string and whether its closing quote arrived. An escape cut short
+    /// is left out.
+    fn string(&mut self) -> Result<(String, bool), Invalid> {
+        self.pos += 1;
+        let mut string = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok((string, true));
+                }
+                '\\' => {
+                    let Some((_, escape)) = chars.next() else {
+                        break;
+                    };
+                    let unescaped = match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => match unicode_escape(&mut chars)? {
+                            Some(c) => c,
+                            None => break,
+                        },
+                        _ => return Err(Invalid),
+                    };
+                    string.push(unescaped);
+                }
+                c => string.push(c),
+            }
+        }
+        self.pos = self.text.len();
+        Ok((string, false))
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Parsed, Invalid> {
+        let rest = &self.text[self.pos..];
+        if rest.starts_with(literal) {
+            self.pos += literal.len();
+            self.complete_scalar(value)
+        } else if literal.starts_with(rest) {
+            self.pos = self.text.len();
+            Ok(Parsed::Partial(None))
+        } else {
+            Err(Invalid)
+        }
+    }
+
+    fn number(&mut self) -> Result<Parsed, Invalid> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        let number = serde_json::from_str::<serde_json::Number>(&self.text[start..self.pos]).ok();
+        match number {
+            Some(number) => self.complete_scalar(Value::Number(number)),
+            None if self.peek().is_none() => Ok(Parsed::Partial(None)),
+            None => Err(Invalid),
+        }
+    }
+
+    /// A number or literal is only known to be complete once something
+    /// follows it
+    fn complete_scalar(&self, value: Value) -> Result<Parsed, Invalid> {
+        Ok(match self.peek() {
+            None => Parsed::Partial(None),
+            Some(_) => Parsed::Complete(value),
+        })
+    }
+}
+
+/// The character of a `\u` escape, `None` when the escape is cut short
+fn unicode_escape(chars: &mut std::str::CharIndices) -> Result<Option<char>, Invalid> {
+    let Some(high) = hex4(chars)? else {
+        return Ok(None);
+    };
+    if !(0xD800..0xDC00).contains(&high) {
+        return Ok(Some(
+            char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER),
+        ));
+    }
+    // A surrogate pair, the low half in a second escape
+    match (chars.next(), chars.next()) {
+        (Some((_, '\\')), Some((_, 'u'))) => {}
+        (None, _) | (Some((_, '\\')), None) => return Ok(None),
+        _ => return Ok(Some(char::REPLACEMENT_CHARACTER)),
+    }
+    let Some(low) = hex4(chars)? else {
+        return Ok(None);
+    };
+    let c = 0x10000 + ((high - 0xD800) << 10) + low.wrapping_sub(0xDC00);
+    Ok(Some(
+        char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER),
+    ))
+}
+
+fn hex4(chars: &mut std::str::CharIndices) -> Result<Option<u32>, Invalid> {
+    let mut code = 0;
+    for _ in 0..4 {
+        let Some((_, c)) = chars.next() else {
+            return Ok(None);
+        };
+        code = code * 16 + c.to_digit(16).ok_or(Invalid)?;
+    }
+    Ok(Some(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_every_prefix_parses() {
+        let text = r#"{"name": "Ada \"Countess\" Lovelace é", "born": 1815, "alive": false, "notes": [{"k": null}, "😀"]}"#;
+        let full: Value = serde_json::from_str(text).unwrap();
+        for end in (0..=text.len()).filter(|&end| text.is_char_boundary(end)) {
+            let partial = parse_partial_json(&text[..end], false);
+            if end == text.len() {
+                assert_eq!(partial, Some(full.clone()));
+            } else if end > 0 {
+                assert!(partial.is_some(), "prefix {:?}", &text[..end]);
+            }
+            parse_partial_json(&text[..end], true);
+        }
+    }
+
+    #[test]
+    fn test_incomplete_scalars_are_left_out() {
+        assert_eq!(parse_partial_json(r#"{"a": 12"#, false), Some(json!({})));
+        assert_eq!(
+            parse_partial_json(r#"{"a": 12, "b": tr"#, false),
+            Some(json!({"a": 12}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"a": "x\"#, false),
+            Some(json!({"a": "x"}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"a": {"b": "c"}, "d": {"e": "f"#, true),
+            Some(json!({"a": {"b": "c"}}))
+        );
+        assert_eq!(parse_partial_json(r#"{"a": 1}}"#, false), None);
+        assert_eq!(parse_partial_json(r#"{"a" 1"#, false), None);
+    }
+
+    #[test]
+    fn test_accumulator_skips_unchanged_partials() {
+        let mut partial = PartialJson::<Value>::new();
+        assert_eq!(partial.push("{\"a\": "), Some(json!({})));
+        assert_eq!(partial.push("1"), None);
+        assert_eq!(partial.push("0"), None);
+        assert_eq!(partial.push("}"), Some(json!({"a": 10})));
+        assert_eq!(partial.finish().unwrap(), json!({"a": 10}));
+    }
+}
@@ -0,0 +1,285 @@
+//! Prompt formatting for local models from their Hugging Face chat template.
+//!
+//! Instruction-tuned models expect the conversation laid out with their own
+//! role markers and special tokens. Models published on the Hugging Face hub
+//! ship that layout as a Jinja template in `tokenizer_config.json`, which
+//! [`ChatTemplate`] renders the way `transformers` does, so a local backend
+//! formats prompts correctly for any model family.
+//!
+//! ```rust,ignore
+//! let template = ChatTemplate::from_tokenizer_config_file("models/qwen/tokenizer_config.json")?;
+//! let prompt = template.render(&messages, true)?;
+//! ```
+
+use minijinja::value::Value as TemplateValue;
+use minijinja::{Environment, Error, ErrorKind, State};
+use serde_json::{json, Value};
+
+use crate::chat::{ChatMessage, ChatRole};
+use crate::error::LLMError;
+
+/// A model's chat template with the special tokens it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatTemplate {
+    source: String,
+    bos_token: String,
+    eos_token: String,
+}
+
+impl ChatTemplate {
+    pub fn new(
+        source: impl Into<String>,
+        bos_token: impl Into<String>,
+        eos_token: impl Into<String>,
+    ) -> Self {
+        Self {
+            source: source.into(),
+            bos_token: bos_token.into(),
+            eos_token: eos_token.into(),
+        }
+    }
+
+    /// The template and special tokens of a parsed `tokenizer_config.json`.
+    /// Of several named templates the `default` one is used.
+    pub fn from_tokenizer_config(config: &Value) -> Result<Self, LLMError> {
+        let source = match config.get("chat_template") {
+            Some(Value::String(source)) => source.clone(),
+            Some(Value::Array(templates)) => templates
+                .iter()
+                .find(|template| template["name"] == "default")
+                .or_else(|| templates.first())
+                .and_then(|template| template["template"].as_str())
+                .ok_or_else(|| {
+                    LLMError::InvalidRequest("Tokenizer config has no chat template".to_string())
+                })?
+                .to_string(),
+            _ => {
+                return Err(LLMError::InvalidRequest(
+                    "Tokenizer config has no chat template".to_string(),
+                ))
+            }
+        };
+        Ok(Self::new(
+            source,
+            special_token(config, "bos_token"),
+            special_token(config, "eos_token"),
+        ))
+    }
+
+    /// Load the template from a `tokenizer_config.json`, or from the
+    /// `chat_template.jinja` next to it that newer models ship instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_tokenizer_config_file(path: impl AsRef<std::path::Path>) -> Result<Self, LLMError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            LLMError::InvalidRequest(format!("Failed to read {}: {e}", path.display()))
+        })?;
+        let mut config: Value = serde_json::from_str(&content)?;
+        if config.get("chat_template").is_none() {
+            let jinja = path.with_file_name("chat_template.jinja");
+            if let Ok(source) = std::fs::read_to_string(jinja) {
+                config["chat_template"] = Value::String(source);
+            }
+        }
+        Self::from_tokenizer_config(&config)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn bos_token(&self) -> &str {
+        &self.bos_token
+    }
+
+    pub fn eos_token(&self) -> &str {
+        &self.eos_token
+    }
+
+    /// The prompt for `messages`, ending with the opening of the assistant's
+    /// turn when `add_generation_prompt` is set.
+    pub fn render(
+        &self,
+        messages: &[ChatMessage],
+        add_generation_prompt: bool,
+    ) -> Result<String, LLMError> {
+        let messages: Vec<Value> = messages
+            .iter()
+            .map(|message| json!({"role": role(&message.role), "content": message.content}))
+            .collect();
+        environment()
+            .render_str(
+                &self.source,
+                json!({
+                    "messages": messages,
+                    "add_generation_prompt": add_generation_prompt,
+                    "bos_token": self.bos_token,
+                    "eos_token": self.eos_token,
+                }),
+            )
+            .map_err(|e| LLMError::InvalidRequest(format!("Failed to render chat template: {e}")))
+    }
+}
+
+fn role(role: &ChatRole) -> &'static str {
+    match role {
+        ChatRole::System => "system",
+        ChatRole::User => "user",
+        ChatRole::Assistant => "assistant",
+        ChatRole::Tool => "tool",
+    }
+}
+
+/// A special token, given either as text or as an added token object
+fn special_token(config: &Value, name: &str) -> String {
+    match &config[name] {
+        Value::String(token) => token.clone(),
+        token => token["content"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// An environment that renders like `transformers`: block tags don't leave
+/// blank lines behind, templates can fail with `raise_exception`, and the
+/// Python string methods templates commonly call are available.
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.add_function("raise_exception", |message: String| -> Result<(), Error> {
+        Err(Error::new(ErrorKind::InvalidOperation, message))
+    });
+    env.set_unknown_method_callback(string_method);
+    env
+}
+
+fn string_method(
+    _state: &State,
+    value: &TemplateValue,
+    method: &str,
+    args: &[TemplateValue],
+) -> Result<TemplateValue, Error> {
+    let Some(text) = value.as_str() else {
+        return Err(ErrorKind::UnknownMethod.into());
+    };
+    let chars = args.first().and_then(TemplateValue::as_str);
+    let trimmed = |start: bool, end: bool| {
+        let is_stripped = |c: char| match chars {
+            Some(chars) => chars.contains(c),
+            None => c.is_whitespace(),
+        };
+        let text = if start {
+            text.trim_start_matches(is_stripped)
+        } else {
+            text
+        };
+        let text = if end {
+            text.trim_end_matches(is_stripped)
+        } else {
+            text
+        };
+        TemplateValue::from(text)
+    };
+    match method {
+        "strip" => Ok(trimmed(true, true)),
+        "lstrip" => Ok(trimmed(true, false)),
+        "rstrip" => Ok(trimmed(false, true)),
+        "startswith" => Ok(TemplateValue::from(
+            chars.is_some_and(|p| text.starts_with(p)),
+        )),
+        "endswith" => Ok(TemplateValue::from(
+            chars.is_some_and(|p| text.ends_with(p)),
+        )),
+        "upper" => Ok(TemplateValue::from(text.to_uppercase())),
+        "lower" => Ok(TemplateValue::from(text.to_lowercase())),
+        _ => Err(ErrorKind::UnknownMethod.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatMessageBuilder;
+
+    const ZEPHYR: &str = "{% for message in messages %}\n{% if message['role'] == 'user' %}\n{{ '<|user|>\n' + message['content'] + eos_token }}\n{% elif message['role'] == 'system' %}\n{{ '<|system|>\n' + message['content'] + eos_token }}\n{% elif message['role'] == 'assistant' %}\n{{ '<|assistant|>\n'  + message['content'] + eos_token }}\n{% endif %}\n{% if loop.last and add_generation_prompt %}\n{{ '<|assistant|>' }}\n{% endif %}\n{% endfor %}";
+
+    fn conversation() -> Vec<ChatMessage> {
+        vec![
+            ChatMessageBuilder::new(ChatRole::System)
+                .content("Be brief.")
+                .build(),
+            ChatMessage::user().content("Hi").build(),
+        ]
+    }
+
+    #[test]
+    fn test_render_matches_transformers_whitespace() {
+        let template = ChatTemplate::new(ZEPHYR, "<s>", "</s>");
+        assert_eq!(
+            template.render(&conversation(), true).unwrap(),
+            "<|system|>\nBe brief.</s>\n<|user|>\nHi</s>\n<|assistant|>\n"
+        );
+        assert!(!template
+            .render(&conversation(), false)
+            .unwrap()
+            .ends_with("<|assistant|>\n"));
+    }
+
+    #[test]
+    fn test_tokenizer_config_with_added_tokens_and_named_templates() {
+        let config = json!({
+            "bos_token": {"content": "<s>", "lstrip": false},
+            "eos_token": "</s>",
+            "chat_template": [
+                {"name": "tool_use", "template": "tools"},
+                {"name": "default", "template": "{{ bos_token }}{% for m in messages %}[{{ m.role }}] {{ m.content.strip() }}{{ eos_token }}{% endfor %}"},
+            ],
+        });
+        let template = ChatTemplate::from_tokenizer_config(&config).unwrap();
+        assert_eq!(template.bos_token(), "<s>");
+
+        let messages = [ChatMessage::user().content("  Hi \n").build()];
+        assert_eq!(
+            template.render(&messages, true).unwrap(),
+            "<s>[user] Hi</s>"
+        );
+
+        assert!(ChatTemplate::from_tokenizer_config(&json!({"eos_token": "</s>"})).is_err());
+    }
+
+    #[test]
+    fn test_raise_exception_fails_the_render() {
+        let template = ChatTemplate::new(
+            "{% if messages[0]['role'] == 'system' %}{{ raise_exception('System role not supported') }}{% endif %}",
+            "",
+            "",
+        );
+        let err = template.render(&conversation(), true).unwrap_err();
+        assert!(
+            err.to_string().contains("System role not supported"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_chat_template_jinja_next_to_the_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("tokenizer_config.json"),
+            r#"{"bos_token": "<|begin_of_text|>"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("chat_template.jinja"),
+            "{{ bos_token }}{{ messages | length }}",
+        )
+        .unwrap();
+
+        let template =
+            ChatTemplate::from_tokenizer_config_file(dir.path().join("tokenizer_config.json"))
+                .unwrap();
+        assert_eq!(
+            template.render(&conversation(), true).unwrap(),
+            "<|begin_of_text|>2"
+        );
+    }
+}
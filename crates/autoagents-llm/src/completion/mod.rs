@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 
 use crate::{
-    chat::{ChatResponse, StructuredOutputFormat},
+    chat::{ChatResponse, StructuredOutputFormat, Usage},
     error::LLMError,
     ToolCall,
 };
@@ -22,6 +22,26 @@ pub struct CompletionRequest {
 pub struct CompletionResponse {
     /// The generated completion text
     pub text: String,
+    /// Tokens used by the request, if the provider reported them
+    pub usage: Option<Usage>,
+}
+
+impl CompletionResponse {
+    /// A completion without usage information.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            usage: None,
+        }
+    }
+
+    /// Takes the text and usage of a chat response answering the prompt.
+    pub fn from_chat(response: &dyn ChatResponse) -> Self {
+        Self {
+            text: response.text().unwrap_or_default(),
+            usage: response.usage(),
+        }
+    }
 }
 
 impl ChatResponse for CompletionResponse {
@@ -32,6 +52,10 @@ impl ChatResponse for CompletionResponse {
     fn tool_calls(&self) -> Option<Vec<ToolCall>> {
         None
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
 }
 
 impl CompletionRequest {
@@ -191,6 +215,7 @@ mod tests {
     fn test_completion_response_new() {
         let response = CompletionResponse {
             text: "Generated text".to_string(),
+            usage: None,
         };
         assert_eq!(response.text, "Generated text");
     }
@@ -199,6 +224,7 @@ mod tests {
     fn test_completion_response_clone() {
         let response = CompletionResponse {
             text: "Cloneable response".to_string(),
+            usage: None,
         };
         let cloned = response.clone();
         assert_eq!(response.text, cloned.text);
@@ -208,6 +234,7 @@ mod tests {
     fn test_completion_response_debug() {
         let response = CompletionResponse {
             text: "Debug response".to_string(),
+            usage: None,
         };
         let debug_str = format!("{response:?}");
         assert!(debug_str.contains("CompletionResponse"));
@@ -218,6 +245,7 @@ mod tests {
     fn test_completion_response_display() {
         let response = CompletionResponse {
             text: "Display test".to_string(),
+            usage: None,
         };
         assert_eq!(response.to_string(), "Display test");
     }
@@ -226,6 +254,7 @@ mod tests {
     fn test_completion_response_chat_response_trait() {
         let response = CompletionResponse {
             text: "Chat response test".to_string(),
+            usage: None,
         };
 
         // Test ChatResponse trait implementation
@@ -233,6 +262,19 @@ mod tests {
         assert!(response.tool_calls().is_none());
     }
 
+    #[test]
+    fn test_completion_response_from_chat_keeps_usage() {
+        let chat = CompletionResponse {
+            text: "From chat".to_string(),
+            usage: Some(Usage::new(7, 3)),
+        };
+
+        let response = CompletionResponse::from_chat(&chat);
+        assert_eq!(response.text, "From chat");
+        assert_eq!(response.usage().unwrap().total_tokens, 10);
+        assert!(CompletionResponse::new("bare").usage.is_none());
+    }
+
     #[test]
     fn test_completion_request_builder_debug() {
         let builder = CompletionRequest::builder("Builder debug")
@@ -293,6 +335,7 @@ mod tests {
     fn test_completion_response_empty_text() {
         let response = CompletionResponse {
             text: String::new(),
+            usage: None,
         };
         assert_eq!(response.text(), Some(String::new()));
         assert_eq!(response.to_string(), "");
@@ -303,6 +346,7 @@ mod tests {
         let multiline_text = "Line 1\nLine 2\nLine 3";
         let response = CompletionResponse {
             text: multiline_text.to_string(),
+            usage: None,
         };
         assert_eq!(response.text(), Some(multiline_text.to_string()));
         assert_eq!(response.to_string(), multiline_text);
@@ -313,6 +357,7 @@ mod tests {
         let unicode_text = "Hello 世界! 🌍";
         let response = CompletionResponse {
             text: unicode_text.to_string(),
+            usage: None,
         };
         assert_eq!(response.text(), Some(unicode_text.to_string()));
         assert_eq!(response.to_string(), unicode_text);
@@ -345,6 +390,7 @@ mod tests {
             } else {
                 Ok(CompletionResponse {
                     text: format!("Completed: {}", req.prompt),
+                    usage: None,
                 })
             }
         }
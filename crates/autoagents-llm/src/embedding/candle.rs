@@ -0,0 +1,199 @@
+//! Sentence embeddings computed on the local machine.
+//!
+//! [`CandleEmbedder`] runs a BERT-family sentence-transformers checkpoint,
+//! such as `all-MiniLM-L6-v2` or `bge-small-en-v1.5`, with candle. The model
+//! directory needs the `config.json`, `tokenizer.json` and
+//! `model.safetensors` files of the checkpoint. Each text is embedded as the
+//! mean of its token states, padding excluded.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use tokenizers::{PaddingStrategy, Tokenizer, TruncationParams};
+
+use super::EmbeddingProvider;
+use crate::error::LLMError;
+
+/// Texts embedded per forward pass unless set otherwise
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Embedding provider backed by a local BERT model.
+#[derive(Clone)]
+pub struct CandleEmbedder {
+    model: Arc<LoadedModel>,
+    dimensions: usize,
+    batch_size: usize,
+}
+
+struct LoadedModel {
+    bert: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+impl std::fmt::Debug for CandleEmbedder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CandleEmbedder")
+            .field("device", &self.model.device)
+            .field("dimensions", &self.dimensions)
+            .field("batch_size", &self.batch_size)
+            .finish()
+    }
+}
+
+impl CandleEmbedder {
+    /// Load the checkpoint in `dir` on the CPU.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, LLMError> {
+        let dir = dir.as_ref();
+        Self::from_files(
+            dir.join("config.json"),
+            dir.join("tokenizer.json"),
+            dir.join("model.safetensors"),
+            Device::Cpu,
+        )
+    }
+
+    /// Load a checkpoint from its individual files onto `device`.
+    pub fn from_files(
+        config: impl AsRef<Path>,
+        tokenizer: impl AsRef<Path>,
+        weights: impl AsRef<Path>,
+        device: Device,
+    ) -> Result<Self, LLMError> {
+        let config = load_config(config.as_ref())?;
+        let tokenizer = load_tokenizer(tokenizer.as_ref(), &config)?;
+        let weights = load_weights(weights.as_ref(), &device)?;
+        let bert = BertModel::load(weights, &config).map_err(model_error)?;
+
+        Ok(Self {
+            model: Arc::new(LoadedModel {
+                bert,
+                tokenizer,
+                device,
+            }),
+            dimensions: config.hidden_size,
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Texts embedded per forward pass, bounding memory use
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+impl LoadedModel {
+    fn embed(&self, input: Vec<String>) -> candle_core::Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(input, true)
+            .map_err(candle_core::Error::msg)?;
+        let ids = encodings
+            .iter()
+            .map(|encoding| Tensor::new(encoding.get_ids(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()?;
+        let masks = encodings
+            .iter()
+            .map(|encoding| Tensor::new(encoding.get_attention_mask(), &self.device))
+            .collect::<candle_core::Result<Vec<_>>>()?;
+        let ids = Tensor::stack(&ids, 0)?;
+        let mask = Tensor::stack(&masks, 0)?;
+        let token_types = ids.zeros_like()?;
+
+        let states = self.bert.forward(&ids, &token_types, Some(&mask))?;
+        let mask = mask.to_dtype(states.dtype())?.unsqueeze(2)?;
+        let summed = states.broadcast_mul(&mask)?.sum(1)?;
+        let pooled = summed.broadcast_div(&mask.sum(1)?)?;
+        pooled.to_dtype(DType::F32)?.to_vec2()
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CandleEmbedder {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut vectors = Vec::with_capacity(input.len());
+        let mut input = input.into_iter();
+        loop {
+            let batch: Vec<String> = input.by_ref().take(self.batch_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+            let model = self.model.clone();
+            let embedded = tokio::task::spawn_blocking(move || model.embed(batch))
+                .await
+                .map_err(|e| LLMError::ProviderError(format!("Embedding task failed: {e}")))?
+                .map_err(model_error)?;
+            vectors.extend(embedded);
+        }
+        Ok(vectors)
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        Some(self.dimensions)
+    }
+}
+
+/// Read a BERT `config.json`.
+pub(crate) fn load_config(path: &Path) -> Result<Config, LLMError> {
+    let config = std::fs::read_to_string(path).map_err(|e| {
+        LLMError::InvalidRequest(format!(
+            "Failed to read model config {}: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(serde_json::from_str(&config)?)
+}
+
+/// Load a `tokenizer.json` set up to pad each batch to its longest input.
+pub(crate) fn load_tokenizer(path: &Path, config: &Config) -> Result<Tokenizer, LLMError> {
+    let mut tokenizer = Tokenizer::from_file(path).map_err(|e| {
+        LLMError::InvalidRequest(format!("Failed to load tokenizer {}: {e}", path.display()))
+    })?;
+    // Keep the checkpoint's pad token
+    let mut padding = tokenizer.get_padding().cloned().unwrap_or_default();
+    padding.strategy = PaddingStrategy::BatchLongest;
+    tokenizer.with_padding(Some(padding));
+    if tokenizer.get_truncation().is_none() {
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..Default::default()
+            }))
+            .map_err(|e| LLMError::InvalidRequest(e.to_string()))?;
+    }
+    Ok(tokenizer)
+}
+
+/// Memory-map `model.safetensors` weights onto `device`.
+pub(crate) fn load_weights(path: &Path, device: &Device) -> Result<VarBuilder<'static>, LLMError> {
+    // SAFETY: the weights file is only read, and must not be modified
+    // while the model is loaded.
+    unsafe { VarBuilder::from_mmaped_safetensors(&[path], DTYPE, device) }.map_err(model_error)
+}
+
+pub(crate) fn model_error(error: candle_core::Error) -> LLMError {
+    LLMError::ProviderError(format!("Local model error: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_checkpoint_is_an_invalid_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = CandleEmbedder::from_dir(dir.path()).unwrap_err();
+        assert!(
+            matches!(err, LLMError::InvalidRequest(ref msg) if msg.contains("config.json")),
+            "{err}"
+        );
+    }
+}
@@ -2,9 +2,95 @@ use async_trait::async_trait;
 
 use crate::error::LLMError;
 
+/// Local sentence embeddings computed with candle
+#[cfg(all(feature = "candle", not(target_arch = "wasm32")))]
+pub mod candle;
+
+/// How [`EmbeddingProvider::embed_with`] splits and post-processes a request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbeddingOptions {
+    /// Most texts sent per request, further capped by the provider's limit
+    pub batch_size: Option<usize>,
+    /// Scale every vector to unit length
+    pub normalize: bool,
+}
+
+impl EmbeddingOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+}
+
 #[async_trait]
 pub trait EmbeddingProvider {
     async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError>;
+
+    /// Length of the vectors returned by [`embed`](Self::embed), when known
+    /// without making a request
+    fn embedding_dimensions(&self) -> Option<usize> {
+        None
+    }
+
+    /// Most texts a single [`embed`](Self::embed) call accepts, when limited
+    fn max_embedding_batch(&self) -> Option<usize> {
+        None
+    }
+
+    /// Embed `input` in batches no larger than both `options` and
+    /// [`max_embedding_batch`](Self::max_embedding_batch) allow, returning
+    /// the vectors in input order, normalized when `options` asks for it.
+    async fn embed_with(
+        &self,
+        input: Vec<String>,
+        options: &EmbeddingOptions,
+    ) -> Result<Vec<Vec<f32>>, LLMError>
+    where
+        Self: Sync,
+    {
+        let batch_size = match (options.batch_size, self.max_embedding_batch()) {
+            (Some(requested), Some(limit)) => Some(requested.min(limit)),
+            (requested, limit) => requested.or(limit),
+        }
+        .map(|size| size.max(1));
+
+        let mut vectors = match batch_size {
+            Some(size) if input.len() > size => {
+                let mut vectors = Vec::with_capacity(input.len());
+                let mut input = input.into_iter();
+                loop {
+                    let batch: Vec<String> = input.by_ref().take(size).collect();
+                    if batch.is_empty() {
+                        break;
+                    }
+                    vectors.extend(self.embed(batch).await?);
+                }
+                vectors
+            }
+            _ => self.embed(input).await?,
+        };
+        if options.normalize {
+            vectors.iter_mut().for_each(|vector| normalize(vector));
+        }
+        Ok(vectors)
+    }
+}
+
+/// Scale `vector` to unit length. Zero vectors are left unchanged.
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
 }
 
 #[cfg(test)]
@@ -262,4 +348,73 @@ mod tests {
             assert_ne!(embeddings[i], embeddings[i + 1]);
         }
     }
+
+    /// Records the size of every batch it is asked to embed
+    struct BatchRecorder {
+        limit: Option<usize>,
+        batches: std::sync::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for BatchRecorder {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            self.batches.lock().unwrap().push(input.len());
+            Ok(input
+                .iter()
+                .map(|text| vec![text.len() as f32, 0.0])
+                .collect())
+        }
+
+        fn max_embedding_batch(&self) -> Option<usize> {
+            self.limit
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_with_splits_batches_in_order() {
+        let provider = BatchRecorder {
+            limit: Some(3),
+            batches: Default::default(),
+        };
+        let input: Vec<String> = (1..=7).map(|n| "x".repeat(n)).collect();
+
+        let vectors = provider
+            .embed_with(input.clone(), &EmbeddingOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(*provider.batches.lock().unwrap(), vec![3, 3, 1]);
+        let lengths: Vec<f32> = vectors.iter().map(|v| v[0]).collect();
+        assert_eq!(lengths, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+
+        provider.batches.lock().unwrap().clear();
+        provider
+            .embed_with(input, &EmbeddingOptions::new().batch_size(5))
+            .await
+            .unwrap();
+        assert_eq!(*provider.batches.lock().unwrap(), vec![3, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_with_normalizes() {
+        let provider = BatchRecorder {
+            limit: None,
+            batches: Default::default(),
+        };
+        let vectors = provider
+            .embed_with(
+                vec!["abcd".to_string(), String::new()],
+                &EmbeddingOptions::new().batch_size(1).normalize(true),
+            )
+            .await
+            .unwrap();
+        assert_eq!(*provider.batches.lock().unwrap(), vec![1, 1]);
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_normalize_scales_to_unit_length() {
+        let mut vector = vec![3.0, 4.0];
+        normalize(&mut vector);
+        assert_eq!(vector, vec![0.6, 0.8]);
+    }
 }
@@ -5,6 +5,10 @@ use std::fmt;
 pub enum LLMError {
     /// HTTP request/response errors
     HttpError(String),
+    /// The provider answered with a non-success HTTP status
+    HttpStatus { status: u16, message: String },
+    /// The request could not be sent or timed out before a response arrived
+    ConnectionError(String),
     /// Authentication and authorization errors
     AuthError(String),
     /// Invalid request parameters or format
@@ -30,6 +34,8 @@ impl fmt::Display for LLMError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             LLMError::HttpError(e) => write!(f, "HTTP Error: {e}"),
+            LLMError::HttpStatus { status, message } => write!(f, "HTTP Error {status}: {message}"),
+            LLMError::ConnectionError(e) => write!(f, "Connection Error: {e}"),
             LLMError::AuthError(e) => write!(f, "Auth Error: {e}"),
             LLMError::InvalidRequest(e) => write!(f, "Invalid Request: {e}"),
             LLMError::ProviderError(e) => write!(f, "Provider Error: {e}"),
@@ -52,11 +58,53 @@ impl fmt::Display for LLMError {
 
 impl std::error::Error for LLMError {}
 
+/// Whether an HTTP status is worth retrying: request timeouts (408), rate
+/// limits (429) and server errors other than 501 Not Implemented.
+pub fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429) || ((500..=599).contains(&status) && status != 501)
+}
+
+impl LLMError {
+    /// The HTTP status the provider answered with, if the error carries one.
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            LLMError::HttpStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the provider was rate limited (HTTP 429).
+    pub fn is_rate_limited(&self) -> bool {
+        self.status_code() == Some(429)
+    }
+
+    /// Whether the request may succeed if retried later or against another
+    /// provider: statuses accepted by [`is_retryable_status`], timeouts and
+    /// connection failures. Authentication, validation and parsing errors are
+    /// not retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LLMError::HttpStatus { status, .. } => is_retryable_status(*status),
+            LLMError::ConnectionError(_) => true,
+            _ => false,
+        }
+    }
+}
+
 /// Converts reqwest HTTP errors into LLMErrors
 #[cfg(not(target_arch = "wasm32"))]
 impl From<reqwest::Error> for LLMError {
     fn from(err: reqwest::Error) -> Self {
-        LLMError::HttpError(err.to_string())
+        if let Some(status) = err.status() {
+            LLMError::HttpStatus {
+                status: status.as_u16(),
+                message: err.to_string(),
+            }
+        } else if err.is_timeout() || err.is_connect() {
+            LLMError::ConnectionError(err.to_string())
+        } else {
+            LLMError::HttpError(err.to_string())
+        }
     }
 }
 
@@ -154,19 +202,13 @@ mod tests {
         assert!(debug_str.contains("test"));
     }
 
-    #[test]
-    fn test_from_reqwest_error() {
-        // Create a mock reqwest error by trying to make a request to an invalid URL
-        let client = reqwest::Client::new();
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        let reqwest_error = rt
-            .block_on(async {
-                client
-                    .get("http://invalid-url-that-does-not-exist-12345.com/")
-                    .timeout(std::time::Duration::from_millis(100))
-                    .send()
-                    .await
-            })
+    #[tokio::test]
+    async fn test_from_reqwest_error() {
+        // A URL reqwest cannot parse fails before any connection is attempted
+        let reqwest_error = reqwest::Client::new()
+            .get("not a url")
+            .send()
+            .await
             .unwrap_err();
 
         let llm_error: LLMError = reqwest_error.into();
@@ -267,4 +309,84 @@ mod tests {
             _ => panic!("Expected ResponseFormatError"),
         }
     }
+
+    #[test]
+    fn test_status_code() {
+        let error = LLMError::HttpStatus {
+            status: 429,
+            message: "Too Many Requests".to_string(),
+        };
+        assert_eq!(error.status_code(), Some(429));
+        assert!(error.is_rate_limited());
+
+        let error = LLMError::HttpStatus {
+            status: 503,
+            message: "Service Unavailable".to_string(),
+        };
+        assert_eq!(error.status_code(), Some(503));
+        assert!(!error.is_rate_limited());
+
+        // Numbers in the message text are never taken for a status
+        assert_eq!(LLMError::AuthError("401".to_string()).status_code(), None);
+        assert_eq!(
+            LLMError::ProviderError("max_tokens 512 exceeds context".to_string()).status_code(),
+            None
+        );
+        assert_eq!(
+            LLMError::ConnectionError(
+                "error sending request for url (http://127.0.0.1:11434/api/chat)".to_string()
+            )
+            .status_code(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(LLMError::ConnectionError(
+            "error sending request for url (http://127.0.0.1:11434/api/chat)".to_string()
+        )
+        .is_retryable());
+        for status in [429, 502, 529] {
+            assert!(LLMError::HttpStatus {
+                status,
+                message: String::new(),
+            }
+            .is_retryable());
+        }
+
+        assert!(!LLMError::HttpStatus {
+            status: 400,
+            message: "connection".to_string(),
+        }
+        .is_retryable());
+        assert!(
+            !LLMError::ProviderError("max_tokens 512 exceeds context".to_string()).is_retryable()
+        );
+        assert!(!LLMError::HttpError("operation timed out".to_string()).is_retryable());
+        assert!(!LLMError::AuthError("timed out".to_string()).is_retryable());
+        assert!(!LLMError::InvalidRequest("bad".to_string()).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_refused_connection_is_retryable() {
+        // Nothing listens on the port of a dropped listener
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/chat", listener.local_addr().unwrap());
+        drop(listener);
+
+        let error: LLMError = reqwest::get(url).await.unwrap_err().into();
+        assert!(matches!(error, LLMError::ConnectionError(_)));
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn test_is_retryable_status() {
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(is_retryable_status(status), "{status}");
+        }
+        for status in [200, 400, 401, 403, 404, 422, 501] {
+            assert!(!is_retryable_status(status), "{status}");
+        }
+    }
 }
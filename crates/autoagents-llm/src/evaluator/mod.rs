@@ -158,6 +158,7 @@ mod tests {
             }
             Ok(CompletionResponse {
                 text: self.response_text.clone(),
+                usage: None,
             })
         }
     }
@@ -265,6 +266,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         }];
 
         let results = evaluator.evaluate_chat(&messages).await.unwrap();
@@ -285,6 +287,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         }];
 
         let results = evaluator.evaluate_chat(&messages).await.unwrap();
@@ -305,6 +308,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         }];
 
         let result = evaluator.evaluate_chat(&messages).await;
@@ -324,6 +328,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         }];
 
         let results = evaluator.evaluate_chat(&messages).await.unwrap();
@@ -343,6 +348,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         }];
 
         let results = evaluator.evaluate_chat(&messages).await.unwrap();
@@ -361,16 +367,19 @@ mod tests {
                 role: ChatRole::System,
                 message_type: MessageType::Text,
                 content: "You are a helpful assistant".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: "Hello, how are you?".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: "I'm doing well, thank you!".to_string(),
+                reasoning: Vec::new(),
             },
         ];
 
@@ -441,6 +450,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         }];
 
         let result = evaluator.evaluate_chat(&messages).await;
@@ -462,6 +472,7 @@ mod tests {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: "test message".to_string(),
+            reasoning: Vec::new(),
         }];
 
         let results = evaluator.evaluate_chat(&messages).await.unwrap();
@@ -123,6 +123,7 @@ mod tests {
 
             Ok(CompletionResponse {
                 text: self.response_text.clone(),
+                usage: None,
             })
         }
     }
@@ -246,6 +247,7 @@ mod tests {
                 role: ChatRole::System,
                 message_type: MessageType::Text,
                 content: "You are a helpful assistant".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage::user().content("Hello").build(),
         ];
@@ -0,0 +1,573 @@
+//! Provider fallback chains.
+//!
+//! [`FallbackLLM`] wraps an ordered list of configured providers and behaves
+//! like a single [`LLMProvider`]. Each request goes to the first available
+//! provider; when it fails with a transient error (rate limit, 5xx, timeout or
+//! connection failure, see [`LLMError::is_retryable`]) the next provider is
+//! tried. Other errors are returned as-is, since another provider would most
+//! likely reject the request as well.
+//!
+//! Providers that fail repeatedly are put on a cooldown and moved to the back
+//! of the chain until it expires, so a degraded provider does not add latency
+//! to every request.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::{
+    audio::{SpeechToTextProvider, TextToSpeechProvider},
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamResponse, StructuredOutputFormat, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    tokenizer::{HeuristicTokenCounter, TokenCounter},
+    LLMProvider,
+};
+
+/// Consecutive transient failures before a provider is put on cooldown.
+const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+/// How long a provider stays at the back of the chain after tripping.
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Snapshot of a provider's health as tracked by [`FallbackLLM`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderHealth {
+    /// Name the provider was registered under.
+    pub name: String,
+    /// Transient failures since the last successful request.
+    pub consecutive_failures: u32,
+    /// Total successful requests.
+    pub total_successes: u64,
+    /// Total transient failures.
+    pub total_failures: u64,
+    /// Message of the most recent transient failure.
+    pub last_error: Option<String>,
+    /// Whether the provider is currently on cooldown.
+    pub cooling_down: bool,
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    consecutive_failures: u32,
+    total_successes: u64,
+    total_failures: u64,
+    last_error: Option<String>,
+    cooldown_until: Option<Instant>,
+}
+
+impl HealthState {
+    fn cooling_down(&self, now: Instant) -> bool {
+        self.cooldown_until.is_some_and(|until| until > now)
+    }
+}
+
+struct FallbackEntry {
+    name: String,
+    provider: Arc<dyn LLMProvider>,
+    health: Mutex<HealthState>,
+}
+
+impl FallbackEntry {
+    fn health(&self) -> std::sync::MutexGuard<'_, HealthState> {
+        self.health
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// An [`LLMProvider`] that fails over across an ordered list of providers.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use autoagents_llm::{fallback::FallbackLLM, LLMProvider};
+///
+/// fn chain(primary: Arc<dyn LLMProvider>, secondary: Arc<dyn LLMProvider>) -> FallbackLLM {
+///     FallbackLLM::new()
+///         .with_provider("primary", primary)
+///         .with_provider("secondary", secondary)
+/// }
+/// ```
+///
+/// Streaming requests fail over only while the stream is being opened; an
+/// error in the middle of a stream is passed through to the caller.
+/// Embeddings always use the first provider, as vectors produced by different
+/// models cannot be compared with each other.
+pub struct FallbackLLM {
+    providers: Vec<FallbackEntry>,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl Default for FallbackLLM {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for FallbackLLM {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FallbackLLM")
+            .field("providers", &self.health())
+            .field("failure_threshold", &self.failure_threshold)
+            .field("cooldown", &self.cooldown)
+            .finish()
+    }
+}
+
+impl FallbackLLM {
+    /// Creates an empty chain; add providers with [`FallbackLLM::with_provider`].
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+
+    /// Appends a provider to the chain. Providers are tried in the order added.
+    pub fn with_provider(
+        mut self,
+        name: impl Into<String>,
+        provider: Arc<dyn LLMProvider>,
+    ) -> Self {
+        self.providers.push(FallbackEntry {
+            name: name.into(),
+            provider,
+            health: Mutex::new(HealthState::default()),
+        });
+        self
+    }
+
+    /// Sets how many consecutive transient failures put a provider on cooldown.
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    /// Sets how long a provider stays at the back of the chain once it trips.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Returns the current health of every provider, in chain order.
+    pub fn health(&self) -> Vec<ProviderHealth> {
+        let now = Instant::now();
+        self.providers
+            .iter()
+            .map(|entry| {
+                let health = entry.health();
+                ProviderHealth {
+                    name: entry.name.clone(),
+                    consecutive_failures: health.consecutive_failures,
+                    total_successes: health.total_successes,
+                    total_failures: health.total_failures,
+                    last_error: health.last_error.clone(),
+                    cooling_down: health.cooling_down(now),
+                }
+            })
+            .collect()
+    }
+
+    /// Providers that are not cooling down come first, in chain order,
+    /// followed by the ones that are, so a request is still attempted when
+    /// every provider has tripped.
+    fn attempt_order(&self) -> Vec<&FallbackEntry> {
+        let now = Instant::now();
+        let (ready, cooling): (Vec<_>, Vec<_>) = self
+            .providers
+            .iter()
+            .partition(|entry| !entry.health().cooling_down(now));
+        ready.into_iter().chain(cooling).collect()
+    }
+
+    fn record_success(&self, entry: &FallbackEntry) {
+        let mut health = entry.health();
+        health.consecutive_failures = 0;
+        health.total_successes += 1;
+        health.cooldown_until = None;
+    }
+
+    fn record_failure(&self, entry: &FallbackEntry, error: &LLMError) {
+        let mut health = entry.health();
+        health.consecutive_failures += 1;
+        health.total_failures += 1;
+        health.last_error = Some(error.to_string());
+        if health.consecutive_failures >= self.failure_threshold {
+            health.cooldown_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    async fn with_fallback<T, F, Fut>(&self, operation: &str, call: F) -> Result<T, LLMError>
+    where
+        F: Fn(Arc<dyn LLMProvider>) -> Fut + Send,
+        Fut: Future<Output = Result<T, LLMError>> + Send,
+    {
+        let mut last_error = None;
+        for entry in self.attempt_order() {
+            match call(entry.provider.clone()).await {
+                Ok(value) => {
+                    self.record_success(entry);
+                    return Ok(value);
+                }
+                Err(error) if error.is_retryable() => {
+                    log::warn!(
+                        "FallbackLLM: {operation} on provider '{}' failed: {error}",
+                        entry.name
+                    );
+                    self.record_failure(entry, &error);
+                    last_error = Some(error);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            LLMError::InvalidRequest("FallbackLLM has no providers configured".to_string())
+        }))
+    }
+}
+
+#[async_trait]
+impl ChatProvider for FallbackLLM {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.with_fallback("chat", |provider| {
+            let json_schema = json_schema.clone();
+            async move { provider.chat(messages, tools, json_schema).await }
+        })
+        .await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.with_fallback("chat_stream", |provider| {
+            let json_schema = json_schema.clone();
+            async move { provider.chat_stream(messages, tools, json_schema).await }
+        })
+        .await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.with_fallback("chat_stream_struct", |provider| {
+            let json_schema = json_schema.clone();
+            async move {
+                provider
+                    .chat_stream_struct(messages, tools, json_schema)
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// Only if every provider does, as any of them may answer.
+    fn supports_structured_output(&self) -> bool {
+        self.providers
+            .iter()
+            .all(|entry| entry.provider.supports_structured_output())
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for FallbackLLM {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        self.with_fallback("complete", |provider| {
+            let json_schema = json_schema.clone();
+            async move { provider.complete(req, json_schema).await }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for FallbackLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        let entry = self.providers.first().ok_or_else(|| {
+            LLMError::InvalidRequest("FallbackLLM has no providers configured".to_string())
+        })?;
+        let result = entry.provider.embed(input).await;
+        match &result {
+            Ok(_) => self.record_success(entry),
+            Err(error) if error.is_retryable() => self.record_failure(entry, error),
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// Those of the first provider, the only one used for embeddings.
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.providers
+            .first()
+            .and_then(|entry| entry.provider.embedding_dimensions())
+    }
+
+    fn max_embedding_batch(&self) -> Option<usize> {
+        self.providers
+            .first()
+            .and_then(|entry| entry.provider.max_embedding_batch())
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for FallbackLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.with_fallback("list_models", |provider| async move {
+            provider.list_models(request).await
+        })
+        .await
+    }
+}
+
+impl LLMProvider for FallbackLLM {
+    fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        self.providers
+            .first()
+            .map(|entry| entry.provider.token_counter())
+            .unwrap_or_else(|| Arc::new(HeuristicTokenCounter::default()))
+    }
+
+    /// The first provider in the chain that can transcribe.
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        self.providers
+            .iter()
+            .find_map(|entry| entry.provider.speech_to_text())
+    }
+
+    /// The first provider in the chain that can synthesize speech.
+    fn text_to_speech(&self) -> Option<&dyn TextToSpeechProvider> {
+        self.providers
+            .iter()
+            .find_map(|entry| entry.provider.text_to_speech())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ToolCall;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    struct MockChatResponse(String);
+
+    impl std::fmt::Display for MockChatResponse {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl ChatResponse for MockChatResponse {
+        fn text(&self) -> Option<String> {
+            Some(self.0.clone())
+        }
+
+        fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+            None
+        }
+    }
+
+    struct MockProvider {
+        reply: &'static str,
+        error: Option<fn() -> LLMError>,
+        calls: AtomicUsize,
+    }
+
+    impl MockProvider {
+        fn ok(reply: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                reply,
+                error: None,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn failing(error: fn() -> LLMError) -> Arc<Self> {
+            Arc::new(Self {
+                reply: "",
+                error: Some(error),
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+
+        fn respond(&self) -> Result<String, LLMError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.error {
+                Some(error) => Err(error()),
+                None => Ok(self.reply.to_string()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ChatProvider for MockProvider {
+        async fn chat(
+            &self,
+            _messages: &[ChatMessage],
+            _tools: Option<&[Tool]>,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<Box<dyn ChatResponse>, LLMError> {
+            Ok(Box::new(MockChatResponse(self.respond()?)))
+        }
+    }
+
+    #[async_trait]
+    impl CompletionProvider for MockProvider {
+        async fn complete(
+            &self,
+            _req: &CompletionRequest,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<CompletionResponse, LLMError> {
+            Ok(CompletionResponse {
+                text: self.respond()?,
+                usage: None,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for MockProvider {
+        async fn embed(&self, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            self.respond()?;
+            Ok(vec![vec![0.5]])
+        }
+    }
+
+    #[async_trait]
+    impl ModelsProvider for MockProvider {}
+
+    impl LLMProvider for MockProvider {}
+
+    fn rate_limited() -> LLMError {
+        LLMError::HttpStatus {
+            status: 429,
+            message: "Too Many Requests".to_string(),
+        }
+    }
+
+    fn unauthorized() -> LLMError {
+        LLMError::AuthError("Invalid API key".to_string())
+    }
+
+    fn messages() -> Vec<ChatMessage> {
+        vec![ChatMessage::user().content("hello").build()]
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_on_transient_error() {
+        let primary = MockProvider::failing(rate_limited);
+        let secondary = MockProvider::ok("from secondary");
+        let llm = FallbackLLM::new()
+            .with_provider("primary", primary.clone())
+            .with_provider("secondary", secondary.clone());
+
+        let response = llm.chat(&messages(), None, None).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("from secondary"));
+        assert_eq!(primary.calls(), 1);
+        assert_eq!(secondary.calls(), 1);
+
+        let health = llm.health();
+        assert_eq!(health[0].consecutive_failures, 1);
+        assert!(health[0].last_error.as_deref().unwrap().contains("429"));
+        assert_eq!(health[1].total_successes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_returns_non_transient_error_without_failover() {
+        let primary = MockProvider::failing(unauthorized);
+        let secondary = MockProvider::ok("from secondary");
+        let llm = FallbackLLM::new()
+            .with_provider("primary", primary.clone())
+            .with_provider("secondary", secondary.clone());
+
+        let error = llm
+            .complete(&CompletionRequest::new("hi"), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, LLMError::AuthError(_)));
+        assert_eq!(secondary.calls(), 0);
+        assert_eq!(llm.health()[0].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cooling_down_provider_is_tried_last() {
+        let primary = MockProvider::failing(rate_limited);
+        let secondary = MockProvider::ok("from secondary");
+        let llm = FallbackLLM::new()
+            .with_provider("primary", primary.clone())
+            .with_provider("secondary", secondary.clone())
+            .with_failure_threshold(2)
+            .with_cooldown(Duration::from_secs(60));
+
+        for _ in 0..2 {
+            llm.chat(&messages(), None, None).await.unwrap();
+        }
+        assert!(llm.health()[0].cooling_down);
+
+        llm.chat(&messages(), None, None).await.unwrap();
+        assert_eq!(primary.calls(), 2);
+        assert_eq!(secondary.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_returns_last_error_when_all_providers_fail() {
+        let llm = FallbackLLM::new()
+            .with_provider("a", MockProvider::failing(rate_limited))
+            .with_provider(
+                "b",
+                MockProvider::failing(|| {
+                    LLMError::ConnectionError("operation timed out".to_string())
+                }),
+            );
+
+        let error = llm.chat(&messages(), None, None).await.unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+
+        let empty = FallbackLLM::new();
+        assert!(matches!(
+            empty.chat(&messages(), None, None).await,
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_use_first_provider_only() {
+        let primary = MockProvider::failing(rate_limited);
+        let secondary = MockProvider::ok("");
+        let llm = FallbackLLM::new()
+            .with_provider("primary", primary.clone())
+            .with_provider("secondary", secondary.clone());
+
+        assert!(llm.embed(vec!["text".to_string()]).await.is_err());
+        assert_eq!(secondary.calls(), 0);
+    }
+}
@@ -0,0 +1,442 @@
+//! JSON schema to GBNF conversion.
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::error::LLMError;
+
+/// Whitespace between JSON tokens, kept short so a model cannot pad forever
+const SPACE: &str = r#"ws ::= | " " | "\n" [ \t]{0,20}"#;
+
+/// Building blocks of JSON values, each followed by optional whitespace
+const PRIMITIVES: [(&str, &str, &[&str]); 9] = [
+    (
+        "char",
+        r#"char ::= [^"\\\x7F\x00-\x1F] | "\\" (["\\/bfnrt] | "u" [0-9a-fA-F]{4})"#,
+        &[],
+    ),
+    (
+        "string",
+        r#"string ::= "\"" char* "\"" ws"#,
+        &["char", "ws"],
+    ),
+    (
+        "number",
+        r#"number ::= "-"? ([0-9] | [1-9] [0-9]{1,15}) ("." [0-9]+)? ([eE] [-+]? [0-9]{1,3})? ws"#,
+        &["ws"],
+    ),
+    (
+        "integer",
+        r#"integer ::= "-"? ([0-9] | [1-9] [0-9]{1,15}) ws"#,
+        &["ws"],
+    ),
+    ("boolean", r#"boolean ::= ("true" | "false") ws"#, &["ws"]),
+    ("null", r#"null ::= "null" ws"#, &["ws"]),
+    (
+        "value",
+        r#"value ::= object | array | string | number | boolean | null"#,
+        &["object", "array", "string", "number", "boolean", "null"],
+    ),
+    (
+        "object",
+        r#"object ::= "{" ws (string ":" ws value ("," ws string ":" ws value)*)? "}" ws"#,
+        &["string", "value", "ws"],
+    ),
+    (
+        "array",
+        r#"array ::= "[" ws (value ("," ws value)*)? "]" ws"#,
+        &["value", "ws"],
+    ),
+];
+
+/// A GBNF grammar, with a `root` rule, of the JSON documents valid for
+/// `schema`.
+///
+/// Covered are `type` (also as a list), `properties` with `required`,
+/// `additionalProperties` of objects without `properties`, `items` with
+/// `minItems` and `maxItems`, `minLength` and `maxLength` of strings,
+/// `enum`, `const`, `anyOf`, `oneOf`, `allOf` of objects, and local `$ref`s
+/// into `$defs` or `definitions`. Required properties come first, followed
+/// by any of the optional ones; undeclared properties are not generated.
+/// Keywords outside this list, such as `pattern` or `minimum`, are not
+/// enforced.
+pub fn json_schema_to_gbnf(schema: &Value) -> Result<String, LLMError> {
+    let mut converter = Converter {
+        root: schema,
+        rules: Vec::new(),
+        refs: HashMap::new(),
+    };
+    let root = converter.visit(schema, "schema")?;
+    converter.rules.push(("root".to_string(), root));
+    let mut grammar = String::new();
+    for (name, body) in converter.rules.iter().rev() {
+        grammar.push_str(&format!("{name} ::= {body}\n"));
+    }
+    Ok(grammar)
+}
+
+struct Converter<'a> {
+    root: &'a Value,
+    /// Rules as name and body, in the order added
+    rules: Vec<(String, String)>,
+    /// Rule names of the `$ref`s converted so far
+    refs: HashMap<String, String>,
+}
+
+impl Converter<'_> {
+    /// A GBNF expression for the values valid for `schema`, with `name` as
+    /// base name for rules it needs
+    fn visit(&mut self, schema: &Value, name: &str) -> Result<String, LLMError> {
+        let Some(object) = schema.as_object() else {
+            // `true` and `{}` allow anything
+            return Ok(self.primitive("value"));
+        };
+        if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+            return self.reference(reference);
+        }
+        if let Some(value) = object.get("const") {
+            return Ok(self.literal(value));
+        }
+        if let Some(values) = object.get("enum").and_then(Value::as_array) {
+            let choices: Vec<String> = values.iter().map(|v| self.literal(v)).collect();
+            return Ok(format!("({})", choices.join(" | ")));
+        }
+        for key in ["anyOf", "oneOf"] {
+            if let Some(schemas) = object.get(key).and_then(Value::as_array) {
+                let choices = schemas
+                    .iter()
+                    .enumerate()
+                    .map(|(i, schema)| self.visit(schema, &format!("{name}-{i}")))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(format!("({})", choices.join(" | ")));
+            }
+        }
+        if let Some(schemas) = object.get("allOf").and_then(Value::as_array) {
+            return self.visit(&self.merge(schemas)?, name);
+        }
+
+        match object.get("type") {
+            Some(Value::Array(types)) => {
+                let choices = types
+                    .iter()
+                    .map(|kind| {
+                        let mut schema = object.clone();
+                        schema.insert("type".to_string(), kind.clone());
+                        self.visit(&Value::Object(schema), name)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(format!("({})", choices.join(" | ")))
+            }
+            Some(Value::String(kind)) => match kind.as_str() {
+                "object" => self.object(schema, name),
+                "array" => self.array(schema, name),
+                "string" => Ok(self.string(schema)),
+                "number" | "integer" | "boolean" | "null" => Ok(self.primitive(kind)),
+                other => Err(LLMError::InvalidRequest(format!(
+                    "Unsupported JSON schema type {other}"
+                ))),
+            },
+            _ if object.contains_key("properties") => self.object(schema, name),
+            _ if object.contains_key("items") => self.array(schema, name),
+            _ => Ok(self.primitive("value")),
+        }
+    }
+
+    fn object(&mut self, schema: &Value, name: &str) -> Result<String, LLMError> {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+            let value = match schema.get("additionalProperties") {
+                Some(Value::Object(values)) => {
+                    self.visit(&Value::Object(values.clone()), &format!("{name}-value"))?
+                }
+                _ => return Ok(self.primitive("object")),
+            };
+            let string = self.primitive("string");
+            let pair = format!("{string} \":\" ws {value}");
+            let body = format!("\"{{\" ws ({pair} (\",\" ws {pair})*)? \"}}\" ws");
+            return Ok(self.add_rule(name, &body));
+        };
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|keys| keys.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut required_pairs = Vec::new();
+        let mut optional_pairs = Vec::new();
+        for (key, property) in properties {
+            let value = self.visit(property, &format!("{name}-{}", rule_name(key)))?;
+            let pair = format!(
+                "{} \":\" ws {value}",
+                self.literal(&Value::String(key.clone()))
+            );
+            if required.contains(&key.as_str()) {
+                required_pairs.push(pair);
+            } else {
+                optional_pairs.push(pair);
+            }
+        }
+
+        let mut body = required_pairs.join(" \",\" ws ");
+        if !optional_pairs.is_empty() {
+            // Each optional property may start the list, followed by any of
+            // the later ones
+            let choices: Vec<String> = (0..optional_pairs.len())
+                .map(|first| {
+                    let mut choice = optional_pairs[first].clone();
+                    for pair in &optional_pairs[first + 1..] {
+                        choice.push_str(&format!(" (\",\" ws {pair})?"));
+                    }
+                    choice
+                })
+                .collect();
+            let optional = if required_pairs.is_empty() {
+                format!("({})?", choices.join(" | "))
+            } else {
+                format!("(\",\" ws ({}))?", choices.join(" | "))
+            };
+            body = format!("{body} {optional}");
+        }
+        self.primitive("ws");
+        let body = format!("\"{{\" ws {} \"}}\" ws", body.trim());
+        Ok(self.add_rule(name, &body))
+    }
+
+    fn array(&mut self, schema: &Value, name: &str) -> Result<String, LLMError> {
+        let item = match schema.get("items") {
+            Some(items) => self.visit(items, &format!("{name}-item"))?,
+            None => self.primitive("value"),
+        };
+        let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+        let max = schema.get("maxItems").and_then(Value::as_u64);
+        let rest = format!("(\",\" ws {item})");
+        let items = match (min, max) {
+            (_, Some(0)) => String::new(),
+            (0, None) => format!("({item} {rest}*)?"),
+            (0, Some(max)) => format!("({item} {rest}{{0,{}}})?", max - 1),
+            (min, None) => format!("{item} {rest}{{{},}}", min - 1),
+            (min, Some(max)) => format!("{item} {rest}{{{},{}}}", min - 1, max.max(min) - 1),
+        };
+        self.primitive("ws");
+        let body = format!("\"[\" ws {items} \"]\" ws");
+        Ok(self.add_rule(name, &body))
+    }
+
+    fn string(&mut self, schema: &Value) -> String {
+        let min = schema.get("minLength").and_then(Value::as_u64);
+        let max = schema.get("maxLength").and_then(Value::as_u64);
+        if min.is_none() && max.is_none() {
+            return self.primitive("string");
+        }
+        self.primitive("char");
+        self.primitive("ws");
+        let min = min.unwrap_or(0);
+        let repeat = match max {
+            Some(max) => format!("{{{min},{}}}", max.max(min)),
+            None => format!("{{{min},}}"),
+        };
+        format!("\"\\\"\" char{repeat} \"\\\"\" ws")
+    }
+
+    fn reference(&mut self, reference: &str) -> Result<String, LLMError> {
+        if let Some(name) = self.refs.get(reference) {
+            return Ok(name.clone());
+        }
+        let pointer = reference.strip_prefix('#').ok_or_else(|| {
+            LLMError::InvalidRequest(format!(
+                "Only local schema references are supported: {reference}"
+            ))
+        })?;
+        let target = self.root.pointer(pointer).ok_or_else(|| {
+            LLMError::InvalidRequest(format!("Unresolved schema reference {reference}"))
+        })?;
+        let base = rule_name(reference.rsplit('/').next().unwrap_or("ref"));
+        let name = self.unique_name(&format!("ref-{base}"));
+        // Registered before the target is converted, so recursive
+        // references point back to this rule
+        self.refs.insert(reference.to_string(), name.clone());
+        self.rules.push((name.clone(), String::new()));
+        let body = self.visit(target, &name)?;
+        if let Some(rule) = self.rules.iter_mut().find(|(rule, _)| *rule == name) {
+            rule.1 = body;
+        }
+        Ok(name)
+    }
+
+    /// `allOf` of object schemas as one object schema
+    fn merge(&self, schemas: &[Value]) -> Result<Value, LLMError> {
+        let mut properties = serde_json::Map::new();
+        let mut required = Vec::new();
+        for schema in schemas {
+            let schema = match schema.get("$ref").and_then(Value::as_str) {
+                Some(reference) => reference
+                    .strip_prefix('#')
+                    .and_then(|pointer| self.root.pointer(pointer))
+                    .ok_or_else(|| {
+                        LLMError::InvalidRequest(format!("Unresolved schema reference {reference}"))
+                    })?,
+                None => schema,
+            };
+            if let Some(more) = schema.get("properties").and_then(Value::as_object) {
+                properties.extend(more.clone());
+            }
+            if let Some(more) = schema.get("required").and_then(Value::as_array) {
+                required.extend(more.iter().cloned());
+            }
+        }
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        }))
+    }
+
+    /// The JSON serialization of `value` as a GBNF literal
+    fn literal(&mut self, value: &Value) -> String {
+        self.primitive("ws");
+        let mut literal = String::from("\"");
+        for c in value.to_string().chars() {
+            match c {
+                '"' => literal.push_str("\\\""),
+                '\\' => literal.push_str("\\\\"),
+                '\n' => literal.push_str("\\n"),
+                '\r' => literal.push_str("\\r"),
+                '\t' => literal.push_str("\\t"),
+                c => literal.push(c),
+            }
+        }
+        literal.push_str("\" ws");
+        literal
+    }
+
+    /// Name of the built-in rule `name`, added with the rules it uses
+    fn primitive(&mut self, name: &str) -> String {
+        if self.rules.iter().any(|(rule, _)| rule == name) {
+            return name.to_string();
+        }
+        if name == "ws" {
+            let body = SPACE.split_once(" ::= ").map(|(_, body)| body);
+            self.rules
+                .push(("ws".to_string(), body.unwrap_or_default().to_string()));
+            return name.to_string();
+        }
+        if let Some((_, rule, uses)) = PRIMITIVES.iter().find(|(rule, ..)| *rule == name) {
+            let body = rule.split_once(" ::= ").map(|(_, body)| body);
+            self.rules
+                .push((name.to_string(), body.unwrap_or_default().to_string()));
+            for used in *uses {
+                self.primitive(used);
+            }
+        }
+        name.to_string()
+    }
+
+    /// Add a rule with a name not taken yet, returning that name
+    fn add_rule(&mut self, name: &str, body: &str) -> String {
+        let name = self.unique_name(name);
+        self.rules.push((name.clone(), body.to_string()));
+        name
+    }
+
+    fn unique_name(&self, base: &str) -> String {
+        let taken = |name: &str| {
+            name == "root"
+                || self.rules.iter().any(|(rule, _)| rule == name)
+                || PRIMITIVES.iter().any(|(rule, ..)| *rule == name)
+        };
+        if !taken(base) {
+            return base.to_string();
+        }
+        (2..)
+            .map(|i| format!("{base}-{i}"))
+            .find(|name| !taken(name))
+            .unwrap_or_default()
+    }
+}
+
+/// `name` with characters GBNF rule names cannot have replaced
+fn rule_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    if name.is_empty() {
+        "property".to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::Grammar;
+    use serde_json::json;
+
+    fn accepts(schema: &Value, text: &str) -> bool {
+        let grammar = Grammar::from_json_schema(schema).unwrap();
+        let mut matcher = grammar.matcher();
+        matcher.accept_str(text) && matcher.is_complete()
+    }
+
+    #[test]
+    fn test_refs_enums_and_bounded_arrays() {
+        let schema = json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "kind": {"const": "leaf"},
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/node"}},
+                    },
+                    "required": ["kind"],
+                },
+            },
+            "type": "object",
+            "properties": {
+                "tree": {"$ref": "#/$defs/node"},
+                "size": {"type": ["integer", "null"]},
+                "labels": {"type": "array", "items": {"type": "string", "maxLength": 2}, "minItems": 1, "maxItems": 2},
+            },
+            "required": ["tree", "size", "labels"],
+        });
+        assert!(accepts(
+            &schema,
+            r#"{"labels": ["ab"], "size": null, "tree": {"kind": "leaf", "children": [{"kind": "leaf"}]}}"#
+        ));
+        assert!(accepts(
+            &schema,
+            r#"{"labels":["a","b"],"size":12,"tree":{"kind":"leaf"}}"#
+        ));
+        assert!(!accepts(
+            &schema,
+            r#"{"labels":[],"size":12,"tree":{"kind":"leaf"}}"#
+        ));
+        assert!(!accepts(
+            &schema,
+            r#"{"labels":["abc"],"size":12,"tree":{"kind":"leaf"}}"#
+        ));
+        assert!(!accepts(
+            &schema,
+            r#"{"labels":["a"],"size":1.5,"tree":{"kind":"leaf"}}"#
+        ));
+        assert!(!accepts(
+            &schema,
+            r#"{"labels":["a"],"size":1,"tree":{"kind":"branch"}}"#
+        ));
+    }
+
+    #[test]
+    fn test_free_form_values_and_invalid_refs() {
+        assert!(accepts(&json!({}), r#"[1, {"a": [true, null]}, "x"]"#));
+        assert!(accepts(
+            &json!({"type": "object", "additionalProperties": {"type": "number"}}),
+            r#"{"a": 1, "b": 2.5}"#
+        ));
+        assert!(!accepts(
+            &json!({"type": "object", "additionalProperties": {"type": "number"}}),
+            r#"{"a": "1"}"#
+        ));
+        assert!(json_schema_to_gbnf(&json!({"$ref": "#/$defs/missing"})).is_err());
+        assert!(json_schema_to_gbnf(&json!({"$ref": "other.json#/a"})).is_err());
+    }
+}
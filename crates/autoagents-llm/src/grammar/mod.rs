@@ -0,0 +1,715 @@
+//! Grammars that constrain what a local model can generate.
+//!
+//! Cloud providers enforce a JSON schema on their side. Local backends get
+//! the same guarantee by checking every candidate token against a
+//! [`Grammar`] while sampling, so only text the grammar can still complete
+//! is ever produced.
+//!
+//! Grammars are written in GBNF, the format of llama.cpp, or derived from a
+//! JSON schema:
+//!
+//! ```rust
+//! use autoagents_llm::grammar::Grammar;
+//!
+//! let grammar = Grammar::parse(r#"root ::= "yes" | "no""#).unwrap();
+//! let mut matcher = grammar.matcher();
+//! assert!(matcher.accept_str("ye"));
+//! assert!(!matcher.is_complete());
+//! assert!(matcher.accept_str("s"));
+//! assert!(matcher.is_complete());
+//! ```
+//!
+//! Supported GBNF: `"literals"`, character classes such as `[^a-z\n]`, `.`,
+//! rule references, groups, alternatives with `|`, the repetitions `*`, `+`,
+//! `?`, `{n}`, `{m,}` and `{m,n}`, and `#` comments. The rule named `root`
+//! is where matching starts. Left-recursive rules are not supported.
+
+mod json_schema;
+
+pub use json_schema::json_schema_to_gbnf;
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::LLMError;
+
+/// Most rule references followed without consuming input, which keeps left
+/// recursion and repeated empty matches from expanding forever
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Element {
+    /// One character in the ranges, or outside of them when negated
+    Chars {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    /// A match of another rule
+    Rule(usize),
+}
+
+impl Element {
+    fn literal(c: char) -> Self {
+        Element::Chars {
+            ranges: vec![(c, c)],
+            negated: false,
+        }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Element::Chars { ranges, negated } => {
+                ranges.iter().any(|&(low, high)| low <= c && c <= high) != *negated
+            }
+            Element::Rule(_) => false,
+        }
+    }
+}
+
+/// Alternatives of a rule, each a sequence of elements
+type Alternatives = Vec<Vec<Element>>;
+
+/// A compiled grammar. Cloning is cheap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    rules: Arc<[Alternatives]>,
+    root: usize,
+}
+
+impl Grammar {
+    /// Parse a GBNF grammar with a `root` rule.
+    pub fn parse(gbnf: &str) -> Result<Self, LLMError> {
+        Parser::new(gbnf).parse()
+    }
+
+    /// The grammar of the JSON values valid for `schema`, see
+    /// [`json_schema_to_gbnf`].
+    pub fn from_json_schema(schema: &Value) -> Result<Self, LLMError> {
+        Self::parse(&json_schema_to_gbnf(schema)?)
+    }
+
+    /// A matcher at the start of the grammar
+    pub fn matcher(&self) -> GrammarMatcher {
+        let mut stacks = Vec::new();
+        for alt in 0..self.rules[self.root].len() {
+            self.expand(
+                vec![Position {
+                    rule: self.root,
+                    alt,
+                    index: 0,
+                }],
+                &mut stacks,
+                0,
+            );
+        }
+        stacks.sort();
+        stacks.dedup();
+        GrammarMatcher {
+            grammar: self.clone(),
+            stacks,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Push the stacks reachable from `stack` without consuming input onto
+    /// `out`: those waiting for a character, and the empty stack once the
+    /// whole grammar matched.
+    fn expand(&self, mut stack: Stack, out: &mut Vec<Stack>, depth: usize) {
+        let Some(top) = stack.last().copied() else {
+            out.push(stack);
+            return;
+        };
+        let sequence = &self.rules[top.rule][top.alt];
+        match sequence.get(top.index) {
+            None => {
+                stack.pop();
+                self.expand(stack, out, depth);
+            }
+            Some(Element::Chars { .. }) => out.push(stack),
+            Some(Element::Rule(rule)) => {
+                if depth >= MAX_DEPTH {
+                    return;
+                }
+                // Continue after the reference once the rule matched, and
+                // drop the frame when nothing follows so repetitions don't
+                // grow the stack
+                stack.pop();
+                if top.index + 1 < sequence.len() {
+                    stack.push(Position {
+                        index: top.index + 1,
+                        ..top
+                    });
+                }
+                for alt in 0..self.rules[*rule].len() {
+                    let mut next = stack.clone();
+                    next.push(Position {
+                        rule: *rule,
+                        alt,
+                        index: 0,
+                    });
+                    self.expand(next, out, depth + 1);
+                }
+            }
+        }
+    }
+
+    /// The stacks after `c` was matched on any of `stacks`
+    fn advance(&self, stacks: &[Stack], c: char) -> Vec<Stack> {
+        let mut next = Vec::new();
+        for stack in stacks {
+            let Some(top) = stack.last() else {
+                continue;
+            };
+            if !self.rules[top.rule][top.alt][top.index].matches(c) {
+                continue;
+            }
+            let mut stack = stack.clone();
+            if let Some(top) = stack.last_mut() {
+                top.index += 1;
+            }
+            self.expand(stack, &mut next, 0);
+        }
+        next.sort();
+        next.dedup();
+        next
+    }
+}
+
+/// Where matching is in an alternative of a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Position {
+    rule: usize,
+    alt: usize,
+    index: usize,
+}
+
+/// The rules a match is nested in, innermost last
+type Stack = Vec<Position>;
+
+/// How far text has matched a [`Grammar`].
+///
+/// Text is fed as characters or as the bytes of tokens; a token may end in
+/// the middle of a UTF-8 character, which is then completed by the next one.
+#[derive(Debug, Clone)]
+pub struct GrammarMatcher {
+    grammar: Grammar,
+    stacks: Vec<Stack>,
+    /// Leading bytes of an unfinished UTF-8 character
+    partial: Vec<u8>,
+}
+
+impl GrammarMatcher {
+    /// Match `c`, returning whether the grammar allows it. Nothing changes
+    /// when it doesn't.
+    pub fn accept_char(&mut self, c: char) -> bool {
+        self.accept_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Match all of `text`, returning whether the grammar allows it. Nothing
+    /// changes when it doesn't.
+    pub fn accept_str(&mut self, text: &str) -> bool {
+        self.accept_bytes(text.as_bytes())
+    }
+
+    /// Match `bytes`, returning whether the grammar allows them. Trailing
+    /// bytes of an unfinished character are held until it completes. Nothing
+    /// changes when they aren't allowed.
+    pub fn accept_bytes(&mut self, bytes: &[u8]) -> bool {
+        match self.try_bytes(bytes) {
+            Some((stacks, partial)) => {
+                self.stacks = stacks;
+                self.partial = partial;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the grammar allows `bytes` next
+    pub fn allows_bytes(&self, bytes: &[u8]) -> bool {
+        self.try_bytes(bytes).is_some()
+    }
+
+    /// Whether the text so far is a complete match, so generation may stop
+    pub fn is_complete(&self) -> bool {
+        self.partial.is_empty() && self.stacks.iter().any(Vec::is_empty)
+    }
+
+    /// Whether the grammar allows more text
+    pub fn can_continue(&self) -> bool {
+        self.stacks.iter().any(|stack| !stack.is_empty())
+    }
+
+    fn try_bytes(&self, bytes: &[u8]) -> Option<(Vec<Stack>, Vec<u8>)> {
+        let mut pending = self.partial.clone();
+        pending.extend_from_slice(bytes);
+        let (text, partial) = match std::str::from_utf8(&pending) {
+            Ok(text) => (text, Vec::new()),
+            // Only a character cut off at the end may be completed later
+            Err(e) if e.error_len().is_none() => {
+                let valid = e.valid_up_to();
+                let text = std::str::from_utf8(&pending[..valid]).ok()?;
+                (text, pending[valid..].to_vec())
+            }
+            Err(_) => return None,
+        };
+        let mut stacks = self.stacks.clone();
+        for c in text.chars() {
+            stacks = self.grammar.advance(&stacks, c);
+            if stacks.is_empty() {
+                return None;
+            }
+        }
+        if !partial.is_empty() && !stacks.iter().any(|stack| !stack.is_empty()) {
+            return None;
+        }
+        Some((stacks, partial))
+    }
+}
+
+/// Recursive descent parser for GBNF
+struct Parser {
+    source: Vec<char>,
+    pos: usize,
+    ids: HashMap<String, usize>,
+    names: Vec<String>,
+    rules: Vec<Option<Alternatives>>,
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Self {
+            source: source.chars().collect(),
+            pos: 0,
+            ids: HashMap::new(),
+            names: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Grammar, LLMError> {
+        loop {
+            self.skip_space();
+            if self.peek().is_none() {
+                break;
+            }
+            let name = self
+                .name()
+                .ok_or_else(|| self.error("expected a rule name"))?;
+            self.skip_space();
+            if !self.eat_str("::=") {
+                return Err(self.error(&format!("expected ::= after {name}")));
+            }
+            let id = self.rule_id(&name);
+            if self.rules[id].is_some() {
+                return Err(self.error(&format!("rule {name} is defined twice")));
+            }
+            let alternatives = self.alternatives()?;
+            self.rules[id] = Some(alternatives);
+        }
+
+        let root = *self
+            .ids
+            .get("root")
+            .ok_or_else(|| LLMError::InvalidRequest("Grammar has no root rule".to_string()))?;
+        let rules = self
+            .rules
+            .into_iter()
+            .zip(&self.names)
+            .map(|(rule, name)| {
+                rule.ok_or_else(|| {
+                    LLMError::InvalidRequest(format!("Grammar rule {name} is not defined"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Grammar {
+            rules: rules.into(),
+            root,
+        })
+    }
+
+    fn alternatives(&mut self) -> Result<Alternatives, LLMError> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Element>, LLMError> {
+        let mut sequence = Vec::new();
+        loop {
+            self.skip_space();
+            let start = sequence.len();
+            match self.peek() {
+                None | Some('|') | Some(')') => break,
+                _ if self.at_rule_start() => break,
+                Some('"') => {
+                    self.pos += 1;
+                    while !self.eat('"') {
+                        let c = self
+                            .char_literal()?
+                            .ok_or_else(|| self.error("unterminated string"))?;
+                        sequence.push(Element::literal(c));
+                    }
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    sequence.push(self.char_class()?);
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    sequence.push(Element::Chars {
+                        ranges: Vec::new(),
+                        negated: true,
+                    });
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let alternatives = self.alternatives()?;
+                    self.skip_space();
+                    if !self.eat(')') {
+                        return Err(self.error("expected )"));
+                    }
+                    sequence.push(Element::Rule(self.anonymous_rule(alternatives)));
+                }
+                _ => {
+                    let name = self
+                        .name()
+                        .ok_or_else(|| self.error("unexpected character"))?;
+                    sequence.push(Element::Rule(self.rule_id(&name)));
+                }
+            }
+            self.repetition(&mut sequence, start)?;
+        }
+        Ok(sequence)
+    }
+
+    /// Apply a repetition operator following the item `sequence[start..]`
+    fn repetition(&mut self, sequence: &mut Vec<Element>, start: usize) -> Result<(), LLMError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self.number()?;
+                let max = if self.eat(',') {
+                    self.skip_space();
+                    if self.peek() == Some('}') {
+                        None
+                    } else {
+                        Some(self.number()?)
+                    }
+                } else {
+                    Some(min)
+                };
+                self.skip_space();
+                if self.peek() != Some('}') {
+                    return Err(self.error("expected }"));
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err(self.error("repetition maximum is below its minimum"));
+                }
+                (min, max)
+            }
+            _ => return Ok(()),
+        };
+        self.pos += 1;
+
+        let item: Vec<Element> = sequence.drain(start..).collect();
+        for _ in 0..min {
+            sequence.extend(item.iter().cloned());
+        }
+        match max {
+            // item*: rest ::= item rest |
+            None => {
+                let rule = self.reserve_rule();
+                let mut repeat = item;
+                repeat.push(Element::Rule(rule));
+                self.rules[rule] = Some(vec![repeat, Vec::new()]);
+                sequence.push(Element::Rule(rule));
+            }
+            // Nested optionals: opt ::= item opt' |
+            Some(max) if max > min => {
+                let mut optional: Option<usize> = None;
+                for _ in min..max {
+                    let mut repeat = item.clone();
+                    repeat.extend(optional.map(Element::Rule));
+                    optional = Some(self.anonymous_rule(vec![repeat, Vec::new()]));
+                }
+                sequence.extend(optional.map(Element::Rule));
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+
+    fn char_class(&mut self) -> Result<Element, LLMError> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        while !self.eat(']') {
+            let low = self
+                .char_literal()?
+                .ok_or_else(|| self.error("unterminated character class"))?;
+            let high = if self.peek() == Some('-') && self.source.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+                self.char_literal()?
+                    .ok_or_else(|| self.error("unterminated character class"))?
+            } else {
+                low
+            };
+            ranges.push((low, high));
+        }
+        Ok(Element::Chars { ranges, negated })
+    }
+
+    /// A possibly escaped character of a literal or class
+    fn char_literal(&mut self) -> Result<Option<char>, LLMError> {
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+        self.pos += 1;
+        if c != '\\' {
+            return Ok(Some(c));
+        }
+        let escaped = self.peek().ok_or_else(|| self.error("dangling escape"))?;
+        self.pos += 1;
+        let hex_digits = match escaped {
+            'n' => return Ok(Some('\n')),
+            'r' => return Ok(Some('\r')),
+            't' => return Ok(Some('\t')),
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            other => return Ok(Some(other)),
+        };
+        let end = self.pos + hex_digits;
+        let code: String = self
+            .source
+            .get(self.pos..end)
+            .ok_or_else(|| self.error("truncated escape"))?
+            .iter()
+            .collect();
+        self.pos = end;
+        u32::from_str_radix(&code, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .map(Some)
+            .ok_or_else(|| self.error(&format!("invalid escape \\{escaped}{code}")))
+    }
+
+    fn number(&mut self) -> Result<usize, LLMError> {
+        self.skip_space();
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.source[start..self.pos].iter().collect();
+        digits.parse().map_err(|_| self.error("expected a number"))
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            self.pos += 1;
+        }
+        (self.pos > start).then(|| self.source[start..self.pos].iter().collect())
+    }
+
+    /// Whether a new rule, `name ::=`, starts here
+    fn at_rule_start(&self) -> bool {
+        let mut pos = self.pos;
+        while self
+            .source
+            .get(pos)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        {
+            pos += 1;
+        }
+        if pos == self.pos {
+            return false;
+        }
+        while self.source.get(pos).is_some_and(|c| c.is_whitespace()) {
+            pos += 1;
+        }
+        self.source.get(pos..pos + 3) == Some(&[':', ':', '='])
+    }
+
+    fn rule_id(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.reserve_rule();
+        self.names[id] = name.to_string();
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    fn reserve_rule(&mut self) -> usize {
+        self.rules.push(None);
+        self.names.push(format!("<generated {}>", self.rules.len()));
+        self.rules.len() - 1
+    }
+
+    fn anonymous_rule(&mut self, alternatives: Alternatives) -> usize {
+        let id = self.reserve_rule();
+        self.rules[id] = Some(alternatives);
+        id
+    }
+
+    /// Skip whitespace and comments
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if c.is_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_space_if_structural(c);
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Structural characters may be preceded by space; characters inside
+    /// literals and classes may not
+    fn skip_space_if_structural(&mut self, c: char) {
+        if matches!(c, '|' | ')' | ',') {
+            self.skip_space();
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let chars: Vec<char> = s.chars().collect();
+        if self.source.get(self.pos..self.pos + chars.len()) == Some(chars.as_slice()) {
+            self.pos += chars.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> LLMError {
+        let line = self.source[..self.pos.min(self.source.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1;
+        LLMError::InvalidRequest(format!("Invalid grammar at line {line}: {message}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn matches(grammar: &Grammar, text: &str) -> bool {
+        let mut matcher = grammar.matcher();
+        matcher.accept_str(text) && matcher.is_complete()
+    }
+
+    #[test]
+    fn test_gbnf_operators() {
+        let grammar = Grammar::parse(
+            r#"
+            # A list of small numbers
+            root ::= "[" (num ("," num)*)? "]"
+            num  ::= [1-9] [0-9]{0,2} | "0"
+            "#,
+        )
+        .unwrap();
+        assert!(matches(&grammar, "[]"));
+        assert!(matches(&grammar, "[1,20,300]"));
+        assert!(!matches(&grammar, "[1000]"));
+        assert!(!matches(&grammar, "[01]"));
+        assert!(!matches(&grammar, "[1,"));
+
+        let mut matcher = grammar.matcher();
+        assert!(!matcher.accept_str("x"));
+        assert!(matcher.accept_str("[4"));
+        assert!(matcher.can_continue());
+    }
+
+    #[test]
+    fn test_char_classes_and_escapes() {
+        let grammar = Grammar::parse(r#"root ::= [^"\\\n]+ "\"" [a\-z]? "é"{2} ."#).unwrap();
+        assert!(matches(&grammar, "hello\"-ééx"));
+        assert!(matches(&grammar, "a b\"zéé\n"));
+        assert!(!matches(&grammar, "a\nb\"éé."));
+        assert!(!matches(&grammar, "ab\"bééx"));
+    }
+
+    #[test]
+    fn test_bytes_split_inside_a_character() {
+        let grammar = Grammar::parse(r#"root ::= "é" "!""#).unwrap();
+        let bytes = "é!".as_bytes();
+        let mut matcher = grammar.matcher();
+        assert!(matcher.accept_bytes(&bytes[..1]));
+        assert!(!matcher.is_complete());
+        assert!(!matcher.allows_bytes(&[0xFF]));
+        assert!(matcher.accept_bytes(&bytes[1..]));
+        assert!(matcher.is_complete());
+    }
+
+    #[test]
+    fn test_invalid_grammars() {
+        for source in [
+            "root ::= missing",
+            "start ::= \"a\"",
+            "root ::= \"a",
+            "root ::= [a-",
+            "root ::= \"a\"{3,1}",
+            "root ::= \"a\"\nroot ::= \"b\"",
+        ] {
+            assert!(
+                matches!(Grammar::parse(source), Err(LLMError::InvalidRequest(_))),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_schema_grammar() {
+        let grammar = Grammar::from_json_schema(&json!({
+            "type": "object",
+            "properties": {
+                "answer": {"type": "string"},
+                "confidence": {"type": "number"},
+                "tags": {"type": "array", "items": {"enum": ["a", "b"]}},
+            },
+            "required": ["answer"],
+        }))
+        .unwrap();
+        assert!(matches(&grammar, r#"{"answer": "yes"}"#));
+        assert!(matches(
+            &grammar,
+            "{\n  \"answer\": \"y\\\"s\",\n  \"confidence\": -0.5e3,\n  \"tags\": [\"a\", \"b\"]\n}"
+        ));
+        assert!(matches(&grammar, r#"{"answer":"x","tags":[]}"#));
+        assert!(!matches(&grammar, r#"{"confidence": 1}"#));
+        assert!(!matches(&grammar, r#"{"answer": 1}"#));
+        assert!(!matches(&grammar, r#"{"answer": "x", "tags": ["c"]}"#));
+        assert!(!matches(&grammar, r#"{"answer": "x",}"#));
+    }
+}
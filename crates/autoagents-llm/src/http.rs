@@ -0,0 +1,239 @@
+//! Settings of the HTTP client backends send their requests with.
+//!
+//! [`HttpOptions`] covers what sits between the process and the provider:
+//! connect and read timeouts, connection pooling, an HTTP(S) proxy, headers
+//! sent with every request, extra trusted root certificates and, for
+//! internal endpoints only, turning off certificate verification. They are
+//! set through [`LLMBuilder`](crate::builder::LLMBuilder) and applied when
+//! the backend is built; invalid values fail the build.
+//!
+//! Clients are shared: every backend built with the same settings sends its
+//! requests through one client, and so reuses its pool of open connections
+//! instead of paying for a new TCP and TLS handshake.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, Proxy};
+
+use crate::error::LLMError;
+
+/// Settings and overall timeout a shared client was built with
+type ClientKey = (HttpOptions, Option<u64>);
+
+/// Timeouts, pooling, proxy, default headers and TLS settings of a
+/// backend's HTTP client.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HttpOptions {
+    /// Longest wait for a connection to be established
+    pub connect_timeout: Option<Duration>,
+    /// Longest wait between two reads of a response, which unlike the
+    /// overall timeout does not cut off long streams that keep producing
+    pub read_timeout: Option<Duration>,
+    /// How long an unused connection is kept open for reuse
+    pub pool_idle_timeout: Option<Duration>,
+    /// Most unused connections kept open per host
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Proxy URL for all requests, e.g. "http://proxy.internal:3128"
+    pub proxy: Option<String>,
+    /// Headers added to every request, in addition to the backend's own
+    pub headers: Vec<(String, String)>,
+    /// PEM-encoded certificates trusted besides the system roots
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Accept any server certificate. Only for internal endpoints with
+    /// self-signed certificates, as it allows interception of the traffic.
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl HttpOptions {
+    /// Whether nothing differs from a plain client
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// A client with these settings and an overall request timeout
+    pub fn client(&self, timeout_seconds: Option<u64>) -> Result<Client, LLMError> {
+        let mut builder = Client::builder();
+        if let Some(seconds) = timeout_seconds {
+            builder = builder.timeout(Duration::from_secs(seconds));
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|e| LLMError::InvalidRequest(format!("Invalid proxy URL: {e}")))?;
+            builder = builder.proxy(proxy);
+        }
+        if !self.headers.is_empty() {
+            builder = builder.default_headers(self.header_map()?);
+        }
+        for pem in &self.root_certificates {
+            let certificate = Certificate::from_pem(pem)
+                .map_err(|e| LLMError::InvalidRequest(format!("Invalid root certificate: {e}")))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        builder
+            .danger_accept_invalid_certs(self.danger_accept_invalid_certs)
+            .build()
+            .map_err(|e| LLMError::HttpError(format!("Failed to build HTTP client: {e}")))
+    }
+
+    /// The client shared by all backends with these settings and timeout,
+    /// built on first use.
+    pub fn shared_client(&self, timeout_seconds: Option<u64>) -> Result<Client, LLMError> {
+        static CLIENTS: OnceLock<Mutex<HashMap<ClientKey, Client>>> = OnceLock::new();
+        let key = (self.clone(), timeout_seconds);
+        let mut clients = CLIENTS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(client) = clients.get(&key) {
+            return Ok(client.clone());
+        }
+        let client = self.client(timeout_seconds)?;
+        clients.insert(key, client.clone());
+        Ok(client)
+    }
+
+    /// Point `client` at the shared client for these settings.
+    #[allow(dead_code)]
+    pub(crate) fn configure(
+        &self,
+        client: &mut Client,
+        timeout_seconds: Option<u64>,
+    ) -> Result<(), LLMError> {
+        if !self.is_default() {
+            *client = self.shared_client(timeout_seconds)?;
+        }
+        Ok(())
+    }
+
+    fn header_map(&self) -> Result<HeaderMap, LLMError> {
+        let mut headers = HeaderMap::with_capacity(self.headers.len());
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                LLMError::InvalidRequest(format!("Invalid header name {name}: {e}"))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                LLMError::InvalidRequest(format!("Invalid value for header {name}: {e}"))
+            })?;
+            headers.append(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+/// The shared client with default settings, which backends start out with
+pub(crate) fn default_client(timeout_seconds: Option<u64>) -> Client {
+    HttpOptions::default()
+        .shared_client(timeout_seconds)
+        .expect("Failed to build reqwest Client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_settings_fail_the_client() {
+        let proxy = HttpOptions {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            proxy.client(None),
+            Err(LLMError::InvalidRequest(_))
+        ));
+
+        let header = HttpOptions {
+            headers: vec![("X-Title".to_string(), "line\nbreak".to_string())],
+            ..Default::default()
+        };
+        assert!(matches!(
+            header.client(None),
+            Err(LLMError::InvalidRequest(_))
+        ));
+
+        let certificate = HttpOptions {
+            root_certificates: vec![b"not a certificate".to_vec()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            certificate.client(None),
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_ends_a_stalled_response() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            // Accept and never answer
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(socket);
+        });
+
+        let options = HttpOptions {
+            read_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let err = options
+            .shared_client(None)
+            .unwrap()
+            .get(url)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout(), "{err}");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_default_headers_are_sent() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request).to_ascii_lowercase()
+        });
+
+        let options = HttpOptions {
+            headers: vec![("X-Title".to_string(), "AutoAgents".to_string())],
+            ..Default::default()
+        };
+        options
+            .client(Some(5))
+            .unwrap()
+            .get(url)
+            .send()
+            .await
+            .unwrap();
+        assert!(server.await.unwrap().contains("x-title: autoagents\r\n"));
+    }
+}
@@ -7,6 +7,8 @@
 //! - Chat-based interactions
 //! - Text completion
 //! - Embeddings generation
+//! - Document reranking
+//! - Speech transcription and synthesis
 //! - Multiple providers (OpenAI, Anthropic, etc.)
 //! - Request validation and retry logic
 //!
@@ -15,15 +17,26 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Speech to text and text to speech
+pub mod audio;
+
 /// Backend implementations for supported LLM providers like OpenAI, Anthropic, etc.
 pub mod backends;
 
 /// Builder pattern for configuring and instantiating LLM providers
 pub mod builder;
 
+/// Opt-in caching of provider responses
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cache;
+
 /// Chat-based interactions with language models (e.g. ChatGPT style)
 pub mod chat;
 
+/// Prompt formatting for local models from their Hugging Face chat template
+#[cfg(feature = "chat_template")]
+pub mod chat_template;
+
 /// Text completion capabilities (e.g. GPT-3 style completion)
 pub mod completion;
 
@@ -36,15 +49,55 @@ pub mod error;
 /// Evaluator for LLM providers
 pub mod evaluator;
 
+/// Failover across an ordered chain of providers
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fallback;
+
+/// Grammars and JSON schemas that constrain local generation
+pub mod grammar;
+
+/// Load balancing across keys and endpoints of the same provider
+#[cfg(not(target_arch = "wasm32"))]
+pub mod load_balancer;
+
+/// Client-side request and token rate limiting
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rate_limit;
+
+/// Retry with backoff for provider requests
+#[cfg(not(target_arch = "wasm32"))]
+pub mod retry;
+
 /// Secret store for storing API keys and other sensitive information
 #[cfg(not(target_arch = "wasm32"))]
 pub mod secret_store;
 
+/// Proxy, default header and TLS settings of the HTTP client
+#[cfg(not(target_arch = "wasm32"))]
+pub mod http;
+
+/// Middleware chains that see every request to a provider
+#[cfg(not(target_arch = "wasm32"))]
+pub mod middleware;
+
 /// Listing models support
 pub mod models;
 
+/// Content moderation of inputs and outputs
+pub mod moderation;
+
+/// Relevance scoring of documents against a query
+pub mod rerank;
+
+/// Headers scoped to an async task and forwarded with every provider request
+#[cfg(not(target_arch = "wasm32"))]
+pub mod request_context;
+
 pub mod providers;
 
+/// Token counting for context budgeting
+pub mod tokenizer;
+
 //Re-export for convenience
 pub use async_trait::async_trait;
 
@@ -59,6 +112,33 @@ pub trait LLMProvider:
     + Sync
     + 'static
 {
+    /// Token counter matching this provider's model.
+    ///
+    /// Defaults to a character-based estimate; the bundled backends return
+    /// [`tokenizer::counter_for_model`] for their configured model.
+    fn token_counter(&self) -> std::sync::Arc<dyn tokenizer::TokenCounter> {
+        std::sync::Arc::new(tokenizer::HeuristicTokenCounter::default())
+    }
+
+    /// Tokens `messages` take up in a request to this provider's model,
+    /// including per-message overhead and tool call payloads.
+    fn count_tokens(&self, messages: &[chat::ChatMessage]) -> usize {
+        let counter = self.token_counter();
+        messages
+            .iter()
+            .map(|message| counter.count_message(message))
+            .sum()
+    }
+
+    /// Transcription support, for providers that can turn speech into text.
+    fn speech_to_text(&self) -> Option<&dyn audio::SpeechToTextProvider> {
+        None
+    }
+
+    /// Speech synthesis support, for providers that can read text aloud.
+    fn text_to_speech(&self) -> Option<&dyn audio::TextToSpeechProvider> {
+        None
+    }
 }
 
 /// Tool call represents a function call that an LLM wants to make.
@@ -83,6 +163,26 @@ pub struct FunctionCall {
     pub arguments: String,
 }
 
+impl std::fmt::Display for ToolCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{\n  \"id\": \"{}\",\n  \"type\": \"{}\",\n  \"function\": {}\n}}",
+            self.id, self.call_type, self.function
+        )
+    }
+}
+
+impl std::fmt::Display for FunctionCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{\n  \"name\": \"{}\",\n  \"arguments\": {}\n}}",
+            self.name, self.arguments
+        )
+    }
+}
+
 /// Default value for call_type field in ToolCall
 pub fn default_call_type() -> String {
     "function".to_string()
@@ -389,6 +489,7 @@ mod tests {
         ) -> Result<completion::CompletionResponse, error::LLMError> {
             Ok(completion::CompletionResponse {
                 text: "Mock completion".to_string(),
+                usage: None,
             })
         }
     }
@@ -463,4 +564,20 @@ mod tests {
         assert_eq!(embeddings[0], vec![0.0, 1.0]);
         assert_eq!(embeddings[1], vec![1.0, 2.0]);
     }
+
+    #[test]
+    fn test_llm_provider_count_tokens_sums_messages() {
+        let provider = MockLLMProvider;
+        let counter = provider.token_counter();
+        let messages = vec![
+            chat::ChatMessage::user()
+                .content("How many tokens?")
+                .build(),
+            chat::ChatMessage::assistant().content("A few.").build(),
+        ];
+
+        let expected: usize = messages.iter().map(|m| counter.count_message(m)).sum();
+        assert_eq!(provider.count_tokens(&messages), expected);
+        assert_eq!(provider.count_tokens(&[]), 0);
+    }
 }
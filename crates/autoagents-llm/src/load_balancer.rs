@@ -0,0 +1,612 @@
+//! Load balancing across several keys or endpoints of the same provider.
+//!
+//! [`LoadBalancedLLM`] spreads requests over a pool of interchangeable
+//! providers, typically the same backend built with different API keys or base
+//! URLs. Endpoints are picked round-robin or by lowest observed latency (see
+//! [`BalanceStrategy`]).
+//!
+//! Every endpoint keeps its own rate-limit accounting: a 429 response puts the
+//! endpoint in a backoff window during which it is only used when every other
+//! endpoint is throttled too, and the request is retried on the next endpoint.
+//! Other transient errors are retried on the next endpoint as well, each
+//! endpoint being attempted at most once per request.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::{
+    audio::{SpeechToTextProvider, TextToSpeechProvider},
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamResponse, StructuredOutputFormat, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    tokenizer::{HeuristicTokenCounter, TokenCounter},
+    LLMProvider,
+};
+
+/// How long an endpoint is avoided after it returned a 429.
+const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(10);
+/// Weight of the newest sample in the latency moving average.
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// How [`LoadBalancedLLM`] picks the endpoint for a request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BalanceStrategy {
+    /// Rotate through the endpoints in order.
+    #[default]
+    RoundRobin,
+    /// Prefer the endpoint with the lowest average latency, then the fewest
+    /// requests in flight. Endpoints without samples yet are tried first.
+    LeastLatency,
+}
+
+/// Snapshot of an endpoint's request and rate-limit accounting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointStats {
+    /// Name the endpoint was registered under.
+    pub name: String,
+    /// Requests sent to the endpoint.
+    pub requests: u64,
+    /// Requests that succeeded.
+    pub successes: u64,
+    /// Requests that failed, including rate-limited ones.
+    pub failures: u64,
+    /// Requests rejected with a 429.
+    pub rate_limited: u64,
+    /// Requests currently in progress.
+    pub in_flight: usize,
+    /// Moving average of successful request latency.
+    pub average_latency: Option<Duration>,
+    /// Whether the endpoint is in its rate-limit backoff window.
+    pub throttled: bool,
+}
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    requests: u64,
+    successes: u64,
+    failures: u64,
+    rate_limited: u64,
+    in_flight: usize,
+    average_latency: Option<Duration>,
+    throttled_until: Option<Instant>,
+}
+
+impl EndpointState {
+    fn throttled(&self, now: Instant) -> bool {
+        self.throttled_until.is_some_and(|until| until > now)
+    }
+}
+
+struct Endpoint {
+    name: String,
+    provider: Arc<dyn LLMProvider>,
+    state: Mutex<EndpointState>,
+}
+
+impl Endpoint {
+    fn state(&self) -> std::sync::MutexGuard<'_, EndpointState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Decrements the in-flight count when a request finishes or is cancelled.
+struct InFlight<'a>(&'a Endpoint);
+
+impl<'a> InFlight<'a> {
+    fn start(endpoint: &'a Endpoint) -> Self {
+        let mut state = endpoint.state();
+        state.requests += 1;
+        state.in_flight += 1;
+        Self(endpoint)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state();
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+}
+
+/// An [`LLMProvider`] that balances requests across interchangeable endpoints.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use autoagents_llm::{
+///     load_balancer::{BalanceStrategy, LoadBalancedLLM},
+///     LLMProvider,
+/// };
+///
+/// fn pool(keys: Vec<Arc<dyn LLMProvider>>) -> LoadBalancedLLM {
+///     keys.into_iter()
+///         .enumerate()
+///         .fold(
+///             LoadBalancedLLM::new().with_strategy(BalanceStrategy::LeastLatency),
+///             |pool, (i, llm)| pool.with_endpoint(format!("key-{i}"), llm),
+///         )
+/// }
+/// ```
+///
+/// All endpoints are expected to serve the same model; responses, embeddings
+/// and token counts are treated as interchangeable.
+pub struct LoadBalancedLLM {
+    endpoints: Vec<Endpoint>,
+    strategy: BalanceStrategy,
+    rate_limit_backoff: Duration,
+    next: AtomicUsize,
+}
+
+impl Default for LoadBalancedLLM {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for LoadBalancedLLM {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadBalancedLLM")
+            .field("endpoints", &self.stats())
+            .field("strategy", &self.strategy)
+            .field("rate_limit_backoff", &self.rate_limit_backoff)
+            .finish()
+    }
+}
+
+impl LoadBalancedLLM {
+    /// Creates an empty pool; add endpoints with [`LoadBalancedLLM::with_endpoint`].
+    pub fn new() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            strategy: BalanceStrategy::default(),
+            rate_limit_backoff: DEFAULT_RATE_LIMIT_BACKOFF,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds an endpoint, usually the same backend built with another key or URL.
+    pub fn with_endpoint(
+        mut self,
+        name: impl Into<String>,
+        provider: Arc<dyn LLMProvider>,
+    ) -> Self {
+        self.endpoints.push(Endpoint {
+            name: name.into(),
+            provider,
+            state: Mutex::new(EndpointState::default()),
+        });
+        self
+    }
+
+    /// Sets how endpoints are picked.
+    pub fn with_strategy(mut self, strategy: BalanceStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets how long an endpoint is avoided after it was rate limited.
+    pub fn with_rate_limit_backoff(mut self, backoff: Duration) -> Self {
+        self.rate_limit_backoff = backoff;
+        self
+    }
+
+    /// Returns the accounting of every endpoint, in registration order.
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                let state = endpoint.state();
+                EndpointStats {
+                    name: endpoint.name.clone(),
+                    requests: state.requests,
+                    successes: state.successes,
+                    failures: state.failures,
+                    rate_limited: state.rate_limited,
+                    in_flight: state.in_flight,
+                    average_latency: state.average_latency,
+                    throttled: state.throttled(now),
+                }
+            })
+            .collect()
+    }
+
+    /// Endpoints in the order they should be attempted for the next request.
+    /// Throttled endpoints go last, soonest-available first.
+    fn attempt_order(&self) -> Vec<&Endpoint> {
+        let now = Instant::now();
+        let mut order: Vec<&Endpoint> = match self.strategy {
+            BalanceStrategy::RoundRobin => {
+                let len = self.endpoints.len().max(1);
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % len;
+                self.endpoints[start..]
+                    .iter()
+                    .chain(&self.endpoints[..start])
+                    .collect()
+            }
+            BalanceStrategy::LeastLatency => {
+                let mut order: Vec<_> = self
+                    .endpoints
+                    .iter()
+                    .map(|endpoint| {
+                        let state = endpoint.state();
+                        let latency = state.average_latency.unwrap_or_default();
+                        (latency, state.in_flight, endpoint)
+                    })
+                    .collect();
+                order.sort_by_key(|(latency, in_flight, _)| (*latency, *in_flight));
+                order.into_iter().map(|(_, _, endpoint)| endpoint).collect()
+            }
+        };
+        // Stable sort keeps the strategy's order among available endpoints.
+        order.sort_by_key(|endpoint| {
+            endpoint
+                .state()
+                .throttled_until
+                .filter(|until| *until > now)
+        });
+        order
+    }
+
+    fn record_success(&self, endpoint: &Endpoint, latency: Duration) {
+        let mut state = endpoint.state();
+        state.successes += 1;
+        state.average_latency = Some(match state.average_latency {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => latency,
+        });
+    }
+
+    fn record_failure(&self, endpoint: &Endpoint, error: &LLMError) {
+        let mut state = endpoint.state();
+        state.failures += 1;
+        if error.is_rate_limited() {
+            state.rate_limited += 1;
+            state.throttled_until = Some(Instant::now() + self.rate_limit_backoff);
+        }
+    }
+
+    async fn balanced<T, F, Fut>(&self, operation: &str, call: F) -> Result<T, LLMError>
+    where
+        F: Fn(Arc<dyn LLMProvider>) -> Fut + Send,
+        Fut: Future<Output = Result<T, LLMError>> + Send,
+    {
+        let mut last_error = None;
+        for endpoint in self.attempt_order() {
+            let _in_flight = InFlight::start(endpoint);
+            let started = Instant::now();
+            let result = call(endpoint.provider.clone()).await;
+            match result {
+                Ok(value) => {
+                    self.record_success(endpoint, started.elapsed());
+                    return Ok(value);
+                }
+                Err(error) => {
+                    self.record_failure(endpoint, &error);
+                    if !error.is_retryable() {
+                        return Err(error);
+                    }
+                    log::warn!(
+                        "LoadBalancedLLM: {operation} on endpoint '{}' failed: {error}",
+                        endpoint.name
+                    );
+                    last_error = Some(error);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            LLMError::InvalidRequest("LoadBalancedLLM has no endpoints configured".to_string())
+        }))
+    }
+}
+
+#[async_trait]
+impl ChatProvider for LoadBalancedLLM {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.balanced("chat", |provider| {
+            let json_schema = json_schema.clone();
+            async move { provider.chat(messages, tools, json_schema).await }
+        })
+        .await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.balanced("chat_stream", |provider| {
+            let json_schema = json_schema.clone();
+            async move { provider.chat_stream(messages, tools, json_schema).await }
+        })
+        .await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.balanced("chat_stream_struct", |provider| {
+            let json_schema = json_schema.clone();
+            async move {
+                provider
+                    .chat_stream_struct(messages, tools, json_schema)
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// Only if every endpoint does, since any of them may serve a call
+    fn supports_structured_output(&self) -> bool {
+        self.endpoints
+            .iter()
+            .all(|endpoint| endpoint.provider.supports_structured_output())
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for LoadBalancedLLM {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        self.balanced("complete", |provider| {
+            let json_schema = json_schema.clone();
+            async move { provider.complete(req, json_schema).await }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for LoadBalancedLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.balanced("embed", |provider| {
+            let input = input.clone();
+            async move { provider.embed(input).await }
+        })
+        .await
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.endpoints
+            .iter()
+            .find_map(|endpoint| endpoint.provider.embedding_dimensions())
+    }
+
+    /// The smallest limit of any endpoint, since any of them may serve a call
+    fn max_embedding_batch(&self) -> Option<usize> {
+        self.endpoints
+            .iter()
+            .filter_map(|endpoint| endpoint.provider.max_embedding_batch())
+            .min()
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for LoadBalancedLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.balanced("list_models", |provider| async move {
+            provider.list_models(request).await
+        })
+        .await
+    }
+}
+
+impl LLMProvider for LoadBalancedLLM {
+    fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        self.endpoints
+            .first()
+            .map(|endpoint| endpoint.provider.token_counter())
+            .unwrap_or_else(|| Arc::new(HeuristicTokenCounter::default()))
+    }
+
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        self.endpoints
+            .iter()
+            .find_map(|endpoint| endpoint.provider.speech_to_text())
+    }
+
+    fn text_to_speech(&self) -> Option<&dyn TextToSpeechProvider> {
+        self.endpoints
+            .iter()
+            .find_map(|endpoint| endpoint.provider.text_to_speech())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ToolCall;
+    use std::sync::atomic::AtomicBool;
+
+    #[derive(Debug)]
+    struct MockChatResponse(String);
+
+    impl std::fmt::Display for MockChatResponse {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl ChatResponse for MockChatResponse {
+        fn text(&self) -> Option<String> {
+            Some(self.0.clone())
+        }
+
+        fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+            None
+        }
+    }
+
+    struct MockEndpoint {
+        name: &'static str,
+        rate_limited: AtomicBool,
+        delay: Duration,
+    }
+
+    impl MockEndpoint {
+        fn new(name: &'static str) -> Arc<Self> {
+            Self::with_delay(name, Duration::ZERO)
+        }
+
+        fn with_delay(name: &'static str, delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                rate_limited: AtomicBool::new(false),
+                delay,
+            })
+        }
+
+        fn rate_limited(name: &'static str) -> Arc<Self> {
+            let endpoint = Self::new(name);
+            endpoint.rate_limited.store(true, Ordering::SeqCst);
+            endpoint
+        }
+    }
+
+    #[async_trait]
+    impl ChatProvider for MockEndpoint {
+        async fn chat(
+            &self,
+            _messages: &[ChatMessage],
+            _tools: Option<&[Tool]>,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<Box<dyn ChatResponse>, LLMError> {
+            tokio::time::sleep(self.delay).await;
+            if self.rate_limited.load(Ordering::SeqCst) {
+                return Err(LLMError::HttpStatus {
+                    status: 429,
+                    message: "Too Many Requests".to_string(),
+                });
+            }
+            Ok(Box::new(MockChatResponse(self.name.to_string())))
+        }
+    }
+
+    #[async_trait]
+    impl CompletionProvider for MockEndpoint {
+        async fn complete(
+            &self,
+            _req: &CompletionRequest,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<CompletionResponse, LLMError> {
+            Err(LLMError::InvalidRequest("prompt too long".to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for MockEndpoint {
+        async fn embed(&self, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(vec![vec![1.0]])
+        }
+    }
+
+    #[async_trait]
+    impl ModelsProvider for MockEndpoint {}
+
+    impl LLMProvider for MockEndpoint {}
+
+    async fn chat_text(llm: &LoadBalancedLLM) -> String {
+        let messages = vec![ChatMessage::user().content("hello").build()];
+        llm.chat(&messages, None, None)
+            .await
+            .unwrap()
+            .text()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_rotates_endpoints() {
+        let llm = LoadBalancedLLM::new()
+            .with_endpoint("a", MockEndpoint::new("a"))
+            .with_endpoint("b", MockEndpoint::new("b"))
+            .with_endpoint("c", MockEndpoint::new("c"));
+
+        let mut served = Vec::new();
+        for _ in 0..4 {
+            served.push(chat_text(&llm).await);
+        }
+        assert_eq!(served, vec!["a", "b", "c", "a"]);
+        assert!(llm.stats().iter().all(|stats| stats.in_flight == 0));
+        assert_eq!(llm.stats()[0].requests, 2);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_endpoint_is_retried_elsewhere_and_throttled() {
+        let llm = LoadBalancedLLM::new()
+            .with_endpoint("a", MockEndpoint::rate_limited("a"))
+            .with_endpoint("b", MockEndpoint::new("b"))
+            .with_rate_limit_backoff(Duration::from_secs(60));
+
+        assert_eq!(chat_text(&llm).await, "b");
+        let stats = llm.stats();
+        assert_eq!(stats[0].rate_limited, 1);
+        assert!(stats[0].throttled);
+
+        // "a" would be next in rotation but is throttled.
+        assert_eq!(chat_text(&llm).await, "b");
+        assert_eq!(chat_text(&llm).await, "b");
+        assert_eq!(llm.stats()[0].requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_least_latency_prefers_faster_endpoint() {
+        let llm = LoadBalancedLLM::new()
+            .with_strategy(BalanceStrategy::LeastLatency)
+            .with_endpoint(
+                "slow",
+                MockEndpoint::with_delay("slow", Duration::from_millis(30)),
+            )
+            .with_endpoint("fast", MockEndpoint::new("fast"));
+
+        // Both endpoints are sampled before latency decides.
+        chat_text(&llm).await;
+        chat_text(&llm).await;
+        for _ in 0..3 {
+            assert_eq!(chat_text(&llm).await, "fast");
+        }
+        assert_eq!(llm.stats()[0].requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_transient_error_is_not_retried() {
+        let llm = LoadBalancedLLM::new()
+            .with_endpoint("a", MockEndpoint::new("a"))
+            .with_endpoint("b", MockEndpoint::new("b"));
+
+        let error = llm
+            .complete(&CompletionRequest::new("hi"), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, LLMError::InvalidRequest(_)));
+        let stats = llm.stats();
+        assert_eq!(stats[0].failures + stats[1].failures, 1);
+        assert_eq!(stats[0].rate_limited, 0);
+    }
+}
@@ -0,0 +1,494 @@
+//! Middleware chains around LLM providers.
+//!
+//! A [`Middleware`] sees every request made through a [`MiddlewareLLM`] and
+//! decides what happens to it. It can inspect or rewrite the request, answer
+//! it itself, or hand it on with [`Next`] and then inspect or rewrite the
+//! response. Every hook passes the request on by default, so a middleware
+//! only implements the calls it cares about; logging, redaction, prompt
+//! rewriting or a custom cache fit in a few lines and work with any
+//! provider.
+//!
+//! Middlewares run in the order they were added, the first one outermost:
+//!
+//! ```rust,ignore
+//! let llm = MiddlewareLLM::new(provider)
+//!     .with(RequestLogger)
+//!     .with(Redactor::new(["password"]));
+//! ```
+//!
+//! Here the logger sees the request before the redactor rewrites it, and the
+//! response after the redactor has handled it. Whole-provider wrappers such
+//! as [`CachedLLM`](crate::cache::CachedLLM) or
+//! [`RateLimitedLLM`](crate::rate_limit::RateLimitedLLM) compose with a
+//! chain by wrapping the provider it is built on, or the chain itself.
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::{
+    audio::{SpeechToTextProvider, TextToSpeechProvider},
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamResponse, StructuredOutputFormat, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    tokenizer::TokenCounter,
+    LLMProvider,
+};
+
+/// Text chunks of a streamed chat response
+pub type TextStream = Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>;
+
+/// Structured chunks of a streamed chat response
+pub type ResponseStream = Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>;
+
+/// A chat request as it travels through a middleware chain.
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    pub messages: Vec<ChatMessage>,
+    pub tools: Option<Vec<Tool>>,
+    pub json_schema: Option<StructuredOutputFormat>,
+}
+
+impl ChatRequest {
+    fn new(
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Self {
+        Self {
+            messages: messages.to_vec(),
+            tools: tools.map(<[Tool]>::to_vec),
+            json_schema,
+        }
+    }
+}
+
+/// Hooks around the calls made to a provider.
+///
+/// Each hook gets the request and the rest of the chain. Calling the
+/// matching method of `next` continues the request; returning without
+/// calling it answers the request here.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn chat(
+        &self,
+        request: ChatRequest,
+        next: Next<'_>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        next.chat(request).await
+    }
+
+    async fn chat_stream(
+        &self,
+        request: ChatRequest,
+        next: Next<'_>,
+    ) -> Result<TextStream, LLMError> {
+        next.chat_stream(request).await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        request: ChatRequest,
+        next: Next<'_>,
+    ) -> Result<ResponseStream, LLMError> {
+        next.chat_stream_struct(request).await
+    }
+
+    async fn complete(
+        &self,
+        request: CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse, LLMError> {
+        next.complete(request, json_schema).await
+    }
+
+    async fn embed(&self, input: Vec<String>, next: Next<'_>) -> Result<Vec<Vec<f32>>, LLMError> {
+        next.embed(input).await
+    }
+}
+
+/// The rest of a middleware chain, ending at the provider.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    provider: &'a dyn LLMProvider,
+}
+
+impl<'a> Next<'a> {
+    /// The chain after the first middleware, if there is one
+    fn split(self) -> Option<(&'a Arc<dyn Middleware>, Next<'a>)> {
+        let (first, rest) = self.middlewares.split_first()?;
+        Some((
+            first,
+            Next {
+                middlewares: rest,
+                provider: self.provider,
+            },
+        ))
+    }
+
+    pub async fn chat(self, request: ChatRequest) -> Result<Box<dyn ChatResponse>, LLMError> {
+        match self.split() {
+            Some((middleware, next)) => middleware.chat(request, next).await,
+            None => {
+                self.provider
+                    .chat(
+                        &request.messages,
+                        request.tools.as_deref(),
+                        request.json_schema,
+                    )
+                    .await
+            }
+        }
+    }
+
+    pub async fn chat_stream(self, request: ChatRequest) -> Result<TextStream, LLMError> {
+        match self.split() {
+            Some((middleware, next)) => middleware.chat_stream(request, next).await,
+            None => {
+                self.provider
+                    .chat_stream(
+                        &request.messages,
+                        request.tools.as_deref(),
+                        request.json_schema,
+                    )
+                    .await
+            }
+        }
+    }
+
+    pub async fn chat_stream_struct(
+        self,
+        request: ChatRequest,
+    ) -> Result<ResponseStream, LLMError> {
+        match self.split() {
+            Some((middleware, next)) => middleware.chat_stream_struct(request, next).await,
+            None => {
+                self.provider
+                    .chat_stream_struct(
+                        &request.messages,
+                        request.tools.as_deref(),
+                        request.json_schema,
+                    )
+                    .await
+            }
+        }
+    }
+
+    pub async fn complete(
+        self,
+        request: CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        match self.split() {
+            Some((middleware, next)) => middleware.complete(request, json_schema, next).await,
+            None => self.provider.complete(&request, json_schema).await,
+        }
+    }
+
+    pub async fn embed(self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        match self.split() {
+            Some((middleware, next)) => middleware.embed(input, next).await,
+            None => self.provider.embed(input).await,
+        }
+    }
+}
+
+/// A provider whose requests go through a chain of [`Middleware`].
+pub struct MiddlewareLLM {
+    provider: Arc<dyn LLMProvider>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl std::fmt::Debug for MiddlewareLLM {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiddlewareLLM")
+            .field("middlewares", &self.middlewares.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl MiddlewareLLM {
+    /// Wraps `provider` in an empty chain.
+    pub fn new(provider: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            provider,
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Adds `middleware` inside the ones added before it.
+    pub fn with(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Adds a middleware shared with other chains.
+    pub fn with_shared(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    fn chain(&self) -> Next<'_> {
+        Next {
+            middlewares: &self.middlewares,
+            provider: self.provider.as_ref(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for MiddlewareLLM {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.chain()
+            .chat(ChatRequest::new(messages, tools, json_schema))
+            .await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<TextStream, LLMError> {
+        self.chain()
+            .chat_stream(ChatRequest::new(messages, tools, json_schema))
+            .await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<ResponseStream, LLMError> {
+        self.chain()
+            .chat_stream_struct(ChatRequest::new(messages, tools, json_schema))
+            .await
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.provider.supports_structured_output()
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for MiddlewareLLM {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        self.chain().complete(req.clone(), json_schema).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MiddlewareLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.chain().embed(input).await
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.provider.embedding_dimensions()
+    }
+
+    fn max_embedding_batch(&self) -> Option<usize> {
+        self.provider.max_embedding_batch()
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for MiddlewareLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.provider.list_models(request).await
+    }
+}
+
+impl LLMProvider for MiddlewareLLM {
+    fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        self.provider.token_counter()
+    }
+
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        self.provider.speech_to_text()
+    }
+
+    fn text_to_speech(&self) -> Option<&dyn TextToSpeechProvider> {
+        self.provider.text_to_speech()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CachedChatResponse;
+    use std::sync::Mutex;
+
+    /// Answers with the content of the last message
+    struct EchoProvider;
+
+    #[async_trait]
+    impl ChatProvider for EchoProvider {
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            _tools: Option<&[Tool]>,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<Box<dyn ChatResponse>, LLMError> {
+            Ok(Box::new(CachedChatResponse {
+                text: messages.last().map(|message| message.content.clone()),
+                tool_calls: None,
+                thinking: None,
+                reasoning: Vec::new(),
+                usage: None,
+                citations: None,
+                logprobs: None,
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl CompletionProvider for EchoProvider {
+        async fn complete(
+            &self,
+            req: &CompletionRequest,
+            _json_schema: Option<StructuredOutputFormat>,
+        ) -> Result<CompletionResponse, LLMError> {
+            Ok(CompletionResponse::new(req.prompt.clone()))
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for EchoProvider {
+        async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(input.iter().map(|text| vec![text.len() as f32]).collect())
+        }
+    }
+
+    #[async_trait]
+    impl ModelsProvider for EchoProvider {}
+
+    impl LLMProvider for EchoProvider {}
+
+    /// Records the order in which chat requests and responses pass
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Middleware for Recorder {
+        async fn chat(
+            &self,
+            request: ChatRequest,
+            next: Next<'_>,
+        ) -> Result<Box<dyn ChatResponse>, LLMError> {
+            self.log.lock().unwrap().push(format!("{} in", self.name));
+            let response = next.chat(request).await;
+            self.log.lock().unwrap().push(format!("{} out", self.name));
+            response
+        }
+    }
+
+    /// Replaces a word in requests and completions
+    struct Redactor(&'static str);
+
+    #[async_trait]
+    impl Middleware for Redactor {
+        async fn chat(
+            &self,
+            mut request: ChatRequest,
+            next: Next<'_>,
+        ) -> Result<Box<dyn ChatResponse>, LLMError> {
+            for message in &mut request.messages {
+                message.content = message.content.replace(self.0, "[redacted]");
+            }
+            next.chat(request).await
+        }
+
+        async fn complete(
+            &self,
+            mut request: CompletionRequest,
+            json_schema: Option<StructuredOutputFormat>,
+            next: Next<'_>,
+        ) -> Result<CompletionResponse, LLMError> {
+            request.prompt = request.prompt.replace(self.0, "[redacted]");
+            next.complete(request, json_schema).await
+        }
+    }
+
+    /// Refuses to embed anything
+    struct NoEmbeddings;
+
+    #[async_trait]
+    impl Middleware for NoEmbeddings {
+        async fn embed(
+            &self,
+            _input: Vec<String>,
+            _next: Next<'_>,
+        ) -> Result<Vec<Vec<f32>>, LLMError> {
+            Err(LLMError::InvalidRequest(
+                "Embeddings are disabled".to_string(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middlewares_run_first_outermost() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let recorder = |name| Recorder {
+            name,
+            log: log.clone(),
+        };
+        let llm = MiddlewareLLM::new(Arc::new(EchoProvider))
+            .with(recorder("outer"))
+            .with(recorder("inner"));
+
+        let messages = [ChatMessage::user().content("hi").build()];
+        let response = llm.chat(&messages, None, None).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("hi"));
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer in", "inner in", "inner out", "outer out"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_middlewares_rewrite_and_answer_requests() {
+        let llm = MiddlewareLLM::new(Arc::new(EchoProvider))
+            .with(Redactor("hunter2"))
+            .with(NoEmbeddings);
+
+        let messages = [ChatMessage::user()
+            .content("my password is hunter2")
+            .build()];
+        let response = llm.chat(&messages, None, None).await.unwrap();
+        assert_eq!(
+            response.text().as_deref(),
+            Some("my password is [redacted]")
+        );
+
+        let completion = llm
+            .complete(&CompletionRequest::new("hunter2"), None)
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "[redacted]");
+
+        let err = llm.embed(vec!["text".to_string()]).await.unwrap_err();
+        assert!(matches!(err, LLMError::InvalidRequest(_)));
+    }
+}
@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::error::LLMError;
+
+/// Moderation verdict for one input string.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModerationResult {
+    /// Whether any category was flagged
+    pub flagged: bool,
+    /// Names of the flagged categories
+    pub categories: Vec<String>,
+    /// Provider confidence per category, when reported
+    #[serde(default)]
+    pub category_scores: BTreeMap<String, f32>,
+}
+
+impl ModerationResult {
+    /// A result with nothing flagged
+    pub fn clean() -> Self {
+        Self::default()
+    }
+
+    pub fn flagged<S: Into<String>>(categories: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            flagged: true,
+            categories: categories.into_iter().map(Into::into).collect(),
+            category_scores: BTreeMap::new(),
+        }
+    }
+}
+
+/// Classifies text against a provider's content policy.
+///
+/// Implemented by hosted moderation endpoints and can be implemented by
+/// local classifiers. Returns one result per input, in the same order.
+#[async_trait]
+pub trait ModerationProvider {
+    async fn moderate(&self, input: Vec<String>) -> Result<Vec<ModerationResult>, LLMError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct KeywordModerator;
+
+    #[async_trait]
+    impl ModerationProvider for KeywordModerator {
+        async fn moderate(&self, input: Vec<String>) -> Result<Vec<ModerationResult>, LLMError> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    if text.contains("attack") {
+                        ModerationResult::flagged(["violence"])
+                    } else {
+                        ModerationResult::clean()
+                    }
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_results_follow_input_order() {
+        let results = KeywordModerator
+            .moderate(vec!["hello".into(), "attack now".into()])
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(!results[0].flagged);
+        assert_eq!(results[1].categories, vec!["violence"]);
+    }
+
+    #[test]
+    fn test_result_deserializes_without_scores() {
+        let result: ModerationResult =
+            serde_json::from_str(r#"{"flagged": true, "categories": ["hate"]}"#).unwrap();
+        assert!(result.flagged);
+        assert!(result.category_scores.is_empty());
+    }
+}
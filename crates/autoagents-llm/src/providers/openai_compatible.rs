@@ -3,8 +3,19 @@
 //! This module provides a generic base for OpenAI-compatible APIs that can be reused
 //! across multiple providers like OpenAI, Mistral, XAI, Groq, DeepSeek, etc.
 
-use crate::chat::{StreamChoice, StreamDelta, StreamToolCallDelta, StreamToolCallFunction};
+#[cfg(feature = "openai")]
+use crate::audio::{Audio, AudioFormat, SpeechRequest};
+#[cfg(any(feature = "openai", feature = "groq"))]
+use crate::audio::{Transcription, TranscriptionRequest};
+#[cfg(any(feature = "openai", feature = "groq"))]
+use crate::chat::utils::check_response_status;
+use crate::chat::{
+    ContentPart, GenerationOptions, StreamChoice, StreamDelta, StreamToolCallDelta,
+    StreamToolCallFunction, TokenLogprob, ToolCallAccumulator,
+};
 use crate::error::LLMError;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
 use crate::FunctionCall;
 use crate::{
     chat::ChatResponse,
@@ -15,6 +26,7 @@ use crate::{
     default_call_type, ToolCall,
 };
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use either::*;
 use futures::{stream::Stream, StreamExt};
 use reqwest::{Client, Url};
@@ -45,6 +57,12 @@ pub struct OpenAICompatibleProvider<T: OpenAIProviderConfig> {
     #[allow(dead_code)]
     pub embedding_dimensions: Option<u32>,
     pub normalize_response: bool,
+    /// Alternatives returned with each token's log probability, if the
+    /// provider supports log probabilities
+    pub top_logprobs: Option<u8>,
+    /// Stop sequences, seed and penalties
+    pub generation: GenerationOptions,
+    pub retry_policy: RetryPolicy,
     pub client: Client,
     _phantom: PhantomData<T>,
 }
@@ -70,6 +88,14 @@ pub trait OpenAIProviderConfig: Send + Sync {
     const SUPPORTS_PARALLEL_TOOL_CALLS: bool = false;
     /// Whether this provider supports stream options (like include_usage)
     const SUPPORTS_STREAM_OPTIONS: bool = false;
+    /// Whether a structured output format without a schema asks for JSON mode
+    /// (`json_object`) rather than being sent as a `json_schema` format
+    const SUPPORTS_JSON_MODE: bool = false;
+    /// Whether this provider returns token log probabilities
+    const SUPPORTS_LOGPROBS: bool = false;
+    /// Whether the sampling seed is sent as `random_seed`, as Mistral names
+    /// it, rather than `seed`
+    const SEED_AS_RANDOM_SEED: bool = false;
     /// Custom headers to add to requests
     fn custom_headers() -> Option<Vec<(String, String)>> {
         None
@@ -136,6 +162,14 @@ pub struct OpenAIChatRequest<'a> {
     pub stream_options: Option<OpenAIStreamOptions>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u8>,
+    #[serde(flatten)]
+    pub generation: GenerationOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<u64>,
 }
 
 /// Generic OpenAI-compatible chat response
@@ -148,6 +182,13 @@ pub struct OpenAIChatResponse {
 #[derive(Deserialize, Debug)]
 pub struct OpenAIChatChoice {
     pub message: OpenAIChatMsg,
+    #[serde(default)]
+    pub logprobs: Option<OpenAIChoiceLogprobs>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OpenAIChoiceLogprobs {
+    pub content: Option<Vec<TokenLogprob>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -204,6 +245,9 @@ pub struct OpenAIStreamDelta {
 /// This is a standardized structure used across all providers.
 #[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
 pub struct StreamToolCall {
+    /// Position of the call in the response, shared by all of its deltas.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
     /// The ID of the tool call.
     pub id: Option<String>,
     /// The type of the tool call (defaults to "function" if not provided).
@@ -220,6 +264,7 @@ pub struct StreamFunctionCall {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// The arguments to pass to the function, typically serialized as a JSON string.
+    #[serde(default)]
     pub arguments: String,
 }
 
@@ -275,6 +320,12 @@ impl ChatResponse for OpenAIChatResponse {
     fn usage(&self) -> Option<Usage> {
         self.usage.clone()
     }
+
+    fn logprobs(&self) -> Option<Vec<TokenLogprob>> {
+        self.choices
+            .first()
+            .and_then(|c| c.logprobs.as_ref()?.content.clone())
+    }
 }
 
 impl std::fmt::Display for OpenAIChatResponse {
@@ -322,10 +373,6 @@ impl<T: OpenAIProviderConfig> OpenAICompatibleProvider<T> {
         embedding_encoding_format: Option<String>,
         embedding_dimensions: Option<u32>,
     ) -> Self {
-        let mut builder = Client::builder();
-        if let Some(sec) = timeout_seconds {
-            builder = builder.timeout(std::time::Duration::from_secs(sec));
-        }
         Self {
             api_key: api_key.into(),
             base_url: Url::parse(&base_url.unwrap_or_else(|| T::DEFAULT_BASE_URL.to_owned()))
@@ -342,52 +389,50 @@ impl<T: OpenAIProviderConfig> OpenAICompatibleProvider<T> {
             voice,
             parallel_tool_calls: parallel_tool_calls.unwrap_or(false),
             normalize_response: normalize_response.unwrap_or(true),
+            top_logprobs: None,
+            generation: GenerationOptions::default(),
             embedding_encoding_format,
             embedding_dimensions,
-            client: builder.build().expect("Failed to build reqwest Client"),
+            retry_policy: RetryPolicy::default(),
+            client: crate::http::default_client(timeout_seconds),
             _phantom: PhantomData,
         }
     }
 
-    pub fn prepare_messages(&self, messages: &[ChatMessage]) -> Vec<OpenAIChatMessage<'_>> {
-        let mut openai_msgs: Vec<OpenAIChatMessage> = messages
-            .iter()
-            .flat_map(|msg| {
-                if let MessageType::ToolResult(ref results) = msg.message_type {
-                    // Expand ToolResult into multiple messages
-                    results
-                        .iter()
-                        .map(|result| OpenAIChatMessage {
-                            role: "tool",
-                            tool_call_id: Some(result.id.clone()),
-                            tool_calls: None,
-                            content: Some(Right(result.function.arguments.clone())),
-                        })
-                        .collect::<Vec<_>>()
-                } else {
-                    // Convert single message
-                    vec![chat_message_to_openai_message(msg.clone())]
-                }
-            })
-            .collect();
-        if let Some(system) = &self.system {
-            openai_msgs.insert(
-                0,
-                OpenAIChatMessage {
-                    role: "system",
-                    content: Some(Left(vec![OpenAIMessageContent {
-                        message_type: Some("text"),
-                        text: Some(system.as_str()),
-                        image_url: None,
-                        tool_call_id: None,
-                        tool_output: None,
-                    }])),
-                    tool_calls: None,
-                    tool_call_id: None,
-                },
-            );
+    /// The generation options to send, and the seed to send as
+    /// `random_seed` instead for providers that name it so
+    pub(crate) fn generation_options(&self) -> (GenerationOptions, Option<u64>) {
+        let mut generation = self.generation.clone();
+        let random_seed = if T::SEED_AS_RANDOM_SEED {
+            generation.seed.take()
+        } else {
+            None
+        };
+        (generation, random_seed)
+    }
+
+    /// The `response_format` requesting `json_schema`, if the provider
+    /// supports structured output
+    pub(crate) fn response_format(
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Option<OpenAIResponseFormat> {
+        if !T::SUPPORTS_STRUCTURED_OUTPUT {
+            return None;
         }
-        openai_msgs
+        json_schema.map(|format| match format.schema {
+            None if T::SUPPORTS_JSON_MODE => OpenAIResponseFormat {
+                response_type: OpenAIResponseType::JsonObject,
+                json_schema: None,
+            },
+            _ => format.into(),
+        })
+    }
+
+    pub fn prepare_messages<'a>(
+        &'a self,
+        messages: &'a [ChatMessage],
+    ) -> Vec<OpenAIChatMessage<'a>> {
+        openai_messages(messages, self.system.as_deref())
     }
 }
 
@@ -407,14 +452,7 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
             )));
         }
         let openai_msgs = self.prepare_messages(messages);
-        let response_format: Option<OpenAIResponseFormat> = if T::SUPPORTS_STRUCTURED_OUTPUT {
-            json_schema
-                .clone()
-                .or_else(|| json_schema.clone())
-                .map(|s| s.into())
-        } else {
-            None
-        };
+        let response_format = Self::response_format(json_schema);
         let request_tools = tools.map(|t| t.to_vec());
         let request_tool_choice = if request_tools.is_some() {
             self.tool_choice.clone()
@@ -431,6 +469,8 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
         } else {
             None
         };
+        let top_logprobs = self.top_logprobs.filter(|_| T::SUPPORTS_LOGPROBS);
+        let (generation, random_seed) = self.generation_options();
         let body = OpenAIChatRequest {
             model: &self.model,
             messages: openai_msgs,
@@ -445,6 +485,10 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
             response_format,
             stream_options: None,
             parallel_tool_calls,
+            logprobs: top_logprobs.map(|_| true),
+            top_logprobs,
+            generation,
+            random_seed,
         };
         let url = self
             .base_url
@@ -465,16 +509,12 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
         if let Some(timeout) = self.timeout_seconds {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
-        let response = request.send().await?;
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
         log::debug!("{} HTTP status: {}", T::PROVIDER_NAME, response.status());
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(LLMError::ResponseFormatError {
-                message: format!("{} API returned error status: {status}", T::PROVIDER_NAME),
-                raw_response: error_text,
-            });
-        }
+        let response = check_response_status(response).await?;
         let resp_text = response.text().await?;
         let json_resp: Result<OpenAIChatResponse, serde_json::Error> =
             serde_json::from_str(&resp_text);
@@ -539,14 +579,8 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
         } else {
             None
         };
-        let response_format: Option<OpenAIResponseFormat> = if T::SUPPORTS_STRUCTURED_OUTPUT {
-            json_schema
-                .clone()
-                .or_else(|| json_schema.clone())
-                .map(|s| s.into())
-        } else {
-            None
-        };
+        let response_format = Self::response_format(json_schema);
+        let (generation, random_seed) = self.generation_options();
         let body = OpenAIChatRequest {
             model: &self.model,
             messages: openai_msgs,
@@ -575,6 +609,10 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
             } else {
                 None
             },
+            logprobs: None,
+            top_logprobs: None,
+            generation,
+            random_seed,
         };
         let url = self
             .base_url
@@ -589,21 +627,67 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
         if let Some(timeout) = self.timeout_seconds {
             request = request.timeout(std::time::Duration::from_secs(timeout));
         }
-        let response = request.send().await?;
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(LLMError::ResponseFormatError {
-                message: format!("{} API returned error status: {status}", T::PROVIDER_NAME),
-                raw_response: error_text,
-            });
-        }
+        let response = request
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let response = check_response_status(response).await?;
         Ok(create_sse_stream(response, self.normalize_response))
     }
+
+    fn supports_structured_output(&self) -> bool {
+        T::SUPPORTS_STRUCTURED_OUTPUT
+    }
+}
+
+/// Convert chat messages into OpenAI messages, each tool result becoming its
+/// own `tool` message, and prepend the system prompt if given
+pub fn openai_messages<'a>(
+    messages: &'a [ChatMessage],
+    system: Option<&'a str>,
+) -> Vec<OpenAIChatMessage<'a>> {
+    let mut openai_msgs: Vec<OpenAIChatMessage> = messages
+        .iter()
+        .flat_map(|msg| {
+            if let MessageType::ToolResult(ref results) = msg.message_type {
+                // Expand ToolResult into multiple messages
+                results
+                    .iter()
+                    .map(|result| OpenAIChatMessage {
+                        role: "tool",
+                        tool_call_id: Some(result.id.clone()),
+                        tool_calls: None,
+                        content: Some(Right(result.function.arguments.clone())),
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                // Convert single message
+                vec![chat_message_to_openai_message(msg)]
+            }
+        })
+        .collect();
+    if let Some(system) = system {
+        openai_msgs.insert(
+            0,
+            OpenAIChatMessage {
+                role: "system",
+                content: Some(Left(vec![OpenAIMessageContent {
+                    message_type: Some("text"),
+                    text: Some(system),
+                    image_url: None,
+                    tool_call_id: None,
+                    tool_output: None,
+                }])),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+        );
+    }
+    openai_msgs
 }
 
-/// Create OpenAICompatibleChatMessage` that doesn't borrow from any temporary variables
-pub fn chat_message_to_openai_message(chat_msg: ChatMessage) -> OpenAIChatMessage<'static> {
+/// Convert a chat message into an `OpenAIChatMessage`, borrowing from the source message
+pub fn chat_message_to_openai_message(chat_msg: &ChatMessage) -> OpenAIChatMessage<'_> {
     OpenAIChatMessage {
         role: match chat_msg.role {
             ChatRole::User => "user",
@@ -614,15 +698,21 @@ pub fn chat_message_to_openai_message(chat_msg: ChatMessage) -> OpenAIChatMessag
         tool_call_id: None,
         content: match &chat_msg.message_type {
             MessageType::Text => Some(Right(chat_msg.content.clone())),
-            MessageType::Image(_) => unreachable!(),
+            MessageType::Image((image_mime, raw_bytes)) => {
+                let data_url = format!(
+                    "data:{};base64,{}",
+                    image_mime.mime_type(),
+                    BASE64.encode(raw_bytes)
+                );
+                Some(Left(image_message_parts(&chat_msg.content, data_url)))
+            }
             MessageType::Pdf(_) => unimplemented!(),
-            MessageType::ImageURL(url) => Some(Left(vec![OpenAIMessageContent {
-                message_type: Some("image_url"),
-                text: None,
-                image_url: Some(ImageUrlContent { url: url.clone() }),
-                tool_output: None,
-                tool_call_id: None,
-            }])),
+            MessageType::ImageURL(url) => {
+                Some(Left(image_message_parts(&chat_msg.content, url.clone())))
+            }
+            MessageType::Parts(parts) => {
+                Some(Left(multimodal_message_parts(&chat_msg.content, parts)))
+            }
             MessageType::ToolUse(_) => None,
             MessageType::ToolResult(_) => None,
         },
@@ -646,16 +736,75 @@ pub fn chat_message_to_openai_message(chat_msg: ChatMessage) -> OpenAIChatMessag
     }
 }
 
+/// Content parts for an image message: the accompanying text, if any, then the image
+fn image_message_parts(text: &str, url: String) -> Vec<OpenAIMessageContent<'_>> {
+    let mut parts = Vec::with_capacity(2);
+    if !text.is_empty() {
+        parts.push(OpenAIMessageContent {
+            message_type: Some("text"),
+            text: Some(text),
+            image_url: None,
+            tool_output: None,
+            tool_call_id: None,
+        });
+    }
+    parts.push(OpenAIMessageContent {
+        message_type: Some("image_url"),
+        text: None,
+        image_url: Some(ImageUrlContent { url }),
+        tool_output: None,
+        tool_call_id: None,
+    });
+    parts
+}
+
+/// Content parts for a multimodal message: the message text, if any, then each part in order
+fn multimodal_message_parts<'a>(
+    text: &'a str,
+    parts: &'a [ContentPart],
+) -> Vec<OpenAIMessageContent<'a>> {
+    let text_part = |text| OpenAIMessageContent {
+        message_type: Some("text"),
+        text: Some(text),
+        image_url: None,
+        tool_output: None,
+        tool_call_id: None,
+    };
+    let mut content = Vec::with_capacity(parts.len() + 1);
+    if !text.is_empty() {
+        content.push(text_part(text));
+    }
+    for part in parts {
+        content.push(match part {
+            ContentPart::Text(text) => text_part(text),
+            image => OpenAIMessageContent {
+                message_type: Some("image_url"),
+                text: None,
+                image_url: image.to_image_url().map(|url| ImageUrlContent { url }),
+                tool_output: None,
+                tool_call_id: None,
+            },
+        });
+    }
+    content
+}
+
 /// Creates a structured SSE stream that returns `StreamResponse` objects
 ///
-/// Buffer required to accumulate JSON payload lines that are split across multiple SSE chunks
+/// Buffer required to accumulate JSON payload lines that are split across multiple SSE chunks.
+///
+/// With `normalize_response`, tool calls are assembled and emitted whole, one
+/// chunk per call, as soon as they are complete; otherwise their deltas are
+/// passed through as they arrive.
 pub fn create_sse_stream(
     response: reqwest::Response,
     normalize_response: bool,
 ) -> std::pin::Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>> {
     struct SSEStreamParser {
         event_buffer: String,
-        tool_buffer: ToolCall,
+        tool_calls: ToolCallAccumulator,
+        /// Complete tool calls emitted so far
+        emitted_tool_calls: usize,
         usage: Option<Usage>,
         results: Vec<Result<StreamResponse, LLMError>>,
         normalize_response: bool,
@@ -664,49 +813,40 @@ pub fn create_sse_stream(
         fn new(normalize_response: bool) -> Self {
             Self {
                 event_buffer: String::new(),
+                tool_calls: ToolCallAccumulator::default(),
+                emitted_tool_calls: 0,
                 usage: None,
                 results: Vec::new(),
-                tool_buffer: ToolCall {
-                    id: String::new(),
-                    call_type: "function".to_string(),
-                    function: FunctionCall {
-                        name: String::new(),
-                        arguments: String::new(),
-                    },
-                },
                 normalize_response,
             }
         }
 
-        /// Push the current `tool_buffer` as a `StreamResponse` and reset it
-        fn push_tool_call(&mut self) {
-            if self.normalize_response && !self.tool_buffer.function.name.is_empty() {
-                // Convert ToolCall to StreamToolCallDelta for normalized output
+        fn push_delta(&mut self, delta: StreamDelta) {
+            self.results.push(Ok(StreamResponse {
+                choices: vec![StreamChoice { delta }],
+                usage: None,
+            }));
+        }
+
+        /// Push each complete tool call as its own `StreamResponse`
+        fn push_tool_calls(&mut self, calls: Vec<ToolCall>) {
+            for call in calls {
                 let tool_call_delta = StreamToolCallDelta {
-                    index: 0,
+                    index: self.emitted_tool_calls,
+                    id: Some(call.id).filter(|id| !id.is_empty()),
                     function: Some(StreamToolCallFunction {
-                        name: self.tool_buffer.function.name.clone(),
-                        arguments: self.tool_buffer.function.arguments.clone(),
+                        name: call.function.name,
+                        arguments: call.function.arguments,
                     }),
                 };
-                self.results.push(Ok(StreamResponse {
-                    choices: vec![StreamChoice {
-                        delta: StreamDelta {
-                            content: None,
-                            tool_calls: Some(vec![tool_call_delta]),
-                        },
-                    }],
-                    usage: None,
-                }));
+                self.emitted_tool_calls += 1;
+                self.push_delta(StreamDelta {
+                    content: None,
+                    tool_calls: Some(vec![tool_call_delta]),
+                    thinking: None,
+                    reasoning: None,
+                });
             }
-            self.tool_buffer = ToolCall {
-                id: String::new(),
-                call_type: "function".to_string(),
-                function: FunctionCall {
-                    name: String::new(),
-                    arguments: String::new(),
-                },
-            };
         }
 
         /// Parse the accumulated event_buffer as one SSE event
@@ -715,13 +855,16 @@ pub fn create_sse_stream(
             for line in self.event_buffer.lines() {
                 if let Some(data) = line.strip_prefix("data: ") {
                     if data == "[DONE]" {
-                        self.push_tool_call();
-                        if let Some(usage) = self.usage.clone() {
+                        let calls = self.tool_calls.finish();
+                        self.push_tool_calls(calls);
+                        if let Some(usage) = self.usage.take() {
                             self.results.push(Ok(StreamResponse {
                                 choices: vec![StreamChoice {
                                     delta: StreamDelta {
                                         content: None,
                                         tool_calls: None,
+                                        thinking: None,
+                                        reasoning: None,
                                     },
                                 }],
                                 usage: Some(usage),
@@ -737,86 +880,48 @@ pub fn create_sse_stream(
             if data_payload.is_empty() {
                 return;
             }
-            if let Ok(response) = serde_json::from_str::<StreamChunk>(&data_payload) {
-                if let Some(resp_usage) = response.usage.clone() {
-                    self.usage = Some(resp_usage);
-                }
-                for choice in &response.choices {
-                    let content = choice.delta.content.clone();
-
-                    if self.normalize_response {
-                        // Map StreamToolCall (some fields are optional) to ToolCall for normalization
-                        let tool_calls: Option<Vec<ToolCall>> =
-                            choice.delta.tool_calls.clone().map(|calls| {
-                                calls
-                                    .into_iter()
-                                    .map(|c| ToolCall {
-                                        id: c.id.unwrap_or_default(),
-                                        call_type: c.call_type,
-                                        function: FunctionCall {
-                                            name: c.function.name.unwrap_or_default(),
-                                            arguments: c.function.arguments,
-                                        },
-                                    })
-                                    .collect::<Vec<ToolCall>>()
-                            });
-
-                        if content.is_some() || tool_calls.is_some() {
-                            // If normalize_response is enabled, accumulate tool call outputs
-                            if let Some(calls) = &tool_calls {
-                                for call in calls {
-                                    // println!("Accumulating tool call: {:?}", call);
-                                    if !call.function.name.is_empty() {
-                                        self.push_tool_call();
-                                        self.tool_buffer.function.name = call.function.name.clone();
-                                    }
-                                    if !call.function.arguments.is_empty() {
-                                        self.tool_buffer
-                                            .function
-                                            .arguments
-                                            .push_str(&call.function.arguments);
-                                    }
-                                    if !call.id.is_empty() {
-                                        self.tool_buffer.id = call.id.clone();
-                                    }
-                                    if !call.call_type.is_empty() {
-                                        self.tool_buffer.call_type = call.call_type.clone();
-                                    }
-                                }
-                            }
-                        }
-                    } else {
-                        // When not normalizing, convert StreamToolCall to StreamToolCallDelta
-                        let tool_call_deltas: Option<Vec<StreamToolCallDelta>> =
-                            choice.delta.tool_calls.clone().map(|calls| {
-                                calls
-                                    .into_iter()
-                                    .enumerate()
-                                    .map(|(idx, c)| StreamToolCallDelta {
-                                        index: idx,
-                                        function: c.function.name.map(|name| {
-                                            StreamToolCallFunction {
-                                                name,
-                                                arguments: c.function.arguments,
-                                            }
-                                        }),
-                                    })
-                                    .collect()
-                            });
-
-                        if content.is_some() || tool_call_deltas.is_some() {
-                            self.push_tool_call();
-                            self.results.push(Ok(StreamResponse {
-                                choices: vec![StreamChoice {
-                                    delta: StreamDelta {
-                                        content,
-                                        tool_calls: tool_call_deltas,
-                                    },
-                                }],
-                                usage: None,
-                            }));
-                        }
+            let Ok(response) = serde_json::from_str::<StreamChunk>(&data_payload) else {
+                return;
+            };
+            if let Some(resp_usage) = response.usage {
+                self.usage = Some(resp_usage);
+            }
+            for choice in response.choices {
+                let deltas: Vec<StreamToolCallDelta> = choice
+                    .delta
+                    .tool_calls
+                    .unwrap_or_default()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(position, call)| StreamToolCallDelta {
+                        index: call.index.unwrap_or(position),
+                        id: call.id,
+                        function: Some(StreamToolCallFunction {
+                            name: call.function.name.unwrap_or_default(),
+                            arguments: call.function.arguments,
+                        }),
+                    })
+                    .collect();
+                let content = choice.delta.content.filter(|content| !content.is_empty());
+
+                if self.normalize_response {
+                    if let Some(content) = content {
+                        self.push_delta(StreamDelta {
+                            content: Some(content),
+                            tool_calls: None,
+                            thinking: None,
+                            reasoning: None,
+                        });
                     }
+                    let completed = self.tool_calls.push(&deltas);
+                    self.push_tool_calls(completed);
+                } else if content.is_some() || !deltas.is_empty() {
+                    self.push_delta(StreamDelta {
+                        content,
+                        tool_calls: (!deltas.is_empty()).then_some(deltas),
+                        thinking: None,
+                        reasoning: None,
+                    });
                 }
             }
         }
@@ -848,3 +953,390 @@ pub fn create_sse_stream(
         .flat_map(futures::stream::iter);
     Box::pin(stream)
 }
+
+/// Transcribe audio through an OpenAI-style `audio/transcriptions` endpoint.
+///
+/// Whisper models answer in `verbose_json`, which adds the spoken language
+/// and the duration to the text.
+#[cfg(any(feature = "openai", feature = "groq"))]
+pub(crate) async fn transcribe_audio(
+    client: &Client,
+    base_url: &Url,
+    api_key: &str,
+    model: &str,
+    request: &TranscriptionRequest,
+    retry_policy: &RetryPolicy,
+) -> Result<Transcription, LLMError> {
+    let url = base_url
+        .join("audio/transcriptions")
+        .map_err(|e| LLMError::HttpError(e.to_string()))?;
+    let response_format = if model.starts_with("whisper") {
+        "verbose_json"
+    } else {
+        "json"
+    };
+    let mut fields = vec![("model", model), ("response_format", response_format)];
+    if let Some(language) = &request.language {
+        fields.push(("language", language));
+    }
+    if let Some(prompt) = &request.prompt {
+        fields.push(("prompt", prompt));
+    }
+    let audio = &request.audio;
+    let (content_type, body) = multipart_form(
+        &fields,
+        &format!("audio.{}", audio.format.extension()),
+        audio.format.mime_type(),
+        &audio.data,
+    );
+
+    let response = client
+        .post(url)
+        .bearer_auth(api_key)
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .body(body)
+        .with_scoped_headers()
+        .send_with_retry(retry_policy)
+        .await?;
+    let response = check_response_status(response).await?;
+    let text = response.text().await?;
+    serde_json::from_str(&text).map_err(|e| LLMError::ResponseFormatError {
+        message: format!("Failed to decode transcription: {e}"),
+        raw_response: text,
+    })
+}
+
+/// Multipart form holding the text fields and `data` as its `file`,
+/// returned with its content type
+#[cfg(any(feature = "openai", feature = "groq"))]
+pub(crate) fn multipart_form(
+    fields: &[(&str, &str)],
+    file_name: &str,
+    file_type: &str,
+    data: &[u8],
+) -> (String, Vec<u8>) {
+    // The boundary must not occur anywhere in the body
+    let mut boundary = String::from("autoagents-form-boundary");
+    while data
+        .windows(boundary.len())
+        .any(|window| window == boundary.as_bytes())
+        || fields.iter().any(|(_, value)| value.contains(&boundary))
+    {
+        boundary.push('-');
+    }
+
+    let mut body = Vec::with_capacity(data.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\nContent-Type: {file_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    (format!("multipart/form-data; boundary={boundary}"), body)
+}
+
+/// Request body of an OpenAI-style `audio/speech` endpoint
+#[cfg(feature = "openai")]
+#[derive(Serialize, Debug)]
+struct OpenAISpeechRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+    voice: &'a str,
+    response_format: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speed: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instructions: Option<&'a str>,
+}
+
+/// Synthesize speech through an OpenAI-style `audio/speech` endpoint
+#[cfg(feature = "openai")]
+pub(crate) async fn synthesize_speech(
+    client: &Client,
+    base_url: &Url,
+    api_key: &str,
+    model: &str,
+    voice: &str,
+    request: &SpeechRequest,
+    retry_policy: &RetryPolicy,
+) -> Result<Audio, LLMError> {
+    let response_format = match request.format {
+        AudioFormat::Mp3
+        | AudioFormat::Opus
+        | AudioFormat::Aac
+        | AudioFormat::Flac
+        | AudioFormat::Wav
+        | AudioFormat::Pcm => request.format.extension(),
+        other => {
+            return Err(LLMError::InvalidRequest(format!(
+                "Speech cannot be returned as {}",
+                other.extension()
+            )))
+        }
+    };
+    let body = OpenAISpeechRequest {
+        model,
+        input: &request.text,
+        voice,
+        response_format,
+        speed: request.speed,
+        instructions: request.instructions.as_deref(),
+    };
+    let url = base_url
+        .join("audio/speech")
+        .map_err(|e| LLMError::HttpError(e.to_string()))?;
+
+    let response = client
+        .post(url)
+        .bearer_auth(api_key)
+        .json(&body)
+        .with_scoped_headers()
+        .send_with_retry(retry_policy)
+        .await?;
+    let response = check_response_status(response).await?;
+    Ok(Audio::new(response.bytes().await?.to_vec(), request.format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "openai", feature = "groq"))]
+    use crate::audio::{Audio, AudioFormat};
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serves `events` as a server-sent event stream and returns the response
+    async fn sse_response(events: Vec<serde_json::Value>) -> reqwest::Response {
+        let mut body: String = events
+            .iter()
+            .map(|event| format!("data: {event}\n\n"))
+            .collect();
+        body.push_str("data: [DONE]\n\n");
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        reqwest::get(&url).await.unwrap()
+    }
+
+    fn parallel_tool_call_events() -> Vec<serde_json::Value> {
+        let delta = |delta: serde_json::Value| json!({"choices": [{"delta": delta}]});
+        vec![
+            delta(json!({"content": "Checking both."})),
+            delta(
+                json!({"tool_calls": [{"index": 0, "id": "call_a", "type": "function",
+                "function": {"name": "weather", "arguments": ""}}]}),
+            ),
+            delta(json!({"tool_calls": [{"index": 0, "function": {"arguments": "{\"city\":"}}]})),
+            delta(json!({"tool_calls": [{"index": 0, "function": {"arguments": "\"Paris\"}"}}]})),
+            delta(
+                json!({"tool_calls": [{"index": 1, "id": "call_b", "type": "function",
+                "function": {"name": "time", "arguments": "{}"}}]}),
+            ),
+            json!({"choices": [], "usage": {"prompt_tokens": 5, "completion_tokens": 7, "total_tokens": 12}}),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_normalized_stream_emits_text_and_whole_tool_calls() {
+        let response = sse_response(parallel_tool_call_events()).await;
+        let chunks: Vec<StreamResponse> = create_sse_stream(response, true)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(
+            chunks[0].choices[0].delta.content.as_deref(),
+            Some("Checking both.")
+        );
+        let call = |i: usize| &chunks[i].choices[0].delta.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call(1).index, 0);
+        assert_eq!(call(1).id.as_deref(), Some("call_a"));
+        let function = call(1).function.as_ref().unwrap();
+        assert_eq!(function.name, "weather");
+        assert_eq!(function.arguments, "{\"city\":\"Paris\"}");
+        assert_eq!(call(2).index, 1);
+        assert_eq!(call(2).id.as_deref(), Some("call_b"));
+        assert_eq!(chunks[3].usage.as_ref().unwrap().total_tokens, 12);
+    }
+
+    #[tokio::test]
+    async fn test_raw_stream_keeps_argument_deltas() {
+        let response = sse_response(parallel_tool_call_events()).await;
+        let chunks: Vec<StreamResponse> = create_sse_stream(response, false)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let deltas: Vec<StreamToolCallDelta> = chunks
+            .iter()
+            .filter_map(|c| c.choices.first()?.delta.tool_calls.clone())
+            .flatten()
+            .collect();
+        assert_eq!(deltas.len(), 4);
+        assert_eq!(deltas[3].index, 1);
+
+        let mut accumulator = ToolCallAccumulator::default();
+        let mut calls = accumulator.push(&deltas);
+        calls.extend(accumulator.finish());
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].function.arguments, "{\"city\":\"Paris\"}");
+        assert_eq!(calls[1].function.name, "time");
+    }
+
+    /// Answers one request with `body` and returns the URL to send it to,
+    /// along with the raw request the server received
+    #[cfg(any(feature = "openai", feature = "groq"))]
+    async fn serve_once(
+        content_type: &'static str,
+        body: Vec<u8>,
+    ) -> (Url, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/v1/", listener.local_addr().unwrap())).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            let header_end = loop {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..header_end]).to_ascii_lowercase();
+            let length: usize = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length: "))
+                .map_or(0, |value| value.trim().parse().unwrap());
+            while request.len() < header_end + length {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+            }
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+            request
+        });
+        (url, server)
+    }
+
+    #[cfg(any(feature = "openai", feature = "groq"))]
+    #[tokio::test]
+    async fn test_transcription_posts_audio_as_multipart_form() {
+        let reply = json!({"text": "hello there", "language": "english", "duration": 1.5});
+        let (url, server) = serve_once("application/json", reply.to_string().into_bytes()).await;
+        let request = TranscriptionRequest::new(Audio::new(b"RIFFdata".to_vec(), AudioFormat::Wav))
+            .language("en");
+
+        let transcription = transcribe_audio(
+            &Client::new(),
+            &url,
+            "key",
+            "whisper-1",
+            &request,
+            &RetryPolicy::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(transcription.text, "hello there");
+        assert_eq!(transcription.duration, Some(1.5));
+
+        let sent = String::from_utf8_lossy(&server.await.unwrap()).into_owned();
+        assert!(sent.starts_with("POST /v1/audio/transcriptions"));
+        assert!(sent.contains("name=\"model\"\r\n\r\nwhisper-1\r\n"));
+        assert!(sent.contains("name=\"response_format\"\r\n\r\nverbose_json\r\n"));
+        assert!(sent.contains("name=\"language\"\r\n\r\nen\r\n"));
+        assert!(
+            sent.contains("filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFFdata\r\n")
+        );
+    }
+
+    #[cfg(any(feature = "openai", feature = "groq"))]
+    #[test]
+    fn test_multipart_boundary_avoids_the_file_bytes() {
+        let data = b"xx--autoagents-form-boundaryxx";
+        let (content_type, body) =
+            multipart_form(&[("model", "whisper-1")], "audio.mp3", "audio/mpeg", data);
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        assert_ne!(boundary, "autoagents-form-boundary");
+        assert!(!data
+            .windows(boundary.len())
+            .any(|window| window == boundary.as_bytes()));
+        assert!(body.ends_with(format!("--{boundary}--\r\n").as_bytes()));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_speech_returns_the_audio_bytes() {
+        let (url, server) = serve_once("audio/mpeg", b"ID3audio".to_vec()).await;
+        let audio = synthesize_speech(
+            &Client::new(),
+            &url,
+            "key",
+            "gpt-4o-mini-tts",
+            "alloy",
+            &SpeechRequest::new("Hi").speed(1.25),
+            &RetryPolicy::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(audio, Audio::new(b"ID3audio".to_vec(), AudioFormat::Mp3));
+
+        let sent = String::from_utf8_lossy(&server.await.unwrap()).into_owned();
+        assert!(sent.starts_with("POST /v1/audio/speech"));
+        let body: serde_json::Value =
+            serde_json::from_str(sent.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(
+            body,
+            json!({"model": "gpt-4o-mini-tts", "input": "Hi", "voice": "alloy",
+                "response_format": "mp3", "speed": 1.25})
+        );
+
+        let unsupported = SpeechRequest::new("Hi").format(AudioFormat::Webm);
+        assert!(matches!(
+            synthesize_speech(
+                &Client::new(),
+                &url,
+                "key",
+                "gpt-4o-mini-tts",
+                "alloy",
+                &unsupported,
+                &RetryPolicy::default(),
+            )
+            .await,
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+}
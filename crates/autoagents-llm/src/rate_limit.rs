@@ -0,0 +1,402 @@
+//! Client-side rate limiting for LLM calls.
+//!
+//! [`RateLimiter`] enforces requests-per-minute and tokens-per-minute budgets
+//! over a sliding one-minute window, and [`RateLimitedLLM`] wraps any provider
+//! so each call waits for budget before it is sent. Bursty multi-agent runs
+//! then queue up locally instead of triggering a storm of 429 responses.
+//!
+//! A limiter is cheap to clone and clones share their budget, so a single
+//! limiter can cover several providers drawing from the same quota.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::{
+    audio::{SpeechToTextProvider, TextToSpeechProvider},
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamResponse, StructuredOutputFormat, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    tokenizer::TokenCounter,
+    LLMProvider,
+};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy)]
+struct WindowEntry {
+    at: Instant,
+    requests: u32,
+    tokens: u32,
+}
+
+/// Sliding-window limiter for requests and tokens per minute.
+///
+/// Token usage is recorded from an estimate when a request is admitted and
+/// topped up with [`RateLimiter::record_tokens`] once the provider reports
+/// actual usage. A single request larger than the token budget is admitted
+/// once the window is empty rather than waiting forever.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+    window: Arc<Mutex<VecDeque<WindowEntry>>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter without limits; configure it with the `with_` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the number of requests started per minute.
+    pub fn with_requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute = Some(requests_per_minute.max(1));
+        self
+    }
+
+    /// Limits the number of tokens consumed per minute.
+    pub fn with_tokens_per_minute(mut self, tokens_per_minute: u32) -> Self {
+        self.tokens_per_minute = Some(tokens_per_minute.max(1));
+        self
+    }
+
+    /// Waits until a request estimated at `tokens` fits the budget, then
+    /// records it.
+    pub async fn acquire(&self, tokens: u32) {
+        loop {
+            let wait = match self.try_acquire_at(tokens, Instant::now()) {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            log::debug!("Rate limit reached, waiting {wait:?} before sending request");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Records a request estimated at `tokens` if it fits the budget right
+    /// now; otherwise returns how long until it would.
+    pub fn try_acquire(&self, tokens: u32) -> Result<(), Duration> {
+        self.try_acquire_at(tokens, Instant::now())
+    }
+
+    /// Records tokens consumed beyond what was estimated when the request
+    /// was admitted.
+    pub fn record_tokens(&self, tokens: u32) {
+        if tokens == 0 {
+            return;
+        }
+        self.entries().push_back(WindowEntry {
+            at: Instant::now(),
+            requests: 0,
+            tokens,
+        });
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, VecDeque<WindowEntry>> {
+        self.window
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn try_acquire_at(&self, tokens: u32, now: Instant) -> Result<(), Duration> {
+        let mut entries = self.entries();
+        while entries
+            .front()
+            .is_some_and(|entry| entry.at + WINDOW <= now)
+        {
+            entries.pop_front();
+        }
+
+        let mut requests: u32 = entries.iter().map(|entry| entry.requests).sum();
+        let mut used: u64 = entries.iter().map(|entry| u64::from(entry.tokens)).sum();
+        if self.fits(requests, used, tokens) {
+            entries.push_back(WindowEntry {
+                at: now,
+                requests: 1,
+                tokens,
+            });
+            return Ok(());
+        }
+
+        // Find the first entry whose expiry frees enough budget.
+        for entry in entries.iter() {
+            requests -= entry.requests;
+            used -= u64::from(entry.tokens);
+            if self.fits(requests, used, tokens) {
+                return Err((entry.at + WINDOW).saturating_duration_since(now));
+            }
+        }
+        Err(WINDOW)
+    }
+
+    fn fits(&self, requests: u32, used: u64, tokens: u32) -> bool {
+        let requests_ok = self
+            .requests_per_minute
+            .is_none_or(|limit| requests < limit);
+        let tokens_ok = self
+            .tokens_per_minute
+            .is_none_or(|limit| used == 0 || used + u64::from(tokens) <= u64::from(limit));
+        requests_ok && tokens_ok
+    }
+}
+
+/// Wraps a provider so every call waits for [`RateLimiter`] budget first.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use autoagents_llm::{
+///     rate_limit::{RateLimitedLLM, RateLimiter},
+///     LLMProvider,
+/// };
+///
+/// fn limited(llm: Arc<dyn LLMProvider>) -> RateLimitedLLM {
+///     let limiter = RateLimiter::new()
+///         .with_requests_per_minute(500)
+///         .with_tokens_per_minute(200_000);
+///     RateLimitedLLM::new(llm, limiter)
+/// }
+/// ```
+///
+/// Requests are charged the prompt size estimated with the provider's
+/// [`TokenCounter`]; when a response reports usage, the difference is
+/// charged afterwards.
+pub struct RateLimitedLLM {
+    provider: Arc<dyn LLMProvider>,
+    limiter: RateLimiter,
+    token_counter: Arc<dyn TokenCounter>,
+}
+
+impl std::fmt::Debug for RateLimitedLLM {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitedLLM")
+            .field("limiter", &self.limiter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RateLimitedLLM {
+    /// Wraps `provider`; pass a clone of `limiter` to share its budget.
+    pub fn new(provider: Arc<dyn LLMProvider>, limiter: RateLimiter) -> Self {
+        let token_counter = provider.token_counter();
+        Self {
+            provider,
+            limiter,
+            token_counter,
+        }
+    }
+
+    /// The limiter shared by this provider.
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    fn estimate_chat(&self, messages: &[ChatMessage], tools: Option<&[Tool]>) -> u32 {
+        let messages: usize = messages
+            .iter()
+            .map(|message| self.token_counter.count_message(message))
+            .sum();
+        let tools = tools
+            .and_then(|tools| serde_json::to_string(tools).ok())
+            .map_or(0, |tools| self.token_counter.count(&tools));
+        u32::try_from(messages + tools).unwrap_or(u32::MAX)
+    }
+
+    fn estimate_text<'a>(&self, texts: impl IntoIterator<Item = &'a str>) -> u32 {
+        let tokens: usize = texts
+            .into_iter()
+            .map(|text| self.token_counter.count(text))
+            .sum();
+        u32::try_from(tokens).unwrap_or(u32::MAX)
+    }
+}
+
+#[async_trait]
+impl ChatProvider for RateLimitedLLM {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let estimate = self.estimate_chat(messages, tools);
+        self.limiter.acquire(estimate).await;
+        let response = self.provider.chat(messages, tools, json_schema).await?;
+        if let Some(usage) = response.usage() {
+            self.limiter
+                .record_tokens(usage.total_tokens.saturating_sub(estimate));
+        }
+        Ok(response)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.limiter
+            .acquire(self.estimate_chat(messages, tools))
+            .await;
+        self.provider
+            .chat_stream(messages, tools, json_schema)
+            .await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.limiter
+            .acquire(self.estimate_chat(messages, tools))
+            .await;
+        self.provider
+            .chat_stream_struct(messages, tools, json_schema)
+            .await
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.provider.supports_structured_output()
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for RateLimitedLLM {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let estimate = self.estimate_text([req.prompt.as_str()]);
+        self.limiter.acquire(estimate).await;
+        let response = self.provider.complete(req, json_schema).await?;
+        let used = match &response.usage {
+            Some(usage) => usage.total_tokens.saturating_sub(estimate),
+            None => self.estimate_text([response.text.as_str()]),
+        };
+        self.limiter.record_tokens(used);
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for RateLimitedLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.limiter
+            .acquire(self.estimate_text(input.iter().map(String::as_str)))
+            .await;
+        self.provider.embed(input).await
+    }
+
+    fn embedding_dimensions(&self) -> Option<usize> {
+        self.provider.embedding_dimensions()
+    }
+
+    fn max_embedding_batch(&self) -> Option<usize> {
+        self.provider.max_embedding_batch()
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for RateLimitedLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.provider.list_models(request).await
+    }
+}
+
+impl LLMProvider for RateLimitedLLM {
+    fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        self.token_counter.clone()
+    }
+
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        self.provider.speech_to_text()
+    }
+
+    fn text_to_speech(&self) -> Option<&dyn TextToSpeechProvider> {
+        self.provider.text_to_speech()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_per_minute() {
+        let limiter = RateLimiter::new().with_requests_per_minute(2);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(0, start).is_ok());
+        assert!(limiter
+            .try_acquire_at(0, start + Duration::from_secs(10))
+            .is_ok());
+        assert_eq!(
+            limiter.try_acquire_at(0, start + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+        assert!(limiter
+            .try_acquire_at(0, start + Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_tokens_per_minute() {
+        let limiter = RateLimiter::new().with_tokens_per_minute(1000);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(600, start).is_ok());
+        assert!(limiter
+            .try_acquire_at(300, start + Duration::from_secs(5))
+            .is_ok());
+        assert_eq!(
+            limiter.try_acquire_at(500, start + Duration::from_secs(30)),
+            Err(Duration::from_secs(30))
+        );
+        // The first request expiring frees enough for a 500 token request.
+        assert!(limiter
+            .try_acquire_at(500, start + Duration::from_secs(61))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_oversized_request_is_admitted_on_empty_window() {
+        let limiter = RateLimiter::new().with_tokens_per_minute(100);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(5000, start).is_ok());
+        assert_eq!(
+            limiter.try_acquire_at(10, start + Duration::from_secs(1)),
+            Err(Duration::from_secs(59))
+        );
+    }
+
+    #[test]
+    fn test_recorded_usage_counts_against_budget() {
+        let limiter = RateLimiter::new().with_tokens_per_minute(1000);
+        assert!(limiter.try_acquire(100).is_ok());
+        limiter.record_tokens(850);
+        assert!(limiter.try_acquire(100).is_err());
+        assert!(limiter.try_acquire(50).is_ok());
+    }
+
+    #[test]
+    fn test_clones_share_budget() {
+        let limiter = RateLimiter::new().with_requests_per_minute(1);
+        let shared = limiter.clone();
+        assert!(limiter.try_acquire(0).is_ok());
+        assert!(shared.try_acquire(0).is_err());
+    }
+}
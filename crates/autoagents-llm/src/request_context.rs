@@ -0,0 +1,123 @@
+//! Per-request HTTP headers scoped to an async task.
+//!
+//! Agents use this to forward run identifiers and caller metadata to LLM
+//! providers without threading them through every `ChatProvider` call. Any
+//! request issued by a backend while a scope is active carries the scoped
+//! headers in addition to its own.
+
+use reqwest::header::{HeaderName, HeaderValue};
+use std::collections::BTreeMap;
+use std::future::Future;
+
+tokio::task_local! {
+    static REQUEST_HEADERS: BTreeMap<String, String>;
+}
+
+/// Run `fut` with `headers` attached to every LLM HTTP request it makes.
+///
+/// Scopes nest: headers from an enclosing scope are kept unless overridden
+/// by a key with the same name.
+pub async fn scope<F>(headers: BTreeMap<String, String>, fut: F) -> F::Output
+where
+    F: Future,
+{
+    let mut merged = current_headers();
+    merged.extend(headers);
+    REQUEST_HEADERS.scope(merged, fut).await
+}
+
+/// Headers of the innermost active scope, or an empty map outside of one.
+pub fn current_headers() -> BTreeMap<String, String> {
+    REQUEST_HEADERS
+        .try_with(|headers| headers.clone())
+        .unwrap_or_default()
+}
+
+/// Applies the scoped headers to an outgoing request.
+pub(crate) trait RequestHeadersExt {
+    fn with_scoped_headers(self) -> Self;
+}
+
+impl RequestHeadersExt for reqwest::RequestBuilder {
+    /// Headers that are not valid HTTP are skipped, since reqwest would fail
+    /// the whole request over them
+    fn with_scoped_headers(self) -> Self {
+        current_headers().iter().fold(self, |req, (name, value)| {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                (Ok(name), Ok(value)) => req.header(name, value),
+                _ => {
+                    log::warn!("Skipping scoped request header '{name}' with an invalid value");
+                    req
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_no_scope_is_empty() {
+        assert!(current_headers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scope_exposes_headers() {
+        let seen = scope(headers(&[("x-run-id", "abc")]), async { current_headers() }).await;
+        assert_eq!(seen.get("x-run-id").map(String::as_str), Some("abc"));
+        assert!(current_headers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_nested_scopes_merge() {
+        let seen = scope(headers(&[("a", "1"), ("b", "2")]), async {
+            scope(headers(&[("b", "3")]), async { current_headers() }).await
+        })
+        .await;
+        assert_eq!(seen, headers(&[("a", "1"), ("b", "3")]));
+    }
+
+    #[tokio::test]
+    async fn test_request_builder_gets_headers() {
+        let client = reqwest::Client::new();
+        let request = scope(headers(&[("x-tenant-id", "acme")]), async {
+            client
+                .get("http://localhost/")
+                .with_scoped_headers()
+                .build()
+                .unwrap()
+        })
+        .await;
+        assert_eq!(request.headers().get("x-tenant-id").unwrap(), "acme");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_headers_are_skipped() {
+        let client = reqwest::Client::new();
+        let request = scope(
+            headers(&[("x-tags", "billing\nurgent"), ("x-run-id", "abc")]),
+            async {
+                client
+                    .get("http://localhost/")
+                    .with_scoped_headers()
+                    .build()
+                    .unwrap()
+            },
+        )
+        .await;
+        assert!(request.headers().get("x-tags").is_none());
+        assert_eq!(request.headers().get("x-run-id").unwrap(), "abc");
+    }
+}
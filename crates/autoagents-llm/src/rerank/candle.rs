@@ -0,0 +1,168 @@
+//! Reranking computed on the local machine.
+//!
+//! [`CandleReranker`] runs a BERT cross-encoder with a single relevance
+//! label, such as `cross-encoder/ms-marco-MiniLM-L-6-v2` or
+//! `BAAI/bge-reranker-base`, with candle. The model directory needs the
+//! `config.json`, `tokenizer.json` and `model.safetensors` files of the
+//! checkpoint. The query and each document are read together, and the
+//! sigmoid of the classifier output is the score, between 0 and 1.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::{Linear, Module};
+use candle_transformers::models::bert::BertModel;
+use tokenizers::Tokenizer;
+
+use super::{sort_results, RerankResult, Reranker};
+use crate::embedding::candle::{load_config, load_tokenizer, load_weights, model_error};
+use crate::error::LLMError;
+
+/// Query and document pairs scored per forward pass unless set otherwise
+const DEFAULT_BATCH_SIZE: usize = 16;
+
+/// Reranker backed by a local cross-encoder.
+#[derive(Clone)]
+pub struct CandleReranker {
+    model: Arc<CrossEncoder>,
+    batch_size: usize,
+}
+
+struct CrossEncoder {
+    bert: BertModel,
+    pooler: Linear,
+    classifier: Linear,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+impl std::fmt::Debug for CandleReranker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CandleReranker")
+            .field("device", &self.model.device)
+            .field("batch_size", &self.batch_size)
+            .finish()
+    }
+}
+
+impl CandleReranker {
+    /// Load the checkpoint in `dir` on the CPU.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, LLMError> {
+        let dir = dir.as_ref();
+        Self::from_files(
+            dir.join("config.json"),
+            dir.join("tokenizer.json"),
+            dir.join("model.safetensors"),
+            Device::Cpu,
+        )
+    }
+
+    /// Load a checkpoint from its individual files onto `device`.
+    pub fn from_files(
+        config: impl AsRef<Path>,
+        tokenizer: impl AsRef<Path>,
+        weights: impl AsRef<Path>,
+        device: Device,
+    ) -> Result<Self, LLMError> {
+        let config = load_config(config.as_ref())?;
+        let tokenizer = load_tokenizer(tokenizer.as_ref(), &config)?;
+        let weights = load_weights(weights.as_ref(), &device)?;
+
+        // Sequence classification checkpoints nest the encoder under "bert"
+        let encoder = if weights.contains_tensor("bert.embeddings.word_embeddings.weight") {
+            weights.pp("bert")
+        } else {
+            weights.clone()
+        };
+        let hidden = config.hidden_size;
+        let pooler = candle_nn::linear(hidden, hidden, encoder.pp("pooler").pp("dense"))
+            .map_err(model_error)?;
+        let bert = BertModel::load(encoder, &config).map_err(model_error)?;
+        let classifier =
+            candle_nn::linear(hidden, 1, weights.pp("classifier")).map_err(model_error)?;
+
+        Ok(Self {
+            model: Arc::new(CrossEncoder {
+                bert,
+                pooler,
+                classifier,
+                tokenizer,
+                device,
+            }),
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Pairs scored per forward pass, bounding memory use
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+impl CrossEncoder {
+    fn score(&self, pairs: Vec<(String, String)>) -> candle_core::Result<Vec<f32>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(candle_core::Error::msg)?;
+        let tensors = |field: fn(&tokenizers::Encoding) -> &[u32]| {
+            let rows = encodings
+                .iter()
+                .map(|encoding| Tensor::new(field(encoding), &self.device))
+                .collect::<candle_core::Result<Vec<_>>>()?;
+            Tensor::stack(&rows, 0)
+        };
+        let ids = tensors(tokenizers::Encoding::get_ids)?;
+        let token_types = tensors(tokenizers::Encoding::get_type_ids)?;
+        let mask = tensors(tokenizers::Encoding::get_attention_mask)?;
+
+        let states = self.bert.forward(&ids, &token_types, Some(&mask))?;
+        let first = states.narrow(1, 0, 1)?.squeeze(1)?;
+        let pooled = self.pooler.forward(&first)?.tanh()?;
+        let logits = self.classifier.forward(&pooled)?.squeeze(1)?;
+        candle_nn::ops::sigmoid(&logits)?
+            .to_dtype(DType::F32)?
+            .to_vec1()
+    }
+}
+
+#[async_trait]
+impl Reranker for CandleReranker {
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_n: Option<usize>,
+    ) -> Result<Vec<RerankResult>, LLMError> {
+        let mut results = Vec::with_capacity(documents.len());
+        for (batch_index, batch) in documents.chunks(self.batch_size).enumerate() {
+            let pairs = batch
+                .iter()
+                .map(|document| (query.to_string(), document.clone()))
+                .collect();
+            let model = self.model.clone();
+            let scores = tokio::task::spawn_blocking(move || model.score(pairs))
+                .await
+                .map_err(|e| LLMError::ProviderError(format!("Rerank task failed: {e}")))?
+                .map_err(model_error)?;
+            let offset = batch_index * self.batch_size;
+            results.extend(
+                scores
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, score)| RerankResult {
+                        index: offset + i,
+                        score,
+                    }),
+            );
+        }
+        sort_results(&mut results);
+        if let Some(top_n) = top_n {
+            results.truncate(top_n);
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,196 @@
+//! Reranking with Jina AI's hosted rerankers.
+
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Serialize;
+
+use super::{RerankResponse, RerankResult, Reranker};
+use crate::chat::utils::check_response_status;
+use crate::error::LLMError;
+use crate::request_context::RequestHeadersExt;
+use crate::retry::{RetryPolicy, RetryRequestExt};
+
+const DEFAULT_BASE_URL: &str = "https://api.jina.ai/v1/";
+const DEFAULT_MODEL: &str = "jina-reranker-v2-base-multilingual";
+
+/// Client for Jina AI's rerank endpoint.
+#[derive(Debug, Clone)]
+pub struct JinaReranker {
+    pub api_key: String,
+    pub base_url: Url,
+    pub model: String,
+    pub retry_policy: RetryPolicy,
+    client: Client,
+}
+
+#[derive(Serialize, Debug)]
+struct JinaRerankRequest<'a> {
+    model: &'a str,
+    query: &'a str,
+    documents: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_n: Option<usize>,
+    return_documents: bool,
+}
+
+impl JinaReranker {
+    /// Creates a client for the default model, "jina-reranker-v2-base-multilingual".
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: Url::parse(DEFAULT_BASE_URL).expect("Failed to parse base URL"),
+            model: DEFAULT_MODEL.to_string(),
+            retry_policy: RetryPolicy::default(),
+            client: Client::new(),
+        }
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Base URL of the v1 API, ending with a slash
+    pub fn base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl Reranker for JinaReranker {
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_n: Option<usize>,
+    ) -> Result<Vec<RerankResult>, LLMError> {
+        if self.api_key.is_empty() {
+            return Err(LLMError::AuthError("Missing Jina API key".to_string()));
+        }
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = JinaRerankRequest {
+            model: &self.model,
+            query,
+            documents,
+            top_n,
+            return_documents: false,
+        };
+        let url = self
+            .base_url
+            .join("rerank")
+            .map_err(|e| LLMError::HttpError(e.to_string()))?;
+        let resp = self
+            .client
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .with_scoped_headers()
+            .send_with_retry(&self.retry_policy)
+            .await?;
+        let resp = check_response_status(resp).await?;
+
+        let json_resp: RerankResponse = resp.json().await?;
+        Ok(json_resp.into_results(documents.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answers one request with `reply`, returning the request body
+    async fn serve_once(reply: Value) -> (Url, tokio::task::JoinHandle<Value>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/v1/", listener.local_addr().unwrap())).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            let header_end = loop {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..header_end]).to_ascii_lowercase();
+            assert!(headers.starts_with("post /v1/rerank "), "{headers}");
+            let length: usize = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length: "))
+                .map_or(0, |value| value.trim().parse().unwrap());
+            while request.len() < header_end + length {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+            }
+            let body = reply.to_string();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(body.as_bytes()).await.unwrap();
+            serde_json::from_slice(&request[header_end..]).unwrap()
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn test_rerank_orders_documents_by_relevance() {
+        let (url, server) = serve_once(json!({
+            "model": DEFAULT_MODEL,
+            "results": [
+                {"index": 1, "relevance_score": 0.91},
+                {"index": 0, "relevance_score": 0.12}
+            ],
+            "usage": {"total_tokens": 20}
+        }))
+        .await;
+        let reranker = JinaReranker::new("test-key").base_url(url);
+        let documents = vec![
+            "Paris is in France".to_string(),
+            "Rust has no garbage collector".to_string(),
+        ];
+
+        let results = reranker
+            .rerank("memory management in rust", &documents, Some(2))
+            .await
+            .unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.index).collect::<Vec<_>>(),
+            vec![1, 0]
+        );
+
+        let request = server.await.unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "model": DEFAULT_MODEL,
+                "query": "memory management in rust",
+                "documents": documents,
+                "top_n": 2,
+                "return_documents": false
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rerank_requires_api_key() {
+        let err = JinaReranker::new("")
+            .rerank("query", &["doc".to_string()], None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LLMError::AuthError(_)));
+    }
+}
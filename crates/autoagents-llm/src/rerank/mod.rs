@@ -0,0 +1,112 @@
+//! Relevance scoring of documents against a query.
+//!
+//! A [`Reranker`] takes the candidates found by a cheaper first stage, such
+//! as an embedding search, and orders them by how well each one answers the
+//! query. The Cohere backend implements it through its rerank endpoint, the
+//! `jina` module wraps Jina's hosted rerankers and the `candle` module runs a
+//! cross-encoder locally.
+
+use async_trait::async_trait;
+#[cfg(any(feature = "cohere", feature = "jina"))]
+use serde::Deserialize;
+
+use crate::error::LLMError;
+
+/// Jina AI rerank API client
+#[cfg(all(feature = "jina", not(target_arch = "wasm32")))]
+pub mod jina;
+
+/// Local cross-encoder reranking computed with candle
+#[cfg(all(feature = "candle", not(target_arch = "wasm32")))]
+pub mod candle;
+
+/// Relevance of one document to the query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RerankResult {
+    /// Position of the document in the reranked slice
+    pub index: usize,
+    /// Higher is more relevant; the scale depends on the reranker
+    pub score: f32,
+}
+
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Score `documents` against `query`, most relevant first.
+    ///
+    /// At most `top_n` results are returned, every document when `None`.
+    async fn rerank(
+        &self,
+        query: &str,
+        documents: &[String],
+        top_n: Option<usize>,
+    ) -> Result<Vec<RerankResult>, LLMError>;
+}
+
+/// Response body shared by the Cohere and Jina rerank endpoints
+#[cfg(any(feature = "cohere", feature = "jina"))]
+#[derive(Debug, Deserialize)]
+pub(crate) struct RerankResponse {
+    results: Vec<RerankHit>,
+}
+
+#[cfg(any(feature = "cohere", feature = "jina"))]
+#[derive(Debug, Deserialize)]
+struct RerankHit {
+    index: usize,
+    relevance_score: f32,
+}
+
+#[cfg(any(feature = "cohere", feature = "jina"))]
+impl RerankResponse {
+    /// The results best first, dropping indices outside `documents`
+    pub(crate) fn into_results(self, documents: usize) -> Vec<RerankResult> {
+        let mut results: Vec<RerankResult> = self
+            .results
+            .into_iter()
+            .filter(|hit| hit.index < documents)
+            .map(|hit| RerankResult {
+                index: hit.index,
+                score: hit.relevance_score,
+            })
+            .collect();
+        sort_results(&mut results);
+        results
+    }
+}
+
+/// Order results from most to least relevant.
+pub fn sort_results(results: &mut [RerankResult]) {
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+#[cfg(all(test, any(feature = "cohere", feature = "jina")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_is_sorted_and_bounded() {
+        let response: RerankResponse = serde_json::from_value(serde_json::json!({
+            "id": "rerank-1",
+            "results": [
+                {"index": 1, "relevance_score": 0.2},
+                {"index": 0, "relevance_score": 0.9},
+                {"index": 7, "relevance_score": 0.95}
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            response.into_results(2),
+            vec![
+                RerankResult {
+                    index: 0,
+                    score: 0.9
+                },
+                RerankResult {
+                    index: 1,
+                    score: 0.2
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Automatic retry of provider HTTP requests.
+//!
+//! Backends send their requests with `send_with_retry`, which retries
+//! responses with a retryable status (see [`is_retryable_status`]) as well as
+//! timeouts and connection failures according to the backend's
+//! [`RetryPolicy`]. The delay between attempts follows the provider's
+//! `Retry-After` header when present, and exponential backoff with jitter
+//! otherwise.
+//!
+//! The default policy performs no retries; enable it through
+//! [`LLMBuilder::retry_policy`](crate::builder::LLMBuilder::retry_policy) or
+//! [`LLMBuilder::max_retries`](crate::builder::LLMBuilder::max_retries).
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response};
+
+use crate::error::is_retryable_status;
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How failed requests are retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every further attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound for the computed backoff.
+    pub max_backoff: Duration,
+    /// Randomize each backoff between half and all of its value so that
+    /// concurrent clients do not retry in lockstep.
+    pub jitter: bool,
+    /// Longest `Retry-After` the client is willing to wait; a response
+    /// asking for longer is returned instead of retried.
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            jitter: true,
+            max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy retrying up to `max_retries` times with the default backoff.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    /// Backoff before retry number `attempt` (starting at 0), without a
+    /// `Retry-After` hint.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        if self.jitter {
+            let half = backoff / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Reads how long the provider asks clients to wait before retrying.
+///
+/// Supports `retry-after-ms` (sent by OpenAI and Azure) and `Retry-After`
+/// given either in seconds or as an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(millis) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return Some(Duration::from_secs_f64(millis.max(0.0) / 1000.0));
+    }
+    let value = header("retry-after")?.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Some(Duration::from_secs_f64(seconds.max(0.0)));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or_default())
+}
+
+/// Sends `request`, retrying it according to `policy`.
+///
+/// Only the sending is retried: a response with a non-retryable status, or
+/// the last response once retries are exhausted, is returned for the caller
+/// to handle as usual. Requests whose body cannot be cloned (streams) are sent
+/// once.
+pub(crate) async fn send_with_retry(
+    request: RequestBuilder,
+    policy: &RetryPolicy,
+) -> Result<Response, reqwest::Error> {
+    let mut attempt = 0;
+    loop {
+        if attempt >= policy.max_retries {
+            return request.send().await;
+        }
+        let Some(current) = request.try_clone() else {
+            return request.send().await;
+        };
+
+        let delay = match current.send().await {
+            Ok(response) if is_retryable_status(response.status().as_u16()) => {
+                match retry_after(response.headers()) {
+                    Some(wait) if wait > policy.max_retry_after => return Ok(response),
+                    Some(wait) => wait,
+                    None => policy.backoff(attempt),
+                }
+            }
+            Ok(response) => return Ok(response),
+            Err(err) if err.is_timeout() || err.is_connect() => policy.backoff(attempt),
+            Err(err) => return Err(err),
+        };
+
+        attempt += 1;
+        log::warn!(
+            "Request failed, retrying in {delay:?} (attempt {attempt} of {})",
+            policy.max_retries
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Sends a request under a [`RetryPolicy`].
+pub(crate) trait RetryRequestExt {
+    fn send_with_retry(
+        self,
+        policy: &RetryPolicy,
+    ) -> impl Future<Output = Result<Response, reqwest::Error>> + Send;
+}
+
+impl RetryRequestExt for RequestBuilder {
+    fn send_with_retry(
+        self,
+        policy: &RetryPolicy,
+    ) -> impl Future<Output = Result<Response, reqwest::Error>> + Send {
+        send_with_retry(self, policy)
+    }
+}
+
+/// A value in `[0, 1)` from the standard library's randomly seeded hasher,
+/// which is plenty for spreading retries.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serves one canned response per connection, in order, and returns how
+    /// many requests were received
+    async fn serve(responses: Vec<&'static str>) -> (String, tokio::task::JoinHandle<usize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut served = 0;
+            for response in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    break;
+                };
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                socket.write_all(response.as_bytes()).await.unwrap();
+                served += 1;
+            }
+            served
+        });
+        (url, server)
+    }
+
+    const TOO_MANY_REQUESTS: &str = "HTTP/1.1 429 Too Many Requests\r\nretry-after: 0\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+    const UNAVAILABLE: &str =
+        "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+    const BAD_REQUEST: &str =
+        "HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok";
+
+    fn fast_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::new(max_retries).with_initial_backoff(Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let (url, server) = serve(vec![TOO_MANY_REQUESTS, UNAVAILABLE, OK]).await;
+        let response = reqwest::Client::new()
+            .get(&url)
+            .send_with_retry(&fast_policy(3))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(server.await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_returns_last_response_when_retries_are_exhausted() {
+        let (url, server) = serve(vec![UNAVAILABLE, UNAVAILABLE]).await;
+        let response = reqwest::Client::new()
+            .get(&url)
+            .send_with_retry(&fast_policy(1))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(server.await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_client_errors() {
+        let (url, server) = serve(vec![BAD_REQUEST]).await;
+        let response = reqwest::Client::new()
+            .get(&url)
+            .send_with_retry(&fast_policy(3))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+        assert_eq!(server.await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_default_policy_does_not_retry() {
+        assert_eq!(RetryPolicy::default().max_retries, 0);
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy::new(5)
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500))
+            .with_jitter(false);
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::new(3).with_initial_backoff(Duration::from_millis(400));
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= Duration::from_millis(400), "{backoff:?}");
+            assert!(backoff <= Duration::from_millis(800), "{backoff:?}");
+        }
+    }
+
+    #[test]
+    fn test_retry_after_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("12"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(12)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("1500"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(1500)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert("retry-after", HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+}
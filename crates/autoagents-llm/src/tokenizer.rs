@@ -0,0 +1,205 @@
+//! Token counting for budgeting how much conversation fits in a context window.
+//!
+//! Providers expose a counter matching their model through
+//! [`LLMProvider::token_counter`](crate::LLMProvider::token_counter), picked
+//! by [`counter_for_model`]. Without the `tiktoken` feature every model falls
+//! back to [`HeuristicTokenCounter`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::chat::{ChatMessage, ContentPart, MessageType};
+
+/// Tokens added per message for role markers and separators
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Counts the tokens a piece of text occupies for a model.
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+
+    /// Tokens a message takes in a request, including tool call payloads.
+    fn count_message(&self, message: &ChatMessage) -> usize {
+        let payload = match &message.message_type {
+            MessageType::ToolUse(calls) | MessageType::ToolResult(calls) => calls
+                .iter()
+                .map(|call| self.count(&call.function.name) + self.count(&call.function.arguments))
+                .sum(),
+            MessageType::Parts(parts) => parts
+                .iter()
+                .filter_map(ContentPart::as_text)
+                .map(|text| self.count(text))
+                .sum(),
+            _ => 0,
+        };
+        MESSAGE_OVERHEAD + self.count(&message.content) + payload
+    }
+}
+
+/// Approximates tokens from the character count.
+///
+/// About four characters per token holds reasonably well for English text
+/// with most BPE tokenizers; it is an estimate, not a guarantee.
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicTokenCounter {
+    chars_per_token: f32,
+}
+
+impl HeuristicTokenCounter {
+    pub fn new(chars_per_token: f32) -> Self {
+        Self {
+            chars_per_token: chars_per_token.max(f32::EPSILON),
+        }
+    }
+}
+
+impl Default for HeuristicTokenCounter {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        (text.chars().count() as f32 / self.chars_per_token).ceil() as usize
+    }
+}
+
+/// Exact counts for OpenAI models using their BPE encodings.
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenCounter {
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenCounter {
+    /// Counter for the encoding used by `model`, e.g. `gpt-4o`.
+    pub fn for_model(model: &str) -> Result<Self, crate::error::LLMError> {
+        tiktoken_rs::get_bpe_from_model(model)
+            .map(|bpe| Self { bpe })
+            .map_err(|e| crate::error::LLMError::InvalidRequest(e.to_string()))
+    }
+
+    /// Counter for the `cl100k_base` encoding, a reasonable approximation
+    /// for models without a published tokenizer.
+    pub fn cl100k() -> Result<Self, crate::error::LLMError> {
+        tiktoken_rs::cl100k_base()
+            .map(|bpe| Self { bpe })
+            .map_err(|e| crate::error::LLMError::InvalidRequest(e.to_string()))
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for TiktokenCounter {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl std::fmt::Debug for TiktokenCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TiktokenCounter").finish_non_exhaustive()
+    }
+}
+
+/// Token counter for `model`, shared across calls.
+///
+/// With the `tiktoken` feature, OpenAI model families get their exact BPE
+/// encoding and other models (Claude, Gemini, Llama, Mistral, ...) are
+/// approximated with `cl100k_base`, which tracks modern BPE vocabularies far
+/// more closely than a character ratio. Without the feature, or when no
+/// encoding can be loaded, a [`HeuristicTokenCounter`] is returned.
+pub fn counter_for_model(model: &str) -> Arc<dyn TokenCounter> {
+    static COUNTERS: OnceLock<Mutex<HashMap<String, Arc<dyn TokenCounter>>>> = OnceLock::new();
+    let mut counters = COUNTERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    counters
+        .entry(model.to_string())
+        .or_insert_with(|| load_counter(model))
+        .clone()
+}
+
+#[cfg(feature = "tiktoken")]
+fn load_counter(model: &str) -> Arc<dyn TokenCounter> {
+    match TiktokenCounter::for_model(model).or_else(|_| TiktokenCounter::cl100k()) {
+        Ok(counter) => Arc::new(counter),
+        Err(_) => Arc::new(HeuristicTokenCounter::default()),
+    }
+}
+
+#[cfg(not(feature = "tiktoken"))]
+fn load_counter(_model: &str) -> Arc<dyn TokenCounter> {
+    Arc::new(HeuristicTokenCounter::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FunctionCall, ToolCall};
+
+    #[test]
+    fn test_heuristic_rounds_up() {
+        let counter = HeuristicTokenCounter::default();
+        assert_eq!(counter.count(""), 0);
+        assert_eq!(counter.count("abc"), 1);
+        assert_eq!(counter.count("abcdefghi"), 3);
+        assert_eq!(HeuristicTokenCounter::new(1.0).count("héllo"), 5);
+    }
+
+    #[test]
+    fn test_message_count_includes_tool_payload() {
+        let counter = HeuristicTokenCounter::new(1.0);
+        let text = ChatMessage::user().content("hi").build();
+        assert_eq!(counter.count_message(&text), MESSAGE_OVERHEAD + 2);
+
+        let tool_result = ChatMessage {
+            role: crate::chat::ChatRole::Tool,
+            message_type: MessageType::ToolResult(vec![ToolCall {
+                id: "1".into(),
+                call_type: "function".into(),
+                function: FunctionCall {
+                    name: "ls".into(),
+                    arguments: "a b c".into(),
+                },
+            }]),
+            content: String::new(),
+            reasoning: Vec::new(),
+        };
+        assert_eq!(counter.count_message(&tool_result), MESSAGE_OVERHEAD + 7);
+    }
+
+    #[test]
+    fn test_counter_for_model_is_shared() {
+        let first = counter_for_model("claude-3-5-sonnet-latest");
+        let second = counter_for_model("claude-3-5-sonnet-latest");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(first.count("hello world") > 0);
+    }
+
+    #[cfg(not(feature = "tiktoken"))]
+    #[test]
+    fn test_counter_for_model_without_tiktoken_is_heuristic() {
+        let counter = counter_for_model("gpt-4o");
+        assert_eq!(
+            counter.count("abcdefgh"),
+            HeuristicTokenCounter::default().count("abcdefgh")
+        );
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_counter_for_model_approximates_unknown_models() {
+        let counter = counter_for_model("llama3.1:8b");
+        assert_eq!(counter.count("hello world"), 2);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken_counts_model_tokens() {
+        let counter = TiktokenCounter::for_model("gpt-4").unwrap();
+        assert_eq!(counter.count("hello world"), 2);
+        assert!(TiktokenCounter::for_model("not-a-model").is_err());
+    }
+}
@@ -345,16 +345,19 @@ mod anthropic_test_cases {
                 role: ChatRole::System,
                 message_type: MessageType::Text,
                 content: "You are a helpful assistant".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: "Hello".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: "Hi there!".to_string(),
+                reasoning: Vec::new(),
             },
         ];
 
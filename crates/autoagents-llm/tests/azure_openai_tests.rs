@@ -16,7 +16,10 @@ use std::sync::Arc;
 #[cfg(feature = "azure_openai")]
 mod azure_openai_test_cases {
     use super::*;
-    use autoagents_llm::{backends::azure_openai::AzureOpenAI, chat::ReasoningEffort};
+    use autoagents_llm::{
+        backends::azure_openai::{AzureOpenAI, AzureTokenProvider},
+        chat::ReasoningEffort,
+    };
 
     fn create_test_azure_openai() -> Arc<AzureOpenAI> {
         LLMBuilder::<AzureOpenAI>::new()
@@ -242,4 +245,31 @@ mod azure_openai_test_cases {
             "https://myresource.openai.azure.com/openai/deployments/my-deployment/"
         );
     }
+
+    struct NoToken;
+
+    #[async_trait::async_trait]
+    impl AzureTokenProvider for NoToken {
+        async fn token(&self) -> Result<String, LLMError> {
+            Err(LLMError::AuthError("not signed in".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_provider_replaces_api_key() {
+        let client = LLMBuilder::<AzureOpenAI>::new()
+            .token_provider(NoToken)
+            .api_version("2024-10-21")
+            .deployment_id("gpt-4o")
+            .base_url("https://myresource.openai.azure.com")
+            .build()
+            .expect("an Entra ID token provider should stand in for the API key");
+        assert!(client.api_key.is_empty());
+
+        let messages = vec![ChatMessage::user().content("Hello").build()];
+        match client.chat(&messages, None, None).await {
+            Err(LLMError::AuthError(msg)) => assert_eq!(msg, "not signed in"),
+            _ => panic!("Expected the token provider's AuthError"),
+        }
+    }
 }
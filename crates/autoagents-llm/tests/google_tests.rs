@@ -56,6 +56,33 @@ mod google_test_cases {
         }
     }
 
+    #[test]
+    fn test_vertex_needs_no_api_key() {
+        struct StaticToken;
+
+        #[async_trait::async_trait]
+        impl autoagents_llm::backends::google::GoogleTokenProvider for StaticToken {
+            async fn token(&self) -> Result<String, LLMError> {
+                Ok("token".to_string())
+            }
+        }
+
+        let client = LLMBuilder::<Google>::new()
+            .model("gemini-2.0-flash")
+            .vertex("my-project", "us-central1")
+            .token_provider(StaticToken)
+            .build()
+            .expect("Failed to build Vertex AI client");
+        assert!(client.api_key.is_empty());
+
+        // A token provider alone does not select Vertex AI
+        let result = LLMBuilder::<Google>::new()
+            .api_key("test-key")
+            .token_provider(StaticToken)
+            .build();
+        assert!(matches!(result, Err(LLMError::InvalidRequest(_))));
+    }
+
     #[test]
     fn test_google_default_values() {
         let client = Google::new("test-key", None, None, None, None, None, None, None);
@@ -268,16 +295,19 @@ mod google_test_cases {
                 role: ChatRole::System,
                 message_type: MessageType::Text,
                 content: "You are a helpful assistant".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: "Hello".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: "Hi there!".to_string(),
+                reasoning: Vec::new(),
             },
         ];
 
@@ -19,7 +19,11 @@ mod azure_openai_tests;
     feature = "deepseek",
     feature = "xai",
     feature = "phind",
-    feature = "groq"
+    feature = "groq",
+    feature = "mistral",
+    feature = "cohere",
+    feature = "llamacpp",
+    feature = "huggingface"
 ))]
 mod other_backends_tests;
 
@@ -322,5 +326,10 @@ mod feature_tests {
         assert!(cfg!(feature = "google"));
         assert!(cfg!(feature = "groq"));
         assert!(cfg!(feature = "azure_openai"));
+        assert!(cfg!(feature = "mistral"));
+        assert!(cfg!(feature = "bedrock"));
+        assert!(cfg!(feature = "cohere"));
+        assert!(cfg!(feature = "llamacpp"));
+        assert!(cfg!(feature = "huggingface"));
     }
 }
@@ -52,6 +52,27 @@ mod ollama_test_cases {
         assert_eq!(client.api_key, Some("test-api-key".to_string()));
     }
 
+    #[test]
+    fn test_ollama_builder_model_options() {
+        let client = LLMBuilder::<Ollama>::new()
+            .model("llama3.1")
+            .num_ctx(8192)
+            .keep_alive("30m")
+            .build()
+            .expect("Failed to build Ollama client with options");
+
+        assert_eq!(client.num_ctx, Some(8192));
+        assert_eq!(client.keep_alive, Some("30m".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_list_models_missing_base_url() {
+        let client = Ollama::new("", None, None, None, None, None, None, None, None);
+
+        let result = client.list_models(None).await;
+        assert!(matches!(result, Err(LLMError::InvalidRequest(_))));
+    }
+
     #[test]
     fn test_ollama_default_values() {
         let client = Ollama::new(
@@ -9,6 +9,7 @@ use autoagents_llm::{
     embedding::EmbeddingProvider,
     error::LLMError,
     models::ModelsProvider,
+    moderation::ModerationProvider,
     FunctionCall, ToolCall,
 };
 use serde_json::json;
@@ -276,6 +277,40 @@ mod openai_test_cases {
         }
     }
 
+    #[tokio::test]
+    async fn test_moderation_auth_error() {
+        let client = OpenAI::new(
+            "", // Empty API key
+            None, None, None, None, None, None, None, None, None, None, None, None, None, None,
+            None, None, None, None, None,
+        );
+
+        let result = client.moderate(vec!["Test text".to_string()]).await;
+
+        match result.err().unwrap() {
+            LLMError::AuthError(msg) => {
+                assert_eq!(msg, "Missing OpenAI API key");
+            }
+            _ => panic!("Expected AuthError"),
+        }
+    }
+
+    #[test]
+    fn test_openai_moderation_model() {
+        let client = create_test_openai();
+        assert_eq!(client.moderation_model, None);
+
+        let client = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .moderation_model("text-moderation-stable")
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.moderation_model.as_deref(),
+            Some("text-moderation-stable")
+        );
+    }
+
     #[tokio::test]
     async fn test_chat_with_tools_auth_error() {
         let client = OpenAI::new(
@@ -428,6 +463,45 @@ mod openai_test_cases {
         }
     }
 
+    #[test]
+    fn test_openai_reasoning_model_rejects_sampling_settings() {
+        let result = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .model("o3-mini")
+            .temperature(0.2)
+            .build();
+        match result.err().unwrap() {
+            LLMError::InvalidRequest(msg) => {
+                assert_eq!(
+                    msg,
+                    "o3-mini is a reasoning model and does not support temperature"
+                )
+            }
+            _ => panic!("Expected InvalidRequest error"),
+        }
+
+        let client = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .model("gpt-5")
+            .reasoning_effort(ReasoningEffort::High)
+            .max_completion_tokens(4096)
+            .build()
+            .expect("Failed to build OpenAI client");
+        assert!(client.is_reasoning_model());
+        assert_eq!(client.max_completion_tokens, Some(4096));
+        assert!(client.max_tokens.is_none());
+    }
+
+    #[test]
+    fn test_openai_rejects_both_token_limits() {
+        let result = LLMBuilder::<OpenAI>::new()
+            .api_key("test-key")
+            .max_tokens(100)
+            .max_completion_tokens(100)
+            .build();
+        assert!(matches!(result, Err(LLMError::InvalidRequest(_))));
+    }
+
     #[test]
     fn test_openai_reasoning_efforts() {
         let efforts = vec![
@@ -534,16 +608,19 @@ mod openai_test_cases {
                 role: ChatRole::System,
                 message_type: MessageType::Text,
                 content: "You are a helpful assistant".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::User,
                 message_type: MessageType::Text,
                 content: "Hello".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage {
                 role: ChatRole::Assistant,
                 message_type: MessageType::Text,
                 content: "Hi there!".to_string(),
+                reasoning: Vec::new(),
             },
         ];
 
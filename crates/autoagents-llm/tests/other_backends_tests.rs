@@ -438,6 +438,29 @@ mod xai_tests {
         assert_eq!(client.xai_search_from_date, Some("2023-06-01".to_string()));
         assert_eq!(client.xai_search_to_date, Some("2023-12-01".to_string()));
     }
+
+    #[test]
+    fn test_xai_builder_search_parameters() {
+        use autoagents_llm::backends::xai::{XaiSearchParameters, XaiSearchSource};
+
+        let client = LLMBuilder::<XAI>::new()
+            .api_key("test-key")
+            .model("grok-3")
+            .search_parameters(XaiSearchParameters {
+                mode: Some("auto".to_string()),
+                sources: Some(vec![XaiSearchSource::web(), XaiSearchSource::news()]),
+                return_citations: Some(true),
+                ..Default::default()
+            })
+            .build()
+            .expect("Failed to build XAI client");
+
+        assert_eq!(client.xai_search_mode, Some("auto".to_string()));
+        assert_eq!(client.xai_search_sources.len(), 2);
+        assert_eq!(client.xai_search_return_citations, Some(true));
+        let parameters = client.search_parameters().unwrap();
+        assert_eq!(parameters.sources.unwrap()[1].source_type, "news");
+    }
 }
 
 #[cfg(feature = "phind")]
@@ -965,3 +988,220 @@ mod groq_tests {
         }
     }
 }
+
+#[cfg(feature = "mistral")]
+mod mistral_tests {
+    use super::*;
+    use autoagents_llm::backends::mistral::Mistral;
+
+    #[test]
+    fn test_mistral_creation() {
+        let client = LLMBuilder::<Mistral>::new()
+            .api_key("test-key")
+            .model("mistral-large-latest")
+            .max_tokens(100)
+            .temperature(0.7)
+            .top_k(40)
+            .system("Test system prompt")
+            .build()
+            .expect("Failed to build Mistral client");
+
+        assert_eq!(client.api_key, "test-key");
+        assert_eq!(client.model, "mistral-large-latest");
+        assert_eq!(client.max_tokens, Some(100));
+        assert_eq!(client.temperature, Some(0.7));
+        assert_eq!(client.system, Some("Test system prompt".to_string()));
+        // Mistral rejects top_k, so it is never sent
+        assert!(client.top_k.is_none());
+        assert_eq!(client.base_url.as_str(), "https://api.mistral.ai/v1/");
+    }
+
+    #[test]
+    fn test_mistral_builder_validation() {
+        let result = LLMBuilder::<Mistral>::new()
+            .model("mistral-small-latest")
+            .build();
+
+        match result.err().unwrap() {
+            LLMError::InvalidRequest(msg) => {
+                assert!(msg.contains("No API key provided"));
+            }
+            _ => panic!("Expected InvalidRequest error"),
+        }
+    }
+
+    #[test]
+    fn test_mistral_default_values() {
+        let client = Mistral::with_config(
+            "test-key", None, None, None, None, None, None, None, None, None, None, None,
+        );
+
+        assert_eq!(client.model, "mistral-small-latest");
+        assert!(client.max_tokens.is_none());
+        assert!(!client.parallel_tool_calls);
+    }
+
+    #[tokio::test]
+    async fn test_mistral_auth_errors() {
+        let client = Mistral::with_config(
+            "", None, None, None, None, None, None, None, None, None, None, None,
+        );
+
+        let messages = vec![ChatMessage::user().content("Hello").build()];
+        match client.chat(&messages, None, None).await.err().unwrap() {
+            LLMError::AuthError(msg) => assert_eq!(msg, "Missing Mistral API key"),
+            _ => panic!("Expected AuthError"),
+        }
+
+        match client.embed(vec!["Hello".to_string()]).await.err().unwrap() {
+            LLMError::AuthError(msg) => assert_eq!(msg, "Missing Mistral API key"),
+            _ => panic!("Expected AuthError"),
+        }
+
+        assert!(matches!(
+            client.list_models(None).await.err().unwrap(),
+            LLMError::AuthError(_)
+        ));
+    }
+}
+
+#[cfg(feature = "cohere")]
+mod cohere_tests {
+    use super::*;
+    use autoagents_llm::backends::cohere::{Cohere, CohereDocument};
+
+    #[test]
+    fn test_cohere_creation() {
+        let client = LLMBuilder::<Cohere>::new()
+            .api_key("test-key")
+            .model("command-a-03-2025")
+            .max_tokens(100)
+            .temperature(0.3)
+            .system("Test system prompt")
+            .documents(vec![CohereDocument::new(
+                "doc-1",
+                "Penguins live in Antarctica.",
+            )])
+            .build()
+            .expect("Failed to build Cohere client");
+
+        assert_eq!(client.api_key, "test-key");
+        assert_eq!(client.model, "command-a-03-2025");
+        assert_eq!(client.max_tokens, Some(100));
+        assert_eq!(client.temperature, Some(0.3));
+        assert_eq!(client.system, Some("Test system prompt".to_string()));
+        assert_eq!(client.base_url.as_str(), "https://api.cohere.com/v2/");
+        assert_eq!(client.documents.len(), 1);
+        assert_eq!(client.documents[0].id, "doc-1");
+    }
+
+    #[test]
+    fn test_cohere_builder_validation() {
+        let result = LLMBuilder::<Cohere>::new().model("command-r-plus").build();
+
+        match result.err().unwrap() {
+            LLMError::InvalidRequest(msg) => {
+                assert!(msg.contains("No API key provided"));
+            }
+            _ => panic!("Expected InvalidRequest error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cohere_auth_errors() {
+        let client = Cohere::new(
+            "", None, None, None, None, None, None, None, None, None, None,
+        );
+
+        let messages = vec![ChatMessage::user().content("Hello").build()];
+        match client.chat(&messages, None, None).await.err().unwrap() {
+            LLMError::AuthError(msg) => assert_eq!(msg, "Missing Cohere API key"),
+            _ => panic!("Expected AuthError"),
+        }
+
+        match client.embed(vec!["Hello".to_string()]).await.err().unwrap() {
+            LLMError::AuthError(msg) => assert_eq!(msg, "Missing Cohere API key"),
+            _ => panic!("Expected AuthError"),
+        }
+    }
+}
+
+#[cfg(feature = "llamacpp")]
+mod llamacpp_tests {
+    use super::*;
+    use autoagents_llm::backends::llamacpp::LlamaCpp;
+
+    #[test]
+    fn test_llamacpp_creation() {
+        let client = LLMBuilder::<LlamaCpp>::new()
+            .base_url("http://localhost:8081")
+            .max_tokens(100)
+            .temperature(0.2)
+            .grammar(r#"root ::= "yes" | "no""#)
+            .slot_id(0)
+            .cache_prompt(true)
+            .build()
+            .expect("Failed to build llama.cpp client");
+
+        assert_eq!(client.base_url.as_str(), "http://localhost:8081/");
+        assert!(client.api_key.is_none());
+        assert_eq!(client.max_tokens, Some(100));
+        assert_eq!(client.temperature, Some(0.2));
+        assert_eq!(client.grammar.as_deref(), Some(r#"root ::= "yes" | "no""#));
+        assert_eq!(client.slot_id, Some(0));
+        assert_eq!(client.cache_prompt, Some(true));
+    }
+
+    #[test]
+    fn test_llamacpp_default_values() {
+        let client = LLMBuilder::<LlamaCpp>::new()
+            .build()
+            .expect("Failed to build llama.cpp client");
+
+        assert_eq!(client.base_url.as_str(), "http://localhost:8080/");
+        assert_eq!(client.model, "default");
+        assert!(client.grammar.is_none());
+        assert!(client.slot_id.is_none());
+    }
+}
+
+#[cfg(feature = "huggingface")]
+mod huggingface_tests {
+    use super::*;
+    use autoagents_llm::backends::huggingface::HuggingFace;
+
+    #[test]
+    fn test_huggingface_creation() {
+        let client = LLMBuilder::<HuggingFace>::new()
+            .base_url("https://example.endpoints.huggingface.cloud")
+            .api_key("hf_test")
+            .max_tokens(100)
+            .stop_sequences(vec!["</answer>".to_string()])
+            .build()
+            .expect("Failed to build Hugging Face client");
+
+        assert_eq!(
+            client.base_url.as_str(),
+            "https://example.endpoints.huggingface.cloud/"
+        );
+        assert_eq!(client.api_key, Some("hf_test".to_string()));
+        assert_eq!(client.model, "tgi");
+        assert_eq!(client.max_tokens, Some(100));
+        assert_eq!(
+            client.generation.stop_sequences,
+            vec!["</answer>".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_huggingface_builder_validation() {
+        let result = LLMBuilder::<HuggingFace>::new().build();
+
+        match result.err().unwrap() {
+            LLMError::InvalidRequest(msg) => {
+                assert!(msg.contains("No base URL provided"));
+            }
+            _ => panic!("Expected InvalidRequest error"),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Type conversions between AutoAgents types and mistral.rs types
 
-use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::chat::{ChatMessage, ChatRole, ContentPart, MessageType};
 use autoagents_llm::{FunctionCall, ToolCall};
 use mistralrs::{TextMessageRole, TextMessages, ToolCallResponse, VisionMessages};
 use std::fmt;
@@ -71,6 +71,7 @@ pub(crate) fn convert_messages(messages: &[ChatMessage]) -> TextMessages {
             MessageType::ImageURL(url) => {
                 format!("[Image URL: {}] {}", url, msg.content)
             }
+            MessageType::Parts(parts) => parts_to_text(&msg.content, parts),
             MessageType::Pdf(_) => {
                 format!("[PDF Document] {}", msg.content)
             }
@@ -110,6 +111,19 @@ pub(crate) fn convert_messages(messages: &[ChatMessage]) -> TextMessages {
     text_messages
 }
 
+/// Text of a multimodal message, with a placeholder for each image
+pub(crate) fn parts_to_text(content: &str, parts: &[ContentPart]) -> String {
+    std::iter::once(content.to_string())
+        .chain(parts.iter().map(|part| match part {
+            ContentPart::Text(text) => text.clone(),
+            ContentPart::Image { .. } => "[Image]".to_string(),
+            ContentPart::ImageUrl { url, .. } => format!("[Image URL: {url}]"),
+        }))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Convert AutoAgents ChatMessages to mistral.rs VisionMessages (supports images)
 ///
 /// Note: This function needs access to the model for proper image message handling
@@ -145,6 +159,29 @@ pub(crate) fn convert_vision_messages(
                     format!("[Image URL not supported yet] {}", msg.content),
                 );
             }
+            MessageType::Parts(parts) => {
+                let text = std::iter::once(msg.content.as_str())
+                    .chain(parts.iter().filter_map(ContentPart::as_text))
+                    .filter(|text| !text.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let images = parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Image { data, .. } => Some(
+                            image::load_from_memory(data)
+                                .map_err(|e| anyhow::anyhow!("Failed to load image: {}", e)),
+                        ),
+                        _ => None,
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                vision_messages = if images.is_empty() {
+                    vision_messages.add_message(role, text)
+                } else {
+                    vision_messages.add_image_message(role, &text, images, model)?
+                };
+            }
             MessageType::Pdf(_) => {
                 vision_messages =
                     vision_messages.add_message(role, format!("[PDF Document] {}", msg.content));
@@ -243,6 +280,7 @@ mod tests {
                 role: ChatRole::System,
                 message_type: MessageType::Text,
                 content: "You are a helpful assistant.".to_string(),
+                reasoning: Vec::new(),
             },
             ChatMessage::user().content("Hello").build(),
         ];
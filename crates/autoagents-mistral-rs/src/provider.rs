@@ -207,6 +207,7 @@ impl MistralRsProvider {
                     role: autoagents_llm::chat::ChatRole::System,
                     message_type: autoagents_llm::chat::MessageType::Text,
                     content: system_prompt.clone(),
+                    reasoning: Vec::new(),
                 });
             }
         }
@@ -228,7 +229,7 @@ impl MistralRsProvider {
                 msg.message_type,
                 autoagents_llm::chat::MessageType::Image(_)
                     | autoagents_llm::chat::MessageType::ImageURL(_)
-            )
+            ) || has_image_parts(msg)
         });
 
         let request_builder = if tools.is_some() || json_schema.is_some() {
@@ -269,8 +270,7 @@ impl MistralRsProvider {
             prompt_tokens: usage.prompt_tokens as u32,
             completion_tokens: usage.completion_tokens as u32,
             total_tokens: usage.total_tokens as u32,
-            completion_tokens_details: None,
-            prompt_tokens_details: None,
+            ..ChatUsage::default()
         }
     }
 
@@ -284,6 +284,7 @@ impl MistralRsProvider {
                         .into_iter()
                         .map(|call| StreamToolCallDelta {
                             index: call.index,
+                            id: Some(call.id),
                             function: Some(StreamToolCallFunction {
                                 name: call.function.name,
                                 arguments: call.function.arguments,
@@ -307,6 +308,8 @@ impl MistralRsProvider {
                     delta: StreamDelta {
                         content,
                         tool_calls,
+                        thinking: None,
+                        reasoning: None,
                     },
                 })
             })
@@ -328,6 +331,8 @@ impl MistralRsProvider {
                 delta: StreamDelta {
                     content: None,
                     tool_calls: None,
+                    thinking: None,
+                    reasoning: None,
                 },
             }],
             usage: Some(Self::convert_usage(done.usage)),
@@ -489,6 +494,16 @@ impl Default for MistralRsProviderBuilder {
     }
 }
 
+/// Whether a multimodal message holds image bytes
+fn has_image_parts(msg: &ChatMessage) -> bool {
+    match &msg.message_type {
+        autoagents_llm::chat::MessageType::Parts(parts) => parts
+            .iter()
+            .any(|part| matches!(part, autoagents_llm::chat::ContentPart::Image { .. })),
+        _ => false,
+    }
+}
+
 /// Convert AutoAgents ChatRole to mistral.rs TextMessageRole for RequestBuilder
 fn convert_role_for_request(role: &autoagents_llm::chat::ChatRole) -> TextMessageRole {
     match role {
@@ -537,6 +552,9 @@ fn build_request_builder(
             autoagents_llm::chat::MessageType::ImageURL(url) => {
                 format!("[Image URL: {}] {}", url, msg.content)
             }
+            autoagents_llm::chat::MessageType::Parts(parts) => {
+                crate::conversion::parts_to_text(&msg.content, parts)
+            }
             autoagents_llm::chat::MessageType::Pdf(_) => {
                 format!("[PDF Document] {}", msg.content)
             }
@@ -622,6 +640,7 @@ impl ChatProvider for MistralRsProvider {
                     role: autoagents_llm::chat::ChatRole::System,
                     message_type: autoagents_llm::chat::MessageType::Text,
                     content: system_prompt.clone(),
+                    reasoning: Vec::new(),
                 });
             }
         }
@@ -635,7 +654,7 @@ impl ChatProvider for MistralRsProvider {
             matches!(
                 msg.message_type,
                 autoagents_llm::chat::MessageType::Image(_)
-            )
+            ) || has_image_parts(msg)
         });
 
         // Send chat request based on model type and message content
@@ -755,7 +774,7 @@ impl CompletionProvider for MistralRsProvider {
             .and_then(|choice| choice.message.content.clone())
             .unwrap_or_default();
 
-        Ok(CompletionResponse { text })
+        Ok(CompletionResponse { text, usage: None })
     }
 }
 
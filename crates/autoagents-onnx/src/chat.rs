@@ -255,6 +255,7 @@ impl OnnxEdge {
                     role: ChatRole::System,
                     message_type: MessageType::Text,
                     content: system.clone(),
+                    reasoning: Vec::new(),
                 });
             }
         }
@@ -545,6 +546,7 @@ impl ChatProvider for OnnxEdge {
                     role: ChatRole::System,
                     message_type: MessageType::Text,
                     content: json_instruction,
+                    reasoning: Vec::new(),
                 },
             );
         }
@@ -586,7 +588,7 @@ impl CompletionProvider for OnnxEdge {
         };
 
         let text = self.generate_text(&req.prompt, generation_config).await?;
-        Ok(CompletionResponse { text })
+        Ok(CompletionResponse { text, usage: None })
     }
 }
 
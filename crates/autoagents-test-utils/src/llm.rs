@@ -1,12 +1,23 @@
 use autoagents::async_trait;
 use autoagents_llm::{
-    chat::{ChatMessage, ChatProvider, ChatResponse, StructuredOutputFormat},
+    audio::{
+        Audio, SpeechRequest, SpeechToTextProvider, TextToSpeechProvider, Transcription,
+        TranscriptionRequest,
+    },
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, StreamChoice, StreamDelta, StreamResponse,
+        StreamToolCallDelta, StreamToolCallFunction, StructuredOutputFormat,
+    },
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
     error::LLMError,
     models::ModelsProvider,
     LLMProvider, ToolCall,
 };
+use futures::Stream;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 // Mock LLM Provider
 pub struct MockLLMProvider;
@@ -21,6 +32,7 @@ impl ChatProvider for MockLLMProvider {
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
         Ok(Box::new(MockChatResponse {
             text: Some("Mock response".to_string()),
+            tool_calls: None,
         }))
     }
 }
@@ -34,6 +46,7 @@ impl CompletionProvider for MockLLMProvider {
     ) -> Result<CompletionResponse, LLMError> {
         Ok(CompletionResponse {
             text: "Mock completion".to_string(),
+            usage: None,
         })
     }
 }
@@ -48,10 +61,173 @@ impl EmbeddingProvider for MockLLMProvider {
 #[async_trait]
 impl ModelsProvider for MockLLMProvider {}
 
-impl LLMProvider for MockLLMProvider {}
+/// Reads the audio bytes back as UTF-8 text.
+#[async_trait]
+impl SpeechToTextProvider for MockLLMProvider {
+    async fn transcribe(&self, req: &TranscriptionRequest) -> Result<Transcription, LLMError> {
+        Ok(Transcription {
+            text: String::from_utf8_lossy(&req.audio.data).into_owned(),
+            language: req.language.clone(),
+            duration: None,
+        })
+    }
+}
+
+/// Returns the text bytes as audio in the requested format.
+#[async_trait]
+impl TextToSpeechProvider for MockLLMProvider {
+    async fn speech(&self, req: &SpeechRequest) -> Result<Audio, LLMError> {
+        Ok(Audio::new(req.text.clone().into_bytes(), req.format))
+    }
+}
+
+impl LLMProvider for MockLLMProvider {
+    fn speech_to_text(&self) -> Option<&dyn SpeechToTextProvider> {
+        Some(self)
+    }
+
+    fn text_to_speech(&self) -> Option<&dyn TextToSpeechProvider> {
+        Some(self)
+    }
+}
+
+/// Mock provider that answers chat calls with `responses` in order,
+/// repeating the last one once the script runs out.
+///
+/// Streaming calls follow the same script, sending each response as a
+/// single chunk.
+pub struct ScriptedLLMProvider {
+    responses: Vec<String>,
+    tool_calls: Vec<ToolCall>,
+    calls: AtomicUsize,
+    last_messages: Mutex<Vec<ChatMessage>>,
+}
+
+impl ScriptedLLMProvider {
+    pub fn new<S: Into<String>>(responses: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            responses: responses.into_iter().map(Into::into).collect(),
+            tool_calls: Vec::new(),
+            calls: AtomicUsize::new(0),
+            last_messages: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Tool calls the model makes alongside its first response
+    pub fn with_tool_calls(mut self, tool_calls: impl IntoIterator<Item = ToolCall>) -> Self {
+        self.tool_calls = tool_calls.into_iter().collect();
+        self
+    }
+
+    /// Number of chat calls made so far
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Messages sent with the most recent chat call
+    pub fn last_messages(&self) -> Vec<ChatMessage> {
+        self.last_messages.lock().unwrap().clone()
+    }
+}
+
+impl ScriptedLLMProvider {
+    /// Text and tool calls of the next response in the script
+    fn next_response(&self, messages: &[ChatMessage]) -> (String, Option<Vec<ToolCall>>) {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        *self.last_messages.lock().unwrap() = messages.to_vec();
+        let text = self
+            .responses
+            .get(call)
+            .or(self.responses.last())
+            .cloned()
+            .unwrap_or_default();
+        let tool_calls =
+            (call == 0 && !self.tool_calls.is_empty()).then(|| self.tool_calls.clone());
+        (text, tool_calls)
+    }
+}
+
+#[async_trait]
+impl ChatProvider for ScriptedLLMProvider {
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[autoagents_llm::chat::Tool]>,
+        _json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let (text, tool_calls) = self.next_response(messages);
+        Ok(Box::new(MockChatResponse {
+            text: Some(text),
+            tool_calls,
+        }))
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[autoagents_llm::chat::Tool]>,
+        _json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        let (text, tool_calls) = self.next_response(messages);
+        let tool_calls = tool_calls.map(|calls| {
+            calls
+                .into_iter()
+                .enumerate()
+                .map(|(index, call)| StreamToolCallDelta {
+                    index,
+                    id: Some(call.id),
+                    function: Some(StreamToolCallFunction {
+                        name: call.function.name,
+                        arguments: call.function.arguments,
+                    }),
+                })
+                .collect()
+        });
+        let chunk = StreamResponse {
+            choices: vec![StreamChoice {
+                delta: StreamDelta {
+                    content: Some(text),
+                    tool_calls,
+                    thinking: None,
+                    reasoning: None,
+                },
+            }],
+            usage: None,
+        };
+        Ok(Box::pin(futures::stream::iter([Ok(chunk)])))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for ScriptedLLMProvider {
+    async fn complete(
+        &self,
+        _req: &CompletionRequest,
+        _json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        Ok(CompletionResponse {
+            text: self.responses.last().cloned().unwrap_or_default(),
+            usage: None,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ScriptedLLMProvider {
+    async fn embed(&self, text: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Ok(text.iter().map(|_| vec![0.1, 0.2, 0.3]).collect())
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for ScriptedLLMProvider {}
+
+impl LLMProvider for ScriptedLLMProvider {}
 
 struct MockChatResponse {
     text: Option<String>,
+    tool_calls: Option<Vec<ToolCall>>,
 }
 
 impl ChatResponse for MockChatResponse {
@@ -60,7 +236,7 @@ impl ChatResponse for MockChatResponse {
     }
 
     fn tool_calls(&self) -> Option<Vec<ToolCall>> {
-        None
+        self.tool_calls.clone()
     }
 }
 
@@ -0,0 +1,43 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc::compile();
+}
+
+/// Generates the `ToolService` client and server from the hand-written
+/// messages in `src/remote/proto.rs`, so building does not need `protoc`.
+/// Keep in sync with `proto/tool_service.proto`.
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    fn method(name: &str, route: &str, input: &str, output: &str) -> Method {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::remote::proto::{input}"))
+            .output_type(format!("crate::remote::proto::{output}"))
+            .codec_path("tonic::codec::ProstCodec")
+            .build()
+    }
+
+    pub fn compile() {
+        println!("cargo:rerun-if-changed=build.rs");
+        let service = Service::builder()
+            .name("ToolService")
+            .package("autoagents.tools.v1")
+            .method(method(
+                "list_tools",
+                "ListTools",
+                "ListToolsRequest",
+                "ListToolsResponse",
+            ))
+            .method(method(
+                "execute",
+                "Execute",
+                "ExecuteRequest",
+                "ExecuteResponse",
+            ))
+            .build();
+        Builder::new().compile(&[service]);
+    }
+}
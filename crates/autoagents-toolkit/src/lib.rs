@@ -4,3 +4,9 @@ pub(crate) mod utils;
 
 #[cfg(all(not(target_arch = "wasm32"), feature = "mcp"))]
 pub mod mcp;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "openapi"))]
+pub mod openapi;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "grpc"))]
+pub mod remote;
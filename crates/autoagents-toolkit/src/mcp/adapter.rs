@@ -1,9 +1,13 @@
 use autoagents::{
     async_trait,
-    core::tool::{ToolCallError, ToolRuntime, ToolT},
+    core::tool::{ToolCallError, ToolRuntime, ToolStream, ToolT},
+    core::utils::intern,
 };
 use rmcp::{
-    model::{CallToolRequestParam, CallToolResult, ClientInfo, Tool as McpTool},
+    model::{
+        CallToolRequestParam, CallToolResult, ClientInfo, ReadResourceRequestParam, Resource,
+        Tool as McpTool,
+    },
     service::{RoleClient, RunningService},
 };
 use serde_json::{json, Value};
@@ -158,6 +162,92 @@ impl McpToolAdapter {
     }
 }
 
+/// Tool that reads the resources of an MCP server by URI.
+///
+/// The description lists the resources the server offered when it was
+/// connected, so the model can pick one without a separate discovery call.
+#[derive(Debug)]
+pub struct McpResourceAdapter {
+    name: &'static str,
+    description: &'static str,
+    service: Arc<RunningService<RoleClient, ClientInfo>>,
+}
+
+impl McpResourceAdapter {
+    /// Create a resource reader for `server` offering `resources`
+    pub fn new(
+        server: &str,
+        resources: &[Resource],
+        service: Arc<RunningService<RoleClient, ClientInfo>>,
+    ) -> Self {
+        let listing = resources
+            .iter()
+            .map(|resource| match &resource.description {
+                Some(description) => {
+                    format!("- {} ({}): {}", resource.uri, resource.name, description)
+                }
+                None => format!("- {} ({})", resource.uri, resource.name),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let description = format!(
+            "Read a resource from the '{}' MCP server by URI. Available resources:\n{}",
+            server, listing
+        );
+
+        Self {
+            name: intern(&format!("{}_read_resource", server)),
+            description: intern(&description),
+            service,
+        }
+    }
+}
+
+impl ToolT for McpResourceAdapter {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "uri": {
+                    "type": "string",
+                    "description": "URI of the resource to read"
+                }
+            },
+            "required": ["uri"]
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for McpResourceAdapter {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let uri = args.get("uri").and_then(Value::as_str).ok_or_else(|| {
+            ToolCallError::RuntimeError("Missing string argument 'uri'".to_string().into())
+        })?;
+
+        let request = ReadResourceRequestParam {
+            uri: uri.to_string(),
+        };
+        match self.service.read_resource(request).await {
+            Ok(result) => Ok(json!({ "contents": result.contents })),
+            Err(e) => {
+                log::error!("MCP resource read failed for {}: {:?}", uri, e);
+                Err(ToolCallError::RuntimeError(
+                    format!("MCP resource read failed: {}", e).into(),
+                ))
+            }
+        }
+    }
+}
+
 /// Wrapper to allow Arc<dyn ToolT> to be used as Box<dyn ToolT>
 /// This is needed for the AgentDeriveT trait which expects Box<dyn ToolT>
 #[derive(Debug)]
@@ -184,6 +274,22 @@ impl ToolT for McpToolWrapper {
     fn args_schema(&self) -> Value {
         self.tool.args_schema()
     }
+
+    fn timeout(&self) -> Option<std::time::Duration> {
+        self.tool.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.tool.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<autoagents::core::tool::ToolRetryPolicy> {
+        self.tool.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.tool.namespace()
+    }
 }
 
 #[async_trait]
@@ -191,4 +297,8 @@ impl ToolRuntime for McpToolWrapper {
     async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
         self.tool.execute(args).await
     }
+
+    fn execute_stream(&self, args: Value) -> Option<ToolStream<'_>> {
+        self.tool.execute_stream(args)
+    }
 }
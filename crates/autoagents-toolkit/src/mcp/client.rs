@@ -1,12 +1,14 @@
 use crate::mcp::{
-    adapter::McpToolAdapter,
+    adapter::{McpResourceAdapter, McpToolAdapter},
     config::{McpConfig, McpServerConfig},
 };
 use autoagents::core::tool::ToolT;
 use rmcp::{
-    model::ClientInfo,
+    model::{ClientInfo, Resource},
     service::{RoleClient, RunningService, ServiceExt},
-    transport::{ConfigureCommandExt, TokioChildProcess},
+    transport::{
+        ConfigureCommandExt, SseClientTransport, StreamableHttpClientTransport, TokioChildProcess,
+    },
 };
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -121,15 +123,15 @@ impl McpToolsManager {
         server_config: &McpServerConfig,
     ) -> Result<McpServerConnection, McpError> {
         // Validate configuration
-        server_config
-            .validate()
-            .map_err(|e| McpError::ConfigError(e))?;
+        server_config.validate().map_err(McpError::ConfigError)?;
 
         let service = match server_config.protocol.as_str() {
             "stdio" => self.connect_stdio_server(server_config).await?,
+            "sse" => self.connect_sse_server(server_config).await?,
+            "http" => self.connect_http_server(server_config).await?,
             _ => {
                 return Err(McpError::ConfigError(format!(
-                    "Unsupported protocol: {}. Currently only 'stdio' is supported.",
+                    "Unsupported protocol: {}",
                     server_config.protocol
                 )))
             }
@@ -178,6 +180,45 @@ impl McpToolsManager {
         Ok(Arc::new(service))
     }
 
+    /// Connect to an MCP server over server-sent events
+    async fn connect_sse_server(
+        &self,
+        config: &McpServerConfig,
+    ) -> Result<Arc<RunningService<RoleClient, ClientInfo>>, McpError> {
+        let url = Self::server_url(config)?;
+        let transport = SseClientTransport::start(url)
+            .await
+            .map_err(|e| McpError::TransportError(e.to_string()))?;
+
+        let service = ClientInfo::default().serve(transport).await.map_err(|e| {
+            McpError::ConnectionFailed(format!("Failed to connect to MCP server: {:?}", e))
+        })?;
+
+        Ok(Arc::new(service))
+    }
+
+    /// Connect to an MCP server over streamable HTTP
+    async fn connect_http_server(
+        &self,
+        config: &McpServerConfig,
+    ) -> Result<Arc<RunningService<RoleClient, ClientInfo>>, McpError> {
+        let url = Self::server_url(config)?;
+        let transport = StreamableHttpClientTransport::from_uri(url);
+
+        let service = ClientInfo::default().serve(transport).await.map_err(|e| {
+            McpError::ConnectionFailed(format!("Failed to connect to MCP server: {:?}", e))
+        })?;
+
+        Ok(Arc::new(service))
+    }
+
+    fn server_url(config: &McpServerConfig) -> Result<String, McpError> {
+        config
+            .url
+            .clone()
+            .ok_or_else(|| McpError::ConfigError(format!("Server '{}' has no url", config.name)))
+    }
+
     /// Load tools from a connected MCP server
     async fn load_server_tools(
         &self,
@@ -196,9 +237,41 @@ impl McpToolsManager {
             adapted_tools.push(Arc::new(adapter) as Arc<dyn ToolT>);
         }
 
+        // Resources are offered through a single tool that reads them by URI
+        let resources = self.load_server_resources(connection).await?;
+        if !resources.is_empty() {
+            let adapter = McpResourceAdapter::new(
+                &connection.name,
+                &resources,
+                Arc::clone(&connection.service),
+            );
+            adapted_tools.push(Arc::new(adapter) as Arc<dyn ToolT>);
+        }
+
         Ok(adapted_tools)
     }
 
+    /// List the resources of a connected MCP server, empty when the server
+    /// does not offer any
+    async fn load_server_resources(
+        &self,
+        connection: &McpServerConnection,
+    ) -> Result<Vec<Resource>, McpError> {
+        let supports_resources = connection
+            .service
+            .peer_info()
+            .is_some_and(|info| info.capabilities.resources.is_some());
+        if !supports_resources {
+            return Ok(Vec::new());
+        }
+
+        connection
+            .service
+            .list_all_resources()
+            .await
+            .map_err(|e| McpError::GenericError(format!("Failed to list resources: {:?}", e)))
+    }
+
     /// Get all available tools
     pub async fn get_tools(&self) -> Vec<Arc<dyn ToolT>> {
         self.tools.read().await.clone()
@@ -217,6 +290,16 @@ impl McpToolsManager {
         self.load_server_tools(connection).await
     }
 
+    /// Get the resources offered by a specific server
+    pub async fn get_server_resources(&self, server_name: &str) -> Result<Vec<Resource>, McpError> {
+        let connections = self.connections.read().await;
+        let connection = connections
+            .get(server_name)
+            .ok_or_else(|| McpError::ServerNotFound(server_name.to_string()))?;
+
+        self.load_server_resources(connection).await
+    }
+
     /// Get a tool by name
     pub async fn get_tool(&self, tool_name: &str) -> Option<Arc<dyn ToolT>> {
         let tools = self.tools.read().await;
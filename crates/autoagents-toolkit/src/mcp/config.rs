@@ -9,8 +9,12 @@ pub struct McpServerConfig {
     pub name: String,
     /// Protocol type (stdio, sse, etc.)
     pub protocol: String,
-    /// Command to execute
+    /// Command to execute, for stdio servers
+    #[serde(default)]
     pub command: String,
+    /// Endpoint of sse and http servers
+    #[serde(default)]
+    pub url: Option<String>,
     /// Arguments for the command
     #[serde(default)]
     pub args: Vec<String>,
@@ -86,6 +90,7 @@ impl McpServerConfig {
             name,
             protocol,
             command,
+            url: None,
             args: Vec::new(),
             env: HashMap::new(),
             cwd: None,
@@ -93,6 +98,17 @@ impl McpServerConfig {
         }
     }
 
+    /// Create a configuration for a server reached over the network
+    pub fn remote(name: String, protocol: String, url: String) -> Self {
+        Self::new(name, protocol, String::new()).with_url(url)
+    }
+
+    /// Set the server endpoint
+    pub fn with_url(mut self, url: String) -> Self {
+        self.url = Some(url);
+        self
+    }
+
     /// Set command arguments
     pub fn with_args(mut self, args: Vec<String>) -> Self {
         self.args = args;
@@ -123,11 +139,12 @@ impl McpServerConfig {
             return Err("Server name cannot be empty".to_string());
         }
 
-        if self.command.is_empty() {
-            return Err("Server command cannot be empty".to_string());
-        }
-
         match self.protocol.as_str() {
+            "stdio" if self.command.is_empty() => Err("Server command cannot be empty".to_string()),
+            "sse" | "http" if self.url.as_deref().is_none_or(str::is_empty) => Err(format!(
+                "Server url is required for the {} protocol",
+                self.protocol
+            )),
             "stdio" | "sse" | "http" => Ok(()),
             _ => Err(format!("Unsupported protocol: {}", self.protocol)),
         }
@@ -203,6 +220,33 @@ mod tests {
         assert!(invalid_protocol.validate().is_err());
     }
 
+    #[test]
+    fn test_remote_server_config_validation() {
+        let sse = McpServerConfig::remote(
+            "remote".to_string(),
+            "sse".to_string(),
+            "http://localhost:8000/sse".to_string(),
+        );
+        assert!(sse.validate().is_ok());
+        assert!(sse.command.is_empty());
+
+        let missing_url =
+            McpServerConfig::new("remote".to_string(), "http".to_string(), "".to_string());
+        assert!(missing_url.validate().is_err());
+
+        let toml_content = r#"
+[mcp]
+[[mcp.server]]
+name = "remote"
+protocol = "http"
+url = "http://localhost:8000/mcp"
+        "#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let server = config.mcp.get_server("remote").unwrap();
+        assert_eq!(server.url.as_deref(), Some("http://localhost:8000/mcp"));
+        assert!(server.validate().is_ok());
+    }
+
     #[test]
     fn test_mcp_config_operations() {
         let mut config = McpConfig::new();
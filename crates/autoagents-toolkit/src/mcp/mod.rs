@@ -1,14 +1,19 @@
 //! MCP (Model Context Protocol) support for AutoAgents
 //!
 //! This module provides integration with MCP servers, allowing AutoAgents
-//! to use tools from external MCP-compatible servers.
+//! to use tools from external MCP-compatible servers. Servers are reached
+//! over stdio, SSE or streamable HTTP, and their resources are exposed as a
+//! tool that reads them by URI. [`McpServer`] works the other way round,
+//! offering AutoAgents tools and agents to MCP clients.
 
 pub mod adapter;
 pub mod client;
 pub mod config;
+pub mod server;
 pub mod tools;
 
-pub use adapter::{McpToolAdapter, McpToolWrapper};
+pub use adapter::{McpResourceAdapter, McpToolAdapter, McpToolWrapper};
 pub use client::{McpError, McpServerConnection, McpToolsManager};
 pub use config::{Config, McpConfig, McpServerConfig};
+pub use server::McpServer;
 pub use tools::McpTools;
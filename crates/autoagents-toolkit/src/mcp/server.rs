@@ -0,0 +1,324 @@
+use crate::mcp::client::McpError;
+use autoagents::core::agent::task::Task;
+use autoagents::core::agent::{AgentEntry, AgentRegistry, RunnableAgent};
+use autoagents::core::tool::ToolT;
+use rmcp::{
+    model::{
+        CallToolRequestParam, CallToolResult, Content, Implementation, JsonObject, ListToolsResult,
+        PaginatedRequestParam, ServerCapabilities, ServerInfo, Tool as McpTool,
+    },
+    service::{RequestContext, RoleServer},
+    transport::{sse_server::SseServer, stdio},
+    ErrorData, ServerHandler, ServiceExt,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// MCP server that offers AutoAgents tools and agents to MCP clients.
+///
+/// Tools are served as they are. Each agent becomes a tool that takes a
+/// `prompt`, runs it as a task and returns the agent's output.
+#[derive(Debug, Clone)]
+pub struct McpServer {
+    name: String,
+    version: String,
+    instructions: Option<String>,
+    tools: Vec<Arc<dyn ToolT>>,
+    agents: Vec<AgentEntry>,
+}
+
+impl McpServer {
+    /// Create a server announcing itself as `name`
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            instructions: None,
+            tools: Vec::new(),
+            agents: Vec::new(),
+        }
+    }
+
+    /// Set the version reported to clients
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Set the usage instructions sent to clients on initialization
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Serve a tool
+    pub fn tool(mut self, tool: Arc<dyn ToolT>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Serve several tools, such as those of an agent definition
+    pub fn tools(mut self, tools: impl IntoIterator<Item = Box<dyn ToolT>>) -> Self {
+        self.tools.extend(tools.into_iter().map(Arc::from));
+        self
+    }
+
+    /// Serve an agent as a tool
+    pub fn agent(mut self, agent: Arc<dyn RunnableAgent>) -> Self {
+        self.agents.push(AgentEntry {
+            name: agent.name().to_string(),
+            description: agent.description().to_string(),
+            capabilities: Vec::new(),
+            agent,
+        });
+        self
+    }
+
+    /// Serve every agent of `registry` under its registered name
+    pub fn registry(mut self, registry: &AgentRegistry) -> Self {
+        self.agents.extend(registry.entries());
+        self
+    }
+
+    /// Names of everything this server offers
+    pub fn tool_names(&self) -> Vec<String> {
+        self.tools
+            .iter()
+            .map(|tool| tool.name().to_string())
+            .chain(self.agents.iter().map(|entry| entry.name.clone()))
+            .collect()
+    }
+
+    /// Serve over stdin and stdout until the client disconnects
+    pub async fn serve_stdio(self) -> Result<(), McpError> {
+        let service = self
+            .serve(stdio())
+            .await
+            .map_err(|e| McpError::TransportError(e.to_string()))?;
+        service
+            .waiting()
+            .await
+            .map_err(|e| McpError::GenericError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Serve over server-sent events on `addr` until Ctrl-C
+    pub async fn serve_sse(self, addr: SocketAddr) -> Result<(), McpError> {
+        let server = SseServer::serve(addr)
+            .await
+            .map_err(|e| McpError::TransportError(e.to_string()))?;
+        let shutdown = server.with_service(move || self.clone());
+        log::info!("MCP server listening on {}", addr);
+
+        tokio::signal::ctrl_c()
+            .await
+            .map_err(|e| McpError::GenericError(e.to_string()))?;
+        shutdown.cancel();
+        Ok(())
+    }
+
+    fn list(&self) -> Vec<McpTool> {
+        let tools = self.tools.iter().map(|tool| {
+            McpTool::new(
+                tool.name(),
+                tool.description(),
+                Arc::new(input_schema(tool.args_schema())),
+            )
+        });
+        let agents = self.agents.iter().map(|entry| {
+            McpTool::new(
+                entry.name.clone(),
+                entry.description.clone(),
+                Arc::new(input_schema(prompt_schema())),
+            )
+        });
+        tools.chain(agents).collect()
+    }
+
+    async fn call(&self, name: &str, args: Value) -> Result<CallToolResult, ErrorData> {
+        let outcome = if let Some(tool) = self.tools.iter().find(|tool| tool.name() == name) {
+            tool.execute(args).await.map_err(|e| e.to_string())
+        } else if let Some(entry) = self.agents.iter().find(|entry| entry.name == name) {
+            let prompt = args.get("prompt").and_then(Value::as_str).ok_or_else(|| {
+                ErrorData::invalid_params("Missing string argument 'prompt'", None)
+            })?;
+            entry
+                .agent
+                .run(Task::new(prompt))
+                .await
+                .map_err(|e| e.to_string())
+        } else {
+            return Err(ErrorData::invalid_params(
+                format!("Unknown tool: {}", name),
+                None,
+            ));
+        };
+
+        Ok(match outcome {
+            Ok(value) => CallToolResult::success(vec![Content::text(to_text(value))]),
+            Err(error) => CallToolResult::error(vec![Content::text(error)]),
+        })
+    }
+}
+
+impl ServerHandler for McpServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: Implementation {
+                name: self.name.clone(),
+                version: self.version.clone(),
+                ..Default::default()
+            },
+            instructions: self.instructions.clone(),
+            ..Default::default()
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        Ok(ListToolsResult {
+            tools: self.list(),
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let args = Value::Object(request.arguments.unwrap_or_default());
+        self.call(&request.name, args).await
+    }
+}
+
+fn prompt_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "prompt": {
+                "type": "string",
+                "description": "Task for the agent"
+            }
+        },
+        "required": ["prompt"]
+    })
+}
+
+/// MCP requires an object schema; tools without one take no arguments
+fn input_schema(schema: Value) -> JsonObject {
+    match schema {
+        Value::Object(map) => map,
+        _ => JsonObject::from_iter([("type".to_string(), json!("object"))]),
+    }
+}
+
+fn to_text(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents::async_trait;
+    use autoagents::core::agent::error::RunnableAgentError;
+    use autoagents::core::tool::{ToolCallError, ToolRuntime};
+
+    #[derive(Debug)]
+    struct Echo;
+
+    impl ToolT for Echo {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn description(&self) -> &'static str {
+            "Echo the input"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object", "properties": {"text": {"type": "string"}}})
+        }
+    }
+
+    #[async_trait]
+    impl ToolRuntime for Echo {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            Ok(args["text"].clone())
+        }
+    }
+
+    struct Shouter;
+
+    #[async_trait]
+    impl RunnableAgent for Shouter {
+        fn name(&self) -> &str {
+            "shouter"
+        }
+
+        fn description(&self) -> &str {
+            "Repeats the task loudly"
+        }
+
+        async fn run(&self, task: Task) -> Result<Value, RunnableAgentError> {
+            Ok(json!({ "response": task.prompt.to_uppercase() }))
+        }
+    }
+
+    fn server() -> McpServer {
+        McpServer::new("test")
+            .tool(Arc::new(Echo))
+            .agent(Arc::new(Shouter))
+    }
+
+    fn text(result: &CallToolResult) -> String {
+        result.content[0].as_text().unwrap().text.clone()
+    }
+
+    #[test]
+    fn test_lists_tools_and_agents() {
+        let server = server();
+        assert_eq!(server.tool_names(), vec!["echo", "shouter"]);
+
+        let tools = server.list();
+        assert_eq!(
+            tools[0].input_schema["properties"]["text"]["type"],
+            "string"
+        );
+        assert_eq!(
+            tools[1].description.as_deref(),
+            Some("Repeats the task loudly")
+        );
+        assert_eq!(tools[1].input_schema["required"], json!(["prompt"]));
+    }
+
+    #[tokio::test]
+    async fn test_calls_tools_and_agents() {
+        let server = server();
+
+        let result = server.call("echo", json!({"text": "hi"})).await.unwrap();
+        assert_eq!(text(&result), "hi");
+
+        let result = server
+            .call("shouter", json!({"prompt": "hi"}))
+            .await
+            .unwrap();
+        assert_eq!(text(&result), r#"{"response":"HI"}"#);
+
+        assert!(server.call("shouter", json!({})).await.is_err());
+        assert!(server.call("missing", json!({})).await.is_err());
+    }
+
+    #[test]
+    fn test_non_object_schema_becomes_empty_object() {
+        assert_eq!(input_schema(Value::Null)["type"], "object");
+    }
+}
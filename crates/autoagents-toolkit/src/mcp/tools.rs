@@ -116,6 +116,22 @@ impl ToolT for McpToolWrapper {
     fn args_schema(&self) -> serde_json::Value {
         self.tool.args_schema()
     }
+
+    fn timeout(&self) -> Option<std::time::Duration> {
+        self.tool.timeout()
+    }
+
+    fn idempotent(&self) -> bool {
+        self.tool.idempotent()
+    }
+
+    fn retry_policy(&self) -> Option<autoagents::core::tool::ToolRetryPolicy> {
+        self.tool.retry_policy()
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.tool.namespace()
+    }
 }
 
 #[autoagents::async_trait]
@@ -126,6 +142,13 @@ impl autoagents::core::tool::ToolRuntime for McpToolWrapper {
     ) -> Result<serde_json::Value, autoagents::core::tool::ToolCallError> {
         self.tool.execute(args).await
     }
+
+    fn execute_stream(
+        &self,
+        args: serde_json::Value,
+    ) -> Option<autoagents::core::tool::ToolStream<'_>> {
+        self.tool.execute_stream(args)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,26 @@
+//! OpenAPI support for AutoAgents
+//!
+//! Turns the operations of an OpenAPI 3 document into agent tools, so an
+//! internal API with many endpoints does not need a hand-written tool for
+//! each. Every operation becomes one tool named after its `operationId`,
+//! taking its parameters and request body as arguments.
+
+pub mod spec;
+pub mod tool;
+
+pub use spec::{Operation, Parameter, ParameterLocation, RequestBody};
+pub use tool::{OpenApiTool, OpenApiTools};
+
+#[derive(Debug, thiserror::Error)]
+pub enum OpenApiError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Parse error: {0}")]
+    Parse(String),
+    #[error("Unsupported document: {0}")]
+    Unsupported(String),
+    #[error("Invalid specification: {0}")]
+    InvalidSpec(String),
+    #[error("No absolute server URL in the document; set one with `base_url`")]
+    MissingBaseUrl,
+}
@@ -0,0 +1,446 @@
+use super::OpenApiError;
+use reqwest::Method;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How deep `$ref`s are followed; deeper (usually recursive) schemas are left
+/// open rather than expanded forever
+const MAX_REF_DEPTH: usize = 8;
+
+/// Tool names longer than this are rejected by most providers
+const MAX_NAME_LEN: usize = 64;
+
+/// Where an operation parameter goes in the request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterLocation {
+    Path,
+    Query,
+    Header,
+    Cookie,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub location: ParameterLocation,
+    pub required: bool,
+    pub description: Option<String>,
+    /// JSON schema of the value, with `$ref`s resolved
+    pub schema: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestBody {
+    pub content_type: String,
+    pub required: bool,
+    pub description: Option<String>,
+    /// JSON schema of the body, with `$ref`s resolved
+    pub schema: Value,
+}
+
+/// One operation of an OpenAPI document, the unit that becomes a tool
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    /// Tool name: the `operationId`, or the method and path without one
+    pub name: String,
+    pub method: Method,
+    /// Path template, such as `/users/{id}`
+    pub path: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub parameters: Vec<Parameter>,
+    pub body: Option<RequestBody>,
+}
+
+impl Operation {
+    /// Arguments schema of the tool: one property per parameter, plus `body`
+    /// when the operation takes a request body
+    pub fn args_schema(&self) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for parameter in &self.parameters {
+            let mut schema = parameter.schema.clone();
+            if let (Some(description), Some(object)) =
+                (&parameter.description, schema.as_object_mut())
+            {
+                object
+                    .entry("description")
+                    .or_insert_with(|| Value::String(description.clone()));
+            }
+            properties.insert(parameter.name.clone(), schema);
+            if parameter.required {
+                required.push(parameter.name.clone());
+            }
+        }
+        if let Some(body) = &self.body {
+            let mut schema = body.schema.clone();
+            if let (Some(description), Some(object)) = (&body.description, schema.as_object_mut()) {
+                object
+                    .entry("description")
+                    .or_insert_with(|| Value::String(description.clone()));
+            }
+            properties.insert("body".to_string(), schema);
+            if body.required {
+                required.push("body".to_string());
+            }
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+}
+
+/// Parse an OpenAPI document written as JSON or YAML
+pub(crate) fn parse_document(text: &str) -> Result<Value, OpenApiError> {
+    let document: Value = match serde_json::from_str(text) {
+        Ok(document) => document,
+        Err(_) => serde_yaml::from_str(text).map_err(|e| OpenApiError::Parse(e.to_string()))?,
+    };
+    match document.get("openapi").and_then(Value::as_str) {
+        Some(version) if version.starts_with("3.") => Ok(document),
+        Some(version) => Err(OpenApiError::Unsupported(format!(
+            "OpenAPI version {version}, only 3.x is supported"
+        ))),
+        None => Err(OpenApiError::Unsupported(
+            "document has no 'openapi' version; Swagger 2.0 is not supported".to_string(),
+        )),
+    }
+}
+
+pub(crate) fn read_document(path: &Path) -> Result<Value, OpenApiError> {
+    parse_document(&std::fs::read_to_string(path)?)
+}
+
+/// URL of the first server, with its variables set to their defaults
+pub(crate) fn server_url(document: &Value) -> Option<String> {
+    let server = document.get("servers")?.as_array()?.first()?;
+    let mut url = server.get("url")?.as_str()?.to_string();
+    if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+        for (name, variable) in variables {
+            if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                url = url.replace(&format!("{{{name}}}"), default);
+            }
+        }
+    }
+    Some(url)
+}
+
+/// Every operation in the document, in document order
+pub(crate) fn operations(document: &Value) -> Result<Vec<Operation>, OpenApiError> {
+    let paths = document
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| OpenApiError::InvalidSpec("document has no 'paths'".to_string()))?;
+
+    let mut operations = Vec::new();
+    for (path, item) in paths {
+        let item = resolve(item, document, 0);
+        let shared = parameters(item.get("parameters"), document)?;
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            operations.push(operation_from(
+                path,
+                method,
+                &resolve(operation, document, 0),
+                &shared,
+                document,
+            )?);
+        }
+    }
+    Ok(operations)
+}
+
+fn operation_from(
+    path: &str,
+    method: &str,
+    operation: &Value,
+    shared: &[Parameter],
+    document: &Value,
+) -> Result<Operation, OpenApiError> {
+    let name = match operation.get("operationId").and_then(Value::as_str) {
+        Some(id) => sanitize_name(id),
+        None => sanitize_name(&format!("{method}_{path}")),
+    };
+    let text = |key: &str| {
+        operation
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+    };
+    let description = match (text("summary"), text("description")) {
+        (Some(summary), Some(description)) if summary != description => {
+            format!("{summary}\n\n{description}")
+        }
+        (Some(text), _) | (None, Some(text)) => text.to_string(),
+        (None, None) => format!("{} {path}", method.to_uppercase()),
+    };
+
+    // Operation parameters override path-level ones with the same name and location
+    let own = parameters(operation.get("parameters"), document)?;
+    let mut parameters: Vec<Parameter> = shared
+        .iter()
+        .filter(|p| {
+            !own.iter()
+                .any(|o| o.name == p.name && o.location == p.location)
+        })
+        .cloned()
+        .collect();
+    parameters.extend(own);
+
+    Ok(Operation {
+        name,
+        method: Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|e| OpenApiError::InvalidSpec(e.to_string()))?,
+        path: path.to_string(),
+        description,
+        tags: operation
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        parameters,
+        body: operation
+            .get("requestBody")
+            .map(|body| request_body(&resolve(body, document, 0)))
+            .transpose()?,
+    })
+}
+
+fn parameters(list: Option<&Value>, document: &Value) -> Result<Vec<Parameter>, OpenApiError> {
+    let Some(list) = list.and_then(Value::as_array) else {
+        return Ok(Vec::new());
+    };
+    list.iter()
+        .map(|parameter| {
+            let parameter = resolve(parameter, document, 0);
+            let name = parameter
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| OpenApiError::InvalidSpec("parameter without a name".to_string()))?;
+            let location = match parameter.get("in").and_then(Value::as_str) {
+                Some("path") => ParameterLocation::Path,
+                Some("query") => ParameterLocation::Query,
+                Some("header") => ParameterLocation::Header,
+                Some("cookie") => ParameterLocation::Cookie,
+                other => {
+                    return Err(OpenApiError::InvalidSpec(format!(
+                        "parameter '{name}' has unknown location {other:?}"
+                    )))
+                }
+            };
+            Ok(Parameter {
+                name: name.to_string(),
+                location,
+                // Path parameters are always required
+                required: location == ParameterLocation::Path
+                    || parameter
+                        .get("required")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                description: parameter
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                schema: parameter
+                    .get("schema")
+                    .cloned()
+                    .unwrap_or_else(|| json!({"type": "string"})),
+            })
+        })
+        .collect()
+}
+
+fn request_body(body: &Value) -> Result<RequestBody, OpenApiError> {
+    let content = body
+        .get("content")
+        .and_then(Value::as_object)
+        .ok_or_else(|| OpenApiError::InvalidSpec("request body without content".to_string()))?;
+    // Prefer JSON, then forms, then whatever the document lists first
+    let (content_type, media) = content
+        .iter()
+        .find(|(content_type, _)| content_type.contains("json"))
+        .or_else(|| {
+            content
+                .iter()
+                .find(|(content_type, _)| *content_type == "application/x-www-form-urlencoded")
+        })
+        .or_else(|| content.iter().next())
+        .ok_or_else(|| OpenApiError::InvalidSpec("request body without content".to_string()))?;
+    Ok(RequestBody {
+        content_type: content_type.clone(),
+        required: body
+            .get("required")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        description: body
+            .get("description")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        schema: media.get("schema").cloned().unwrap_or_else(|| json!({})),
+    })
+}
+
+/// Copy of `value` with every local `$ref` replaced by what it points to
+fn resolve(value: &Value, document: &Value, depth: usize) -> Value {
+    match value {
+        Value::Object(object) => {
+            if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+                if depth >= MAX_REF_DEPTH {
+                    return json!({});
+                }
+                return match reference
+                    .strip_prefix('#')
+                    .and_then(|pointer| document.pointer(pointer))
+                {
+                    Some(target) => resolve(target, document, depth + 1),
+                    None => {
+                        log::warn!("Unresolved OpenAPI reference '{reference}'");
+                        json!({})
+                    }
+                };
+            }
+            Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), resolve(value, document, depth)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| resolve(item, document, depth))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Reduce `name` to the characters tool names allow
+fn sanitize_name(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.chars() {
+        let c = if c.is_ascii_alphanumeric() || c == '-' {
+            c
+        } else {
+            '_'
+        };
+        // Collapse runs of replaced characters, e.g. from `/{id}/`
+        if c != '_' || !sanitized.ends_with('_') {
+            sanitized.push(c);
+        }
+    }
+    let sanitized = sanitized.trim_matches('_');
+    sanitized.chars().take(MAX_NAME_LEN).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info:
+  title: Users
+  version: "1"
+servers:
+  - url: https://{region}.example.com/v1
+    variables:
+      region:
+        default: eu
+paths:
+  /users/{id}:
+    parameters:
+      - $ref: "#/components/parameters/UserId"
+    get:
+      operationId: getUser
+      summary: Fetch a user
+      parameters:
+        - name: fields
+          in: query
+          schema:
+            type: array
+            items: {type: string}
+    patch:
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/User"
+components:
+  parameters:
+    UserId:
+      name: id
+      in: path
+      description: User id
+      schema: {type: integer}
+  schemas:
+    User:
+      type: object
+      properties:
+        name: {type: string}
+        manager:
+          $ref: "#/components/schemas/User"
+"##;
+
+    #[test]
+    fn test_operations_are_extracted_with_refs_resolved() {
+        let document = parse_document(SPEC).unwrap();
+        assert_eq!(
+            server_url(&document).as_deref(),
+            Some("https://eu.example.com/v1")
+        );
+
+        let operations = operations(&document).unwrap();
+        assert_eq!(operations.len(), 2);
+
+        let get = &operations[0];
+        assert_eq!(get.name, "getUser");
+        assert_eq!(get.method, Method::GET);
+        assert_eq!(get.description, "Fetch a user");
+        assert_eq!(
+            get.args_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer", "description": "User id"},
+                    "fields": {"type": "array", "items": {"type": "string"}},
+                },
+                "required": ["id"],
+            })
+        );
+
+        let patch = &operations[1];
+        assert_eq!(patch.name, "patch_users_id");
+        assert_eq!(patch.description, "PATCH /users/{id}");
+        let schema = patch.args_schema();
+        assert_eq!(schema["required"], json!(["id", "body"]));
+        assert_eq!(
+            schema["properties"]["body"]["properties"]["name"],
+            json!({"type": "string"})
+        );
+        // The recursive reference is expanded a bounded number of times
+        assert!(schema["properties"]["body"]["properties"]["manager"].is_object());
+    }
+
+    #[test]
+    fn test_swagger_2_is_rejected() {
+        let error = parse_document(r#"{"swagger": "2.0", "paths": {}}"#).unwrap_err();
+        assert!(matches!(error, OpenApiError::Unsupported(_)));
+    }
+}
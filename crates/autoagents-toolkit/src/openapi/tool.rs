@@ -0,0 +1,508 @@
+use super::spec::{self, Operation, ParameterLocation};
+use super::OpenApiError;
+use autoagents::core::{
+    ractor::async_trait,
+    tool::{ToolCallError, ToolRuntime, ToolT},
+    utils::intern,
+};
+use log::debug;
+use reqwest::{Client, Method, Request, Url};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Builds one tool per operation of an OpenAPI 3 document.
+///
+/// Requests go to the document's first server unless [`Self::base_url`] says
+/// otherwise. Injected headers, such as API keys, are added to every request
+/// without being shown to the model.
+///
+/// ```no_run
+/// # use autoagents_toolkit::openapi::OpenApiTools;
+/// let tools = OpenApiTools::from_file("billing.yaml")?
+///     .base_url("https://billing.internal/api")
+///     .header("Authorization", "Bearer token")
+///     .tag("invoices")
+///     .tools()?;
+/// # Ok::<(), autoagents_toolkit::openapi::OpenApiError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenApiTools {
+    document: Value,
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    operations: Vec<String>,
+    tags: Vec<String>,
+    timeout: Duration,
+    max_response_bytes: usize,
+}
+
+impl OpenApiTools {
+    /// Read the JSON or YAML document at `path`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, OpenApiError> {
+        Ok(Self::with_document(spec::read_document(path.as_ref())?))
+    }
+
+    /// Parse a JSON or YAML document
+    pub fn parse(document: &str) -> Result<Self, OpenApiError> {
+        Ok(Self::with_document(spec::parse_document(document)?))
+    }
+
+    fn with_document(document: Value) -> Self {
+        Self {
+            document,
+            base_url: None,
+            headers: Vec::new(),
+            operations: Vec::new(),
+            tags: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        }
+    }
+
+    /// Send requests to `url` instead of the document's first server
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Add a header to every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only build tools for the named operations. Names are the tool names,
+    /// which is the `operationId` for operations that have one.
+    pub fn operations(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.operations.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only build tools for operations tagged `tag`; repeat to allow several
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Give up on requests after `timeout`, 30 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Truncate response bodies after `bytes`, 1 MiB by default
+    pub fn max_response_bytes(mut self, bytes: usize) -> Self {
+        self.max_response_bytes = bytes;
+        self
+    }
+
+    /// Every operation of the document that passes the filters
+    pub fn list_operations(&self) -> Result<Vec<Operation>, OpenApiError> {
+        Ok(spec::operations(&self.document)?
+            .into_iter()
+            .filter(|operation| {
+                self.operations.is_empty() || self.operations.contains(&operation.name)
+            })
+            .filter(|operation| {
+                self.tags.is_empty() || operation.tags.iter().any(|tag| self.tags.contains(tag))
+            })
+            .collect())
+    }
+
+    pub fn tools(&self) -> Result<Vec<Box<dyn ToolT>>, OpenApiError> {
+        let base_url = self
+            .base_url
+            .clone()
+            .or_else(|| spec::server_url(&self.document))
+            .ok_or(OpenApiError::MissingBaseUrl)?;
+        // Relative server URLs only make sense next to the document
+        Url::parse(&base_url).map_err(|_| OpenApiError::MissingBaseUrl)?;
+
+        // Redirects are not followed so injected headers never leave the API
+        let client = Client::builder()
+            .timeout(self.timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| OpenApiError::InvalidSpec(e.to_string()))?;
+        let endpoint = Arc::new(Endpoint {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            headers: self.headers.clone(),
+            max_response_bytes: self.max_response_bytes,
+        });
+
+        Ok(self
+            .list_operations()?
+            .into_iter()
+            .map(|operation| {
+                Box::new(OpenApiTool::new(operation, Arc::clone(&endpoint))) as Box<dyn ToolT>
+            })
+            .collect())
+    }
+}
+
+/// Where and how the tools of one document send their requests
+#[derive(Debug)]
+struct Endpoint {
+    client: Client,
+    base_url: String,
+    headers: Vec<(String, String)>,
+    max_response_bytes: usize,
+}
+
+/// Calls a single operation of an OpenAPI document, built by [`OpenApiTools`].
+///
+/// The model passes every parameter by name and the request body as `body`;
+/// the result has the response `status`, `content_type` and `body`.
+#[derive(Debug, Clone)]
+pub struct OpenApiTool {
+    operation: Operation,
+    name: &'static str,
+    description: &'static str,
+    endpoint: Arc<Endpoint>,
+}
+
+impl OpenApiTool {
+    fn new(operation: Operation, endpoint: Arc<Endpoint>) -> Self {
+        Self {
+            name: intern(&operation.name),
+            description: intern(&operation.description),
+            operation,
+            endpoint,
+        }
+    }
+
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    fn build_request(&self, args: &Value) -> Result<Request, ToolCallError> {
+        let mut path = self.operation.path.clone();
+        let mut query = Vec::new();
+        let mut headers = Vec::new();
+        let mut cookies = Vec::new();
+        for parameter in &self.operation.parameters {
+            let values: Vec<String> = match args.get(&parameter.name) {
+                None | Some(Value::Null) if parameter.required => {
+                    return Err(runtime_error(format!(
+                        "Missing required argument '{}'",
+                        parameter.name
+                    )))
+                }
+                None | Some(Value::Null) => continue,
+                Some(Value::Array(items)) => items.iter().map(text).collect(),
+                Some(value) => vec![text(value)],
+            };
+            match parameter.location {
+                ParameterLocation::Path => {
+                    let segment = values
+                        .iter()
+                        .map(|value| encode_segment(value))
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    path = path.replace(&format!("{{{}}}", parameter.name), &segment);
+                }
+                // Arrays are sent as repeated keys, OpenAPI's default `form` style
+                ParameterLocation::Query => query.extend(
+                    values
+                        .into_iter()
+                        .map(|value| (parameter.name.clone(), value)),
+                ),
+                ParameterLocation::Header => {
+                    headers.push((parameter.name.clone(), values.join(",")))
+                }
+                ParameterLocation::Cookie => {
+                    cookies.push(format!("{}={}", parameter.name, values.join(",")))
+                }
+            }
+        }
+
+        let url = Url::parse(&format!("{}{path}", self.endpoint.base_url))
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let mut request = self
+            .endpoint
+            .client
+            .request(self.operation.method.clone(), url);
+        if !query.is_empty() {
+            request = request.query(&query);
+        }
+        for (name, value) in headers.iter().chain(&self.endpoint.headers) {
+            request = request.header(name, value);
+        }
+        if !cookies.is_empty() {
+            request = request.header(reqwest::header::COOKIE, cookies.join("; "));
+        }
+
+        match (&self.operation.body, args.get("body")) {
+            (Some(body), None | Some(Value::Null)) if body.required => {
+                return Err(runtime_error(
+                    "Missing required argument 'body'".to_string(),
+                ))
+            }
+            (Some(body), Some(value)) if !value.is_null() => {
+                request = if body.content_type.contains("json") {
+                    request
+                        .header(reqwest::header::CONTENT_TYPE, &body.content_type)
+                        .body(value.to_string())
+                } else if body.content_type == "application/x-www-form-urlencoded" {
+                    let fields: Vec<(&String, String)> = value
+                        .as_object()
+                        .ok_or_else(|| runtime_error("Form bodies must be objects".to_string()))?
+                        .iter()
+                        .map(|(key, value)| (key, text(value)))
+                        .collect();
+                    request.form(&fields)
+                } else {
+                    request
+                        .header(reqwest::header::CONTENT_TYPE, &body.content_type)
+                        .body(text(value))
+                };
+            }
+            _ => {}
+        }
+
+        request
+            .build()
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))
+    }
+
+    async fn read_body(
+        &self,
+        mut response: reqwest::Response,
+    ) -> Result<(Vec<u8>, bool), ToolCallError> {
+        let limit = self.endpoint.max_response_bytes;
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?
+        {
+            let room = limit - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                return Ok((body, true));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((body, false))
+    }
+}
+
+fn runtime_error(message: String) -> ToolCallError {
+    ToolCallError::RuntimeError(message.into())
+}
+
+/// Parameter value as sent on the wire, strings without their quotes
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn encode_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            other => format!("%{other:02X}"),
+        })
+        .collect()
+}
+
+impl ToolT for OpenApiTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn args_schema(&self) -> Value {
+        self.operation.args_schema()
+    }
+
+    fn idempotent(&self) -> bool {
+        matches!(
+            self.operation.method,
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+        )
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for OpenApiTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let request = self.build_request(&args)?;
+        debug!(
+            "OpenAPI Tool {} Executing: {} {}",
+            self.name,
+            request.method(),
+            request.url()
+        );
+
+        let response = self
+            .endpoint
+            .client
+            .execute(request)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let (bytes, truncated) = self.read_body(response).await?;
+
+        let body = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) if !truncated => value,
+            _ => Value::String(String::from_utf8_lossy(&bytes).into_owned()),
+        };
+
+        Ok(json!({
+            "status": status,
+            "content_type": content_type,
+            "body": body,
+            "truncated": truncated,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"{
+        "openapi": "3.1.0",
+        "info": {"title": "Orders", "version": "1"},
+        "servers": [{"url": "https://shop.example.com/api/"}],
+        "paths": {
+            "/orders/{orderId}": {
+                "get": {
+                    "operationId": "getOrder",
+                    "tags": ["orders"],
+                    "parameters": [
+                        {"name": "orderId", "in": "path", "schema": {"type": "string"}},
+                        {"name": "expand", "in": "query", "schema": {"type": "array"}},
+                        {"name": "X-Tenant", "in": "header", "required": true, "schema": {"type": "string"}}
+                    ]
+                }
+            },
+            "/orders": {
+                "post": {
+                    "operationId": "createOrder",
+                    "tags": ["orders"],
+                    "requestBody": {
+                        "required": true,
+                        "content": {"application/json": {"schema": {"type": "object"}}}
+                    }
+                }
+            },
+            "/health": {
+                "get": {"operationId": "health", "tags": ["ops"]}
+            }
+        }
+    }"#;
+
+    fn tools() -> Vec<Box<dyn ToolT>> {
+        OpenApiTools::parse(SPEC)
+            .unwrap()
+            .header("Authorization", "Bearer secret")
+            .tag("orders")
+            .tools()
+            .unwrap()
+    }
+
+    fn tool(name: &str) -> OpenApiTool {
+        let tools = OpenApiTools::parse(SPEC)
+            .unwrap()
+            .header("Authorization", "Bearer secret");
+        let endpoint = Arc::new(Endpoint {
+            client: Client::new(),
+            base_url: "https://shop.example.com/api".to_string(),
+            headers: tools.headers.clone(),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        });
+        let operation = tools
+            .list_operations()
+            .unwrap()
+            .into_iter()
+            .find(|operation| operation.name == name)
+            .unwrap();
+        OpenApiTool::new(operation, endpoint)
+    }
+
+    #[test]
+    fn test_tools_are_filtered_by_tag() {
+        let tools = tools();
+        let mut names: Vec<&str> = tools.iter().map(|tool| tool.name()).collect();
+        // Path order depends on whether serde_json preserves key order
+        names.sort_unstable();
+        assert_eq!(names, vec!["createOrder", "getOrder"]);
+    }
+
+    #[test]
+    fn test_parameters_are_placed_in_the_request() {
+        let request = tool("getOrder")
+            .build_request(&json!({
+                "orderId": "a/b 1",
+                "expand": ["items", "customer"],
+                "X-Tenant": "acme",
+            }))
+            .unwrap();
+
+        assert_eq!(request.method(), Method::GET);
+        assert_eq!(
+            request.url().as_str(),
+            "https://shop.example.com/api/orders/a%2Fb%201?expand=items&expand=customer"
+        );
+        assert_eq!(request.headers()["X-Tenant"], "acme");
+        assert_eq!(request.headers()["Authorization"], "Bearer secret");
+    }
+
+    #[test]
+    fn test_missing_required_arguments_are_refused() {
+        let error = tool("getOrder")
+            .build_request(&json!({"orderId": "1"}))
+            .unwrap_err();
+        assert!(error.to_string().contains("X-Tenant"));
+
+        let error = tool("createOrder").build_request(&json!({})).unwrap_err();
+        assert!(error.to_string().contains("body"));
+    }
+
+    #[test]
+    fn test_body_is_sent_as_json() {
+        let create = tool("createOrder");
+        assert!(!create.idempotent());
+        let request = create
+            .build_request(&json!({"body": {"sku": "X1", "qty": 2}}))
+            .unwrap();
+
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+        let body: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(body, json!({"sku": "X1", "qty": 2}));
+        assert_eq!(
+            request.headers()[reqwest::header::CONTENT_TYPE],
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn test_relative_servers_need_a_base_url() {
+        let spec = SPEC.replace("https://shop.example.com/api/", "/api");
+        let tools = OpenApiTools::parse(&spec).unwrap();
+        assert!(matches!(tools.tools(), Err(OpenApiError::MissingBaseUrl)));
+        assert!(tools.base_url("http://localhost:8080/api").tools().is_ok());
+    }
+}
@@ -0,0 +1,140 @@
+use super::proto::{
+    tool_service_client::ToolServiceClient, ExecuteRequest, ListToolsRequest, Outcome,
+    ToolDescriptor,
+};
+use super::RemoteToolError;
+use autoagents::core::{
+    ractor::async_trait,
+    tool::{ToolCallError, ToolRuntime, ToolT},
+    utils::intern,
+};
+use serde_json::Value;
+use std::time::Duration;
+use tonic::transport::{Channel, Endpoint};
+
+/// Connection to a worker serving tools over gRPC
+#[derive(Debug, Clone)]
+pub struct RemoteTools {
+    client: ToolServiceClient<Channel>,
+}
+
+impl RemoteTools {
+    /// Connect to the worker at `url`, such as `http://127.0.0.1:50051`
+    pub async fn connect(url: impl Into<String>) -> Result<Self, RemoteToolError> {
+        let channel = Endpoint::from_shared(url.into())?.connect().await?;
+        Ok(Self::with_channel(channel))
+    }
+
+    /// Use an already configured channel, for instance one with TLS
+    pub fn with_channel(channel: Channel) -> Self {
+        Self {
+            client: ToolServiceClient::new(channel),
+        }
+    }
+
+    /// Every tool the worker serves
+    pub async fn tools(&self) -> Result<Vec<Box<dyn ToolT>>, RemoteToolError> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .map(|descriptor| Box::new(self.remote_tool(descriptor)) as Box<dyn ToolT>)
+            .collect())
+    }
+
+    /// The served tool called `name`
+    pub async fn tool(&self, name: &str) -> Result<RemoteTool, RemoteToolError> {
+        self.list()
+            .await?
+            .into_iter()
+            .find(|descriptor| descriptor.name == name)
+            .map(|descriptor| self.remote_tool(descriptor))
+            .ok_or_else(|| RemoteToolError::ToolNotFound(name.to_string()))
+    }
+
+    async fn list(&self) -> Result<Vec<ToolDescriptor>, RemoteToolError> {
+        let response = self.client.clone().list_tools(ListToolsRequest {}).await?;
+        Ok(response.into_inner().tools)
+    }
+
+    fn remote_tool(&self, descriptor: ToolDescriptor) -> RemoteTool {
+        let args_schema = serde_json::from_str(&descriptor.args_schema_json).unwrap_or_else(|e| {
+            log::warn!("Invalid schema for remote tool '{}': {e}", descriptor.name);
+            Value::Null
+        });
+        RemoteTool {
+            client: self.client.clone(),
+            name: intern(&descriptor.name),
+            description: intern(&descriptor.description),
+            namespace: descriptor.namespace.as_deref().map(intern),
+            args_schema,
+            timeout: descriptor.timeout_ms.map(Duration::from_millis),
+            idempotent: descriptor.idempotent,
+        }
+    }
+}
+
+/// Tool that runs on a remote worker, obtained from [`RemoteTools`].
+///
+/// Calls are sent to the worker and its result or error is returned as if
+/// the tool ran locally. Progress reports and streamed output of the remote
+/// tool are not forwarded.
+#[derive(Debug, Clone)]
+pub struct RemoteTool {
+    client: ToolServiceClient<Channel>,
+    name: &'static str,
+    description: &'static str,
+    namespace: Option<&'static str>,
+    args_schema: Value,
+    timeout: Option<Duration>,
+    idempotent: bool,
+}
+
+#[async_trait]
+impl ToolRuntime for RemoteTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let request = ExecuteRequest {
+            tool: self.name.to_string(),
+            args_json: args.to_string(),
+        };
+        let response = self
+            .client
+            .clone()
+            .execute(request)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(RemoteToolError::from(e))))?;
+        match response.into_inner().outcome {
+            Some(Outcome::ResultJson(json)) => Ok(serde_json::from_str(&json)?),
+            Some(Outcome::Error(error)) => Err(ToolCallError::RuntimeError(error.into())),
+            None => Err(ToolCallError::RuntimeError(
+                format!("Remote tool '{}' returned no outcome", self.name).into(),
+            )),
+        }
+    }
+}
+
+impl ToolT for RemoteTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn args_schema(&self) -> Value {
+        self.args_schema.clone()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn idempotent(&self) -> bool {
+        self.idempotent
+    }
+
+    fn namespace(&self) -> Option<&'static str> {
+        self.namespace
+    }
+}
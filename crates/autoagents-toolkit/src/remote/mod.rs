@@ -0,0 +1,26 @@
+//! Remote tool execution over gRPC
+//!
+//! Heavy tools, such as browsers or compilers, can run on separate workers
+//! while the agent process stays light. A worker serves its tools with a
+//! [`ToolServer`]; the agent side connects with [`RemoteTools`] and gets a
+//! [`RemoteTool`] per served tool, used like any local tool. The wire
+//! format is described in `proto/tool_service.proto`.
+
+pub mod client;
+pub mod proto;
+pub mod server;
+
+pub use client::{RemoteTool, RemoteTools};
+pub use server::ToolServer;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteToolError {
+    #[error("Transport error: {0}")]
+    Transport(#[from] tonic::transport::Error),
+    #[error("Remote call failed: {0}")]
+    Status(#[from] tonic::Status),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Tool not found: {0}")]
+    ToolNotFound(String),
+}
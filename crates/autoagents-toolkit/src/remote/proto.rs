@@ -0,0 +1,56 @@
+//! Messages of the `autoagents.tools.v1.ToolService` gRPC service, matching
+//! `proto/tool_service.proto`.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListToolsRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ToolDescriptor {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub description: String,
+    /// JSON schema of the arguments
+    #[prost(string, tag = "3")]
+    pub args_schema_json: String,
+    #[prost(uint64, optional, tag = "4")]
+    pub timeout_ms: Option<u64>,
+    #[prost(bool, tag = "5")]
+    pub idempotent: bool,
+    #[prost(string, optional, tag = "6")]
+    pub namespace: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListToolsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub tools: Vec<ToolDescriptor>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExecuteRequest {
+    #[prost(string, tag = "1")]
+    pub tool: String,
+    #[prost(string, tag = "2")]
+    pub args_json: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExecuteResponse {
+    #[prost(oneof = "Outcome", tags = "1, 2")]
+    pub outcome: Option<Outcome>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Outcome {
+    #[prost(string, tag = "1")]
+    ResultJson(String),
+    /// The tool ran and failed; transport problems are gRPC statuses instead
+    #[prost(string, tag = "2")]
+    Error(String),
+}
+
+include!(concat!(
+    env!("OUT_DIR"),
+    "/autoagents.tools.v1.ToolService.rs"
+));
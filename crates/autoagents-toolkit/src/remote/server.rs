@@ -0,0 +1,215 @@
+use super::proto::{
+    tool_service_server::{ToolService, ToolServiceServer},
+    ExecuteRequest, ExecuteResponse, ListToolsRequest, ListToolsResponse, Outcome, ToolDescriptor,
+};
+use super::RemoteToolError;
+use autoagents::core::tool::ToolT;
+use serde_json::Value;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+/// gRPC server that runs tools for agents in other processes.
+///
+/// Tool failures are sent back as the call's outcome; gRPC errors are only
+/// used for unknown tools and malformed requests.
+#[derive(Debug, Clone, Default)]
+pub struct ToolServer {
+    tools: Vec<Arc<dyn ToolT>>,
+}
+
+impl ToolServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve a tool
+    pub fn tool(mut self, tool: Arc<dyn ToolT>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Serve several tools, such as those of an agent definition
+    pub fn tools(mut self, tools: impl IntoIterator<Item = Box<dyn ToolT>>) -> Self {
+        self.tools.extend(tools.into_iter().map(Arc::from));
+        self
+    }
+
+    /// The gRPC service, for adding to a server set up by the caller
+    pub fn into_service(self) -> ToolServiceServer<Self> {
+        ToolServiceServer::new(self)
+    }
+
+    /// Serve on `addr` until the server fails
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), RemoteToolError> {
+        self.serve_with_shutdown(addr, std::future::pending()).await
+    }
+
+    /// Serve on `addr` until `signal` completes
+    pub async fn serve_with_shutdown(
+        self,
+        addr: SocketAddr,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), RemoteToolError> {
+        log::info!("Tool server listening on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(self.into_service())
+            .serve_with_shutdown(addr, signal)
+            .await?;
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Option<&Arc<dyn ToolT>> {
+        self.tools.iter().find(|tool| tool.name() == name)
+    }
+}
+
+fn descriptor(tool: &dyn ToolT) -> ToolDescriptor {
+    ToolDescriptor {
+        name: tool.name().to_string(),
+        description: tool.description().to_string(),
+        args_schema_json: tool.args_schema().to_string(),
+        timeout_ms: tool
+            .timeout()
+            .map(|timeout| u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX)),
+        idempotent: tool.idempotent(),
+        namespace: tool.namespace().map(str::to_string),
+    }
+}
+
+#[tonic::async_trait]
+impl ToolService for ToolServer {
+    async fn list_tools(
+        &self,
+        _request: Request<ListToolsRequest>,
+    ) -> Result<Response<ListToolsResponse>, Status> {
+        Ok(Response::new(ListToolsResponse {
+            tools: self
+                .tools
+                .iter()
+                .map(|tool| descriptor(tool.as_ref()))
+                .collect(),
+        }))
+    }
+
+    async fn execute(
+        &self,
+        request: Request<ExecuteRequest>,
+    ) -> Result<Response<ExecuteResponse>, Status> {
+        let ExecuteRequest { tool, args_json } = request.into_inner();
+        let tool = self
+            .find(&tool)
+            .ok_or_else(|| Status::not_found(format!("Unknown tool: {tool}")))?;
+        let args: Value = serde_json::from_str(&args_json)
+            .map_err(|e| Status::invalid_argument(format!("Arguments are not valid JSON: {e}")))?;
+
+        let outcome = match tool.execute(args).await {
+            Ok(result) => Outcome::ResultJson(result.to_string()),
+            Err(error) => Outcome::Error(error.to_string()),
+        };
+        Ok(Response::new(ExecuteResponse {
+            outcome: Some(outcome),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::RemoteTools;
+    use autoagents::async_trait;
+    use autoagents::core::tool::{ToolCallError, ToolRuntime};
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    #[derive(Debug)]
+    struct Compile;
+
+    impl ToolT for Compile {
+        fn name(&self) -> &'static str {
+            "compile"
+        }
+
+        fn description(&self) -> &'static str {
+            "Compile a source file"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object", "properties": {"file": {"type": "string"}}})
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            Some(Duration::from_secs(90))
+        }
+
+        fn namespace(&self) -> Option<&'static str> {
+            Some("build")
+        }
+    }
+
+    #[async_trait]
+    impl ToolRuntime for Compile {
+        async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            match args["file"].as_str() {
+                Some(file) if file.ends_with(".rs") => Ok(json!({"ok": true, "file": file})),
+                _ => Err(ToolCallError::RuntimeError("not a Rust file".into())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tools_and_bad_arguments_are_rejected() {
+        let server = ToolServer::new().tool(Arc::new(Compile));
+
+        let status = server
+            .execute(Request::new(ExecuteRequest {
+                tool: "missing".into(),
+                args_json: "{}".into(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let status = server
+            .execute(Request::new(ExecuteRequest {
+                tool: "compile".into(),
+                args_json: "{".into(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_remote_tools_run_on_the_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(ToolServer::new().tool(Arc::new(Compile)).into_service())
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let remote = RemoteTools::connect(format!("http://{addr}"))
+            .await
+            .unwrap();
+        let tool = remote.tool("compile").await.unwrap();
+        assert_eq!(tool.description(), "Compile a source file");
+        assert_eq!(tool.args_schema(), Compile.args_schema());
+        assert_eq!(tool.timeout(), Some(Duration::from_secs(90)));
+        assert_eq!(tool.namespace(), Some("build"));
+
+        let result = tool.execute(json!({"file": "main.rs"})).await.unwrap();
+        assert_eq!(result, json!({"ok": true, "file": "main.rs"}));
+        let error = tool.execute(json!({"file": "main.c"})).await.unwrap_err();
+        assert!(error.to_string().contains("not a Rust file"));
+
+        assert!(matches!(
+            remote.tool("missing").await,
+            Err(RemoteToolError::ToolNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+use autoagents::core::{
+    ractor::async_trait,
+    tool::{ToolCallError, ToolInputT, ToolRuntime, ToolT},
+};
+use autoagents_derive::{tool, ToolInput};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use super::BaseFileTool;
+
+const DEFAULT_MAX_RESULTS: usize = 200;
+
+#[derive(Serialize, Deserialize, ToolInput, Debug)]
+pub struct GlobFilesArgs {
+    #[input(
+        description = "Glob pattern relative to the working directory, e.g. src/**/*.rs (** matches any number of directories)"
+    )]
+    pattern: String,
+}
+
+#[tool(
+    name = "glob_files",
+    description = "Find files and directories whose paths match a glob pattern",
+    input = GlobFilesArgs,
+)]
+#[derive(Clone)]
+pub struct GlobFiles {
+    root_dir: Option<String>,
+    max_results: usize,
+}
+
+impl GlobFiles {
+    pub fn new() -> Self {
+        Self {
+            root_dir: None,
+            max_results: DEFAULT_MAX_RESULTS,
+        }
+    }
+
+    pub fn new_with_root_dir(root_dir: String) -> Self {
+        Self {
+            root_dir: Some(root_dir),
+            max_results: DEFAULT_MAX_RESULTS,
+        }
+    }
+
+    /// Stop after `max_results` matches, 200 by default
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    fn find(&self, pattern: &str) -> Result<(Vec<String>, bool), ToolCallError> {
+        // The root is matched literally, only the model's pattern is a glob
+        let full_pattern = match self.root_dir() {
+            Some(root) if !Path::new(pattern).is_absolute() => {
+                Path::new(&glob::Pattern::escape(&root)).join(pattern)
+            }
+            _ => PathBuf::from(pattern),
+        };
+        let paths = glob::glob(&full_pattern.to_string_lossy())
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+
+        let root = match self.root_dir() {
+            Some(root) => Some(
+                Path::new(&root)
+                    .canonicalize()
+                    .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?,
+            ),
+            None => None,
+        };
+
+        let mut matches = Vec::new();
+        // Unreadable entries and matches outside the root are skipped
+        for path in paths.flatten() {
+            let Ok(resolved) = self.ensure_within_root(&path) else {
+                continue;
+            };
+            if matches.len() == self.max_results {
+                return Ok((matches, true));
+            }
+            let shown = match &root {
+                Some(root) => resolved.strip_prefix(root).unwrap_or(&resolved),
+                None => &path,
+            };
+            matches.push(shown.display().to_string());
+        }
+
+        Ok((matches, false))
+    }
+}
+
+impl BaseFileTool for GlobFiles {
+    fn root_dir(&self) -> Option<String> {
+        self.root_dir.clone()
+    }
+}
+
+impl Default for GlobFiles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for GlobFiles
+where
+    Self: BaseFileTool,
+{
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let GlobFilesArgs { pattern } = serde_json::from_value(args)?;
+
+        debug!("Glob Files Executing: Pattern: {}", pattern);
+
+        // Walking the tree is blocking work
+        let tool = self.clone();
+        let search_pattern = pattern.clone();
+        let (matches, truncated) = tokio::task::spawn_blocking(move || tool.find(&search_pattern))
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))??;
+
+        Ok(json!({
+            "success": true,
+            "pattern": pattern,
+            "matches": matches,
+            "truncated": truncated
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_tree(root: &Path) {
+        std::fs::create_dir_all(root.join("src/nested")).expect("Failed to create dirs");
+        std::fs::write(root.join("src/lib.rs"), "").expect("Failed to create file");
+        std::fs::write(root.join("src/nested/mod.rs"), "").expect("Failed to create file");
+        std::fs::write(root.join("README.md"), "").expect("Failed to create file");
+    }
+
+    #[tokio::test]
+    async fn test_glob_recursive_pattern() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        create_tree(temp_dir.path());
+
+        let glob_files =
+            GlobFiles::new_with_root_dir(temp_dir.path().to_str().unwrap().to_string());
+        let result = glob_files
+            .execute(json!({"pattern": "src/**/*.rs"}))
+            .await
+            .expect("Failed to glob");
+
+        assert_eq!(
+            result["matches"],
+            json!(["src/lib.rs", "src/nested/mod.rs"])
+        );
+        assert_eq!(result["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn test_glob_max_results() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        create_tree(temp_dir.path());
+
+        let glob_files =
+            GlobFiles::new_with_root_dir(temp_dir.path().to_str().unwrap().to_string())
+                .with_max_results(1);
+        let result = glob_files
+            .execute(json!({"pattern": "**/*"}))
+            .await
+            .expect("Failed to glob");
+
+        assert_eq!(result["matches"].as_array().unwrap().len(), 1);
+        assert_eq!(result["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_glob_skips_matches_outside_root() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let root_dir = temp_dir.path().join("root");
+        std::fs::create_dir(&root_dir).expect("Failed to create root dir");
+        std::fs::write(temp_dir.path().join("secret.txt"), "").expect("Failed to create file");
+
+        let glob_files = GlobFiles::new_with_root_dir(root_dir.to_str().unwrap().to_string());
+        let result = glob_files
+            .execute(json!({"pattern": "../*.txt"}))
+            .await
+            .expect("Failed to glob");
+
+        assert_eq!(result["matches"], json!([]));
+    }
+}
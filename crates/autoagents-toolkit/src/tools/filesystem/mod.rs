@@ -1,5 +1,6 @@
 mod copy_file;
 mod delete_file;
+mod glob_files;
 mod list_dir;
 mod move_file;
 mod read_file;
@@ -8,13 +9,32 @@ mod write_file;
 
 pub use copy_file::CopyFile;
 pub use delete_file::DeleteFile;
+pub use glob_files::GlobFiles;
 pub use list_dir::ListDir;
 pub use move_file::MoveFile;
 pub use read_file::ReadFile;
 pub use search_file::SearchFile;
 pub use write_file::WriteFile;
 
-use std::path::{Path, PathBuf};
+use autoagents::core::tool::ToolT;
+use std::path::{Component, Path, PathBuf};
+
+/// Largest file the read and write tools handle unless configured otherwise
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// The read, write, list and glob tools, all confined to `root_dir`.
+///
+/// Suited to coding assistants that should only see one project. Paths
+/// leaving the directory, through `..` or symlinks, are refused.
+pub fn sandboxed_tools(root_dir: impl Into<String>) -> Vec<Box<dyn ToolT>> {
+    let root_dir = root_dir.into();
+    vec![
+        Box::new(ReadFile::new_with_root_dir(root_dir.clone())),
+        Box::new(WriteFile::new_with_root_dir(root_dir.clone())),
+        Box::new(ListDir::new_with_root_dir(root_dir.clone())),
+        Box::new(GlobFiles::new_with_root_dir(root_dir)),
+    ]
+}
 
 pub trait BaseFileTool {
     fn root_dir(&self) -> Option<String>;
@@ -35,12 +55,17 @@ pub trait BaseFileTool {
         }
     }
 
+    /// Check that `path` stays inside the root directory once `..` and
+    /// symlinks are resolved, and return the resolved path.
+    ///
+    /// Paths that do not exist yet are resolved through their nearest
+    /// existing ancestor, so files about to be written are checked too.
     fn ensure_within_root(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
-        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let resolved = resolve(path)?;
 
         if let Some(root) = self.root_dir() {
             let root_canonical = Path::new(&root).canonicalize()?;
-            if !canonical.starts_with(&root_canonical) {
+            if !resolved.starts_with(&root_canonical) {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::PermissionDenied,
                     format!("Path {} is outside of root directory", path.display()),
@@ -48,6 +73,94 @@ pub trait BaseFileTool {
             }
         }
 
-        Ok(canonical)
+        Ok(resolved)
+    }
+}
+
+/// Canonicalize the longest existing prefix of `path` and append the rest,
+/// which may not contain `..`.
+fn resolve(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            // `..` or the end of a relative path
+            _ => break,
+        }
+    }
+
+    if existing.exists() {
+        let mut resolved = existing.canonicalize()?;
+        resolved.extend(missing.iter().rev());
+        Ok(resolved)
+    } else if path.components().any(|c| c == Component::ParentDir) {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("Path {} cannot be resolved", path.display()),
+        ))
+    } else {
+        Ok(path.to_path_buf())
+    }
+}
+
+/// Refuse files over `limit` bytes
+fn check_size(path: &Path, size: u64, limit: Option<u64>) -> Result<(), std::io::Error> {
+    match limit {
+        Some(limit) if size > limit => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} is {} bytes, over the {} byte limit",
+                path.display(),
+                size,
+                limit
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    struct Rooted(String);
+
+    impl BaseFileTool for Rooted {
+        fn root_dir(&self) -> Option<String> {
+            Some(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_traversal_out_of_root_is_refused() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        let tool = Rooted(root.to_str().unwrap().to_string());
+        let check = |path: &str| tool.ensure_within_root(&tool.get_relative_path(path));
+
+        assert!(check("new/file.txt").is_ok());
+        assert!(check("new/../file.txt").is_err());
+        assert!(check("../outside.txt").is_err());
+        assert!(check(temp_dir.path().join("outside.txt").to_str().unwrap()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_out_of_root_is_refused() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path(), root.join("escape")).unwrap();
+        let tool = Rooted(root.to_str().unwrap().to_string());
+
+        assert!(tool
+            .ensure_within_root(&tool.get_relative_path("escape/secret.txt"))
+            .is_err());
     }
 }
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tokio::fs;
 
-use super::BaseFileTool;
+use super::{check_size, BaseFileTool, DEFAULT_MAX_FILE_SIZE};
 
 #[derive(Serialize, Deserialize, ToolInput, Debug)]
 pub struct ReadFileArgs {
@@ -23,18 +23,35 @@ pub struct ReadFileArgs {
 )]
 pub struct ReadFile {
     root_dir: Option<String>,
+    max_file_size: Option<u64>,
 }
 
 impl ReadFile {
     pub fn new() -> Self {
-        Self { root_dir: None }
+        Self {
+            root_dir: None,
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+        }
     }
 
     pub fn new_with_root_dir(root_dir: String) -> Self {
         Self {
             root_dir: Some(root_dir),
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
         }
     }
+
+    /// Refuse files larger than `bytes`, 10 MiB by default
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Allow files of any size
+    pub fn without_size_limit(mut self) -> Self {
+        self.max_file_size = None;
+        self
+    }
 }
 
 impl BaseFileTool for ReadFile {
@@ -80,6 +97,12 @@ where
                 .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
         }
 
+        let metadata = fs::metadata(&path)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        check_size(&path, metadata.len(), self.max_file_size)
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+
         let encoding = "utf8".to_string();
 
         match encoding.as_str() {
@@ -192,4 +215,35 @@ mod tests {
         let content = result.get("content").and_then(|v| v.as_str()).unwrap();
         assert_eq!(content, "Test content");
     }
+
+    #[tokio::test]
+    async fn test_read_file_over_size_limit() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let file_path = temp_dir.path().join("large.txt");
+        std::fs::write(&file_path, "0123456789").expect("Failed to create test file");
+
+        let read_file = ReadFile::new().with_max_file_size(4);
+        let args = json!({
+            "file_path": file_path.display().to_string()
+        });
+
+        let error = read_file.execute(args).await.unwrap_err();
+        assert!(error.to_string().contains("byte limit"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_outside_root_dir() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let root_dir = temp_dir.path().join("root");
+        std::fs::create_dir(&root_dir).expect("Failed to create root dir");
+        std::fs::write(temp_dir.path().join("secret.txt"), "secret")
+            .expect("Failed to create test file");
+
+        let read_file = ReadFile::new_with_root_dir(root_dir.to_str().unwrap().to_string());
+        let args = json!({
+            "file_path": "../secret.txt"
+        });
+
+        assert!(read_file.execute(args).await.is_err());
+    }
 }
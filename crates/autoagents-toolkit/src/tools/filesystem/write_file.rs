@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tokio::fs;
 
-use super::BaseFileTool;
+use super::{check_size, BaseFileTool, DEFAULT_MAX_FILE_SIZE};
 
 #[derive(Serialize, Deserialize, ToolInput, Debug)]
 pub struct WriteFileArgs {
@@ -27,18 +27,35 @@ pub struct WriteFileArgs {
 )]
 pub struct WriteFile {
     root_dir: Option<String>,
+    max_file_size: Option<u64>,
 }
 
 impl WriteFile {
     pub fn new() -> Self {
-        Self { root_dir: None }
+        Self {
+            root_dir: None,
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+        }
     }
 
     pub fn new_with_root_dir(root_dir: String) -> Self {
         Self {
             root_dir: Some(root_dir),
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
         }
     }
+
+    /// Refuse files larger than `bytes`, 10 MiB by default
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Allow files of any size
+    pub fn without_size_limit(mut self) -> Self {
+        self.max_file_size = None;
+        self
+    }
 }
 
 impl BaseFileTool for WriteFile {
@@ -100,6 +117,14 @@ where
             }
         };
 
+        let existing = if append {
+            fs::metadata(&path).await.map(|m| m.len()).unwrap_or(0)
+        } else {
+            0
+        };
+        check_size(&path, existing + bytes.len() as u64, self.max_file_size)
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+
         // Write or append to file
         if append {
             use tokio::fs::OpenOptions;
@@ -212,4 +237,37 @@ mod tests {
         let content = std::fs::read_to_string(&file_path).expect("Failed to read file");
         assert_eq!(content, "Test content");
     }
+
+    #[tokio::test]
+    async fn test_write_file_over_size_limit() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let file_path = temp_dir.path().join("large.txt");
+
+        let write_file = WriteFile::new().with_max_file_size(4);
+        let args = json!({
+            "file_path": file_path.display().to_string(),
+            "content": "0123456789",
+            "append": false
+        });
+
+        assert!(write_file.execute(args).await.is_err());
+        assert!(!file_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_write_file_outside_root_dir() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let root_dir = temp_dir.path().join("root");
+        std::fs::create_dir(&root_dir).expect("Failed to create root dir");
+
+        let write_file = WriteFile::new_with_root_dir(root_dir.to_str().unwrap().to_string());
+        let args = json!({
+            "file_path": "nested/../../escaped.txt",
+            "content": "escaped",
+            "append": false
+        });
+
+        assert!(write_file.execute(args).await.is_err());
+        assert!(!temp_dir.path().join("escaped.txt").exists());
+    }
 }
@@ -0,0 +1,413 @@
+use autoagents::core::{
+    ractor::async_trait,
+    tool::{ToolCallError, ToolRuntime, ToolT},
+};
+use log::debug;
+use reqwest::{Client, Method, Url};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Calls REST APIs on behalf of an agent.
+///
+/// Only URLs under an allowed prefix can be requested; with no prefix
+/// configured every request is refused. Injected headers, such as API keys,
+/// are added to every request without being shown to the model. With a body
+/// template the model only supplies the template's `{{placeholders}}`.
+#[derive(Debug, Clone)]
+pub struct HttpTool {
+    client: Client,
+    methods: Vec<Method>,
+    allowed_urls: Vec<Url>,
+    headers: Vec<(String, String)>,
+    body_template: Option<String>,
+    content_type: String,
+    max_response_bytes: usize,
+}
+
+impl HttpTool {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            methods: vec![Method::GET],
+            allowed_urls: Vec::new(),
+            headers: Vec::new(),
+            body_template: None,
+            content_type: "application/json".to_string(),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        }
+        .timeout(DEFAULT_TIMEOUT)
+    }
+
+    /// Allow requests to `prefix` and everything below it. The scheme, host
+    /// and port must match exactly and the path must be the prefix path or
+    /// continue it with a `/`, so `/v1` admits `/v1/users` but not
+    /// `/v1admin`.
+    pub fn allow_url(mut self, prefix: &str) -> Result<Self, ToolCallError> {
+        let url = Url::parse(prefix).map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        self.allowed_urls.push(url);
+        Ok(self)
+    }
+
+    /// Set the methods the model may use, GET only by default
+    pub fn methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Add a header to every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send `template` as the body, replacing each `{{name}}` with the
+    /// matching value from the model's `params`.
+    ///
+    /// Values are escaped as JSON strings while the content type is JSON.
+    pub fn body_template(mut self, template: impl Into<String>) -> Self {
+        self.body_template = Some(template.into());
+        self
+    }
+
+    /// Content type of templated bodies, `application/json` by default
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+
+    /// Truncate response bodies after `bytes`, 1 MiB by default
+    pub fn max_response_bytes(mut self, bytes: usize) -> Self {
+        self.max_response_bytes = bytes;
+        self
+    }
+
+    /// Give up on requests after `timeout`, 30 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        // Redirects are not followed so injected headers never leave the allowlist
+        self.client = Client::builder()
+            .timeout(timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+        self
+    }
+
+    fn is_allowed(&self, url: &Url) -> bool {
+        self.allowed_urls.iter().any(|allowed| {
+            allowed.scheme() == url.scheme()
+                && allowed.host_str() == url.host_str()
+                && allowed.port_or_known_default() == url.port_or_known_default()
+                && is_below(url.path(), allowed.path())
+        })
+    }
+
+    /// Fill in the template in a single pass, so placeholders inside the
+    /// model's values are sent as they are instead of being filled in too.
+    /// Placeholders without a value are left untouched.
+    fn render_template(&self, template: &str, params: &Map<String, Value>) -> String {
+        let mut body = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            body.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let placeholder = after
+                .find("}}")
+                .and_then(|end| Some((end, params.get(&after[..end])?)));
+            match placeholder {
+                Some((end, value)) => {
+                    body.push_str(&self.render_value(value));
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    body.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        body.push_str(rest);
+        body
+    }
+
+    fn render_value(&self, value: &Value) -> String {
+        let text = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        if self.content_type.contains("json") {
+            // Escape as the inside of a JSON string
+            let quoted = Value::String(text).to_string();
+            quoted[1..quoted.len() - 1].to_string()
+        } else {
+            text
+        }
+    }
+
+    async fn read_body(
+        &self,
+        mut response: reqwest::Response,
+    ) -> Result<(Vec<u8>, bool), ToolCallError> {
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?
+        {
+            let room = self.max_response_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                return Ok((body, true));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((body, false))
+    }
+}
+
+impl Default for HttpTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether `path` is `prefix` itself or a path below it
+fn is_below(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/'))
+}
+
+fn runtime_error(message: String) -> ToolCallError {
+    ToolCallError::RuntimeError(message.into())
+}
+
+impl ToolT for HttpTool {
+    fn name(&self) -> &'static str {
+        "http_request"
+    }
+
+    fn description(&self) -> &'static str {
+        "Send an HTTP request to an allowed URL and return the status and response body"
+    }
+
+    fn args_schema(&self) -> Value {
+        let methods: Vec<&str> = self.methods.iter().map(Method::as_str).collect();
+        let allowed: Vec<&str> = self.allowed_urls.iter().map(Url::as_str).collect();
+        let mut properties = json!({
+            "url": {
+                "type": "string",
+                "description": format!("Absolute URL starting with one of: {}", allowed.join(", ")),
+            },
+            "method": {
+                "type": "string",
+                "enum": methods,
+                "description": "HTTP method, defaults to the first allowed one",
+            },
+            "query": {
+                "type": "object",
+                "additionalProperties": {"type": "string"},
+                "description": "Query string parameters",
+            },
+        });
+        if self.body_template.is_some() {
+            properties["params"] = json!({
+                "type": "object",
+                "additionalProperties": {"type": "string"},
+                "description": "Values for the request body",
+            });
+        } else {
+            properties["body"] = json!({
+                "description": "Request body; objects and arrays are sent as JSON",
+            });
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["url"],
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for HttpTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let url = args
+            .get("url")
+            .and_then(Value::as_str)
+            .ok_or_else(|| runtime_error("Missing string argument 'url'".to_string()))?;
+        let url = Url::parse(url).map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        if !self.is_allowed(&url) {
+            return Err(runtime_error(format!("URL is not allowed: {url}")));
+        }
+
+        let method = match args.get("method").and_then(Value::as_str) {
+            Some(method) => self
+                .methods
+                .iter()
+                .find(|allowed| allowed.as_str().eq_ignore_ascii_case(method))
+                .cloned()
+                .ok_or_else(|| runtime_error(format!("Method is not allowed: {method}")))?,
+            None => self
+                .methods
+                .first()
+                .cloned()
+                .ok_or_else(|| runtime_error("No HTTP methods are allowed".to_string()))?,
+        };
+
+        debug!("HTTP Tool Executing: {} {}", method, url);
+
+        let mut request = self.client.request(method, url);
+        if let Some(query) = args.get("query").and_then(Value::as_object) {
+            let pairs: Vec<(&str, String)> = query
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::String(text) => (key.as_str(), text.clone()),
+                    other => (key.as_str(), other.to_string()),
+                })
+                .collect();
+            request = request.query(&pairs);
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request = match (&self.body_template, args.get("body")) {
+            (Some(template), _) => {
+                let params = args
+                    .get("params")
+                    .and_then(Value::as_object)
+                    .cloned()
+                    .unwrap_or_default();
+                request
+                    .header(reqwest::header::CONTENT_TYPE, &self.content_type)
+                    .body(self.render_template(template, &params))
+            }
+            (None, Some(Value::String(text))) => request.body(text.clone()),
+            (None, Some(body @ (Value::Object(_) | Value::Array(_)))) => request.json(body),
+            (None, _) => request,
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let (bytes, truncated) = self.read_body(response).await?;
+
+        let body = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) if !truncated => value,
+            _ => Value::String(String::from_utf8_lossy(&bytes).into_owned()),
+        };
+
+        Ok(json!({
+            "status": status,
+            "content_type": content_type,
+            "body": body,
+            "truncated": truncated,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool() -> HttpTool {
+        HttpTool::new()
+            .allow_url("https://api.example.com/v1/")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_allowlist_matches_origin_and_path_prefix() {
+        let tool = tool();
+        let allowed = |url: &str| tool.is_allowed(&Url::parse(url).unwrap());
+
+        assert!(allowed("https://api.example.com/v1/users?id=1"));
+        assert!(!allowed("https://api.example.com/v2/users"));
+        assert!(!allowed("http://api.example.com/v1/users"));
+        assert!(!allowed("https://api.example.com.evil.net/v1/users"));
+        assert!(!allowed("https://api.example.com:8443/v1/users"));
+        assert!(!HttpTool::new().is_allowed(&Url::parse("https://example.com").unwrap()));
+    }
+
+    #[test]
+    fn test_allowlist_prefix_ends_at_a_path_segment() {
+        let tool = HttpTool::new()
+            .allow_url("https://api.example.com/v1")
+            .unwrap();
+        let allowed = |url: &str| tool.is_allowed(&Url::parse(url).unwrap());
+
+        assert!(allowed("https://api.example.com/v1"));
+        assert!(allowed("https://api.example.com/v1/users"));
+        assert!(!allowed("https://api.example.com/v1admin/users"));
+        assert!(!allowed("https://api.example.com/v10"));
+    }
+
+    #[test]
+    fn test_body_template_escapes_json_values() {
+        let tool = tool().body_template(r#"{"text": "{{text}}", "lang": "{{lang}}"}"#);
+        let params = json!({"text": "say \"hi\"", "lang": "en"});
+        let body = tool.render_template(
+            tool.body_template.as_deref().unwrap(),
+            params.as_object().unwrap(),
+        );
+
+        let parsed: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed, json!({"text": "say \"hi\"", "lang": "en"}));
+    }
+
+    #[test]
+    fn test_body_template_values_are_not_rendered_again() {
+        let tool = tool()
+            .content_type("text/plain")
+            .body_template("to={{to}}&text={{text}}");
+        let params = json!({"text": "hi {{to}}", "to": "ops"});
+        let body = tool.render_template(
+            tool.body_template.as_deref().unwrap(),
+            params.as_object().unwrap(),
+        );
+        assert_eq!(body, "to=ops&text=hi {{to}}");
+
+        let params = json!({"to": "{{text}}", "text": "hi"});
+        let body = tool.render_template(
+            tool.body_template.as_deref().unwrap(),
+            params.as_object().unwrap(),
+        );
+        assert_eq!(body, "to={{text}}&text=hi");
+    }
+
+    #[test]
+    fn test_schema_lists_allowed_methods() {
+        let schema = tool().methods([Method::GET, Method::POST]).args_schema();
+        assert_eq!(
+            schema["properties"]["method"]["enum"],
+            json!(["GET", "POST"])
+        );
+        assert!(schema["properties"].get("body").is_some());
+
+        let templated = tool().body_template("{}").args_schema();
+        assert!(templated["properties"].get("params").is_some());
+        assert!(templated["properties"].get("body").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_requests_are_refused() {
+        let error = tool()
+            .execute(json!({"url": "https://other.example.com/"}))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not allowed"));
+
+        let error = tool()
+            .execute(json!({"url": "https://api.example.com/v1/", "method": "DELETE"}))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Method is not allowed"));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod wasi;
+
+pub use wasi::{CodeInterpreter, CodeInterpreterBuilder};
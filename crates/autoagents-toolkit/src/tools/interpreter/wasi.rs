@@ -0,0 +1,404 @@
+use autoagents::core::{
+    ractor::async_trait,
+    tool::{ToolCallError, ToolRuntime, ToolT},
+};
+use log::debug;
+use serde_json::{json, Value};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use wasmtime::{
+    Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap, UpdateDeadline,
+};
+use wasmtime_wasi::p1::{self, WasiP1Ctx};
+use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::{I32Exit, WasiCtxBuilder};
+
+const CODE_PLACEHOLDER: &str = "{code}";
+const DEFAULT_MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often running code checks the wall clock
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Runs model-written code in an interpreter compiled to WASI, such as
+/// CPython or QuickJS built for `wasm32-wasi`.
+///
+/// The code has no access to the filesystem, network or environment of the
+/// host. Memory, CPU time (as wasmtime fuel) and wall-clock time are
+/// capped, and stdout and stderr are returned to the agent.
+#[derive(Clone)]
+pub struct CodeInterpreter {
+    engine: Engine,
+    module: Module,
+    language: String,
+    args: Vec<String>,
+    max_memory_bytes: usize,
+    max_output_bytes: usize,
+    fuel: Option<u64>,
+    timeout: Duration,
+}
+
+struct SandboxState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// Builder for [`CodeInterpreter`].
+#[derive(Debug)]
+pub struct CodeInterpreterBuilder {
+    source: Option<Source>,
+    language: String,
+    args: Vec<String>,
+    max_memory_bytes: usize,
+    max_output_bytes: usize,
+    fuel: Option<u64>,
+    timeout: Duration,
+}
+
+#[derive(Debug)]
+enum Source {
+    File(String),
+    Bytes(Vec<u8>),
+}
+
+impl Default for CodeInterpreterBuilder {
+    fn default() -> Self {
+        Self {
+            source: None,
+            language: "Python".to_string(),
+            args: Vec::new(),
+            max_memory_bytes: DEFAULT_MAX_MEMORY_BYTES,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            fuel: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl CodeInterpreterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the interpreter from a `.wasm` file
+    pub fn source_file(mut self, path: impl Into<String>) -> Self {
+        self.source = Some(Source::File(path.into()));
+        self
+    }
+
+    /// Load the interpreter from wasm bytes or WAT text
+    pub fn source_bytes(mut self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.source = Some(Source::Bytes(bytes.into()));
+        self
+    }
+
+    /// Name of the language, shown to the model, Python by default
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = language.into();
+        self
+    }
+
+    /// Command line arguments of the interpreter. An argument containing
+    /// `{code}` has it replaced with the code, e.g. `["-c", "{code}"]`;
+    /// without one the code is passed on stdin.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Cap the linear memory of the interpreter, 256 MiB by default
+    pub fn max_memory_bytes(mut self, bytes: usize) -> Self {
+        self.max_memory_bytes = bytes;
+        self
+    }
+
+    /// Truncate stdout and stderr after `bytes` each, 64 KiB by default
+    pub fn max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Stop the code after it consumes `fuel` units, unlimited by default.
+    /// One unit is roughly one wasm instruction.
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// Stop the code after `timeout`, 10 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn build(self) -> Result<CodeInterpreter, ToolCallError> {
+        let mut config = Config::new();
+        config.consume_fuel(true).epoch_interruption(true);
+        let engine = Engine::new(&config).map_err(|e| ToolCallError::RuntimeError(e.into()))?;
+
+        let module = match self.source {
+            Some(Source::File(path)) => Module::from_file(&engine, &path),
+            Some(Source::Bytes(bytes)) => Module::new(&engine, bytes),
+            None => {
+                return Err(ToolCallError::RuntimeError(
+                    "Missing interpreter module".into(),
+                ))
+            }
+        }
+        .map_err(|e| ToolCallError::RuntimeError(e.into()))?;
+
+        Ok(CodeInterpreter {
+            engine,
+            module,
+            language: self.language,
+            args: self.args,
+            max_memory_bytes: self.max_memory_bytes,
+            max_output_bytes: self.max_output_bytes,
+            fuel: self.fuel,
+            timeout: self.timeout,
+        })
+    }
+}
+
+impl CodeInterpreter {
+    pub fn builder() -> CodeInterpreterBuilder {
+        CodeInterpreterBuilder::new()
+    }
+
+    /// Run `code` to completion, blocking the current thread
+    pub fn run(&self, code: &str) -> Result<Value, ToolCallError> {
+        let stdout = MemoryOutputPipe::new(self.max_output_bytes);
+        let stderr = MemoryOutputPipe::new(self.max_output_bytes);
+
+        let mut argv = vec![self.language.to_lowercase()];
+        argv.extend(
+            self.args
+                .iter()
+                .map(|arg| arg.replace(CODE_PLACEHOLDER, code)),
+        );
+        let mut wasi = WasiCtxBuilder::new();
+        wasi.args(&argv)
+            .stdout(stdout.clone())
+            .stderr(stderr.clone());
+        if !self.args.iter().any(|arg| arg.contains(CODE_PLACEHOLDER)) {
+            wasi.stdin(MemoryInputPipe::new(code.to_string()));
+        }
+
+        let state = SandboxState {
+            wasi: wasi.build_p1(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.max_memory_bytes)
+                .build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(self.fuel.unwrap_or(u64::MAX))
+            .map_err(|e| ToolCallError::RuntimeError(e.into()))?;
+
+        // The epoch is shared by every run on this engine, so each store
+        // checks its own deadline whenever it ticks
+        let started = Instant::now();
+        let timeout = self.timeout;
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |_| {
+            if started.elapsed() >= timeout {
+                Err(Trap::Interrupt.into())
+            } else {
+                Ok(UpdateDeadline::Continue(1))
+            }
+        });
+
+        let mut linker: Linker<SandboxState> = Linker::new(&self.engine);
+        p1::add_to_linker_sync(&mut linker, |state| &mut state.wasi)
+            .map_err(|e| ToolCallError::RuntimeError(e.into()))?;
+        let instance = linker
+            .instantiate(&mut store, &self.module)
+            .map_err(|e| ToolCallError::RuntimeError(e.into()))?;
+        let start = instance
+            .get_typed_func::<(), ()>(&mut store, "_start")
+            .map_err(|e| ToolCallError::RuntimeError(e.into()))?;
+
+        let (done, ticks) = mpsc::channel::<()>();
+        let engine = self.engine.clone();
+        std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = ticks.recv_timeout(EPOCH_TICK) {
+                engine.increment_epoch();
+            }
+        });
+        let result = start.call(&mut store, ());
+        drop(done);
+
+        let (exit_code, error) = match result {
+            Ok(()) => (Some(0), None),
+            Err(e) => match (e.downcast_ref::<I32Exit>(), e.downcast_ref::<Trap>()) {
+                (Some(exit), _) => (Some(exit.0), None),
+                (_, Some(Trap::Interrupt)) => return Err(ToolCallError::Timeout(self.timeout)),
+                (_, Some(Trap::OutOfFuel)) => (None, Some("Ran out of fuel".to_string())),
+                _ => (None, Some(format!("{e:#}"))),
+            },
+        };
+
+        let stdout = stdout.contents();
+        let stderr = stderr.contents();
+        Ok(json!({
+            "exit_code": exit_code,
+            "stdout": String::from_utf8_lossy(&stdout),
+            "stderr": String::from_utf8_lossy(&stderr),
+            "error": error,
+            "truncated": stdout.len() >= self.max_output_bytes
+                || stderr.len() >= self.max_output_bytes,
+        }))
+    }
+}
+
+impl std::fmt::Debug for CodeInterpreter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CodeInterpreter")
+            .field("language", &self.language)
+            .field("args", &self.args)
+            .field("max_memory_bytes", &self.max_memory_bytes)
+            .field("fuel", &self.fuel)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ToolT for CodeInterpreter {
+    fn name(&self) -> &'static str {
+        "code_interpreter"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run code in a sandbox without file or network access and return its stdout and stderr"
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "code": {
+                    "type": "string",
+                    "description": format!(
+                        "{} code to run. Only printed output is returned.",
+                        self.language
+                    ),
+                },
+            },
+            "required": ["code"],
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for CodeInterpreter {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let code = args
+            .get("code")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolCallError::RuntimeError("Missing string argument 'code'".into()))?
+            .to_string();
+
+        debug!(
+            "Code Interpreter Executing: {} bytes of {}",
+            code.len(),
+            self.language
+        );
+
+        // Wasmtime runs synchronously
+        let interpreter = self.clone();
+        tokio::task::spawn_blocking(move || interpreter.run(&code))
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echoes stdin to stdout, like an interpreter printing its input
+    const ECHO: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_read"
+            (func $fd_read (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 16))
+            (i32.store (i32.const 4) (i32.const 1024))
+            (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 4) (i32.load (i32.const 8)))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    const SPIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "_start") (loop $spin (br $spin))))
+    "#;
+
+    #[tokio::test]
+    async fn test_code_reaches_interpreter_and_output_is_returned() {
+        let interpreter = CodeInterpreter::builder()
+            .source_bytes(ECHO)
+            .build()
+            .unwrap();
+
+        let result = interpreter
+            .execute(json!({"code": "print('hi')"}))
+            .await
+            .unwrap();
+        assert_eq!(result["exit_code"], 0);
+        assert_eq!(result["stdout"], "print('hi')");
+        assert_eq!(result["error"], Value::Null);
+    }
+
+    #[test]
+    fn test_fuel_limit_stops_code() {
+        let interpreter = CodeInterpreter::builder()
+            .source_bytes(SPIN)
+            .fuel(10_000)
+            .build()
+            .unwrap();
+
+        let result = interpreter.run("").unwrap();
+        assert_eq!(result["exit_code"], Value::Null);
+        assert_eq!(result["error"], "Ran out of fuel");
+    }
+
+    #[test]
+    fn test_timeout_stops_code() {
+        let interpreter = CodeInterpreter::builder()
+            .source_bytes(SPIN)
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            interpreter.run(""),
+            Err(ToolCallError::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn test_schema_names_language() {
+        let interpreter = CodeInterpreter::builder()
+            .source_bytes(SPIN)
+            .language("JavaScript")
+            .args(["-e", "{code}"])
+            .build()
+            .unwrap();
+
+        let schema = interpreter.args_schema();
+        assert!(schema["properties"]["code"]["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("JavaScript"));
+    }
+}
@@ -3,5 +3,20 @@
 #[cfg(all(not(target_arch = "wasm32"), feature = "filesystem"))]
 pub mod filesystem;
 
+#[cfg(all(not(target_arch = "wasm32"), feature = "http"))]
+pub mod http;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "interpreter"))]
+pub mod interpreter;
+
 #[cfg(all(not(target_arch = "wasm32"), feature = "search"))]
 pub mod search;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "shell"))]
+pub mod shell;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "sql"))]
+pub mod sql;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "vision"))]
+pub mod vision;
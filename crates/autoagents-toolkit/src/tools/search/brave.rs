@@ -6,9 +6,10 @@ use autoagents_derive::{tool, ToolInput};
 use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::env;
 
+use super::provider::{request_error, text, SearchProvider, SearchResult};
 use crate::utils::constant::RestHeaders;
 
 static BRAVE_SEARCH_API_KEY: Lazy<String> = Lazy::new(|| {
@@ -45,8 +46,13 @@ impl BraveSearch {
         Self { api_key }
     }
 
-    async fn fetch_raw_results(&self, query: &str) -> Result<Value, ToolCallError> {
-        let params = [("q", query), ("extra_snippets", "true")];
+    async fn fetch_raw_results(&self, query: &str, count: usize) -> Result<Value, ToolCallError> {
+        let count = count.to_string();
+        let params = [
+            ("q", query),
+            ("extra_snippets", "true"),
+            ("count", count.as_str()),
+        ];
 
         let response = Client::new()
             .get(BRAVE_API_ENDPOINT)
@@ -58,69 +64,98 @@ impl BraveSearch {
             .header(RestHeaders::XSubscriptionToken.as_str(), &self.api_key)
             .send()
             .await
-            .map_err(|err| ToolCallError::RuntimeError(Box::new(err)))?;
+            .map_err(request_error)?;
 
         let payload = response
             .error_for_status()
-            .map_err(|err| ToolCallError::RuntimeError(Box::new(err)))?
+            .map_err(request_error)?
             .json::<Value>()
             .await
-            .map_err(|err| ToolCallError::RuntimeError(Box::new(err)))?;
+            .map_err(request_error)?;
 
         Ok(payload)
     }
 }
 
-#[async_trait]
-impl ToolRuntime for BraveSearch {
-    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
-        let BraveSearchArgs { query } = serde_json::from_value(args)?;
-        let payload = self.fetch_raw_results(&query).await?;
-
-        let raw_results = payload
-            .get("web")
-            .and_then(|web| web.get("results"))
-            .and_then(|results| results.as_array())
-            .cloned()
-            .unwrap_or_default();
-
-        let summarized_results = raw_results
-            .into_iter()
-            .map(|item| {
-                let title = item
-                    .get("title")
-                    .and_then(Value::as_str)
-                    .unwrap_or_default()
-                    .to_string();
-                let link = item
-                    .get("url")
-                    .and_then(Value::as_str)
-                    .unwrap_or_default()
-                    .to_string();
-
-                let mut snippet_parts: Vec<String> = Vec::new();
-                if let Some(description) = item.get("description").and_then(Value::as_str) {
-                    if !description.is_empty() {
-                        snippet_parts.push(description.to_string());
-                    }
+/// Normalize a Brave web search response, joining the description and any
+/// extra snippets into one snippet.
+fn parse_results(payload: &Value) -> Vec<SearchResult> {
+    payload
+        .get("web")
+        .and_then(|web| web.get("results"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            let mut snippet_parts: Vec<&str> = Vec::new();
+            if let Some(description) = item.get("description").and_then(Value::as_str) {
+                if !description.is_empty() {
+                    snippet_parts.push(description);
                 }
+            }
 
-                if let Some(extra_snippets) = item.get("extra_snippets").and_then(Value::as_array) {
-                    for snippet in extra_snippets.iter().filter_map(Value::as_str) {
-                        if !snippet.is_empty() {
-                            snippet_parts.push(snippet.to_string());
-                        }
+            if let Some(extra_snippets) = item.get("extra_snippets").and_then(Value::as_array) {
+                for snippet in extra_snippets.iter().filter_map(Value::as_str) {
+                    if !snippet.is_empty() {
+                        snippet_parts.push(snippet);
                     }
                 }
+            }
+
+            SearchResult {
+                title: text(item, "title"),
+                link: text(item, "url"),
+                snippet: snippet_parts.join(" "),
+            }
+        })
+        .collect()
+}
+
+#[async_trait]
+impl SearchProvider for BraveSearch {
+    async fn search(
+        &self,
+        query: &str,
+        max_results: usize,
+    ) -> Result<Vec<SearchResult>, ToolCallError> {
+        // Brave returns at most 20 results per request
+        let payload = self.fetch_raw_results(query, max_results.min(20)).await?;
+        Ok(parse_results(&payload))
+    }
+}
 
-                json!({
-                    "title": title,
-                    "link": link,
-                    "snippet": snippet_parts.join(" "),
-                })
-            })
-            .collect::<Vec<Value>>();
+#[async_trait]
+impl ToolRuntime for BraveSearch {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let BraveSearchArgs { query } = serde_json::from_value(args)?;
+        // Brave's own default page size
+        let results = self.search(&query, 20).await?;
+        Ok(serde_json::to_value(results)?)
+    }
+}
 
-        Ok(Value::Array(summarized_results))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_results_joins_snippets() {
+        let payload = json!({"web": {"results": [{
+            "title": "Rust",
+            "url": "https://www.rust-lang.org",
+            "description": "A language",
+            "extra_snippets": ["fast", ""]
+        }]}});
+
+        assert_eq!(
+            parse_results(&payload),
+            vec![SearchResult {
+                title: "Rust".to_string(),
+                link: "https://www.rust-lang.org".to_string(),
+                snippet: "A language fast".to_string(),
+            }]
+        );
+        assert!(parse_results(&json!({})).is_empty());
     }
 }
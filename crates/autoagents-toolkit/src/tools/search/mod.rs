@@ -1,3 +1,11 @@
 pub mod brave;
+pub mod provider;
+pub mod searxng;
+pub mod serpapi;
+pub mod tavily;
 
 pub use brave::BraveSearch;
+pub use provider::{SearchProvider, SearchResult, WebSearchTool};
+pub use searxng::SearxngSearch;
+pub use serpapi::SerpApiSearch;
+pub use tavily::TavilySearch;
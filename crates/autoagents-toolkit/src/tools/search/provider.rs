@@ -0,0 +1,116 @@
+use autoagents::core::{
+    ractor::async_trait,
+    tool::{ToolCallError, ToolInputT, ToolRuntime, ToolT},
+};
+use autoagents_derive::{tool, ToolInput};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Debug;
+
+const DEFAULT_MAX_RESULTS: usize = 5;
+
+/// A search hit in the shape every provider is normalized to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub link: String,
+    pub snippet: String,
+}
+
+/// A web search backend used by [`WebSearchTool`].
+#[async_trait]
+pub trait SearchProvider: Send + Sync + Debug {
+    async fn search(
+        &self,
+        query: &str,
+        max_results: usize,
+    ) -> Result<Vec<SearchResult>, ToolCallError>;
+}
+
+pub(crate) fn request_error(err: reqwest::Error) -> ToolCallError {
+    ToolCallError::RuntimeError(Box::new(err))
+}
+
+/// Read a string field, treating a missing one as empty
+pub(crate) fn text(item: &Value, field: &str) -> String {
+    item.get(field)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[derive(Serialize, Deserialize, ToolInput, Debug)]
+pub struct WebSearchArgs {
+    #[input(description = "Query for search")]
+    query: String,
+}
+
+#[tool(
+    name = "web_search",
+    description = "Search the web and return the title, link and snippet of the top results",
+    input = WebSearchArgs,
+)]
+pub struct WebSearchTool {
+    provider: Box<dyn SearchProvider>,
+    max_results: usize,
+}
+
+impl WebSearchTool {
+    pub fn new(provider: impl SearchProvider + 'static) -> Self {
+        Self {
+            provider: Box::new(provider),
+            max_results: DEFAULT_MAX_RESULTS,
+        }
+    }
+
+    /// Return at most `max_results` results, 5 by default
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results;
+        self
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for WebSearchTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let WebSearchArgs { query } = serde_json::from_value(args)?;
+        let mut results = self.provider.search(&query, self.max_results).await?;
+        results.truncate(self.max_results);
+        Ok(serde_json::to_value(results)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct Canned;
+
+    #[async_trait]
+    impl SearchProvider for Canned {
+        async fn search(
+            &self,
+            query: &str,
+            _max_results: usize,
+        ) -> Result<Vec<SearchResult>, ToolCallError> {
+            Ok((0..3)
+                .map(|i| SearchResult {
+                    title: format!("{query} {i}"),
+                    link: format!("https://example.com/{i}"),
+                    snippet: String::new(),
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_web_search_caps_results() {
+        let tool = WebSearchTool::new(Canned).with_max_results(2);
+        let results = tool.execute(json!({"query": "rust"})).await.unwrap();
+
+        assert_eq!(results.as_array().unwrap().len(), 2);
+        assert_eq!(results[1]["title"], "rust 1");
+    }
+}
@@ -0,0 +1,84 @@
+use autoagents::core::{ractor::async_trait, tool::ToolCallError};
+use reqwest::Client;
+use serde_json::Value;
+
+use super::provider::{request_error, text, SearchProvider, SearchResult};
+
+/// Search through a self-hosted SearxNG instance.
+///
+/// The instance must have the `json` format enabled in its settings.
+#[derive(Debug, Clone)]
+pub struct SearxngSearch {
+    base_url: String,
+}
+
+impl SearxngSearch {
+    /// Use the instance at `base_url`, e.g. `http://localhost:8080`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+fn parse_results(payload: &Value) -> Vec<SearchResult> {
+    payload
+        .get("results")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|item| SearchResult {
+            title: text(item, "title"),
+            link: text(item, "url"),
+            snippet: text(item, "content"),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl SearchProvider for SearxngSearch {
+    async fn search(
+        &self,
+        query: &str,
+        max_results: usize,
+    ) -> Result<Vec<SearchResult>, ToolCallError> {
+        let payload = Client::new()
+            .get(format!("{}/search", self.base_url))
+            .query(&[("q", query), ("format", "json")])
+            .send()
+            .await
+            .map_err(request_error)?
+            .error_for_status()
+            .map_err(request_error)?
+            .json::<Value>()
+            .await
+            .map_err(request_error)?;
+
+        // SearxNG has no page size parameter
+        let mut results = parse_results(&payload);
+        results.truncate(max_results);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_trailing_slash_is_dropped() {
+        assert_eq!(
+            SearxngSearch::new("http://localhost:8080/").base_url,
+            "http://localhost:8080"
+        );
+    }
+
+    #[test]
+    fn test_parse_results() {
+        let payload = json!({"query": "rust", "results": [
+            {"title": "Rust", "url": "https://www.rust-lang.org", "content": "A language"}
+        ]});
+        assert_eq!(parse_results(&payload)[0].title, "Rust");
+    }
+}
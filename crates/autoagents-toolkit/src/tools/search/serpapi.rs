@@ -0,0 +1,93 @@
+use autoagents::core::{ractor::async_trait, tool::ToolCallError};
+use reqwest::Client;
+use serde_json::Value;
+
+use super::provider::{request_error, text, SearchProvider, SearchResult};
+
+const SERPAPI_ENDPOINT: &str = "https://serpapi.com/search.json";
+
+/// Google results through SerpAPI.
+#[derive(Debug, Clone)]
+pub struct SerpApiSearch {
+    api_key: String,
+    engine: String,
+}
+
+impl SerpApiSearch {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            engine: "google".to_string(),
+        }
+    }
+
+    /// Use another SerpAPI engine, such as `bing` or `duckduckgo`
+    pub fn with_engine(mut self, engine: impl Into<String>) -> Self {
+        self.engine = engine.into();
+        self
+    }
+}
+
+fn parse_results(payload: &Value) -> Vec<SearchResult> {
+    payload
+        .get("organic_results")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|item| SearchResult {
+            title: text(item, "title"),
+            link: text(item, "link"),
+            snippet: text(item, "snippet"),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl SearchProvider for SerpApiSearch {
+    async fn search(
+        &self,
+        query: &str,
+        max_results: usize,
+    ) -> Result<Vec<SearchResult>, ToolCallError> {
+        let num = max_results.to_string();
+        let params = [
+            ("q", query),
+            ("engine", self.engine.as_str()),
+            ("num", num.as_str()),
+            ("api_key", self.api_key.as_str()),
+        ];
+
+        let payload = Client::new()
+            .get(SERPAPI_ENDPOINT)
+            .query(&params)
+            .send()
+            .await
+            .map_err(request_error)?
+            .error_for_status()
+            .map_err(request_error)?
+            .json::<Value>()
+            .await
+            .map_err(request_error)?;
+
+        Ok(parse_results(&payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_organic_results() {
+        let payload = json!({"organic_results": [
+            {"title": "Rust", "link": "https://www.rust-lang.org", "snippet": "A language"},
+            {"title": "No snippet", "link": "https://example.com"}
+        ]});
+
+        let results = parse_results(&payload);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].snippet, "A language");
+        assert_eq!(results[1].snippet, "");
+    }
+}
@@ -0,0 +1,78 @@
+use autoagents::core::{ractor::async_trait, tool::ToolCallError};
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use super::provider::{request_error, text, SearchProvider, SearchResult};
+
+const TAVILY_ENDPOINT: &str = "https://api.tavily.com/search";
+
+/// Search through the Tavily API, which is tuned for LLM agents.
+#[derive(Debug, Clone)]
+pub struct TavilySearch {
+    api_key: String,
+}
+
+impl TavilySearch {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+        }
+    }
+}
+
+fn parse_results(payload: &Value) -> Vec<SearchResult> {
+    payload
+        .get("results")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|item| SearchResult {
+            title: text(item, "title"),
+            link: text(item, "url"),
+            snippet: text(item, "content"),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl SearchProvider for TavilySearch {
+    async fn search(
+        &self,
+        query: &str,
+        max_results: usize,
+    ) -> Result<Vec<SearchResult>, ToolCallError> {
+        let payload = Client::new()
+            .post(TAVILY_ENDPOINT)
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "query": query,
+                "max_results": max_results,
+            }))
+            .send()
+            .await
+            .map_err(request_error)?
+            .error_for_status()
+            .map_err(request_error)?
+            .json::<Value>()
+            .await
+            .map_err(request_error)?;
+
+        Ok(parse_results(&payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results() {
+        let payload = json!({"results": [
+            {"title": "Rust", "url": "https://www.rust-lang.org", "content": "A language", "score": 0.9}
+        ]});
+
+        let results = parse_results(&payload);
+        assert_eq!(results[0].link, "https://www.rust-lang.org");
+        assert_eq!(results[0].snippet, "A language");
+    }
+}
@@ -0,0 +1,439 @@
+use autoagents::core::{
+    agent::HookOutcome,
+    ractor::async_trait,
+    tool::{ToolCallError, ToolRuntime, ToolT},
+};
+use log::debug;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A command the model asked to run, as passed to a [`ShellHook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+}
+
+/// Notified around every command a [`ShellTool`] runs.
+///
+/// Return [`HookOutcome::Abort`] from `on_command` to refuse a command, for
+/// example after asking a user for approval.
+#[async_trait]
+pub trait ShellHook: Send + Sync {
+    async fn on_command(&self, _command: &ShellCommand) -> HookOutcome {
+        HookOutcome::Continue
+    }
+
+    /// Called with the exit code, or `None` if the process was killed
+    async fn on_command_complete(&self, _command: &ShellCommand, _exit_code: Option<i32>) {}
+}
+
+/// Runs commands on behalf of an agent inside a working directory.
+///
+/// Commands are run directly, not through a shell, so pipes, redirects and
+/// `;` have no special meaning. Only allowed programs can run; with none
+/// allowed every command is refused. Denied programs are refused even
+/// after [`allow_all`](Self::allow_all).
+#[derive(Clone)]
+pub struct ShellTool {
+    root_dir: PathBuf,
+    allowed: HashSet<String>,
+    allow_all: bool,
+    denied: HashSet<String>,
+    timeout: Duration,
+    max_output_bytes: usize,
+    hooks: Vec<Arc<dyn ShellHook>>,
+}
+
+impl ShellTool {
+    /// Run commands in `root_dir` or the directories below it
+    pub fn new(root_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            root_dir: root_dir.into(),
+            allowed: HashSet::new(),
+            allow_all: false,
+            denied: HashSet::new(),
+            timeout: DEFAULT_TIMEOUT,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Allow running `program`, given by name, e.g. `git`
+    pub fn allow(mut self, program: impl Into<String>) -> Self {
+        self.allowed.insert(program.into());
+        self
+    }
+
+    /// Allow every program that is not denied
+    pub fn allow_all(mut self) -> Self {
+        self.allow_all = true;
+        self
+    }
+
+    /// Never run `program`
+    pub fn deny(mut self, program: impl Into<String>) -> Self {
+        self.denied.insert(program.into());
+        self
+    }
+
+    /// Kill commands after `timeout`, 30 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Truncate stdout and stderr after `bytes` each, 64 KiB by default
+    pub fn max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Notify `hook` around every command
+    pub fn hook(mut self, hook: Arc<dyn ShellHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    fn is_allowed(&self, program: &str) -> bool {
+        if self.denied.contains(program) {
+            return false;
+        }
+        // Paths could name any binary, so `allow_all` only covers bare names
+        self.allowed.contains(program) || (self.allow_all && !program.contains(['/', '\\']))
+    }
+
+    /// Resolve `dir` below the root, refusing anything outside it
+    fn working_dir(&self, dir: Option<&str>) -> Result<PathBuf, ToolCallError> {
+        let root = self
+            .root_dir
+            .canonicalize()
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let Some(dir) = dir else {
+            return Ok(root);
+        };
+        let resolved = root
+            .join(dir)
+            .canonicalize()
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        if !resolved.starts_with(&root) || !resolved.is_dir() {
+            return Err(runtime_error(format!(
+                "Working directory is outside of {}: {}",
+                self.root_dir.display(),
+                dir
+            )));
+        }
+        Ok(resolved)
+    }
+
+    async fn run(&self, command: &ShellCommand) -> Result<Value, ToolCallError> {
+        let mut child = Command::new(&command.program)
+            .args(&command.args)
+            .current_dir(&command.working_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let outcome = tokio::time::timeout(self.timeout, async {
+            tokio::join!(
+                read_capped(stdout, self.max_output_bytes),
+                read_capped(stderr, self.max_output_bytes),
+                child.wait(),
+            )
+        })
+        .await;
+
+        let (stdout, stderr, status) = match outcome {
+            Ok((stdout, stderr, status)) => (stdout, stderr, status),
+            Err(_) => {
+                // Dropping the child kills it
+                for hook in &self.hooks {
+                    hook.on_command_complete(command, None).await;
+                }
+                return Err(ToolCallError::Timeout(self.timeout));
+            }
+        };
+        let status = status.map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let (stdout, stdout_truncated) =
+            stdout.map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let (stderr, stderr_truncated) =
+            stderr.map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+
+        for hook in &self.hooks {
+            hook.on_command_complete(command, status.code()).await;
+        }
+
+        Ok(json!({
+            "exit_code": status.code(),
+            "stdout": String::from_utf8_lossy(&stdout),
+            "stderr": String::from_utf8_lossy(&stderr),
+            "truncated": stdout_truncated || stderr_truncated,
+        }))
+    }
+}
+
+impl std::fmt::Debug for ShellTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShellTool")
+            .field("root_dir", &self.root_dir)
+            .field("allowed", &self.allowed)
+            .field("allow_all", &self.allow_all)
+            .field("denied", &self.denied)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+fn runtime_error(message: String) -> ToolCallError {
+    ToolCallError::RuntimeError(message.into())
+}
+
+/// Read up to `limit` bytes and discard the rest, so the process never
+/// blocks on a full pipe
+async fn read_capped(
+    mut reader: impl AsyncRead + Unpin,
+    limit: usize,
+) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut buf = Vec::new();
+    (&mut reader)
+        .take(limit as u64)
+        .read_to_end(&mut buf)
+        .await?;
+    let rest = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await?;
+    Ok((buf, rest > 0))
+}
+
+/// Split a command line into words, honouring quotes and backslashes
+fn split_command(line: &str) -> Result<Vec<String>, ToolCallError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(runtime_error(format!("Unterminated quote in: {line}")));
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+impl ToolT for ShellTool {
+    fn name(&self) -> &'static str {
+        "shell"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a command without a shell and return its exit code, stdout and stderr"
+    }
+
+    fn args_schema(&self) -> Value {
+        let mut allowed: Vec<&str> = self.allowed.iter().map(String::as_str).collect();
+        allowed.sort_unstable();
+        let programs = if self.allow_all {
+            "Any program".to_string()
+        } else {
+            format!("One of: {}", allowed.join(", "))
+        };
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": format!(
+                        "Program and arguments, quoted like in a shell. Pipes and redirects are not supported. {programs}"
+                    ),
+                },
+                "working_dir": {
+                    "type": "string",
+                    "description": "Directory to run in, relative to the workspace root",
+                },
+            },
+            "required": ["command"],
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for ShellTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let line = args
+            .get("command")
+            .and_then(Value::as_str)
+            .ok_or_else(|| runtime_error("Missing string argument 'command'".to_string()))?;
+        let mut words = split_command(line)?.into_iter();
+        let program = words
+            .next()
+            .ok_or_else(|| runtime_error("Empty command".to_string()))?;
+        if !self.is_allowed(&program) {
+            return Err(runtime_error(format!("Program is not allowed: {program}")));
+        }
+
+        let command = ShellCommand {
+            program,
+            args: words.collect(),
+            working_dir: self.working_dir(args.get("working_dir").and_then(Value::as_str))?,
+        };
+
+        for hook in &self.hooks {
+            if hook.on_command(&command).await == HookOutcome::Abort {
+                return Err(runtime_error(format!("Command was not approved: {line}")));
+            }
+        }
+
+        debug!(
+            "Shell Tool Executing: {} in {}",
+            line,
+            command.working_dir.display()
+        );
+
+        self.run(&command).await
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command(r#"git commit -m "a \"quoted\" msg" 'it''s' a\ b"#).unwrap(),
+            vec!["git", "commit", "-m", r#"a "quoted" msg"#, "its", "a b"]
+        );
+        assert_eq!(split_command("echo ''").unwrap(), vec!["echo", ""]);
+        assert!(split_command("echo 'open").is_err());
+    }
+
+    #[test]
+    fn test_allow_and_deny_lists() {
+        let tool = ShellTool::new(".").allow("ls").allow_all().deny("rm");
+        assert!(tool.is_allowed("ls"));
+        assert!(tool.is_allowed("cat"));
+        assert!(!tool.is_allowed("rm"));
+        assert!(!tool.is_allowed("/tmp/cat"));
+        assert!(!ShellTool::new(".").is_allowed("ls"));
+    }
+
+    #[tokio::test]
+    async fn test_runs_in_jailed_working_dir() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        let tool = ShellTool::new(temp_dir.path()).allow("pwd");
+
+        let result = tool
+            .execute(json!({"command": "pwd", "working_dir": "sub"}))
+            .await
+            .unwrap();
+        assert_eq!(result["exit_code"], 0);
+        assert!(result["stdout"]
+            .as_str()
+            .unwrap()
+            .trim_end()
+            .ends_with("sub"));
+
+        let error = tool
+            .execute(json!({"command": "pwd", "working_dir": ".."}))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("outside"));
+
+        let error = tool.execute(json!({"command": "ls"})).await.unwrap_err();
+        assert!(error.to_string().contains("not allowed"));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_truncation() {
+        let temp_dir = tempdir().unwrap();
+        let tool = ShellTool::new(temp_dir.path())
+            .allow("sleep")
+            .allow("seq")
+            .timeout(Duration::from_millis(100))
+            .max_output_bytes(10);
+
+        let error = tool.execute(json!({"command": "sleep 5"})).await;
+        assert!(matches!(error, Err(ToolCallError::Timeout(_))));
+
+        let result = tool.execute(json!({"command": "seq 1000"})).await.unwrap();
+        assert_eq!(result["stdout"].as_str().unwrap().len(), 10);
+        assert_eq!(result["truncated"], true);
+    }
+
+    struct DenyGit(Mutex<Vec<Option<i32>>>);
+
+    #[async_trait]
+    impl ShellHook for DenyGit {
+        async fn on_command(&self, command: &ShellCommand) -> HookOutcome {
+            if command.program == "git" {
+                HookOutcome::Abort
+            } else {
+                HookOutcome::Continue
+            }
+        }
+
+        async fn on_command_complete(&self, _command: &ShellCommand, exit_code: Option<i32>) {
+            self.0.lock().unwrap().push(exit_code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_can_refuse_commands() {
+        let temp_dir = tempdir().unwrap();
+        let hook = Arc::new(DenyGit(Mutex::new(Vec::new())));
+        let tool = ShellTool::new(temp_dir.path())
+            .allow_all()
+            .hook(hook.clone());
+
+        let error = tool.execute(json!({"command": "git status"})).await;
+        assert!(error.unwrap_err().to_string().contains("not approved"));
+
+        tool.execute(json!({"command": "true"})).await.unwrap();
+        assert_eq!(*hook.0.lock().unwrap(), vec![Some(0)]);
+    }
+}
@@ -0,0 +1,289 @@
+use autoagents::core::{
+    ractor::async_trait,
+    tool::{ToolCallError, ToolRuntime, ToolT},
+};
+use autoagents::llm::chat::{ChatMessage, ChatRole, ImageMime, MessageType};
+use autoagents::llm::LLMProvider;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use log::debug;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const DEFAULT_MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Answers questions about an image with a vision-capable LLM, so agents
+/// whose own model only handles text can delegate visual subtasks.
+///
+/// Images are given as a URL, base64 data or, when a root directory is
+/// set, a path below it. Image URLs are passed to the backend as they are.
+#[derive(Clone)]
+pub struct VisionTool {
+    llm: Arc<dyn LLMProvider>,
+    instructions: Option<String>,
+    root_dir: Option<PathBuf>,
+    max_image_bytes: u64,
+}
+
+impl VisionTool {
+    /// Analyze images with `llm`, which must accept image input
+    pub fn new(llm: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            llm,
+            instructions: None,
+            root_dir: None,
+            max_image_bytes: DEFAULT_MAX_IMAGE_BYTES,
+        }
+    }
+
+    /// System prompt for the vision model, e.g. to ask for terse answers
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Accept image paths, resolved against and confined to `root_dir`.
+    /// Without one only URLs and base64 data are accepted.
+    pub fn root_dir(mut self, root_dir: impl Into<PathBuf>) -> Self {
+        self.root_dir = Some(root_dir.into());
+        self
+    }
+
+    /// Refuse images larger than `bytes`, 20 MiB by default
+    pub fn max_image_bytes(mut self, bytes: u64) -> Self {
+        self.max_image_bytes = bytes;
+        self
+    }
+
+    async fn read_path(&self, path: &str) -> Result<Vec<u8>, ToolCallError> {
+        let root = self
+            .root_dir
+            .as_ref()
+            .ok_or_else(|| runtime_error("Reading images from paths is not enabled".to_string()))?;
+        let root = tokio::fs::canonicalize(root)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let resolved = tokio::fs::canonicalize(root.join(Path::new(path)))
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        if !resolved.starts_with(&root) {
+            return Err(runtime_error(format!(
+                "Path is outside the allowed directory: {path}"
+            )));
+        }
+        let size = tokio::fs::metadata(&resolved)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?
+            .len();
+        self.check_size(size)?;
+        tokio::fs::read(&resolved)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))
+    }
+
+    fn check_size(&self, size: u64) -> Result<(), ToolCallError> {
+        if size > self.max_image_bytes {
+            return Err(runtime_error(format!(
+                "Image is {size} bytes, more than the limit of {} bytes",
+                self.max_image_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    async fn image(&self, args: &Value) -> Result<MessageType, ToolCallError> {
+        let arg = |name: &str| args.get(name).and_then(Value::as_str);
+        let bytes = match (arg("url"), arg("image_base64"), arg("path")) {
+            (Some(url), None, None) => return Ok(MessageType::ImageURL(url.to_string())),
+            (None, Some(data), None) => {
+                let bytes = STANDARD
+                    .decode(data.trim())
+                    .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+                self.check_size(bytes.len() as u64)?;
+                bytes
+            }
+            (None, None, Some(path)) => self.read_path(path).await?,
+            _ => {
+                return Err(runtime_error(
+                    "Give exactly one of 'url', 'image_base64' or 'path'".to_string(),
+                ))
+            }
+        };
+        // The bytes decide the format, so only images are ever sent on
+        let mime = sniff_mime(&bytes)
+            .ok_or_else(|| runtime_error("Not a PNG, JPEG, GIF or WebP image".to_string()))?;
+        Ok(MessageType::Image((mime, bytes)))
+    }
+}
+
+impl std::fmt::Debug for VisionTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VisionTool")
+            .field("root_dir", &self.root_dir)
+            .field("max_image_bytes", &self.max_image_bytes)
+            .finish()
+    }
+}
+
+fn runtime_error(message: String) -> ToolCallError {
+    ToolCallError::RuntimeError(message.into())
+}
+
+/// Image format from the file signature
+fn sniff_mime(bytes: &[u8]) -> Option<ImageMime> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some(ImageMime::PNG),
+        [0xFF, 0xD8, 0xFF, ..] => Some(ImageMime::JPEG),
+        [b'G', b'I', b'F', b'8', ..] => Some(ImageMime::GIF),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageMime::WEBP),
+        _ => None,
+    }
+}
+
+impl ToolT for VisionTool {
+    fn name(&self) -> &'static str {
+        "analyze_image"
+    }
+
+    fn description(&self) -> &'static str {
+        "Look at an image and answer a question about it, such as describing it, reading its text or finding something in it"
+    }
+
+    fn args_schema(&self) -> Value {
+        let mut properties = json!({
+            "question": {
+                "type": "string",
+                "description": "What to find out about the image",
+            },
+            "url": {
+                "type": "string",
+                "description": "URL of the image",
+            },
+            "image_base64": {
+                "type": "string",
+                "description": "Base64 encoded PNG, JPEG, GIF or WebP image",
+            },
+        });
+        if self.root_dir.is_some() {
+            properties["path"] = json!({
+                "type": "string",
+                "description": "Path of an image file",
+            });
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["question"],
+        })
+    }
+}
+
+#[async_trait]
+impl ToolRuntime for VisionTool {
+    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let question = args
+            .get("question")
+            .and_then(Value::as_str)
+            .ok_or_else(|| runtime_error("Missing string argument 'question'".to_string()))?;
+        let image = self.image(&args).await?;
+        debug!("Vision Tool Executing: {}", question);
+
+        let mut messages = Vec::new();
+        if let Some(instructions) = &self.instructions {
+            messages.push(ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: instructions.clone(),
+                reasoning: Vec::new(),
+            });
+        }
+        messages.push(ChatMessage {
+            role: ChatRole::User,
+            message_type: image,
+            content: question.to_string(),
+            reasoning: Vec::new(),
+        });
+
+        let response = self
+            .llm
+            .chat(&messages, None, None)
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?;
+        let answer = response
+            .text()
+            .ok_or_else(|| runtime_error("The vision model returned no text".to_string()))?;
+        Ok(json!({ "answer": answer }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    fn vision() -> Arc<ScriptedLLMProvider> {
+        Arc::new(ScriptedLLMProvider::new(["a red square"]))
+    }
+
+    #[tokio::test]
+    async fn test_images_are_sent_with_the_question() {
+        let llm = vision();
+        let tool = VisionTool::new(llm.clone()).instructions("Be brief");
+
+        let result = tool
+            .execute(json!({"question": "What is it?", "image_base64": STANDARD.encode(PNG)}))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({"answer": "a red square"}));
+
+        let seen = llm.last_messages();
+        assert_eq!(seen[0].role, ChatRole::System);
+        assert_eq!(seen[1].content, "What is it?");
+        assert_eq!(
+            seen[1].message_type,
+            MessageType::Image((ImageMime::PNG, PNG.to_vec()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paths_are_confined_to_the_root_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("chart.png"), PNG).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "secret").unwrap();
+        let llm = vision();
+
+        let without_root = VisionTool::new(llm.clone());
+        assert!(without_root.args_schema()["properties"]
+            .get("path")
+            .is_none());
+        assert!(without_root
+            .execute(json!({"question": "?", "path": "chart.png"}))
+            .await
+            .is_err());
+
+        let tool = VisionTool::new(llm).root_dir(dir.path());
+        assert!(tool
+            .execute(json!({"question": "?", "path": "chart.png"}))
+            .await
+            .is_ok());
+        let error = tool
+            .execute(json!({"question": "?", "path": "notes.txt"}))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Not a PNG"));
+        assert!(tool
+            .execute(json!({"question": "?", "path": "../../etc/passwd"}))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_formats_are_detected_from_signatures() {
+        assert_eq!(sniff_mime(PNG), Some(ImageMime::PNG));
+        assert_eq!(sniff_mime(&[0xFF, 0xD8, 0xFF, 0xE0]), Some(ImageMime::JPEG));
+        assert_eq!(sniff_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some(ImageMime::WEBP));
+        assert_eq!(sniff_mime(b"hello"), None);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod analyze;
+
+pub use analyze::VisionTool;
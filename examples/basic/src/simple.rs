@@ -37,26 +37,14 @@ impl ToolRuntime for Addition {
     }
 }
 
-#[derive(Serialize, Deserialize, ToolInput, Debug)]
-pub struct MultiplicationArgs {
-    #[input(description = "Left operand for multiplication")]
-    left: i32,
-    #[input(description = "Right operand for multiplication")]
-    right: i32,
-}
-
-#[tool(name = "multiplication", description = "Multiply two numbers together", input = MultiplicationArgs
-)]
-struct Multiplication {}
-
-#[async_trait]
-impl ToolRuntime for Multiplication {
-    async fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
-        println!("Executing Multiplication");
-        let typed_args: MultiplicationArgs = serde_json::from_value(args)?;
-        let result = typed_args.left * typed_args.right;
-        Ok(result.into())
-    }
+/// Multiply two numbers together
+#[tool]
+async fn multiplication(
+    #[input(description = "Left operand for multiplication")] left: i32,
+    #[input(description = "Right operand for multiplication")] right: i32,
+) -> i32 {
+    println!("Executing Multiplication");
+    left * right
 }
 
 /// Math agent output with Value and Explanation
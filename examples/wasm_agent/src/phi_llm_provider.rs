@@ -179,6 +179,8 @@ impl ChatProvider for PhiLLMProvider {
                             delta: StreamDelta {
                                 content: Some(first_token.token),
                                 tool_calls: None,
+                                thinking: None,
+                                reasoning: None,
                             },
                         }],
                         usage: None,
@@ -224,6 +226,8 @@ impl ChatProvider for PhiLLMProvider {
                             delta: StreamDelta {
                                 content: Some(token.token),
                                  tool_calls: None,
+                                thinking: None,
+                                reasoning: None,
                             },
                         }],
                         usage: None,
@@ -285,12 +289,14 @@ impl CompletionProvider for PhiLLMProvider {
             role: ChatRole::User,
             message_type: MessageType::Text,
             content: req.prompt.clone(),
+            reasoning: Vec::new(),
         }];
 
         let response = self.chat(&messages, None, None).await?;
 
         Ok(CompletionResponse {
             text: response.text().unwrap_or_default(),
+            usage: None,
         })
     }
 }